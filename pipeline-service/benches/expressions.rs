@@ -0,0 +1,122 @@
+// Expression Benchmarks
+// Matrix-heavy condition evaluation and template expansion, with and without the expression cache
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use pipeline_service::expression::ExpressionCache;
+use pipeline_service::parser::models::Value;
+use pipeline_service::{AzureParser, ExpressionContext, ExpressionEngine};
+
+/// Matrix instances in each benchmark
+const INSTANCES: usize = 256;
+
+/// Conditions every matrix instance evaluates, as a typical step list would
+const CONDITIONS: &[&str] = &[
+    "succeeded()",
+    "and(succeeded(), eq(variables['Build.SourceBranch'], 'refs/heads/main'))",
+    "and(succeeded(), ne(variables['Build.Reason'], 'PullRequest'))",
+    "or(eq(variables.os, 'linux'), eq(variables.os, 'macos'))",
+    "in(variables.toolchain, 'stable', 'beta')",
+    "and(eq(variables.os, 'windows'), startsWith(variables.toolchain, 'nightly'))",
+    "contains(format('{0}-{1}', variables.os, variables.toolchain), 'linux-stable')",
+    "not(eq(coalesce(variables.features, 'default'), 'none'))",
+];
+
+fn instance_context(index: usize) -> ExpressionContext {
+    let os = ["linux", "macos", "windows"][index % 3];
+    let toolchain = ["stable", "beta", "nightly"][index / 3 % 3];
+    let mut context = ExpressionContext::default();
+    for (name, value) in [
+        ("os", os.to_string()),
+        ("toolchain", toolchain.to_string()),
+        ("features", format!("set{}", index)),
+        ("Build.SourceBranch", "refs/heads/main".to_string()),
+        ("Build.Reason", "IndividualCI".to_string()),
+    ] {
+        context
+            .variables
+            .insert(name.to_string(), Value::String(value));
+    }
+    context
+}
+
+fn evaluate_conditions(contexts: &[ExpressionContext], cache: &Arc<ExpressionCache>) -> usize {
+    let mut truthy = 0;
+    for context in contexts {
+        // The executor builds a fresh engine for every evaluation
+        for condition in CONDITIONS {
+            let engine = ExpressionEngine::new(context.clone()).with_cache(cache.clone());
+            if engine.evaluate_runtime(condition).unwrap().is_truthy() {
+                truthy += 1;
+            }
+        }
+    }
+    truthy
+}
+
+fn bench_matrix_conditions(c: &mut Criterion) {
+    let contexts: Vec<ExpressionContext> = (0..INSTANCES).map(instance_context).collect();
+    let mut group = c.benchmark_group("matrix_conditions");
+
+    let cached = Arc::new(ExpressionCache::default());
+    group.bench_function("cached", |b| {
+        b.iter(|| evaluate_conditions(&contexts, &cached))
+    });
+
+    let uncached = Arc::new(ExpressionCache::new(0));
+    group.bench_function("uncached", |b| {
+        b.iter(|| evaluate_conditions(&contexts, &uncached))
+    });
+
+    group.finish();
+}
+
+const JOBS_TEMPLATE: &str = r#"
+parameters:
+- name: platforms
+  type: object
+  default: []
+jobs:
+- ${{ each platform in parameters.platforms }}:
+  - job: build_${{ platform }}
+    steps:
+    - ${{ if endsWith(platform, '0') }}:
+      - script: echo first of ten
+    - ${{ if not(endsWith(platform, '0')) }}:
+      - script: echo ${{ format('{0}-{1}', platform, 'release') }}
+    - script: echo ${{ upper(platform) }}
+      condition: and(succeeded(), ne(variables['Build.Reason'], 'PullRequest'))
+"#;
+
+fn bench_template_expansion(c: &mut Criterion) {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("jobs.yml"), JOBS_TEMPLATE).unwrap();
+    let platforms: Vec<String> = (0..INSTANCES).map(|i| format!("p{}", i)).collect();
+    std::fs::write(
+        dir.path().join("pipeline.yml"),
+        format!(
+            "jobs:\n- template: jobs.yml\n  parameters:\n    platforms: [{}]\n",
+            platforms.join(", ")
+        ),
+    )
+    .unwrap();
+    let pipeline = dir.path().join("pipeline.yml");
+    let root = dir.path().to_path_buf();
+
+    let mut group = c.benchmark_group("template_expansion");
+    group.bench_function("warm", |b| {
+        b.iter(|| AzureParser::parse_with_templates(&pipeline, &root).unwrap())
+    });
+    group.bench_function("cold", |b| {
+        b.iter(|| {
+            ExpressionCache::shared().clear();
+            AzureParser::parse_with_templates(&pipeline, &root).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_matrix_conditions, bench_template_expansion);
+criterion_main!(benches);
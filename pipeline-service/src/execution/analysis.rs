@@ -0,0 +1,412 @@
+// Pipeline Analysis
+// Critical path and duration forecasts from recorded job timings
+
+use crate::execution::executor::ExecutionResult;
+use crate::execution::graph::ExecutionGraph;
+use crate::execution::run_record::RunRecord;
+use crate::parser::models::{collect_deployment_steps, JobResult, JobStatus};
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Job durations recorded from a run, keyed by `Stage.Job`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobTimings {
+    /// Duration of each job in seconds
+    pub jobs: BTreeMap<String, f64>,
+}
+
+impl JobTimings {
+    /// Record the durations of jobs that ran in `result`
+    pub fn from_result(result: &ExecutionResult) -> Self {
+        let jobs = result
+            .stages
+            .iter()
+            .flat_map(|stage| {
+                JobResult::combine_instances(&stage.jobs)
+                    .into_iter()
+                    .filter(|job| job.status != JobStatus::Skipped)
+                    .map(move |job| {
+                        (
+                            format!("{}.{}", stage.stage_name, job.job_name),
+                            job.duration.as_secs_f64(),
+                        )
+                    })
+            })
+            .collect();
+        Self { jobs }
+    }
+
+    /// Record the durations of jobs that ran in a run record, such as one
+    /// imported from Azure DevOps
+    pub fn from_record(record: &RunRecord) -> Self {
+        let jobs = record
+            .jobs()
+            .filter(|(_, job)| job.result != "skipped")
+            .map(|(key, job)| (key, job.duration_seconds))
+            .collect();
+        Self { jobs }
+    }
+
+    /// Read timings written by [`JobTimings::save`], or take them from a run
+    /// record or Azure DevOps timeline
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("invalid timings file {}: {}", path.display(), e))?;
+        if value.get("jobs").is_none() {
+            return RunRecord::from_json(&content)
+                .map(|record| Self::from_record(&record))
+                .map_err(|e| format!("invalid timings file {}: {}", path.display(), e));
+        }
+        serde_json::from_value(value)
+            .map_err(|e| format!("invalid timings file {}: {}", path.display(), e))
+    }
+
+    /// Write the timings as JSON
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+    }
+
+    /// Keep durations from `other` for jobs it ran, so partial runs update the record
+    pub fn merge(&mut self, other: JobTimings) {
+        self.jobs.extend(other.jobs);
+    }
+}
+
+/// Forecast for one job
+#[derive(Debug, Clone)]
+pub struct JobEstimate {
+    /// `Stage.Job`
+    pub key: String,
+    pub duration: Duration,
+    /// The duration was recorded rather than estimated from the step count
+    pub measured: bool,
+    /// Earliest start with unlimited parallelism
+    pub earliest_start: Duration,
+    /// How long the job can be delayed without delaying the pipeline
+    pub slack: Duration,
+    /// How much shorter the pipeline would be if this job took no time
+    pub impact: Duration,
+}
+
+/// Critical path and duration forecast for a pipeline
+///
+/// Jobs are the unit of work. A job waits for the jobs it depends on and for
+/// every job of the stages its stage depends on.
+#[derive(Debug, Clone)]
+pub struct PipelineAnalysis {
+    /// Jobs in declaration order
+    pub jobs: Vec<JobEstimate>,
+    /// Longest chain of dependent jobs (`Stage.Job`), first to last
+    pub critical_path: Vec<String>,
+    /// Length of the critical path: the minimum duration with unlimited parallelism
+    pub critical_duration: Duration,
+    /// Duration when every job runs one after another
+    pub serial_duration: Duration,
+    /// Predecessors of each job (indices into `jobs`)
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl PipelineAnalysis {
+    /// Analyze a pipeline graph
+    ///
+    /// Jobs without a recorded duration are estimated at `step_estimate` per step.
+    pub fn new(graph: &ExecutionGraph, timings: &JobTimings, step_estimate: Duration) -> Self {
+        let mut keys = Vec::new();
+        let mut durations = Vec::new();
+        let mut measured = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut stage_jobs: HashMap<&str, Vec<usize>> = HashMap::new();
+
+        for stage in &graph.stages {
+            let stage_name = stage.stage.stage.as_deref().unwrap_or("");
+            for node in &stage.jobs {
+                let key = format!(
+                    "{}.{}",
+                    stage_name,
+                    node.job.identifier().unwrap_or("unknown")
+                );
+                let recorded = timings.jobs.get(&key).copied();
+                let steps = node.job.steps.len() + collect_deployment_steps(&node.job).len();
+                durations
+                    .push(recorded.unwrap_or(step_estimate.as_secs_f64() * steps.max(1) as f64));
+                measured.push(recorded.is_some());
+                index.insert(key.clone(), keys.len());
+                stage_jobs.entry(stage_name).or_default().push(keys.len());
+                keys.push(key);
+            }
+        }
+
+        let mut predecessors = vec![Vec::new(); keys.len()];
+        for stage in &graph.stages {
+            let stage_name = stage.stage.stage.as_deref().unwrap_or("");
+            let upstream: Vec<usize> = stage
+                .dependencies
+                .iter()
+                .flat_map(|dep| stage_jobs.get(dep.as_str()).cloned().unwrap_or_default())
+                .collect();
+            for node in &stage.jobs {
+                let own = index[&format!(
+                    "{}.{}",
+                    stage_name,
+                    node.job.identifier().unwrap_or("unknown")
+                )];
+                predecessors[own].extend(&upstream);
+                predecessors[own].extend(
+                    node.dependencies
+                        .iter()
+                        .filter_map(|dep| index.get(&format!("{}.{}", stage_name, dep))),
+                );
+            }
+        }
+
+        let order = topological_order(&predecessors);
+        let finish = finish_times(&predecessors, &order, &durations);
+        let critical = finish.iter().copied().fold(0.0, f64::max);
+
+        // Latest finish that doesn't delay the pipeline
+        let mut latest = vec![critical; keys.len()];
+        for &node in order.iter().rev() {
+            let latest_start = latest[node] - durations[node];
+            for &pred in &predecessors[node] {
+                latest[pred] = latest[pred].min(latest_start);
+            }
+        }
+
+        // Walk back from the job that finishes last along the predecessors
+        // that finish last
+        let mut critical_path = Vec::new();
+        let mut current = (0..keys.len()).max_by(|a, b| finish[*a].total_cmp(&finish[*b]));
+        while let Some(node) = current {
+            critical_path.push(keys[node].clone());
+            current = predecessors[node]
+                .iter()
+                .copied()
+                .max_by(|a, b| finish[*a].total_cmp(&finish[*b]));
+        }
+        critical_path.reverse();
+
+        let jobs = (0..keys.len())
+            .map(|node| {
+                let mut without = durations.clone();
+                without[node] = 0.0;
+                let shortened = finish_times(&predecessors, &order, &without)
+                    .into_iter()
+                    .fold(0.0, f64::max);
+                JobEstimate {
+                    key: keys[node].clone(),
+                    duration: secs(durations[node]),
+                    measured: measured[node],
+                    earliest_start: secs(finish[node] - durations[node]),
+                    slack: secs(latest[node] - finish[node]),
+                    impact: secs(critical - shortened),
+                }
+            })
+            .collect();
+
+        Self {
+            jobs,
+            critical_path,
+            critical_duration: secs(critical),
+            serial_duration: secs(durations.iter().sum()),
+            predecessors,
+        }
+    }
+
+    /// Forecast the pipeline duration with at most `workers` jobs at once
+    ///
+    /// Simulates list scheduling that starts the ready job with the longest
+    /// remaining chain first. 0 means unlimited.
+    pub fn duration_with_parallelism(&self, workers: usize) -> Duration {
+        if workers == 0 {
+            return self.critical_duration;
+        }
+
+        let durations: Vec<f64> = self.jobs.iter().map(|j| j.duration.as_secs_f64()).collect();
+        let order = topological_order(&self.predecessors);
+
+        // Longest chain from each job to the end of the pipeline
+        let mut remaining = durations.clone();
+        for &node in order.iter().rev() {
+            for &pred in &self.predecessors[node] {
+                remaining[pred] = remaining[pred].max(durations[pred] + remaining[node]);
+            }
+        }
+
+        let mut waiting: Vec<usize> = self.predecessors.iter().map(Vec::len).collect();
+        let mut ready: Vec<usize> = (0..durations.len()).filter(|&n| waiting[n] == 0).collect();
+        let mut running: Vec<(f64, usize)> = Vec::new();
+        let mut now = 0.0;
+
+        loop {
+            ready.sort_by(|a, b| remaining[*b].total_cmp(&remaining[*a]));
+            while running.len() < workers && !ready.is_empty() {
+                let node = ready.remove(0);
+                running.push((now + durations[node], node));
+            }
+
+            let Some(next) =
+                (0..running.len()).min_by(|a, b| running[*a].0.total_cmp(&running[*b].0))
+            else {
+                break;
+            };
+            let (finish, node) = running.swap_remove(next);
+            now = finish;
+            for (dependent, preds) in self.predecessors.iter().enumerate() {
+                for _ in preds.iter().filter(|&&p| p == node) {
+                    waiting[dependent] -= 1;
+                    if waiting[dependent] == 0 {
+                        ready.push(dependent);
+                    }
+                }
+            }
+        }
+
+        secs(now)
+    }
+
+    /// Jobs whose speedup would shorten the pipeline, largest effect first
+    pub fn bottlenecks(&self) -> Vec<&JobEstimate> {
+        let mut jobs: Vec<&JobEstimate> =
+            self.jobs.iter().filter(|j| !j.impact.is_zero()).collect();
+        jobs.sort_by_key(|j| std::cmp::Reverse(j.impact));
+        jobs
+    }
+}
+
+/// Kahn's algorithm over predecessor lists
+fn topological_order(predecessors: &[Vec<usize>]) -> Vec<usize> {
+    let mut waiting: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+    let mut dependents = vec![Vec::new(); predecessors.len()];
+    for (node, preds) in predecessors.iter().enumerate() {
+        for &pred in preds {
+            dependents[pred].push(node);
+        }
+    }
+
+    let mut order: Vec<usize> = (0..predecessors.len())
+        .filter(|&n| waiting[n] == 0)
+        .collect();
+    let mut i = 0;
+    while i < order.len() {
+        for &dependent in &dependents[order[i]] {
+            waiting[dependent] -= 1;
+            if waiting[dependent] == 0 {
+                order.push(dependent);
+            }
+        }
+        i += 1;
+    }
+    order
+}
+
+/// Earliest finish time of each job with unlimited parallelism
+fn finish_times(predecessors: &[Vec<usize>], order: &[usize], durations: &[f64]) -> Vec<f64> {
+    let mut finish = vec![0.0; durations.len()];
+    for &node in order {
+        let start = predecessors[node]
+            .iter()
+            .map(|&p| finish[p])
+            .fold(0.0, f64::max);
+        finish[node] = start + durations[node];
+    }
+    finish
+}
+
+fn secs(seconds: f64) -> Duration {
+    Duration::from_secs_f64(seconds.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    const PIPELINE: &str = r#"
+stages:
+  - stage: Build
+    jobs:
+      - job: Compile
+        steps:
+          - script: make
+      - job: Lint
+        dependsOn: []
+        steps:
+          - script: lint
+  - stage: Test
+    jobs:
+      - job: Unit
+        steps:
+          - script: test
+      - job: Docs
+        dependsOn: []
+        steps:
+          - script: docs
+"#;
+
+    fn analysis() -> PipelineAnalysis {
+        let graph = ExecutionGraph::from_pipeline(&normalize_pipeline(
+            AzureParser::parse(PIPELINE).unwrap(),
+        ))
+        .unwrap();
+        let timings = JobTimings {
+            jobs: BTreeMap::from([
+                ("Build.Compile".to_string(), 60.0),
+                ("Build.Lint".to_string(), 10.0),
+                ("Test.Unit".to_string(), 30.0),
+            ]),
+        };
+        PipelineAnalysis::new(&graph, &timings, Duration::from_secs(5))
+    }
+
+    #[test]
+    fn test_critical_path_and_slack() {
+        let analysis = analysis();
+
+        assert_eq!(analysis.critical_path, vec!["Build.Compile", "Test.Unit"]);
+        assert_eq!(analysis.critical_duration, Duration::from_secs(90));
+        assert_eq!(analysis.serial_duration, Duration::from_secs(105));
+
+        let docs = &analysis.jobs[3];
+        assert!(!docs.measured);
+        assert_eq!(docs.duration, Duration::from_secs(5));
+        assert_eq!(docs.earliest_start, Duration::from_secs(60));
+        assert_eq!(docs.slack, Duration::from_secs(25));
+
+        let lint = &analysis.jobs[1];
+        assert_eq!(lint.slack, Duration::from_secs(50));
+    }
+
+    #[test]
+    fn test_parallelism_forecast_and_bottlenecks() {
+        let analysis = analysis();
+
+        assert_eq!(
+            analysis.duration_with_parallelism(1),
+            Duration::from_secs(105)
+        );
+        assert_eq!(
+            analysis.duration_with_parallelism(2),
+            Duration::from_secs(90)
+        );
+        assert_eq!(
+            analysis.duration_with_parallelism(0),
+            Duration::from_secs(90)
+        );
+
+        // Removing Compile leaves Lint (10s) in front of Unit
+        let bottlenecks: Vec<_> = analysis
+            .bottlenecks()
+            .iter()
+            .map(|j| (j.key.as_str(), j.impact.as_secs()))
+            .collect();
+        assert_eq!(bottlenecks, vec![("Build.Compile", 50), ("Test.Unit", 25)]);
+    }
+}
@@ -0,0 +1,155 @@
+// Event Bus
+// Fans execution events out to any number of subscribed sinks
+
+use crate::execution::events::{progress_channel, ExecutionEvent, ProgressSender};
+
+use tokio::task::JoinHandle;
+
+/// A consumer of execution events
+///
+/// Sinks are driven from a single dispatcher task and see every event in the
+/// order the executor emitted it.
+pub trait EventSink: Send + 'static {
+    /// Handle one event
+    fn handle(&mut self, event: &ExecutionEvent);
+
+    /// Called once after the last event, e.g. to flush buffered output
+    fn finish(&mut self) {}
+}
+
+impl<F> EventSink for F
+where
+    F: FnMut(&ExecutionEvent) + Send + 'static,
+{
+    fn handle(&mut self, event: &ExecutionEvent) {
+        self(event)
+    }
+}
+
+/// Sink that forwards events to another progress channel
+///
+/// Lets a front end keep consuming events from its own receiver while other
+/// sinks observe the same stream.
+pub struct ChannelSink(ProgressSender);
+
+impl ChannelSink {
+    pub fn new(sender: ProgressSender) -> Self {
+        Self(sender)
+    }
+}
+
+impl EventSink for ChannelSink {
+    fn handle(&mut self, event: &ExecutionEvent) {
+        let _ = self.0.send(event.clone());
+    }
+}
+
+/// Distributes the events of one execution to multiple sinks
+///
+/// The executor keeps publishing through a plain [`ProgressSender`], so new
+/// integrations only need to implement [`EventSink`] and subscribe here.
+#[derive(Default)]
+pub struct EventBus {
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sink that receives every event
+    pub fn subscribe(mut self, sink: impl EventSink) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Number of subscribed sinks
+    pub fn sink_count(&self) -> usize {
+        self.sinks.len()
+    }
+
+    /// Start dispatching events
+    ///
+    /// Returns the sender to hand to the executor and a handle that completes
+    /// once every sender has been dropped and all sinks have finished.
+    pub fn start(self) -> (ProgressSender, EventBusHandle) {
+        let (tx, mut rx) = progress_channel();
+        let mut sinks = self.sinks;
+
+        let task = tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                for sink in sinks.iter_mut() {
+                    sink.handle(&event);
+                }
+            }
+            for sink in sinks.iter_mut() {
+                sink.finish();
+            }
+        });
+
+        (tx, EventBusHandle { task })
+    }
+}
+
+/// Handle to a running [`EventBus`] dispatcher
+pub struct EventBusHandle {
+    task: JoinHandle<()>,
+}
+
+impl EventBusHandle {
+    /// Wait until all events have been delivered and sinks have finished
+    pub async fn finished(self) {
+        let _ = self.task.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::events::EventSender;
+
+    use std::sync::{Arc, Mutex};
+
+    struct CountingSink {
+        seen: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl EventSink for CountingSink {
+        fn handle(&mut self, event: &ExecutionEvent) {
+            if let ExecutionEvent::Log { message, .. } = event {
+                self.seen.lock().unwrap().push(message.clone());
+            }
+        }
+
+        fn finish(&mut self) {
+            self.seen.lock().unwrap().push("finished".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bus_delivers_to_all_sinks_in_order() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let closure_seen = Arc::new(Mutex::new(0usize));
+        let counter = closure_seen.clone();
+        let (forward_tx, mut forward_rx) = progress_channel();
+
+        let bus = EventBus::new()
+            .subscribe(CountingSink { seen: seen.clone() })
+            .subscribe(move |_: &ExecutionEvent| *counter.lock().unwrap() += 1)
+            .subscribe(ChannelSink::new(forward_tx));
+        assert_eq!(bus.sink_count(), 3);
+
+        let (tx, handle) = bus.start();
+        tx.send_event(ExecutionEvent::info("one", None, None));
+        tx.send_event(ExecutionEvent::info("two", None, None));
+        drop(tx);
+        handle.finished().await;
+
+        assert_eq!(*seen.lock().unwrap(), vec!["one", "two", "finished"]);
+        assert_eq!(*closure_seen.lock().unwrap(), 2);
+        assert!(forward_rx.recv().await.is_some());
+        assert!(forward_rx.recv().await.is_some());
+        assert!(forward_rx.recv().await.is_none());
+    }
+}
@@ -0,0 +1,625 @@
+// Cache Storage
+// Where Cache@2 entries live: a local directory (default) or a shared HTTP / S3-compatible store
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Environment variable with the bearer token for `backend = "http"`, unless
+/// `token-env` names another
+pub const CACHE_TOKEN_ENV: &str = "ROXID_CACHE_TOKEN";
+
+/// Suffix of an entry's archive; its SHA-256 is stored next to it with
+/// `.sha256`, written last so a reader never trusts a partial upload.
+/// Remote stores name the archive by its checksum as well (see
+/// [`RemoteCacheStorage`])
+const ARCHIVE_SUFFIX: &str = ".tar";
+const CHECKSUM_SUFFIX: &str = ".sha256";
+
+/// A store for cache entries: tar archives named by their key's fingerprint
+///
+/// Entries are immutable. Every archive is stored with its SHA-256, which is
+/// checked before an entry is restored, so a truncated or tampered archive
+/// reads as a miss instead of restoring garbage.
+#[async_trait::async_trait]
+pub trait CacheStorage: Send + Sync + std::fmt::Debug {
+    /// Where the entries are, for messages
+    fn describe(&self) -> String;
+
+    /// Copy entry `name` to `dest`; false if there is no (intact) entry
+    async fn fetch(&self, name: &str, dest: &Path) -> Result<bool, String>;
+
+    /// Store `archive` as entry `name`; false if the entry already existed,
+    /// e.g. because another machine saved it first
+    async fn store(&self, name: &str, archive: &Path) -> Result<bool, String>;
+
+    /// The most recently stored entry whose name starts with `prefix`, for
+    /// `restoreKeys`; stores that can't list their entries find none
+    async fn latest_with_prefix(&self, _prefix: &str) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+}
+
+/// Which store Cache@2 steps use (`[cache]` in `.roxid.toml`)
+///
+/// ```toml
+/// [cache]
+/// backend = "s3"                               # local (default), http or s3
+/// endpoint = "https://s3.eu-west-1.amazonaws.com"
+/// bucket = "ci-cache"
+/// prefix = "roxid/"
+/// region = "eu-west-1"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields, default)]
+pub struct CacheSettings {
+    pub backend: CacheBackend,
+    /// Directory of the local store (default `~/.roxid/cache`)
+    pub dir: Option<PathBuf>,
+    /// Base URL of the HTTP store; entries are GET and PUT under it
+    pub url: Option<String>,
+    /// Environment variable holding the HTTP store's bearer token
+    /// (default `ROXID_CACHE_TOKEN`)
+    pub token_env: Option<String>,
+    /// S3-compatible endpoint, e.g. `https://storage.googleapis.com` for GCS
+    /// with HMAC keys. Credentials come from `AWS_ACCESS_KEY_ID` and
+    /// `AWS_SECRET_ACCESS_KEY`.
+    pub endpoint: Option<String>,
+    pub bucket: Option<String>,
+    /// Prepended to entry names in the bucket
+    pub prefix: Option<String>,
+    /// Signing region (default `us-east-1`)
+    pub region: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheBackend {
+    #[default]
+    Local,
+    Http,
+    S3,
+}
+
+impl CacheSettings {
+    /// The configured store
+    pub fn storage(&self) -> Result<Arc<dyn CacheStorage>, String> {
+        let missing = |key: &str| format!("[cache] backend = \"{}\" needs {}", self.name(), key);
+        Ok(match self.backend {
+            CacheBackend::Local => Arc::new(match &self.dir {
+                Some(dir) => LocalCacheStorage::new(expand_home(dir)),
+                None => LocalCacheStorage::default(),
+            }),
+            CacheBackend::Http => {
+                let url = self.url.as_deref().ok_or_else(|| missing("url"))?;
+                let token_env = self.token_env.as_deref().unwrap_or(CACHE_TOKEN_ENV);
+                let auth = std::env::var(token_env)
+                    .ok()
+                    .filter(|token| !token.is_empty())
+                    .map(RemoteAuth::Bearer)
+                    .unwrap_or(RemoteAuth::None);
+                Arc::new(RemoteCacheStorage::http(url, auth))
+            }
+            CacheBackend::S3 => {
+                let endpoint = self
+                    .endpoint
+                    .as_deref()
+                    .ok_or_else(|| missing("endpoint"))?;
+                let bucket = self.bucket.as_deref().ok_or_else(|| missing("bucket"))?;
+                let credential = |name: &str| {
+                    std::env::var(name)
+                        .ok()
+                        .filter(|value| !value.is_empty())
+                        .ok_or_else(|| format!("[cache] backend = \"s3\" needs {} set", name))
+                };
+                Arc::new(RemoteCacheStorage::s3(
+                    endpoint,
+                    bucket,
+                    self.prefix.as_deref().unwrap_or_default(),
+                    RemoteAuth::AwsSigV4 {
+                        region: self.region.clone().unwrap_or_else(|| "us-east-1".into()),
+                        access_key: credential("AWS_ACCESS_KEY_ID")?,
+                        secret_key: credential("AWS_SECRET_ACCESS_KEY")?,
+                    },
+                ))
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self.backend {
+            CacheBackend::Local => "local",
+            CacheBackend::Http => "http",
+            CacheBackend::S3 => "s3",
+        }
+    }
+}
+
+/// Entries in a directory on this machine
+#[derive(Debug, Clone)]
+pub struct LocalCacheStorage {
+    dir: PathBuf,
+}
+
+impl Default for LocalCacheStorage {
+    fn default() -> Self {
+        Self::new(
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".roxid")
+                .join("cache"),
+        )
+    }
+}
+
+impl LocalCacheStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn entry(&self, name: &str, suffix: &str) -> PathBuf {
+        self.dir.join(format!("{}{}", name, suffix))
+    }
+}
+
+#[async_trait::async_trait]
+impl CacheStorage for LocalCacheStorage {
+    fn describe(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    async fn fetch(&self, name: &str, dest: &Path) -> Result<bool, String> {
+        let Ok(expected) = tokio::fs::read_to_string(self.entry(name, CHECKSUM_SUFFIX)).await
+        else {
+            return Ok(false);
+        };
+        let archive = self.entry(name, ARCHIVE_SUFFIX);
+        if tokio::fs::copy(&archive, dest).await.is_err() {
+            return Ok(false);
+        }
+        verify_checksum(dest, &expected).await
+    }
+
+    async fn store(&self, name: &str, archive: &Path) -> Result<bool, String> {
+        let checksum_path = self.entry(name, CHECKSUM_SUFFIX);
+        if checksum_path.exists() {
+            return Ok(false);
+        }
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| format!("failed to create {}: {}", self.dir.display(), e))?;
+        let checksum = file_checksum(archive).await?;
+
+        // Write under temporary names and rename into place, archive first:
+        // concurrent savers of the same key each rename a complete copy, and
+        // readers only trust an archive once its checksum exists
+        let staging = |suffix: &str| {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            self.dir.join(format!(
+                ".{}{}.{}-{}.tmp",
+                name,
+                suffix,
+                std::process::id(),
+                nanos
+            ))
+        };
+        let (archive_tmp, checksum_tmp) = (staging(ARCHIVE_SUFFIX), staging(CHECKSUM_SUFFIX));
+        let result = async {
+            tokio::fs::copy(archive, &archive_tmp).await?;
+            tokio::fs::rename(&archive_tmp, self.entry(name, ARCHIVE_SUFFIX)).await?;
+            tokio::fs::write(&checksum_tmp, &checksum).await?;
+            tokio::fs::rename(&checksum_tmp, &checksum_path).await
+        }
+        .await;
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_file(&archive_tmp).await;
+            let _ = tokio::fs::remove_file(&checksum_tmp).await;
+            return Err(format!("failed to store cache entry {}: {}", name, e));
+        }
+        Ok(true)
+    }
+
+    async fn latest_with_prefix(&self, prefix: &str) -> Result<Option<String>, String> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Ok(None);
+        };
+        let latest = entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let name = file_name.strip_suffix(CHECKSUM_SUFFIX)?;
+                let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+                name.starts_with(prefix)
+                    .then(|| (modified, name.to_string()))
+            })
+            .max();
+        Ok(latest.map(|(_, name)| name))
+    }
+}
+
+/// How requests to a remote store are authenticated
+#[derive(Clone)]
+pub enum RemoteAuth {
+    None,
+    Bearer(String),
+    /// AWS Signature Version 4, which S3-compatible stores (MinIO, R2, GCS
+    /// HMAC keys) accept
+    AwsSigV4 {
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+impl std::fmt::Debug for RemoteAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print credentials
+        f.write_str(match self {
+            Self::None => "None",
+            Self::Bearer(_) => "Bearer(***)",
+            Self::AwsSigV4 { .. } => "AwsSigV4(***)",
+        })
+    }
+}
+
+/// Entries on an HTTP server that takes PUT uploads, or in an S3-compatible
+/// bucket; requests go through `curl`
+#[derive(Debug, Clone)]
+pub struct RemoteCacheStorage {
+    /// Entries are at `<base>/<name>.tar`
+    base: String,
+    /// The bucket's URL and key prefix, for listing an S3 bucket
+    listing: Option<(String, String)>,
+    auth: RemoteAuth,
+}
+
+impl RemoteCacheStorage {
+    pub fn http(url: &str, auth: RemoteAuth) -> Self {
+        Self {
+            base: url.trim_end_matches('/').to_string(),
+            listing: None,
+            auth,
+        }
+    }
+
+    /// Path-style URLs (`<endpoint>/<bucket>/<prefix><name>`), which every
+    /// S3-compatible store supports
+    pub fn s3(endpoint: &str, bucket: &str, prefix: &str, auth: RemoteAuth) -> Self {
+        let bucket_url = format!("{}/{}", endpoint.trim_end_matches('/'), bucket);
+        let prefix = prefix.trim_start_matches('/').to_string();
+        Self {
+            base: format!("{}/{}", bucket_url, prefix.trim_end_matches('/'))
+                .trim_end_matches('/')
+                .to_string(),
+            listing: Some((bucket_url, prefix)),
+            auth,
+        }
+    }
+
+    fn url(&self, name: &str, suffix: &str) -> String {
+        match &self.listing {
+            // The prefix is part of the object key, not a directory
+            Some((bucket_url, prefix)) => format!("{}/{}{}{}", bucket_url, prefix, name, suffix),
+            None => format!("{}/{}{}", self.base, name, suffix),
+        }
+    }
+
+    /// Run curl against `url`, returning the HTTP status and the body
+    /// (written to `output` instead, when given)
+    async fn curl(
+        &self,
+        args: &[&str],
+        url: &str,
+        output: Option<&Path>,
+    ) -> Result<(u16, Vec<u8>), String> {
+        let mut command = Command::new("curl");
+        command
+            .args(["-sS", "-L", "-w", "\n%{http_code}"])
+            .args(args);
+        // Credentials go through a config on stdin, not the command line
+        // where any local user could read them
+        let config = match &self.auth {
+            RemoteAuth::None => None,
+            RemoteAuth::Bearer(token) => Some(format!(
+                "header = {}\n",
+                curl_quote(&format!("Authorization: Bearer {}", token))
+            )),
+            RemoteAuth::AwsSigV4 {
+                region,
+                access_key,
+                secret_key,
+            } => {
+                command
+                    .arg("--aws-sigv4")
+                    .arg(format!("aws:amz:{}:s3", region));
+                Some(format!(
+                    "user = {}\n",
+                    curl_quote(&format!("{}:{}", access_key, secret_key))
+                ))
+            }
+        };
+        if config.is_some() {
+            command.args(["-K", "-"]).stdin(Stdio::piped());
+        }
+        if let Some(output) = output {
+            command.arg("-o").arg(output);
+        }
+        let mut child = command
+            .arg(url)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("failed to run curl (is it installed?): {}", e))?;
+        if let (Some(config), Some(mut stdin)) = (config, child.stdin.take()) {
+            stdin
+                .write_all(config.as_bytes())
+                .await
+                .map_err(|e| format!("failed to pass credentials to curl: {}", e))?;
+        }
+        let result = child
+            .wait_with_output()
+            .await
+            .map_err(|e| format!("failed to run curl: {}", e))?;
+        if !result.status.success() {
+            return Err(format!(
+                "{}: {}",
+                url,
+                String::from_utf8_lossy(&result.stderr).trim()
+            ));
+        }
+        let mut body = result.stdout;
+        let newline = body.iter().rposition(|b| *b == b'\n').unwrap_or(0);
+        let status = String::from_utf8_lossy(&body[newline..])
+            .trim()
+            .parse()
+            .unwrap_or_default();
+        body.truncate(newline);
+        Ok((status, body))
+    }
+}
+
+/// A value for a curl config file, quoted
+fn curl_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[async_trait::async_trait]
+impl CacheStorage for RemoteCacheStorage {
+    fn describe(&self) -> String {
+        self.base.clone()
+    }
+
+    async fn fetch(&self, name: &str, dest: &Path) -> Result<bool, String> {
+        let url = self.url(name, CHECKSUM_SUFFIX);
+        let (status, expected) = self.curl(&[], &url, None).await?;
+        match status {
+            200..=299 => {}
+            404 | 403 => return Ok(false),
+            status => return Err(format!("{}: HTTP {}", url, status)),
+        }
+        let expected = String::from_utf8_lossy(&expected).trim().to_string();
+        if expected.len() != 64 || !expected.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(false);
+        }
+
+        let url = self.url(&archive_name(name, &expected), ARCHIVE_SUFFIX);
+        let (status, _) = self.curl(&[], &url, Some(dest)).await?;
+        match status {
+            200..=299 => verify_checksum(dest, &expected).await,
+            404 | 403 => Ok(false),
+            status => Err(format!("{}: HTTP {}", url, status)),
+        }
+    }
+
+    async fn store(&self, name: &str, archive: &Path) -> Result<bool, String> {
+        let checksum_url = self.url(name, CHECKSUM_SUFFIX);
+        let (status, _) = self.curl(&["-I"], &checksum_url, None).await?;
+        if (200..300).contains(&status) {
+            return Ok(false);
+        }
+
+        // The archive is named by its checksum, so uploading it never replaces
+        // an archive that a written checksum points to; one left without a
+        // checksum by an interrupted upload is just never read
+        let checksum = file_checksum(archive).await?;
+        let archive_path = archive.to_string_lossy();
+        let url = self.url(&archive_name(name, &checksum), ARCHIVE_SUFFIX);
+        let (status, _) = self.curl(&["-T", &archive_path], &url, None).await?;
+        if !(200..300).contains(&status) {
+            return Err(format!("{}: upload failed with HTTP {}", url, status));
+        }
+
+        // The checksum goes last: until it's there, readers see a miss.
+        // If-None-Match makes a concurrent upload of the same entry fail with
+        // 412 on stores that support it, instead of overwriting
+        let (status, _) = self
+            .curl(
+                &[
+                    "-X",
+                    "PUT",
+                    "-H",
+                    "If-None-Match: *",
+                    "--data-binary",
+                    &checksum,
+                ],
+                &checksum_url,
+                None,
+            )
+            .await?;
+        match status {
+            200..=299 => Ok(true),
+            412 => Ok(false),
+            status => Err(format!(
+                "{}: upload failed with HTTP {}",
+                checksum_url, status
+            )),
+        }
+    }
+
+    async fn latest_with_prefix(&self, prefix: &str) -> Result<Option<String>, String> {
+        let Some((bucket_url, key_prefix)) = &self.listing else {
+            return Ok(None);
+        };
+        let url = format!(
+            "{}?list-type=2&prefix={}{}",
+            bucket_url,
+            percent_encode(key_prefix),
+            percent_encode(prefix)
+        );
+        let (status, body) = self.curl(&[], &url, None).await?;
+        if !(200..300).contains(&status) {
+            return Err(format!("{}: listing failed with HTTP {}", url, status));
+        }
+        Ok(latest_listed(&String::from_utf8_lossy(&body), key_prefix))
+    }
+}
+
+/// Name of the remote archive of entry `name` with checksum `checksum`
+fn archive_name(name: &str, checksum: &str) -> String {
+    format!("{}-{}", name, checksum)
+}
+
+/// The newest complete entry in a ListObjectsV2 response, by `LastModified`
+fn latest_listed(xml: &str, key_prefix: &str) -> Option<String> {
+    let tag = |text: &str, name: &str| -> Option<String> {
+        let start = text.find(&format!("<{}>", name))? + name.len() + 2;
+        let end = text[start..].find(&format!("</{}>", name))? + start;
+        Some(text[start..end].to_string())
+    };
+    xml.split("<Contents>")
+        .skip(1)
+        .filter_map(|contents| {
+            let key = tag(contents, "Key")?;
+            let name = key
+                .strip_prefix(key_prefix)?
+                .strip_suffix(CHECKSUM_SUFFIX)?
+                .to_string();
+            // ISO 8601 timestamps sort by time
+            Some((tag(contents, "LastModified")?, name))
+        })
+        .max()
+        .map(|(_, name)| name)
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Hex SHA-256 of a file's contents
+pub async fn file_checksum(path: &Path) -> Result<String, String> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Whether `path` has the `expected` checksum; a mismatch removes the file
+async fn verify_checksum(path: &Path, expected: &str) -> Result<bool, String> {
+    if file_checksum(path).await? == expected.trim() {
+        return Ok(true);
+    }
+    let _ = tokio::fs::remove_file(path).await;
+    Ok(false)
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::RoxidConfig;
+
+    #[tokio::test]
+    async fn test_local_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalCacheStorage::new(dir.path().join("cache"));
+        let archive = dir.path().join("entry.tar");
+        std::fs::write(&archive, b"contents").unwrap();
+        let restored = dir.path().join("restored.tar");
+
+        assert!(!storage.fetch("npm-a1", &restored).await.unwrap());
+        assert!(storage.store("npm-a1", &archive).await.unwrap());
+        assert!(!storage.store("npm-a1", &archive).await.unwrap());
+        assert!(storage.fetch("npm-a1", &restored).await.unwrap());
+        assert_eq!(std::fs::read(&restored).unwrap(), b"contents");
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(storage.store("npm-b2", &archive).await.unwrap());
+        assert_eq!(
+            storage.latest_with_prefix("npm-").await.unwrap().as_deref(),
+            Some("npm-b2")
+        );
+        assert_eq!(storage.latest_with_prefix("pip-").await.unwrap(), None);
+
+        // A corrupted archive is a miss, not a restore
+        std::fs::write(dir.path().join("cache/npm-a1.tar"), b"tampered").unwrap();
+        assert!(!storage.fetch("npm-a1", &restored).await.unwrap());
+        assert!(!restored.exists());
+    }
+
+    #[test]
+    fn test_curl_quote() {
+        assert_eq!(curl_quote("key:se\"c\\ret"), "\"key:se\\\"c\\\\ret\"");
+    }
+
+    #[test]
+    fn test_s3_listing() {
+        let xml = "<ListBucketResult><Contents><Key>roxid/npm-1.tar</Key><LastModified>2026-03-02T10:00:00.000Z</LastModified></Contents>\
+<Contents><Key>roxid/npm-1.sha256</Key><LastModified>2026-03-02T10:00:01.000Z</LastModified></Contents>\
+<Contents><Key>roxid/npm-2.tar</Key><LastModified>2026-03-03T10:00:00.000Z</LastModified></Contents>\
+<Contents><Key>roxid/npm-0.sha256</Key><LastModified>2026-01-01T10:00:00.000Z</LastModified></Contents></ListBucketResult>";
+        // npm-2 has no checksum yet, so it isn't complete
+        assert_eq!(latest_listed(xml, "roxid/").as_deref(), Some("npm-1"));
+
+        let storage =
+            RemoteCacheStorage::s3("https://s3.example.com/", "ci", "roxid/", RemoteAuth::None);
+        assert_eq!(
+            storage.url(&archive_name("npm-1", "ab12"), ARCHIVE_SUFFIX),
+            "https://s3.example.com/ci/roxid/npm-1-ab12.tar"
+        );
+        let storage = RemoteCacheStorage::http("https://cache.example.com/", RemoteAuth::None);
+        assert_eq!(
+            storage.url("npm-1", CHECKSUM_SUFFIX),
+            "https://cache.example.com/npm-1.sha256"
+        );
+    }
+
+    #[test]
+    fn test_cache_settings() {
+        let config = RoxidConfig::parse("[cache]\nbackend = \"http\"\n").unwrap();
+        assert_eq!(config.cache.backend, CacheBackend::Http);
+        assert!(config.cache.storage().unwrap_err().contains("needs url"));
+
+        let config =
+            RoxidConfig::parse("[cache]\nbackend = \"http\"\nurl = \"https://c.example.com\"\n")
+                .unwrap();
+        assert_eq!(
+            config.cache.storage().unwrap().describe(),
+            "https://c.example.com"
+        );
+        assert_eq!(RoxidConfig::default().cache.backend, CacheBackend::Local);
+        assert!(RoxidConfig::parse("[cache]\nbackend = \"ftp\"\n").is_err());
+    }
+}
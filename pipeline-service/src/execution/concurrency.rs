@@ -0,0 +1,251 @@
+// Concurrency Groups
+// Runs on this machine that share a group take turns; with cancel-in-progress a newer run stops the older one
+
+use crate::parser::models::LockBehavior;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often waiting and running members of a group check on each other
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What happens when a run starts while another run in its group is in progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConcurrencyPolicy {
+    /// Wait for the earlier runs to finish (`lockBehavior: sequential`)
+    #[default]
+    Queue,
+    /// Cancel the earlier runs (`cancel-in-progress`, `lockBehavior: runLatest`)
+    CancelInProgress,
+}
+
+impl From<&LockBehavior> for ConcurrencyPolicy {
+    fn from(behavior: &LockBehavior) -> Self {
+        match behavior {
+            LockBehavior::RunLatest => ConcurrencyPolicy::CancelInProgress,
+            LockBehavior::Sequential => ConcurrencyPolicy::Queue,
+        }
+    }
+}
+
+/// A named group of runs, coordinated through ticket files in a shared directory
+///
+/// Each run adds a ticket named after its start time and process ID; the run
+/// holding the oldest live ticket goes first. Tickets of processes that have
+/// died are removed by whoever sees them.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyGroup {
+    name: String,
+    policy: ConcurrencyPolicy,
+    dir: PathBuf,
+}
+
+impl ConcurrencyGroup {
+    /// A group coordinated under the system temp directory
+    pub fn new(name: impl Into<String>, policy: ConcurrencyPolicy) -> Self {
+        Self::in_dir(
+            name,
+            policy,
+            &std::env::temp_dir().join("roxid-concurrency"),
+        )
+    }
+
+    /// A group coordinated under `root`
+    pub fn in_dir(name: impl Into<String>, policy: ConcurrencyPolicy, root: &Path) -> Self {
+        let name = name.into();
+        let dir = root.join(group_key(&name));
+        Self { name, policy, dir }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn policy(&self) -> ConcurrencyPolicy {
+        self.policy
+    }
+
+    /// Process IDs of the runs currently in the group, oldest first
+    pub fn members(&self) -> Vec<u32> {
+        self.tickets()
+            .iter()
+            .filter_map(|t| ticket_pid(t))
+            .collect()
+    }
+
+    /// Join the group and wait for this run's turn
+    ///
+    /// Returns `None` if, under [`ConcurrencyPolicy::CancelInProgress`], a
+    /// newer run joined while this one was still waiting.
+    pub async fn acquire(&self) -> std::io::Result<Option<ConcurrencyLock>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let ticket = format!("{:024}-{}", started, std::process::id());
+        std::fs::write(self.dir.join(&ticket), "")?;
+        let lock = ConcurrencyLock {
+            group: self.clone(),
+            ticket,
+        };
+
+        loop {
+            if lock.superseded_now() {
+                return Ok(None);
+            }
+            let tickets = self.tickets();
+            if tickets.first() == Some(&lock.ticket) || tickets.is_empty() {
+                return Ok(Some(lock));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Live tickets, oldest first; dead processes' tickets are removed
+    fn tickets(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut tickets: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|ticket| match ticket_pid(ticket) {
+                Some(pid) if process_alive(pid) => true,
+                _ => {
+                    let _ = std::fs::remove_file(self.dir.join(ticket));
+                    false
+                }
+            })
+            .collect();
+        tickets.sort();
+        tickets
+    }
+}
+
+/// A run's place in its concurrency group; dropping it leaves the group
+#[derive(Debug)]
+pub struct ConcurrencyLock {
+    group: ConcurrencyGroup,
+    ticket: String,
+}
+
+impl ConcurrencyLock {
+    pub fn group(&self) -> &ConcurrencyGroup {
+        &self.group
+    }
+
+    /// Resolves when a newer run in a cancel-in-progress group wants this one
+    /// to stop; never resolves for queued groups
+    pub async fn superseded(&self) {
+        loop {
+            if self.superseded_now() {
+                return;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    fn superseded_now(&self) -> bool {
+        self.group.policy == ConcurrencyPolicy::CancelInProgress
+            && self
+                .group
+                .tickets()
+                .last()
+                .is_some_and(|newest| *newest > self.ticket)
+    }
+}
+
+impl Drop for ConcurrencyLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(self.group.dir.join(&self.ticket));
+    }
+}
+
+/// Directory name for a group: readable, plus a hash so distinct names never share one
+fn group_key(name: &str) -> String {
+    let readable: String = name
+        .chars()
+        .take(64)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // FNV-1a, stable across builds unlike the std hasher
+    let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{}-{:016x}", readable, hash)
+}
+
+fn ticket_pid(ticket: &str) -> Option<u32> {
+    ticket.rsplit_once('-')?.1.parse().ok()
+}
+
+pub(crate) fn process_alive(pid: u32) -> bool {
+    if pid == std::process::id() {
+        true
+    } else if cfg!(target_os = "linux") {
+        Path::new("/proc").join(pid.to_string()).exists()
+    } else if cfg!(unix) {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(true)
+    } else {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queued_runs_take_turns() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let group = ConcurrencyGroup::in_dir("main", ConcurrencyPolicy::Queue, dir.path());
+
+        let first = group.acquire().await.unwrap().unwrap();
+        let waiting = tokio::spawn({
+            let group = group.clone();
+            async move { group.acquire().await.unwrap().is_some() }
+        });
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert!(!waiting.is_finished());
+        assert_eq!(group.members().len(), 2);
+
+        // A dead process's ticket doesn't hold up the queue
+        std::fs::write(group.dir.join(format!("{:024}-{}", 0, u32::MAX)), "").unwrap();
+        drop(first);
+        assert!(waiting.await.unwrap());
+        assert!(group.members().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_newer_run_cancels_older() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let group = ConcurrencyGroup::in_dir(
+            "refs/heads/main",
+            ConcurrencyPolicy::CancelInProgress,
+            dir.path(),
+        );
+
+        let older = group.acquire().await.unwrap().unwrap();
+        let newer = tokio::spawn({
+            let group = group.clone();
+            async move { group.acquire().await.unwrap().is_some() }
+        });
+        tokio::time::timeout(Duration::from_secs(5), older.superseded())
+            .await
+            .expect("older run is told to stop");
+        drop(older);
+        assert!(newer.await.unwrap());
+    }
+}
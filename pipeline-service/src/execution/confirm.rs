@@ -0,0 +1,43 @@
+// Step Confirmation
+// Lets a front end preview each command before it runs and decline it
+
+use std::collections::HashMap;
+
+/// What a step is about to run
+#[derive(Debug, Clone)]
+pub struct StepPreview {
+    pub stage_name: String,
+    pub job_name: String,
+    /// Display name, falling back to the step name
+    pub step: String,
+    /// Program and arguments; the last argument is the script (or its file).
+    /// For a task, its name and then its inputs, one `name: value` per line.
+    /// Secrets are masked.
+    pub command: Vec<String>,
+    pub working_dir: String,
+    /// Variables the step's environment adds or changes relative to roxid's
+    /// own environment, sorted by name, with secrets masked
+    pub env_changes: Vec<(String, String)>,
+}
+
+impl StepPreview {
+    /// Environment entries in `env` that differ from the current process's
+    pub fn env_changes(env: &HashMap<String, String>) -> Vec<(String, String)> {
+        let mut changes: Vec<(String, String)> = env
+            .iter()
+            .filter(|(name, value)| std::env::var(name).ok().as_ref() != Some(*value))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        changes.sort();
+        changes
+    }
+}
+
+/// Decides, step by step, whether commands run
+///
+/// Called from the executor right before any step starts, on a blocking
+/// thread so it can wait for an answer; declined steps are reported as
+/// skipped.
+pub trait StepConfirmer: Send + Sync {
+    fn confirm(&self, preview: &StepPreview) -> bool;
+}
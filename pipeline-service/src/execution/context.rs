@@ -1,6 +1,9 @@
 // Runtime Execution Context
 // Manages execution state and context for expression evaluation
 
+use crate::execution::disk_quota::DiskGuard;
+use crate::execution::pipeline_cache::CacheSave;
+use crate::execution::run_id::RUN_ID_VARIABLE;
 use crate::expression::{
     DependenciesContext, ExpressionContext, ExpressionEngine, JobContext, JobDependency,
     JobStatusContext, PipelineContext, StageContext, StageDependency, StepContext,
@@ -8,10 +11,12 @@ use crate::expression::{
 };
 use crate::parser::models::{
     ExecutionContext, Job, JobResult, JobStatus, Pipeline, Stage, StageResult, StageStatus,
-    StepResult, StepStatus, Value, Variable,
+    StepResult, StepStatus, Value, Variable, VariableSubstitution,
 };
+use crate::runners::container::ContainerHandle;
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Runtime context during pipeline execution
 #[derive(Debug, Clone)]
@@ -45,16 +50,49 @@ pub struct RuntimeContext {
 
     /// Output variables from steps (step_name -> output_name -> value)
     pub step_outputs: HashMap<String, HashMap<String, Value>>,
+
+    /// Variables set or declared by stages and jobs that ran on forks; they're
+    /// reported in the run's results, but no other job sees them
+    pub job_variables: HashMap<String, Value>,
+
+    /// Container the current job's script steps run in
+    pub job_container: Option<ContainerHandle>,
+
+    /// Disk limits every step is held to, when configured
+    pub disk_guard: Option<DiskGuard>,
+
+    /// Caches the current job's Cache@2 steps save if the job succeeds
+    pub cache_saves: Vec<CacheSave>,
+}
+
+/// A read-only copy of a runtime context, handed to each stage or job that
+/// may run alongside others
+///
+/// Each runs on its own [`fork`](Self::fork), so the variables a job sets stay
+/// local to it. Only results and outputs come back, via
+/// [`RuntimeContext::absorb`].
+#[derive(Debug, Clone)]
+pub struct RuntimeSnapshot(Arc<RuntimeContext>);
+
+impl RuntimeSnapshot {
+    /// A private copy to run a stage or job on
+    pub fn fork(&self) -> RuntimeContext {
+        RuntimeContext::clone(&self.0)
+    }
 }
 
 impl RuntimeContext {
     /// Create a new runtime context from base execution context
     pub fn new(base: ExecutionContext) -> Self {
-        let variables: HashMap<String, Value> = base
+        let mut variables: HashMap<String, Value> = base
             .variables
             .iter()
             .map(|(k, v)| (k.clone(), Value::String(v.clone())))
             .collect();
+        variables.insert(
+            RUN_ID_VARIABLE.to_string(),
+            Value::String(base.run_id.clone()),
+        );
 
         let parameters: HashMap<String, Value> = base
             .parameters
@@ -73,6 +111,10 @@ impl RuntimeContext {
             parameters,
             env: HashMap::new(),
             step_outputs: HashMap::new(),
+            job_variables: HashMap::new(),
+            job_container: None,
+            disk_guard: None,
+            cache_saves: Vec::new(),
         }
     }
 
@@ -88,8 +130,9 @@ impl RuntimeContext {
 
         let mut ctx = Self::new(base);
 
-        // Merge pipeline-level variables
-        ctx.merge_variables(&pipeline.variables);
+        // Merge pipeline-level variables; the context isn't strict, so this
+        // can't fail
+        let _ = ctx.merge_variables(&pipeline.variables);
 
         // Merge pipeline-level parameters
         for param in &pipeline.parameters {
@@ -104,14 +147,14 @@ impl RuntimeContext {
     }
 
     /// Enter a stage (set current stage and merge variables)
-    pub fn enter_stage(&mut self, stage: &Stage) {
+    pub fn enter_stage(&mut self, stage: &Stage) -> Result<(), String> {
         self.current_stage = stage.stage.clone();
         self.current_job = None;
         self.step_results.clear();
         self.step_outputs.clear();
 
         // Merge stage-level variables
-        self.merge_variables(&stage.variables);
+        self.merge_variables(&stage.variables)
     }
 
     /// Exit current stage with result
@@ -122,13 +165,13 @@ impl RuntimeContext {
     }
 
     /// Enter a job (set current job and merge variables)
-    pub fn enter_job(&mut self, job: &Job) {
+    pub fn enter_job(&mut self, job: &Job) -> Result<(), String> {
         self.current_job = job.identifier().map(|s| s.to_string());
         self.step_results.clear();
         self.step_outputs.clear();
 
         // Merge job-level variables
-        self.merge_variables(&job.variables);
+        self.merge_variables(&job.variables)
     }
 
     /// Exit current job with result
@@ -142,6 +185,57 @@ impl RuntimeContext {
         self.current_job = None;
     }
 
+    /// Record the combined result of a matrix job's instances, which is what
+    /// `dependsOn` and `dependencies.<job>` see
+    pub fn record_matrix_job(&mut self, result: JobResult) {
+        let key = match &self.current_stage {
+            Some(stage) => format!("{}.{}", stage, result.job_name),
+            None => result.job_name.clone(),
+        };
+        self.job_results.insert(key, result);
+    }
+
+    /// A read-only copy of this context to fork stages or jobs from
+    pub fn snapshot(&self) -> RuntimeSnapshot {
+        RuntimeSnapshot(Arc::new(self.clone()))
+    }
+
+    /// Merge the results of a stage or job that ran on a fork of `base`
+    ///
+    /// Variables and environment entries the fork set are not merged: they
+    /// were local to it. The variables are returned instead, for
+    /// [`publish`](Self::publish) once the fork's siblings have finished.
+    pub fn absorb(
+        &mut self,
+        base: &RuntimeSnapshot,
+        fork: RuntimeContext,
+    ) -> HashMap<String, Value> {
+        self.stage_results.extend(fork.stage_results);
+        self.job_results.extend(fork.job_results);
+        let mut written: HashMap<String, Value> = fork
+            .variables
+            .into_iter()
+            .filter(|(name, value)| base.0.variables.get(name) != Some(value))
+            .collect();
+        written.extend(fork.job_variables);
+        written
+    }
+
+    /// Report variables a stage or job set in the run's results; call in
+    /// declaration order so the last declared writer wins, not the last to finish
+    pub fn publish(&mut self, written: HashMap<String, Value>) {
+        self.job_variables.extend(written);
+    }
+
+    /// Take the result of a stage from an earlier run instead of running it
+    pub fn reuse_stage(&mut self, result: StageResult) {
+        for job in JobResult::combine_instances(&result.jobs) {
+            self.job_results
+                .insert(format!("{}.{}", result.stage_name, job.job_name), job);
+        }
+        self.stage_results.insert(result.stage_name.clone(), result);
+    }
+
     /// Record a step result
     pub fn record_step_result(&mut self, result: StepResult) {
         // Store step outputs
@@ -180,60 +274,52 @@ impl RuntimeContext {
     }
 
     /// Merge variables from a variable list
-    fn merge_variables(&mut self, variables: &[Variable]) {
+    ///
+    /// A value whose expression fails to evaluate is kept as written, unless
+    /// the run is strict: then it's an error, rather than `${{ ... }}` text
+    /// pasted into scripts.
+    fn merge_variables(&mut self, variables: &[Variable]) -> Result<(), String> {
         for var in variables {
             match var {
                 Variable::KeyValue { name, value, .. } => {
                     let trimmed = value.trim();
-                    if trimmed.starts_with("$[") && trimmed.ends_with(']') {
+                    let engine = self.expression_engine();
+                    let evaluated = if trimmed.starts_with("$[") && trimmed.ends_with(']') {
                         // Runtime expression ($[...]): evaluate the inner expression
-                        let inner = &trimmed[2..trimmed.len() - 1];
-                        let engine = self.expression_engine();
-                        match engine.evaluate_runtime(inner) {
-                            Ok(result) => {
-                                self.variables.insert(name.clone(), result);
-                            }
-                            Err(_) => {
-                                // If evaluation fails, store the raw string
-                                self.variables
-                                    .insert(name.clone(), Value::String(value.clone()));
-                            }
-                        }
+                        engine.evaluate_runtime(&trimmed[2..trimmed.len() - 1])
                     } else if trimmed.starts_with("${{") && trimmed.ends_with("}}") {
                         // Compile-time expression (${{ expr }}): evaluate it now since
                         // template resolution may not have processed pipeline-level variables.
-                        let inner = &trimmed[3..trimmed.len() - 2].trim();
-                        let engine = self.expression_engine();
-                        match engine.evaluate_compile_time(inner) {
-                            Ok(result) => {
-                                self.variables.insert(name.clone(), result);
-                            }
-                            Err(_) => {
-                                self.variables
-                                    .insert(name.clone(), Value::String(value.clone()));
-                            }
-                        }
+                        engine.evaluate_compile_time(trimmed[3..trimmed.len() - 2].trim())
                     } else if trimmed.contains("${{") {
                         // Value contains inline compile-time expressions; use substitute_macros
                         // which handles ${{ }}, $[ ], and $() patterns within a string.
-                        let engine = self.expression_engine();
-                        match engine.substitute_macros(trimmed) {
-                            Ok(result) => {
-                                self.variables.insert(name.clone(), Value::String(result));
-                            }
-                            Err(_) => {
-                                self.variables
-                                    .insert(name.clone(), Value::String(value.clone()));
-                            }
-                        }
+                        engine.substitute_macros(trimmed).map(Value::String)
                     } else {
-                        self.variables
-                            .insert(name.clone(), Value::String(value.clone()));
+                        Ok(Value::String(value.clone()))
+                    };
+                    match evaluated {
+                        Ok(result) => {
+                            self.variables.insert(name.clone(), result);
+                        }
+                        Err(e) if self.base.strict => {
+                            return Err(format!("variable '{}': {}", name, e));
+                        }
+                        Err(_) => {
+                            // If evaluation fails, store the raw string
+                            self.variables
+                                .insert(name.clone(), Value::String(value.clone()));
+                        }
                     }
                 }
-                Variable::Group { .. } => {
-                    // Variable groups would need to be resolved from external source
-                    // For now, skip them
+                Variable::Group { group } => {
+                    // Groups come from a local provider; unknown ones were reported before the run
+                    if let Some(values) = self.base.variable_groups.get(group) {
+                        for (name, value) in values {
+                            self.variables
+                                .insert(name.clone(), Value::String(value.clone()));
+                        }
+                    }
                 }
                 Variable::Template { .. } => {
                     // Template variables would be expanded earlier
@@ -241,11 +327,12 @@ impl RuntimeContext {
                 }
             }
         }
+        Ok(())
     }
 
     /// Merge pipeline-level variables (public entry point for the executor)
-    pub fn merge_pipeline_variables(&mut self, variables: &[Variable]) {
-        self.merge_variables(variables);
+    pub fn merge_pipeline_variables(&mut self, variables: &[Variable]) -> Result<(), String> {
+        self.merge_variables(variables)
     }
 
     /// Build an ExpressionContext for evaluating conditions
@@ -257,6 +344,7 @@ impl RuntimeContext {
                 name: Some(self.base.pipeline_name.clone()),
                 workspace: Some(self.base.working_dir.clone()),
             },
+            strict: self.base.strict,
             ..Default::default()
         };
 
@@ -330,6 +418,26 @@ impl RuntimeContext {
         engine.substitute_macros(text).map_err(|e| e.message)
     }
 
+    /// Resolve `$(var)`, `${{ }}` and `$[ ]` in a stage, job or step display name,
+    /// keeping the name as written if an expression fails to evaluate
+    pub fn resolve_display_name(&self, display_name: Option<&str>) -> Option<String> {
+        display_name.map(|name| {
+            self.substitute_variables(name)
+                .unwrap_or_else(|_| name.to_string())
+        })
+    }
+
+    /// Substitute variables in a string, recording which variables were substituted
+    pub fn substitute_variables_audited(
+        &self,
+        text: &str,
+    ) -> Result<(String, Vec<VariableSubstitution>), String> {
+        let engine = self.expression_engine();
+        engine
+            .substitute_macros_audited(text)
+            .map_err(|e| e.message)
+    }
+
     /// Get current job status context
     fn current_job_status(&self) -> JobStatusContext {
         // Determine job status based on step results
@@ -337,11 +445,15 @@ impl RuntimeContext {
             .step_results
             .iter()
             .any(|r| r.status == StepStatus::Failed);
+        let canceled = self
+            .step_results
+            .iter()
+            .any(|r| r.status == StepStatus::Canceled);
 
         JobStatusContext {
-            succeeded: !has_failed && !self.step_results.is_empty(),
+            succeeded: !has_failed && !canceled && !self.step_results.is_empty(),
             failed: has_failed,
-            canceled: false, // Would need cancellation tracking
+            canceled,
         }
     }
 
@@ -460,6 +572,7 @@ impl RuntimeContext {
             "PIPELINE_WORKSPACE".to_string(),
             self.base.working_dir.clone(),
         );
+        env.insert("ROXID_RUNID".to_string(), self.base.run_id.clone());
 
         if let Some(stage) = &self.current_stage {
             env.insert("SYSTEM_STAGENAME".to_string(), stage.clone());
@@ -553,9 +666,10 @@ mod tests {
             parameters: HashMap::new(),
             pool: None,
             has_template_directives: false,
+            from_template: None,
         };
 
-        ctx.enter_stage(&stage);
+        ctx.enter_stage(&stage).unwrap();
         assert_eq!(ctx.current_stage, Some("Build".to_string()));
         assert_eq!(
             ctx.variables.get("stage_var"),
@@ -568,6 +682,8 @@ mod tests {
             status: StageStatus::Succeeded,
             jobs: Vec::new(),
             duration: Duration::from_secs(10),
+            started_at: None,
+            finished_at: None,
         };
 
         ctx.exit_stage(result);
@@ -575,6 +691,28 @@ mod tests {
         assert!(ctx.stage_results.contains_key("Build"));
     }
 
+    #[test]
+    fn test_strict_run_rejects_variables_that_fail_to_evaluate() {
+        let variables = vec![Variable::KeyValue {
+            name: "home".to_string(),
+            value: "${{ env('HOME') }}".to_string(),
+            readonly: false,
+        }];
+
+        let base = ExecutionContext::new("test".to_string(), "/work".to_string());
+        let mut ctx = RuntimeContext::new(base.clone());
+        ctx.merge_pipeline_variables(&variables).unwrap();
+        assert_ne!(
+            ctx.variables.get("home"),
+            Some(&Value::String("${{ env('HOME') }}".to_string()))
+        );
+
+        let mut ctx = RuntimeContext::new(base.with_strict(true));
+        let err = ctx.merge_pipeline_variables(&variables).unwrap_err();
+        assert!(err.contains("variable 'home'"), "{}", err);
+        assert!(!ctx.variables.contains_key("home"));
+    }
+
     #[test]
     fn test_evaluate_condition() {
         let mut base = ExecutionContext::new("test".to_string(), "/work".to_string());
@@ -636,6 +774,8 @@ mod tests {
                 status: StageStatus::Succeeded,
                 jobs: Vec::new(),
                 duration: Duration::from_secs(10),
+                started_at: None,
+                finished_at: None,
             },
         );
 
@@ -0,0 +1,260 @@
+// Environment Diagnostics
+// Checks the machine roxid runs on: container engine, shells, writable directories and free ports
+
+use crate::execution::preflight::Requirement;
+use crate::parser::models::{ComposeSpec, Pipeline};
+use crate::runners::compose::ComposeRunner;
+use crate::runners::shell::find_executable;
+
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Command;
+
+/// Tools checked on PATH; only `sh` is needed for every pipeline
+const TOOLS: &[&str] = &["sh", "bash", "pwsh", "git", "node"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticStatus {
+    Ok,
+    /// Some pipelines won't run
+    Warning,
+    /// Pipelines will fail
+    Error,
+}
+
+/// The outcome of one environment check
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub status: DiagnosticStatus,
+    pub message: String,
+    /// What to do about a warning or error
+    pub fix: Option<String>,
+}
+
+impl Diagnostic {
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            status: DiagnosticStatus::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            status: DiagnosticStatus::Warning,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn error(message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            status: DiagnosticStatus::Error,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Check the machine: container engine, tools on PATH and the directories roxid writes to
+pub fn environment(task_cache_dir: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = container_engine();
+    diagnostics.extend(TOOLS.iter().map(|tool| tool_on_path(tool)));
+    diagnostics.push(writable_dir("Task cache", task_cache_dir));
+    diagnostics.push(writable_dir("Temp directory", &std::env::temp_dir()));
+    diagnostics
+}
+
+/// Docker (with the compose plugin), or podman, which roxid can't drive directly
+pub fn container_engine() -> Vec<Diagnostic> {
+    if !find_executable("docker") {
+        return vec![if find_executable("podman") {
+            Diagnostic::warning(
+                "podman is installed, but roxid runs 'docker'",
+                "install podman-docker (or put a 'docker' wrapper for podman on PATH)",
+            )
+        } else {
+            Diagnostic::warning(
+                "No container engine found; compose services, container jobs and the container sandbox need docker",
+                "install Docker: https://docs.docker.com/get-docker/",
+            )
+        }];
+    }
+
+    let server = match command_output("docker", &["version", "--format", "{{.Server.Version}}"]) {
+        Ok(version) => Diagnostic::ok(format!("docker {} (daemon reachable)", version)),
+        Err(e) => {
+            return vec![Diagnostic::warning(
+                format!("docker is installed but the daemon isn't reachable: {}", e),
+                "start Docker Desktop or the docker service (sudo systemctl start docker) \
+                 and check that your user can access the docker socket",
+            )]
+        }
+    };
+    let compose = match command_output("docker", &["compose", "version", "--short"]) {
+        Ok(version) => Diagnostic::ok(format!("docker compose {}", version)),
+        Err(_) => Diagnostic::warning(
+            "docker compose plugin not found; `compose:` services won't start",
+            "install the compose plugin: https://docs.docker.com/compose/install/",
+        ),
+    };
+    vec![server, compose]
+}
+
+/// Whether `tool` is on PATH; a missing `sh` is an error, anything else a warning
+pub fn tool_on_path(tool: &str) -> Diagnostic {
+    if let Ok(path) = which::which(tool) {
+        return Diagnostic::ok(format!("{} ({})", tool, path.display()));
+    }
+    let requirement = Requirement {
+        tool: tool.to_string(),
+        used_by: Vec::new(),
+    };
+    let fix = requirement
+        .install_hint()
+        .unwrap_or("install it and make sure it is on PATH");
+    if tool == "sh" {
+        Diagnostic::error("sh not found on PATH; script steps can't run", fix)
+    } else {
+        Diagnostic::warning(format!("{} not found on PATH", tool), fix)
+    }
+}
+
+/// Whether `dir` exists (or can be created) and accepts new files
+pub fn writable_dir(label: &str, dir: &Path) -> Diagnostic {
+    let probe = dir.join(format!(".roxid-doctor-{}", std::process::id()));
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&probe, b""))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => Diagnostic::ok(format!("{} is writable ({})", label, dir.display())),
+        Err(e) => Diagnostic::error(
+            format!("{} isn't writable ({}): {}", label, dir.display(), e),
+            format!(
+                "fix its permissions (e.g. chown -R \"$USER\" {}) or point roxid at another directory",
+                dir.display()
+            ),
+        ),
+    }
+}
+
+/// Fixed host ports published by the pipeline's compose services that are already taken
+pub fn port_conflicts(pipeline: &Pipeline, working_dir: &Path) -> Vec<Diagnostic> {
+    let specs: Vec<&ComposeSpec> = pipeline
+        .compose
+        .iter()
+        .chain(
+            pipeline
+                .stages
+                .iter()
+                .flat_map(|s| &s.jobs)
+                .filter_map(|j| j.compose.as_ref()),
+        )
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    for spec in specs {
+        let ports = match ComposeRunner::fixed_host_ports(spec, working_dir) {
+            Ok(ports) => ports,
+            Err(e) => {
+                diagnostics.push(Diagnostic::warning(
+                    format!("Can't check compose ports: {}", e),
+                    format!("make sure {} exists and is valid YAML", spec.file),
+                ));
+                continue;
+            }
+        };
+        let mut free = Vec::new();
+        for (service, port) in ports {
+            if port_available(port) {
+                free.push(port.to_string());
+            } else {
+                diagnostics.push(Diagnostic::error(
+                    format!(
+                        "Port {} for compose service '{}' ({}) is already in use",
+                        port, service, spec.file
+                    ),
+                    format!(
+                        "stop whatever listens on {} (see `lsof -i :{}`) or publish the service on another port",
+                        port, port
+                    ),
+                ));
+            }
+        }
+        if !free.is_empty() {
+            diagnostics.push(Diagnostic::ok(format!(
+                "Compose ports free in {}: {}",
+                spec.file,
+                free.join(", ")
+            )));
+        }
+    }
+    diagnostics
+}
+
+fn port_available(port: u16) -> bool {
+    TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+/// Trimmed stdout of a command, or its first line of stderr when it fails
+fn command_output(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.lines().next().unwrap_or("failed").trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    #[test]
+    fn test_writable_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = dir.path().join("tasks");
+        assert_eq!(
+            writable_dir("Task cache", &cache).status,
+            DiagnosticStatus::Ok
+        );
+        assert!(cache.is_dir());
+
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let diagnostic = writable_dir("Task cache", &file.join("tasks"));
+        assert_eq!(diagnostic.status, DiagnosticStatus::Error);
+        assert!(diagnostic.fix.is_some());
+    }
+
+    #[test]
+    fn test_port_conflicts() {
+        let taken = TcpListener::bind(("0.0.0.0", 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("compose.yml"),
+            format!("services:\n  db:\n    ports: [\"{}:5432\"]\n", port),
+        )
+        .unwrap();
+        let pipeline = normalize_pipeline(
+            AzureParser::parse("compose:\n  file: compose.yml\nsteps:\n  - script: echo hi\n")
+                .unwrap(),
+        );
+
+        let diagnostics = port_conflicts(&pipeline, dir.path());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].status, DiagnosticStatus::Error);
+        assert!(diagnostics[0].message.contains("'db'"));
+
+        drop(taken);
+        let diagnostics = port_conflicts(&pipeline, dir.path());
+        assert_eq!(diagnostics[0].status, DiagnosticStatus::Ok);
+    }
+}
@@ -0,0 +1,304 @@
+// Disk Quotas
+// Keeps a run from filling the disk: a free-space check before it starts and a cap on how much it grows the workspace
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+/// How often a running step's disk use is measured
+pub const QUOTA_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Disk limits for a run (`[workspace]` in `.roxid.toml`)
+///
+/// ```toml
+/// [workspace]
+/// quota = "5GB"
+/// min-free = "2GB"
+/// ```
+///
+/// Sizes are bytes, or a number with a `K`, `M`, `G` or `T` suffix (powers
+/// of 1024).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DiskQuota {
+    /// Most a run may add to the workspace
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub quota: Option<u64>,
+    /// Free space the workspace's disk needs to start a run, and to keep
+    /// running it
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub min_free: Option<u64>,
+}
+
+/// A limit a run went over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaViolation {
+    /// The run grew the workspace by more than the quota
+    Workspace { grown: u64, quota: u64 },
+    /// The workspace's disk has less than `min-free` space left
+    FreeSpace { free: u64, min_free: u64 },
+}
+
+impl fmt::Display for QuotaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Workspace { grown, quota } => write!(
+                f,
+                "the run grew the workspace by {}, over its {} quota",
+                format_size(*grown),
+                format_size(*quota)
+            ),
+            Self::FreeSpace { free, min_free } => write!(
+                f,
+                "only {} free on the workspace's disk, below the {} minimum",
+                format_size(*free),
+                format_size(*min_free)
+            ),
+        }
+    }
+}
+
+impl DiskQuota {
+    pub fn is_set(&self) -> bool {
+        self.quota.is_some() || self.min_free.is_some()
+    }
+
+    /// Check the workspace's disk has `min-free` space before a run starts
+    pub fn check_free_space(&self, workspace: &Path) -> Result<(), String> {
+        match (self.min_free, free_space(workspace)) {
+            (Some(min_free), Some(free)) if free < min_free => Err(format!(
+                "Not enough disk space to start the run: {}",
+                QuotaViolation::FreeSpace { free, min_free }
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Start guarding a run in `workspace`, taking its current size as the
+    /// baseline the quota applies to; `None` when no limits are set
+    pub async fn guard(&self, workspace: &Path) -> Option<DiskGuard> {
+        if !self.is_set() {
+            return None;
+        }
+        let workspace = workspace.to_path_buf();
+        let baseline = match self.quota {
+            Some(_) => {
+                let dir = workspace.clone();
+                tokio::task::spawn_blocking(move || directory_size(&dir))
+                    .await
+                    .unwrap_or_default()
+            }
+            None => 0,
+        };
+        Some(DiskGuard {
+            quota: *self,
+            workspace,
+            baseline,
+        })
+    }
+}
+
+/// A run's disk limits, with the workspace size it started at
+#[derive(Debug, Clone)]
+pub struct DiskGuard {
+    quota: DiskQuota,
+    workspace: PathBuf,
+    baseline: u64,
+}
+
+impl DiskGuard {
+    /// Measure the workspace and its disk once
+    pub async fn check(&self) -> Option<QuotaViolation> {
+        let guard = self.clone();
+        tokio::task::spawn_blocking(move || guard.measure())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Resolve once the run goes over a limit, measuring every
+    /// [`QUOTA_POLL_INTERVAL`]
+    pub async fn exceeded(&self) -> QuotaViolation {
+        loop {
+            if let Some(violation) = self.check().await {
+                return violation;
+            }
+            tokio::time::sleep(QUOTA_POLL_INTERVAL).await;
+        }
+    }
+
+    fn measure(&self) -> Option<QuotaViolation> {
+        if let (Some(min_free), Some(free)) = (self.quota.min_free, free_space(&self.workspace)) {
+            if free < min_free {
+                return Some(QuotaViolation::FreeSpace { free, min_free });
+            }
+        }
+        let quota = self.quota.quota?;
+        let grown = directory_size(&self.workspace).saturating_sub(self.baseline);
+        (grown > quota).then_some(QuotaViolation::Workspace { grown, quota })
+    }
+}
+
+/// Total size of the files under `dir`, not following symlinks; files that
+/// vanish while it's measured are left out
+pub fn directory_size(dir: &Path) -> u64 {
+    let mut total = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    total
+}
+
+/// Free space on the disk holding `path`, from `df`; `None` where it can't
+/// be measured
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let available = stdout.lines().nth(1)?.split_whitespace().nth(3)?;
+    available.parse::<u64>().ok().map(|kb| kb * 1024)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// `1536`, `512K`, `1.5G`, `5GB` or `2GiB` in bytes
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let trimmed = text.trim();
+    let upper = trimmed.to_ascii_uppercase();
+    let number = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (number, scale) = match number.chars().last() {
+        Some('K') => (&number[..number.len() - 1], 1u64 << 10),
+        Some('M') => (&number[..number.len() - 1], 1 << 20),
+        Some('G') => (&number[..number.len() - 1], 1 << 30),
+        Some('T') => (&number[..number.len() - 1], 1 << 40),
+        _ => (number, 1),
+    };
+    match number.trim().parse::<f64>() {
+        Ok(value) if value >= 0.0 && value.is_finite() => Ok((value * scale as f64) as u64),
+        _ => Err(format!("invalid size '{}' (e.g. 512M or 5GB)", trimmed)),
+    }
+}
+
+/// `512 B`, `1.5 KB`, `2.0 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn deserialize_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+    match Size::deserialize(deserializer)? {
+        Size::Bytes(bytes) => Ok(Some(bytes)),
+        Size::Text(text) => parse_size(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(parse_size("1536"), Ok(1536));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("1.5G"), Ok(3 << 29));
+        assert_eq!(parse_size("5GB"), Ok(5 << 30));
+        assert_eq!(parse_size(" 2gib "), Ok(2 << 30));
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("-1M").is_err());
+
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(2 << 30), "2.0 GB");
+
+        #[derive(Deserialize)]
+        struct Config {
+            workspace: DiskQuota,
+        }
+        let config: Config =
+            toml::from_str("[workspace]\nquota = \"1M\"\nmin-free = 4096\n").unwrap();
+        assert_eq!(
+            config.workspace,
+            DiskQuota {
+                quota: Some(1 << 20),
+                min_free: Some(4096),
+            }
+        );
+        assert!(toml::from_str::<Config>("[workspace]\nquota = \"big\"\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_guard_measures_growth() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("existing"), vec![0u8; 8192]).unwrap();
+        let quota = DiskQuota {
+            quota: Some(4096),
+            min_free: None,
+        };
+        let guard = quota.guard(dir.path()).await.unwrap();
+        assert_eq!(guard.check().await, None);
+
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        std::fs::write(dir.path().join("out/big"), vec![0u8; 6000]).unwrap();
+        assert_eq!(
+            guard.check().await,
+            Some(QuotaViolation::Workspace {
+                grown: 6000,
+                quota: 4096
+            })
+        );
+
+        let starved = DiskQuota {
+            quota: None,
+            min_free: Some(u64::MAX),
+        };
+        if free_space(dir.path()).is_some() {
+            assert!(starved.check_free_space(dir.path()).is_err());
+        }
+        assert!(DiskQuota::default().guard(dir.path()).await.is_none());
+    }
+}
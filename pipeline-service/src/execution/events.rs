@@ -1,20 +1,320 @@
 // Execution Events
 // Progress reporting and event types for pipeline execution
 
+use crate::execution::run_id::correlation_id;
 use crate::parser::models::{JobStatus, StageStatus, StepStatus};
 
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::{SendError, TryRecvError};
+
+/// Output events a progress channel queues before its overflow policy applies
+pub const DEFAULT_OUTPUT_CAPACITY: usize = 1024;
+
+/// Bytes of step output the merge policy holds back at most
+pub const MAX_HELD_OUTPUT: usize = 1024 * 1024;
+
+/// What a progress channel does with step output while its output queue is full
+///
+/// Other events are never held back, so a slow consumer can't stall the
+/// executor or lose track of a step's lifecycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the output
+    Drop,
+    /// Combine consecutive output of the same step into a single event,
+    /// delivered once there is room or before the next non-output event;
+    /// output past [`MAX_HELD_OUTPUT`] bytes is discarded as with `Drop`
+    #[default]
+    Merge,
+}
+
+/// Step output lines a progress channel discarded or combined under backpressure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputCounters {
+    pub dropped_lines: u64,
+    pub merged_lines: u64,
+}
+
+/// State shared by both ends of a progress channel
+#[derive(Debug)]
+struct ChannelState {
+    capacity: usize,
+    policy: OverflowPolicy,
+    /// Output events sent but not yet received
+    queued_output: AtomicUsize,
+    /// Output held back while the queue is full (merge policy)
+    held: Mutex<Option<HeldOutput>>,
+    dropped_lines: AtomicU64,
+    merged_lines: AtomicU64,
+    /// Values replaced with `***` in the text of every event
+    secrets: Mutex<Vec<String>>,
+}
+
+impl ChannelState {
+    fn held(&self) -> MutexGuard<'_, Option<HeldOutput>> {
+        self.held.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn counters(&self) -> OutputCounters {
+        OutputCounters {
+            dropped_lines: self.dropped_lines.load(Ordering::Relaxed),
+            merged_lines: self.merged_lines.load(Ordering::Relaxed),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn enqueue(
+        &self,
+        tx: &mpsc::UnboundedSender<ExecutionEvent>,
+        event: ExecutionEvent,
+    ) -> Result<(), SendError<ExecutionEvent>> {
+        if matches!(event, ExecutionEvent::StepOutput { .. }) {
+            self.queued_output.fetch_add(1, Ordering::AcqRel);
+        }
+        tx.send(event)
+    }
+}
+
+/// Step output held back by the merge policy; later lines collect in `text`
+/// and become the event's output when it's delivered
+#[derive(Debug)]
+struct HeldOutput {
+    event: ExecutionEvent,
+    text: String,
+}
+
+impl HeldOutput {
+    fn new(event: ExecutionEvent) -> Self {
+        let text = match &event {
+            ExecutionEvent::StepOutput { output, .. } => output.to_string(),
+            _ => String::new(),
+        };
+        Self { event, text }
+    }
+
+    /// Append `next` when it comes from the same stream of the same step and
+    /// fits under [`MAX_HELD_OUTPUT`]; returns whether it was appended
+    fn append(&mut self, next: &ExecutionEvent) -> bool {
+        let ExecutionEvent::StepOutput { output, .. } = next else {
+            return false;
+        };
+        if self.text.len() + 1 + output.len() > MAX_HELD_OUTPUT {
+            return false;
+        }
+        self.text.push('\n');
+        self.text.push_str(output);
+        true
+    }
+
+    fn into_event(self) -> ExecutionEvent {
+        let mut event = self.event;
+        if let ExecutionEvent::StepOutput { output, .. } = &mut event {
+            *output = self.text.into();
+        }
+        event
+    }
+}
 
 /// Sender for execution progress events
-pub type ProgressSender = mpsc::UnboundedSender<ExecutionEvent>;
+#[derive(Debug, Clone)]
+pub struct ProgressSender {
+    tx: mpsc::UnboundedSender<ExecutionEvent>,
+    state: Arc<ChannelState>,
+}
+
+impl ProgressSender {
+    /// Send an event, applying the overflow policy to step output when the
+    /// output queue is full
+    #[allow(clippy::result_large_err)]
+    pub fn send(&self, event: ExecutionEvent) -> Result<(), SendError<ExecutionEvent>> {
+        let event = self.redacted(event);
+        let mut held = self.state.held();
+        let full = self.state.queued_output.load(Ordering::Acquire) >= self.state.capacity;
+
+        if !matches!(event, ExecutionEvent::StepOutput { .. }) || !full {
+            // Keep held output ahead of whatever follows it
+            if let Some(output) = held.take() {
+                self.state.enqueue(&self.tx, output.into_event())?;
+            }
+            return self.state.enqueue(&self.tx, event);
+        }
+
+        let lines = event.output_lines();
+        match self.state.policy {
+            OverflowPolicy::Drop => {
+                self.state.dropped_lines.fetch_add(lines, Ordering::Relaxed);
+            }
+            OverflowPolicy::Merge => match held.as_mut() {
+                Some(previous) if previous.event.same_output_stream(&event) => {
+                    if previous.append(&event) {
+                        self.state.merged_lines.fetch_add(lines, Ordering::Relaxed);
+                    } else {
+                        self.state.dropped_lines.fetch_add(lines, Ordering::Relaxed);
+                    }
+                }
+                _ => {
+                    if let Some(previous) = held.replace(HeldOutput::new(event)) {
+                        // Output of another step goes out as it is, over the limit
+                        self.state.enqueue(&self.tx, previous.into_event())?;
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Step output lines discarded or combined so far
+    pub fn counters(&self) -> OutputCounters {
+        self.state.counters()
+    }
+
+    /// Mask `secrets` in events sent from now on, by any clone of this sender
+    pub fn mask_secrets(&self, secrets: impl IntoIterator<Item = String>) {
+        let mut masked = self.state.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        masked.extend(secrets.into_iter().filter(|secret| !secret.is_empty()));
+    }
+
+    /// Secrets masked so far
+    pub fn secrets(&self) -> Vec<String> {
+        self.state
+            .secrets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Mask secrets in every piece of text the event carries
+    fn redacted(&self, mut event: ExecutionEvent) -> ExecutionEvent {
+        let secrets = self.state.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        if secrets.is_empty() {
+            return event;
+        }
+        let leaks = |text: &str| secrets.iter().any(|secret| text.contains(secret.as_str()));
+        let mask = |text: &mut String| {
+            if leaks(text) {
+                *text = redact(text, &secrets);
+            }
+        };
+        match &mut event {
+            ExecutionEvent::StepOutput { output, .. } if leaks(output) => {
+                *output = redact(output, &secrets).into();
+            }
+            ExecutionEvent::StepSummary { markdown, .. } => mask(markdown),
+            ExecutionEvent::DiskQuotaExceeded { message, .. }
+            | ExecutionEvent::Log { message, .. }
+            | ExecutionEvent::Error { message, .. } => mask(message),
+            ExecutionEvent::StageSkipped { reason, .. }
+            | ExecutionEvent::JobSkipped { reason, .. }
+            | ExecutionEvent::StepSkipped { reason, .. } => mask(reason),
+            ExecutionEvent::VariableSet { value, .. } => mask(value),
+            _ => {}
+        }
+        event
+    }
+}
 
 /// Receiver for execution progress events
-pub type ProgressReceiver = mpsc::UnboundedReceiver<ExecutionEvent>;
+#[derive(Debug)]
+pub struct ProgressReceiver {
+    rx: mpsc::UnboundedReceiver<ExecutionEvent>,
+    /// Queues held output once there's room again, without keeping the
+    /// channel open
+    tx: mpsc::WeakUnboundedSender<ExecutionEvent>,
+    state: Arc<ChannelState>,
+}
+
+impl ProgressReceiver {
+    /// Receive the next event; `None` once every sender is gone and all
+    /// events, including held output, have been received
+    pub async fn recv(&mut self) -> Option<ExecutionEvent> {
+        match self.rx.recv().await {
+            Some(event) => Some(self.received(event)),
+            None => self.state.held().take().map(HeldOutput::into_event),
+        }
+    }
+
+    /// Receive an event without waiting
+    pub fn try_recv(&mut self) -> Result<ExecutionEvent, TryRecvError> {
+        match self.rx.try_recv() {
+            Ok(event) => Ok(self.received(event)),
+            Err(TryRecvError::Disconnected) => self
+                .state
+                .held()
+                .take()
+                .map(HeldOutput::into_event)
+                .ok_or(TryRecvError::Disconnected),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Step output lines discarded or combined so far
+    pub fn counters(&self) -> OutputCounters {
+        self.state.counters()
+    }
+
+    fn received(&self, event: ExecutionEvent) -> ExecutionEvent {
+        if matches!(event, ExecutionEvent::StepOutput { .. }) {
+            let queued = self.state.queued_output.fetch_sub(1, Ordering::AcqRel) - 1;
+            if queued < self.state.capacity {
+                self.flush_held();
+            }
+        }
+        event
+    }
+
+    /// Queue held output behind what's already queued; anything sent after
+    /// it was held would have flushed it, so the order is kept
+    fn flush_held(&self) {
+        let mut held = self.state.held();
+        if held.is_none() {
+            return;
+        }
+        // With every sender gone, recv delivers it last instead
+        if let Some(tx) = self.tx.upgrade() {
+            if let Some(output) = held.take() {
+                let _ = self.state.enqueue(&tx, output.into_event());
+            }
+        }
+    }
+}
 
-/// Create a new progress channel
+/// Create a new progress channel that merges step output beyond
+/// [`DEFAULT_OUTPUT_CAPACITY`] queued events
 pub fn progress_channel() -> (ProgressSender, ProgressReceiver) {
-    mpsc::unbounded_channel()
+    bounded_progress_channel(DEFAULT_OUTPUT_CAPACITY, OverflowPolicy::default())
+}
+
+/// Create a progress channel that queues up to `capacity` step output events
+/// and applies `policy` to any more
+pub fn bounded_progress_channel(
+    capacity: usize,
+    policy: OverflowPolicy,
+) -> (ProgressSender, ProgressReceiver) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let weak_tx = tx.downgrade();
+    let state = Arc::new(ChannelState {
+        capacity,
+        policy,
+        queued_output: AtomicUsize::new(0),
+        held: Mutex::new(None),
+        dropped_lines: AtomicU64::new(0),
+        merged_lines: AtomicU64::new(0),
+        secrets: Mutex::new(Vec::new()),
+    });
+    (
+        ProgressSender {
+            tx,
+            state: state.clone(),
+        },
+        ProgressReceiver {
+            rx,
+            tx: weak_tx,
+            state,
+        },
+    )
 }
 
 /// Events emitted during pipeline execution
@@ -23,14 +323,19 @@ pub enum ExecutionEvent {
     /// Pipeline execution started
     PipelineStarted {
         pipeline_name: String,
+        /// ID of the run; the other events of the run belong to it
+        run_id: String,
         total_stages: usize,
+        started_at: SystemTime,
     },
 
     /// Pipeline execution completed
     PipelineCompleted {
         pipeline_name: String,
+        run_id: String,
         success: bool,
         duration: Duration,
+        finished_at: SystemTime,
     },
 
     /// Stage execution started
@@ -38,6 +343,7 @@ pub enum ExecutionEvent {
         stage_name: String,
         display_name: Option<String>,
         total_jobs: usize,
+        started_at: SystemTime,
     },
 
     /// Stage execution completed
@@ -45,6 +351,7 @@ pub enum ExecutionEvent {
         stage_name: String,
         status: StageStatus,
         duration: Duration,
+        finished_at: SystemTime,
     },
 
     /// Stage was skipped (condition evaluated to false)
@@ -57,6 +364,7 @@ pub enum ExecutionEvent {
         display_name: Option<String>,
         matrix_instance: Option<String>,
         total_steps: usize,
+        started_at: SystemTime,
     },
 
     /// Job execution completed
@@ -66,6 +374,7 @@ pub enum ExecutionEvent {
         matrix_instance: Option<String>,
         status: JobStatus,
         duration: Duration,
+        finished_at: SystemTime,
     },
 
     /// Job was skipped (condition evaluated to false)
@@ -82,6 +391,7 @@ pub enum ExecutionEvent {
         step_name: Option<String>,
         display_name: Option<String>,
         step_index: usize,
+        started_at: SystemTime,
     },
 
     /// Step output (stdout/stderr)
@@ -90,7 +400,8 @@ pub enum ExecutionEvent {
         job_name: String,
         step_name: Option<String>,
         step_index: usize,
-        output: String,
+        /// Shared so sinks and subscribers can hold it without copying
+        output: Arc<str>,
         is_error: bool,
     },
 
@@ -103,6 +414,26 @@ pub enum ExecutionEvent {
         status: StepStatus,
         duration: Duration,
         exit_code: Option<i32>,
+        finished_at: SystemTime,
+    },
+
+    /// Step published a markdown summary
+    StepSummary {
+        stage_name: String,
+        job_name: String,
+        step_name: Option<String>,
+        step_index: usize,
+        markdown: String,
+    },
+
+    /// Step went over the run's disk quota or free-space minimum and was
+    /// stopped
+    DiskQuotaExceeded {
+        stage_name: String,
+        job_name: String,
+        step_name: Option<String>,
+        step_index: usize,
+        message: String,
     },
 
     /// Step was skipped (condition evaluated to false or disabled)
@@ -152,19 +483,32 @@ pub enum LogLevel {
 
 impl ExecutionEvent {
     /// Create a pipeline started event
-    pub fn pipeline_started(name: impl Into<String>, total_stages: usize) -> Self {
+    pub fn pipeline_started(
+        name: impl Into<String>,
+        run_id: impl Into<String>,
+        total_stages: usize,
+    ) -> Self {
         Self::PipelineStarted {
             pipeline_name: name.into(),
+            run_id: run_id.into(),
             total_stages,
+            started_at: SystemTime::now(),
         }
     }
 
     /// Create a pipeline completed event
-    pub fn pipeline_completed(name: impl Into<String>, success: bool, duration: Duration) -> Self {
+    pub fn pipeline_completed(
+        name: impl Into<String>,
+        run_id: impl Into<String>,
+        success: bool,
+        duration: Duration,
+    ) -> Self {
         Self::PipelineCompleted {
             pipeline_name: name.into(),
+            run_id: run_id.into(),
             success,
             duration,
+            finished_at: SystemTime::now(),
         }
     }
 
@@ -178,6 +522,7 @@ impl ExecutionEvent {
             stage_name: name.into(),
             display_name,
             total_jobs,
+            started_at: SystemTime::now(),
         }
     }
 
@@ -191,6 +536,7 @@ impl ExecutionEvent {
             stage_name: name.into(),
             status,
             duration,
+            finished_at: SystemTime::now(),
         }
     }
 
@@ -208,6 +554,7 @@ impl ExecutionEvent {
             display_name,
             matrix_instance,
             total_steps,
+            started_at: SystemTime::now(),
         }
     }
 
@@ -225,6 +572,7 @@ impl ExecutionEvent {
             matrix_instance,
             status,
             duration,
+            finished_at: SystemTime::now(),
         }
     }
 
@@ -242,6 +590,7 @@ impl ExecutionEvent {
             step_name,
             display_name,
             step_index,
+            started_at: SystemTime::now(),
         }
     }
 
@@ -251,7 +600,7 @@ impl ExecutionEvent {
         job_name: impl Into<String>,
         step_name: Option<String>,
         step_index: usize,
-        output: impl Into<String>,
+        output: impl Into<Arc<str>>,
         is_error: bool,
     ) -> Self {
         Self::StepOutput {
@@ -282,6 +631,41 @@ impl ExecutionEvent {
             status,
             duration,
             exit_code,
+            finished_at: SystemTime::now(),
+        }
+    }
+
+    /// Create a step summary event
+    pub fn step_summary(
+        stage_name: impl Into<String>,
+        job_name: impl Into<String>,
+        step_name: Option<String>,
+        step_index: usize,
+        markdown: impl Into<String>,
+    ) -> Self {
+        Self::StepSummary {
+            stage_name: stage_name.into(),
+            job_name: job_name.into(),
+            step_name,
+            step_index,
+            markdown: markdown.into(),
+        }
+    }
+
+    /// Create a disk quota exceeded event
+    pub fn disk_quota_exceeded(
+        stage_name: impl Into<String>,
+        job_name: impl Into<String>,
+        step_name: Option<String>,
+        step_index: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::DiskQuotaExceeded {
+            stage_name: stage_name.into(),
+            job_name: job_name.into(),
+            step_name,
+            step_index,
+            message: message.into(),
         }
     }
 
@@ -343,6 +727,132 @@ impl ExecutionEvent {
     }
 }
 
+impl ExecutionEvent {
+    /// ID of the stage, job or step the event is about, within its run
+    ///
+    /// `None` for pipeline events and for logs and errors not tied to a stage.
+    pub fn correlation_id(&self) -> Option<String> {
+        let (stage, job, step) = match self {
+            Self::PipelineStarted { .. } | Self::PipelineCompleted { .. } => return None,
+            Self::StageStarted { stage_name, .. }
+            | Self::StageCompleted { stage_name, .. }
+            | Self::StageSkipped { stage_name, .. } => (stage_name, None, None),
+            Self::JobStarted {
+                stage_name,
+                job_name,
+                ..
+            }
+            | Self::JobCompleted {
+                stage_name,
+                job_name,
+                ..
+            }
+            | Self::JobSkipped {
+                stage_name,
+                job_name,
+                ..
+            }
+            | Self::VariableSet {
+                stage_name,
+                job_name,
+                ..
+            } => (stage_name, Some(job_name), None),
+            Self::StepStarted {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            }
+            | Self::StepOutput {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            }
+            | Self::StepCompleted {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            }
+            | Self::StepSummary {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            }
+            | Self::DiskQuotaExceeded {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            }
+            | Self::StepSkipped {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            } => (stage_name, Some(job_name), Some(*step_index)),
+            Self::Log {
+                stage_name,
+                job_name,
+                ..
+            } => (stage_name.as_ref()?, job_name.as_ref(), None),
+            Self::Error {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            } => (stage_name.as_ref()?, job_name.as_ref(), *step_index),
+        };
+        Some(correlation_id(stage, job.map(String::as_str), step))
+    }
+
+    /// Number of lines in a step output event (0 for other events)
+    fn output_lines(&self) -> u64 {
+        match self {
+            Self::StepOutput { output, .. } => output.lines().count().max(1) as u64,
+            _ => 0,
+        }
+    }
+
+    /// Whether this and `next` are output of the same stream of the same step
+    fn same_output_stream(&self, next: &Self) -> bool {
+        let (
+            Self::StepOutput {
+                stage_name,
+                job_name,
+                step_index,
+                is_error,
+                ..
+            },
+            Self::StepOutput {
+                stage_name: next_stage,
+                job_name: next_job,
+                step_index: next_index,
+                is_error: next_is_error,
+                ..
+            },
+        ) = (self, next)
+        else {
+            return false;
+        };
+        stage_name == next_stage
+            && job_name == next_job
+            && step_index == next_index
+            && is_error == next_is_error
+    }
+}
+
+/// Replace each occurrence of a secret in `text` with `***`, longest secrets first
+pub fn redact(text: &str, secrets: &[String]) -> String {
+    let mut secrets: Vec<&String> = secrets.iter().filter(|s| !s.is_empty()).collect();
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    secrets.into_iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret.as_str(), "***")
+    })
+}
+
 /// Helper trait for sending events, ignoring errors (fire-and-forget)
 pub trait EventSender {
     fn send_event(&self, event: ExecutionEvent);
@@ -366,11 +876,68 @@ impl EventSender for Option<ProgressSender> {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_secrets_are_masked() {
+        let (tx, mut rx) = progress_channel();
+        tx.mask_secrets(["hunter2".to_string(), "hunter".to_string()]);
+        tx.clone().send_event(ExecutionEvent::step_output(
+            "Build",
+            "Deploy",
+            None,
+            0,
+            "login --password hunter2 --user hunter",
+            false,
+        ));
+
+        match rx.recv().await {
+            Some(ExecutionEvent::StepOutput { output, .. }) => {
+                assert_eq!(&*output, "login --password *** --user ***")
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secrets_are_masked_in_every_event() {
+        let (tx, mut rx) = progress_channel();
+        tx.mask_secrets(["hunter2".to_string()]);
+        tx.send_event(ExecutionEvent::info("token is hunter2", None, None));
+        tx.send_event(ExecutionEvent::Error {
+            message: "login failed for hunter2".to_string(),
+            stage_name: None,
+            job_name: None,
+            step_index: None,
+        });
+        tx.send_event(ExecutionEvent::step_summary(
+            "Build",
+            "Deploy",
+            None,
+            0,
+            "| key | hunter2 |",
+        ));
+        drop(tx);
+
+        let mut texts = Vec::new();
+        while let Some(event) = rx.recv().await {
+            texts.push(match event {
+                ExecutionEvent::Log { message, .. } | ExecutionEvent::Error { message, .. } => {
+                    message
+                }
+                ExecutionEvent::StepSummary { markdown, .. } => markdown,
+                other => panic!("unexpected event: {:?}", other),
+            });
+        }
+        assert_eq!(
+            texts,
+            ["token is ***", "login failed for ***", "| key | *** |"]
+        );
+    }
+
     #[tokio::test]
     async fn test_progress_channel() {
         let (tx, mut rx) = progress_channel();
 
-        tx.send_event(ExecutionEvent::pipeline_started("test", 2));
+        tx.send_event(ExecutionEvent::pipeline_started("test", "01RUN", 2));
         tx.send_event(ExecutionEvent::stage_started("Build", None, 1));
 
         let event1 = rx.recv().await.unwrap();
@@ -396,6 +963,7 @@ mod tests {
             matrix_instance,
             status,
             duration,
+            finished_at,
         } = event
         {
             assert_eq!(stage_name, "Build");
@@ -403,11 +971,115 @@ mod tests {
             assert_eq!(matrix_instance, Some("linux".to_string()));
             assert_eq!(status, JobStatus::Succeeded);
             assert_eq!(duration, Duration::from_secs(30));
+            assert!(finished_at <= SystemTime::now());
         } else {
             panic!("wrong event type");
         }
     }
 
+    fn output(step_index: usize, text: &str) -> ExecutionEvent {
+        ExecutionEvent::step_output("Build", "Compile", None, step_index, text, false)
+    }
+
+    #[tokio::test]
+    async fn test_full_channel_merges_output_in_order() {
+        let (tx, mut rx) = bounded_progress_channel(1, OverflowPolicy::Merge);
+
+        tx.send_event(output(0, "one"));
+        tx.send_event(output(0, "two"));
+        tx.send_event(output(0, "three"));
+        tx.send_event(ExecutionEvent::info("done", None, None));
+        tx.send_event(output(1, "four"));
+        tx.send_event(output(1, "five"));
+        assert_eq!(tx.counters().merged_lines, 2);
+        drop(tx);
+
+        let mut received = Vec::new();
+        while let Some(event) = rx.recv().await {
+            received.push(match event {
+                ExecutionEvent::StepOutput { output, .. } => output.to_string(),
+                ExecutionEvent::Log { message, .. } => message,
+                other => panic!("unexpected event {:?}", other),
+            });
+        }
+        // Held output is delivered before later events and when the senders go away
+        assert_eq!(received, vec!["one", "two\nthree", "done", "four\nfive"]);
+    }
+
+    #[tokio::test]
+    async fn test_held_output_is_delivered_once_there_is_room() {
+        let (tx, mut rx) = bounded_progress_channel(1, OverflowPolicy::Merge);
+
+        tx.send_event(output(0, "one"));
+        tx.send_event(output(0, "two"));
+        tx.send_event(output(0, "three"));
+
+        let mut received = Vec::new();
+        while let Ok(ExecutionEvent::StepOutput { output, .. }) = rx.try_recv() {
+            received.push(output.to_string());
+        }
+        // The senders are still around, so nothing else would flush it
+        assert_eq!(received, vec!["one", "two\nthree"]);
+    }
+
+    #[tokio::test]
+    async fn test_held_output_is_capped() {
+        let (tx, mut rx) = bounded_progress_channel(1, OverflowPolicy::Merge);
+        let big = "x".repeat(MAX_HELD_OUTPUT - 4);
+
+        tx.send_event(output(0, "one"));
+        tx.send_event(output(0, &big));
+        tx.send_event(output(0, "two"));
+        tx.send_event(output(0, "three"));
+        assert_eq!(
+            tx.counters(),
+            OutputCounters {
+                dropped_lines: 1,
+                merged_lines: 1
+            }
+        );
+        drop(tx);
+
+        let mut received = Vec::new();
+        while let Some(ExecutionEvent::StepOutput { output, .. }) = rx.recv().await {
+            received.push(output.len());
+        }
+        assert_eq!(received, vec![3, big.len() + 4]);
+    }
+
+    #[tokio::test]
+    async fn test_full_channel_drops_output_but_not_other_events() {
+        let (tx, mut rx) = bounded_progress_channel(2, OverflowPolicy::Drop);
+
+        for i in 0..5 {
+            tx.send_event(output(0, &format!("line {}", i)));
+        }
+        tx.send_event(ExecutionEvent::info("done", None, None));
+        assert!(matches!(
+            rx.recv().await,
+            Some(ExecutionEvent::StepOutput { .. })
+        ));
+        tx.send_event(output(0, "after"));
+        drop(tx);
+
+        let mut outputs = 1;
+        let mut logs = 0;
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                ExecutionEvent::StepOutput { .. } => outputs += 1,
+                _ => logs += 1,
+            }
+        }
+        assert_eq!((outputs, logs), (3, 1));
+        assert_eq!(
+            rx.counters(),
+            OutputCounters {
+                dropped_lines: 3,
+                merged_lines: 0
+            }
+        );
+    }
+
     #[test]
     fn test_optional_sender() {
         let sender: Option<ProgressSender> = None;
@@ -1,40 +1,70 @@
 // Pipeline Executor
 // Orchestrates pipeline execution with DAG-based scheduling
 
+use crate::execution::cache_storage::{CacheStorage, LocalCacheStorage};
+use crate::execution::confirm::{StepConfirmer, StepPreview};
 use crate::execution::context::RuntimeContext;
-use crate::execution::events::{EventSender, ExecutionEvent, ProgressSender};
+use crate::execution::disk_quota::DiskQuota;
+use crate::execution::events::{redact, EventSender, ExecutionEvent, ProgressSender};
+use crate::execution::faults::{self, Fault};
 use crate::execution::graph::{ExecutionGraph, GraphError, JobNode, StageNode};
-use crate::execution::matrix::MatrixExpander;
+use crate::execution::matrix::{MatrixExpander, MatrixSelection};
+use crate::execution::pipeline_cache::{self, CacheHit, CacheSave};
+use crate::execution::process::StepProcess;
+use crate::execution::recording::{CommandRecording, RecordingMode, StepRecorder};
+use crate::execution::rerun::StageRerun;
+use crate::execution::resources::{ResourcePool, Resources};
+use crate::execution::scheduler::{concurrency_limit, ReadyQueue};
+use crate::execution::secret_provider::{filter_secrets, SecretProvider};
+use crate::execution::step_summary::{SummaryFile, STEP_SUMMARY_ENV};
 use crate::parser::models::{
-    ExecutionContext, Job, JobResult, JobStatus, Pipeline, StageResult, StageStatus, Step,
-    StepAction, StepResult, StepStatus,
+    collect_deployment_steps, CheckoutSource, ComposeSpec, ExecutionContext, Job, JobResult,
+    JobStatus, Pipeline, ScriptStep, StageResult, StageStatus, Step, StepAction, StepResult,
+    StepStatus, TaskStep, UnsupportedFeature, UnsupportedKind, VariableSubstitution,
 };
-use crate::runners::container::ContainerRunner;
-use crate::runners::task::TaskRunner;
-
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::time::{Duration, Instant};
+use crate::runners::checkout::CheckoutRunner;
+use crate::runners::compose::{ComposeProject, ComposeRunner};
+use crate::runners::container::{ContainerConfig, ContainerRunner, ImagePullPolicy};
+use crate::runners::hosts::{host_env, HostOverride};
+use crate::runners::sandbox::{Sandbox, SandboxProfile};
+use crate::runners::shell::ShellSpec;
+use crate::runners::task::{TaskRunner, TaskRunnerError};
+use crate::tasks::service_connection::ServiceConnections;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::stream::{FuturesUnordered, StreamExt};
 
 /// Result of pipeline execution
 #[derive(Debug, Clone)]
 pub struct ExecutionResult {
+    /// ID of the run; empty for results not produced by a run
+    pub run_id: String,
     /// All stage results
     pub stages: Vec<StageResult>,
     /// Total duration
     pub duration: Duration,
+    /// When the run started; `None` for results not produced by a run
+    pub started_at: Option<SystemTime>,
+    /// When the run finished; `None` for results not produced by a run
+    pub finished_at: Option<SystemTime>,
     /// Overall success
     pub success: bool,
     /// Final variables state
     pub variables: HashMap<String, String>,
+    /// Digest each container image ran at, by the reference the pipeline wrote
+    pub images: BTreeMap<String, String>,
 }
 
 /// Configuration for pipeline execution
 #[derive(Debug, Clone)]
 pub struct ExecutorConfig {
-    /// Maximum parallel stages (0 = unlimited)
+    /// Maximum stages running at once (0 = unlimited)
     pub max_parallel_stages: usize,
-    /// Maximum parallel jobs within a stage (0 = unlimited)
+    /// Maximum jobs running at once within a stage (0 = unlimited)
     pub max_parallel_jobs: usize,
     /// Default timeout for steps (in minutes)
     pub default_step_timeout: u32,
@@ -44,17 +74,87 @@ pub struct ExecutorConfig {
     pub task_cache_dir: Option<PathBuf>,
     /// Whether to enable container support
     pub enable_containers: bool,
+    /// Whether to log every variable substituted into step commands, and
+    /// warn about values that could change a command's structure
+    pub audit_substitutions: bool,
+    /// Whether to log the environment each step's command gets, secrets masked
+    pub audit_env: bool,
+    /// Custom shells for `script` steps (name to command template, e.g. `perl {0}`)
+    pub shells: HashMap<String, String>,
+    /// Clone sources for repository resources (alias to URL or local directory)
+    pub repositories: HashMap<String, String>,
+    /// Record the output of script and task steps, or replay it instead of
+    /// running them
+    pub recorder: Option<StepRecorder>,
+    /// Fail steps that use a construct roxid doesn't implement, instead of
+    /// skipping or stubbing it
+    pub fail_on_unsupported: bool,
+    /// Run every command without network access and with writes confined to
+    /// the workspace
+    pub sandbox: Option<SandboxProfile>,
+    /// Report every script step's command instead of running it
+    pub dry_run: bool,
+    /// Matrix instances to run
+    pub matrix: MatrixSelection,
+    /// Service connections passed to tasks that name them in their inputs
+    pub service_connections: ServiceConnections,
+    /// Local stand-ins for key vaults read by `AzureKeyVault` steps
+    pub key_vaults: HashMap<String, SecretProvider>,
+    /// Values to mask in step output, e.g. from secret variable groups
+    pub secrets: Vec<String>,
+    /// Run only these stages again, reusing an earlier run's results for the rest
+    pub rerun: Option<StageRerun>,
+    /// Failures and delays to force on stages, jobs and steps
+    pub faults: Vec<Fault>,
+    /// Hostnames to point at local stand-ins, in containers and in steps'
+    /// environment
+    pub hosts: Vec<HostOverride>,
+    /// When container images are pulled
+    pub pull_policy: ImagePullPolicy,
+    /// Digests to run container images at, by image reference
+    pub image_pins: HashMap<String, String>,
+    /// Host directories mounted in job containers, by host path
+    pub container_mounts: BTreeMap<String, String>,
+    /// Workspace quota and free-space minimum; a step that goes over either
+    /// is stopped and fails
+    pub disk_quota: DiskQuota,
+    /// Host resources every job draws on, by its `resources:`; a ready job
+    /// waits until what it needs is free (`--max-parallel auto`)
+    pub resources: Option<ResourcePool>,
+    /// Where Cache@2 steps keep their entries (default: `~/.roxid/cache`)
+    pub cache_storage: Option<Arc<dyn CacheStorage>>,
 }
 
 impl Default for ExecutorConfig {
     fn default() -> Self {
         Self {
-            max_parallel_stages: 0,
-            max_parallel_jobs: 0,
+            max_parallel_stages: 1,
+            max_parallel_jobs: 1,
             default_step_timeout: 60,
             continue_on_error: false,
             task_cache_dir: None,
             enable_containers: false,
+            audit_substitutions: false,
+            audit_env: false,
+            shells: HashMap::new(),
+            repositories: HashMap::new(),
+            recorder: None,
+            fail_on_unsupported: false,
+            sandbox: None,
+            dry_run: false,
+            matrix: MatrixSelection::default(),
+            service_connections: ServiceConnections::default(),
+            key_vaults: HashMap::new(),
+            secrets: Vec::new(),
+            rerun: None,
+            faults: Vec::new(),
+            hosts: Vec::new(),
+            pull_policy: ImagePullPolicy::default(),
+            image_pins: HashMap::new(),
+            container_mounts: BTreeMap::new(),
+            disk_quota: DiskQuota::default(),
+            resources: None,
+            cache_storage: None,
         }
     }
 }
@@ -71,6 +171,10 @@ pub struct PipelineExecutor {
     task_runner: Option<TaskRunner>,
     /// Container runner for Docker-based jobs
     container_runner: Option<ContainerRunner>,
+    /// Asked before each command runs, when set
+    confirmer: Option<Arc<dyn StepConfirmer>>,
+    /// Sandbox for the configured profile, or why it can't be enforced here
+    sandbox: Option<Result<Sandbox, String>>,
 }
 
 impl PipelineExecutor {
@@ -83,6 +187,8 @@ impl PipelineExecutor {
             event_tx: None,
             task_runner: None,
             container_runner: None,
+            confirmer: None,
+            sandbox: None,
         })
     }
 
@@ -94,22 +200,27 @@ impl PipelineExecutor {
             event_tx: None,
             task_runner: None,
             container_runner: None,
+            confirmer: None,
+            sandbox: None,
         }
     }
 
     /// Set executor configuration
     pub fn with_config(mut self, config: ExecutorConfig) -> Self {
+        self.sandbox = config.sandbox.clone().map(Sandbox::detect);
+
         // Set up task runner if cache dir is specified
         if let Some(cache_dir) = &config.task_cache_dir {
-            self.task_runner = Some(TaskRunner::new(cache_dir.clone()));
+            self.task_runner = Some(self.new_task_runner(cache_dir.clone()));
         }
 
+        self.config = config;
+
         // Set up container runner if enabled
-        if config.enable_containers {
-            self.container_runner = Some(ContainerRunner::new());
+        if self.config.enable_containers {
+            self.container_runner = Some(self.new_container_runner());
         }
 
-        self.config = config;
         self
     }
 
@@ -121,61 +232,190 @@ impl PipelineExecutor {
 
     /// Enable task execution with the specified cache directory
     pub fn with_task_runner(mut self, cache_dir: PathBuf) -> Self {
-        self.task_runner = Some(TaskRunner::new(cache_dir));
+        self.task_runner = Some(self.new_task_runner(cache_dir));
         self
     }
 
+    fn new_task_runner(&self, cache_dir: PathBuf) -> TaskRunner {
+        let runner = TaskRunner::new(cache_dir);
+        match &self.sandbox {
+            Some(Ok(sandbox)) => runner.with_sandbox(sandbox.clone()),
+            _ => runner,
+        }
+    }
+
     /// Enable container execution
     pub fn with_container_runner(mut self) -> Self {
-        self.container_runner = Some(ContainerRunner::new());
+        self.container_runner = Some(self.new_container_runner());
+        self
+    }
+
+    fn new_container_runner(&self) -> ContainerRunner {
+        ContainerRunner::with_config(ContainerConfig {
+            hosts: self.config.hosts.clone(),
+            pull_policy: self.config.pull_policy,
+            pins: self.config.image_pins.clone(),
+            mounts: self.config.container_mounts.clone(),
+            ..Default::default()
+        })
+        .with_resources(&self.graph.containers)
+        .with_registries(self.config.service_connections.clone())
+    }
+
+    /// Ask `confirmer` before running each command; declined steps are skipped
+    pub fn with_confirmer(mut self, confirmer: Arc<dyn StepConfirmer>) -> Self {
+        self.confirmer = Some(confirmer);
         self
     }
 
     /// Execute the pipeline
     pub async fn execute(&self, context: ExecutionContext) -> ExecutionResult {
         let start = Instant::now();
+        let started_at = SystemTime::now();
         let mut runtime = RuntimeContext::new(context);
 
         // Merge pipeline-level variables (test-provided variables from ExecutionContext
         // were already loaded in RuntimeContext::new, so save them, merge pipeline vars,
         // then re-apply test vars so they take precedence)
         let test_vars = runtime.variables.clone();
-        runtime.merge_pipeline_variables(&self.graph.variables);
+        let variables_error = runtime
+            .merge_pipeline_variables(&self.graph.variables)
+            .err();
         for (k, v) in test_vars {
             runtime.variables.insert(k, v);
         }
+        for (name, value) in host_env(&self.config.hosts) {
+            runtime.set_env(name, crate::parser::models::Value::String(value));
+        }
 
         let mut stage_results = Vec::new();
         let mut overall_success = true;
 
+        if let Some(tx) = &self.event_tx {
+            tx.mask_secrets(self.secrets());
+        }
+
         // Send pipeline started event
         self.event_tx.send_event(ExecutionEvent::pipeline_started(
             &runtime.base.pipeline_name,
+            &runtime.base.run_id,
             self.graph.stages.len(),
         ));
 
-        // Execute stages in topological order, respecting parallelism
-        let parallel_stages = self.graph.parallel_stages();
+        if let Some(error) = variables_error {
+            self.event_tx
+                .send_event(ExecutionEvent::execution_error(error, None, None, None));
+            overall_success = false;
+        }
 
-        for stage_level in parallel_stages {
-            // Execute stages at this level (potentially in parallel)
-            let level_results = self.execute_stage_level(&stage_level, &mut runtime).await;
+        // Fail fast if a step selects a shell that is unknown or not installed
+        for error in self.preflight_shells() {
+            self.event_tx
+                .send_event(ExecutionEvent::execution_error(error, None, None, None));
+            overall_success = false;
+        }
+        if let Some(Err(error)) = &self.sandbox {
+            self.event_tx.send_event(ExecutionEvent::execution_error(
+                error.clone(),
+                None,
+                None,
+                None,
+            ));
+            overall_success = false;
+        }
+        for error in self.preflight_sandboxed_containers() {
+            self.event_tx
+                .send_event(ExecutionEvent::execution_error(error, None, None, None));
+            overall_success = false;
+        }
 
-            for result in level_results {
-                let failed = result.status == StageStatus::Failed;
-                stage_results.push(result);
+        // Refuse to start on a nearly full disk, then hold every step to the limits
+        let workspace = PathBuf::from(&runtime.base.working_dir);
+        if let Err(error) = self.config.disk_quota.check_free_space(&workspace) {
+            self.event_tx
+                .send_event(ExecutionEvent::execution_error(error, None, None, None));
+            overall_success = false;
+        }
+        runtime.disk_guard = self.config.disk_quota.guard(&workspace).await;
+
+        // Bring up pipeline-scoped compose services; nothing runs if they fail to start
+        let pipeline_name = runtime.base.pipeline_name.clone();
+        let compose = match &self.graph.compose {
+            Some(spec) if overall_success => {
+                let project = self
+                    .start_compose(spec, &pipeline_name, None, None, &mut runtime)
+                    .await;
+                overall_success = project.is_some();
+                project
+            }
+            _ => None,
+        };
 
-                if failed {
-                    overall_success = false;
-                    if !self.config.continue_on_error {
+        // Start each stage as soon as the stages it depends on have finished
+        if overall_success {
+            let mut queue = ReadyQueue::new(self.graph.stages.iter().map(|s| {
+                (
+                    s.stage.stage.as_deref().unwrap_or(""),
+                    s.dependencies.as_slice(),
+                )
+            }));
+            let limit = concurrency_limit(self.config.max_parallel_stages);
+            let mut running = FuturesUnordered::new();
+            let mut results = Vec::new();
+            let mut written = Vec::new();
+            // A reused failure fails the run, but doesn't stop the re-run stages from starting
+            let mut reused_failure = false;
+
+            loop {
+                // After a failure, let running stages finish but start no new ones
+                while (overall_success || self.config.continue_on_error) && running.len() < limit {
+                    let Some(index) = queue.next_ready() else {
                         break;
+                    };
+                    let stage_node = &self.graph.stages[index];
+                    if let Some(previous) = self.config.rerun.as_ref().and_then(|rerun| {
+                        rerun.reused(stage_node.stage.stage.as_deref().unwrap_or(""))
+                    }) {
+                        // Not run again: its earlier result stands in for it
+                        runtime.reuse_stage(previous.clone());
+                        queue.complete(index);
+                        reused_failure |=
+                            matches!(previous.status, StageStatus::Failed | StageStatus::Canceled);
+                        results.push((index, previous.clone()));
+                        continue;
                     }
+                    let base = runtime.snapshot();
+                    running.push(async move {
+                        let mut fork = base.fork();
+                        let result = self.execute_stage(stage_node, &mut fork).await;
+                        (index, base, fork, result)
+                    });
+                }
+
+                let Some((index, base, fork, result)) = running.next().await else {
+                    break;
+                };
+                queue.complete(index);
+                written.push((index, runtime.absorb(&base, fork)));
+                if matches!(result.status, StageStatus::Failed | StageStatus::Canceled) {
+                    overall_success = false;
                 }
+                results.push((index, result));
             }
 
-            if !overall_success && !self.config.continue_on_error {
-                break;
+            overall_success &= !reused_failure;
+
+            // Report stages in declaration order, whatever order they finished in
+            results.sort_by_key(|(index, _)| *index);
+            written.sort_by_key(|(index, _)| *index);
+            for (_, variables) in written {
+                runtime.publish(variables);
             }
+            stage_results.extend(results.into_iter().map(|(_, result)| result));
+        }
+
+        if let Some(project) = compose {
+            self.stop_compose(project, None, None).await;
         }
 
         let duration = start.elapsed();
@@ -183,38 +423,83 @@ impl PipelineExecutor {
         // Send pipeline completed event
         self.event_tx.send_event(ExecutionEvent::pipeline_completed(
             &runtime.base.pipeline_name,
+            &runtime.base.run_id,
             overall_success,
             duration,
         ));
 
         ExecutionResult {
+            run_id: runtime.base.run_id.clone(),
             stages: stage_results,
             duration,
             success: overall_success,
             variables: runtime
                 .variables
                 .iter()
+                .chain(&runtime.job_variables)
                 .map(|(k, v)| (k.clone(), v.as_string()))
                 .collect(),
+            images: self
+                .container_runner
+                .as_ref()
+                .map(ContainerRunner::resolved_digests)
+                .unwrap_or_default(),
+            started_at: Some(started_at),
+            finished_at: Some(SystemTime::now()),
         }
     }
 
-    /// Execute a level of stages (can run in parallel)
-    async fn execute_stage_level(
-        &self,
-        stages: &[&StageNode],
-        runtime: &mut RuntimeContext,
-    ) -> Vec<StageResult> {
-        // For now, execute sequentially
-        // TODO: Add parallel execution with semaphore limiting
-        let mut results = Vec::new();
-
-        for stage in stages {
-            let result = self.execute_stage(stage, runtime).await;
-            results.push(result);
+    /// Check that every shell selected by a `script` step resolves and is installed
+    fn preflight_shells(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut checked = HashSet::new();
+
+        for stage in &self.graph.stages {
+            for node in &stage.jobs {
+                let deployment_steps = collect_deployment_steps(&node.job);
+                for step in node.job.steps.iter().chain(&deployment_steps) {
+                    let StepAction::Script(ScriptStep {
+                        shell: Some(name), ..
+                    }) = &step.action
+                    else {
+                        continue;
+                    };
+                    if !checked.insert(name.clone()) {
+                        continue;
+                    }
+                    match ShellSpec::resolve(name, &self.config.shells) {
+                        Ok(spec) if !spec.is_available() => errors.push(format!(
+                            "Shell '{}' is not available: '{}' was not found on PATH",
+                            name, spec.program
+                        )),
+                        Ok(_) => {}
+                        Err(e) => errors.push(e),
+                    }
+                }
+            }
         }
 
-        results
+        errors
+    }
+
+    /// Refuse container jobs under the sandbox, which only confines processes on the host
+    fn preflight_sandboxed_containers(&self) -> Vec<String> {
+        if self.sandbox.is_none() || self.container_runner.is_none() {
+            return Vec::new();
+        }
+        self.graph
+            .stages
+            .iter()
+            .flat_map(|stage| &stage.jobs)
+            .filter(|node| node.job.container.is_some())
+            .map(|node| {
+                format!(
+                    "Job '{}' runs in a container, which the sandbox can't confine; \
+                     run it without the sandbox or without containers",
+                    node.job.identifier().unwrap_or("")
+                )
+            })
+            .collect()
     }
 
     /// Execute a single stage
@@ -224,8 +509,10 @@ impl PipelineExecutor {
         runtime: &mut RuntimeContext,
     ) -> StageResult {
         let start = Instant::now();
+        let started_at = SystemTime::now();
         let stage = &stage_node.stage;
         let stage_name = stage.stage.clone().unwrap_or_default();
+        let display_name = runtime.resolve_display_name(stage.display_name.as_deref());
 
         // Check dependencies
         if !stage_node.dependencies.is_empty()
@@ -238,10 +525,12 @@ impl PipelineExecutor {
 
             return StageResult {
                 stage_name: stage_name.clone(),
-                display_name: stage.display_name.clone(),
+                display_name: display_name.clone(),
                 status: StageStatus::Skipped,
-                jobs: skipped_job_results(stage_node),
+                jobs: skipped_job_results(stage_node, runtime),
                 duration: start.elapsed(),
+                started_at: None,
+                finished_at: None,
             };
         }
 
@@ -257,10 +546,12 @@ impl PipelineExecutor {
 
                     return StageResult {
                         stage_name: stage_name.clone(),
-                        display_name: stage.display_name.clone(),
+                        display_name: display_name.clone(),
                         status: StageStatus::Skipped,
-                        jobs: skipped_job_results(stage_node),
+                        jobs: skipped_job_results(stage_node, runtime),
                         duration: start.elapsed(),
+                        started_at: None,
+                        finished_at: None,
                     };
                 }
                 Err(e) => {
@@ -272,62 +563,146 @@ impl PipelineExecutor {
 
                     return StageResult {
                         stage_name: stage_name.clone(),
-                        display_name: stage.display_name.clone(),
+                        display_name: display_name.clone(),
                         status: StageStatus::Failed,
-                        jobs: skipped_job_results(stage_node),
+                        jobs: skipped_job_results(stage_node, runtime),
                         duration: start.elapsed(),
+                        started_at: None,
+                        finished_at: None,
                     };
                 }
             }
         }
 
         // Enter stage
-        runtime.enter_stage(stage);
+        if let Err(e) = runtime.enter_stage(stage) {
+            self.event_tx
+                .send_event(ExecutionEvent::error(e, Some(stage_name.clone()), None));
+
+            return StageResult {
+                stage_name: stage_name.clone(),
+                display_name: display_name.clone(),
+                status: StageStatus::Failed,
+                jobs: skipped_job_results(stage_node, runtime),
+                duration: start.elapsed(),
+                started_at: None,
+                finished_at: None,
+            };
+        }
+        // Stage variables may appear in the display name
+        let display_name = runtime.resolve_display_name(stage.display_name.as_deref());
 
         self.event_tx.send_event(ExecutionEvent::stage_started(
             &stage_name,
-            stage.display_name.clone(),
+            display_name.clone(),
             stage_node.jobs.len(),
         ));
+        self.inject_delay(
+            faults::stage_delay(&self.config.faults, &stage_name),
+            Some(&stage_name),
+            None,
+        )
+        .await;
 
         // Execute jobs
         let mut job_results = Vec::new();
         let mut stage_status = StageStatus::Succeeded;
 
-        // Get jobs in topological order with parallel levels
-        let parallel_jobs = self.graph.parallel_jobs(stage_node);
-
-        for job_level in parallel_jobs {
-            // Execute jobs at this level
-            let level_results = self
-                .execute_job_level(&job_level, &stage_name, runtime)
-                .await;
+        // Start each job as soon as the jobs it depends on have finished
+        let mut queue = ReadyQueue::new(stage_node.jobs.iter().map(|j| {
+            (
+                j.job.identifier().unwrap_or("unknown"),
+                j.dependencies.as_slice(),
+            )
+        }));
+        let limit = concurrency_limit(self.config.max_parallel_jobs);
+        let mut running = FuturesUnordered::new();
+        let mut written = Vec::new();
+
+        loop {
+            // Subscribed before trying, so a release in between still wakes the wait below
+            let mut released = self.config.resources.as_ref().map(ResourcePool::subscribe);
+            let mut waiting_for_resources = false;
+
+            // After a failure, let running jobs finish but start no new ones
+            while (!matches!(stage_status, StageStatus::Failed | StageStatus::Canceled)
+                || self.config.continue_on_error)
+                && running.len() < limit
+            {
+                let Some(index) = queue.peek_ready() else {
+                    break;
+                };
+                let job_node = &stage_node.jobs[index];
+                let lease = match &self.config.resources {
+                    Some(pool) => match pool.try_acquire(Resources::for_job(&job_node.job)) {
+                        Some(lease) => Some(lease),
+                        None => {
+                            waiting_for_resources = true;
+                            break;
+                        }
+                    },
+                    None => None,
+                };
+                queue.next_ready();
+                let base = runtime.snapshot();
+                let stage_name = stage_name.as_str();
+                running.push(async move {
+                    let _lease = lease;
+                    let mut fork = base.fork();
+                    let results = self.execute_job(job_node, stage_name, &mut fork).await;
+                    (index, base, fork, results)
+                });
+            }
 
-            for result in level_results {
+            // Jobs of other stages may free the resources before ours finish
+            let finished = match released.as_mut() {
+                Some(released) if waiting_for_resources => tokio::select! {
+                    finished = running.next(), if !running.is_empty() => finished,
+                    _ = released.changed() => continue,
+                },
+                _ => running.next().await,
+            };
+            let Some((index, base, fork, results)) = finished else {
+                break;
+            };
+            queue.complete(index);
+            written.push((index, runtime.absorb(&base, fork)));
+            for result in &results {
                 if result.status == JobStatus::Failed {
                     stage_status = StageStatus::Failed;
+                } else if result.status == JobStatus::Canceled {
+                    if stage_status != StageStatus::Failed {
+                        stage_status = StageStatus::Canceled;
+                    }
                 } else if result.status == JobStatus::SucceededWithIssues
                     && stage_status == StageStatus::Succeeded
                 {
                     stage_status = StageStatus::SucceededWithIssues;
                 }
-                job_results.push(result);
             }
+            job_results.push((index, results));
+        }
+        drop(running);
 
-            if stage_status == StageStatus::Failed && !self.config.continue_on_error {
-                break;
-            }
+        // Report jobs in declaration order, whatever order they finished in
+        job_results.sort_by_key(|(index, _)| *index);
+        written.sort_by_key(|(index, _)| *index);
+        for (_, variables) in written {
+            runtime.publish(variables);
         }
+        let job_results: Vec<JobResult> = job_results.into_iter().flat_map(|(_, r)| r).collect();
 
         let duration = start.elapsed();
 
         // Exit stage
         let result = StageResult {
             stage_name: stage_name.clone(),
-            display_name: stage.display_name.clone(),
+            display_name: display_name.clone(),
             status: stage_status.clone(),
             jobs: job_results,
             duration,
+            started_at: Some(started_at),
+            finished_at: Some(SystemTime::now()),
         };
 
         runtime.exit_stage(result.clone());
@@ -341,34 +716,16 @@ impl PipelineExecutor {
         result
     }
 
-    /// Execute a level of jobs (can run in parallel)
-    async fn execute_job_level(
-        &self,
-        jobs: &[&JobNode],
-        stage_name: &str,
-        runtime: &mut RuntimeContext,
-    ) -> Vec<JobResult> {
-        // For now, execute sequentially
-        // TODO: Add parallel execution with semaphore and max_parallel
-        let mut results = Vec::new();
-
-        for job_node in jobs {
-            let result = self.execute_job(job_node, stage_name, runtime).await;
-            results.push(result);
-        }
-
-        results
-    }
-
-    /// Execute a single job (potentially with matrix expansion)
+    /// Execute a single job, or each instance of a matrix job
     async fn execute_job(
         &self,
         job_node: &JobNode,
         stage_name: &str,
         runtime: &mut RuntimeContext,
-    ) -> JobResult {
+    ) -> Vec<JobResult> {
         let job = &job_node.job;
         let job_name = job.identifier().unwrap_or("unknown").to_string();
+        let display_name = runtime.resolve_display_name(job.display_name.as_deref());
         let start = Instant::now();
 
         // Check dependencies
@@ -381,14 +738,16 @@ impl PipelineExecutor {
                 reason: "Dependencies failed".to_string(),
             });
 
-            return JobResult {
+            return vec![JobResult {
                 job_name,
-                display_name: job.display_name.clone(),
+                display_name: display_name.clone(),
                 status: JobStatus::Skipped,
-                steps: skipped_step_results(job),
+                steps: skipped_step_results(job, runtime),
                 duration: start.elapsed(),
                 outputs: HashMap::new(),
-            };
+                started_at: None,
+                finished_at: None,
+            }];
         }
 
         // Evaluate condition
@@ -402,14 +761,16 @@ impl PipelineExecutor {
                         reason: format!("Condition '{}' evaluated to false", condition),
                     });
 
-                    return JobResult {
+                    return vec![JobResult {
                         job_name,
-                        display_name: job.display_name.clone(),
+                        display_name: display_name.clone(),
                         status: JobStatus::Skipped,
-                        steps: skipped_step_results(job),
+                        steps: skipped_step_results(job, runtime),
                         duration: start.elapsed(),
                         outputs: HashMap::new(),
-                    };
+                        started_at: None,
+                        finished_at: None,
+                    }];
                 }
                 Err(e) => {
                     self.event_tx.send_event(ExecutionEvent::error(
@@ -418,14 +779,16 @@ impl PipelineExecutor {
                         Some(job_name.clone()),
                     ));
 
-                    return JobResult {
+                    return vec![JobResult {
                         job_name,
-                        display_name: job.display_name.clone(),
+                        display_name: display_name.clone(),
                         status: JobStatus::Failed,
-                        steps: skipped_step_results(job),
+                        steps: skipped_step_results(job, runtime),
                         duration: start.elapsed(),
                         outputs: HashMap::new(),
-                    };
+                        started_at: None,
+                        finished_at: None,
+                    }];
                 }
             }
         }
@@ -434,6 +797,26 @@ impl PipelineExecutor {
         if let Some(strategy) = &job.strategy {
             let instances = MatrixExpander::expand(strategy);
             if !instances.is_empty() {
+                let instances = self.config.matrix.select(instances);
+                if instances.is_empty() {
+                    self.event_tx.send_event(ExecutionEvent::JobSkipped {
+                        stage_name: stage_name.to_string(),
+                        job_name: job_name.clone(),
+                        reason: "No matrix instances match --matrix".to_string(),
+                    });
+
+                    return vec![JobResult {
+                        job_name,
+                        display_name: display_name.clone(),
+                        status: JobStatus::Skipped,
+                        steps: skipped_step_results(job, runtime),
+                        duration: start.elapsed(),
+                        outputs: HashMap::new(),
+                        started_at: None,
+                        finished_at: None,
+                    }];
+                }
+
                 // Execute matrix instances
                 return self
                     .execute_matrix_job(job_node, stage_name, &instances, runtime)
@@ -442,21 +825,23 @@ impl PipelineExecutor {
         }
 
         // Execute single job instance
-        self.execute_job_instance(job, stage_name, &job_name, None, runtime)
-            .await
+        vec![
+            self.execute_job_instance(job, stage_name, &job_name, None, runtime)
+                .await,
+        ]
     }
 
-    /// Execute a job with matrix expansion
+    /// Execute a job with matrix expansion, one result per instance
+    /// (`Build[linux]`)
     async fn execute_matrix_job(
         &self,
         job_node: &JobNode,
         stage_name: &str,
         instances: &[super::matrix::MatrixInstance],
         runtime: &mut RuntimeContext,
-    ) -> JobResult {
+    ) -> Vec<JobResult> {
         let job = &job_node.job;
         let job_name = job.identifier().unwrap_or("unknown").to_string();
-        let start = Instant::now();
 
         let _max_parallel = job
             .strategy
@@ -465,49 +850,66 @@ impl PipelineExecutor {
             .unwrap_or(instances.len() as u32);
 
         // TODO: Use max_parallel with a semaphore for parallel matrix instance execution
-        let mut all_steps = Vec::new();
-        let mut overall_status = JobStatus::Succeeded;
+        let mut results = Vec::new();
 
-        // Execute each matrix instance
-        for instance in instances {
-            // Apply matrix variables to runtime
+        // Execute each matrix instance on its own copy of the context, so one
+        // instance's variables don't carry over into the next
+        let base = runtime.snapshot();
+        let mut remaining = instances.iter();
+        let mut failed_instance = None;
+        for instance in remaining.by_ref() {
+            let mut fork = base.fork();
             for (var_name, var_value) in &instance.variables {
-                runtime.set_variable(var_name.clone(), var_value.clone());
+                fork.set_variable(var_name.clone(), var_value.clone());
             }
 
-            let instance_result = self
-                .execute_job_instance(job, stage_name, &job_name, Some(&instance.name), runtime)
+            let mut instance_result = self
+                .execute_job_instance(job, stage_name, &job_name, Some(&instance.name), &mut fork)
                 .await;
+            let written = runtime.absorb(&base, fork);
+            runtime.publish(written);
+
+            instance_result.job_name = JobResult::instance_name(&job_name, &instance.name);
+            let failed = matches!(
+                instance_result.status,
+                JobStatus::Failed | JobStatus::Canceled
+            );
+            results.push(instance_result);
+            if failed && !job.continue_on_error.as_bool() {
+                failed_instance = Some(&instance.name);
+                break;
+            }
+        }
 
-            all_steps.extend(instance_result.steps);
+        // Instances after a failed one don't run, but are still reported
+        if let Some(failed_instance) = failed_instance {
+            for instance in remaining {
+                let instance_name = JobResult::instance_name(&job_name, &instance.name);
+                self.event_tx.send_event(ExecutionEvent::JobSkipped {
+                    stage_name: stage_name.to_string(),
+                    job_name: instance_name.clone(),
+                    reason: format!("Matrix instance '{}' failed", failed_instance),
+                });
 
-            if instance_result.status == JobStatus::Failed {
-                overall_status = JobStatus::Failed;
-                if !job.continue_on_error.as_bool() {
-                    break;
+                let mut fork = base.fork();
+                for (var_name, var_value) in &instance.variables {
+                    fork.set_variable(var_name.clone(), var_value.clone());
                 }
-            } else if instance_result.status == JobStatus::SucceededWithIssues
-                && overall_status == JobStatus::Succeeded
-            {
-                overall_status = JobStatus::SucceededWithIssues;
+                results.push(JobResult {
+                    job_name: instance_name,
+                    display_name: fork.resolve_display_name(job.display_name.as_deref()),
+                    status: JobStatus::Skipped,
+                    steps: skipped_step_results(job, &fork),
+                    duration: Duration::ZERO,
+                    outputs: HashMap::new(),
+                    started_at: None,
+                    finished_at: None,
+                });
             }
         }
 
-        JobResult {
-            job_name,
-            display_name: job.display_name.clone(),
-            status: overall_status,
-            steps: all_steps,
-            duration: start.elapsed(),
-            outputs: runtime
-                .step_outputs
-                .iter()
-                .flat_map(|(step_name, m)| {
-                    m.iter()
-                        .map(move |(k, v)| (format!("{}.{}", step_name, k), v.as_string()))
-                })
-                .collect(),
-        }
+        runtime.record_matrix_job(JobResult::combine(&results));
+        results
     }
 
     /// Execute a single job instance
@@ -520,8 +922,11 @@ impl PipelineExecutor {
         runtime: &mut RuntimeContext,
     ) -> JobResult {
         let start = Instant::now();
+        let started_at = SystemTime::now();
 
-        runtime.enter_job(job);
+        let variables_error = runtime.enter_job(job).err();
+        // Job and matrix variables may appear in the display name
+        let display_name = runtime.resolve_display_name(job.display_name.as_deref());
 
         // For deployment jobs, collect steps from strategy hooks
         let deployment_steps = if job.deployment.is_some() {
@@ -539,24 +944,88 @@ impl PipelineExecutor {
         self.event_tx.send_event(ExecutionEvent::job_started(
             stage_name,
             job_name,
-            job.display_name.clone(),
+            display_name.clone(),
             matrix_instance.map(String::from),
             effective_steps.len(),
         ));
+        self.inject_delay(
+            faults::job_delay(&self.config.faults, stage_name, job_name),
+            Some(stage_name),
+            Some(job_name),
+        )
+        .await;
 
         let mut step_results = Vec::new();
         let mut job_status = JobStatus::Succeeded;
         let mut should_run = true;
 
+        if let Some(e) = variables_error {
+            self.event_tx.send_event(ExecutionEvent::error(
+                e,
+                Some(stage_name.to_string()),
+                Some(job_name.to_string()),
+            ));
+            should_run = false;
+            job_status = JobStatus::Failed;
+        }
+
+        // Bring up job-scoped compose services; steps are skipped if they fail to start
+        let compose = match &job.compose {
+            Some(spec) => {
+                let project = self
+                    .start_compose(spec, job_name, Some(stage_name), Some(job_name), runtime)
+                    .await;
+                if project.is_none() {
+                    should_run = false;
+                    job_status = JobStatus::Failed;
+                }
+                project
+            }
+            None => None,
+        };
+
+        // Run the script steps of a `container:` job inside its container
+        let container = match (&self.container_runner, &job.container) {
+            (Some(runner), Some(container)) if should_run => {
+                let working_dir = PathBuf::from(&runtime.base.working_dir);
+                match runner
+                    .start_job_container(
+                        job_name,
+                        container,
+                        &runtime.env_as_strings(),
+                        &working_dir,
+                    )
+                    .await
+                {
+                    Ok(handle) => {
+                        self.event_tx.send_event(ExecutionEvent::info(
+                            format!("Started container {} ({})", handle.name, handle.image),
+                            Some(stage_name.to_string()),
+                            Some(job_name.to_string()),
+                        ));
+                        runtime.job_container = Some(handle.clone());
+                        Some((runner, handle))
+                    }
+                    Err(e) => {
+                        self.event_tx.send_event(ExecutionEvent::error(
+                            format!("Failed to start the job's container: {}", e),
+                            Some(stage_name.to_string()),
+                            Some(job_name.to_string()),
+                        ));
+                        should_run = false;
+                        job_status = JobStatus::Failed;
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
         for (step_index, step) in effective_steps.iter().enumerate() {
-            if !should_run && !should_always_run(step) {
-                // Skip remaining steps if a previous step failed
-                let resolved_display = step.display_name.as_ref().and_then(|dn| {
-                    runtime
-                        .substitute_variables(dn)
-                        .ok()
-                        .or_else(|| Some(dn.clone()))
-                });
+            let handles_cancel = job_status == JobStatus::Canceled && runs_when_canceled(step);
+            if !should_run && !should_always_run(step) && !handles_cancel {
+                // Skip remaining steps if a previous step failed or was canceled
+                let resolved_display = runtime.resolve_display_name(step.display_name.as_deref());
                 let skipped = StepResult {
                     step_name: step.name.clone(),
                     display_name: resolved_display,
@@ -566,6 +1035,12 @@ impl PipelineExecutor {
                     duration: Duration::ZERO,
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 };
                 step_results.push(skipped);
                 continue;
@@ -586,22 +1061,45 @@ impl PipelineExecutor {
                         job_status = JobStatus::SucceededWithIssues;
                     }
                 }
-                StepStatus::SucceededWithIssues => {
-                    if job_status == JobStatus::Succeeded {
-                        job_status = JobStatus::SucceededWithIssues;
+                StepStatus::Canceled => {
+                    should_run = false;
+                    if job_status != JobStatus::Failed {
+                        job_status = JobStatus::Canceled;
                     }
                 }
+                StepStatus::SucceededWithIssues if job_status == JobStatus::Succeeded => {
+                    job_status = JobStatus::SucceededWithIssues;
+                }
                 _ => {}
             }
 
             step_results.push(result);
         }
 
+        // Like Azure DevOps' post-job Cache step, only a job that succeeded saves
+        let saves = std::mem::take(&mut runtime.cache_saves);
+        if matches!(
+            job_status,
+            JobStatus::Succeeded | JobStatus::SucceededWithIssues
+        ) {
+            self.save_caches(saves, stage_name, job_name).await;
+        }
+
+        if let Some((runner, handle)) = container {
+            runtime.job_container = None;
+            runner.stop_job_container(&handle).await;
+        }
+
+        if let Some(project) = compose {
+            self.stop_compose(project, Some(stage_name), Some(job_name))
+                .await;
+        }
+
         let duration = start.elapsed();
 
         let result = JobResult {
             job_name: job_name.to_string(),
-            display_name: job.display_name.clone(),
+            display_name: display_name.clone(),
             status: job_status.clone(),
             steps: step_results,
             duration,
@@ -613,6 +1111,8 @@ impl PipelineExecutor {
                         .map(move |(k, v)| (format!("{}.{}", step_name, k), v.as_string()))
                 })
                 .collect(),
+            started_at: Some(started_at),
+            finished_at: Some(SystemTime::now()),
         };
 
         runtime.exit_job(result.clone());
@@ -628,6 +1128,84 @@ impl PipelineExecutor {
         result
     }
 
+    /// Wait out a delay forced by a fault, saying so
+    async fn inject_delay(
+        &self,
+        delay: Duration,
+        stage_name: Option<&str>,
+        job_name: Option<&str>,
+    ) {
+        if delay.is_zero() {
+            return;
+        }
+        self.event_tx.send_event(ExecutionEvent::warning(
+            format!("Injected delay of {}ms", delay.as_millis()),
+            stage_name.map(String::from),
+            job_name.map(String::from),
+        ));
+        tokio::time::sleep(delay).await;
+    }
+
+    /// Bring up compose services and expose their endpoints as variables
+    ///
+    /// Each `compose.<service>.*` variable is also exported to the environment
+    /// (e.g. `COMPOSE_DB_PORTS_5432`). Returns `None` if the services failed to start.
+    async fn start_compose(
+        &self,
+        spec: &ComposeSpec,
+        scope: &str,
+        stage_name: Option<&str>,
+        job_name: Option<&str>,
+        runtime: &mut RuntimeContext,
+    ) -> Option<ComposeProject> {
+        let working_dir = PathBuf::from(&runtime.base.working_dir);
+        match ComposeRunner::up(spec, &working_dir, scope, &self.config.hosts).await {
+            Ok(project) => {
+                self.event_tx.send_event(ExecutionEvent::info(
+                    format!(
+                        "Started compose project '{}' from {}",
+                        project.name, spec.file
+                    ),
+                    stage_name.map(String::from),
+                    job_name.map(String::from),
+                ));
+                for (name, value) in project.variables() {
+                    runtime.set_env(
+                        name.to_uppercase().replace('.', "_"),
+                        crate::parser::models::Value::String(value.clone()),
+                    );
+                    runtime.set_variable(name, crate::parser::models::Value::String(value));
+                }
+                Some(project)
+            }
+            Err(e) => {
+                self.event_tx.send_event(ExecutionEvent::error(
+                    format!("Failed to start compose services from {}: {}", spec.file, e),
+                    stage_name.map(String::from),
+                    job_name.map(String::from),
+                ));
+                None
+            }
+        }
+    }
+
+    /// Tear down a compose project started by `start_compose`
+    async fn stop_compose(
+        &self,
+        project: ComposeProject,
+        stage_name: Option<&str>,
+        job_name: Option<&str>,
+    ) {
+        let name = project.name.clone();
+        if let Err(e) = ComposeRunner::down(project).await {
+            self.event_tx.send_event(ExecutionEvent::warning(
+                format!("Failed to stop compose project '{}': {}", name, e),
+                stage_name.map(String::from),
+                job_name.map(String::from),
+            ));
+        }
+    }
+
     /// Execute a single step
     async fn execute_step(
         &self,
@@ -641,12 +1219,7 @@ impl PipelineExecutor {
         let step_name = step.name.clone();
 
         // Resolve display name by substituting variables (e.g., "Build for $(targetTriple)")
-        let display_name = step.display_name.as_ref().and_then(|dn| {
-            runtime
-                .substitute_variables(dn)
-                .ok()
-                .or_else(|| Some(dn.clone()))
-        });
+        let display_name = runtime.resolve_display_name(step.display_name.as_deref());
 
         // Check if step is enabled
         if !step.enabled {
@@ -667,6 +1240,12 @@ impl PipelineExecutor {
                 duration: start.elapsed(),
                 exit_code: None,
                 outputs: HashMap::new(),
+                substitutions: Vec::new(),
+                summary: None,
+                unsupported: Vec::new(),
+                started_at: None,
+                finished_at: None,
+                env: Vec::new(),
             };
         }
 
@@ -692,6 +1271,12 @@ impl PipelineExecutor {
                         duration: start.elapsed(),
                         exit_code: None,
                         outputs: HashMap::new(),
+                        substitutions: Vec::new(),
+                        summary: None,
+                        unsupported: Vec::new(),
+                        started_at: None,
+                        finished_at: None,
+                        env: Vec::new(),
                     };
                 }
                 Err(e) => {
@@ -704,11 +1289,19 @@ impl PipelineExecutor {
                         duration: start.elapsed(),
                         exit_code: None,
                         outputs: HashMap::new(),
+                        substitutions: Vec::new(),
+                        summary: None,
+                        unsupported: Vec::new(),
+                        started_at: None,
+                        finished_at: None,
+                        env: Vec::new(),
                     };
                 }
             }
         }
 
+        let started_at = SystemTime::now();
+
         // Send step started event
         self.event_tx.send_event(ExecutionEvent::step_started(
             stage_name,
@@ -718,20 +1311,143 @@ impl PipelineExecutor {
             step_index,
         ));
 
-        // Execute the step based on its action type
-        let mut result = self
-            .execute_step_action(
-                &step.action,
-                step,
-                step_index,
-                stage_name,
-                job_name,
-                runtime,
-            )
-            .await;
+        let names = [step.name.as_deref(), display_name.as_deref()];
+        self.inject_delay(
+            faults::step_delay(&self.config.faults, stage_name, job_name, &names),
+            Some(stage_name),
+            Some(job_name),
+        )
+        .await;
+        let failure = faults::step_failure(
+            &self.config.faults,
+            stage_name,
+            job_name,
+            step_index,
+            &names,
+        );
+        let canceled = faults::step_canceled(
+            &self.config.faults,
+            stage_name,
+            job_name,
+            step_index,
+            &names,
+        );
+
+        // Execute the step based on its action type, unless a fault fails or cancels it
+        let mut result = match failure {
+            _ if canceled => {
+                let message = "Injected cancellation".to_string();
+                self.event_tx.send_event(ExecutionEvent::warning(
+                    message.clone(),
+                    Some(stage_name.to_string()),
+                    Some(job_name.to_string()),
+                ));
+                StepResult {
+                    step_name: step_name.clone(),
+                    display_name: None,
+                    status: StepStatus::Canceled,
+                    output: String::new(),
+                    error: Some(message),
+                    duration: Duration::ZERO,
+                    exit_code: None,
+                    outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
+                }
+            }
+            Some(exit_code) => {
+                let message = format!("Injected failure (exit code {})", exit_code);
+                self.event_tx.send_event(ExecutionEvent::warning(
+                    message.clone(),
+                    Some(stage_name.to_string()),
+                    Some(job_name.to_string()),
+                ));
+                StepResult {
+                    step_name: step_name.clone(),
+                    display_name: None,
+                    status: StepStatus::Failed,
+                    output: String::new(),
+                    error: Some(message),
+                    duration: Duration::ZERO,
+                    exit_code: Some(exit_code),
+                    outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
+                }
+            }
+            None => {
+                let guard = runtime.disk_guard.clone();
+                let action = self.execute_step_action(
+                    &step.action,
+                    step,
+                    step_index,
+                    stage_name,
+                    job_name,
+                    runtime,
+                );
+                // Dropping the action stops the step's command when the disk
+                // fills up first
+                let outcome = match &guard {
+                    Some(guard) => tokio::select! {
+                        result = action => {
+                            let violation = match result.status {
+                                StepStatus::Failed => None,
+                                _ => guard.check().await,
+                            };
+                            violation.map_or(Ok(result), Err)
+                        }
+                        violation = guard.exceeded() => Err(violation),
+                    },
+                    None => Ok(action.await),
+                };
+                outcome.unwrap_or_else(|violation| {
+                    let message = violation.to_string();
+                    self.event_tx
+                        .send_event(ExecutionEvent::disk_quota_exceeded(
+                            stage_name,
+                            job_name,
+                            step_name.clone(),
+                            step_index,
+                            message.clone(),
+                        ));
+                    StepResult {
+                        step_name: step_name.clone(),
+                        display_name: None,
+                        status: StepStatus::Failed,
+                        output: String::new(),
+                        error: Some(format!("Disk quota exceeded: {}", message)),
+                        duration: started_at.elapsed().unwrap_or_default(),
+                        exit_code: None,
+                        outputs: HashMap::new(),
+                        substitutions: Vec::new(),
+                        summary: None,
+                        unsupported: Vec::new(),
+                        started_at: None,
+                        finished_at: None,
+                        env: Vec::new(),
+                    }
+                })
+            }
+        };
 
         // Override display_name with variable-substituted version
         result.display_name = display_name;
+        result.started_at = Some(started_at);
+        result.finished_at = Some(SystemTime::now());
+
+        if self.config.fail_on_unsupported && !result.unsupported.is_empty() {
+            let features: Vec<String> = result.unsupported.iter().map(|f| f.to_string()).collect();
+            result.status = StepStatus::Failed;
+            result.error = Some(format!("Not supported by roxid: {}", features.join(", ")));
+        }
 
         // Send step completed event
         self.event_tx.send_event(ExecutionEvent::step_completed(
@@ -760,17 +1476,79 @@ impl PipelineExecutor {
         let start = Instant::now();
         let step_name = step.name.clone();
 
-        match action {
-            StepAction::Script(script_step) => {
-                self.execute_script(
-                    &script_step.script,
-                    script_step.working_directory.as_deref(),
-                    script_step.fail_on_stderr,
+        // Scripts ask once their command line is built; nothing runs for a
+        // replayed step or an unexpanded template
+        let preview = match action {
+            StepAction::Task(task_step) => {
+                let mut env = runtime.env_as_strings();
+                env.extend(self.config.service_connections.task_env(&task_step.inputs));
+                let mut inputs: Vec<String> = task_step
+                    .inputs
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect();
+                inputs.sort();
+                Some((vec![task_step.task.clone(), inputs.join("\n")], env))
+            }
+            StepAction::Checkout(checkout_step) => Some((
+                vec![
+                    "checkout".to_string(),
+                    match &checkout_step.checkout {
+                        CheckoutSource::SelfRepo(_) => "self".to_string(),
+                        CheckoutSource::None(_) => "none".to_string(),
+                        CheckoutSource::Repository(name) => name.clone(),
+                    },
+                ],
+                runtime.env_as_strings(),
+            )),
+            StepAction::Download(_) => Some((
+                vec!["download".to_string(), String::new()],
+                runtime.env_as_strings(),
+            )),
+            StepAction::Publish(publish) => Some((
+                vec!["publish".to_string(), publish.publish.clone()],
+                runtime.env_as_strings(),
+            )),
+            StepAction::GetPackage(package) => Some((
+                vec!["getPackage".to_string(), package.get_package.clone()],
+                runtime.env_as_strings(),
+            )),
+            StepAction::ReviewApp(review) => Some((
+                vec!["reviewApp".to_string(), review.review_app.clone()],
+                runtime.env_as_strings(),
+            )),
+            _ => None,
+        };
+        if let (Some((command, env)), None) = (preview, self.replaying()) {
+            let working_dir = runtime.base.working_dir.clone();
+            if let Some(declined) = self
+                .declined(
                     step,
                     step_index,
                     stage_name,
                     job_name,
-                    runtime,
+                    command,
+                    &working_dir,
+                    &env,
+                )
+                .await
+            {
+                return declined;
+            }
+        }
+
+        match action {
+            StepAction::Script(script_step) => {
+                self.execute_script(
+                    &script_step.script,
+                    script_step.working_directory.as_deref(),
+                    script_step.fail_on_stderr,
+                    script_step.shell.as_deref(),
+                    step,
+                    step_index,
+                    stage_name,
+                    job_name,
+                    runtime,
                 )
                 .await
             }
@@ -814,10 +1592,34 @@ impl PipelineExecutor {
                 .await
             }
             StepAction::Task(task_step) => {
+                if let Some(recorder) = self.replaying() {
+                    let identity = recorder.step_identity(stage_name, job_name, step, step_index);
+                    return match recorder.next(&identity) {
+                        Some(recording) => {
+                            self.replay_task(recording, step, step_index, stage_name, job_name)
+                        }
+                        None => missing_recording(step, &identity),
+                    };
+                }
+
+                if let Some(result) = self
+                    .download_key_vault(task_step, step, step_index, stage_name, job_name, runtime)
+                    .await
+                {
+                    return result;
+                }
+                if let Some(result) = self
+                    .restore_cache(task_step, step, step_index, stage_name, job_name, runtime)
+                    .await
+                {
+                    return result;
+                }
+
                 // Execute task using TaskRunner
                 if let Some(task_runner) = &self.task_runner {
                     let working_dir = std::path::PathBuf::from(&runtime.base.working_dir);
-                    let env = runtime.env_as_strings();
+                    let mut env = runtime.env_as_strings();
+                    env.extend(self.config.service_connections.task_env(&task_step.inputs));
 
                     match task_runner
                         .execute_task(&task_step.task, &task_step.inputs, &env, &working_dir)
@@ -826,6 +1628,21 @@ impl PipelineExecutor {
                         Ok(mut result) => {
                             result.step_name = step_name;
                             result.display_name = step.display_name.clone();
+                            result.env = self.step_env(&env, step, stage_name, job_name);
+                            let secrets = self.secrets();
+                            result.output = redact(&result.output, &secrets);
+                            result.error = result.error.map(|error| redact(&error, &secrets));
+
+                            if let Some(recorder) = &self.config.recorder {
+                                recorder.store(
+                                    recorder.step_identity(stage_name, job_name, step, step_index),
+                                    CommandRecording {
+                                        stdout: result.output.clone(),
+                                        stderr: result.error.clone().unwrap_or_default(),
+                                        exit_code: result.exit_code,
+                                    },
+                                );
+                            }
 
                             // Send output event
                             if !result.output.is_empty() {
@@ -834,7 +1651,7 @@ impl PipelineExecutor {
                                     job_name,
                                     result.step_name.clone(),
                                     step_index,
-                                    &result.output,
+                                    result.output.as_str(),
                                     false,
                                 ));
                             }
@@ -850,6 +1667,20 @@ impl PipelineExecutor {
                             duration: start.elapsed(),
                             exit_code: None,
                             outputs: HashMap::new(),
+                            substitutions: Vec::new(),
+                            summary: None,
+                            unsupported: match e {
+                                TaskRunnerError::UnsupportedExecution(_) => {
+                                    vec![UnsupportedFeature::new(
+                                        UnsupportedKind::Task,
+                                        &task_step.task,
+                                    )]
+                                }
+                                _ => Vec::new(),
+                            },
+                            started_at: None,
+                            finished_at: None,
+                            env: Vec::new(),
                         },
                     }
                 } else {
@@ -875,20 +1706,96 @@ impl PipelineExecutor {
                         duration: start.elapsed(),
                         exit_code: None,
                         outputs: HashMap::new(),
+                        substitutions: Vec::new(),
+                        summary: None,
+                        unsupported: vec![UnsupportedFeature::new(
+                            UnsupportedKind::Task,
+                            &task_step.task,
+                        )],
+                        started_at: None,
+                        finished_at: None,
+                        env: Vec::new(),
                     }
                 }
             }
-            StepAction::Checkout(_) => {
-                // Checkout - for now, assume already checked out
-                StepResult {
-                    step_name,
-                    display_name: step.display_name.clone(),
-                    status: StepStatus::Succeeded,
-                    output: "Checkout: Using existing working directory".to_string(),
-                    error: None,
-                    duration: start.elapsed(),
-                    exit_code: Some(0),
-                    outputs: HashMap::new(),
+            StepAction::Checkout(checkout_step) => {
+                let working_dir = PathBuf::from(&runtime.base.working_dir);
+                let result = match CheckoutRunner::resolve(
+                    checkout_step,
+                    &self.graph.repositories,
+                    &self.config.repositories,
+                    &working_dir,
+                ) {
+                    Ok(Some(target)) => CheckoutRunner::checkout(&target, checkout_step)
+                        .await
+                        .map(|output| (output, Some(target))),
+                    Ok(None) => Ok(("Checkout: none".to_string(), None)),
+                    Err(e) => Err(e),
+                };
+
+                match result {
+                    Ok((output, target)) => {
+                        // Later steps see the most recently checked out repository
+                        if let Some(target) = target {
+                            let local_path = target.path.to_string_lossy().to_string();
+                            for (name, value) in [
+                                ("Build.Repository.LocalPath", local_path),
+                                ("Build.Repository.Name", target.name),
+                            ] {
+                                runtime.set_env(
+                                    name.to_uppercase().replace('.', "_"),
+                                    crate::parser::models::Value::String(value.clone()),
+                                );
+                                runtime.set_variable(
+                                    name.to_string(),
+                                    crate::parser::models::Value::String(value),
+                                );
+                            }
+                        }
+                        for line in output.lines() {
+                            self.event_tx.send_event(ExecutionEvent::step_output(
+                                stage_name,
+                                job_name,
+                                step_name.clone(),
+                                step_index,
+                                line.to_string(),
+                                false,
+                            ));
+                        }
+
+                        StepResult {
+                            step_name,
+                            display_name: step.display_name.clone(),
+                            status: StepStatus::Succeeded,
+                            output,
+                            error: None,
+                            duration: start.elapsed(),
+                            exit_code: Some(0),
+                            outputs: HashMap::new(),
+                            substitutions: Vec::new(),
+                            summary: None,
+                            unsupported: Vec::new(),
+                            started_at: None,
+                            finished_at: None,
+                            env: Vec::new(),
+                        }
+                    }
+                    Err(e) => StepResult {
+                        step_name,
+                        display_name: step.display_name.clone(),
+                        status: StepStatus::Failed,
+                        output: String::new(),
+                        error: Some(format!("Checkout failed: {}", e)),
+                        duration: start.elapsed(),
+                        exit_code: None,
+                        outputs: HashMap::new(),
+                        substitutions: Vec::new(),
+                        summary: None,
+                        unsupported: Vec::new(),
+                        started_at: None,
+                        finished_at: None,
+                        env: Vec::new(),
+                    },
                 }
             }
             StepAction::Template(_) => {
@@ -902,10 +1809,23 @@ impl PipelineExecutor {
                     duration: start.elapsed(),
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: vec![UnsupportedFeature::new(
+                        UnsupportedKind::StepType,
+                        "template",
+                    )],
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 }
             }
             StepAction::Download(_) | StepAction::Publish(_) => {
                 // Download/Publish - placeholder for now
+                let keyword = match action {
+                    StepAction::Download(_) => "download",
+                    _ => "publish",
+                };
                 StepResult {
                     step_name,
                     display_name: step.display_name.clone(),
@@ -915,10 +1835,20 @@ impl PipelineExecutor {
                     duration: start.elapsed(),
                     exit_code: Some(0),
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: vec![UnsupportedFeature::new(UnsupportedKind::StepType, keyword)],
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 }
             }
             StepAction::GetPackage(_) | StepAction::ReviewApp(_) => {
                 // Other steps - placeholder
+                let keyword = match action {
+                    StepAction::GetPackage(_) => "getPackage",
+                    _ => "reviewApp",
+                };
                 StepResult {
                     step_name,
                     display_name: step.display_name.clone(),
@@ -928,9 +1858,465 @@ impl PipelineExecutor {
                     duration: start.elapsed(),
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: vec![UnsupportedFeature::new(UnsupportedKind::StepType, keyword)],
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
+                }
+            }
+        }
+    }
+
+    /// Values masked in step output: service connection auth and configured secrets
+    fn secrets(&self) -> Vec<String> {
+        let mut secrets = self.config.service_connections.secrets();
+        secrets.extend(self.config.secrets.iter().cloned());
+        secrets
+    }
+
+    /// Every secret to mask, including ones masked through the progress channel
+    fn masked_secrets(&self) -> Vec<String> {
+        let mut secrets = self.secrets();
+        if let Some(tx) = &self.event_tx {
+            secrets.extend(tx.secrets());
+        }
+        secrets
+    }
+
+    /// The environment a step's command gets, sorted, with secrets masked;
+    /// logged as it starts with `audit_env`
+    fn step_env(
+        &self,
+        env: &HashMap<String, String>,
+        step: &Step,
+        stage_name: &str,
+        job_name: &str,
+    ) -> Vec<(String, String)> {
+        let secrets = self.masked_secrets();
+        let mut step_env: Vec<(String, String)> = env
+            .iter()
+            .map(|(name, value)| (name.clone(), redact(value, &secrets)))
+            .collect();
+        step_env.sort();
+
+        if self.config.audit_env {
+            let step_label = step
+                .name
+                .as_deref()
+                .or(step.display_name.as_deref())
+                .unwrap_or("step");
+            for (name, value) in &step_env {
+                self.event_tx.send_event(ExecutionEvent::info(
+                    format!("{}: env {}={}", step_label, name, value),
+                    Some(stage_name.to_string()),
+                    Some(job_name.to_string()),
+                ));
+            }
+        }
+        step_env
+    }
+
+    /// Run an `AzureKeyVault` step against its local stand-in, if `.roxid.toml`
+    /// defines one for the vault; the secrets become variables of the job
+    async fn download_key_vault(
+        &self,
+        task_step: &TaskStep,
+        step: &Step,
+        step_index: usize,
+        stage_name: &str,
+        job_name: &str,
+        runtime: &mut RuntimeContext,
+    ) -> Option<StepResult> {
+        let task_name = task_step.task.split('@').next().unwrap_or_default();
+        if !task_name.eq_ignore_ascii_case("AzureKeyVault") {
+            return None;
+        }
+        let vault = task_step.inputs.get("KeyVaultName")?;
+        let vault = runtime
+            .substitute_variables(vault)
+            .unwrap_or_else(|_| vault.clone());
+        let (_, provider) = self
+            .config
+            .key_vaults
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(vault.trim()))?;
+
+        let start = Instant::now();
+        let working_dir = PathBuf::from(&runtime.base.working_dir);
+        let loader = provider.clone();
+        let loaded = tokio::task::spawn_blocking(move || loader.load(&working_dir))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()))
+            .and_then(|values| {
+                filter_secrets(
+                    &values,
+                    task_step.inputs.get("SecretsFilter").map(String::as_str),
+                )
+            });
+
+        let (status, output, error) = match loaded {
+            Ok(values) => {
+                if provider.secret {
+                    if let Some(tx) = &self.event_tx {
+                        tx.mask_secrets(values.values().cloned());
+                    }
+                }
+                let output = format!(
+                    "Downloaded {} secret(s) from key vault '{}' (local): {}",
+                    values.len(),
+                    vault,
+                    values.keys().cloned().collect::<Vec<_>>().join(", ")
+                );
+                for (name, value) in values {
+                    runtime.set_variable(name, crate::parser::models::Value::String(value));
+                }
+                self.event_tx.send_event(ExecutionEvent::step_output(
+                    stage_name,
+                    job_name,
+                    step.name.clone(),
+                    step_index,
+                    output.as_str(),
+                    false,
+                ));
+                (StepStatus::Succeeded, output, None)
+            }
+            Err(e) => (
+                StepStatus::Failed,
+                String::new(),
+                Some(format!("Key vault '{}': {}", vault, e)),
+            ),
+        };
+
+        Some(StepResult {
+            step_name: step.name.clone(),
+            display_name: step.display_name.clone(),
+            status,
+            output,
+            error,
+            duration: start.elapsed(),
+            exit_code: None,
+            outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
+        })
+    }
+
+    /// Run a `Cache@2` step: restore the entry for its key (or the newest
+    /// matching a restore key) into its path, and note the path to save when
+    /// the job ends if the key itself wasn't found
+    async fn restore_cache(
+        &self,
+        task_step: &TaskStep,
+        step: &Step,
+        step_index: usize,
+        stage_name: &str,
+        job_name: &str,
+        runtime: &mut RuntimeContext,
+    ) -> Option<StepResult> {
+        if !pipeline_cache::is_cache_task(&task_step.task) {
+            return None;
+        }
+        let input = |name: &str| {
+            task_step.inputs.get(name).map(|value| {
+                runtime
+                    .substitute_variables(value)
+                    .unwrap_or_else(|_| value.clone())
+            })
+        };
+        let key = input("key").unwrap_or_default();
+        let path = input("path").unwrap_or_default();
+        let restore_keys = input("restoreKeys").unwrap_or_default();
+        let hit_variable = input("cacheHitVar");
+
+        let start = Instant::now();
+        let working_dir = PathBuf::from(&runtime.base.working_dir);
+        let storage = self.cache_storage();
+        let outcome = async {
+            if key.trim().is_empty() || path.trim().is_empty() {
+                return Err("Cache needs the key and path inputs".to_string());
+            }
+            let name = pipeline_cache::fingerprint(&key, &working_dir)?;
+            let prefixes = restore_keys
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| pipeline_cache::fingerprint(line, &working_dir).map(|name| name + "-"))
+                .collect::<Result<Vec<_>, _>>()?;
+            let path = working_dir.join(path.trim());
+            let hit = pipeline_cache::restore(storage.as_ref(), &name, &prefixes, &path).await;
+            Ok((name, path, hit))
+        }
+        .await;
+
+        let (status, output, error) = match outcome {
+            Ok((name, path, hit)) => {
+                let (status, output, hit) = match hit {
+                    Ok(CacheHit::Exact) => (
+                        StepStatus::Succeeded,
+                        format!("Cache hit for '{}', restored to {}", key, path.display()),
+                        CacheHit::Exact,
+                    ),
+                    Ok(CacheHit::Inexact(found)) => (
+                        StepStatus::Succeeded,
+                        format!(
+                            "Cache restored from entry {} matching a restore key, to {}",
+                            found,
+                            path.display()
+                        ),
+                        CacheHit::Inexact(found),
+                    ),
+                    Ok(CacheHit::Miss) => (
+                        StepStatus::Succeeded,
+                        format!("Cache miss for '{}'", key),
+                        CacheHit::Miss,
+                    ),
+                    // An unreachable cache slows the job down but doesn't fail it
+                    Err(e) => (
+                        StepStatus::SucceededWithIssues,
+                        format!("Cache unavailable ({}), continuing without it", e),
+                        CacheHit::Miss,
+                    ),
+                };
+                if let Some(variable) = &hit_variable {
+                    runtime.set_variable(
+                        variable.clone(),
+                        crate::parser::models::Value::String(hit.variable_value().to_string()),
+                    );
                 }
+                if hit != CacheHit::Exact {
+                    runtime.cache_saves.push(CacheSave {
+                        key: key.clone(),
+                        name,
+                        path,
+                    });
+                }
+                self.event_tx.send_event(ExecutionEvent::step_output(
+                    stage_name,
+                    job_name,
+                    step.name.clone(),
+                    step_index,
+                    format!("{} ({})", output, storage.describe()),
+                    false,
+                ));
+                (status, output, None)
+            }
+            Err(e) => (
+                StepStatus::Failed,
+                String::new(),
+                Some(format!("Cache: {}", e)),
+            ),
+        };
+
+        Some(StepResult {
+            step_name: step.name.clone(),
+            display_name: step.display_name.clone(),
+            status,
+            output,
+            error,
+            duration: start.elapsed(),
+            exit_code: None,
+            outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
+        })
+    }
+
+    /// Save the caches a job's Cache@2 steps didn't find
+    async fn save_caches(&self, saves: Vec<CacheSave>, stage_name: &str, job_name: &str) {
+        if saves.is_empty() {
+            return;
+        }
+        let storage = self.cache_storage();
+        for save in saves {
+            let event = match pipeline_cache::save(storage.as_ref(), &save).await {
+                Ok(true) => ExecutionEvent::info(
+                    format!("Saved cache for '{}' to {}", save.key, storage.describe()),
+                    Some(stage_name.to_string()),
+                    Some(job_name.to_string()),
+                ),
+                Ok(false) => ExecutionEvent::info(
+                    format!("Cache for '{}' was already saved", save.key),
+                    Some(stage_name.to_string()),
+                    Some(job_name.to_string()),
+                ),
+                Err(e) => ExecutionEvent::warning(
+                    format!("Cache for '{}' not saved: {}", save.key, e),
+                    Some(stage_name.to_string()),
+                    Some(job_name.to_string()),
+                ),
+            };
+            self.event_tx.send_event(event);
+        }
+    }
+
+    fn cache_storage(&self) -> Arc<dyn CacheStorage> {
+        self.config
+            .cache_storage
+            .clone()
+            .unwrap_or_else(|| Arc::new(LocalCacheStorage::default()))
+    }
+
+    /// Ask the confirmer, if any, whether a step may run; the result to
+    /// report when it's declined
+    ///
+    /// Secrets are masked in the preview. The confirmer runs on a blocking
+    /// thread, since it may wait for someone to answer a prompt.
+    #[allow(clippy::too_many_arguments)]
+    async fn declined(
+        &self,
+        step: &Step,
+        step_index: usize,
+        stage_name: &str,
+        job_name: &str,
+        command: Vec<String>,
+        working_dir: &str,
+        env: &HashMap<String, String>,
+    ) -> Option<StepResult> {
+        let confirmer = self.confirmer.clone()?;
+        let start = Instant::now();
+        let mut secrets = self.secrets();
+        if let Some(tx) = &self.event_tx {
+            secrets.extend(tx.secrets());
+        }
+        let preview = StepPreview {
+            stage_name: stage_name.to_string(),
+            job_name: job_name.to_string(),
+            step: step
+                .display_name
+                .clone()
+                .or_else(|| step.name.clone())
+                .unwrap_or_else(|| format!("step {}", step_index + 1)),
+            command: command.iter().map(|arg| redact(arg, &secrets)).collect(),
+            working_dir: working_dir.to_string(),
+            env_changes: StepPreview::env_changes(env)
+                .into_iter()
+                .map(|(name, value)| (name, redact(&value, &secrets)))
+                .collect(),
+        };
+        let confirmed = tokio::task::spawn_blocking(move || confirmer.confirm(&preview))
+            .await
+            .unwrap_or(false);
+        if confirmed {
+            return None;
+        }
+        Some(StepResult {
+            step_name: step.name.clone(),
+            display_name: step.display_name.clone(),
+            status: StepStatus::Skipped,
+            output: "Declined at the confirmation prompt".to_string(),
+            error: None,
+            duration: start.elapsed(),
+            exit_code: None,
+            outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
+        })
+    }
+
+    /// Recorder to replay step output from, when replaying
+    fn replaying(&self) -> Option<&StepRecorder> {
+        self.config
+            .recorder
+            .as_ref()
+            .filter(|recorder| recorder.mode() == RecordingMode::Replay)
+    }
+
+    /// Result of a task step from its recorded output, without running the task
+    fn replay_task(
+        &self,
+        recording: CommandRecording,
+        step: &Step,
+        step_index: usize,
+        stage_name: &str,
+        job_name: &str,
+    ) -> StepResult {
+        for (text, is_error) in [(&recording.stdout, false), (&recording.stderr, true)] {
+            if !text.is_empty() {
+                self.event_tx.send_event(ExecutionEvent::step_output(
+                    stage_name,
+                    job_name,
+                    step.name.clone(),
+                    step_index,
+                    text.as_str(),
+                    is_error,
+                ));
             }
         }
+
+        StepResult {
+            step_name: step.name.clone(),
+            display_name: step.display_name.clone(),
+            status: if recording.succeeded() {
+                StepStatus::Succeeded
+            } else {
+                StepStatus::Failed
+            },
+            output: recording.stdout,
+            error: (!recording.stderr.is_empty()).then_some(recording.stderr),
+            duration: Duration::ZERO,
+            exit_code: recording.exit_code,
+            outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
+        }
+    }
+
+    /// Result of a dry-run step: its substituted command as output, without
+    /// running it. `None` when the step should run for real.
+    fn dry_run(
+        &self,
+        script: &str,
+        step: &Step,
+        step_index: usize,
+        stage_name: &str,
+        job_name: &str,
+    ) -> Option<StepResult> {
+        if !step.dry_run && !self.config.dry_run {
+            return None;
+        }
+
+        self.event_tx.send_event(ExecutionEvent::step_output(
+            stage_name,
+            job_name,
+            step.name.clone(),
+            step_index,
+            script,
+            false,
+        ));
+
+        Some(StepResult {
+            step_name: step.name.clone(),
+            display_name: step.display_name.clone(),
+            status: StepStatus::Succeeded,
+            output: script.to_string(),
+            error: None,
+            duration: Duration::ZERO,
+            exit_code: None,
+            outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
+        })
     }
 
     /// Execute a script step
@@ -940,6 +2326,7 @@ impl PipelineExecutor {
         script: &str,
         working_directory: Option<&str>,
         fail_on_stderr: bool,
+        shell: Option<&str>,
         step: &Step,
         step_index: usize,
         stage_name: &str,
@@ -947,7 +2334,7 @@ impl PipelineExecutor {
         runtime: &mut RuntimeContext,
     ) -> StepResult {
         // Substitute variables in script
-        let script = match runtime.substitute_variables(script) {
+        let (script, substitutions) = match runtime.substitute_variables_audited(script) {
             Ok(s) => s,
             Err(e) => {
                 return StepResult {
@@ -959,23 +2346,75 @@ impl PipelineExecutor {
                     duration: Duration::ZERO,
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 };
             }
         };
 
-        self.run_shell_command(
-            &script,
-            "sh",
-            &["-c"],
-            working_directory,
-            fail_on_stderr,
-            step,
-            step_index,
-            stage_name,
-            job_name,
-            runtime,
-        )
-        .await
+        self.report_substitutions(&substitutions, step, stage_name, job_name);
+
+        if let Some(mut result) = self.dry_run(&script, step, step_index, stage_name, job_name) {
+            result.substitutions = substitutions;
+            return result;
+        }
+
+        // Resolve the shell: `sh -c` unless the step selects one with `shell:`
+        let command = match shell {
+            Some(name) => ShellSpec::resolve(name, &self.config.shells),
+            None => Ok(ShellSpec::default()),
+        }
+        .and_then(|spec| {
+            spec.command_args(&script)
+                .map(|(args, file)| (spec, args, file))
+                .map_err(|e| format!("Failed to write script file: {}", e))
+        });
+        let (shell, args, _script_file) = match command {
+            Ok(command) => command,
+            Err(e) => {
+                return StepResult {
+                    step_name: step.name.clone(),
+                    display_name: step.display_name.clone(),
+                    status: StepStatus::Failed,
+                    output: String::new(),
+                    error: Some(e),
+                    duration: Duration::ZERO,
+                    exit_code: None,
+                    outputs: HashMap::new(),
+                    substitutions,
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
+                };
+            }
+        };
+
+        // The final argument is the script (or its file); the rest are shell flags
+        let (script_arg, shell_args) = args.split_last().unwrap_or((&script, &[]));
+        let shell_args: Vec<&str> = shell_args.iter().map(String::as_str).collect();
+
+        let mut result = self
+            .run_shell_command(
+                script_arg,
+                &shell.program,
+                &shell_args,
+                working_directory,
+                fail_on_stderr,
+                step,
+                step_index,
+                stage_name,
+                job_name,
+                runtime,
+            )
+            .await;
+        result.substitutions = substitutions;
+        result
     }
 
     /// Execute a bash step
@@ -991,7 +2430,7 @@ impl PipelineExecutor {
         job_name: &str,
         runtime: &mut RuntimeContext,
     ) -> StepResult {
-        let script = match runtime.substitute_variables(script) {
+        let (script, substitutions) = match runtime.substitute_variables_audited(script) {
             Ok(s) => s,
             Err(e) => {
                 return StepResult {
@@ -1003,23 +2442,34 @@ impl PipelineExecutor {
                     duration: Duration::ZERO,
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 };
             }
         };
 
-        self.run_shell_command(
-            &script,
-            "bash",
-            &["-c"],
-            working_directory,
-            fail_on_stderr,
-            step,
-            step_index,
-            stage_name,
-            job_name,
-            runtime,
-        )
-        .await
+        self.report_substitutions(&substitutions, step, stage_name, job_name);
+
+        let mut result = self
+            .run_shell_command(
+                &script,
+                "bash",
+                &["-c"],
+                working_directory,
+                fail_on_stderr,
+                step,
+                step_index,
+                stage_name,
+                job_name,
+                runtime,
+            )
+            .await;
+        result.substitutions = substitutions;
+        result
     }
 
     /// Execute a pwsh (PowerShell Core) step
@@ -1035,7 +2485,7 @@ impl PipelineExecutor {
         job_name: &str,
         runtime: &mut RuntimeContext,
     ) -> StepResult {
-        let script = match runtime.substitute_variables(script) {
+        let (script, substitutions) = match runtime.substitute_variables_audited(script) {
             Ok(s) => s,
             Err(e) => {
                 return StepResult {
@@ -1047,23 +2497,34 @@ impl PipelineExecutor {
                     duration: Duration::ZERO,
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 };
             }
         };
 
-        self.run_shell_command(
-            &script,
-            "pwsh",
-            &["-Command"],
-            working_directory,
-            fail_on_stderr,
-            step,
-            step_index,
-            stage_name,
-            job_name,
-            runtime,
-        )
-        .await
+        self.report_substitutions(&substitutions, step, stage_name, job_name);
+
+        let mut result = self
+            .run_shell_command(
+                &script,
+                "pwsh",
+                &["-Command"],
+                working_directory,
+                fail_on_stderr,
+                step,
+                step_index,
+                stage_name,
+                job_name,
+                runtime,
+            )
+            .await;
+        result.substitutions = substitutions;
+        result
     }
 
     /// Execute a PowerShell (Windows) step
@@ -1079,7 +2540,7 @@ impl PipelineExecutor {
         job_name: &str,
         runtime: &mut RuntimeContext,
     ) -> StepResult {
-        let script = match runtime.substitute_variables(script) {
+        let (script, substitutions) = match runtime.substitute_variables_audited(script) {
             Ok(s) => s,
             Err(e) => {
                 return StepResult {
@@ -1091,6 +2552,12 @@ impl PipelineExecutor {
                     duration: Duration::ZERO,
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 };
             }
         };
@@ -1102,19 +2569,75 @@ impl PipelineExecutor {
             ("pwsh", &["-Command"])
         };
 
-        self.run_shell_command(
-            &script,
-            shell,
-            args,
-            working_directory,
-            fail_on_stderr,
-            step,
-            step_index,
-            stage_name,
-            job_name,
-            runtime,
-        )
-        .await
+        self.report_substitutions(&substitutions, step, stage_name, job_name);
+
+        let mut result = self
+            .run_shell_command(
+                &script,
+                shell,
+                args,
+                working_directory,
+                fail_on_stderr,
+                step,
+                step_index,
+                stage_name,
+                job_name,
+                runtime,
+            )
+            .await;
+        result.substitutions = substitutions;
+        result
+    }
+
+    /// With `audit_substitutions`, log each substituted variable, secrets
+    /// masked, and warn about values with shell metacharacters that can
+    /// change the command structure
+    fn report_substitutions(
+        &self,
+        substitutions: &[VariableSubstitution],
+        step: &Step,
+        stage_name: &str,
+        job_name: &str,
+    ) {
+        let step_label = step
+            .name
+            .as_deref()
+            .or(step.display_name.as_deref())
+            .unwrap_or("step");
+
+        if !self.config.audit_substitutions {
+            return;
+        }
+        let secrets = self.masked_secrets();
+
+        for sub in substitutions {
+            self.event_tx.send_event(ExecutionEvent::info(
+                format!(
+                    "{}: substituted '{}' = '{}'",
+                    step_label,
+                    sub.name,
+                    redact(&sub.value, &secrets)
+                ),
+                Some(stage_name.to_string()),
+                Some(job_name.to_string()),
+            ));
+
+            let metacharacters = sub.shell_metacharacters();
+            if !metacharacters.is_empty() {
+                let chars: Vec<String> =
+                    metacharacters.iter().map(|c| format!("{:?}", c)).collect();
+                self.event_tx.send_event(ExecutionEvent::warning(
+                    format!(
+                        "{}: value of '{}' contains shell metacharacters [{}] that may change the command structure",
+                        step_label,
+                        sub.name,
+                        chars.join(", ")
+                    ),
+                    Some(stage_name.to_string()),
+                    Some(job_name.to_string()),
+                ));
+            }
+        }
     }
 
     /// Run a shell command
@@ -1133,6 +2656,11 @@ impl PipelineExecutor {
         runtime: &mut RuntimeContext,
     ) -> StepResult {
         use tokio::process::Command;
+
+        if let Some(result) = self.dry_run(script, step, step_index, stage_name, job_name) {
+            return result;
+        }
+
         let start = Instant::now();
 
         let working_dir = working_directory
@@ -1149,9 +2677,37 @@ impl PipelineExecutor {
             env.insert(k.clone(), value);
         }
 
-        let mut cmd = Command::new(shell);
-        cmd.args(shell_args);
-        cmd.arg(script);
+        // Give the step a file to write its markdown summary to
+        let summary_file = SummaryFile::create().ok();
+        if let Some(file) = &summary_file {
+            env.insert(
+                STEP_SUMMARY_ENV.to_string(),
+                file.path().to_string_lossy().to_string(),
+            );
+        }
+
+        let step_env = self.step_env(&env, step, stage_name, job_name);
+
+        let command: Vec<String> = std::iter::once(shell)
+            .chain(shell_args.iter().copied())
+            .chain([script])
+            .map(str::to_string)
+            .collect();
+        let spawned = match (&runtime.job_container, &self.sandbox) {
+            (Some(container), _) => {
+                ContainerRunner::exec_command(container, &command, Path::new(&working_dir), &env)
+            }
+            (None, Some(Ok(sandbox))) => sandbox.wrap(
+                &command,
+                Path::new(&runtime.base.working_dir),
+                Path::new(&working_dir),
+                &env,
+            ),
+            _ => command.clone(),
+        };
+
+        let mut cmd = Command::new(&spawned[0]);
+        cmd.args(&spawned[1..]);
         cmd.current_dir(&working_dir);
         cmd.envs(&env);
 
@@ -1159,24 +2715,71 @@ impl PipelineExecutor {
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
 
-        let output = match cmd.output().await {
-            Ok(output) => output,
-            Err(e) => {
-                return StepResult {
-                    step_name: step.name.clone(),
-                    display_name: step.display_name.clone(),
-                    status: StepStatus::Failed,
-                    output: String::new(),
-                    error: Some(format!("Failed to execute command: {}", e)),
-                    duration: start.elapsed(),
-                    exit_code: None,
-                    outputs: HashMap::new(),
+        let (recording, record_to) = match self.config.recorder.as_ref().map(|recorder| {
+            (
+                recorder,
+                recorder.step_identity(stage_name, job_name, step, step_index),
+            )
+        }) {
+            Some((recorder, identity)) if recorder.mode() == RecordingMode::Replay => {
+                match recorder.next(&identity) {
+                    Some(recording) => (recording, None),
+                    None => return missing_recording(step, &identity),
+                }
+            }
+            recorder => {
+                if let Some(declined) = self
+                    .declined(
+                        step,
+                        step_index,
+                        stage_name,
+                        job_name,
+                        command,
+                        &working_dir,
+                        &env,
+                    )
+                    .await
+                {
+                    return declined;
+                }
+
+                let output = match StepProcess::spawn(&mut cmd) {
+                    Ok(process) => process.output().await,
+                    Err(e) => Err(e),
+                };
+                let output = match output {
+                    Ok(output) => output,
+                    Err(e) => {
+                        return StepResult {
+                            step_name: step.name.clone(),
+                            display_name: step.display_name.clone(),
+                            status: StepStatus::Failed,
+                            output: String::new(),
+                            error: Some(format!("Failed to execute command: {}", e)),
+                            duration: start.elapsed(),
+                            exit_code: None,
+                            outputs: HashMap::new(),
+                            substitutions: Vec::new(),
+                            summary: None,
+                            unsupported: Vec::new(),
+                            started_at: None,
+                            finished_at: None,
+                            env: Vec::new(),
+                        };
+                    }
                 };
+                let recording = CommandRecording {
+                    stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                    exit_code: output.status.code(),
+                };
+                (recording, recorder)
             }
         };
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let exit_code = recording.exit_code;
+        let succeeded = recording.succeeded();
+        let CommandRecording { stdout, stderr, .. } = recording;
 
         // Send output events
         if !stdout.is_empty() {
@@ -1185,7 +2788,7 @@ impl PipelineExecutor {
                 job_name,
                 step.name.clone(),
                 step_index,
-                &stdout,
+                stdout.as_str(),
                 false,
             ));
         }
@@ -1196,17 +2799,46 @@ impl PipelineExecutor {
                 job_name,
                 step.name.clone(),
                 step_index,
-                &stderr,
+                stderr.as_str(),
                 true,
             ));
         }
 
         // Parse output for Azure DevOps logging commands
         let outputs = parse_logging_commands(&stdout, runtime);
+        let unsupported = unhandled_logging_commands(&stdout);
+
+        let summary = summary_file
+            .as_ref()
+            .and_then(|file| file.collect(&stdout, Path::new(&working_dir)));
+        if let Some(markdown) = &summary {
+            self.event_tx.send_event(ExecutionEvent::step_summary(
+                stage_name,
+                job_name,
+                step.name.clone(),
+                step_index,
+                markdown,
+            ));
+        }
+
+        // Keep only masked output in the result and the recording
+        let secrets = self.masked_secrets();
+        let stdout = redact(&stdout, &secrets);
+        let stderr = redact(&stderr, &secrets);
+        let summary = summary.map(|markdown| redact(&markdown, &secrets));
+        if let Some((recorder, identity)) = record_to {
+            recorder.store(
+                identity,
+                CommandRecording {
+                    stdout: stdout.clone(),
+                    stderr: stderr.clone(),
+                    exit_code,
+                },
+            );
+        }
 
         // Determine status
-        let exit_code = output.status.code();
-        let status = if !output.status.success() || (fail_on_stderr && !stderr.is_empty()) {
+        let status = if !succeeded || (fail_on_stderr && !stderr.is_empty()) {
             StepStatus::Failed
         } else {
             StepStatus::Succeeded
@@ -1225,10 +2857,39 @@ impl PipelineExecutor {
             duration: start.elapsed(),
             exit_code,
             outputs,
+            substitutions: Vec::new(),
+            summary,
+            unsupported,
+            started_at: None,
+            finished_at: None,
+            env: step_env,
         }
     }
 }
 
+/// Failed result for a step that has no recording to replay
+fn missing_recording(step: &Step, identity: &str) -> StepResult {
+    StepResult {
+        step_name: step.name.clone(),
+        display_name: step.display_name.clone(),
+        status: StepStatus::Failed,
+        output: String::new(),
+        error: Some(format!(
+            "No recording for step '{}' (record one with --record)",
+            identity
+        )),
+        duration: Duration::ZERO,
+        exit_code: None,
+        outputs: HashMap::new(),
+        substitutions: Vec::new(),
+        summary: None,
+        unsupported: Vec::new(),
+        started_at: None,
+        finished_at: None,
+        env: Vec::new(),
+    }
+}
+
 /// Check if a step should always run (has always() condition)
 fn should_always_run(step: &Step) -> bool {
     step.condition
@@ -1237,8 +2898,15 @@ fn should_always_run(step: &Step) -> bool {
         .unwrap_or(false)
 }
 
+/// Whether a step's condition is worth evaluating after the job was canceled
+fn runs_when_canceled(step: &Step) -> bool {
+    step.condition
+        .as_ref()
+        .is_some_and(|c| c.contains("canceled()"))
+}
+
 /// Build synthetic skipped step results for all steps in a job
-fn skipped_step_results(job: &Job) -> Vec<StepResult> {
+fn skipped_step_results(job: &Job, runtime: &RuntimeContext) -> Vec<StepResult> {
     // Use deployment steps if this is a deployment job
     let steps: Vec<&Step> = if job.deployment.is_some() {
         let deployment = collect_deployment_steps(job);
@@ -1247,13 +2915,19 @@ fn skipped_step_results(job: &Job) -> Vec<StepResult> {
                 .iter()
                 .map(|step| StepResult {
                     step_name: step.name.clone(),
-                    display_name: step.display_name.clone(),
+                    display_name: runtime.resolve_display_name(step.display_name.as_deref()),
                     status: StepStatus::Skipped,
                     output: String::new(),
                     error: None,
                     duration: Duration::ZERO,
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 })
                 .collect();
         }
@@ -1266,60 +2940,25 @@ fn skipped_step_results(job: &Job) -> Vec<StepResult> {
         .iter()
         .map(|step| StepResult {
             step_name: step.name.clone(),
-            display_name: step.display_name.clone(),
+            display_name: runtime.resolve_display_name(step.display_name.as_deref()),
             status: StepStatus::Skipped,
             output: String::new(),
             error: None,
             duration: Duration::ZERO,
             exit_code: None,
             outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
         })
         .collect()
 }
 
-/// Collect steps from deployment strategy hooks in execution order.
-///
-/// Deployment jobs define steps inside strategy hooks (runOnce, rolling, canary)
-/// rather than in the top-level `steps` field. This function extracts steps from
-/// the hooks in the correct Azure DevOps execution order:
-/// preDeploy → deploy → routeTraffic → postRouteTraffic
-fn collect_deployment_steps(job: &Job) -> Vec<Step> {
-    let mut steps = Vec::new();
-
-    if let Some(strategy) = &job.strategy {
-        // Helper to extract steps from DeploymentHooks
-        let extract_from_hooks =
-            |hooks: &crate::parser::models::DeploymentHooks, steps: &mut Vec<Step>| {
-                if let Some(hook) = &hooks.pre_deploy {
-                    steps.extend(hook.steps.clone());
-                }
-                if let Some(hook) = &hooks.deploy {
-                    steps.extend(hook.steps.clone());
-                }
-                if let Some(hook) = &hooks.route_traffic {
-                    steps.extend(hook.steps.clone());
-                }
-                if let Some(hook) = &hooks.post_route_traffic {
-                    steps.extend(hook.steps.clone());
-                }
-            };
-
-        if let Some(run_once) = &strategy.run_once {
-            extract_from_hooks(run_once, &mut steps);
-        }
-        if let Some(rolling) = &strategy.rolling {
-            extract_from_hooks(&rolling.hooks, &mut steps);
-        }
-        if let Some(canary) = &strategy.canary {
-            extract_from_hooks(&canary.hooks, &mut steps);
-        }
-    }
-
-    steps
-}
-
 /// Build synthetic skipped job results for all jobs in a stage node
-fn skipped_job_results(stage_node: &StageNode) -> Vec<JobResult> {
+fn skipped_job_results(stage_node: &StageNode, runtime: &RuntimeContext) -> Vec<JobResult> {
     stage_node
         .jobs
         .iter()
@@ -1328,11 +2967,13 @@ fn skipped_job_results(stage_node: &StageNode) -> Vec<JobResult> {
             let job_name = job.identifier().unwrap_or("unknown").to_string();
             JobResult {
                 job_name,
-                display_name: job.display_name.clone(),
+                display_name: runtime.resolve_display_name(job.display_name.as_deref()),
                 status: JobStatus::Skipped,
-                steps: skipped_step_results(job),
+                steps: skipped_step_results(job, runtime),
                 duration: Duration::ZERO,
                 outputs: HashMap::new(),
+                started_at: None,
+                finished_at: None,
             }
         })
         .collect()
@@ -1377,10 +3018,44 @@ fn parse_logging_commands(output: &str, runtime: &mut RuntimeContext) -> HashMap
     outputs
 }
 
+/// Logging commands roxid acts on, or that only affect how output is displayed
+const HANDLED_LOGGING_COMMANDS: &[&str] = &[
+    "task.setvariable",
+    "task.uploadsummary",
+    "task.logissue",
+    "task.setprogress",
+];
+
+/// `##vso[area.action]` commands in the output that roxid ignores, once each
+fn unhandled_logging_commands(output: &str) -> Vec<UnsupportedFeature> {
+    let mut unsupported = Vec::new();
+    for line in output.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("##vso[") else {
+            continue;
+        };
+        let command = rest
+            .split([' ', ']'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if command.is_empty() || HANDLED_LOGGING_COMMANDS.contains(&command.as_str()) {
+            continue;
+        }
+        let feature = UnsupportedFeature::new(UnsupportedKind::LoggingCommand, command);
+        if !unsupported.contains(&feature) {
+            unsupported.push(feature);
+        }
+    }
+    unsupported
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::models::{BoolOrExpression, DependsOn, Job, ScriptStep, Stage, Step};
+    use crate::parser::models::{
+        BoolOrExpression, ContainerRef, DependsOn, Job, ScriptStep, Stage, Step, TaskStep,
+    };
+    use crate::runners::sandbox::SandboxBackend;
 
     fn make_simple_pipeline() -> Pipeline {
         Pipeline {
@@ -1410,11 +3085,14 @@ mod tests {
                         enabled: true,
                         timeout_in_minutes: None,
                         retry_count_on_task_failure: None,
+                        dry_run: false,
+                        tags: Vec::new(),
                         env: HashMap::new(),
                         action: StepAction::Script(ScriptStep {
                             script: "echo Hello".to_string(),
                             working_directory: None,
                             fail_on_stderr: false,
+                            shell: None,
                         }),
                     }],
                     timeout_in_minutes: None,
@@ -1425,13 +3103,17 @@ mod tests {
                     template: None,
                     parameters: HashMap::new(),
                     environment: None,
+                    compose: None,
+                    resources: None,
                     has_template_directives: false,
+                    from_template: None,
                 }],
                 lock_behavior: None,
                 template: None,
                 parameters: HashMap::new(),
                 pool: None,
                 has_template_directives: false,
+                from_template: None,
             }],
             ..Default::default()
         }
@@ -1465,6 +3147,1465 @@ mod tests {
         assert_eq!(result.stages[0].status, StageStatus::Succeeded);
     }
 
+    #[tokio::test]
+    async fn test_substitutions_recorded_and_metacharacters_warned() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo $(greeting)".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let executor = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                audit_substitutions: true,
+                ..Default::default()
+            })
+            .with_progress(tx);
+
+        let mut context = ExecutionContext::new(
+            "test".to_string(),
+            std::env::current_dir()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+        );
+        context
+            .variables
+            .insert("greeting".to_string(), "hi; true".to_string());
+
+        let result = executor.execute(context).await;
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(
+            step.substitutions,
+            vec![VariableSubstitution::new("greeting", "hi; true")]
+        );
+
+        let mut audited = false;
+        let mut warned = false;
+        while let Ok(event) = rx.try_recv() {
+            if let ExecutionEvent::Log { level, message, .. } = event {
+                match level {
+                    crate::execution::events::LogLevel::Info => {
+                        audited |= message.contains("substituted 'greeting'")
+                    }
+                    crate::execution::events::LogLevel::Warning => {
+                        warned |= message.contains("shell metacharacters")
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert!(audited);
+        assert!(warned);
+    }
+
+    #[tokio::test]
+    async fn test_substitution_audit_masks_secrets_and_ignores_quotes() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo \"$(greeting)\" $(token)".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let mut context = ExecutionContext::new(
+            "test".to_string(),
+            std::env::current_dir()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+        );
+        context
+            .variables
+            .insert("greeting".to_string(), "it's (fine)".to_string());
+        context
+            .variables
+            .insert("token".to_string(), "s3cr3t".to_string());
+
+        for audit in [true, false] {
+            let (tx, mut rx) = crate::execution::events::progress_channel();
+            let executor = PipelineExecutor::from_pipeline(&pipeline)
+                .unwrap()
+                .with_config(ExecutorConfig {
+                    audit_substitutions: audit,
+                    secrets: vec!["s3cr3t".to_string()],
+                    ..Default::default()
+                })
+                .with_progress(tx);
+            executor.execute(context.clone()).await;
+
+            let mut audited = Vec::new();
+            while let Ok(event) = rx.try_recv() {
+                if let ExecutionEvent::Log { level, message, .. } = event {
+                    assert_ne!(level, crate::execution::events::LogLevel::Warning);
+                    if message.contains("substituted") {
+                        audited.push(message);
+                    }
+                }
+            }
+            if audit {
+                assert!(audited.iter().any(|m| m.contains("'token' = '***'")));
+                assert!(audited.iter().all(|m| !m.contains("s3cr3t")));
+            } else {
+                assert!(audited.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ready_jobs_start_without_waiting_for_a_level() {
+        let yaml = r#"
+jobs:
+  - job: Slow
+    steps:
+      - script: sleep 0.3
+  - job: AfterSlow
+    dependsOn: Slow
+    steps:
+      - script: echo after
+  - job: Quick
+    dependsOn: []
+    steps:
+      - script: echo quick
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let working_dir = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let job_events = |config: ExecutorConfig| {
+            let pipeline = pipeline.clone();
+            let working_dir = working_dir.clone();
+            async move {
+                let (tx, mut rx) = crate::execution::events::progress_channel();
+                let result = PipelineExecutor::from_pipeline(&pipeline)
+                    .unwrap()
+                    .with_config(config)
+                    .with_progress(tx)
+                    .execute(ExecutionContext::new("test".to_string(), working_dir))
+                    .await;
+                let mut events = Vec::new();
+                while let Ok(event) = rx.try_recv() {
+                    match event {
+                        ExecutionEvent::JobStarted { job_name, .. } => {
+                            events.push(format!("start {}", job_name))
+                        }
+                        ExecutionEvent::JobCompleted { job_name, .. } => {
+                            events.push(format!("end {}", job_name))
+                        }
+                        _ => {}
+                    }
+                }
+                (result, events)
+            }
+        };
+
+        // One at a time: declaration order among ready jobs
+        let (result, events) = job_events(ExecutorConfig::default()).await;
+        assert!(result.success);
+        assert_eq!(
+            events,
+            vec![
+                "start Slow",
+                "end Slow",
+                "start AfterSlow",
+                "end AfterSlow",
+                "start Quick",
+                "end Quick"
+            ]
+        );
+
+        // Two at a time: Quick runs alongside Slow and results keep declaration order
+        let (result, events) = job_events(ExecutorConfig {
+            max_parallel_jobs: 2,
+            ..Default::default()
+        })
+        .await;
+        assert!(result.success);
+        let position = |e: &str| events.iter().position(|x| x == e).unwrap();
+        assert!(position("end Quick") < position("end Slow"));
+        assert!(position("end Slow") < position("start AfterSlow"));
+        let names: Vec<_> = result.stages[0]
+            .jobs
+            .iter()
+            .map(|j| j.job_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Slow", "AfterSlow", "Quick"]);
+    }
+
+    #[tokio::test]
+    async fn test_resource_pool_limits_jobs() {
+        let yaml = r#"
+jobs:
+  - job: Build
+    resources: { cpus: 2 }
+    steps:
+      - script: sleep 0.2
+  - job: Test
+    dependsOn: []
+    resources: { cpus: 2 }
+    steps:
+      - script: sleep 0.2
+  - job: Lint
+    dependsOn: []
+    steps:
+      - script: echo lint
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let working_dir = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let job_events = |cpus: u32| {
+            let pipeline = pipeline.clone();
+            let working_dir = working_dir.clone();
+            async move {
+                let (tx, mut rx) = crate::execution::events::progress_channel();
+                let result = PipelineExecutor::from_pipeline(&pipeline)
+                    .unwrap()
+                    .with_config(ExecutorConfig {
+                        max_parallel_jobs: 0,
+                        resources: Some(ResourcePool::new(Resources { cpus, memory: None })),
+                        ..Default::default()
+                    })
+                    .with_progress(tx)
+                    .execute(ExecutionContext::new("test".to_string(), working_dir))
+                    .await;
+                assert!(result.success);
+                let mut events = Vec::new();
+                while let Ok(event) = rx.try_recv() {
+                    match event {
+                        ExecutionEvent::JobStarted { job_name, .. } => {
+                            events.push(format!("start {}", job_name))
+                        }
+                        ExecutionEvent::JobCompleted { job_name, .. } => {
+                            events.push(format!("end {}", job_name))
+                        }
+                        _ => {}
+                    }
+                }
+                events
+            }
+        };
+
+        // Room for one two-CPU job at a time; Lint waits its turn behind them
+        let events = job_events(2).await;
+        assert_eq!(
+            events,
+            vec![
+                "start Build",
+                "end Build",
+                "start Test",
+                "end Test",
+                "start Lint",
+                "end Lint"
+            ]
+        );
+
+        // Room for both; Lint starts once one of them is done
+        let events = job_events(4).await;
+        let position = |e: &str| events.iter().position(|x| x == e).unwrap();
+        assert!(position("start Test") < position("end Build"));
+        assert!(position("start Lint") > position("end Build").min(position("end Test")));
+    }
+
+    #[tokio::test]
+    async fn test_script_custom_shell() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo \"from $0\"".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: Some("file-sh".to_string()),
+        });
+
+        let executor = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                shells: HashMap::from([("file-sh".to_string(), "sh {0}".to_string())]),
+                ..Default::default()
+            });
+
+        let context = ExecutionContext::new(
+            "test".to_string(),
+            std::env::current_dir()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+        );
+
+        let result = executor.execute(context).await;
+        assert!(result.success);
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert!(step.output.contains("roxid-script-"));
+    }
+
+    #[tokio::test]
+    async fn test_checkout_repository_into_path() {
+        use crate::parser::models::{
+            CheckoutSource, CheckoutStep, RepositoryResource, Resources, SubmoduleOption,
+        };
+
+        let source = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=roxid",
+                    "-c",
+                    "user.email=roxid@example.com",
+                ])
+                .args(args)
+                .current_dir(source.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "--quiet"]);
+        std::fs::write(source.path().join("README"), "tools repo").unwrap();
+        git(&["add", "README"]);
+        git(&["commit", "--quiet", "-m", "init"]);
+
+        let mut pipeline = make_simple_pipeline();
+        pipeline.resources = Some(Resources {
+            repositories: vec![RepositoryResource {
+                repository: "tools".to_string(),
+                repo_type: Some("git".to_string()),
+                name: Some("Project/tools".to_string()),
+                git_ref: None,
+                endpoint: None,
+                trigger: None,
+            }],
+            containers: Vec::new(),
+            pipelines: Vec::new(),
+            packages: Vec::new(),
+            webhooks: Vec::new(),
+        });
+        let steps = &mut pipeline.stages[0].jobs[0].steps;
+        let mut checkout = steps[0].clone();
+        checkout.name = Some("checkout_tools".to_string());
+        checkout.action = StepAction::Checkout(CheckoutStep {
+            checkout: CheckoutSource::Repository("tools".to_string()),
+            clean: false,
+            fetch_depth: None,
+            fetch_tags: None,
+            lfs: false,
+            submodules: SubmoduleOption::False,
+            path: Some("deps/tools".to_string()),
+            persistent_credentials: None,
+        });
+        steps.insert(0, checkout);
+        steps[1].action = StepAction::Script(ScriptStep {
+            script: "cat \"$BUILD_REPOSITORY_LOCALPATH/README\"".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+
+        let workspace = tempfile::tempdir().unwrap();
+        let executor = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                repositories: HashMap::from([(
+                    "tools".to_string(),
+                    source.path().to_string_lossy().to_string(),
+                )]),
+                ..Default::default()
+            });
+        let context = ExecutionContext::new(
+            "test".to_string(),
+            workspace.path().to_string_lossy().to_string(),
+        );
+
+        let result = executor.execute(context).await;
+        assert!(result.success);
+        assert!(workspace.path().join("deps/tools/README").exists());
+        let steps = &result.stages[0].jobs[0].steps;
+        assert!(steps[1].output.contains("tools repo"));
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_shell_fails_before_running() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo hi".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: Some("roxid-missing-shell {0}".to_string()),
+        });
+
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let executor = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_progress(tx);
+        let context = ExecutionContext::new("test".to_string(), "/tmp".to_string());
+
+        let result = executor.execute(context).await;
+        assert!(!result.success);
+        assert!(result.stages.is_empty());
+
+        let mut reported = false;
+        while let Ok(event) = rx.try_recv() {
+            if let ExecutionEvent::Error { message, .. } = event {
+                reported |= message.contains("not found on PATH");
+            }
+        }
+        assert!(reported);
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_step_output() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo recorded; echo '##vso[task.setvariable variable=fromStep]yes'"
+                .to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let run = |pipeline: &Pipeline, recorder: StepRecorder| {
+            let executor = PipelineExecutor::from_pipeline(pipeline)
+                .unwrap()
+                .with_config(ExecutorConfig {
+                    recorder: Some(recorder),
+                    ..Default::default()
+                });
+            async move {
+                executor
+                    .execute(ExecutionContext::new(
+                        "test".to_string(),
+                        "/tmp".to_string(),
+                    ))
+                    .await
+            }
+        };
+
+        let recorder = StepRecorder::record();
+        assert!(run(&pipeline, recorder.clone()).await.success);
+        let recordings = recorder.recordings();
+        assert_eq!(
+            recordings.steps["Build/BuildJob/echo"][0].exit_code,
+            Some(0)
+        );
+
+        // The command is not run again: the recorded output is used instead
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "exit 1".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let result = run(&pipeline, StepRecorder::replay(recordings)).await;
+        assert!(result.success);
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert!(step.output.starts_with("recorded\n"));
+        assert_eq!(
+            result.variables.get("fromStep").map(String::as_str),
+            Some("yes")
+        );
+
+        // Steps without a recording fail instead of running
+        let result = run(&pipeline, StepRecorder::replay(Default::default())).await;
+        assert!(!result.success);
+        let error = result.stages[0].jobs[0].steps[0].error.clone().unwrap();
+        assert!(error.contains("No recording for step 'Build/BuildJob/echo'"));
+    }
+
+    #[tokio::test]
+    async fn test_recorded_script_output_is_masked() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo token=hunter2; echo denied: hunter2 >&2".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let recorder = StepRecorder::record();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                recorder: Some(recorder.clone()),
+                secrets: vec!["hunter2".to_string()],
+                ..Default::default()
+            })
+            .execute(ExecutionContext::new(
+                "test".to_string(),
+                "/tmp".to_string(),
+            ))
+            .await;
+
+        assert!(result.success);
+        let recording = &recorder.recordings().steps["Build/BuildJob/echo"][0];
+        assert_eq!(recording.stdout.trim(), "token=***");
+        assert_eq!(recording.stderr.trim(), "denied: ***");
+    }
+
+    #[tokio::test]
+    async fn test_job_compose_failure_skips_steps() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].compose = Some(ComposeSpec {
+            file: "does-not-exist.compose.yml".to_string(),
+            services: Vec::new(),
+            wait_timeout_in_seconds: None,
+        });
+
+        let executor = PipelineExecutor::from_pipeline(&pipeline).unwrap();
+        let context = ExecutionContext::new(
+            "test".to_string(),
+            std::env::current_dir()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+        );
+
+        let result = executor.execute(context).await;
+        let job = &result.stages[0].jobs[0];
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.steps.iter().all(|s| s.status == StepStatus::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_step_summary_collected() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo '## Results' >> \"$GITHUB_STEP_SUMMARY\"".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let executor = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_progress(tx);
+
+        let context = ExecutionContext::new(
+            "test".to_string(),
+            std::env::current_dir()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+        );
+
+        let result = executor.execute(context).await;
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.summary.as_deref(), Some("## Results"));
+
+        let mut published = false;
+        while let Ok(event) = rx.try_recv() {
+            if let ExecutionEvent::StepSummary { markdown, .. } = event {
+                published |= markdown == "## Results";
+            }
+        }
+        assert!(published);
+    }
+
+    #[tokio::test]
+    async fn test_confirmer_previews_and_declines_steps() {
+        struct DeclineAll(std::sync::Mutex<Vec<StepPreview>>);
+        impl StepConfirmer for DeclineAll {
+            fn confirm(&self, preview: &StepPreview) -> bool {
+                self.0.lock().unwrap().push(preview.clone());
+                false
+            }
+        }
+
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo $(greeting) > confirmed.txt".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        pipeline.stages[0].jobs[0].steps[0].env =
+            HashMap::from([("ROXID_CONFIRM_TEST".to_string(), "1".to_string())]);
+        let dir = tempfile::TempDir::new().unwrap();
+        let context =
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string())
+                .with_variables(HashMap::from([(
+                    "greeting".to_string(),
+                    "hello".to_string(),
+                )]));
+
+        let confirmer = Arc::new(DeclineAll(Default::default()));
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_confirmer(confirmer.clone())
+            .execute(context)
+            .await;
+
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.status, StepStatus::Skipped);
+        assert!(!dir.path().join("confirmed.txt").exists());
+
+        let previews = confirmer.0.lock().unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].step, "Echo Hello");
+        assert_eq!(
+            previews[0].command.last().map(String::as_str),
+            Some("echo hello > confirmed.txt")
+        );
+        assert!(previews[0]
+            .env_changes
+            .contains(&("ROXID_CONFIRM_TEST".to_string(), "1".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_failed_script_output_is_masked_in_summary() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo token=hunter2; echo denied: hunter2 >&2; exit 1".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let dir = tempfile::TempDir::new().unwrap();
+        let context =
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string());
+
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                secrets: vec!["hunter2".to_string()],
+                ..Default::default()
+            })
+            .execute(context)
+            .await;
+
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.status, StepStatus::Failed);
+        assert_eq!(step.output.trim(), "token=***");
+        let markdown = crate::execution::markdown_summary::run_markdown("test", &result);
+        assert!(markdown.contains("denied: ***"));
+        assert!(!markdown.contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_confirmer_asks_for_tasks_with_secrets_masked() {
+        struct DeclineAll(std::sync::Mutex<Vec<StepPreview>>);
+        impl StepConfirmer for DeclineAll {
+            fn confirm(&self, preview: &StepPreview) -> bool {
+                self.0.lock().unwrap().push(preview.clone());
+                false
+            }
+        }
+
+        let mut pipeline = make_simple_pipeline();
+        let mut script = pipeline.stages[0].jobs[0].steps[0].clone();
+        script.action = StepAction::Script(ScriptStep {
+            script: "deploy --token hunter2".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        script.env = HashMap::from([("DEPLOY_TOKEN".to_string(), "hunter2".to_string())]);
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Task(TaskStep {
+            task: "AzureCLI@2".to_string(),
+            inputs: HashMap::from([
+                ("scriptType".to_string(), "bash".to_string()),
+                (
+                    "inlineScript".to_string(),
+                    "az login -p hunter2".to_string(),
+                ),
+            ]),
+        });
+        pipeline.stages[0].jobs[0].steps.push(script);
+        let dir = tempfile::TempDir::new().unwrap();
+        let context =
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string());
+
+        let confirmer = Arc::new(DeclineAll(Default::default()));
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                secrets: vec!["hunter2".to_string()],
+                ..Default::default()
+            })
+            .with_confirmer(confirmer.clone())
+            .execute(context)
+            .await;
+
+        let steps = &result.stages[0].jobs[0].steps;
+        assert!(steps.iter().all(|step| step.status == StepStatus::Skipped));
+        let previews = confirmer.0.lock().unwrap();
+        assert_eq!(previews.len(), 2);
+        assert_eq!(
+            previews[0].command,
+            [
+                "AzureCLI@2",
+                "inlineScript: az login -p ***\nscriptType: bash"
+            ]
+        );
+        assert_eq!(
+            previews[1].command.last().map(String::as_str),
+            Some("deploy --token ***")
+        );
+        assert!(previews[1]
+            .env_changes
+            .contains(&("DEPLOY_TOKEN".to_string(), "***".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_step_reports_command() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo $(greeting) > dry.txt".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        pipeline.stages[0].jobs[0].steps[0].dry_run = true;
+        let dir = tempfile::TempDir::new().unwrap();
+        let context =
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string())
+                .with_variables(HashMap::from([(
+                    "greeting".to_string(),
+                    "hello".to_string(),
+                )]));
+
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .execute(context)
+            .await;
+
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.status, StepStatus::Succeeded);
+        assert_eq!(step.output, "echo hello > dry.txt");
+        assert_eq!(step.substitutions.len(), 1);
+        assert!(!dir.path().join("dry.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_confines_writes_to_workspace() {
+        let profile = SandboxProfile::default();
+        match Sandbox::detect(profile.clone()) {
+            Ok(sandbox) if sandbox.backend() == SandboxBackend::Namespaces => {}
+            _ => {
+                eprintln!("skipped: the sandbox needs unprivileged user namespaces");
+                return;
+            }
+        }
+
+        let outside = Path::new(env!("CARGO_MANIFEST_DIR")).join("sandbox-probe.txt");
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: format!(
+                "echo ok > inside.txt && ! echo no > '{}'",
+                outside.display()
+            ),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let dir = tempfile::TempDir::new().unwrap();
+        let context =
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string());
+
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                sandbox: Some(profile),
+                ..Default::default()
+            })
+            .execute(context)
+            .await;
+
+        let _ = std::fs::remove_file(&outside);
+        assert!(result.success, "{:?}", result.stages[0].jobs[0].steps[0]);
+        assert!(dir.path().join("inside.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_rejects_container_jobs() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].container = Some(ContainerRef::Image("ubuntu:24.04".into()));
+        let dir = tempfile::TempDir::new().unwrap();
+        let context =
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string());
+
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                sandbox: Some(SandboxProfile::default()),
+                ..Default::default()
+            })
+            .with_container_runner()
+            .with_progress(tx)
+            .execute(context)
+            .await;
+
+        assert!(!result.success);
+        assert!(result.stages.is_empty());
+        let mut rejected = false;
+        while let Ok(event) = rx.try_recv() {
+            if let ExecutionEvent::Error { message, .. } = event {
+                rejected |= message.contains("the sandbox can't confine");
+            }
+        }
+        assert!(rejected);
+    }
+
+    #[tokio::test]
+    async fn test_disk_quota_stops_step() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "head -c 1048576 /dev/zero > big.bin && sleep 30".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let dir = tempfile::TempDir::new().unwrap();
+        let context =
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string());
+
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let started = Instant::now();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                disk_quota: DiskQuota {
+                    quota: Some(64 * 1024),
+                    min_free: None,
+                },
+                ..Default::default()
+            })
+            .with_progress(tx)
+            .execute(context)
+            .await;
+
+        assert!(!result.success);
+        assert!(started.elapsed() < Duration::from_secs(20));
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.status, StepStatus::Failed);
+        let error = step.error.as_deref().unwrap_or_default();
+        assert!(error.starts_with("Disk quota exceeded: "), "{}", error);
+        assert!(error.contains("over its 64.0 KB quota"), "{}", error);
+
+        let mut reported = false;
+        while let Ok(event) = rx.try_recv() {
+            reported |= matches!(
+                event,
+                ExecutionEvent::DiskQuotaExceeded { step_index: 0, .. }
+            );
+        }
+        assert!(reported);
+    }
+
+    #[tokio::test]
+    async fn test_unhandled_logging_commands_recorded() {
+        let mut pipeline = make_simple_pipeline();
+        pipeline.stages[0].jobs[0].steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo '##vso[task.setvariable variable=a]1'; \
+                     echo '##vso[artifact.upload artifactname=drop]out.zip'; \
+                     echo '##vso[artifact.upload artifactname=logs]logs.zip'"
+                .to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        let context = || ExecutionContext::new("test".to_string(), ".".to_string());
+
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .execute(context())
+            .await;
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.status, StepStatus::Succeeded);
+        assert_eq!(
+            step.unsupported,
+            vec![UnsupportedFeature::new(
+                UnsupportedKind::LoggingCommand,
+                "artifact.upload"
+            )]
+        );
+
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                fail_on_unsupported: true,
+                ..Default::default()
+            })
+            .execute(context())
+            .await;
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.status, StepStatus::Failed);
+        assert_eq!(
+            step.error.as_deref(),
+            Some("Not supported by roxid: ##vso[artifact.upload]")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_vault_step_uses_local_provider() {
+        let mut pipeline = make_simple_pipeline();
+        let steps = &mut pipeline.stages[0].jobs[0].steps;
+        let mut vault = steps[0].clone();
+        vault.name = Some("vault".to_string());
+        vault.action = StepAction::Task(TaskStep {
+            task: "AzureKeyVault@2".to_string(),
+            inputs: HashMap::from([
+                ("KeyVaultName".to_string(), "kv-dev".to_string()),
+                ("SecretsFilter".to_string(), "dbPassword".to_string()),
+            ]),
+        });
+        steps[0].action = StepAction::Script(ScriptStep {
+            script: "echo \"password=$(dbPassword)\"".to_string(),
+            working_directory: None,
+            fail_on_stderr: false,
+            shell: None,
+        });
+        steps.insert(0, vault);
+
+        let provider: SecretProvider =
+            toml::from_str("command = \"echo dbPassword=hunter2; echo apiKey=k1\"").unwrap();
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                key_vaults: HashMap::from([("KV-Dev".to_string(), provider)]),
+                ..Default::default()
+            })
+            .with_progress(tx)
+            .execute(ExecutionContext::new("test".to_string(), ".".to_string()))
+            .await;
+        assert!(result.success);
+        let vault = &result.stages[0].jobs[0].steps[0];
+        assert!(vault.output.contains("1 secret(s)"));
+        assert!(!vault.output.contains("apiKey"));
+
+        let mut printed = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let ExecutionEvent::StepOutput { output, .. } = event {
+                printed.push(output.to_string());
+            }
+        }
+        assert!(printed.iter().any(|line| line.trim() == "password=***"));
+        assert!(!printed.iter().any(|line| line.contains("hunter2")));
+    }
+
+    #[tokio::test]
+    async fn test_cache_task_saves_after_job_and_restores() {
+        let store = tempfile::TempDir::new().unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        std::fs::write(workspace.path().join("deps.lock"), "left-pad 1.0").unwrap();
+        let yaml = r#"
+steps:
+  - task: Cache@2
+    inputs:
+      key: 'deps | "$(os)" | deps.lock'
+      restoreKeys: |
+        deps | "$(os)"
+      path: vendor
+      cacheHitVar: DEPS_RESTORED
+  - script: |
+      echo "restored=$(DEPS_RESTORED)"
+      test -f vendor/lib.txt || (mkdir -p vendor && echo fetched > vendor/lib.txt)
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let run = || async {
+            let result = PipelineExecutor::from_pipeline(&pipeline)
+                .unwrap()
+                .with_config(ExecutorConfig {
+                    cache_storage: Some(Arc::new(LocalCacheStorage::new(store.path()))),
+                    ..Default::default()
+                })
+                .execute(
+                    ExecutionContext::new(
+                        "test".to_string(),
+                        workspace.path().to_string_lossy().to_string(),
+                    )
+                    .with_variables(HashMap::from([("os".to_string(), "Linux".to_string())])),
+                )
+                .await;
+            assert!(result.success);
+            result.stages[0].jobs[0].steps[1].output.clone()
+        };
+
+        // A miss, saved once the job succeeded
+        assert!(run().await.contains("restored=false"));
+        std::fs::remove_dir_all(workspace.path().join("vendor")).unwrap();
+        assert!(run().await.contains("restored=true"));
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("vendor/lib.txt")).unwrap(),
+            "fetched\n"
+        );
+
+        // A changed lock file misses its key but restores from the restore key
+        std::fs::write(workspace.path().join("deps.lock"), "left-pad 1.1").unwrap();
+        assert!(run().await.contains("restored=inexact"));
+    }
+
+    #[tokio::test]
+    async fn test_rerun_stage_reuses_upstream_results() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+stages:
+  - stage: Build
+    jobs:
+      - job: Compile
+        steps:
+          - script: echo built >> runs.txt
+  - stage: Lint
+    dependsOn: []
+    jobs:
+      - job: Check
+        steps:
+          - script: exit 1
+  - stage: Test
+    dependsOn: Build
+    condition: eq(dependencies.Build.result, 'Succeeded')
+    jobs:
+      - job: Unit
+        steps:
+          - script: echo "tested $(attempt)" >> runs.txt
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let context = |attempt: &str| {
+            ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string())
+                .with_variables(HashMap::from([(
+                    "attempt".to_string(),
+                    attempt.to_string(),
+                )]))
+        };
+
+        let first = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .execute(context("1"))
+            .await;
+        assert!(!first.success);
+
+        let rerun = StageRerun::new(&pipeline, "Test", &first).unwrap();
+        let second = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                rerun: Some(rerun),
+                ..Default::default()
+            })
+            .execute(context("2"))
+            .await;
+
+        // Lint's failure kept Test from starting the first time. Build isn't run
+        // again, yet Test sees it succeeded, and Lint's failure carries over.
+        let runs = std::fs::read_to_string(dir.path().join("runs.txt")).unwrap();
+        assert_eq!(runs, "built\ntested 2\n");
+        assert_eq!(second.stages.len(), 3);
+        assert_eq!(second.stages[2].status, StageStatus::Succeeded);
+        assert!(!second.success);
+    }
+
+    #[tokio::test]
+    async fn test_matrix_instances_have_own_results() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r###"
+jobs:
+  - job: Build
+    strategy:
+      matrix:
+        linux:
+          flag: yes
+        windows:
+          flag: no
+    steps:
+      - script: echo "##vso[task.setvariable variable=flag;isOutput=true]$(flag)"
+        name: set
+  - job: After
+    dependsOn: Build
+    condition: eq(dependencies.Build.outputs['linux.set.flag'], 'yes')
+    steps:
+      - script: echo after
+"###;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .execute(ExecutionContext::new(
+                "test".to_string(),
+                dir.path().to_string_lossy().to_string(),
+            ))
+            .await;
+
+        assert!(result.success);
+        let jobs = &result.stages[0].jobs;
+        let names: Vec<&str> = jobs.iter().map(|j| j.job_name.as_str()).collect();
+        assert_eq!(names, ["Build[linux]", "Build[windows]", "After"]);
+        assert_eq!(jobs[0].matrix_instance(), Some("linux"));
+        assert_eq!(jobs[1].job(), "Build");
+        assert_eq!(jobs[1].steps.len(), 1);
+        assert_eq!(jobs[1].outputs["set.flag"], "no");
+        assert_eq!(jobs[2].status, JobStatus::Succeeded);
+
+        let combined = JobResult::combine_instances(jobs);
+        assert_eq!(combined.len(), 2);
+        assert_eq!(combined[0].job_name, "Build");
+        assert_eq!(combined[0].steps.len(), 2);
+        assert_eq!(combined[0].outputs["windows.set.flag"], "no");
+    }
+
+    #[tokio::test]
+    async fn test_matrix_instances_after_a_failure_are_skipped() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+jobs:
+  - job: Build
+    strategy:
+      matrix:
+        linux:
+          code: 1
+        macos:
+          code: 0
+        windows:
+          code: 0
+    steps:
+      - script: exit $(code)
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_progress(tx)
+            .execute(ExecutionContext::new(
+                "test".to_string(),
+                dir.path().to_string_lossy().to_string(),
+            ))
+            .await;
+
+        assert!(!result.success);
+        let jobs = &result.stages[0].jobs;
+        let statuses: Vec<(&str, &JobStatus)> = jobs
+            .iter()
+            .map(|j| (j.job_name.as_str(), &j.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("Build[linux]", &JobStatus::Failed),
+                ("Build[macos]", &JobStatus::Skipped),
+                ("Build[windows]", &JobStatus::Skipped),
+            ]
+        );
+        assert_eq!(jobs[1].steps[0].status, StepStatus::Skipped);
+
+        let mut reasons = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let ExecutionEvent::JobSkipped {
+                job_name, reason, ..
+            } = event
+            {
+                reasons.push((job_name, reason));
+            }
+        }
+        assert_eq!(
+            reasons,
+            [
+                (
+                    "Build[macos]".to_string(),
+                    "Matrix instance 'linux' failed".to_string()
+                ),
+                (
+                    "Build[windows]".to_string(),
+                    "Matrix instance 'linux' failed".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_results_record_start_and_finish_times() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+steps:
+  - script: echo one
+  - script: echo two
+    condition: eq('a', 'b')
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .execute(ExecutionContext::new(
+                "test".to_string(),
+                dir.path().to_string_lossy().to_string(),
+            ))
+            .await;
+
+        let stage = &result.stages[0];
+        let job = &stage.jobs[0];
+        let (ran, skipped) = (&job.steps[0], &job.steps[1]);
+        assert!(result.started_at <= stage.started_at);
+        assert!(stage.started_at <= job.started_at);
+        assert!(job.started_at <= ran.started_at);
+        assert!(ran.started_at.is_some() && ran.started_at <= ran.finished_at);
+        assert!(ran.finished_at <= job.finished_at);
+        assert!(job.finished_at <= stage.finished_at);
+        assert!(stage.finished_at <= result.finished_at);
+        assert_eq!(skipped.status, StepStatus::Skipped);
+        assert_eq!((skipped.started_at, skipped.finished_at), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_injected_failure_runs_cleanup_steps() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+steps:
+  - script: echo deployed > deployed.txt
+    displayName: Deploy
+  - script: echo next
+  - script: echo cleaned > cleaned.txt
+    condition: always()
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                faults: vec![Fault::parse("step=Deploy,exit=7").unwrap()],
+                ..Default::default()
+            })
+            .execute(ExecutionContext::new(
+                "test".to_string(),
+                dir.path().to_string_lossy().to_string(),
+            ))
+            .await;
+
+        let steps = &result.stages[0].jobs[0].steps;
+        assert!(!result.success);
+        assert_eq!(steps[0].status, StepStatus::Failed);
+        assert_eq!(steps[0].exit_code, Some(7));
+        assert_eq!(steps[1].status, StepStatus::Skipped);
+        assert_eq!(steps[2].status, StepStatus::Succeeded);
+        assert!(!dir.path().join("deployed.txt").exists());
+        assert!(dir.path().join("cleaned.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_injected_cancellation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+steps:
+  - script: echo deployed
+    displayName: Deploy
+  - script: echo next
+  - script: echo rolled back > rollback.txt
+    condition: canceled()
+  - script: echo cleaned
+    condition: always()
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                faults: vec![Fault::parse("step=Deploy,cancel=true").unwrap()],
+                ..Default::default()
+            })
+            .execute(ExecutionContext::new(
+                "test".to_string(),
+                dir.path().to_string_lossy().to_string(),
+            ))
+            .await;
+
+        let job = &result.stages[0].jobs[0];
+        assert!(!result.success);
+        assert_eq!(result.stages[0].status, StageStatus::Canceled);
+        assert_eq!(job.status, JobStatus::Canceled);
+        assert_eq!(job.steps[0].status, StepStatus::Canceled);
+        assert_eq!(job.steps[1].status, StepStatus::Skipped);
+        assert_eq!(job.steps[2].status, StepStatus::Succeeded);
+        assert_eq!(job.steps[3].status, StepStatus::Succeeded);
+        assert!(dir.path().join("rollback.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_run_id_on_events_results_and_variables() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+steps:
+  - script: echo "$ROXID_RUNID $(Roxid.RunId)"
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_progress(tx)
+            .execute(
+                ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string())
+                    .with_run_id("01JRUN"),
+            )
+            .await;
+
+        assert_eq!(result.run_id, "01JRUN");
+        let step = &result.stages[0].jobs[0].steps[0];
+        assert_eq!(step.output.trim(), "01JRUN 01JRUN");
+
+        let mut run_ids = Vec::new();
+        let mut step_ids = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            match &event {
+                ExecutionEvent::PipelineStarted { run_id, .. }
+                | ExecutionEvent::PipelineCompleted { run_id, .. } => run_ids.push(run_id.clone()),
+                ExecutionEvent::StepStarted { .. } | ExecutionEvent::StepCompleted { .. } => {
+                    step_ids.push(event.correlation_id().unwrap())
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(run_ids, ["01JRUN", "01JRUN"]);
+        assert_eq!(step_ids, ["Build/Job/1", "Build/Job/1"]);
+    }
+
+    #[tokio::test]
+    async fn test_step_env_captured_with_secrets_masked() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+variables:
+  configuration: Release
+steps:
+  - script: echo ok
+    env:
+      CONFIGURATION: $(configuration)
+      TOKEN: token-s3cr3t
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                secrets: vec!["s3cr3t".to_string()],
+                audit_env: true,
+                ..Default::default()
+            })
+            .with_progress(tx)
+            .execute(ExecutionContext::new(
+                "test".to_string(),
+                dir.path().to_string_lossy().to_string(),
+            ))
+            .await;
+
+        let env = &result.stages[0].jobs[0].steps[0].env;
+        assert!(env.contains(&("CONFIGURATION".to_string(), "Release".to_string())));
+        assert!(env.contains(&("TOKEN".to_string(), "token-***".to_string())));
+
+        let mut logged = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let ExecutionEvent::Log { message, .. } = event {
+                logged.push(message);
+            }
+        }
+        assert!(logged.iter().any(|m| m.ends_with("env TOKEN=token-***")));
+        assert!(!logged.iter().any(|m| m.contains("s3cr3t")));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_jobs_keep_variables_to_themselves() {
+        let yaml = r#"
+variables:
+  shared: initial
+jobs:
+  - job: A
+    steps:
+      - script: echo '##vso[task.setvariable variable=shared]A'; sleep 0.3
+      - script: echo "A sees $(shared)"
+  - job: B
+    steps:
+      - script: echo '##vso[task.setvariable variable=shared]B'
+      - script: sleep 0.1; echo "B sees $(shared)"
+  - job: After
+    dependsOn: [A, B]
+    steps:
+      - script: echo "After sees $(shared)"
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_config(ExecutorConfig {
+                max_parallel_jobs: 2,
+                ..Default::default()
+            })
+            .execute(ExecutionContext::new("test".to_string(), ".".to_string()))
+            .await;
+        assert!(result.success);
+        let output = |job: usize, step: usize| result.stages[0].jobs[job].steps[step].output.trim();
+        assert_eq!(output(0, 1), "A sees A");
+        assert_eq!(output(1, 1), "B sees B");
+        assert_eq!(output(2, 0), "After sees initial");
+        // B is declared after A, so its value is reported though A finished last
+        assert_eq!(result.variables["shared"], "B");
+    }
+
+    #[tokio::test]
+    async fn test_display_names_resolved_in_events_and_results() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = r#"
+variables:
+  region: eu
+stages:
+  - stage: Deploy
+    displayName: Deploy to $(env)-$(region)
+    variables:
+      env: prod
+    jobs:
+      - job: Ship
+        displayName: Ship ${{ parameters.app }}
+        variables:
+          tier: web
+        steps:
+          - script: echo ok
+            displayName: Push $(tier) to $(region)
+          - script: echo never
+            displayName: Notify $(tier)
+            condition: eq('a', 'b')
+"#;
+        let pipeline =
+            crate::parser::normalize_pipeline(crate::parser::AzureParser::parse(yaml).unwrap());
+        let mut parameters = HashMap::new();
+        parameters.insert("app".to_string(), serde_yaml::Value::from("api"));
+        let (tx, mut rx) = crate::execution::events::progress_channel();
+        let result = PipelineExecutor::from_pipeline(&pipeline)
+            .unwrap()
+            .with_progress(tx)
+            .execute(
+                ExecutionContext::new("test".to_string(), dir.path().to_string_lossy().to_string())
+                    .with_parameters(parameters),
+            )
+            .await;
+
+        let stage = &result.stages[0];
+        assert_eq!(stage.display_name.as_deref(), Some("Deploy to prod-eu"));
+        assert_eq!(stage.jobs[0].display_name.as_deref(), Some("Ship api"));
+        let steps = &stage.jobs[0].steps;
+        assert_eq!(steps[0].display_name.as_deref(), Some("Push web to eu"));
+        assert_eq!(steps[1].display_name.as_deref(), Some("Notify web"));
+
+        let mut started = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                ExecutionEvent::StageStarted { display_name, .. }
+                | ExecutionEvent::JobStarted { display_name, .. }
+                | ExecutionEvent::StepStarted { display_name, .. } => {
+                    started.push(display_name.unwrap())
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(started, ["Deploy to prod-eu", "Ship api", "Push web to eu"]);
+    }
+
     #[test]
     fn test_parse_logging_commands() {
         let base = ExecutionContext::new("test".to_string(), "/work".to_string());
@@ -1496,11 +4637,14 @@ World
             enabled: true,
             timeout_in_minutes: None,
             retry_count_on_task_failure: None,
+            dry_run: false,
+            tags: Vec::new(),
             env: HashMap::new(),
             action: StepAction::Script(ScriptStep {
                 script: "echo".to_string(),
                 working_directory: None,
                 fail_on_stderr: false,
+                shell: None,
             }),
         };
 
@@ -1512,11 +4656,14 @@ World
             enabled: true,
             timeout_in_minutes: None,
             retry_count_on_task_failure: None,
+            dry_run: false,
+            tags: Vec::new(),
             env: HashMap::new(),
             action: StepAction::Script(ScriptStep {
                 script: "echo".to_string(),
                 working_directory: None,
                 fail_on_stderr: false,
+                shell: None,
             }),
         };
 
@@ -0,0 +1,182 @@
+// Exit Status
+// How a run's outcome becomes the process exit code, so CI wrappers can tell kinds of failure apart
+
+use crate::execution::executor::ExecutionResult;
+use crate::execution::parity::ParityReport;
+use crate::parser::models::StepStatus;
+
+use thiserror::Error;
+
+/// The pipeline succeeded
+pub const EXIT_SUCCESS: i32 = 0;
+/// The pipeline ran and failed (or had warnings, with `--fail-on-warnings`)
+pub const EXIT_PIPELINE_FAILED: i32 = 1;
+/// The pipeline or the command line is invalid, so nothing ran
+pub const EXIT_INVALID: i32 = 2;
+/// The pipeline couldn't be run: missing tools, I/O errors, crashes
+pub const EXIT_INFRASTRUCTURE: i32 = 3;
+
+/// How a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Succeeded {
+        /// Steps that succeeded with issues or failed with `continueOnError`,
+        /// plus features roxid degraded
+        warnings: usize,
+    },
+    Failed,
+    Invalid,
+    Infrastructure,
+}
+
+impl RunOutcome {
+    pub fn from_result(result: &ExecutionResult) -> Self {
+        if !result.success {
+            return Self::Failed;
+        }
+        // A successful run's failed steps all had continueOnError
+        let with_issues = result
+            .stages
+            .iter()
+            .flat_map(|stage| &stage.jobs)
+            .flat_map(|job| &job.steps)
+            .filter(|step| {
+                matches!(
+                    step.status,
+                    StepStatus::SucceededWithIssues | StepStatus::Failed
+                )
+            })
+            .count();
+        Self::Succeeded {
+            warnings: with_issues + ParityReport::from_result(result).len(),
+        }
+    }
+}
+
+/// `--exit-zero-on-failure` and `--fail-on-warnings`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExitPolicy {
+    /// Report-only: a failed pipeline exits 0. Invalid pipelines and
+    /// infrastructure errors still don't, since nothing was reported.
+    pub zero_on_failure: bool,
+    /// A pipeline that succeeded with warnings counts as failed
+    pub fail_on_warnings: bool,
+}
+
+impl ExitPolicy {
+    pub fn exit_code(&self, outcome: RunOutcome) -> i32 {
+        let failed = match outcome {
+            RunOutcome::Succeeded { warnings } => self.fail_on_warnings && warnings > 0,
+            RunOutcome::Failed => true,
+            RunOutcome::Invalid => return EXIT_INVALID,
+            RunOutcome::Infrastructure => return EXIT_INFRASTRUCTURE,
+        };
+        if failed && !self.zero_on_failure {
+            EXIT_PIPELINE_FAILED
+        } else {
+            EXIT_SUCCESS
+        }
+    }
+}
+
+/// An error from the machine running the pipeline rather than the pipeline
+/// itself, reported with [`EXIT_INFRASTRUCTURE`]
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct InfrastructureError(pub String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::models::{JobResult, JobStatus, StageResult, StageStatus, StepResult};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn result(success: bool, status: StepStatus) -> ExecutionResult {
+        ExecutionResult {
+            run_id: String::new(),
+            stages: vec![StageResult {
+                stage_name: "Build".to_string(),
+                display_name: None,
+                status: StageStatus::Succeeded,
+                jobs: vec![JobResult {
+                    job_name: "Compile".to_string(),
+                    display_name: None,
+                    status: JobStatus::Succeeded,
+                    steps: vec![StepResult {
+                        step_name: Some("make".to_string()),
+                        display_name: None,
+                        status,
+                        output: String::new(),
+                        error: None,
+                        duration: Duration::ZERO,
+                        exit_code: Some(0),
+                        outputs: HashMap::new(),
+                        substitutions: Vec::new(),
+                        summary: None,
+                        unsupported: Vec::new(),
+                        started_at: None,
+                        finished_at: None,
+                        env: Vec::new(),
+                    }],
+                    duration: Duration::ZERO,
+                    outputs: HashMap::new(),
+                    started_at: None,
+                    finished_at: None,
+                }],
+                duration: Duration::ZERO,
+                started_at: None,
+                finished_at: None,
+            }],
+            duration: Duration::ZERO,
+            success,
+            variables: HashMap::new(),
+            images: Default::default(),
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let clean = RunOutcome::from_result(&result(true, StepStatus::Succeeded));
+        let warned = RunOutcome::from_result(&result(true, StepStatus::SucceededWithIssues));
+        let failed = RunOutcome::from_result(&result(false, StepStatus::Failed));
+        assert_eq!(clean, RunOutcome::Succeeded { warnings: 0 });
+        assert_eq!(warned, RunOutcome::Succeeded { warnings: 1 });
+        assert_eq!(
+            RunOutcome::from_result(&result(true, StepStatus::Failed)),
+            RunOutcome::Succeeded { warnings: 1 }
+        );
+        assert_eq!(failed, RunOutcome::Failed);
+
+        let default = ExitPolicy::default();
+        assert_eq!(default.exit_code(clean), EXIT_SUCCESS);
+        assert_eq!(default.exit_code(warned), EXIT_SUCCESS);
+        assert_eq!(default.exit_code(failed), EXIT_PIPELINE_FAILED);
+        assert_eq!(default.exit_code(RunOutcome::Invalid), EXIT_INVALID);
+        assert_eq!(
+            default.exit_code(RunOutcome::Infrastructure),
+            EXIT_INFRASTRUCTURE
+        );
+
+        let strict = ExitPolicy {
+            fail_on_warnings: true,
+            ..Default::default()
+        };
+        assert_eq!(strict.exit_code(clean), EXIT_SUCCESS);
+        assert_eq!(strict.exit_code(warned), EXIT_PIPELINE_FAILED);
+
+        let report_only = ExitPolicy {
+            zero_on_failure: true,
+            fail_on_warnings: true,
+        };
+        assert_eq!(report_only.exit_code(warned), EXIT_SUCCESS);
+        assert_eq!(report_only.exit_code(failed), EXIT_SUCCESS);
+        assert_eq!(report_only.exit_code(RunOutcome::Invalid), EXIT_INVALID);
+        assert_eq!(
+            report_only.exit_code(RunOutcome::Infrastructure),
+            EXIT_INFRASTRUCTURE
+        );
+    }
+}
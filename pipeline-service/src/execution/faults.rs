@@ -0,0 +1,223 @@
+// Fault Injection
+// Forced step failures and delays, for checking conditions, retries and cleanup steps
+
+use crate::utils::wildcard_match;
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// A failure or delay forced on matching stages, jobs or steps
+///
+/// Names support `*` and `?` wildcards; steps match by name or display name.
+/// Omitted names match anything, so `{ job: Tests, ms: 30000 }` delays every
+/// job called `Tests`. In `roxid-test.yml`:
+///
+/// ```yaml
+/// faults:
+///   - step: Deploy
+///     exit: 1
+///   - step: Publish
+///     cancel: true
+///   - job: Tests
+///     ms: 30000
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fault {
+    pub stage: Option<String>,
+    pub job: Option<String>,
+    pub step: Option<String>,
+    /// Fail the matching steps with this exit code instead of running them;
+    /// without `step`, the first step of each matching job fails
+    pub exit: Option<i32>,
+    /// Cancel the matching steps instead of running them, as a stopped run
+    /// would; later steps only run if their condition allows `canceled()`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancel: bool,
+    /// Wait this many milliseconds before the matching steps start; without
+    /// `step`, before the jobs start, and without `job`, before the stages start
+    pub ms: Option<u64>,
+}
+
+impl Fault {
+    /// Parse `--inject-failure` (`step=Deploy,exit=1`) and `--inject-delay`
+    /// (`job=Tests,ms=30000`) values
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut fault = Fault::default();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", part))?;
+            let value = value.trim().to_string();
+            match key.trim() {
+                "stage" => fault.stage = Some(value),
+                "job" => fault.job = Some(value),
+                "step" => fault.step = Some(value),
+                "exit" => {
+                    fault.exit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid exit code '{}'", value))?,
+                    )
+                }
+                "cancel" => {
+                    fault.cancel = value
+                        .parse()
+                        .map_err(|_| format!("invalid cancel '{}' (true or false)", value))?
+                }
+                "ms" => {
+                    fault.ms = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid delay '{}'", value))?,
+                    )
+                }
+                other => {
+                    return Err(format!(
+                        "unknown key '{}' (expected stage, job, step, exit, cancel or ms)",
+                        other
+                    ))
+                }
+            }
+        }
+        if fault.stage.is_none() && fault.job.is_none() && fault.step.is_none() {
+            return Err(format!("'{}' names no stage, job or step", spec));
+        }
+        Ok(fault)
+    }
+
+    /// Matches the step; without `step`, the first step of each matching job
+    fn hits_step(
+        &self,
+        stage: &str,
+        job: &str,
+        step_index: usize,
+        step_names: &[Option<&str>],
+    ) -> bool {
+        let step_matches = match &self.step {
+            Some(_) => Fault::matches(&self.step, step_names),
+            None => step_index == 0,
+        };
+        step_matches
+            && Fault::matches(&self.stage, &[Some(stage)])
+            && Fault::matches(&self.job, &[Some(job)])
+    }
+
+    fn matches(pattern: &Option<String>, names: &[Option<&str>]) -> bool {
+        match pattern {
+            None => true,
+            Some(pattern) => names
+                .iter()
+                .flatten()
+                .any(|name| wildcard_match(pattern, name)),
+        }
+    }
+}
+
+/// Exit code forced on a step, if any fault fails it
+pub fn step_failure(
+    faults: &[Fault],
+    stage: &str,
+    job: &str,
+    step_index: usize,
+    step_names: &[Option<&str>],
+) -> Option<i32> {
+    faults
+        .iter()
+        .filter(|fault| fault.hits_step(stage, job, step_index, step_names))
+        .find_map(|fault| fault.exit)
+}
+
+/// Whether a fault cancels the step
+pub fn step_canceled(
+    faults: &[Fault],
+    stage: &str,
+    job: &str,
+    step_index: usize,
+    step_names: &[Option<&str>],
+) -> bool {
+    faults
+        .iter()
+        .any(|fault| fault.cancel && fault.hits_step(stage, job, step_index, step_names))
+}
+
+/// Delay before a stage starts
+pub fn stage_delay(faults: &[Fault], stage: &str) -> Duration {
+    total(faults.iter().filter(|fault| {
+        fault.job.is_none() && fault.step.is_none() && Fault::matches(&fault.stage, &[Some(stage)])
+    }))
+}
+
+/// Delay before a job starts
+pub fn job_delay(faults: &[Fault], stage: &str, job: &str) -> Duration {
+    total(faults.iter().filter(|fault| {
+        fault.job.is_some()
+            && fault.step.is_none()
+            && Fault::matches(&fault.stage, &[Some(stage)])
+            && Fault::matches(&fault.job, &[Some(job)])
+    }))
+}
+
+/// Delay before a step starts
+pub fn step_delay(
+    faults: &[Fault],
+    stage: &str,
+    job: &str,
+    step_names: &[Option<&str>],
+) -> Duration {
+    total(faults.iter().filter(|fault| {
+        fault.step.is_some()
+            && Fault::matches(&fault.stage, &[Some(stage)])
+            && Fault::matches(&fault.job, &[Some(job)])
+            && Fault::matches(&fault.step, step_names)
+    }))
+}
+
+fn total<'a>(faults: impl Iterator<Item = &'a Fault>) -> Duration {
+    faults
+        .filter_map(|fault| fault.ms)
+        .map(Duration::from_millis)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_match_faults() {
+        let faults = vec![
+            Fault::parse("step=Deploy*,exit=3").unwrap(),
+            Fault::parse("job=Tests, ms=250").unwrap(),
+            Fault::parse("stage=Build,job=Lint,exit=1").unwrap(),
+            Fault::parse("step=Publish,cancel=true").unwrap(),
+        ];
+        assert!(Fault::parse("step=A,cancel=maybe").is_err());
+        assert!(Fault::parse("exit=1").is_err());
+        assert!(Fault::parse("step=A,code=1").is_err());
+        assert!(Fault::parse("step=A,ms=soon").is_err());
+
+        let deploy = [Some("deploy_app"), Some("Deploy app")];
+        assert_eq!(step_failure(&faults, "Ship", "Run", 4, &deploy), Some(3));
+        assert_eq!(step_failure(&faults, "Build", "Lint", 0, &[None]), Some(1));
+        assert_eq!(step_failure(&faults, "Build", "Lint", 1, &[None]), None);
+        assert_eq!(step_failure(&faults, "Build", "Tests", 0, &[None]), None);
+        assert!(step_canceled(&faults, "Ship", "Run", 2, &[Some("Publish")]));
+        assert!(!step_canceled(&faults, "Ship", "Run", 4, &deploy));
+        assert_eq!(
+            step_failure(&faults, "Ship", "Run", 2, &[Some("Publish")]),
+            None
+        );
+
+        assert_eq!(
+            job_delay(&faults, "Build", "Tests"),
+            Duration::from_millis(250)
+        );
+        assert_eq!(stage_delay(&faults, "Build"), Duration::ZERO);
+        assert_eq!(
+            step_delay(&faults, "Build", "Tests", &deploy),
+            Duration::ZERO
+        );
+    }
+}
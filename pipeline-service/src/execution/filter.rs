@@ -0,0 +1,357 @@
+// Pipeline Filter
+// Prunes stages, jobs and steps before execution for partial runs
+
+use crate::execution::graph::GraphError;
+use crate::parser::models::{DeploymentHooks, Job, Pipeline, Step};
+use crate::utils::wildcard_match;
+
+use std::collections::{HashMap, HashSet};
+
+/// Selects the part of a pipeline to run
+///
+/// Patterns support `*` and `?` wildcards. Job patterns containing a `.` are
+/// matched against `Stage.Job`, others against the job name alone.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineFilter {
+    /// Stages to run (all stages when empty)
+    pub stages: Vec<String>,
+    /// Jobs to run (all jobs of the selected stages when empty)
+    pub jobs: Vec<String>,
+    /// Steps to leave out, matched against the step name and display name
+    pub skip_steps: Vec<String>,
+    /// Steps to leave out by their `tags`
+    pub skip_tags: Vec<String>,
+    /// Also run excluded stages and jobs that selected ones depend on
+    pub include_dependencies: bool,
+}
+
+impl PipelineFilter {
+    /// Whether the filter leaves the pipeline unchanged
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+            && self.jobs.is_empty()
+            && self.skip_steps.is_empty()
+            && self.skip_tags.is_empty()
+    }
+
+    /// Prune the pipeline down to the selected stages, jobs and steps
+    ///
+    /// Explicit `dependsOn` references to excluded stages or jobs are an
+    /// error, because their outputs would not exist, unless
+    /// `include_dependencies` is set, in which case they are pulled back in.
+    /// Implicit ordering (no `dependsOn`) is re-derived from the nodes that
+    /// remain.
+    pub fn apply(&self, mut pipeline: Pipeline) -> Result<Pipeline, GraphError> {
+        if self.is_empty() {
+            return Ok(pipeline);
+        }
+
+        let stage_names: Vec<String> = pipeline
+            .stages
+            .iter()
+            .map(|s| s.stage.clone().unwrap_or_default())
+            .collect();
+
+        // Selected job names per stage; a stage is selected when it has an entry
+        let mut selected: HashMap<String, HashSet<String>> = HashMap::new();
+        for (stage, name) in pipeline.stages.iter().zip(&stage_names) {
+            if !self.stages.is_empty() && !matches_any(&self.stages, name) {
+                continue;
+            }
+            let jobs: HashSet<String> = stage
+                .jobs
+                .iter()
+                .map(job_name)
+                .filter(|job| self.job_selected(name, job))
+                .collect();
+            if !jobs.is_empty() {
+                selected.insert(name.clone(), jobs);
+            }
+        }
+
+        if selected.is_empty() {
+            return Err(GraphError::invalid_structure(
+                "no stages or jobs match the filter",
+            ));
+        }
+
+        let mut missing = Vec::new();
+        loop {
+            let mut added = false;
+
+            for (stage, name) in pipeline.stages.iter().zip(&stage_names) {
+                let Some(jobs) = selected.get(name).cloned() else {
+                    continue;
+                };
+
+                // Stage dependencies
+                for dep in stage.depends_on.as_vec() {
+                    if selected.contains_key(&dep) {
+                        continue;
+                    }
+                    if self.include_dependencies {
+                        if let Some(dep_stage) = stage_names
+                            .iter()
+                            .position(|n| *n == dep)
+                            .map(|i| &pipeline.stages[i])
+                        {
+                            let all_jobs = dep_stage.jobs.iter().map(job_name).collect();
+                            selected.insert(dep, all_jobs);
+                            added = true;
+                        }
+                    } else {
+                        missing.push(format!(
+                            "stage '{}' depends on stage '{}', which is excluded",
+                            name, dep
+                        ));
+                    }
+                }
+
+                // Job dependencies within the stage
+                for job in &stage.jobs {
+                    let job_id = job_name(job);
+                    if !jobs.contains(&job_id) {
+                        continue;
+                    }
+                    for dep in job.depends_on.as_vec() {
+                        if jobs.contains(&dep) {
+                            continue;
+                        }
+                        if self.include_dependencies {
+                            if let Some(stage_jobs) = selected.get_mut(name) {
+                                added |= stage_jobs.insert(dep);
+                            }
+                        } else {
+                            missing.push(format!(
+                                "job '{}.{}' depends on job '{}', which is excluded",
+                                name, job_id, dep
+                            ));
+                        }
+                    }
+                }
+            }
+
+            if !added || !missing.is_empty() {
+                break;
+            }
+        }
+
+        if !missing.is_empty() {
+            missing.dedup();
+            return Err(GraphError::unknown_dependency(format!(
+                "{} (select it as well or use --with-deps)",
+                missing.join("; ")
+            )));
+        }
+
+        let stages = std::mem::take(&mut pipeline.stages);
+        for (mut stage, name) in stages.into_iter().zip(stage_names) {
+            let Some(jobs) = selected.get(&name) else {
+                continue;
+            };
+            stage.jobs = std::mem::take(&mut stage.jobs)
+                .into_iter()
+                .filter(|job| jobs.contains(&job_name(job)))
+                .map(|mut job| {
+                    self.skip_job_steps(&mut job);
+                    job
+                })
+                .collect();
+            pipeline.stages.push(stage);
+        }
+
+        Ok(pipeline)
+    }
+
+    fn job_selected(&self, stage: &str, job: &str) -> bool {
+        self.jobs.is_empty()
+            || self.jobs.iter().any(|pattern| {
+                if pattern.contains('.') {
+                    wildcard_match(pattern, &format!("{}.{}", stage, job))
+                } else {
+                    wildcard_match(pattern, job)
+                }
+            })
+    }
+
+    fn skip_job_steps(&self, job: &mut Job) {
+        if self.skip_steps.is_empty() && self.skip_tags.is_empty() {
+            return;
+        }
+        self.retain_steps(&mut job.steps);
+        if let Some(strategy) = &mut job.strategy {
+            if let Some(hooks) = &mut strategy.run_once {
+                self.retain_hook_steps(hooks);
+            }
+            if let Some(rolling) = &mut strategy.rolling {
+                self.retain_hook_steps(&mut rolling.hooks);
+            }
+            if let Some(canary) = &mut strategy.canary {
+                self.retain_hook_steps(&mut canary.hooks);
+            }
+        }
+    }
+
+    fn retain_hook_steps(&self, hooks: &mut DeploymentHooks) {
+        for hook in [
+            &mut hooks.pre_deploy,
+            &mut hooks.deploy,
+            &mut hooks.route_traffic,
+            &mut hooks.post_route_traffic,
+            &mut hooks.on_failure,
+            &mut hooks.on_success,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.retain_steps(&mut hook.steps);
+        }
+    }
+
+    fn retain_steps(&self, steps: &mut Vec<Step>) {
+        steps.retain(|step| {
+            let skipped_name = [step.name.as_deref(), step.display_name.as_deref()]
+                .into_iter()
+                .flatten()
+                .any(|name| matches_any(&self.skip_steps, name));
+            let skipped_tag = step.tags.iter().any(|tag| self.skip_tags.contains(tag));
+            !skipped_name && !skipped_tag
+        });
+    }
+}
+
+/// Job name as used by the execution graph
+fn job_name(job: &Job) -> String {
+    job.identifier().unwrap_or("unknown").to_string()
+}
+
+fn matches_any(patterns: &[String], text: &str) -> bool {
+    patterns.iter().any(|p| wildcard_match(p, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::graph::GraphErrorKind;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    const PIPELINE: &str = r#"
+stages:
+  - stage: Build
+    jobs:
+      - job: Compile
+        steps:
+          - script: echo compile
+            displayName: Compile
+          - script: echo publish
+            displayName: Publish artifacts
+      - job: Lint
+        steps:
+          - script: echo lint
+  - stage: Test
+    dependsOn: Build
+    jobs:
+      - job: Unit
+        steps:
+          - script: echo unit
+      - job: Report
+        dependsOn: Unit
+        steps:
+          - script: echo report
+  - stage: Docs
+    dependsOn: []
+    jobs:
+      - job: Publish
+        steps:
+          - script: echo docs
+"#;
+
+    fn pipeline() -> Pipeline {
+        normalize_pipeline(AzureParser::parse(PIPELINE).unwrap())
+    }
+
+    fn layout(pipeline: &Pipeline) -> Vec<String> {
+        pipeline
+            .stages
+            .iter()
+            .flat_map(|s| {
+                s.jobs.iter().map(move |j| {
+                    format!(
+                        "{}.{}",
+                        s.stage.as_deref().unwrap_or_default(),
+                        j.identifier().unwrap_or_default()
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_stage_and_job() {
+        let filter = PipelineFilter {
+            stages: vec!["Build".into()],
+            jobs: vec!["Comp*".into()],
+            ..Default::default()
+        };
+        let filtered = filter.apply(pipeline()).unwrap();
+        assert_eq!(layout(&filtered), vec!["Build.Compile"]);
+
+        let filter = PipelineFilter {
+            jobs: vec!["Docs.*".into()],
+            ..Default::default()
+        };
+        let filtered = filter.apply(pipeline()).unwrap();
+        assert_eq!(layout(&filtered), vec!["Docs.Publish"]);
+
+        let filter = PipelineFilter {
+            stages: vec!["Nope".into()],
+            ..Default::default()
+        };
+        let err = filter.apply(pipeline()).unwrap_err();
+        assert_eq!(err.kind, GraphErrorKind::InvalidStructure);
+    }
+
+    #[test]
+    fn test_filter_excluded_dependencies() {
+        let filter = PipelineFilter {
+            stages: vec!["Test".into()],
+            jobs: vec!["Report".into()],
+            ..Default::default()
+        };
+        let err = filter.apply(pipeline()).unwrap_err();
+        assert_eq!(err.kind, GraphErrorKind::UnknownDependency);
+        assert!(err
+            .message
+            .contains("stage 'Test' depends on stage 'Build'"));
+        assert!(err
+            .message
+            .contains("job 'Test.Report' depends on job 'Unit'"));
+
+        let filter = PipelineFilter {
+            include_dependencies: true,
+            ..filter
+        };
+        let filtered = filter.apply(pipeline()).unwrap();
+        assert_eq!(
+            layout(&filtered),
+            vec!["Build.Compile", "Build.Lint", "Test.Unit", "Test.Report"]
+        );
+    }
+
+    #[test]
+    fn test_filter_skip_steps() {
+        let filter = PipelineFilter {
+            stages: vec!["Build".into()],
+            skip_steps: vec!["Publish*".into()],
+            ..Default::default()
+        };
+        let filtered = filter.apply(pipeline()).unwrap();
+        let steps: Vec<_> = filtered.stages[0].jobs[0]
+            .steps
+            .iter()
+            .filter_map(|s| s.display_name.clone())
+            .collect();
+        assert_eq!(steps, vec!["Compile"]);
+        assert_eq!(filtered.stages[0].jobs[1].steps.len(), 1);
+    }
+}
@@ -1,7 +1,12 @@
 // Execution Graph (DAG) Builder
 // Builds a directed acyclic graph from pipeline definition for execution ordering
 
-use crate::parser::models::{BoolOrExpression, DependsOn, Job, Pipeline, Stage, Variable};
+use crate::parser::models::{
+    BoolOrExpression, ComposeSpec, ContainerResource, DependsOn, Job, Pipeline, RepositoryResource,
+    Stage, Variable,
+};
+
+use crate::utils::closest_match;
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
@@ -63,6 +68,12 @@ pub struct ExecutionGraph {
     stage_indices: HashMap<String, usize>,
     /// Pipeline-level variables
     pub variables: Vec<Variable>,
+    /// Pipeline-level Docker Compose services
+    pub compose: Option<ComposeSpec>,
+    /// Repository resources available to `checkout:` steps
+    pub repositories: Vec<RepositoryResource>,
+    /// Container resources jobs and services refer to by alias
+    pub containers: Vec<ContainerResource>,
 }
 
 /// A node representing a stage in the execution graph
@@ -101,13 +112,19 @@ impl ExecutionGraph {
 
         for (i, stage) in stages.iter().enumerate() {
             let stage_name = stage.stage.clone().unwrap_or_default();
-            stage_indices.insert(stage_name.clone(), i);
+            if stage_indices.insert(stage_name.clone(), i).is_some() && stage.stage.is_some() {
+                return Err(GraphError::invalid_structure(format!(
+                    "stage '{}' is defined more than once{}",
+                    stage_name,
+                    from_template(stage.from_template.as_deref())
+                )));
+            }
 
             // Calculate stage dependencies
-            let dependencies = Self::calculate_stage_dependencies(stage, i, &stages);
+            let dependencies = Self::calculate_stage_dependencies(stage, i, &stages)?;
 
             // Build job nodes for this stage
-            let jobs = Self::build_job_nodes(&stage.jobs)?;
+            let jobs = Self::build_job_nodes(&stage_name, &stage.jobs)?;
 
             stage_nodes.push(StageNode {
                 stage: stage.clone(),
@@ -126,6 +143,17 @@ impl ExecutionGraph {
             stages: stage_nodes,
             stage_indices,
             variables: pipeline.variables.clone(),
+            compose: pipeline.compose.clone(),
+            repositories: pipeline
+                .resources
+                .as_ref()
+                .map(|r| r.repositories.clone())
+                .unwrap_or_default(),
+            containers: pipeline
+                .resources
+                .as_ref()
+                .map(|r| r.containers.clone())
+                .unwrap_or_default(),
         };
 
         // Validate the graph
@@ -155,6 +183,7 @@ impl ExecutionGraph {
                 parameters: HashMap::new(),
                 pool: pipeline.pool.clone(),
                 has_template_directives: false,
+                from_template: None,
             }]);
         }
 
@@ -180,7 +209,10 @@ impl ExecutionGraph {
                 template: None,
                 parameters: HashMap::new(),
                 environment: None,
+                compose: None,
+                resources: None,
                 has_template_directives: false,
+                from_template: None,
             };
 
             return Ok(vec![Stage {
@@ -195,6 +227,7 @@ impl ExecutionGraph {
                 parameters: HashMap::new(),
                 pool: pipeline.pool.clone(),
                 has_template_directives: false,
+                from_template: None,
             }]);
         }
 
@@ -207,26 +240,44 @@ impl ExecutionGraph {
         stage: &Stage,
         index: usize,
         all_stages: &[Stage],
-    ) -> Vec<String> {
+    ) -> Result<Vec<String>, GraphError> {
         match &stage.depends_on {
             DependsOn::Default => {
                 // Default: depends on the previous stage (if any)
                 if index > 0 {
-                    vec![all_stages[index - 1].stage.clone().unwrap_or_default()]
+                    Ok(vec![all_stages[index - 1]
+                        .stage
+                        .clone()
+                        .unwrap_or_default()])
                 } else {
-                    vec![]
+                    Ok(vec![])
                 }
             }
-            DependsOn::None => vec![],
-            DependsOn::Single(name) => vec![name.clone()],
-            DependsOn::Multiple(names) => names.clone(),
+            explicit => check_depends_on(
+                "stage",
+                stage.stage.as_deref().unwrap_or("unknown"),
+                explicit.as_vec(),
+                stage.from_template.as_deref(),
+            ),
         }
     }
 
     /// Build job nodes for a stage
-    fn build_job_nodes(jobs: &[Job]) -> Result<Vec<JobNode>, GraphError> {
+    fn build_job_nodes(stage: &str, jobs: &[Job]) -> Result<Vec<JobNode>, GraphError> {
         let mut job_nodes = Vec::with_capacity(jobs.len());
-        let job_names: HashSet<_> = jobs.iter().filter_map(|j| j.identifier()).collect();
+        let mut job_names = HashSet::new();
+        for job in jobs {
+            if let Some(name) = job.identifier() {
+                if !job_names.insert(name) {
+                    return Err(GraphError::invalid_structure(format!(
+                        "job '{}' is defined more than once in stage '{}'{}",
+                        name,
+                        stage,
+                        from_template(job.from_template.as_deref())
+                    )));
+                }
+            }
+        }
 
         for (i, job) in jobs.iter().enumerate() {
             let dependencies = Self::calculate_job_dependencies(job, i, jobs, &job_names)?;
@@ -261,18 +312,23 @@ impl ExecutionGraph {
                     vec![]
                 }
             }
-            DependsOn::None => vec![],
-            DependsOn::Single(name) => vec![name.clone()],
-            DependsOn::Multiple(names) => names.clone(),
+            explicit => check_depends_on(
+                "job",
+                job.identifier().unwrap_or("unknown"),
+                explicit.as_vec(),
+                job.from_template.as_deref(),
+            )?,
         };
 
         // Validate that all dependencies exist
         for dep in &deps {
             if !job_names.contains(dep.as_str()) {
                 return Err(GraphError::unknown_dependency(format!(
-                    "job '{}' depends on unknown job '{}'",
+                    "job '{}' depends on unknown job '{}'{}{}",
                     job.identifier().unwrap_or("unknown"),
-                    dep
+                    dep,
+                    from_template(job.from_template.as_deref()),
+                    did_you_mean(dep, job_names.iter().copied())
                 )));
             }
         }
@@ -293,9 +349,11 @@ impl ExecutionGraph {
             for dep in &stage_node.dependencies {
                 if !stage_names.contains(dep.as_str()) {
                     return Err(GraphError::unknown_dependency(format!(
-                        "stage '{}' depends on unknown stage '{}'",
+                        "stage '{}' depends on unknown stage '{}'{}{}",
                         stage_node.stage.stage.as_deref().unwrap_or("unknown"),
-                        dep
+                        dep,
+                        from_template(stage_node.stage.from_template.as_deref()),
+                        did_you_mean(dep, stage_names.iter().copied())
                     )));
                 }
             }
@@ -323,7 +381,12 @@ impl ExecutionGraph {
                 {
                     return Err(GraphError::cyclic(format!(
                         "circular dependency detected in stages: {}",
-                        cycle.join(" -> ")
+                        describe_cycle(&cycle, |from, to| {
+                            let implicit = self
+                                .get_stage(from)
+                                .is_some_and(|s| matches!(s.stage.depends_on, DependsOn::Default));
+                            describe_edge("stage", from, to, implicit)
+                        })
                     )));
                 }
             }
@@ -375,7 +438,12 @@ impl ExecutionGraph {
                     return Err(GraphError::cyclic(format!(
                         "circular dependency detected in jobs of stage '{}': {}",
                         stage.stage.stage.as_deref().unwrap_or("unknown"),
-                        cycle.join(" -> ")
+                        describe_cycle(&cycle, |from, to| {
+                            let implicit = stage
+                                .get_job(from)
+                                .is_some_and(|j| matches!(j.job.depends_on, DependsOn::Default));
+                            describe_edge("job", from, to, implicit)
+                        })
                     )));
                 }
             }
@@ -573,6 +641,97 @@ impl ExecutionGraph {
     }
 }
 
+/// ` (did you mean 'X'?)` when a candidate is close to `name`, otherwise empty
+/// Check an explicit `dependsOn` list and drop repeated names
+///
+/// Names are checked after templates are resolved, so an expression still in
+/// the list means it was never resolved.
+fn check_depends_on(
+    kind: &str,
+    name: &str,
+    deps: Vec<String>,
+    template: Option<&str>,
+) -> Result<Vec<String>, GraphError> {
+    let mut checked: Vec<String> = Vec::with_capacity(deps.len());
+    for dep in deps {
+        if dep.contains("${{") {
+            let hint = match template {
+                Some(_) => "",
+                None => {
+                    "; roxid resolves template expressions in templates, not in the pipeline file"
+                }
+            };
+            return Err(GraphError::invalid_structure(format!(
+                "{} '{}' has dependsOn '{}'{}, a template expression that wasn't resolved{}",
+                kind,
+                name,
+                dep,
+                from_template(template),
+                hint
+            )));
+        }
+        if dep.trim().is_empty() {
+            return Err(GraphError::invalid_structure(format!(
+                "{} '{}' has an empty name in dependsOn{}",
+                kind,
+                name,
+                from_template(template)
+            )));
+        }
+        if dep == name {
+            return Err(GraphError::cyclic(format!(
+                "{} '{}' depends on itself{}",
+                kind,
+                name,
+                from_template(template)
+            )));
+        }
+        if !checked.contains(&dep) {
+            checked.push(dep);
+        }
+    }
+    Ok(checked)
+}
+
+/// ` (from template 'file')` for stages and jobs expanded from a template
+fn from_template(template: Option<&str>) -> String {
+    template
+        .map(|file| format!(" (from template '{}')", file))
+        .unwrap_or_default()
+}
+
+fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    closest_match(name, candidates)
+        .map(|m| format!(" (did you mean '{}'?)", m))
+        .unwrap_or_default()
+}
+
+/// Render a cycle as `A → B → C → A`, followed by the dependency behind each edge
+///
+/// `path` is the DFS path ending in the node that closed the cycle; anything
+/// before that node's first occurrence led into the cycle and is dropped.
+fn describe_cycle(path: &[String], edge: impl Fn(&str, &str) -> String) -> String {
+    let start = path
+        .last()
+        .and_then(|closing| path.iter().position(|n| n == closing))
+        .unwrap_or(0);
+    let cycle = &path[start..];
+
+    let edges: Vec<String> = cycle.windows(2).map(|w| edge(&w[0], &w[1])).collect();
+    format!("{} ({})", cycle.join(" → "), edges.join("; "))
+}
+
+fn describe_edge(kind: &str, from: &str, to: &str, implicit: bool) -> String {
+    if implicit {
+        format!(
+            "{} '{}' implicitly depends on the preceding {} '{}'",
+            kind, from, kind, to
+        )
+    } else {
+        format!("{} '{}' has dependsOn '{}'", kind, from, to)
+    }
+}
+
 impl StageNode {
     /// Get a job by name
     pub fn get_job(&self, name: &str) -> Option<&JobNode> {
@@ -604,6 +763,7 @@ mod tests {
             parameters: HashMap::new(),
             pool: None,
             has_template_directives: false,
+            from_template: None,
         }
     }
 
@@ -628,7 +788,10 @@ mod tests {
             template: None,
             parameters: HashMap::new(),
             environment: None,
+            compose: None,
+            resources: None,
             has_template_directives: false,
+            from_template: None,
         }
     }
 
@@ -696,6 +859,37 @@ mod tests {
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert_eq!(err.kind, GraphErrorKind::CyclicDependency);
+        assert!(err.message.contains("A → C → B → A"), "{}", err.message);
+        assert!(err.message.contains("stage 'A' has dependsOn 'C'"));
+    }
+
+    #[test]
+    fn test_cycle_path_excludes_lead_in_and_marks_implicit_edges() {
+        let pipeline = make_pipeline_with_stages(vec![
+            make_stage("Setup", DependsOn::None),
+            make_stage("A", DependsOn::Multiple(vec!["Setup".into(), "C".into()])),
+            make_stage("B", DependsOn::Default),
+            make_stage("C", DependsOn::Default),
+        ]);
+
+        let err = ExecutionGraph::from_pipeline(&pipeline).unwrap_err();
+        assert!(
+            err.message
+                .ends_with("A → C → B → A (stage 'A' has dependsOn 'C'; stage 'C' implicitly depends on the preceding stage 'B'; stage 'B' implicitly depends on the preceding stage 'A')"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_unknown_dependency_suggestion() {
+        let pipeline = make_pipeline_with_stages(vec![
+            make_stage("Build", DependsOn::None),
+            make_stage("Test", DependsOn::Single("Biuld".to_string())),
+        ]);
+
+        let err = ExecutionGraph::from_pipeline(&pipeline).unwrap_err();
+        assert!(err.message.ends_with("(did you mean 'Build'?)"));
     }
 
     #[test]
@@ -711,6 +905,59 @@ mod tests {
         assert_eq!(err.kind, GraphErrorKind::UnknownDependency);
     }
 
+    #[test]
+    fn test_invalid_depends_on() {
+        let error = |stages: Vec<Stage>| {
+            ExecutionGraph::from_pipeline(&make_pipeline_with_stages(stages))
+                .unwrap_err()
+                .message
+        };
+
+        let mut deploy = make_stage("Deploy", DependsOn::Single("Deploy".to_string()));
+        deploy.from_template = Some("templates/deploy.yml".to_string());
+        assert_eq!(
+            error(vec![deploy]),
+            "stage 'Deploy' depends on itself (from template 'templates/deploy.yml')"
+        );
+
+        assert_eq!(
+            error(vec![
+                make_stage("Build", DependsOn::None),
+                make_stage("Build", DependsOn::None),
+            ]),
+            "stage 'Build' is defined more than once"
+        );
+
+        let unresolved = error(vec![make_stage(
+            "Deploy",
+            DependsOn::Single("${{ parameters.after }}".to_string()),
+        )]);
+        assert!(unresolved.starts_with(
+            "stage 'Deploy' has dependsOn '${{ parameters.after }}', a template expression"
+        ));
+
+        let mut stage = make_stage("Build", DependsOn::None);
+        stage.jobs = vec![
+            make_job("Compile", DependsOn::None),
+            make_job("Compile", DependsOn::None),
+        ];
+        assert_eq!(
+            error(vec![stage]),
+            "job 'Compile' is defined more than once in stage 'Build'"
+        );
+
+        // Names repeated by merged template lists are kept once
+        let graph = ExecutionGraph::from_pipeline(&make_pipeline_with_stages(vec![
+            make_stage("Build", DependsOn::None),
+            make_stage(
+                "Deploy",
+                DependsOn::Multiple(vec!["Build".to_string(), "Build".to_string()]),
+            ),
+        ]))
+        .unwrap();
+        assert_eq!(graph.stages[1].dependencies, ["Build"]);
+    }
+
     #[test]
     fn test_jobs_within_stage() {
         let mut stage = make_stage("Build", DependsOn::None);
@@ -767,11 +1014,14 @@ mod tests {
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Script(ScriptStep {
                     script: "echo hello".to_string(),
                     working_directory: None,
                     fail_on_stderr: false,
+                    shell: None,
                 }),
             }],
             ..Default::default()
@@ -0,0 +1,130 @@
+// Run History
+// Records of recent local runs, kept by run ID for 'roxid query'
+
+use crate::execution::run_record::RunRecord;
+
+use std::path::{Path, PathBuf};
+
+/// Runs kept; older records are removed when a new one is saved
+pub const MAX_RUNS: usize = 100;
+
+/// Name that stands for the most recent run
+pub const LAST_RUN: &str = "last";
+
+/// Directory of run records, one `<run-id>.json` per run
+///
+/// Run IDs are ULIDs, so file names sort in the order the runs started.
+#[derive(Debug, Clone)]
+pub struct RunHistory {
+    dir: PathBuf,
+}
+
+impl Default for RunHistory {
+    /// `~/.roxid/runs`
+    fn default() -> Self {
+        Self::new(
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".roxid")
+                .join("runs"),
+        )
+    }
+}
+
+impl RunHistory {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Save a run's record and drop the oldest beyond [`MAX_RUNS`]
+    pub fn save(&self, record: &RunRecord) -> Result<PathBuf, String> {
+        let run_id = record
+            .run_id
+            .as_deref()
+            .ok_or("the run record has no run ID")?;
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("failed to create {}: {}", self.dir.display(), e))?;
+        let path = self.dir.join(format!("{}.json", run_id));
+        record.save(&path)?;
+
+        let runs = self.run_ids()?;
+        for old in &runs[..runs.len().saturating_sub(MAX_RUNS)] {
+            let _ = std::fs::remove_file(self.dir.join(format!("{}.json", old)));
+        }
+        Ok(path)
+    }
+
+    /// Load a run by ID, unique ID prefix, or `last`
+    pub fn load(&self, run: &str) -> Result<RunRecord, String> {
+        let runs = self.run_ids()?;
+        let matches: Vec<&String> = if run == LAST_RUN {
+            runs.last().into_iter().collect()
+        } else {
+            let run = run.to_uppercase();
+            runs.iter().filter(|id| id.starts_with(&run)).collect()
+        };
+        match matches.as_slice() {
+            [id] => RunRecord::load(&self.dir.join(format!("{}.json", id))),
+            [] => Err(format!(
+                "no run '{}' in {} (runs are kept for the last {} 'roxid run's)",
+                run,
+                self.dir.display(),
+                MAX_RUNS
+            )),
+            _ => Err(format!(
+                "'{}' matches {} runs; give more of the run ID",
+                run,
+                matches.len()
+            )),
+        }
+    }
+
+    /// IDs of the saved runs, oldest first
+    pub fn run_ids(&self) -> Result<Vec<String>, String> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("failed to read {}: {}", self.dir.display(), e)),
+        };
+        let mut ids: Vec<String> = entries
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name().into_string().ok()?;
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_runs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let history = RunHistory::new(dir.path());
+        assert!(history.load(LAST_RUN).is_err());
+
+        let ids = ["01J0000000AAAA", "01J0000000BBBB", "01J0000001CCCC"];
+        for id in ids {
+            let record = RunRecord {
+                run_id: Some(id.to_string()),
+                ..Default::default()
+            };
+            history.save(&record).unwrap();
+        }
+
+        let load = |run: &str| history.load(run).map(|r| r.run_id.unwrap());
+        assert_eq!(load(LAST_RUN).unwrap(), "01J0000001CCCC");
+        assert_eq!(load("01j0000000a").unwrap(), "01J0000000AAAA");
+        assert!(load("01J0000000").unwrap_err().contains("matches 2 runs"));
+        assert!(load("01K").is_err());
+        assert!(history.save(&RunRecord::default()).is_err());
+    }
+}
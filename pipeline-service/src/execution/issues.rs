@@ -0,0 +1,183 @@
+// Step Issues
+// Errors and warnings steps report with ##vso[task.logissue] or ##[error]/##[warning], as GitHub Actions annotations
+
+/// Logging command that reports an error or warning, optionally at a source location
+const LOG_ISSUE_COMMAND: &str = "##vso[task.logissue";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+/// An error or warning reported in a step's output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepIssue {
+    pub severity: IssueSeverity,
+    pub message: String,
+    /// `sourcepath`, as the step wrote it
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub code: Option<String>,
+}
+
+impl StepIssue {
+    /// The issue a line of output reports:
+    /// `##vso[task.logissue type=error;sourcepath=src/app.ts;linenumber=3;columnnumber=7;code=TS2322]message`,
+    /// `##[error]message` or `##[warning]message`
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_start();
+        if let Some(message) = line.strip_prefix("##[error]") {
+            return Some(Self::new(IssueSeverity::Error, message));
+        }
+        if let Some(message) = line.strip_prefix("##[warning]") {
+            return Some(Self::new(IssueSeverity::Warning, message));
+        }
+
+        let head = line.get(..LOG_ISSUE_COMMAND.len())?;
+        if !head.eq_ignore_ascii_case(LOG_ISSUE_COMMAND) {
+            return None;
+        }
+        let (properties, message) = line[LOG_ISSUE_COMMAND.len()..].split_once(']')?;
+        let mut issue = Self::new(IssueSeverity::Error, message);
+        let mut severity = None;
+        for property in properties.split(';') {
+            let Some((key, value)) = property.trim().split_once('=') else {
+                continue;
+            };
+            let value = unescape(value.trim());
+            match key.trim().to_lowercase().as_str() {
+                "type" => {
+                    severity = match value.to_lowercase().as_str() {
+                        "error" => Some(IssueSeverity::Error),
+                        "warning" => Some(IssueSeverity::Warning),
+                        _ => None,
+                    }
+                }
+                "sourcepath" if !value.is_empty() => issue.file = Some(value),
+                "linenumber" => issue.line = value.parse().ok(),
+                "columnnumber" => issue.column = value.parse().ok(),
+                "code" if !value.is_empty() => issue.code = Some(value),
+                _ => {}
+            }
+        }
+        // Azure DevOps ignores logissue without a valid type
+        issue.severity = severity?;
+        Some(issue)
+    }
+
+    fn new(severity: IssueSeverity, message: &str) -> Self {
+        Self {
+            severity,
+            message: unescape(message.trim()),
+            file: None,
+            line: None,
+            column: None,
+            code: None,
+        }
+    }
+
+    /// The GitHub Actions workflow command that shows this issue as an
+    /// annotation, e.g. `::error file=src/app.ts,line=3,col=7,title=Build::message`
+    ///
+    /// `file` should be relative to the repository root for GitHub to place it
+    /// on the diff.
+    pub fn to_github_annotation(&self, title: Option<&str>) -> String {
+        let command = match self.severity {
+            IssueSeverity::Error => "error",
+            IssueSeverity::Warning => "warning",
+        };
+        let mut properties = Vec::new();
+        if let Some(file) = &self.file {
+            properties.push(format!("file={}", escape_property(file)));
+        }
+        if let Some(line) = self.line {
+            properties.push(format!("line={}", line));
+        }
+        if let Some(column) = self.column {
+            properties.push(format!("col={}", column));
+        }
+        if let Some(title) = title {
+            properties.push(format!("title={}", escape_property(title)));
+        }
+        let message = match &self.code {
+            Some(code) => format!("{}: {}", code, self.message),
+            None => self.message.clone(),
+        };
+        if properties.is_empty() {
+            format!("::{}::{}", command, escape_data(&message))
+        } else {
+            format!(
+                "::{} {}::{}",
+                command,
+                properties.join(","),
+                escape_data(&message)
+            )
+        }
+    }
+}
+
+/// Undo the escaping Azure DevOps logging commands use for values
+fn unescape(value: &str) -> String {
+    value
+        .replace("%0D", "\r")
+        .replace("%0A", "\n")
+        .replace("%3B", ";")
+        .replace("%5D", "]")
+        .replace("%AZP25", "%")
+}
+
+/// Escape a workflow command's message
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command's property value
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_issue() {
+        let issue = StepIssue::parse(
+            "##vso[task.logissue type=warning;sourcepath=src/app.ts;linenumber=3;columnnumber=7;code=TS2322;]Type %3Bmismatch",
+        )
+        .unwrap();
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert_eq!(issue.file.as_deref(), Some("src/app.ts"));
+        assert_eq!((issue.line, issue.column), (Some(3), Some(7)));
+        assert_eq!(issue.code.as_deref(), Some("TS2322"));
+        assert_eq!(issue.message, "Type ;mismatch");
+
+        let issue = StepIssue::parse("  ##[error]Build failed").unwrap();
+        assert_eq!(issue.severity, IssueSeverity::Error);
+        assert_eq!(issue.message, "Build failed");
+        assert_eq!(issue.file, None);
+
+        assert!(StepIssue::parse("##vso[task.logissue]no type").is_none());
+        assert!(StepIssue::parse("##vso[task.logissue type=info]no").is_none());
+        assert!(StepIssue::parse("error: plain output").is_none());
+    }
+
+    #[test]
+    fn test_github_annotation() {
+        let issue = StepIssue::parse(
+            "##vso[task.logissue type=error;sourcepath=src/a,b.rs;linenumber=3;code=E1]50% done%0Anext",
+        )
+        .unwrap();
+        assert_eq!(
+            issue.to_github_annotation(Some("Build/Compile: Test")),
+            "::error file=src/a%2Cb.rs,line=3,title=Build/Compile%3A Test::E1: 50%25 done%0Anext"
+        );
+        let issue = StepIssue::parse("##[warning]Deprecated").unwrap();
+        assert_eq!(issue.to_github_annotation(None), "::warning::Deprecated");
+    }
+}
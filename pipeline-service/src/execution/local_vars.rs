@@ -0,0 +1,118 @@
+// Local Variable Overrides
+// Developer-specific variables from .roxid/vars.yml, applied to every run in the repository
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Per-developer overrides, relative to the repository root; keep it out of git
+pub const LOCAL_VARS_FILE: &str = ".roxid/vars.yml";
+
+/// Variables from `.roxid/vars.yml`
+///
+/// ```yaml
+/// # Stand-ins for values the pipeline normally gets from a service connection
+/// registry: localhost:5000
+/// deployToStaging: false
+/// ```
+///
+/// They override the pipeline's own variables and `.roxid.toml` profile
+/// variables; `--var` overrides them.
+#[derive(Debug, Clone, Default)]
+pub struct LocalVariables {
+    /// File the variables were read from
+    pub path: PathBuf,
+    pub variables: BTreeMap<String, String>,
+}
+
+impl LocalVariables {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let variables =
+            Self::parse(&content).map_err(|e| format!("invalid {}: {}", path.display(), e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            variables,
+        })
+    }
+
+    /// Parse a mapping of variable names to scalar values
+    pub fn parse(content: &str) -> Result<BTreeMap<String, String>, String> {
+        let value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+        let mapping = match value {
+            serde_yaml::Value::Null => return Ok(BTreeMap::new()),
+            serde_yaml::Value::Mapping(mapping) => mapping,
+            _ => return Err("expected a mapping of variable names to values".to_string()),
+        };
+
+        let mut variables = BTreeMap::new();
+        for (name, value) in mapping {
+            let name = match name {
+                serde_yaml::Value::String(name) => name,
+                other => return Err(format!("variable names must be strings, got {:?}", other)),
+            };
+            let value = match value {
+                serde_yaml::Value::String(s) => s,
+                serde_yaml::Value::Bool(b) => b.to_string(),
+                serde_yaml::Value::Number(n) => n.to_string(),
+                serde_yaml::Value::Null => String::new(),
+                _ => {
+                    return Err(format!(
+                        "variable '{}' must be a string, number or bool",
+                        name
+                    ))
+                }
+            };
+            variables.insert(name, value);
+        }
+        Ok(variables)
+    }
+
+    /// The overrides for the repository at `repo_root`, if it has any
+    pub fn discover(repo_root: &Path) -> Result<Option<Self>, String> {
+        let path = repo_root.join(LOCAL_VARS_FILE);
+        if path.is_file() {
+            Self::load(&path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Add the overrides to `variables` without replacing ones already set
+    pub fn apply_to(&self, variables: &mut HashMap<String, String>) {
+        for (name, value) in &self.variables {
+            variables
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_variables() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(LocalVariables::discover(dir.path()).unwrap().is_none());
+
+        std::fs::create_dir(dir.path().join(".roxid")).unwrap();
+        std::fs::write(
+            dir.path().join(LOCAL_VARS_FILE),
+            "registry: localhost:5000\nreplicas: 2\ndeploy: false\n",
+        )
+        .unwrap();
+        let local = LocalVariables::discover(dir.path()).unwrap().unwrap();
+        assert_eq!(local.variables["replicas"], "2");
+        assert_eq!(local.variables["deploy"], "false");
+
+        let mut variables = HashMap::from([("registry".to_string(), "ghcr.io".to_string())]);
+        local.apply_to(&mut variables);
+        assert_eq!(variables["registry"], "ghcr.io");
+        assert_eq!(variables["replicas"], "2");
+
+        assert!(LocalVariables::parse("- a\n- b\n").is_err());
+        assert!(LocalVariables::parse("nested: { a: 1 }\n").is_err());
+    }
+}
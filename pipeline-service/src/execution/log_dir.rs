@@ -0,0 +1,238 @@
+// Step Log Files
+// Event sink that tees each step's output to its own file
+
+use crate::execution::bus::EventSink;
+use crate::execution::events::ExecutionEvent;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes the raw stdout/stderr of every step to a file under a log directory
+///
+/// Files are laid out as `<dir>/<stage>/<job>/<NN>-<step>.log`, where `NN` is
+/// the 1-based step index and names are reduced to filesystem-safe
+/// characters. Matrix instances get their own job directory
+/// (`<job>.<instance>`). Re-running into the same directory overwrites the
+/// previous logs for the same steps.
+pub struct LogDirSink {
+    dir: PathBuf,
+    /// Matrix instance currently running for each `(stage, job)`
+    instances: HashMap<(String, String), String>,
+    /// Open log files keyed by `(stage, job, step_index)`
+    files: HashMap<(String, String, usize), BufWriter<File>>,
+}
+
+impl LogDirSink {
+    /// Create the sink, creating `dir` if needed
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            instances: HashMap::new(),
+            files: HashMap::new(),
+        })
+    }
+
+    /// Directory the logs are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the log file for a step
+    pub fn step_path(&self, stage: &str, job: &str, step_index: usize, step: &str) -> PathBuf {
+        self.dir
+            .join(sanitize(stage))
+            .join(sanitize(job))
+            .join(format!("{:02}-{}.log", step_index + 1, sanitize(step)))
+    }
+
+    fn job_dir_name(&self, stage: &str, job: &str) -> String {
+        match self.instances.get(&(stage.to_string(), job.to_string())) {
+            Some(instance) => format!("{}.{}", job, instance),
+            None => job.to_string(),
+        }
+    }
+
+    fn open(&mut self, stage: &str, job: &str, step_index: usize, step: &str) {
+        let path = self.step_path(stage, &self.job_dir_name(stage, job), step_index, step);
+        let file = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| File::create(&path));
+        if let Ok(file) = file {
+            self.files.insert(
+                (stage.to_string(), job.to_string(), step_index),
+                BufWriter::new(file),
+            );
+        }
+    }
+}
+
+impl EventSink for LogDirSink {
+    fn handle(&mut self, event: &ExecutionEvent) {
+        match event {
+            ExecutionEvent::JobStarted {
+                stage_name,
+                job_name,
+                matrix_instance: Some(instance),
+                ..
+            } => {
+                self.instances
+                    .insert((stage_name.clone(), job_name.clone()), instance.clone());
+            }
+            ExecutionEvent::JobCompleted {
+                stage_name,
+                job_name,
+                ..
+            } => {
+                self.instances
+                    .remove(&(stage_name.clone(), job_name.clone()));
+            }
+            ExecutionEvent::StepStarted {
+                stage_name,
+                job_name,
+                step_name,
+                display_name,
+                step_index,
+                ..
+            } => {
+                let step = display_name
+                    .as_deref()
+                    .or(step_name.as_deref())
+                    .unwrap_or("step");
+                self.open(stage_name, job_name, *step_index, step);
+            }
+            ExecutionEvent::StepOutput {
+                stage_name,
+                job_name,
+                step_name,
+                step_index,
+                output,
+                ..
+            } => {
+                let key = (stage_name.clone(), job_name.clone(), *step_index);
+                if !self.files.contains_key(&key) {
+                    let step = step_name.as_deref().unwrap_or("step");
+                    self.open(stage_name, job_name, *step_index, step);
+                }
+                if let Some(file) = self.files.get_mut(&key) {
+                    let _ = writeln!(file, "{}", output);
+                }
+            }
+            ExecutionEvent::StepCompleted {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            } => {
+                if let Some(mut file) =
+                    self.files
+                        .remove(&(stage_name.clone(), job_name.clone(), *step_index))
+                {
+                    let _ = file.flush();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self) {
+        for file in self.files.values_mut() {
+            let _ = file.flush();
+        }
+        self.files.clear();
+    }
+}
+
+/// Reduce a name to characters that are safe in a file name
+fn sanitize(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match sanitized.trim_matches('.') {
+        "" => "_".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::models::StepStatus;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_step_output_written_per_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = LogDirSink::new(dir.path().join("logs")).unwrap();
+
+        sink.handle(&ExecutionEvent::step_started(
+            "Build",
+            "Compile",
+            None,
+            Some("Run make/all".to_string()),
+            0,
+        ));
+        sink.handle(&ExecutionEvent::step_output(
+            "Build",
+            "Compile",
+            None,
+            0,
+            "compiling",
+            false,
+        ));
+        sink.handle(&ExecutionEvent::step_output(
+            "Build",
+            "Compile",
+            None,
+            0,
+            "warning: x",
+            true,
+        ));
+        sink.handle(&ExecutionEvent::step_completed(
+            "Build",
+            "Compile",
+            None,
+            0,
+            StepStatus::Succeeded,
+            Duration::ZERO,
+            Some(0),
+        ));
+        // Output without a start event still lands in a file
+        sink.handle(&ExecutionEvent::step_output(
+            "Build",
+            "Compile",
+            Some("tail".to_string()),
+            1,
+            "done",
+            false,
+        ));
+        sink.finish();
+
+        let log =
+            std::fs::read_to_string(dir.path().join("logs/Build/Compile/01-Run_make_all.log"))
+                .unwrap();
+        assert_eq!(log, "compiling\nwarning: x\n");
+        let log =
+            std::fs::read_to_string(dir.path().join("logs/Build/Compile/02-tail.log")).unwrap();
+        assert_eq!(log, "done\n");
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("Build & Test"), "Build___Test");
+        assert_eq!(sanitize("../etc"), "_etc");
+        assert_eq!(sanitize(""), "_");
+    }
+}
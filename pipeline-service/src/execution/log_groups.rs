@@ -0,0 +1,122 @@
+// Log Grouping
+// Recognizes collapsible section markers (##[group], ::group::) and progress updates in step output
+
+/// A single line of step output, classified by grouping markers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLine<'a> {
+    /// Start of a collapsible section with the given title
+    GroupStart(&'a str),
+    /// End of the current section
+    GroupEnd,
+    /// A `##vso[task.setprogress value=N]` update: how far along the step is,
+    /// and what it's doing
+    Progress { percent: u8, operation: &'a str },
+    /// Regular output text
+    Text(&'a str),
+}
+
+impl<'a> LogLine<'a> {
+    /// Classify a line of output.
+    ///
+    /// Supports the Azure DevOps `##[group]Title` / `##[endgroup]` markers and the
+    /// GitHub Actions `::group::Title` / `::endgroup::` workflow commands.
+    pub fn parse(line: &'a str) -> Self {
+        let trimmed = line.trim_start();
+
+        if let Some(title) = trimmed
+            .strip_prefix("##[group]")
+            .or_else(|| trimmed.strip_prefix("::group::"))
+        {
+            return LogLine::GroupStart(title.trim());
+        }
+
+        if trimmed.starts_with("##[endgroup]") || trimmed.starts_with("::endgroup::") {
+            return LogLine::GroupEnd;
+        }
+
+        if let Some(progress) = Self::parse_progress(trimmed) {
+            return progress;
+        }
+
+        LogLine::Text(line)
+    }
+
+    /// `##vso[task.setprogress value=75;]Uploading`; the command is matched
+    /// case-insensitively, like Azure DevOps does
+    fn parse_progress(line: &'a str) -> Option<Self> {
+        const COMMAND: &str = "##vso[task.setprogress";
+        let head = line.get(..COMMAND.len())?;
+        if !head.eq_ignore_ascii_case(COMMAND) {
+            return None;
+        }
+        let (properties, operation) = line[COMMAND.len()..].split_once(']')?;
+        let percent = properties.split(';').find_map(|property| {
+            let (key, value) = property.trim().split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case("value")
+                .then(|| value.trim().parse::<u32>().ok())
+                .flatten()
+        })?;
+        Some(LogLine::Progress {
+            percent: percent.min(100) as u8,
+            operation: operation.trim(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_azure_markers() {
+        assert_eq!(
+            LogLine::parse("##[group]Install dependencies"),
+            LogLine::GroupStart("Install dependencies")
+        );
+        assert_eq!(LogLine::parse("##[endgroup]"), LogLine::GroupEnd);
+    }
+
+    #[test]
+    fn test_parse_github_markers() {
+        assert_eq!(
+            LogLine::parse("  ::group::Build"),
+            LogLine::GroupStart("Build")
+        );
+        assert_eq!(LogLine::parse("::endgroup::"), LogLine::GroupEnd);
+    }
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(
+            LogLine::parse("##vso[task.setprogress value=75;]Upload Log"),
+            LogLine::Progress {
+                percent: 75,
+                operation: "Upload Log"
+            }
+        );
+        assert_eq!(
+            LogLine::parse("  ##vso[task.setProgress value = 250]"),
+            LogLine::Progress {
+                percent: 100,
+                operation: ""
+            }
+        );
+        assert_eq!(
+            LogLine::parse("##vso[task.setprogress value=half;]Upload"),
+            LogLine::Text("##vso[task.setprogress value=half;]Upload")
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_text() {
+        assert_eq!(
+            LogLine::parse("compiling roxid"),
+            LogLine::Text("compiling roxid")
+        );
+        assert_eq!(
+            LogLine::parse("echo ##[group] later"),
+            LogLine::Text("echo ##[group] later")
+        );
+    }
+}
@@ -0,0 +1,240 @@
+// Markdown Summary
+// Compact markdown report of a run (--summary-md), meant to be posted as a pull request comment
+
+use crate::execution::executor::ExecutionResult;
+use crate::parser::models::{JobStatus, StageStatus, StepResult, StepStatus};
+
+use std::time::Duration;
+
+/// Output lines kept from the end of a failed step
+pub const EXCERPT_LINES: usize = 20;
+
+/// Heading, a status table with a row per job, and the error and last
+/// output lines of each failed step
+pub fn run_markdown(pipeline: &str, result: &ExecutionResult) -> String {
+    let mut out = format!(
+        "### {} {} {} in {}\n\n",
+        if result.success { "✅" } else { "❌" },
+        cell(pipeline),
+        if result.success {
+            "succeeded"
+        } else {
+            "failed"
+        },
+        format_duration(result.duration)
+    );
+
+    out.push_str("| Stage | Job | Result | Duration |\n");
+    out.push_str("|-------|-----|--------|----------|\n");
+    for stage in &result.stages {
+        let stage_name = stage.display_name.as_deref().unwrap_or(&stage.stage_name);
+        if stage.jobs.is_empty() {
+            out.push_str(&format!(
+                "| {} | | {} | {} |\n",
+                cell(stage_name),
+                stage_badge(&stage.status),
+                format_duration(stage.duration)
+            ));
+        }
+        for job in &stage.jobs {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                cell(stage_name),
+                cell(job.display_name.as_deref().unwrap_or(&job.job_name)),
+                job_badge(&job.status),
+                format_duration(job.duration)
+            ));
+        }
+    }
+
+    for stage in &result.stages {
+        for job in &stage.jobs {
+            for (index, step) in job.steps.iter().enumerate() {
+                if step.status != StepStatus::Failed {
+                    continue;
+                }
+                let location = format!(
+                    "{} / {} / {}",
+                    stage.stage_name,
+                    job.job_name,
+                    step_label(step, index)
+                );
+                out.push('\n');
+                out.push_str(&failure_details(&location, step));
+            }
+        }
+    }
+    out
+}
+
+/// A collapsed section with a failed step's error and output excerpt
+fn failure_details(location: &str, step: &StepResult) -> String {
+    let exit = match step.exit_code {
+        Some(code) if code != 0 => format!(" (exit code {})", code),
+        _ => String::new(),
+    };
+    let mut excerpt: Vec<&str> = step.output.lines().collect();
+    if excerpt.len() > EXCERPT_LINES {
+        excerpt.drain(..excerpt.len() - EXCERPT_LINES);
+    }
+    let mut body = step.error.clone().unwrap_or_default();
+    if !excerpt.is_empty() {
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        body.push_str(&excerpt.join("\n"));
+    }
+
+    let mut out = format!(
+        "<details><summary>❌ {}{}</summary>\n\n",
+        cell(location),
+        exit
+    );
+    if !body.is_empty() {
+        out.push_str(&fenced(&body));
+    }
+    out.push_str("</details>\n");
+    out
+}
+
+fn step_label(step: &StepResult, index: usize) -> String {
+    step.display_name
+        .clone()
+        .or_else(|| step.step_name.clone())
+        .unwrap_or_else(|| format!("Step {}", index + 1))
+}
+
+fn stage_badge(status: &StageStatus) -> &'static str {
+    match status {
+        StageStatus::Succeeded => "✅ Succeeded",
+        StageStatus::SucceededWithIssues => "⚠️ Succeeded with issues",
+        StageStatus::Failed => "❌ Failed",
+        StageStatus::Canceled => "🚫 Canceled",
+        StageStatus::Skipped => "⏭️ Skipped",
+        StageStatus::Pending | StageStatus::Running => "⏳ Not finished",
+    }
+}
+
+fn job_badge(status: &JobStatus) -> &'static str {
+    match status {
+        JobStatus::Succeeded => "✅ Succeeded",
+        JobStatus::SucceededWithIssues => "⚠️ Succeeded with issues",
+        JobStatus::Failed => "❌ Failed",
+        JobStatus::Canceled => "🚫 Canceled",
+        JobStatus::Skipped => "⏭️ Skipped",
+        JobStatus::Pending | JobStatus::Running => "⏳ Not finished",
+    }
+}
+
+/// `1m 05s` or `3.21s`
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs >= 60.0 {
+        format!("{}m {:02}s", (secs / 60.0) as u64, (secs % 60.0) as u64)
+    } else {
+        format!("{:.2}s", secs)
+    }
+}
+
+/// Text safe to put in a table cell or summary line
+pub(crate) fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// A code block fenced with more backticks than `text` contains in a row
+pub(crate) fn fenced(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat((longest + 1).max(3));
+    format!("{}text\n{}\n{}\n", fence, text.trim_end(), fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::models::{JobResult, StageResult};
+    use std::collections::HashMap;
+
+    fn step(name: &str, status: StepStatus, output: &str) -> StepResult {
+        let exit_code = if status == StepStatus::Failed { 2 } else { 0 };
+        StepResult {
+            step_name: Some(name.to_string()),
+            display_name: None,
+            status,
+            output: output.to_string(),
+            error: None,
+            duration: Duration::ZERO,
+            exit_code: Some(exit_code),
+            outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_run_markdown() {
+        let output: Vec<String> = (1..=25).map(|i| format!("line {}", i)).collect();
+        let output = format!("{}\n```", output.join("\n"));
+        let result = ExecutionResult {
+            run_id: String::new(),
+            stages: vec![
+                StageResult {
+                    stage_name: "Build".to_string(),
+                    display_name: None,
+                    status: StageStatus::Failed,
+                    jobs: vec![JobResult {
+                        job_name: "Compile".to_string(),
+                        display_name: Some("Compile | release".to_string()),
+                        status: JobStatus::Failed,
+                        steps: vec![
+                            step("restore", StepStatus::Succeeded, "ok"),
+                            step("test", StepStatus::Failed, &output),
+                        ],
+                        duration: Duration::from_secs(65),
+                        outputs: HashMap::new(),
+                        started_at: None,
+                        finished_at: None,
+                    }],
+                    duration: Duration::from_secs(65),
+                    started_at: None,
+                    finished_at: None,
+                },
+                StageResult {
+                    stage_name: "Deploy".to_string(),
+                    display_name: None,
+                    status: StageStatus::Skipped,
+                    jobs: Vec::new(),
+                    duration: Duration::ZERO,
+                    started_at: None,
+                    finished_at: None,
+                },
+            ],
+            duration: Duration::from_millis(65_500),
+            success: false,
+            variables: HashMap::new(),
+            images: Default::default(),
+            started_at: None,
+            finished_at: None,
+        };
+
+        let markdown = run_markdown("ci", &result);
+        assert!(
+            markdown.starts_with("### ❌ ci failed in 1m 05s\n"),
+            "{}",
+            markdown
+        );
+        assert!(markdown.contains("| Build | Compile \\| release | ❌ Failed | 1m 05s |\n"));
+        assert!(markdown.contains("| Deploy | | ⏭️ Skipped | 0.00s |\n"));
+        assert!(markdown.contains("<summary>❌ Build / Compile / test (exit code 2)</summary>"));
+        assert!(!markdown.contains("restore"));
+        assert!(!markdown.contains("line 5\n"));
+        assert!(markdown.contains("````text\nline 7\n"), "{}", markdown);
+    }
+}
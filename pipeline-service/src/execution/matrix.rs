@@ -1,7 +1,7 @@
 // Matrix Strategy Expansion
 // Expands matrix strategies into concrete job instances
 
-use crate::parser::models::{MatrixStrategy, Strategy, Value};
+use crate::parser::models::{MatrixStrategy, Pipeline, Strategy, Value};
 
 use std::collections::HashMap;
 
@@ -128,6 +128,122 @@ impl MatrixExpander {
     }
 }
 
+/// Matrix instances to run, chosen on the command line
+#[derive(Debug, Clone, Default)]
+pub struct MatrixSelection {
+    /// Variable name and value pairs an instance must match; pairs naming the
+    /// same variable are alternatives. Names compare case-insensitively and
+    /// only apply to matrices that define them.
+    pub filters: Vec<(String, String)>,
+    /// Run only the first matching instance of each matrix, by instance name
+    pub first_instance: bool,
+}
+
+impl MatrixSelection {
+    /// Parse a `name=value` filter
+    pub fn parse_filter(text: &str) -> Result<(String, String), String> {
+        match text.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(format!(
+                "invalid matrix filter '{}', expected name=value (e.g. os=linux)",
+                text
+            )),
+        }
+    }
+
+    /// Whether every instance is kept
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty() && !self.first_instance
+    }
+
+    /// The instances of one matrix that the selection keeps, ordered by name
+    pub fn select(&self, mut instances: Vec<MatrixInstance>) -> Vec<MatrixInstance> {
+        instances.sort_by(|a, b| a.name.cmp(&b.name));
+        let defined: Vec<&(String, String)> = self
+            .filters
+            .iter()
+            .filter(|(name, _)| instances.iter().any(|i| lookup(i, name).is_some()))
+            .collect();
+
+        let mut selected: Vec<MatrixInstance> = instances
+            .into_iter()
+            .filter(|instance| {
+                defined.iter().all(|(name, _)| {
+                    let value = lookup(instance, name).map(Value::as_string);
+                    defined
+                        .iter()
+                        .filter(|(other, _)| other.eq_ignore_ascii_case(name))
+                        .any(|(_, wanted)| value.as_deref() == Some(wanted.as_str()))
+                })
+            })
+            .collect();
+        if self.first_instance {
+            selected.truncate(1);
+        }
+        selected
+    }
+
+    /// Check that each filter names a variable, and a value for it, that some
+    /// matrix in the pipeline has
+    pub fn validate(&self, pipeline: &Pipeline) -> Result<(), String> {
+        let instances: Vec<MatrixInstance> = pipeline
+            .stages
+            .iter()
+            .flat_map(|stage| &stage.jobs)
+            .chain(&pipeline.jobs)
+            .filter_map(|job| job.strategy.as_ref())
+            .filter(|strategy| strategy.matrix.is_some())
+            .flat_map(MatrixExpander::expand)
+            .collect();
+
+        for (name, value) in &self.filters {
+            let mut values: Vec<String> = instances
+                .iter()
+                .filter_map(|instance| lookup(instance, name))
+                .map(Value::as_string)
+                .collect();
+            if values.is_empty() {
+                let mut names: Vec<&String> =
+                    instances.iter().flat_map(|i| i.variables.keys()).collect();
+                names.sort();
+                names.dedup();
+                let known: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+                return Err(format!(
+                    "no matrix defines '{}' (matrix variables: {})",
+                    name,
+                    if known.is_empty() {
+                        "none".to_string()
+                    } else {
+                        known.join(", ")
+                    }
+                ));
+            }
+            if !values.contains(value) {
+                values.sort();
+                values.dedup();
+                return Err(format!(
+                    "no matrix instance has {}={} (values: {})",
+                    name,
+                    value,
+                    values.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A matrix variable of `instance`, looked up case-insensitively
+fn lookup<'a>(instance: &'a MatrixInstance, name: &str) -> Option<&'a Value> {
+    instance
+        .variables
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
 /// Builder for creating matrix configurations programmatically
 pub struct MatrixBuilder {
     instances: HashMap<String, HashMap<String, Value>>,
@@ -309,6 +425,55 @@ mod tests {
         assert!(instances.is_empty());
     }
 
+    #[test]
+    fn test_matrix_selection() {
+        let instances = || {
+            MatrixBuilder::new()
+                .add_instance(
+                    "linux_node18",
+                    HashMap::from([
+                        ("os".to_string(), Value::from("linux")),
+                        ("node".to_string(), Value::Number(18.0)),
+                    ]),
+                )
+                .add_instance(
+                    "linux_node20",
+                    HashMap::from([
+                        ("os".to_string(), Value::from("linux")),
+                        ("node".to_string(), Value::Number(20.0)),
+                    ]),
+                )
+                .add_instance(
+                    "mac_node20",
+                    HashMap::from([
+                        ("os".to_string(), Value::from("mac")),
+                        ("node".to_string(), Value::Number(20.0)),
+                    ]),
+                )
+                .build()
+        };
+        let names = |selected: Vec<MatrixInstance>| -> Vec<String> {
+            selected.into_iter().map(|i| i.name).collect()
+        };
+
+        let selection = MatrixSelection {
+            filters: vec![
+                MatrixSelection::parse_filter("OS=linux").unwrap(),
+                MatrixSelection::parse_filter("node=20").unwrap(),
+                MatrixSelection::parse_filter("arch=arm64").unwrap(),
+            ],
+            first_instance: false,
+        };
+        assert_eq!(names(selection.select(instances())), ["linux_node20"]);
+
+        let selection = MatrixSelection {
+            filters: vec![("os".into(), "linux".into()), ("os".into(), "mac".into())],
+            first_instance: true,
+        };
+        assert_eq!(names(selection.select(instances())), ["linux_node18"]);
+        assert!(MatrixSelection::parse_filter("linux").is_err());
+    }
+
     #[test]
     fn test_yaml_value_conversion() {
         let yaml_string = serde_yaml::Value::String("test".to_string());
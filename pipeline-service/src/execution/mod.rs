@@ -1,15 +1,85 @@
 // Execution Engine Module
 // Handles DAG construction, execution orchestration, and matrix expansion
 
+pub mod analysis;
+pub mod bus;
+pub mod cache_storage;
+pub mod concurrency;
+pub mod confirm;
 pub mod context;
+pub mod diagnostics;
+pub mod disk_quota;
 pub mod events;
 pub mod executor;
+pub mod exit_status;
+pub mod faults;
+pub mod filter;
 pub mod graph;
+pub mod history;
+pub mod issues;
+pub mod local_vars;
+pub mod log_dir;
+pub mod log_groups;
+pub mod markdown_summary;
 pub mod matrix;
+pub mod notification;
+pub mod parity;
+pub mod pipeline_cache;
+pub mod pools;
+pub mod preflight;
+pub mod process;
+pub mod profile;
+pub mod query;
+pub mod recording;
+pub mod rerun;
+pub mod resources;
+pub mod run_id;
+pub mod run_journal;
+pub mod run_parameters;
+pub mod run_record;
+pub mod scheduler;
+pub mod secret_provider;
+pub mod step_summary;
+pub mod watch;
 
 // Re-export key types
+pub use analysis::{JobEstimate, JobTimings, PipelineAnalysis};
+pub use bus::{ChannelSink, EventBus, EventBusHandle, EventSink};
+pub use cache_storage::{
+    CacheBackend, CacheSettings, CacheStorage, LocalCacheStorage, RemoteAuth, RemoteCacheStorage,
+};
+pub use concurrency::{ConcurrencyGroup, ConcurrencyLock, ConcurrencyPolicy};
+pub use confirm::{StepConfirmer, StepPreview};
 pub use context::RuntimeContext;
-pub use events::{ExecutionEvent, ProgressSender};
+pub use diagnostics::{Diagnostic, DiagnosticStatus};
+pub use disk_quota::{DiskGuard, DiskQuota, QuotaViolation};
+pub use events::{ExecutionEvent, OutputCounters, OverflowPolicy, ProgressSender};
 pub use executor::{ExecutionResult, PipelineExecutor};
+pub use exit_status::{ExitPolicy, InfrastructureError, RunOutcome};
+pub use faults::Fault;
+pub use filter::PipelineFilter;
 pub use graph::{ExecutionGraph, GraphError, JobNode, StageNode};
-pub use matrix::{MatrixExpander, MatrixInstance};
+pub use history::{RunHistory, LAST_RUN};
+pub use issues::{IssueSeverity, StepIssue};
+pub use local_vars::{LocalVariables, LOCAL_VARS_FILE};
+pub use log_dir::LogDirSink;
+pub use log_groups::LogLine;
+pub use matrix::{MatrixExpander, MatrixInstance, MatrixSelection};
+pub use notification::{notify_desktop, NotificationSettings};
+pub use parity::{ParityEntry, ParityReport};
+pub use pipeline_cache::{is_cache_task, CacheHit, CacheSave};
+pub use pools::{apply_pools, PoolAssignment, PoolBackend, PoolProfile};
+pub use preflight::{Preflight, Requirement};
+pub use profile::{ExecutionProfile, RoxidConfig, CONFIG_FILE};
+pub use query::Query;
+pub use recording::{Recordings, StepRecorder};
+pub use rerun::StageRerun;
+pub use resources::{Parallelism, ResourceLease, ResourcePool, Resources};
+pub use run_id::{correlation_id, new_run_id, RUN_ID_VARIABLE};
+pub use run_journal::{ActiveRun, Cleanup, JournalEntry, RunJournal};
+pub use run_parameters::RunParameters;
+pub use run_record::{ComparisonRow, RunComparison, RunRecord};
+pub use scheduler::ReadyQueue;
+pub use secret_provider::{referenced_groups, SecretProvider};
+pub use step_summary::{SummaryFile, STEP_SUMMARY_ENV};
+pub use watch::{WatchBatch, WatchConfig, WatchRule, Watcher};
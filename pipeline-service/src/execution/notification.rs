@@ -0,0 +1,123 @@
+// Desktop Notifications
+// Tells the user a run finished, through the desktop's own notifier (notify-send, osascript)
+
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// When finished runs raise a desktop notification (`[notifications]` in
+/// `.roxid.toml`)
+///
+/// ```toml
+/// [notifications]
+/// enabled = true
+/// min-duration-secs = 60
+/// on-failure = true
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields, default)]
+pub struct NotificationSettings {
+    /// Off unless turned on here or with `roxid run --notify`
+    pub enabled: bool,
+    /// Only runs that took at least this long
+    pub min_duration_secs: u64,
+    /// Failed runs regardless of how long they took
+    pub on_failure: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_duration_secs: 30,
+            on_failure: true,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Whether a run that ended this way is worth a notification
+    pub fn should_notify(&self, success: bool, duration: Duration) -> bool {
+        self.enabled
+            && ((!success && self.on_failure)
+                || duration >= Duration::from_secs(self.min_duration_secs))
+    }
+
+    /// Notify that `pipeline` finished, if the settings call for it
+    pub fn notify_run(&self, pipeline: &str, success: bool, duration: Duration) -> bool {
+        if !self.should_notify(success, duration) {
+            return false;
+        }
+        let (title, outcome) = if success {
+            ("roxid: pipeline succeeded", "succeeded in")
+        } else {
+            ("roxid: pipeline failed", "failed after")
+        };
+        notify_desktop(
+            title,
+            &format!("{} {} {:.1}s", pipeline, outcome, duration.as_secs_f64()),
+        )
+    }
+}
+
+/// Show a desktop notification; returns whether a notifier was available
+///
+/// Uses `notify-send` on Linux and `osascript` on macOS. Failures are
+/// ignored: a missing notifier shouldn't fail a run.
+pub fn notify_desktop(title: &str, body: &str) -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ));
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", "roxid", title, body]);
+        command
+    } else {
+        return false;
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_notify() {
+        let settings = NotificationSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(!settings.should_notify(true, Duration::from_secs(5)));
+        assert!(settings.should_notify(true, Duration::from_secs(30)));
+        assert!(settings.should_notify(false, Duration::from_secs(5)));
+
+        let long_only = NotificationSettings {
+            on_failure: false,
+            ..settings
+        };
+        assert!(!long_only.should_notify(false, Duration::from_secs(5)));
+        assert!(long_only.should_notify(false, Duration::from_secs(45)));
+
+        assert!(!NotificationSettings::default().should_notify(false, Duration::from_secs(600)));
+        let parsed: NotificationSettings =
+            toml::from_str("enabled = true\nmin-duration-secs = 5\n").unwrap();
+        assert_eq!(parsed.min_duration_secs, 5);
+        assert!(parsed.on_failure);
+    }
+}
@@ -0,0 +1,135 @@
+// Parity Report
+// Summarizes the constructs a run skipped or stubbed because roxid doesn't implement them
+
+use crate::execution::executor::ExecutionResult;
+use crate::parser::models::UnsupportedFeature;
+
+/// Unsupported constructs encountered during a run, in the order first seen
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParityReport {
+    pub entries: Vec<ParityEntry>,
+}
+
+/// One unsupported construct and the steps that used it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParityEntry {
+    pub feature: UnsupportedFeature,
+    /// `Stage/Job/step` of every step that used the construct
+    pub locations: Vec<String>,
+}
+
+impl ParityReport {
+    pub fn from_result(result: &ExecutionResult) -> Self {
+        let mut report = Self::default();
+        for stage in &result.stages {
+            for job in &stage.jobs {
+                for (i, step) in job.steps.iter().enumerate() {
+                    let step_name = step
+                        .display_name
+                        .clone()
+                        .or_else(|| step.step_name.clone())
+                        .unwrap_or_else(|| format!("step {}", i + 1));
+                    let location = format!("{}/{}/{}", stage.stage_name, job.job_name, step_name);
+                    for feature in &step.unsupported {
+                        report.record(feature, &location);
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    fn record(&mut self, feature: &UnsupportedFeature, location: &str) {
+        match self.entries.iter_mut().find(|e| &e.feature == feature) {
+            Some(entry) => entry.locations.push(location.to_string()),
+            None => self.entries.push(ParityEntry {
+                feature: feature.clone(),
+                locations: vec![location.to_string()],
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of distinct constructs that were degraded
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::models::{
+        JobResult, JobStatus, StageResult, StageStatus, StepResult, StepStatus, UnsupportedKind,
+    };
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn step(name: &str, unsupported: Vec<UnsupportedFeature>) -> StepResult {
+        StepResult {
+            step_name: Some(name.to_string()),
+            display_name: None,
+            status: StepStatus::Succeeded,
+            output: String::new(),
+            error: None,
+            duration: Duration::ZERO,
+            exit_code: Some(0),
+            outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported,
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_groups_features_by_location() {
+        let upload = UnsupportedFeature::new(UnsupportedKind::LoggingCommand, "artifact.upload");
+        let task = UnsupportedFeature::new(UnsupportedKind::Task, "AzureCLI@2");
+        let result = ExecutionResult {
+            run_id: String::new(),
+            stages: vec![StageResult {
+                stage_name: "Build".to_string(),
+                display_name: None,
+                status: StageStatus::Succeeded,
+                jobs: vec![JobResult {
+                    job_name: "Compile".to_string(),
+                    display_name: None,
+                    status: JobStatus::Succeeded,
+                    steps: vec![
+                        step("pack", vec![upload.clone()]),
+                        step("clean", Vec::new()),
+                        step("deploy", vec![task.clone(), upload.clone()]),
+                    ],
+                    duration: Duration::ZERO,
+                    outputs: HashMap::new(),
+                    started_at: None,
+                    finished_at: None,
+                }],
+                duration: Duration::ZERO,
+                started_at: None,
+                finished_at: None,
+            }],
+            duration: Duration::ZERO,
+            success: true,
+            variables: HashMap::new(),
+            images: Default::default(),
+            started_at: None,
+            finished_at: None,
+        };
+
+        let report = ParityReport::from_result(&result);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.entries[0].feature, upload);
+        assert_eq!(
+            report.entries[0].locations,
+            vec!["Build/Compile/pack", "Build/Compile/deploy"]
+        );
+        assert_eq!(report.entries[1].feature.to_string(), "task AzureCLI@2");
+    }
+}
@@ -0,0 +1,317 @@
+// Pipeline Caching
+// The Cache@2 task: fingerprints its key, restores a matching entry and saves the path after the job
+
+use crate::execution::cache_storage::CacheStorage;
+use crate::utils::wildcard_match;
+
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+use tokio::process::Command;
+
+/// Hex digits of a segment's hash kept in an entry name
+const SEGMENT_DIGITS: usize = 16;
+
+/// Whether `task` (`Name@version`) is the Cache task
+pub fn is_cache_task(task: &str) -> bool {
+    let name = task.split('@').next().unwrap_or_default();
+    name.eq_ignore_ascii_case("Cache") || name.eq_ignore_ascii_case("CacheBeta")
+}
+
+/// Whether a Cache@2 step found its entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheHit {
+    /// The entry for the key itself
+    Exact,
+    /// The newest entry matching one of `restoreKeys`
+    Inexact(String),
+    Miss,
+}
+
+impl CacheHit {
+    /// Value of the step's `cacheHitVar`, as Azure DevOps sets it
+    pub fn variable_value(&self) -> &'static str {
+        match self {
+            Self::Exact => "true",
+            Self::Inexact(_) => "inexact",
+            Self::Miss => "false",
+        }
+    }
+}
+
+/// A cache to save once the job has succeeded, because the step didn't find
+/// the entry for its exact key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheSave {
+    /// The key as written in the step, for messages
+    pub key: String,
+    /// Fingerprint of the key, naming the entry
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// The entry name for a Cache@2 `key`: one hash per `|`-separated segment
+///
+/// A quoted segment, or one that doesn't look like a path, is hashed as
+/// text. Anything else is a file or a glob (`**/package-lock.json`) relative
+/// to `working_dir`, hashed by the contents of the files it matches; it's an
+/// error if it matches none. Because each segment is hashed on its own, the
+/// name of a `restoreKeys` entry is a prefix of the names it should restore.
+pub fn fingerprint(key: &str, working_dir: &Path) -> Result<String, String> {
+    let mut hashes = Vec::new();
+    for segment in key.split('|').map(str::trim).filter(|s| !s.is_empty()) {
+        let mut hasher = Sha256::new();
+        match segment.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            Some(text) => hasher.update(format!("text:{}", text)),
+            None if !looks_like_path(segment) => hasher.update(format!("text:{}", segment)),
+            None => {
+                let files = matching_files(segment, working_dir);
+                if files.is_empty() {
+                    return Err(format!(
+                        "cache key segment '{}' matches no files (quote it to use it as text)",
+                        segment
+                    ));
+                }
+                for file in files {
+                    let contents = std::fs::read(working_dir.join(&file))
+                        .map_err(|e| format!("failed to read {}: {}", file, e))?;
+                    hasher.update(format!("file:{}:{:x}\n", file, Sha256::digest(&contents)));
+                }
+            }
+        }
+        let hash = format!("{:x}", hasher.finalize());
+        hashes.push(hash[..SEGMENT_DIGITS].to_string());
+    }
+    if hashes.is_empty() {
+        return Err("cache key is empty".to_string());
+    }
+    Ok(hashes.join("-"))
+}
+
+/// Azure DevOps treats segments with path separators, wildcards or a file
+/// extension as paths
+fn looks_like_path(segment: &str) -> bool {
+    segment.contains(['/', '\\', '*', '?', '.'])
+}
+
+/// Files under `working_dir` matching `pattern`, relative and sorted
+fn matching_files(pattern: &str, working_dir: &Path) -> Vec<String> {
+    let pattern = pattern.replace('\\', "/");
+    let pattern = pattern.trim_start_matches("./");
+    if !pattern.contains(['*', '?']) {
+        return if working_dir.join(pattern).is_file() {
+            vec![pattern.to_string()]
+        } else {
+            Vec::new()
+        };
+    }
+
+    // Only walk the directories before the first wildcard
+    let base: Vec<&str> = pattern
+        .split('/')
+        .take_while(|part| !part.contains(['*', '?']))
+        .collect();
+    let mut files = Vec::new();
+    let mut pending = vec![working_dir.join(base.join("/"))];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if entry.file_name() != ".git" {
+                    pending.push(path);
+                }
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(working_dir) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            // `**/` also matches files at the top
+            let matched = wildcard_match(pattern, &relative)
+                || pattern
+                    .strip_prefix("**/")
+                    .is_some_and(|rest| wildcard_match(rest, &relative));
+            if matched {
+                files.push(relative);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Restore the entry for `name`, or else the newest one matching a restore
+/// key, into `path`
+pub async fn restore(
+    storage: &dyn CacheStorage,
+    name: &str,
+    restore_prefixes: &[String],
+    path: &Path,
+) -> Result<CacheHit, String> {
+    // A fresh file per download: a failed checksum removes the last one
+    let mut staged = staging_archive()?;
+    let mut hit = CacheHit::Miss;
+    if storage.fetch(name, staged.path()).await? {
+        hit = CacheHit::Exact;
+    } else {
+        for prefix in restore_prefixes {
+            if let Some(found) = storage.latest_with_prefix(prefix).await? {
+                staged = staging_archive()?;
+                if storage.fetch(&found, staged.path()).await? {
+                    hit = CacheHit::Inexact(found);
+                    break;
+                }
+            }
+        }
+    }
+    if hit != CacheHit::Miss {
+        extract_archive(staged.path(), path).await?;
+    }
+    Ok(hit)
+}
+
+/// Save `save.path` as its entry; false if the entry was already saved
+pub async fn save(storage: &dyn CacheStorage, save: &CacheSave) -> Result<bool, String> {
+    if !save.path.is_dir() {
+        return Err(format!(
+            "{} doesn't exist, nothing to cache",
+            save.path.display()
+        ));
+    }
+    let staged = staging_archive()?;
+    create_archive(&save.path, staged.path()).await?;
+    storage.store(&save.name, staged.path()).await
+}
+
+/// A new, uniquely named file to build or download an archive in, removed
+/// when dropped
+fn staging_archive() -> Result<NamedTempFile, String> {
+    tempfile::Builder::new()
+        .prefix("roxid-cache-")
+        .suffix(".tar")
+        .tempfile()
+        .map_err(|e| format!("failed to create a temporary archive: {}", e))
+}
+
+async fn create_archive(dir: &Path, archive: &Path) -> Result<(), String> {
+    tar(Command::new("tar")
+        .arg("-cf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .arg("."))
+    .await
+}
+
+async fn extract_archive(archive: &Path, dir: &Path) -> Result<(), String> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    tar(Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir))
+    .await
+}
+
+async fn tar(command: &mut Command) -> Result<(), String> {
+    let output = command
+        .output()
+        .await
+        .map_err(|e| format!("failed to run tar: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::cache_storage::LocalCacheStorage;
+
+    #[test]
+    fn test_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("web")).unwrap();
+        std::fs::write(dir.path().join("package-lock.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("web/package-lock.json"), "{\"a\":1}").unwrap();
+
+        let key = fingerprint("npm | \"Linux\" | **/package-lock.json", dir.path()).unwrap();
+        let restore = fingerprint("npm | \"Linux\"", dir.path()).unwrap();
+        assert!(key.starts_with(&format!("{}-", restore)));
+        assert_eq!(key.split('-').count(), 3);
+
+        // Unquoted words are text; quoting doesn't change them
+        assert_eq!(
+            fingerprint("npm", dir.path()).unwrap(),
+            fingerprint("\"npm\"", dir.path()).unwrap()
+        );
+
+        // The files' contents are part of the key
+        std::fs::write(dir.path().join("web/package-lock.json"), "{\"a\":2}").unwrap();
+        let changed = fingerprint("npm | \"Linux\" | **/package-lock.json", dir.path()).unwrap();
+        assert_ne!(key, changed);
+        assert!(changed.starts_with(&restore));
+
+        let err = fingerprint("npm | yarn.lock", dir.path()).unwrap_err();
+        assert!(err.contains("'yarn.lock' matches no files"));
+        assert!(fingerprint(" | ", dir.path()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalCacheStorage::new(dir.path().join("store"));
+        let path = dir.path().join("deps");
+
+        let name = "aaaa-bbbb".to_string();
+        let miss = restore(&storage, &name, &["aaaa-".to_string()], &path)
+            .await
+            .unwrap();
+        assert_eq!(miss, CacheHit::Miss);
+
+        std::fs::create_dir_all(path.join("lib")).unwrap();
+        std::fs::write(path.join("lib/module.js"), "cached").unwrap();
+        let entry = CacheSave {
+            key: "npm | package-lock.json".to_string(),
+            name: name.clone(),
+            path: path.clone(),
+        };
+        assert!(save(&storage, &entry).await.unwrap());
+        assert!(!save(&storage, &entry).await.unwrap());
+
+        // Jobs saving the same key at once each build their own archive
+        let same_key = CacheSave {
+            name: "eeee-ffff".to_string(),
+            ..entry.clone()
+        };
+        let (first, second) = tokio::join!(save(&storage, &same_key), save(&storage, &same_key));
+        assert!(first.is_ok() && second.is_ok());
+
+        std::fs::remove_dir_all(&path).unwrap();
+        let hit = restore(&storage, &name, &[], &path).await.unwrap();
+        assert_eq!(hit, CacheHit::Exact);
+        assert_eq!(
+            std::fs::read_to_string(path.join("lib/module.js")).unwrap(),
+            "cached"
+        );
+
+        let other = dir.path().join("other");
+        let hit = restore(&storage, "aaaa-cccc", &["aaaa-".to_string()], &other)
+            .await
+            .unwrap();
+        assert_eq!(hit, CacheHit::Inexact(name));
+        assert!(other.join("lib/module.js").exists());
+        assert_eq!(hit.variable_value(), "inexact");
+    }
+}
@@ -0,0 +1,337 @@
+// Agent Pools
+// Resolves the pool each job asks for and maps it to how the job runs locally (.roxid.toml [pools])
+
+use crate::execution::profile::CONFIG_FILE;
+use crate::parser::models::{
+    ContainerRef, DeploymentHooks, Job, Pipeline, Pool, PoolDemands, PoolSpec, Stage, Step,
+};
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+
+/// Where a pool's jobs run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PoolBackend {
+    /// On the host, as if the pool were not configured
+    #[default]
+    Shell,
+    /// In a container started from the pool's `image`, unless the job names
+    /// its own `container:`
+    Container,
+}
+
+/// How jobs in a pool run locally (`[pools.name]`)
+///
+/// ```toml
+/// [pools.linux]
+/// default = true
+/// backend = "container"
+/// image = "ubuntu:22.04"
+/// env = { CI = "true" }
+/// demands = ["node"]
+/// ```
+///
+/// A pool is matched by its `name:`, or by `vmImage:` for hosted pools.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PoolProfile {
+    #[serde(default)]
+    pub backend: PoolBackend,
+    /// Image for the container backend
+    pub image: Option<String>,
+    /// Environment given to every step of the pool's jobs; a step's own
+    /// `env:` wins
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Tools the pool's jobs need, checked before the run like `demands:`
+    #[serde(default)]
+    pub demands: Vec<String>,
+    /// Use this pool for jobs that don't ask for one
+    #[serde(default)]
+    pub default: bool,
+}
+
+/// A job placed in a configured pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolAssignment {
+    /// `Stage/Job`
+    pub job: String,
+    pub pool: String,
+    pub backend: PoolBackend,
+}
+
+/// Check the `[pools]` of `.roxid.toml`
+pub fn validate_pools(pools: &HashMap<String, PoolProfile>) -> Result<(), String> {
+    let mut defaults: Vec<&str> = pools
+        .iter()
+        .filter(|(_, pool)| pool.default)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    defaults.sort();
+    if defaults.len() > 1 {
+        return Err(format!(
+            "only one pool can be the default, found {}",
+            defaults.join(", ")
+        ));
+    }
+    for (name, pool) in pools {
+        if pool.backend == PoolBackend::Container && pool.image.is_none() {
+            return Err(format!(
+                "pool '{}' uses the container backend but has no image",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The pool a job asks for: its own, else its stage's, else the pipeline's
+pub fn job_pool<'a>(pipeline: &'a Pipeline, stage: &'a Stage, job: &'a Job) -> Option<&'a Pool> {
+    job.pool
+        .as_ref()
+        .or(stage.pool.as_ref())
+        .or(pipeline.pool.as_ref())
+}
+
+/// A pool's `name:`, or the `vmImage:` of a hosted pool
+pub fn pool_name(pool: &Pool) -> Option<&str> {
+    match pool {
+        Pool::Name(name) => Some(name),
+        Pool::Full(spec) => spec.name.as_deref().or(spec.vm_image.as_deref()),
+    }
+}
+
+/// Place every job in the configured pool it resolves to
+///
+/// Jobs asking for a pool by `name:` that isn't configured are an error once
+/// any pool is; hosted pools (`vmImage:`) that aren't configured, and jobs
+/// without a pool, use the default pool if there is one. Nothing changes
+/// when no pools are configured.
+pub fn apply_pools(
+    mut pipeline: Pipeline,
+    pools: &HashMap<String, PoolProfile>,
+) -> Result<(Pipeline, Vec<PoolAssignment>), String> {
+    if pools.is_empty() {
+        return Ok((pipeline, Vec::new()));
+    }
+    let default = pools.iter().find(|(_, pool)| pool.default);
+
+    let mut resolved = Vec::new();
+    for stage in &pipeline.stages {
+        for job in &stage.jobs {
+            let job_path = format!(
+                "{}/{}",
+                stage.stage.as_deref().unwrap_or_default(),
+                job.identifier().unwrap_or("unknown")
+            );
+            let requested = job_pool(&pipeline, stage, job);
+            let configured = requested
+                .and_then(pool_name)
+                .and_then(|name| pools.get_key_value(name));
+            let chosen = match (configured, requested) {
+                (Some(pool), _) => Some(pool),
+                (
+                    None,
+                    Some(pool @ (Pool::Name(_) | Pool::Full(PoolSpec { name: Some(_), .. }))),
+                ) => {
+                    let mut names: Vec<&str> = pools.keys().map(String::as_str).collect();
+                    names.sort();
+                    return Err(format!(
+                        "job '{}' uses pool '{}', which isn't in {} [pools] (defined: {})",
+                        job_path,
+                        pool_name(pool).unwrap_or_default(),
+                        CONFIG_FILE,
+                        names.join(", ")
+                    ));
+                }
+                (None, _) => default,
+            };
+            resolved.push(chosen.map(|(name, profile)| (job_path, name.clone(), profile)));
+        }
+    }
+
+    let mut assignments = Vec::new();
+    let mut resolved = resolved.into_iter();
+    for stage in &mut pipeline.stages {
+        for job in &mut stage.jobs {
+            let Some((job_path, name, profile)) = resolved.next().flatten() else {
+                continue;
+            };
+            apply_profile(job, &name, profile);
+            assignments.push(PoolAssignment {
+                job: job_path,
+                pool: name,
+                backend: profile.backend,
+            });
+        }
+    }
+    Ok((pipeline, assignments))
+}
+
+fn apply_profile(job: &mut Job, name: &str, profile: &PoolProfile) {
+    if profile.backend == PoolBackend::Container && job.container.is_none() {
+        job.container = profile.image.clone().map(ContainerRef::Image);
+    }
+
+    if !profile.demands.is_empty() {
+        let vm_image = match &job.pool {
+            Some(Pool::Full(spec)) => spec.vm_image.clone(),
+            _ => None,
+        };
+        let mut demands = match &job.pool {
+            Some(Pool::Full(PoolSpec {
+                demands: Some(PoolDemands::List(list)),
+                ..
+            })) => list.clone(),
+            Some(Pool::Full(PoolSpec {
+                demands: Some(PoolDemands::Map(map)),
+                ..
+            })) => map.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+        demands.extend(profile.demands.iter().cloned());
+        job.pool = Some(Pool::Full(PoolSpec {
+            name: Some(name.to_string()),
+            vm_image,
+            demands: Some(PoolDemands::List(demands)),
+        }));
+    }
+
+    if !profile.env.is_empty() {
+        for_each_step(job, |step| {
+            for (key, value) in &profile.env {
+                step.env.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        });
+    }
+}
+
+/// Visit a job's steps, including those of deployment lifecycle hooks
+fn for_each_step(job: &mut Job, mut visit: impl FnMut(&mut Step)) {
+    job.steps.iter_mut().for_each(&mut visit);
+    let Some(strategy) = &mut job.strategy else {
+        return;
+    };
+    let hooks: [Option<&mut DeploymentHooks>; 3] = [
+        strategy.run_once.as_mut(),
+        strategy.rolling.as_mut().map(|rolling| &mut rolling.hooks),
+        strategy.canary.as_mut().map(|canary| &mut canary.hooks),
+    ];
+    for hooks in hooks.into_iter().flatten() {
+        for hook in [
+            &mut hooks.pre_deploy,
+            &mut hooks.deploy,
+            &mut hooks.route_traffic,
+            &mut hooks.post_route_traffic,
+            &mut hooks.on_failure,
+            &mut hooks.on_success,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            hook.steps.iter_mut().for_each(&mut visit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    fn pools(toml: &str) -> HashMap<String, PoolProfile> {
+        #[derive(Deserialize)]
+        struct Config {
+            pools: HashMap<String, PoolProfile>,
+        }
+        toml::from_str::<Config>(toml).unwrap().pools
+    }
+
+    #[test]
+    fn test_apply_pools() {
+        let yaml = r#"
+pool:
+  vmImage: ubuntu-latest
+stages:
+  - stage: Build
+    pool: builders
+    jobs:
+      - job: Compile
+        steps:
+          - script: make
+            env:
+              CI: "false"
+      - job: Lint
+        pool:
+          name: tools
+          demands: [npm]
+        steps:
+          - script: npm run lint
+  - stage: Test
+    jobs:
+      - job: Unit
+        steps:
+          - script: make test
+"#;
+        let pipeline = normalize_pipeline(AzureParser::parse(yaml).unwrap());
+        let configured = pools(
+            r#"
+[pools.builders]
+backend = "container"
+image = "rust:1.80"
+env = { CI = "true", CARGO_TERM_COLOR = "never" }
+
+[pools.tools]
+demands = ["node"]
+
+[pools.local]
+default = true
+"#,
+        );
+        validate_pools(&configured).unwrap();
+
+        let (pipeline, assignments) = apply_pools(pipeline, &configured).unwrap();
+        let placed: Vec<(&str, &str)> = assignments
+            .iter()
+            .map(|a| (a.job.as_str(), a.pool.as_str()))
+            .collect();
+        assert_eq!(
+            placed,
+            [
+                ("Build/Compile", "builders"),
+                ("Build/Lint", "tools"),
+                ("Test/Unit", "local"),
+            ]
+        );
+
+        let compile = &pipeline.stages[0].jobs[0];
+        assert!(
+            matches!(&compile.container, Some(ContainerRef::Image(image)) if image == "rust:1.80")
+        );
+        assert_eq!(compile.steps[0].env["CI"], "false");
+        assert_eq!(compile.steps[0].env["CARGO_TERM_COLOR"], "never");
+        let lint = &pipeline.stages[0].jobs[1];
+        assert!(matches!(
+            &lint.pool,
+            Some(Pool::Full(PoolSpec { demands: Some(PoolDemands::List(demands)), .. }))
+                if demands == &["npm", "node"]
+        ));
+        assert!(pipeline.stages[1].jobs[0].container.is_none());
+
+        let err = apply_pools(
+            normalize_pipeline(AzureParser::parse("pool: gpu\nsteps:\n  - script: x\n").unwrap()),
+            &configured,
+        )
+        .unwrap_err();
+        assert!(err.contains("uses pool 'gpu'"), "{}", err);
+        assert!(err.contains("defined: builders, local, tools"), "{}", err);
+
+        assert!(validate_pools(&pools("[pools.a]\nbackend = \"container\"\n")).is_err());
+        assert!(validate_pools(&pools(
+            "[pools.a]\ndefault = true\n[pools.b]\ndefault = true\n"
+        ))
+        .is_err());
+    }
+}
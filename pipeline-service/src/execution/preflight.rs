@@ -0,0 +1,238 @@
+// Tool Preflight
+// Finds the programs a pipeline needs (shells, git, docker, node, pool demands) before it runs
+
+use crate::execution::pools::job_pool;
+use crate::parser::models::{
+    collect_deployment_steps, CheckoutSource, Job, Pipeline, Pool, PoolDemands, Step, StepAction,
+};
+use crate::runners::shell::{find_executable, ShellSpec};
+
+use std::collections::{BTreeMap, HashMap};
+
+/// A program the pipeline runs, and where it is needed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    /// Executable looked up on PATH
+    pub tool: String,
+    /// Steps and jobs that need it, e.g. `Build/Test: Run tests`
+    pub used_by: Vec<String>,
+}
+
+impl Requirement {
+    pub fn is_available(&self) -> bool {
+        find_executable(&self.tool)
+    }
+
+    /// How to install the tool, for the tools roxid knows about
+    pub fn install_hint(&self) -> Option<&'static str> {
+        let hint = match self.tool.as_str() {
+            "sh" | "bash" => {
+                "install bash from your package manager; on Windows use Git Bash or WSL"
+            }
+            "pwsh" => "install PowerShell 7: https://aka.ms/install-powershell",
+            "git" => "install git: https://git-scm.com/downloads",
+            "docker" => "install Docker: https://docs.docker.com/get-docker/",
+            "node" => "install Node.js (used by most tasks): https://nodejs.org/",
+            "zsh" | "fish" => "install it from your package manager",
+            "nu" => "install Nushell: https://www.nushell.sh/",
+            "python" | "python3" => "install Python: https://www.python.org/downloads/",
+            _ => return None,
+        };
+        Some(hint)
+    }
+}
+
+/// The tools a resolved pipeline needs, checked before anything runs
+#[derive(Debug, Clone, Default)]
+pub struct Preflight {
+    /// Requirements sorted by tool name
+    pub requirements: Vec<Requirement>,
+}
+
+impl Preflight {
+    /// Collect the tools needed by `pipeline`
+    ///
+    /// `shells` are the custom shells for `script` steps. Steps with
+    /// `dryRun: true` are left out since their commands are only printed.
+    /// Pool demands without a condition (e.g. `npm`) are treated as tools.
+    pub fn scan(pipeline: &Pipeline, shells: &HashMap<String, String>) -> Self {
+        let mut tools: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut need = |tool: &str, user: String| {
+            let users = tools.entry(tool.to_string()).or_default();
+            if !users.contains(&user) {
+                users.push(user);
+            }
+        };
+
+        if pipeline.compose.is_some() {
+            need("docker", "compose services".to_string());
+        }
+
+        for stage in &pipeline.stages {
+            let stage_name = stage.stage.as_deref().unwrap_or_default();
+            for job in &stage.jobs {
+                let job_path = format!("{}/{}", stage_name, job.identifier().unwrap_or("unknown"));
+                if job.compose.is_some() {
+                    need("docker", format!("{} (compose services)", job_path));
+                }
+
+                let pool = job_pool(pipeline, stage, job);
+                for demand in pool.map(pool_demands).unwrap_or_default() {
+                    need(&demand, format!("{} (pool demand)", job_path));
+                }
+
+                for (index, step) in job_steps(job).iter().enumerate() {
+                    if step.dry_run {
+                        continue;
+                    }
+                    if let Some(tool) = step_tool(step, shells) {
+                        need(&tool, format!("{}: {}", job_path, step_label(step, index)));
+                    }
+                }
+            }
+        }
+
+        Self {
+            requirements: tools
+                .into_iter()
+                .map(|(tool, used_by)| Requirement { tool, used_by })
+                .collect(),
+        }
+    }
+
+    /// Requirements whose tool isn't on PATH
+    pub fn missing(&self) -> Vec<&Requirement> {
+        self.requirements
+            .iter()
+            .filter(|requirement| !requirement.is_available())
+            .collect()
+    }
+}
+
+fn job_steps(job: &Job) -> Vec<Step> {
+    let mut steps = job.steps.clone();
+    steps.extend(collect_deployment_steps(job));
+    steps
+}
+
+/// The program a step runs, if it runs one
+fn step_tool(step: &Step, shells: &HashMap<String, String>) -> Option<String> {
+    let powershell = if cfg!(target_os = "windows") {
+        "powershell.exe"
+    } else {
+        "pwsh"
+    };
+    match &step.action {
+        StepAction::Script(script) => {
+            let spec = match &script.shell {
+                // Unknown shells are reported when the step runs
+                Some(name) => ShellSpec::resolve(name, shells).ok()?,
+                None => ShellSpec::default(),
+            };
+            Some(spec.program)
+        }
+        StepAction::Bash(_) => Some("bash".to_string()),
+        StepAction::Pwsh(_) => Some("pwsh".to_string()),
+        StepAction::PowerShell(_) => Some(powershell.to_string()),
+        StepAction::Checkout(checkout) => match checkout.checkout {
+            CheckoutSource::Repository(_) => Some("git".to_string()),
+            _ => None,
+        },
+        StepAction::Task(task) => {
+            let name = task.task.split('@').next().unwrap_or_default();
+            let tool = match name {
+                "Bash" => "bash",
+                "PowerShell" => powershell,
+                "CmdLine" => "sh",
+                _ => "node",
+            };
+            Some(tool.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Demands that name a capability without a condition (`npm`, not `Agent.OS -equals Linux`)
+fn pool_demands(pool: &Pool) -> Vec<String> {
+    let demands: Vec<&str> = match pool {
+        Pool::Full(spec) => match &spec.demands {
+            Some(PoolDemands::List(list)) => list.iter().map(String::as_str).collect(),
+            Some(PoolDemands::Map(map)) => map.keys().map(String::as_str).collect(),
+            None => Vec::new(),
+        },
+        Pool::Name(_) => Vec::new(),
+    };
+    demands
+        .into_iter()
+        .map(str::trim)
+        .filter(|demand| {
+            !demand.is_empty() && !demand.contains(char::is_whitespace) && !demand.contains('.')
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn step_label(step: &Step, index: usize) -> String {
+    step.display_name
+        .clone()
+        .or_else(|| step.name.clone())
+        .unwrap_or_else(|| format!("step {}", index + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    #[test]
+    fn test_scan_finds_tools() {
+        let pipeline = normalize_pipeline(
+            AzureParser::parse(
+                r#"
+resources:
+  repositories:
+    - repository: tools
+      type: git
+      name: org/tools
+pool:
+  name: Default
+  demands:
+    - roxid-missing-tool
+    - Agent.OS -equals Linux
+steps:
+  - checkout: self
+  - checkout: tools
+  - bash: echo hi
+    displayName: Greet
+  - script: echo hi
+  - script: echo hi
+    shell: bash
+  - task: Npm@1
+  - pwsh: Write-Host hi
+    dryRun: true
+"#,
+            )
+            .unwrap(),
+        );
+
+        let preflight = Preflight::scan(&pipeline, &HashMap::new());
+        let tools: Vec<&str> = preflight
+            .requirements
+            .iter()
+            .map(|r| r.tool.as_str())
+            .collect();
+        assert_eq!(tools, ["bash", "git", "node", "roxid-missing-tool", "sh"]);
+
+        let bash = &preflight.requirements[0];
+        assert_eq!(bash.used_by, ["Build/Job: Greet", "Build/Job: step 5"]);
+        assert!(bash.install_hint().is_some());
+
+        let missing = preflight.missing();
+        let demand = missing
+            .iter()
+            .find(|r| r.tool == "roxid-missing-tool")
+            .unwrap();
+        assert_eq!(demand.used_by, ["Build/Job (pool demand)"]);
+        assert!(demand.install_hint().is_none());
+    }
+}
@@ -0,0 +1,148 @@
+// Step Processes
+// Runs a step's command so that cancelling the step stops everything it started
+
+use std::process::{ExitStatus, Output};
+
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
+
+/// A spawned step command; dropping it before it exits kills the command and
+/// every process it started, so a cancelled step (e.g. a test timing out)
+/// doesn't leave orphans behind
+pub(crate) struct StepProcess(Child);
+
+impl StepProcess {
+    pub(crate) fn spawn(cmd: &mut Command) -> std::io::Result<Self> {
+        cmd.kill_on_drop(true);
+        cmd.spawn().map(Self)
+    }
+
+    /// The command's piped stdout, for reading it while it runs
+    pub(crate) fn take_stdout(&mut self) -> Option<ChildStdout> {
+        self.0.stdout.take()
+    }
+
+    /// The command's piped stderr, for reading it while it runs
+    pub(crate) fn take_stderr(&mut self) -> Option<ChildStderr> {
+        self.0.stderr.take()
+    }
+
+    /// Wait for the command to exit
+    pub(crate) async fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        self.0.wait().await
+    }
+
+    /// Wait for the command to exit, collecting its output
+    pub(crate) async fn output(mut self) -> std::io::Result<Output> {
+        let mut stdout = self.0.stdout.take();
+        let mut stderr = self.0.stderr.take();
+        let read_stdout = async {
+            let mut buf = Vec::new();
+            if let Some(pipe) = stdout.as_mut() {
+                pipe.read_to_end(&mut buf).await?;
+            }
+            Ok::<_, std::io::Error>(buf)
+        };
+        let read_stderr = async {
+            let mut buf = Vec::new();
+            if let Some(pipe) = stderr.as_mut() {
+                pipe.read_to_end(&mut buf).await?;
+            }
+            Ok::<_, std::io::Error>(buf)
+        };
+        let (status, stdout, stderr) = tokio::try_join!(self.0.wait(), read_stdout, read_stderr)?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+impl Drop for StepProcess {
+    fn drop(&mut self) {
+        // The id is only available until the child has been waited for
+        if let Some(pid) = self.0.id() {
+            kill_descendants(pid);
+        }
+    }
+}
+
+/// Kill the processes started by `pid`; the process itself is killed by
+/// `kill_on_drop` once its descendants are gone
+fn kill_descendants(pid: u32) {
+    // Found with pgrep, so only on Unix
+    if !cfg!(unix) {
+        return;
+    }
+    let mut pending = vec![pid];
+    let mut descendants = Vec::new();
+    while let Some(parent) = pending.pop() {
+        let Ok(output) = std::process::Command::new("pgrep")
+            .args(["-P", &parent.to_string()])
+            .output()
+        else {
+            return;
+        };
+        for child in String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse::<u32>().ok())
+        {
+            descendants.push(child);
+            pending.push(child);
+        }
+    }
+    if !descendants.is_empty() {
+        let _ = std::process::Command::new("kill")
+            .arg("-KILL")
+            .args(descendants.iter().map(u32::to_string))
+            .output();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_output() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        let output = StepProcess::spawn(&mut cmd)
+            .unwrap()
+            .output()
+            .await
+            .unwrap();
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_drop_kills_descendants() {
+        // Checks on the processes through /proc
+        if !cfg!(target_os = "linux") {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            &format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+        ]);
+        let process = StepProcess::spawn(&mut cmd).unwrap();
+        let _ = tokio::time::timeout(Duration::from_millis(500), process.output()).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Killed processes may linger as zombies until reaped
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        let running = stat.is_ok_and(|stat| !stat.contains(") Z "));
+        assert!(!running);
+    }
+}
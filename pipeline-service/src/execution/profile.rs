@@ -0,0 +1,226 @@
+// Execution Profiles
+// Named presets from .roxid.toml that trim a pipeline before it runs (e.g. a "fast" mode)
+
+use crate::execution::cache_storage::CacheSettings;
+use crate::execution::disk_quota::DiskQuota;
+use crate::execution::filter::PipelineFilter;
+use crate::execution::graph::GraphError;
+use crate::execution::notification::NotificationSettings;
+use crate::execution::pools::{validate_pools, PoolProfile};
+use crate::execution::secret_provider::SecretProvider;
+use crate::execution::watch::WatchConfig;
+use crate::parser::models::{Job, MatrixStrategy, Pipeline};
+use crate::runners::container::ContainerSettings;
+use crate::tasks::service_connection::ServiceConnection;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// Project configuration file, looked up in the repository root
+pub const CONFIG_FILE: &str = ".roxid.toml";
+
+/// Contents of `.roxid.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoxidConfig {
+    /// Execution profiles by name (`[profiles.fast]`)
+    #[serde(default)]
+    pub profiles: HashMap<String, ExecutionProfile>,
+    /// Local stand-ins for service connections (`[service-connections.name]`)
+    #[serde(default, rename = "service-connections")]
+    pub service_connections: HashMap<String, ServiceConnection>,
+    /// Sources for `- group:` variable groups (`[variable-groups.name]`)
+    #[serde(default, rename = "variable-groups")]
+    pub variable_groups: HashMap<String, SecretProvider>,
+    /// Sources for `AzureKeyVault` steps, by vault name (`[key-vaults.name]`)
+    #[serde(default, rename = "key-vaults")]
+    pub key_vaults: HashMap<String, SecretProvider>,
+    /// Image pull policy and digest pins (`[containers]`)
+    #[serde(default)]
+    pub containers: ContainerSettings,
+    /// How jobs in each agent pool run locally (`[pools.name]`)
+    #[serde(default)]
+    pub pools: HashMap<String, PoolProfile>,
+    /// Workspace quota and free-space minimum for runs (`[workspace]`)
+    #[serde(default)]
+    pub workspace: DiskQuota,
+    /// Pipelines `roxid watch` runs and the paths that trigger them (`[watch]`)
+    #[serde(default)]
+    pub watch: WatchConfig,
+    /// Desktop notifications for finished runs (`[notifications]`)
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Color theme and when to color output (`[ui]`), read by the CLI and TUI
+    #[serde(default)]
+    pub ui: toml::Table,
+    /// Where Cache@2 steps keep their entries (`[cache]`)
+    #[serde(default)]
+    pub cache: CacheSettings,
+}
+
+/// A named set of changes applied to a pipeline before it runs
+///
+/// ```toml
+/// [profiles.fast]
+/// skip-tags = ["slow"]
+/// max-matrix = 1
+/// task-cache = "~/.roxid/tasks"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExecutionProfile {
+    /// Leave out steps with any of these `tags`
+    #[serde(default)]
+    pub skip_tags: Vec<String>,
+    /// Leave out steps whose name or display name matches (supports wildcards)
+    #[serde(default)]
+    pub skip_steps: Vec<String>,
+    /// Run at most this many instances of each matrix, in name order
+    pub max_matrix: Option<usize>,
+    /// Reuse downloaded tasks from this directory (`~` is the home directory)
+    pub task_cache: Option<PathBuf>,
+    /// Variables set for the run; `--var` takes precedence
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+impl RoxidConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Self::parse(&content).map_err(|e| format!("invalid {}: {}", path.display(), e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(content).map_err(|e| e.message().to_string())?;
+        config.containers.validate()?;
+        validate_pools(&config.pools)?;
+        Ok(config)
+    }
+
+    /// The config in `dir`, or the default when it has none
+    pub fn discover(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(CONFIG_FILE);
+        if path.is_file() {
+            Self::load(&path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&ExecutionProfile, String> {
+        self.profiles.get(name).ok_or_else(|| {
+            let mut names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            names.sort();
+            if names.is_empty() {
+                format!(
+                    "unknown profile '{}' (no profiles in {})",
+                    name, CONFIG_FILE
+                )
+            } else {
+                format!(
+                    "unknown profile '{}' (available: {})",
+                    name,
+                    names.join(", ")
+                )
+            }
+        })
+    }
+}
+
+impl ExecutionProfile {
+    /// Remove the steps and matrix instances the profile leaves out
+    pub fn apply(&self, pipeline: Pipeline) -> Result<Pipeline, GraphError> {
+        let filter = PipelineFilter {
+            skip_steps: self.skip_steps.clone(),
+            skip_tags: self.skip_tags.clone(),
+            ..Default::default()
+        };
+        let mut pipeline = filter.apply(pipeline)?;
+
+        if let Some(max) = self.max_matrix {
+            for job in pipeline.stages.iter_mut().flat_map(|s| s.jobs.iter_mut()) {
+                shrink_matrix(job, max);
+            }
+        }
+        Ok(pipeline)
+    }
+
+    /// The task cache directory with a leading `~` expanded
+    pub fn task_cache_dir(&self) -> Option<PathBuf> {
+        let path = self.task_cache.as_ref()?;
+        match (path.strip_prefix("~"), dirs::home_dir()) {
+            (Ok(rest), Some(home)) => Some(home.join(rest)),
+            _ => Some(path.clone()),
+        }
+    }
+}
+
+fn shrink_matrix(job: &mut Job, max: usize) {
+    let Some(MatrixStrategy::Inline(matrix)) = job
+        .strategy
+        .as_mut()
+        .and_then(|strategy| strategy.matrix.as_mut())
+    else {
+        return;
+    };
+    let mut names: Vec<String> = matrix.keys().cloned().collect();
+    names.sort();
+    for name in names.into_iter().skip(max.max(1)) {
+        matrix.remove(&name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    #[test]
+    fn test_fast_profile() {
+        let config = RoxidConfig::parse(
+            r#"
+[profiles.fast]
+skip-tags = ["slow"]
+max-matrix = 1
+
+[profiles.full]
+"#,
+        )
+        .unwrap();
+        let pipeline = normalize_pipeline(
+            AzureParser::parse(
+                r#"
+jobs:
+  - job: Test
+    strategy:
+      matrix:
+        linux: { os: linux }
+        mac: { os: mac }
+        windows: { os: windows }
+    steps:
+      - script: cargo test
+      - script: cargo test -- --ignored
+        tags: [slow]
+"#,
+            )
+            .unwrap(),
+        );
+
+        let fast = config.profile("fast").unwrap().apply(pipeline).unwrap();
+        let job = &fast.stages[0].jobs[0];
+        assert_eq!(job.steps.len(), 1);
+        let Some(MatrixStrategy::Inline(matrix)) =
+            job.strategy.as_ref().and_then(|s| s.matrix.as_ref())
+        else {
+            panic!("matrix kept");
+        };
+        assert_eq!(matrix.keys().collect::<Vec<_>>(), ["linux"]);
+
+        let err = config.profile("quick").unwrap_err();
+        assert!(err.contains("available: fast, full"));
+        assert!(RoxidConfig::parse("[profiles.fast]\nskip = 1").is_err());
+    }
+}
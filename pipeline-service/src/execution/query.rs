@@ -0,0 +1,517 @@
+// Run Queries
+// A small jq-like filter language over a run's JSON document
+
+use serde_json::Value;
+
+/// A filter such as `.stages[].jobs[].steps[] | select(.result == "failed") | .name`
+///
+/// Supported, as in jq:
+/// - `.`, `.name`, `.["name"]`, `.[0]`, `.[-1]` and `.[]`, chained (`.stages[0].jobs[]`)
+/// - `a | b` to feed every output of `a` into `b`
+/// - `select(cond)`, where `cond` compares paths and literals with `==`, `!=`,
+///   `<`, `<=`, `>`, `>=`, combined with `and` / `or`
+/// - `length` and `keys`
+/// - string, number, `true`, `false` and `null` literals
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// The `|`-separated stages, applied in order
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Path(Vec<Segment>),
+    Select(Box<Condition>),
+    Length,
+    Keys,
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(i64),
+    Iterate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Value(Filter),
+    Compare(Filter, Comparison, Filter),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Open,
+    Close,
+    OpenParen,
+    CloseParen,
+    Pipe,
+    Ident(String),
+    Str(String),
+    Number(f64),
+    Op(Comparison),
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let mut filters = vec![parser.filter()?];
+        while parser.eat(&Token::Pipe) {
+            filters.push(parser.filter()?);
+        }
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {}", describe(token)));
+        }
+        Ok(Self { filters })
+    }
+
+    /// Every output of the query for `input`
+    pub fn apply(&self, input: &Value) -> Result<Vec<Value>, String> {
+        let mut values = vec![input.clone()];
+        for filter in &self.filters {
+            let mut next = Vec::new();
+            for value in &values {
+                next.extend(filter.apply(value)?);
+            }
+            values = next;
+        }
+        Ok(values)
+    }
+}
+
+impl Filter {
+    fn apply(&self, input: &Value) -> Result<Vec<Value>, String> {
+        match self {
+            Filter::Path(segments) => {
+                let mut values = vec![input.clone()];
+                for segment in segments {
+                    let mut next = Vec::new();
+                    for value in &values {
+                        segment.apply(value, &mut next)?;
+                    }
+                    values = next;
+                }
+                Ok(values)
+            }
+            Filter::Select(condition) => Ok(if condition.holds(input)? {
+                vec![input.clone()]
+            } else {
+                Vec::new()
+            }),
+            Filter::Length => Ok(vec![Value::from(match input {
+                Value::Null => 0,
+                Value::Array(items) => items.len(),
+                Value::Object(fields) => fields.len(),
+                Value::String(s) => s.chars().count(),
+                other => return Err(format!("{} has no length", kind(other))),
+            })]),
+            Filter::Keys => match input {
+                Value::Object(fields) => {
+                    let mut keys: Vec<&String> = fields.keys().collect();
+                    keys.sort();
+                    Ok(vec![Value::from(
+                        keys.into_iter().cloned().collect::<Vec<_>>(),
+                    )])
+                }
+                other => Err(format!("{} has no keys", kind(other))),
+            },
+            Filter::Literal(value) => Ok(vec![value.clone()]),
+        }
+    }
+
+    /// The first output, `null` if there is none
+    fn value(&self, input: &Value) -> Result<Value, String> {
+        Ok(self.apply(input)?.into_iter().next().unwrap_or(Value::Null))
+    }
+}
+
+impl Segment {
+    fn apply(&self, value: &Value, out: &mut Vec<Value>) -> Result<(), String> {
+        match (self, value) {
+            (_, Value::Null) if *self != Segment::Iterate => out.push(Value::Null),
+            (Segment::Field(name), Value::Object(fields)) => {
+                out.push(fields.get(name).cloned().unwrap_or(Value::Null))
+            }
+            (Segment::Index(index), Value::Array(items)) => {
+                let index = if *index < 0 {
+                    items.len() as i64 + index
+                } else {
+                    *index
+                };
+                out.push(
+                    usize::try_from(index)
+                        .ok()
+                        .and_then(|i| items.get(i))
+                        .cloned()
+                        .unwrap_or(Value::Null),
+                );
+            }
+            (Segment::Iterate, Value::Array(items)) => out.extend(items.iter().cloned()),
+            (Segment::Iterate, Value::Object(fields)) => out.extend(fields.values().cloned()),
+            (Segment::Field(name), other) => {
+                return Err(format!("cannot get '{}' of {}", name, kind(other)))
+            }
+            (Segment::Index(index), other) => {
+                return Err(format!("cannot index {} with {}", kind(other), index))
+            }
+            (Segment::Iterate, other) => {
+                return Err(format!("cannot iterate over {}", kind(other)))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Condition {
+    fn holds(&self, input: &Value) -> Result<bool, String> {
+        match self {
+            Condition::Value(filter) => Ok(truthy(&filter.value(input)?)),
+            Condition::Compare(left, op, right) => {
+                let (left, right) = (left.value(input)?, right.value(input)?);
+                let ordering = match (&left, &right) {
+                    (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+                    (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                Ok(match op {
+                    Comparison::Eq => left == right,
+                    Comparison::Ne => left != right,
+                    Comparison::Lt => ordering.is_some_and(|o| o.is_lt()),
+                    Comparison::Le => ordering.is_some_and(|o| o.is_le()),
+                    Comparison::Gt => ordering.is_some_and(|o| o.is_gt()),
+                    Comparison::Ge => ordering.is_some_and(|o| o.is_ge()),
+                })
+            }
+            Condition::And(a, b) => Ok(a.holds(input)? && b.holds(input)?),
+            Condition::Or(a, b) => Ok(a.holds(input)? || b.holds(input)?),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {}, found {}",
+                describe(&token),
+                self.peek().map(describe).unwrap_or("the end".into())
+            ))
+        }
+    }
+
+    fn filter(&mut self) -> Result<Filter, String> {
+        match self.peek().cloned() {
+            Some(Token::Dot) => self.path(),
+            Some(Token::Str(s)) => {
+                self.pos += 1;
+                Ok(Filter::Literal(Value::String(s)))
+            }
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(Filter::Literal(Value::from(n)))
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                match name.as_str() {
+                    "select" => {
+                        self.expect(Token::OpenParen)?;
+                        let condition = self.condition()?;
+                        self.expect(Token::CloseParen)?;
+                        Ok(Filter::Select(Box::new(condition)))
+                    }
+                    "length" => Ok(Filter::Length),
+                    "keys" => Ok(Filter::Keys),
+                    "true" => Ok(Filter::Literal(Value::Bool(true))),
+                    "false" => Ok(Filter::Literal(Value::Bool(false))),
+                    "null" => Ok(Filter::Literal(Value::Null)),
+                    other => Err(format!("unknown function '{}'", other)),
+                }
+            }
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("expected a filter, found the end".to_string()),
+        }
+    }
+
+    fn path(&mut self) -> Result<Filter, String> {
+        let mut segments = Vec::new();
+        while self.eat(&Token::Dot) {
+            if let Some(Token::Ident(name)) = self.peek().cloned() {
+                self.pos += 1;
+                segments.push(Segment::Field(name));
+            }
+            while self.eat(&Token::Open) {
+                let segment = match self.peek().cloned() {
+                    Some(Token::Close) => Segment::Iterate,
+                    Some(Token::Str(name)) => {
+                        self.pos += 1;
+                        Segment::Field(name)
+                    }
+                    Some(Token::Number(n)) if n.fract() == 0.0 => {
+                        self.pos += 1;
+                        Segment::Index(n as i64)
+                    }
+                    other => {
+                        return Err(format!(
+                            "expected an index, a quoted name or ']', found {}",
+                            other.as_ref().map(describe).unwrap_or("the end".into())
+                        ))
+                    }
+                };
+                self.expect(Token::Close)?;
+                segments.push(segment);
+            }
+        }
+        Ok(Filter::Path(segments))
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let mut condition = self.comparison()?;
+        loop {
+            match self.peek() {
+                Some(Token::Ident(word)) if word == "and" => {
+                    self.pos += 1;
+                    condition = Condition::And(Box::new(condition), Box::new(self.comparison()?));
+                }
+                Some(Token::Ident(word)) if word == "or" => {
+                    self.pos += 1;
+                    condition = Condition::Or(Box::new(condition), Box::new(self.comparison()?));
+                }
+                _ => return Ok(condition),
+            }
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Condition, String> {
+        let left = self.filter()?;
+        match self.peek() {
+            Some(Token::Op(op)) => {
+                let op = *op;
+                self.pos += 1;
+                Ok(Condition::Compare(left, op, self.filter()?))
+            }
+            _ => Ok(Condition::Value(left)),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '.' => {
+                chars.next();
+                tokens.push(Token::Dot);
+            }
+            '[' | ']' | '(' | ')' | '|' => {
+                chars.next();
+                tokens.push(match c {
+                    '[' => Token::Open,
+                    ']' => Token::Close,
+                    '(' => Token::OpenParen,
+                    ')' => Token::CloseParen,
+                    _ => Token::Pipe,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(c) => s.push(c),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(c) => s.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                tokens.push(Token::Op(match (c, equals) {
+                    ('=', true) => Comparison::Eq,
+                    ('!', true) => Comparison::Ne,
+                    ('<', false) => Comparison::Lt,
+                    ('<', true) => Comparison::Le,
+                    ('>', false) => Comparison::Gt,
+                    ('>', true) => Comparison::Ge,
+                    _ => return Err(format!("unknown operator '{}'", c)),
+                }));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = String::new();
+                number.push(c);
+                chars.next();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                // `.stages[0].jobs`: the dot after a number starts a path
+                if number.ends_with('.') {
+                    number.pop();
+                    tokens.push(Token::Number(parse_number(&number)?));
+                    tokens.push(Token::Dot);
+                } else {
+                    tokens.push(Token::Number(parse_number(&number)?));
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_number(text: &str) -> Result<f64, String> {
+    text.parse()
+        .map_err(|_| format!("invalid number '{}'", text))
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Dot => "'.'".into(),
+        Token::Open => "'['".into(),
+        Token::Close => "']'".into(),
+        Token::OpenParen => "'('".into(),
+        Token::CloseParen => "')'".into(),
+        Token::Pipe => "'|'".into(),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Str(s) => format!("\"{}\"", s),
+        Token::Number(n) => n.to_string(),
+        Token::Op(_) => "an operator".into(),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run() -> Value {
+        serde_json::json!({
+            "runId": "01JRUN",
+            "stages": [{
+                "name": "Build",
+                "jobs": [{
+                    "name": "Compile",
+                    "outputs": {"version.tag": "1.2"},
+                    "steps": [
+                        {"name": "Restore", "result": "succeeded", "durationSeconds": 3.5},
+                        {"name": "Test", "result": "failed", "durationSeconds": 12.0, "exitCode": 2}
+                    ]
+                }]
+            }]
+        })
+    }
+
+    fn query(text: &str) -> Vec<Value> {
+        Query::parse(text).unwrap().apply(&run()).unwrap()
+    }
+
+    #[test]
+    fn test_paths_and_pipes() {
+        assert_eq!(query(".runId"), ["01JRUN"]);
+        assert_eq!(query(".stages[0].jobs[0].steps[-1].exitCode"), [2]);
+        assert_eq!(
+            query(".stages[].jobs[].steps[] | .name"),
+            ["Restore", "Test"]
+        );
+        assert_eq!(
+            query(r#".stages[0].jobs[0].outputs["version.tag"]"#),
+            ["1.2"]
+        );
+        assert_eq!(query(".stages[0].jobs[0].steps | length"), [2]);
+        assert_eq!(query(".missing.deeper"), [Value::Null]);
+        assert_eq!(query("."), [run()]);
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(
+            query(r#".stages[].jobs[].steps[] | select(.result == "failed") | .name"#),
+            ["Test"]
+        );
+        assert_eq!(
+            query(".stages[].jobs[].steps[] | select(.durationSeconds > 5 or .exitCode) | .name"),
+            ["Test"]
+        );
+        assert_eq!(
+            query(".stages[].jobs[].steps[] | select(.exitCode and .durationSeconds < 5)"),
+            Vec::<Value>::new()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Query::parse(".stages[").is_err());
+        assert!(Query::parse("frobnicate")
+            .unwrap_err()
+            .contains("unknown function"));
+        assert!(Query::parse(".a b").is_err());
+        let err = Query::parse(".runId[]").unwrap().apply(&run()).unwrap_err();
+        assert!(err.contains("cannot iterate over a string"));
+    }
+}
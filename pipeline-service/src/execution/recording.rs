@@ -0,0 +1,221 @@
+// Step Recordings
+// Captures command output so pipelines can be replayed without running commands
+
+use crate::parser::models::Step;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// Captured result of running one step's command
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandRecording {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stdout: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+    /// Exit code, absent if the process was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+impl CommandRecording {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Recordings keyed by step identity
+///
+/// A step that runs more than once under the same identity (matrix
+/// instances, repeated test runs) has one recording per run, replayed in the
+/// order they were recorded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recordings {
+    pub steps: BTreeMap<String, Vec<CommandRecording>>,
+}
+
+impl Recordings {
+    /// Read recordings written by [`Recordings::save`]
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        serde_yaml::from_str(&content)
+            .map_err(|e| format!("invalid recordings file {}: {}", path.display(), e))
+    }
+
+    /// Write the recordings as YAML
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_yaml::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingMode {
+    /// Run commands and capture their output
+    Record,
+    /// Return captured output instead of running commands
+    Replay,
+}
+
+/// Records or replays the commands of script and task steps
+///
+/// Clones share the same recordings, so one recorder can be handed to
+/// several executors (e.g. every test of a suite) and saved afterwards.
+#[derive(Debug, Clone)]
+pub struct StepRecorder {
+    mode: RecordingMode,
+    /// Prefix added to every step identity
+    scope: String,
+    recordings: Arc<Mutex<Recordings>>,
+    /// Recordings already replayed per identity
+    replayed: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl StepRecorder {
+    /// Recorder that captures the output of every command it sees
+    pub fn record() -> Self {
+        Self::new(RecordingMode::Record, Recordings::default())
+    }
+
+    /// Recorder that replays `recordings` instead of running commands
+    pub fn replay(recordings: Recordings) -> Self {
+        Self::new(RecordingMode::Replay, recordings)
+    }
+
+    fn new(mode: RecordingMode, recordings: Recordings) -> Self {
+        Self {
+            mode,
+            scope: String::new(),
+            recordings: Arc::new(Mutex::new(recordings)),
+            replayed: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Recorder sharing these recordings with identities prefixed by `scope`,
+    /// so different pipelines can use one recordings file
+    pub fn scoped(&self, scope: &str) -> Self {
+        Self {
+            scope: format!("{}{}: ", self.scope, scope),
+            ..self.clone()
+        }
+    }
+
+    pub fn mode(&self) -> RecordingMode {
+        self.mode
+    }
+
+    /// Snapshot of the recordings, e.g. to save after a recording run
+    pub fn recordings(&self) -> Recordings {
+        self.recordings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Identity of a step: `Stage/Job/<name>`, where the name is the step's
+    /// `name:`, else its unexpanded `displayName:`, else its 1-based index
+    pub fn step_identity(&self, stage: &str, job: &str, step: &Step, step_index: usize) -> String {
+        let name = step
+            .name
+            .clone()
+            .or_else(|| step.display_name.clone())
+            .unwrap_or_else(|| (step_index + 1).to_string());
+        format!("{}{}/{}/{}", self.scope, stage, job, name)
+    }
+
+    /// Store the output of a command that ran
+    pub fn store(&self, identity: String, recording: CommandRecording) {
+        self.recordings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .steps
+            .entry(identity)
+            .or_default()
+            .push(recording);
+    }
+
+    /// Next recording to replay for a step, if one was captured
+    pub fn next(&self, identity: &str) -> Option<CommandRecording> {
+        let recordings = self.recordings.lock().unwrap_or_else(|e| e.into_inner());
+        let runs = recordings.steps.get(identity)?;
+        let mut replayed = self.replayed.lock().unwrap_or_else(|e| e.into_inner());
+        let count = replayed.entry(identity.to_string()).or_default();
+        let recording = runs.get(*count).or_else(|| runs.last())?.clone();
+        *count += 1;
+        Some(recording)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(yaml: &str) -> Step {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_step_identity() {
+        let recorder = StepRecorder::record();
+        assert_eq!(
+            recorder.step_identity(
+                "Build",
+                "Compile",
+                &step("{script: make, name: make, displayName: Make}"),
+                0
+            ),
+            "Build/Compile/make"
+        );
+        assert_eq!(
+            recorder.step_identity(
+                "Build",
+                "Compile",
+                &step("{script: make, displayName: Make $(x)}"),
+                0
+            ),
+            "Build/Compile/Make $(x)"
+        );
+        assert_eq!(
+            recorder.scoped("smoke test").step_identity(
+                "Build",
+                "Compile",
+                &step("script: make"),
+                2
+            ),
+            "smoke test: Build/Compile/3"
+        );
+    }
+
+    #[test]
+    fn test_replay_in_recorded_order() {
+        let recorder = StepRecorder::record();
+        let scoped = recorder.scoped("t");
+        for code in [0, 1] {
+            scoped.store(
+                "t: Build/Job/1".to_string(),
+                CommandRecording {
+                    stdout: format!("run {}\n", code),
+                    stderr: String::new(),
+                    exit_code: Some(code),
+                },
+            );
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recordings.yml");
+        recorder.recordings().save(&path).unwrap();
+
+        let replay = StepRecorder::replay(Recordings::load(&path).unwrap());
+        assert_eq!(replay.mode(), RecordingMode::Replay);
+        assert!(replay.next("t: Build/Job/1").unwrap().succeeded());
+        assert_eq!(replay.next("t: Build/Job/1").unwrap().stdout, "run 1\n");
+        // Extra runs repeat the last recording
+        assert_eq!(replay.next("t: Build/Job/1").unwrap().exit_code, Some(1));
+        assert!(replay.next("t: Build/Job/2").is_none());
+    }
+}
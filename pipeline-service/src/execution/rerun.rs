@@ -0,0 +1,142 @@
+// Stage Re-runs
+// Runs a stage again with the stages that depend on it, reusing the recorded results of the rest
+
+use crate::execution::executor::ExecutionResult;
+use crate::execution::graph::ExecutionGraph;
+use crate::parser::models::{Pipeline, StageResult};
+
+use std::collections::{HashMap, HashSet};
+
+/// Which stages of a pipeline to run again after an earlier run
+///
+/// The other stages aren't run: their results from the earlier run, job
+/// outputs included, stand in for them so `dependencies.<stage>` and
+/// `stageDependencies` expressions see what they produced.
+#[derive(Debug, Clone, Default)]
+pub struct StageRerun {
+    /// Stages to run, in declaration order
+    stages: Vec<String>,
+    /// Results of the earlier run, by stage name
+    previous: HashMap<String, StageResult>,
+}
+
+impl StageRerun {
+    /// Re-run `stage` and every stage that depends on it, directly or not
+    pub fn new(
+        pipeline: &Pipeline,
+        stage: &str,
+        previous: &ExecutionResult,
+    ) -> Result<Self, String> {
+        let graph = ExecutionGraph::from_pipeline(pipeline).map_err(|e| e.message)?;
+        let names: Vec<&str> = graph
+            .stages
+            .iter()
+            .map(|s| s.stage.stage.as_deref().unwrap_or(""))
+            .collect();
+        if !names.contains(&stage) {
+            return Err(format!("stage '{}' not found in the pipeline", stage));
+        }
+
+        // Repeat until no more dependents turn up, whatever order stages are declared in
+        let mut rerun: HashSet<&str> = HashSet::from([stage]);
+        loop {
+            let before = rerun.len();
+            for (name, node) in names.iter().zip(&graph.stages) {
+                if node.dependencies.iter().any(|d| rerun.contains(d.as_str())) {
+                    rerun.insert(name);
+                }
+            }
+            if rerun.len() == before {
+                break;
+            }
+        }
+
+        Ok(Self {
+            stages: names
+                .into_iter()
+                .filter(|name| rerun.contains(name))
+                .map(str::to_string)
+                .collect(),
+            previous: previous
+                .stages
+                .iter()
+                .map(|result| (result.stage_name.clone(), result.clone()))
+                .collect(),
+        })
+    }
+
+    /// Names of the stages that run again, in declaration order
+    pub fn stages(&self) -> &[String] {
+        &self.stages
+    }
+
+    /// The earlier result standing in for `stage`, unless it runs again
+    ///
+    /// Stages that didn't run last time have no result and run as usual.
+    pub fn reused(&self, stage: &str) -> Option<&StageResult> {
+        if self.stages.iter().any(|name| name == stage) {
+            return None;
+        }
+        self.previous.get(stage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::models::StageStatus;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_rerun_includes_dependents() {
+        let pipeline = normalize_pipeline(
+            AzureParser::parse(
+                r#"
+stages:
+  - stage: Build
+    jobs: [{ job: A, steps: [{ script: echo }] }]
+  - stage: Test
+    jobs: [{ job: A, steps: [{ script: echo }] }]
+  - stage: Lint
+    dependsOn: []
+    jobs: [{ job: A, steps: [{ script: echo }] }]
+  - stage: Deploy
+    dependsOn: [Test, Lint]
+    jobs: [{ job: A, steps: [{ script: echo }] }]
+"#,
+            )
+            .unwrap(),
+        );
+        let stage = |name: &str| StageResult {
+            stage_name: name.to_string(),
+            display_name: None,
+            status: StageStatus::Succeeded,
+            jobs: Vec::new(),
+            duration: Duration::ZERO,
+            started_at: None,
+            finished_at: None,
+        };
+        let previous = ExecutionResult {
+            run_id: String::new(),
+            stages: vec![stage("Build"), stage("Test"), stage("Lint")],
+            duration: Duration::ZERO,
+            success: false,
+            variables: HashMap::new(),
+            images: Default::default(),
+            started_at: None,
+            finished_at: None,
+        };
+
+        let rerun = StageRerun::new(&pipeline, "Test", &previous).unwrap();
+        assert_eq!(rerun.stages(), ["Test", "Deploy"]);
+        assert!(rerun.reused("Build").is_some());
+        assert!(rerun.reused("Test").is_none());
+        assert!(rerun.reused("Deploy").is_none());
+
+        let rerun = StageRerun::new(&pipeline, "Lint", &previous).unwrap();
+        assert_eq!(rerun.stages(), ["Lint", "Deploy"]);
+        assert!(StageRerun::new(&pipeline, "Missing", &previous).is_err());
+    }
+}
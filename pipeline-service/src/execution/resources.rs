@@ -0,0 +1,278 @@
+// Host Resources
+// Sizes parallel runs to the machine: its CPUs, its free memory and the `resources:` each job asks for
+
+use crate::execution::disk_quota::{format_size, parse_size};
+use crate::parser::models::Job;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+
+/// Memory a job is assumed to need when it doesn't say
+pub const DEFAULT_JOB_MEMORY: u64 = 1 << 30;
+
+/// How many stages and jobs may run at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parallelism {
+    /// Up to N at once (0 = unlimited)
+    Fixed(usize),
+    /// As many as the host's CPUs and free memory allow
+    Auto,
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Parallelism::Fixed(1)
+    }
+}
+
+impl FromStr for Parallelism {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Parallelism::Auto);
+        }
+        s.parse()
+            .map(Parallelism::Fixed)
+            .map_err(|_| format!("expected a number or 'auto', got '{}'", s))
+    }
+}
+
+impl fmt::Display for Parallelism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Parallelism::Fixed(n) => write!(f, "{}", n),
+            Parallelism::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// CPUs and memory, of the host or of a job
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Resources {
+    pub cpus: u32,
+    /// Bytes; `None` where the host's free memory can't be measured
+    pub memory: Option<u64>,
+}
+
+impl Resources {
+    /// The host's CPU count and currently available memory
+    pub fn detect() -> Self {
+        Self {
+            cpus: std::thread::available_parallelism()
+                .map(|n| n.get() as u32)
+                .unwrap_or(1),
+            memory: available_memory(),
+        }
+    }
+
+    /// What `job` declared under `resources:`, defaulting to one CPU and
+    /// [`DEFAULT_JOB_MEMORY`]
+    pub fn for_job(job: &Job) -> Self {
+        let declared = job.resources.as_ref();
+        Self {
+            cpus: declared.and_then(|r| r.cpus).unwrap_or(1),
+            memory: Some(
+                declared
+                    .and_then(|r| r.memory.as_deref())
+                    .and_then(|m| parse_size(m).ok())
+                    .unwrap_or(DEFAULT_JOB_MEMORY),
+            ),
+        }
+    }
+
+    /// How many jobs with the default needs fit at once, at least one
+    pub fn default_jobs(&self) -> usize {
+        let by_memory = self
+            .memory
+            .map_or(u64::MAX, |memory| memory / DEFAULT_JOB_MEMORY);
+        (self.cpus as u64).min(by_memory).max(1) as usize
+    }
+}
+
+impl fmt::Display for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} CPU{}",
+            self.cpus,
+            if self.cpus == 1 { "" } else { "s" }
+        )?;
+        if let Some(memory) = self.memory {
+            write!(f, ", {} memory", format_size(memory))?;
+        }
+        Ok(())
+    }
+}
+
+/// Resources shared by every job of a run; a job starts once what it asked
+/// for is free
+///
+/// A job asking for more than the whole pool still runs, alone, rather than
+/// never.
+#[derive(Debug, Clone)]
+pub struct ResourcePool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    capacity: Resources,
+    used: Mutex<(Resources, usize)>,
+    released: watch::Sender<u64>,
+}
+
+impl ResourcePool {
+    pub fn new(capacity: Resources) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                capacity,
+                used: Mutex::new((Resources::default(), 0)),
+                released: watch::channel(0).0,
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> Resources {
+        self.inner.capacity
+    }
+
+    /// Take `needs` from the pool if it fits; the lease gives them back when
+    /// dropped
+    pub fn try_acquire(&self, needs: Resources) -> Option<ResourceLease> {
+        let capacity = self.inner.capacity;
+        let mut used = self.inner.used.lock().unwrap_or_else(|e| e.into_inner());
+        let (taken, leases) = &mut *used;
+        let fits = taken.cpus + needs.cpus <= capacity.cpus
+            && match (capacity.memory, taken.memory, needs.memory) {
+                (Some(capacity), taken, Some(needs)) => taken.unwrap_or(0) + needs <= capacity,
+                _ => true,
+            };
+        if !fits && *leases > 0 {
+            return None;
+        }
+        taken.cpus += needs.cpus;
+        taken.memory = Some(taken.memory.unwrap_or(0) + needs.memory.unwrap_or(0));
+        *leases += 1;
+        Some(ResourceLease {
+            pool: self.clone(),
+            needs,
+        })
+    }
+
+    /// Changes whenever a lease is given back; subscribe before
+    /// [`try_acquire`](Self::try_acquire) so a release in between isn't missed
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.inner.released.subscribe()
+    }
+
+    fn release(&self, needs: Resources) {
+        {
+            let mut used = self.inner.used.lock().unwrap_or_else(|e| e.into_inner());
+            let (taken, leases) = &mut *used;
+            taken.cpus = taken.cpus.saturating_sub(needs.cpus);
+            taken.memory = taken
+                .memory
+                .map(|m| m.saturating_sub(needs.memory.unwrap_or(0)));
+            *leases = leases.saturating_sub(1);
+        }
+        self.inner.released.send_modify(|n| *n = n.wrapping_add(1));
+    }
+}
+
+/// Resources a running job holds
+#[derive(Debug)]
+pub struct ResourceLease {
+    pool: ResourcePool,
+    needs: Resources,
+}
+
+impl Drop for ResourceLease {
+    fn drop(&mut self) {
+        self.pool.release(self.needs);
+    }
+}
+
+/// `MemAvailable` from `/proc/meminfo`
+#[cfg(target_os = "linux")]
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_memory() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallelism_parse() {
+        assert_eq!("auto".parse(), Ok(Parallelism::Auto));
+        assert_eq!("4".parse(), Ok(Parallelism::Fixed(4)));
+        assert!("lots".parse::<Parallelism>().is_err());
+
+        let host = Resources {
+            cpus: 8,
+            memory: Some(3 * DEFAULT_JOB_MEMORY + 1),
+        };
+        assert_eq!(host.default_jobs(), 3);
+        assert_eq!(
+            Resources {
+                cpus: 2,
+                memory: None
+            }
+            .default_jobs(),
+            2
+        );
+        assert_eq!(
+            Resources {
+                cpus: 4,
+                memory: Some(0)
+            }
+            .default_jobs(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_pool_leases() {
+        let pool = ResourcePool::new(Resources {
+            cpus: 4,
+            memory: Some(4 << 30),
+        });
+        let needs = |cpus, gb: u64| Resources {
+            cpus,
+            memory: Some(gb << 30),
+        };
+
+        let first = pool.try_acquire(needs(2, 1)).unwrap();
+        let second = pool.try_acquire(needs(2, 1)).unwrap();
+        assert!(pool.try_acquire(needs(1, 1)).is_none());
+
+        let mut released = pool.subscribe();
+        drop(first);
+        assert!(released.has_changed().unwrap());
+        released.mark_unchanged();
+        // Memory runs out before CPUs do
+        assert!(pool.try_acquire(needs(1, 4)).is_none());
+        let third = pool.try_acquire(needs(1, 2)).unwrap();
+        drop((second, third));
+
+        // Too big for the pool: runs, but only on its own
+        let huge = pool.try_acquire(needs(16, 1)).unwrap();
+        assert!(pool.try_acquire(needs(1, 1)).is_none());
+        drop(huge);
+        assert!(pool.try_acquire(needs(1, 1)).is_some());
+    }
+}
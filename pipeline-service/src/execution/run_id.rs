@@ -0,0 +1,89 @@
+// Run IDs
+// Unique, time-ordered IDs for runs and the stage, job and step IDs derived from them
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Predefined variable holding the run's ID (`ROXID_RUNID` in step environments)
+pub const RUN_ID_VARIABLE: &str = "Roxid.RunId";
+
+/// Crockford's base32 alphabet, as used by ULIDs
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// A new run ID: a ULID, 26 characters that sort in the order runs started
+pub fn new_run_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let millis = now.as_millis() & ((1 << 48) - 1);
+
+    // 80 random bits; every RandomState is keyed differently
+    let seed = COUNTER.fetch_add(1, Ordering::Relaxed) ^ u64::from(now.subsec_nanos());
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(seed);
+    hasher.write_u32(std::process::id());
+    let high = hasher.finish();
+    hasher.write_u64(high);
+    let low = hasher.finish();
+    let random = (u128::from(high & 0xFFFF) << 64) | u128::from(low);
+
+    encode((millis << 80) | random)
+}
+
+/// ID of a stage, job or step within a run: `<stage>[/<job>[/<step number>]]`
+///
+/// Derived from names alone, so the same item gets the same ID in every event
+/// and result of a run; pair it with the run ID to tell runs apart.
+pub fn correlation_id(stage: &str, job: Option<&str>, step_index: Option<usize>) -> String {
+    let mut id = stage.to_string();
+    if let Some(job) = job {
+        id.push('/');
+        id.push_str(job);
+        if let Some(index) = step_index {
+            id.push_str(&format!("/{}", index + 1));
+        }
+    }
+    id
+}
+
+fn encode(value: u128) -> String {
+    (0..26)
+        .map(|i| ALPHABET[((value >> (125 - 5 * i)) & 0x1F) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_ids_are_unique_and_ordered() {
+        let first = new_run_id();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = new_run_id();
+
+        assert_eq!(first.len(), 26);
+        assert!(first.bytes().all(|b| ALPHABET.contains(&b)));
+        assert_ne!(first, second);
+        // The timestamp takes the first 10 characters
+        assert!(first[..10] < second[..10]);
+        assert_eq!(encode(u128::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+    }
+
+    #[test]
+    fn test_correlation_ids() {
+        assert_eq!(correlation_id("Build", None, Some(0)), "Build");
+        assert_eq!(
+            correlation_id("Build", Some("Compile"), None),
+            "Build/Compile"
+        );
+        assert_eq!(
+            correlation_id("Build", Some("Compile"), Some(2)),
+            "Build/Compile/3"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+// Run Journal
+// Runs in progress, so the next run can clean up after one that crashed or was killed
+
+use crate::execution::concurrency::process_alive;
+use crate::execution::history::RunHistory;
+use crate::execution::run_record::{RunRecord, LOCAL_SOURCE};
+use crate::runners::compose::project_pid;
+use crate::runners::container::PID_LABEL;
+use crate::runners::shell::find_executable;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// A run in progress
+///
+/// Everything a run leaves outside the workspace is tagged with its process
+/// ID: script files and `--ref` worktrees in the temp directory, job and
+/// service containers (the `roxid.pid` label) and compose projects.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveRun {
+    pub run_id: String,
+    pub pipeline: String,
+    pub pid: u32,
+    /// Repository the run added a worktree to, for `git worktree prune`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<PathBuf>,
+}
+
+/// What was removed after an interrupted run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cleanup {
+    pub containers: usize,
+    pub compose_projects: usize,
+    /// Temp files and worktrees
+    pub temp_paths: usize,
+}
+
+impl Cleanup {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for Cleanup {
+    /// `2 container(s), 1 temp path(s)`, listing only what was removed
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            (self.containers, "container(s)"),
+            (self.compose_projects, "compose project(s)"),
+            (self.temp_paths, "temp path(s)"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{} {}", count, what))
+        .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Directory of runs in progress, one `<run-id>.json` per run
+#[derive(Debug, Clone)]
+pub struct RunJournal {
+    dir: PathBuf,
+    temp_dir: PathBuf,
+}
+
+impl Default for RunJournal {
+    /// `~/.roxid/active`
+    fn default() -> Self {
+        Self::new(
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".roxid")
+                .join("active"),
+        )
+    }
+}
+
+impl RunJournal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Look for a run's temp files under `temp_dir` instead of the system one
+    pub fn with_temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = temp_dir.into();
+        self
+    }
+
+    /// Record `run` as in progress until the returned entry is dropped
+    pub fn begin(&self, run: &ActiveRun) -> Result<JournalEntry, String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("failed to create {}: {}", self.dir.display(), e))?;
+        let path = self.dir.join(format!("{}.json", run.run_id));
+        let json = serde_json::to_string_pretty(run).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        Ok(JournalEntry { path })
+    }
+
+    /// Runs whose process exited without finishing them
+    pub fn orphans(&self) -> Vec<ActiveRun> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut runs: Vec<ActiveRun> = entries
+            .filter_map(|entry| {
+                let content = std::fs::read_to_string(entry.ok()?.path()).ok()?;
+                serde_json::from_str::<ActiveRun>(&content).ok()
+            })
+            .filter(|run| !process_alive(run.pid))
+            .collect();
+        runs.sort_by(|a, b| a.run_id.cmp(&b.run_id));
+        runs
+    }
+
+    /// Remove what an orphaned run left behind, keep it in `history` as
+    /// interrupted and drop it from the journal
+    ///
+    /// Containers and compose projects are only removed when docker is
+    /// installed and its daemon answers.
+    pub async fn recover(&self, run: &ActiveRun, history: &RunHistory) -> Cleanup {
+        let mut cleanup = Cleanup::default();
+        if find_executable("docker") {
+            cleanup.containers = remove_containers(run.pid).await;
+            cleanup.compose_projects = remove_compose_projects(run.pid).await;
+        }
+        cleanup.temp_paths = self.remove_temp_paths(run.pid);
+        if let Some(repo) = &run.repo {
+            let _ = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(["worktree", "prune"])
+                .output()
+                .await;
+        }
+
+        if history.load(&run.run_id).is_err() {
+            let _ = history.save(&RunRecord {
+                source: LOCAL_SOURCE.to_string(),
+                run_id: Some(run.run_id.clone()),
+                interrupted: true,
+                ..Default::default()
+            });
+        }
+        let _ = std::fs::remove_file(self.dir.join(format!("{}.json", run.run_id)));
+        cleanup
+    }
+
+    /// Script files, worktrees and compose host overrides of process `pid`
+    fn remove_temp_paths(&self, pid: u32) -> usize {
+        let Ok(entries) = std::fs::read_dir(&self.temp_dir) else {
+            return 0;
+        };
+        let prefixes = [
+            format!("roxid-script-{}-", pid),
+            format!("roxid-worktree-{}-", pid),
+        ];
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                prefixes.iter().any(|prefix| name.starts_with(prefix))
+                    || name
+                        .strip_suffix(".hosts.yml")
+                        .and_then(project_pid)
+                        .is_some_and(|owner| owner == pid)
+            })
+            .filter(|entry| remove_path(&entry.path()))
+            .count()
+    }
+}
+
+/// A run's place in the journal; dropping it marks the run finished
+#[derive(Debug)]
+pub struct JournalEntry {
+    path: PathBuf,
+}
+
+impl Drop for JournalEntry {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn remove_path(path: &Path) -> bool {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path).is_ok()
+    } else {
+        std::fs::remove_file(path).is_ok()
+    }
+}
+
+/// Lines of stdout from a docker command, or none if it failed
+async fn docker_lines(args: &[&str]) -> Vec<String> {
+    match Command::new("docker").args(args).output().await {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+async fn remove_containers(pid: u32) -> usize {
+    let filter = format!("label={}={}", PID_LABEL, pid);
+    let ids = docker_lines(&["ps", "--all", "--quiet", "--filter", &filter]).await;
+    if ids.is_empty() {
+        return 0;
+    }
+    let mut args = vec!["rm", "--force", "--volumes"];
+    args.extend(ids.iter().map(String::as_str));
+    docker_lines(&args).await.len()
+}
+
+async fn remove_compose_projects(pid: u32) -> usize {
+    let mut removed = 0;
+    for name in docker_lines(&["compose", "ls", "--all", "--quiet"]).await {
+        if project_pid(&name) != Some(pid) {
+            continue;
+        }
+        let status = Command::new("docker")
+            .args(["compose", "--project-name", &name, "down"])
+            .args(["--volumes", "--remove-orphans"])
+            .output()
+            .await;
+        if status.is_ok_and(|output| output.status.success()) {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_recover_orphaned_run() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let temp = dir.path().join("tmp");
+        std::fs::create_dir(&temp).unwrap();
+        let journal = RunJournal::new(dir.path().join("active")).with_temp_dir(&temp);
+        let history = RunHistory::new(dir.path().join("runs"));
+
+        let current = ActiveRun {
+            run_id: "01J0000000AAAA".to_string(),
+            pipeline: "ci".to_string(),
+            pid: std::process::id(),
+            repo: None,
+        };
+        let entry = journal.begin(&current).unwrap();
+        assert!(journal.orphans().is_empty());
+        drop(entry);
+        assert!(!dir.path().join("active/01J0000000AAAA.json").exists());
+
+        // No process has the largest pid
+        let crashed = ActiveRun {
+            run_id: "01J0000000BBBB".to_string(),
+            pid: u32::MAX,
+            ..current
+        };
+        std::mem::forget(journal.begin(&crashed).unwrap());
+        std::fs::write(temp.join(format!("roxid-script-{}-1", u32::MAX)), "").unwrap();
+        std::fs::create_dir(temp.join(format!("roxid-worktree-{}-7", u32::MAX))).unwrap();
+        std::fs::write(temp.join("roxid-script-1-1"), "").unwrap();
+        assert_eq!(journal.orphans().len(), 1);
+
+        let cleanup = journal.recover(&crashed, &history).await;
+        assert_eq!(cleanup.temp_paths, 2);
+        assert_eq!(cleanup.to_string(), "2 temp path(s)");
+        assert!(temp.join("roxid-script-1-1").exists());
+        assert!(journal.orphans().is_empty());
+        assert!(history.load("01J0000000BBBB").unwrap().interrupted);
+    }
+}
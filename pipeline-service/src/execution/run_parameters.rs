@@ -0,0 +1,228 @@
+// Run Parameters
+// Values for a pipeline's runtime parameters from --params-file and --param, checked against their declarations
+
+use crate::parser::models::{Parameter, ParameterType};
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Parameter values supplied for a run
+///
+/// ```yaml
+/// environment: staging
+/// regions: [westeurope, northeurope]
+/// deploy:
+///   replicas: 3
+///   canary: true
+/// ```
+///
+/// JSON works too, being YAML. Values given with `--param` replace ones
+/// from the file; declared defaults fill in the rest.
+#[derive(Debug, Clone, Default)]
+pub struct RunParameters {
+    pub values: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl RunParameters {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Self::parse(&content).map_err(|e| format!("invalid {}: {}", path.display(), e))
+    }
+
+    /// Parse a mapping of parameter names to values of any shape
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+        let mapping = match value {
+            serde_yaml::Value::Null => return Ok(Self::default()),
+            serde_yaml::Value::Mapping(mapping) => mapping,
+            _ => return Err("expected a mapping of parameter names to values".to_string()),
+        };
+
+        let mut values = BTreeMap::new();
+        for (name, value) in mapping {
+            match name {
+                serde_yaml::Value::String(name) => values.insert(name, value),
+                other => return Err(format!("parameter names must be strings, got {:?}", other)),
+            };
+        }
+        Ok(Self { values })
+    }
+
+    /// Apply a `name=value` assignment from the command line
+    ///
+    /// The value stays text until [`resolve`](Self::resolve) reads it as the
+    /// declared type, so `--param 'regions=[a, b]'` works for lists.
+    pub fn set(&mut self, assignment: &str) -> Result<(), String> {
+        match assignment.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                self.values.insert(
+                    name.trim().to_string(),
+                    serde_yaml::Value::String(value.to_string()),
+                );
+                Ok(())
+            }
+            _ => Err(format!(
+                "Invalid parameter format '{}'. Expected name=value",
+                assignment
+            )),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Every declared parameter's value, checked against its type and
+    /// allowed values
+    pub fn resolve(
+        &self,
+        declared: &[Parameter],
+    ) -> Result<HashMap<String, serde_yaml::Value>, String> {
+        if let Some(name) = self
+            .values
+            .keys()
+            .find(|name| !declared.iter().any(|p| &p.name == *name))
+        {
+            let known: Vec<&str> = declared.iter().map(|p| p.name.as_str()).collect();
+            return Err(if known.is_empty() {
+                format!(
+                    "unknown parameter '{}': the pipeline declares no parameters",
+                    name
+                )
+            } else {
+                format!(
+                    "unknown parameter '{}'; the pipeline declares: {}",
+                    name,
+                    known.join(", ")
+                )
+            });
+        }
+
+        let mut resolved = HashMap::new();
+        for param in declared {
+            let value = match (self.values.get(&param.name), &param.default) {
+                (Some(value), _) => coerce(value, &param.param_type),
+                (None, Some(default)) => default.clone(),
+                (None, None) => {
+                    return Err(format!(
+                        "parameter '{}' has no default and was not provided",
+                        param.name
+                    ))
+                }
+            };
+            if !param.param_type.accepts(&value) {
+                return Err(format!(
+                    "parameter '{}' expects a {}, got {}",
+                    param.name,
+                    param.param_type.name(),
+                    describe(&value)
+                ));
+            }
+            if let Some(allowed) = &param.values {
+                if !allowed.contains(&value) {
+                    let allowed: Vec<String> = allowed.iter().map(describe).collect();
+                    return Err(format!(
+                        "parameter '{}' must be one of {}, got {}",
+                        param.name,
+                        allowed.join(", "),
+                        describe(&value)
+                    ));
+                }
+            }
+            resolved.insert(param.name.clone(), value);
+        }
+        Ok(resolved)
+    }
+}
+
+/// Read command-line text as the declared type; other values are left as is
+fn coerce(value: &serde_yaml::Value, param_type: &ParameterType) -> serde_yaml::Value {
+    match (value, param_type) {
+        (serde_yaml::Value::String(_), ParameterType::String) => value.clone(),
+        (serde_yaml::Value::String(text), _) => {
+            serde_yaml::from_str(text).unwrap_or_else(|_| value.clone())
+        }
+        _ => value.clone(),
+    }
+}
+
+/// A value as it would be written inline in YAML
+fn describe(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(s) => format!("'{}'", s),
+        serde_yaml::Value::Mapping(_) => "a mapping".to_string(),
+        serde_yaml::Value::Sequence(_) => "a list".to_string(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declared() -> Vec<Parameter> {
+        serde_yaml::from_str(
+            r#"
+- name: environment
+  values: [dev, staging, prod]
+- name: replicas
+  type: number
+  default: 1
+- name: regions
+  type: object
+  default: []
+- name: canary
+  type: boolean
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_run_parameters() {
+        let mut params = RunParameters::parse(
+            r#"{"environment": "staging", "regions": ["westeurope"], "canary": false}"#,
+        )
+        .unwrap();
+        params.set("replicas=3").unwrap();
+        params.set("canary=true").unwrap();
+
+        let resolved = params.resolve(&declared()).unwrap();
+        assert_eq!(resolved["environment"], serde_yaml::Value::from("staging"));
+        assert_eq!(resolved["replicas"], serde_yaml::Value::from(3));
+        assert_eq!(resolved["canary"], serde_yaml::Value::Bool(true));
+        assert!(resolved["regions"].is_sequence());
+
+        params.set("regions=[a, b]").unwrap();
+        let resolved = params.resolve(&declared()).unwrap();
+        assert_eq!(resolved["regions"].as_sequence().unwrap().len(), 2);
+
+        let mut missing = RunParameters::default();
+        missing.set("environment=dev").unwrap();
+        let err = missing.resolve(&declared()).unwrap_err();
+        assert!(err.contains("'canary' has no default"), "{}", err);
+
+        params.set("environment=qa").unwrap();
+        let err = params.resolve(&declared()).unwrap_err();
+        assert!(
+            err.contains("must be one of 'dev', 'staging', 'prod'"),
+            "{}",
+            err
+        );
+
+        params.set("environment=dev").unwrap();
+        params.set("replicas=many").unwrap();
+        let err = params.resolve(&declared()).unwrap_err();
+        assert!(err.contains("'replicas' expects a number"), "{}", err);
+
+        params.set("colour=red").unwrap();
+        let err = params.resolve(&declared()).unwrap_err();
+        assert!(err.contains("unknown parameter 'colour'"), "{}", err);
+
+        assert!(RunParameters::parse("- a\n").is_err());
+        assert!(params.set("=x").is_err());
+    }
+}
@@ -0,0 +1,732 @@
+// Run Records
+// Portable per-step record of a run, importable from Azure DevOps build timelines
+
+use crate::execution::executor::ExecutionResult;
+use crate::parser::models::JobResult;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Source label of records created from local runs
+pub const LOCAL_SOURCE: &str = "local";
+
+/// Source label of records imported from Azure DevOps timelines
+pub const AZURE_DEVOPS_SOURCE: &str = "Azure DevOps";
+
+/// Steps Azure DevOps adds to every job that have no counterpart in the YAML
+const SYSTEM_TASKS: &[&str] = &[
+    "Initialize job",
+    "Initialize containers",
+    "Finalize Job",
+    "Stop Containers",
+    "Report build status",
+];
+
+/// Stage, job and step outcomes of one pipeline run
+///
+/// Results use the Azure DevOps vocabulary (`succeeded`, `succeededWithIssues`,
+/// `failed`, `canceled`, `skipped`) so local and cloud runs compare directly.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    /// Where the run happened (`local` or `Azure DevOps`)
+    pub source: String,
+    /// roxid's ID for a local run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub stages: Vec<StageRecord>,
+    /// Digest each container image of a local run resolved to
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub images: BTreeMap<String, String>,
+    /// The run never finished: roxid crashed or was killed while it ran
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageRecord {
+    pub name: String,
+    pub result: String,
+    pub duration_seconds: f64,
+    pub jobs: Vec<JobRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRecord {
+    pub name: String,
+    pub result: String,
+    pub duration_seconds: f64,
+    /// Output variables (`step.name`) of local runs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, String>,
+    /// Steps in the order they ran
+    pub steps: Vec<StepRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepRecord {
+    /// Display name, falling back to the step name
+    pub name: String,
+    pub result: String,
+    pub duration_seconds: f64,
+    /// Exit code of local script and task steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Environment a local step's command got, secrets masked
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl RunRecord {
+    /// Record a local run
+    pub fn from_result(result: &ExecutionResult) -> Self {
+        let stages = result
+            .stages
+            .iter()
+            .map(|stage| StageRecord {
+                name: stage.stage_name.clone(),
+                result: result_name(&stage.status),
+                duration_seconds: stage.duration.as_secs_f64(),
+                // Matrix instances as one job, as Azure DevOps timelines have them
+                jobs: JobResult::combine_instances(&stage.jobs)
+                    .iter()
+                    .map(|job| JobRecord {
+                        name: job.job_name.clone(),
+                        result: result_name(&job.status),
+                        duration_seconds: job.duration.as_secs_f64(),
+                        outputs: job
+                            .outputs
+                            .iter()
+                            .map(|(name, value)| (name.clone(), value.clone()))
+                            .collect(),
+                        steps: job
+                            .steps
+                            .iter()
+                            .enumerate()
+                            .map(|(i, step)| StepRecord {
+                                name: step
+                                    .display_name
+                                    .clone()
+                                    .or_else(|| step.step_name.clone())
+                                    .unwrap_or_else(|| format!("step {}", i + 1)),
+                                result: result_name(&step.status),
+                                duration_seconds: step.duration.as_secs_f64(),
+                                exit_code: step.exit_code,
+                                env: step.env.iter().cloned().collect(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            source: LOCAL_SOURCE.to_string(),
+            run_id: Some(result.run_id.clone()).filter(|id| !id.is_empty()),
+            stages,
+            images: result.images.clone(),
+            interrupted: false,
+        }
+    }
+
+    /// Convert a build timeline exported from the Azure DevOps REST API
+    /// (`_apis/build/builds/{id}/timeline`)
+    ///
+    /// Stage, phase, job and task records are nested through `parentId` and
+    /// ordered by `order`. Matrix and parallel instances of a job are merged
+    /// into one job, steps in instance order, as roxid reports them. The
+    /// implicit `__default` stage and job of pipelines without stages or jobs
+    /// are renamed to the names roxid gives them (`Build` and `Job`). Steps
+    /// Azure DevOps adds to every job (`Initialize job`, `Post-job: ...`)
+    /// are left out.
+    pub fn from_ado_timeline(json: &str) -> Result<Self, String> {
+        let timeline: Timeline =
+            serde_json::from_str(json).map_err(|e| format!("invalid timeline: {}", e))?;
+
+        let mut children: HashMap<Option<&str>, Vec<&TimelineRecord>> = HashMap::new();
+        for record in &timeline.records {
+            children
+                .entry(record.parent_id.as_deref())
+                .or_default()
+                .push(record);
+        }
+        for records in children.values_mut() {
+            records.sort_by_key(|r| r.order.unwrap_or(i64::MAX));
+        }
+        let children_of = |parent: &str, kind: &str| -> Vec<&TimelineRecord> {
+            children
+                .get(&Some(parent))
+                .map(|records| {
+                    records
+                        .iter()
+                        .copied()
+                        .filter(|r| r.record_type == kind)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut stage_records: Vec<&TimelineRecord> = timeline
+            .records
+            .iter()
+            .filter(|r| r.record_type == "Stage")
+            .collect();
+        if stage_records.is_empty() {
+            return Err("timeline has no stage records".to_string());
+        }
+        stage_records.sort_by_key(|r| r.order.unwrap_or(i64::MAX));
+
+        let mut stages = Vec::new();
+        for stage in stage_records {
+            let stage_name = match stage.identifier_or_name() {
+                "__default" => "Build".to_string(),
+                name => name.to_string(),
+            };
+
+            let mut jobs = Vec::new();
+            for phase in children_of(&stage.id, "Phase") {
+                let job_name = match phase.identifier_or_name().rsplit('.').next() {
+                    Some("__default") | None => "Job".to_string(),
+                    Some(name) => name.to_string(),
+                };
+
+                let instances = children_of(&phase.id, "Job");
+                let mut steps = Vec::new();
+                let mut duration = 0.0;
+                for instance in &instances {
+                    duration += instance.duration_seconds()?;
+                    for task in children_of(&instance.id, "Task") {
+                        if is_system_task(&task.name) {
+                            continue;
+                        }
+                        steps.push(StepRecord {
+                            name: task.name.clone(),
+                            result: task.result(),
+                            duration_seconds: task.duration_seconds()?,
+                            exit_code: None,
+                            env: BTreeMap::new(),
+                        });
+                    }
+                }
+
+                jobs.push(JobRecord {
+                    name: job_name,
+                    result: combined_result(instances.iter().map(|r| r.result()), phase),
+                    duration_seconds: if instances.is_empty() {
+                        phase.duration_seconds()?
+                    } else {
+                        duration
+                    },
+                    outputs: BTreeMap::new(),
+                    steps,
+                });
+            }
+
+            stages.push(StageRecord {
+                name: stage_name,
+                result: stage.result(),
+                duration_seconds: stage.duration_seconds()?,
+                jobs,
+            });
+        }
+
+        Ok(Self {
+            source: AZURE_DEVOPS_SOURCE.to_string(),
+            run_id: None,
+            stages,
+            images: BTreeMap::new(),
+            interrupted: false,
+        })
+    }
+
+    /// Read a run record, or an Azure DevOps timeline export
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Self::from_json(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parse a run record, or an Azure DevOps timeline export
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("invalid JSON: {}", e))?;
+        if value.get("records").is_some() {
+            Self::from_ado_timeline(content)
+        } else {
+            serde_json::from_value(value).map_err(|e| format!("invalid run record: {}", e))
+        }
+    }
+
+    /// Write the record as JSON
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+    }
+
+    /// Jobs keyed by `Stage.Job`, in run order
+    pub fn jobs(&self) -> impl Iterator<Item = (String, &JobRecord)> {
+        self.stages.iter().flat_map(|stage| {
+            stage
+                .jobs
+                .iter()
+                .map(move |job| (format!("{}.{}", stage.name, job.name), job))
+        })
+    }
+}
+
+/// Duration of one job or step in two runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonRow {
+    /// `Stage.Job` for jobs, `Stage.Job/step` for steps
+    pub key: String,
+    pub is_step: bool,
+    pub left: Option<f64>,
+    pub right: Option<f64>,
+}
+
+/// Differences between two runs of the same pipeline
+///
+/// Jobs are matched by `Stage.Job` and steps by name within a job, so steps
+/// need the same display names in both runs to line up. Repeated step names
+/// are matched by occurrence.
+#[derive(Debug, Clone)]
+pub struct RunComparison {
+    pub left_source: String,
+    pub right_source: String,
+    /// Jobs and their steps, in the left run's order followed by anything
+    /// that only ran on the right
+    pub rows: Vec<ComparisonRow>,
+    /// Missing jobs and steps, ordering and result mismatches
+    pub differences: Vec<String>,
+}
+
+impl RunComparison {
+    pub fn new(left: &RunRecord, right: &RunRecord) -> Self {
+        let mut rows = Vec::new();
+        let mut differences = Vec::new();
+        let (left_source, right_source) = if left.source == right.source {
+            (
+                format!("{} (1)", left.source),
+                format!("{} (2)", right.source),
+            )
+        } else {
+            (left.source.clone(), right.source.clone())
+        };
+
+        let right_jobs: HashMap<String, &JobRecord> = right.jobs().collect();
+        let left_keys: Vec<String> = left.jobs().map(|(key, _)| key).collect();
+
+        for (key, job) in left.jobs() {
+            let other = right_jobs.get(&key).copied();
+            rows.push(ComparisonRow {
+                key: key.clone(),
+                is_step: false,
+                left: Some(job.duration_seconds),
+                right: other.map(|j| j.duration_seconds),
+            });
+            let Some(other) = other else {
+                differences.push(format!("job '{}' only ran in {}", key, left_source));
+                continue;
+            };
+            if job.result != other.result {
+                differences.push(format!(
+                    "job '{}' {} in {} but {} in {}",
+                    key, job.result, left_source, other.result, right_source
+                ));
+            }
+
+            let left_steps = occurrences(&job.steps);
+            let right_steps = occurrences(&other.steps);
+            let right_index: HashMap<&(String, usize), usize> = right_steps
+                .iter()
+                .enumerate()
+                .map(|(i, id)| (id, i))
+                .collect();
+
+            for (id, step) in left_steps.iter().zip(&job.steps) {
+                let other_step = right_index.get(id).map(|&i| &other.steps[i]);
+                rows.push(ComparisonRow {
+                    key: format!("{}/{}", key, step.name),
+                    is_step: true,
+                    left: Some(step.duration_seconds),
+                    right: other_step.map(|s| s.duration_seconds),
+                });
+                match other_step {
+                    None => differences.push(format!(
+                        "step '{}/{}' only ran in {}",
+                        key, step.name, left_source
+                    )),
+                    Some(other_step) if other_step.result != step.result => {
+                        differences.push(format!(
+                            "step '{}/{}' {} in {} but {} in {}",
+                            key,
+                            step.name,
+                            step.result,
+                            left_source,
+                            other_step.result,
+                            right_source
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            let left_set: std::collections::HashSet<&(String, usize)> = left_steps.iter().collect();
+            for (id, step) in right_steps.iter().zip(&other.steps) {
+                if !left_set.contains(id) {
+                    rows.push(ComparisonRow {
+                        key: format!("{}/{}", key, step.name),
+                        is_step: true,
+                        left: None,
+                        right: Some(step.duration_seconds),
+                    });
+                    differences.push(format!(
+                        "step '{}/{}' only ran in {}",
+                        key, step.name, right_source
+                    ));
+                }
+            }
+
+            // Steps present in both runs should run in the same order
+            let left_order: Vec<&str> = left_steps
+                .iter()
+                .filter(|id| right_index.contains_key(id))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            let right_order: Vec<&str> = right_steps
+                .iter()
+                .filter(|id| left_set.contains(id))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            if left_order != right_order {
+                differences.push(format!(
+                    "steps of job '{}' ran in a different order: {} in {}, {} in {}",
+                    key,
+                    left_order.join(", "),
+                    left_source,
+                    right_order.join(", "),
+                    right_source
+                ));
+            }
+        }
+
+        for (key, job) in right.jobs() {
+            if !left_keys.contains(&key) {
+                rows.push(ComparisonRow {
+                    key: key.clone(),
+                    is_step: false,
+                    left: None,
+                    right: Some(job.duration_seconds),
+                });
+                differences.push(format!("job '{}' only ran in {}", key, right_source));
+            }
+        }
+
+        Self {
+            left_source,
+            right_source,
+            rows,
+            differences,
+        }
+    }
+}
+
+/// Step identities as `(name, occurrence)` so repeated names line up in order
+fn occurrences(steps: &[StepRecord]) -> Vec<(String, usize)> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    steps
+        .iter()
+        .map(|step| {
+            let count = seen.entry(step.name.as_str()).or_default();
+            *count += 1;
+            (step.name.clone(), *count)
+        })
+        .collect()
+}
+
+/// Azure DevOps result name for a stage, job or step status
+/// (`SucceededWithIssues` -> `succeededWithIssues`)
+fn result_name(status: &impl std::fmt::Debug) -> String {
+    let name = format!("{:?}", status);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_system_task(name: &str) -> bool {
+    SYSTEM_TASKS.contains(&name) || name.starts_with("Post-job: ") || name.starts_with("Pre-job: ")
+}
+
+/// Worst result across job instances, falling back to the phase's own result
+fn combined_result(results: impl Iterator<Item = String>, phase: &TimelineRecord) -> String {
+    const SEVERITY: &[&str] = &[
+        "failed",
+        "canceled",
+        "succeededWithIssues",
+        "succeeded",
+        "skipped",
+    ];
+    results
+        .min_by_key(|r| {
+            SEVERITY
+                .iter()
+                .position(|s| s == r)
+                .unwrap_or(SEVERITY.len())
+        })
+        .unwrap_or_else(|| phase.result())
+}
+
+#[derive(Deserialize)]
+struct Timeline {
+    records: Vec<TimelineRecord>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimelineRecord {
+    id: String,
+    parent_id: Option<String>,
+    #[serde(rename = "type")]
+    record_type: String,
+    #[serde(default)]
+    name: String,
+    identifier: Option<String>,
+    order: Option<i64>,
+    start_time: Option<String>,
+    finish_time: Option<String>,
+    result: Option<String>,
+}
+
+impl TimelineRecord {
+    fn identifier_or_name(&self) -> &str {
+        self.identifier.as_deref().unwrap_or(&self.name)
+    }
+
+    fn result(&self) -> String {
+        match self.result.as_deref() {
+            // Abandoned records never ran to completion
+            Some("abandoned") => "canceled".to_string(),
+            Some(result) => result.to_string(),
+            None => "pending".to_string(),
+        }
+    }
+
+    fn duration_seconds(&self) -> Result<f64, String> {
+        match (&self.start_time, &self.finish_time) {
+            (Some(start), Some(finish)) => {
+                Ok((parse_timestamp(finish)? - parse_timestamp(start)?).max(0.0))
+            }
+            _ => Ok(0.0),
+        }
+    }
+}
+
+/// Seconds since the Unix epoch for an RFC 3339 timestamp such as
+/// `2024-05-01T10:15:30.1233333Z`
+fn parse_timestamp(timestamp: &str) -> Result<f64, String> {
+    let invalid = || format!("invalid timestamp '{}'", timestamp);
+    let (date, time) = timestamp.split_once('T').ok_or_else(invalid)?;
+
+    let mut date_parts = date.splitn(3, '-');
+    let mut next_date = || -> Result<i64, String> {
+        date_parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)
+    };
+    let (year, month, day) = (next_date()?, next_date()?, next_date()?);
+
+    let (clock, offset) = if let Some(clock) = time.strip_suffix('Z') {
+        (clock, 0.0)
+    } else if let Some(pos) = time.rfind(['+', '-']) {
+        let (clock, offset) = time.split_at(pos);
+        let sign = if offset.starts_with('-') { -1.0 } else { 1.0 };
+        let (hours, minutes) = offset[1..].split_once(':').ok_or_else(invalid)?;
+        let hours: f64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: f64 = minutes.parse().map_err(|_| invalid())?;
+        (clock, sign * (hours * 3600.0 + minutes * 60.0))
+    } else {
+        (time, 0.0)
+    };
+
+    let mut clock_parts = clock.splitn(3, ':');
+    let hours: f64 = clock_parts
+        .next()
+        .and_then(|p| p.parse().ok())
+        .ok_or_else(invalid)?;
+    let minutes: f64 = clock_parts
+        .next()
+        .and_then(|p| p.parse().ok())
+        .ok_or_else(invalid)?;
+    let seconds: f64 = clock_parts
+        .next()
+        .and_then(|p| p.parse().ok())
+        .ok_or_else(invalid)?;
+
+    Ok(days_from_civil(year, month, day) as f64 * 86400.0
+        + hours * 3600.0
+        + minutes * 60.0
+        + seconds
+        - offset)
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMELINE: &str = r#"{
+  "records": [
+    {"id": "s1", "parentId": null, "type": "Stage", "name": "Build", "identifier": "Build",
+     "order": 1, "startTime": "2024-05-01T10:00:00Z", "finishTime": "2024-05-01T10:02:00Z",
+     "result": "succeeded"},
+    {"id": "p1", "parentId": "s1", "type": "Phase", "name": "Compile code", "identifier": "Build.Compile",
+     "order": 1, "startTime": "2024-05-01T10:00:00Z", "finishTime": "2024-05-01T10:01:30Z",
+     "result": "succeeded"},
+    {"id": "j1", "parentId": "p1", "type": "Job", "name": "Compile code", "identifier": "Build.Compile.__default",
+     "order": 1, "startTime": "2024-05-01T10:00:00.5000000Z", "finishTime": "2024-05-01T10:01:30.5000000Z",
+     "result": "succeeded"},
+    {"id": "t1", "parentId": "j1", "type": "Task", "name": "Initialize job", "order": 1,
+     "startTime": "2024-05-01T10:00:00Z", "finishTime": "2024-05-01T10:00:02Z", "result": "succeeded"},
+    {"id": "t3", "parentId": "j1", "type": "Task", "name": "Test", "order": 3,
+     "startTime": "2024-05-01T10:00:30Z", "finishTime": "2024-05-01T10:01:20Z", "result": "failed"},
+    {"id": "t2", "parentId": "j1", "type": "Task", "name": "Restore", "order": 2,
+     "startTime": "2024-05-01T11:00:02+01:00", "finishTime": "2024-05-01T10:00:30Z", "result": "succeeded"},
+    {"id": "t4", "parentId": "j1", "type": "Task", "name": "Post-job: Checkout", "order": 4,
+     "startTime": "2024-05-01T10:01:20Z", "finishTime": "2024-05-01T10:01:21Z", "result": "succeeded"},
+    {"id": "s2", "parentId": null, "type": "Stage", "name": "Deploy", "identifier": "Deploy",
+     "order": 2, "startTime": null, "finishTime": null, "result": "skipped"}
+  ]
+}"#;
+
+    fn step(name: &str, result: &str, duration: f64) -> StepRecord {
+        StepRecord {
+            name: name.to_string(),
+            result: result.to_string(),
+            duration_seconds: duration,
+            exit_code: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    fn record(source: &str, steps: Vec<StepRecord>) -> RunRecord {
+        RunRecord {
+            source: source.to_string(),
+            run_id: None,
+            stages: vec![StageRecord {
+                name: "Build".to_string(),
+                result: "succeeded".to_string(),
+                duration_seconds: 10.0,
+                jobs: vec![JobRecord {
+                    name: "Compile".to_string(),
+                    result: "succeeded".to_string(),
+                    duration_seconds: 10.0,
+                    outputs: BTreeMap::new(),
+                    steps,
+                }],
+            }],
+            images: BTreeMap::new(),
+            interrupted: false,
+        }
+    }
+
+    #[test]
+    fn test_import_ado_timeline() {
+        let record = RunRecord::from_json(TIMELINE).unwrap();
+        assert_eq!(record.source, AZURE_DEVOPS_SOURCE);
+        assert_eq!(record.stages.len(), 2);
+
+        let build = &record.stages[0];
+        assert_eq!(build.duration_seconds, 120.0);
+        assert_eq!(build.jobs[0].name, "Compile");
+        assert_eq!(build.jobs[0].result, "succeeded");
+        assert_eq!(build.jobs[0].duration_seconds, 90.0);
+        assert_eq!(
+            build.jobs[0].steps,
+            vec![
+                step("Restore", "succeeded", 28.0),
+                step("Test", "failed", 50.0),
+            ]
+        );
+
+        let deploy = &record.stages[1];
+        assert_eq!(deploy.result, "skipped");
+        assert!(deploy.jobs.is_empty());
+
+        // Round-trips through the record format
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(RunRecord::from_json(&json).unwrap(), record);
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let local = record(
+            LOCAL_SOURCE,
+            vec![
+                step("Test", "succeeded", 5.0),
+                step("Restore", "succeeded", 1.0),
+                step("Lint", "succeeded", 1.0),
+            ],
+        );
+        let cloud = record(
+            AZURE_DEVOPS_SOURCE,
+            vec![
+                step("Checkout", "succeeded", 3.0),
+                step("Restore", "succeeded", 28.0),
+                step("Test", "failed", 50.0),
+            ],
+        );
+
+        let comparison = RunComparison::new(&local, &cloud);
+        assert_eq!(
+            comparison.differences,
+            vec![
+                "step 'Build.Compile/Test' succeeded in local but failed in Azure DevOps",
+                "step 'Build.Compile/Lint' only ran in local",
+                "step 'Build.Compile/Checkout' only ran in Azure DevOps",
+                "steps of job 'Build.Compile' ran in a different order: Test, Restore in local, \
+                 Restore, Test in Azure DevOps",
+            ]
+        );
+        let restore = comparison
+            .rows
+            .iter()
+            .find(|r| r.key == "Build.Compile/Restore")
+            .unwrap();
+        assert_eq!((restore.left, restore.right), (Some(1.0), Some(28.0)));
+        assert_eq!(comparison.rows.len(), 5);
+
+        assert!(RunComparison::new(&local, &local).differences.is_empty());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z").unwrap(), 0.0);
+        assert_eq!(
+            parse_timestamp("2000-03-01T00:00:01.5Z").unwrap(),
+            951_868_801.5
+        );
+        assert_eq!(
+            parse_timestamp("2000-03-01T02:00:00+02:00").unwrap(),
+            951_868_800.0
+        );
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+}
@@ -0,0 +1,148 @@
+// Ready Queue Scheduler
+// Releases graph nodes for execution as soon as their dependencies complete
+
+use std::collections::{BTreeSet, HashMap};
+
+/// Tracks which nodes of a dependency graph can start
+///
+/// Nodes are identified by their index in declaration order. A node becomes
+/// ready once every dependency has completed, whatever its outcome; deciding
+/// whether to skip it after a failed dependency is left to the caller. When
+/// several nodes are ready, the one declared first is started first, so a
+/// concurrency limit of one runs nodes in a stable order.
+#[derive(Debug, Clone)]
+pub struct ReadyQueue {
+    /// Number of unfinished dependencies per node
+    pending: Vec<usize>,
+    /// Nodes that depend on each node
+    dependents: Vec<Vec<usize>>,
+    /// Nodes whose dependencies have all completed and that haven't started
+    ready: BTreeSet<usize>,
+    /// Number of nodes started but not yet completed
+    running: usize,
+}
+
+impl ReadyQueue {
+    /// Create a queue from `(name, dependencies)` pairs in declaration order
+    ///
+    /// Dependencies on names that aren't part of the graph are ignored; the
+    /// graph is expected to have been validated already.
+    pub fn new<'a>(nodes: impl IntoIterator<Item = (&'a str, &'a [String])>) -> Self {
+        let nodes: Vec<(&str, &[String])> = nodes.into_iter().collect();
+        let indices: HashMap<&str, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (*name, i))
+            .collect();
+
+        let mut pending = vec![0; nodes.len()];
+        let mut dependents = vec![Vec::new(); nodes.len()];
+        for (i, (_, deps)) in nodes.iter().enumerate() {
+            for dep in deps.iter() {
+                if let Some(&dep_index) = indices.get(dep.as_str()) {
+                    pending[i] += 1;
+                    dependents[dep_index].push(i);
+                }
+            }
+        }
+
+        let ready = (0..nodes.len()).filter(|&i| pending[i] == 0).collect();
+        Self {
+            pending,
+            dependents,
+            ready,
+            running: 0,
+        }
+    }
+
+    /// The node [`next_ready`](Self::next_ready) would take, without taking it
+    pub fn peek_ready(&self) -> Option<usize> {
+        self.ready.first().copied()
+    }
+
+    /// Take the next node that can start, marking it as running
+    pub fn next_ready(&mut self) -> Option<usize> {
+        let index = self.ready.pop_first()?;
+        self.running += 1;
+        Some(index)
+    }
+
+    /// Mark a running node as completed, releasing its dependents
+    pub fn complete(&mut self, index: usize) {
+        self.running = self.running.saturating_sub(1);
+        for &dependent in &self.dependents[index] {
+            self.pending[dependent] -= 1;
+            if self.pending[dependent] == 0 {
+                self.ready.insert(dependent);
+            }
+        }
+    }
+
+    /// Number of nodes currently running
+    pub fn running(&self) -> usize {
+        self.running
+    }
+}
+
+/// Concurrency limit for a configured maximum, where 0 means unlimited
+pub fn concurrency_limit(max: usize) -> usize {
+    if max == 0 {
+        usize::MAX
+    } else {
+        max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(nodes: &[(&'static str, Vec<String>)]) -> ReadyQueue {
+        ReadyQueue::new(nodes.iter().map(|(name, deps)| (*name, deps.as_slice())))
+    }
+
+    #[test]
+    fn test_node_starts_when_its_dependencies_complete() {
+        // A ─► B ─► D, C has no dependencies, E waits for the slow chain
+        let mut q = queue(&[
+            ("A", vec![]),
+            ("B", vec!["A".into()]),
+            ("C", vec![]),
+            ("D", vec!["B".into()]),
+            ("E", vec!["D".into(), "C".into()]),
+        ]);
+
+        assert_eq!(q.next_ready(), Some(0));
+        assert_eq!(q.next_ready(), Some(2));
+        assert_eq!(q.next_ready(), None);
+        assert_eq!(q.running(), 2);
+
+        // B doesn't wait for C, which would share its level in a level-based plan
+        q.complete(0);
+        assert_eq!(q.next_ready(), Some(1));
+        q.complete(1);
+        assert_eq!(q.next_ready(), Some(3));
+        q.complete(3);
+        assert_eq!(q.next_ready(), None);
+
+        q.complete(2);
+        assert_eq!(q.next_ready(), Some(4));
+        q.complete(4);
+        assert_eq!(q.running(), 0);
+        assert_eq!(q.next_ready(), None);
+    }
+
+    #[test]
+    fn test_declaration_order_breaks_ties() {
+        let mut q = queue(&[("A", vec![]), ("B", vec!["A".into()]), ("C", vec![])]);
+
+        let mut order = Vec::new();
+        while let Some(index) = q.next_ready() {
+            order.push(index);
+            q.complete(index);
+        }
+        assert_eq!(order, vec![0, 1, 2]);
+        assert_eq!(concurrency_limit(0), usize::MAX);
+        assert_eq!(concurrency_limit(3), 3);
+    }
+}
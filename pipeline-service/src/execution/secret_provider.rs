@@ -0,0 +1,213 @@
+// Secret Providers
+// Local sources for variable groups and key vaults: a decrypting command (sops, age, ...) or inline values
+
+use crate::execution::local_vars::LocalVariables;
+use crate::parser::models::{Pipeline, Variable};
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use serde::Deserialize;
+
+/// Where a variable group's or key vault's values come from, in `.roxid.toml`
+///
+/// ```toml
+/// [variable-groups.app-secrets]
+/// command = "sops --decrypt --output-type json secrets/app.enc.json"
+///
+/// [variable-groups.app-settings]
+/// secret = false
+/// variables = { region = "westeurope" }
+///
+/// [key-vaults.kv-dev]
+/// command = "age --decrypt -i ~/.config/age/key.txt secrets/kv-dev.env.age"
+/// ```
+///
+/// The command runs with `sh -c` in the working directory and prints a
+/// JSON or YAML mapping, or `NAME=value` lines.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecretProvider {
+    /// Command that prints the values
+    pub command: Option<String>,
+    /// Values defined in place; the command's output overrides them
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Mask the values in step output (default: true)
+    #[serde(default = "default_secret")]
+    pub secret: bool,
+}
+
+fn default_secret() -> bool {
+    true
+}
+
+impl SecretProvider {
+    /// Run the command, if any, and return every value
+    pub fn load(&self, working_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+        let mut values = self.variables.clone();
+        if let Some(command) = &self.command {
+            let output = Command::new("sh")
+                .args(["-c", command])
+                .current_dir(working_dir)
+                .output()
+                .map_err(|e| format!("failed to run '{}': {}", command, e))?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(format!(
+                    "'{}' failed: {}",
+                    command,
+                    stderr.lines().next().unwrap_or("no error output").trim()
+                ));
+            }
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            values.extend(parse_values(&stdout).map_err(|e| {
+                format!(
+                    "'{}' printed something that isn't a set of values: {}",
+                    command, e
+                )
+            })?);
+        }
+        Ok(values)
+    }
+}
+
+/// Parse a JSON or YAML mapping of scalars, or `NAME=value` lines
+fn parse_values(text: &str) -> Result<BTreeMap<String, String>, String> {
+    let looks_like_mapping = text
+        .lines()
+        .find(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .is_some_and(|line| !line.contains('=') || line.trim_start().starts_with('{'));
+    if looks_like_mapping {
+        return LocalVariables::parse(text);
+    }
+
+    let mut values = BTreeMap::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=value, got '{}'", line))?;
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        values.insert(name.trim().to_string(), value.to_string());
+    }
+    Ok(values)
+}
+
+/// Variable groups referenced at pipeline, stage or job level, in order of appearance
+pub fn referenced_groups(pipeline: &Pipeline) -> Vec<String> {
+    let jobs = pipeline.stages.iter().flat_map(|s| &s.jobs);
+    let lists = std::iter::once(&pipeline.variables)
+        .chain(pipeline.stages.iter().map(|s| &s.variables))
+        .chain(jobs.map(|j| &j.variables));
+
+    let mut groups: Vec<String> = Vec::new();
+    for variable in lists.flatten() {
+        if let Variable::Group { group } = variable {
+            if !groups.contains(group) {
+                groups.push(group.clone());
+            }
+        }
+    }
+    groups
+}
+
+/// Secrets an `AzureKeyVault` step downloads: all of them for `*`, else the
+/// comma-separated names in its `SecretsFilter` input
+pub fn filter_secrets(
+    values: &BTreeMap<String, String>,
+    filter: Option<&str>,
+) -> Result<BTreeMap<String, String>, String> {
+    let filter = filter.map(str::trim).unwrap_or("*");
+    if filter == "*" || filter.is_empty() {
+        return Ok(values.clone());
+    }
+    filter
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            values
+                .get(name)
+                .map(|value| (name.to_string(), value.clone()))
+                .ok_or_else(|| format!("secret '{}' not found", name))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    #[test]
+    fn test_provider_command_output() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let provider = SecretProvider {
+            command: Some(
+                "printf 'export DB_PASSWORD=\"p@ss\"\\n# comment\\nAPI_KEY=k1\\n'".into(),
+            ),
+            variables: BTreeMap::from([("region".to_string(), "westeurope".to_string())]),
+            secret: true,
+        };
+        let values = provider.load(dir.path()).unwrap();
+        assert_eq!(values["DB_PASSWORD"], "p@ss");
+        assert_eq!(values["API_KEY"], "k1");
+        assert_eq!(values["region"], "westeurope");
+
+        let json = SecretProvider {
+            command: Some(r#"echo '{"token": "abc", "port": 5432}'"#.into()),
+            variables: BTreeMap::new(),
+            secret: true,
+        };
+        assert_eq!(json.load(dir.path()).unwrap()["port"], "5432");
+
+        let failing = SecretProvider {
+            command: Some("echo 'no key found' >&2; exit 1".into()),
+            variables: BTreeMap::new(),
+            secret: true,
+        };
+        assert!(failing
+            .load(dir.path())
+            .unwrap_err()
+            .contains("no key found"));
+
+        let filtered = filter_secrets(&values, Some("API_KEY, region")).unwrap();
+        assert_eq!(filtered.len(), 2);
+        assert!(filter_secrets(&values, Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_referenced_groups() {
+        let pipeline = normalize_pipeline(
+            AzureParser::parse(
+                r#"
+variables:
+  - group: shared
+  - name: a
+    value: b
+stages:
+  - stage: Deploy
+    variables:
+      - group: deploy-secrets
+      - group: shared
+    jobs:
+      - job: Run
+        steps:
+          - script: echo
+"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(referenced_groups(&pipeline), ["shared", "deploy-secrets"]);
+    }
+}
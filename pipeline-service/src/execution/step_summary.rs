@@ -0,0 +1,122 @@
+// Step Summaries
+// Collects markdown published by steps via $GITHUB_STEP_SUMMARY or ##vso[task.uploadsummary]
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Environment variable pointing steps at their summary file
+pub const STEP_SUMMARY_ENV: &str = "GITHUB_STEP_SUMMARY";
+
+/// Logging command that attaches a markdown file as the step summary
+const UPLOAD_SUMMARY_COMMAND: &str = "##vso[task.uploadsummary]";
+
+static NEXT_SUMMARY_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Temporary markdown file a step can append its summary to
+///
+/// The file is removed when dropped.
+#[derive(Debug)]
+pub struct SummaryFile {
+    path: PathBuf,
+}
+
+impl SummaryFile {
+    /// Create an empty summary file in the system temp directory
+    pub fn create() -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "roxid-step-summary-{}-{}.md",
+            std::process::id(),
+            NEXT_SUMMARY_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, "")?;
+        Ok(Self { path })
+    }
+
+    /// Path exposed to the step through `GITHUB_STEP_SUMMARY`
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Collect the step's summary from this file and any uploaded summary files
+    ///
+    /// Relative `##vso[task.uploadsummary]` paths are resolved against `working_dir`.
+    /// Returns `None` when the step published nothing.
+    pub fn collect(&self, stdout: &str, working_dir: &Path) -> Option<String> {
+        let mut sections = Vec::new();
+
+        if let Ok(content) = std::fs::read_to_string(&self.path) {
+            sections.push(content);
+        }
+
+        for path in uploaded_summaries(stdout) {
+            let path = working_dir.join(path);
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                sections.push(content);
+            }
+        }
+
+        let summary = sections
+            .iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        if summary.is_empty() {
+            None
+        } else {
+            Some(summary)
+        }
+    }
+}
+
+impl Drop for SummaryFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Paths passed to `##vso[task.uploadsummary]` in step output
+pub fn uploaded_summaries(stdout: &str) -> Vec<&str> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix(UPLOAD_SUMMARY_COMMAND))
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uploaded_summaries() {
+        let stdout = "building\n##vso[task.uploadsummary]report.md\n  ##vso[task.uploadsummary] /tmp/b.md\n##vso[task.uploadsummary]\n";
+        assert_eq!(uploaded_summaries(stdout), vec!["report.md", "/tmp/b.md"]);
+    }
+
+    #[test]
+    fn test_collect_summary_file_and_uploads() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("report.md"), "## Report\n").unwrap();
+
+        let file = SummaryFile::create().unwrap();
+        std::fs::write(file.path(), "# Build\n- ok\n").unwrap();
+
+        let summary = file
+            .collect("##vso[task.uploadsummary]report.md", dir.path())
+            .unwrap();
+        assert_eq!(summary, "# Build\n- ok\n\n## Report");
+    }
+
+    #[test]
+    fn test_collect_empty_summary() {
+        let file = SummaryFile::create().unwrap();
+        assert!(file.collect("no commands", Path::new(".")).is_none());
+
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,403 @@
+// File Watching
+// Polls a repository for changed files and picks the pipelines they trigger ('roxid watch')
+
+use crate::parser::models::{PathFilter, Trigger, TriggerConfig};
+use crate::parser::{AzureParser, TemplateEngine};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::Deserialize;
+
+/// How often the repository is scanned for changes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Quiet period after the last change before pipelines run
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Paths left out of watching unless `ignore` is set
+pub const DEFAULT_IGNORE: [&str; 2] = ["target", "node_modules"];
+
+/// Where `roxid watch --daemon` writes its output: `~/.roxid/watch.log`
+pub fn daemon_log_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".roxid")
+        .join("watch.log")
+}
+
+/// `roxid watch` settings (`[watch]` in `.roxid.toml`)
+///
+/// ```toml
+/// [watch]
+/// debounce-ms = 1000
+/// ignore = ["target", "dist"]
+///
+/// [watch.pipelines]
+/// "ci/build.yml" = ["src", "Cargo.toml"]
+/// "ci/docs.yml" = ["docs/*.md"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Quiet period after the last change before pipelines run
+    pub debounce_ms: Option<u64>,
+    /// Paths never watched besides `.git` (default: target, node_modules)
+    pub ignore: Option<Vec<String>>,
+    /// Pipelines to watch and the paths that run each, in place of the
+    /// pipelines' own `trigger:` paths
+    #[serde(default)]
+    pub pipelines: BTreeMap<PathBuf, Vec<String>>,
+}
+
+impl WatchConfig {
+    pub fn debounce(&self) -> Duration {
+        self.debounce_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_DEBOUNCE)
+    }
+
+    pub fn ignore(&self) -> Vec<String> {
+        self.ignore
+            .clone()
+            .unwrap_or_else(|| DEFAULT_IGNORE.iter().map(|p| p.to_string()).collect())
+    }
+
+    /// The rule for `pipeline`, relative to `dir`: its `[watch.pipelines]`
+    /// paths, else its trigger's; `None` for `trigger: none`
+    pub fn rule(&self, dir: &Path, pipeline: &Path) -> Result<Option<WatchRule>, String> {
+        let paths = match self.pipelines.get(pipeline) {
+            Some(paths) => PathFilter {
+                include: paths.clone(),
+                exclude: Vec::new(),
+            },
+            None => match trigger_paths(&dir.join(pipeline))? {
+                Some(paths) => paths,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(WatchRule {
+            pipeline: pipeline.to_path_buf(),
+            paths,
+            sources: pipeline_sources(dir, pipeline),
+        }))
+    }
+}
+
+/// The paths a pipeline's CI trigger watches: every path when it has no
+/// path filter, `None` for `trigger: none`
+fn trigger_paths(pipeline: &Path) -> Result<Option<PathFilter>, String> {
+    let parsed = AzureParser::parse_file(pipeline)
+        .map_err(|e| format!("{}: {}", pipeline.display(), e.message))?;
+    Ok(match parsed.trigger {
+        Some(Trigger::None) => None,
+        Some(Trigger::Full(TriggerConfig {
+            paths: Some(paths), ..
+        })) => Some(paths),
+        _ => Some(PathFilter::default()),
+    })
+}
+
+/// The pipeline file and every template it includes, repository-relative
+///
+/// A pipeline that no longer resolves keeps the templates read before the
+/// error, so fixing one of them still runs it.
+fn pipeline_sources(dir: &Path, pipeline: &Path) -> Vec<String> {
+    let mut sources = vec![pipeline.to_string_lossy().replace('\\', "/")];
+    let Ok(parsed) = AzureParser::parse_file(dir.join(pipeline)) else {
+        return sources;
+    };
+    let mut engine = TemplateEngine::new(dir.to_path_buf());
+    let _ = engine.resolve_pipeline(parsed);
+    for include in engine.trace() {
+        if !sources.contains(&include.template) {
+            sources.push(include.template.clone());
+        }
+    }
+    sources
+}
+
+/// A pipeline and the changed paths that run it
+#[derive(Debug, Clone)]
+pub struct WatchRule {
+    pub pipeline: PathBuf,
+    pub paths: PathFilter,
+    /// The pipeline file and the templates it includes, which run it
+    /// whenever they change
+    pub sources: Vec<String>,
+}
+
+impl WatchRule {
+    pub fn matches(&self, changed: &[String]) -> bool {
+        changed
+            .iter()
+            .any(|path| self.paths.matches(path) || self.sources.contains(path))
+    }
+
+    /// Resolve the pipeline again, picking up templates added or dropped
+    /// since the last run
+    pub fn refresh_sources(&mut self, dir: &Path) {
+        self.sources = pipeline_sources(dir, &self.pipeline);
+    }
+}
+
+/// Modification time and size of every watched file, by
+/// repository-relative path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileSnapshot(BTreeMap<String, (Option<SystemTime>, u64)>);
+
+impl FileSnapshot {
+    /// Scan `root`, leaving out `.git` and the `ignore` paths
+    pub fn scan(root: &Path, ignore: &[String]) -> Self {
+        let ignored = PathFilter {
+            include: ignore.to_vec(),
+            exclude: Vec::new(),
+        };
+        let mut files = BTreeMap::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(relative) = path.strip_prefix(root) else {
+                    continue;
+                };
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                if relative == ".git" || (!ignore.is_empty() && ignored.matches(&relative)) {
+                    continue;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(path);
+                } else {
+                    files.insert(relative, (metadata.modified().ok(), metadata.len()));
+                }
+            }
+        }
+        Self(files)
+    }
+
+    /// Paths added, removed or modified since `earlier`, sorted
+    pub fn changes_since(&self, earlier: &Self) -> Vec<String> {
+        let mut changed: BTreeSet<&String> = self
+            .0
+            .iter()
+            .filter(|(path, stamp)| earlier.0.get(*path) != Some(stamp))
+            .map(|(path, _)| path)
+            .collect();
+        changed.extend(earlier.0.keys().filter(|path| !self.0.contains_key(*path)));
+        changed.into_iter().cloned().collect()
+    }
+}
+
+/// Changes that settled, and the pipelines they run
+#[derive(Debug, Clone)]
+pub struct WatchBatch {
+    pub changed: Vec<String>,
+    pub pipelines: Vec<PathBuf>,
+}
+
+/// Polls a repository and batches changes until they settle
+#[derive(Debug)]
+pub struct Watcher {
+    root: PathBuf,
+    ignore: Vec<String>,
+    rules: Vec<WatchRule>,
+    debounce: Duration,
+    snapshot: FileSnapshot,
+}
+
+impl Watcher {
+    pub fn new(root: &Path, rules: Vec<WatchRule>, config: &WatchConfig) -> Self {
+        let ignore = config.ignore();
+        Self {
+            snapshot: FileSnapshot::scan(root, &ignore),
+            root: root.to_path_buf(),
+            ignore,
+            rules,
+            debounce: config.debounce(),
+        }
+    }
+
+    /// Wait until files change and then stay unchanged for the debounce
+    /// period, and some pipeline watches one of them
+    pub async fn next_batch(&mut self) -> WatchBatch {
+        let mut changed = BTreeSet::new();
+        let mut last_change: Option<Instant> = None;
+        loop {
+            tokio::time::sleep(POLL_INTERVAL.min(self.debounce)).await;
+            let current = self.scan().await;
+            let changes = current.changes_since(&self.snapshot);
+            if !changes.is_empty() {
+                changed.extend(changes);
+                last_change = Some(Instant::now());
+                self.snapshot = current;
+                continue;
+            }
+            if last_change.is_none_or(|at| at.elapsed() < self.debounce) {
+                continue;
+            }
+
+            let changed_paths: Vec<String> = std::mem::take(&mut changed).into_iter().collect();
+            last_change = None;
+            let pipelines: Vec<PathBuf> = self
+                .rules
+                .iter()
+                .filter(|rule| rule.matches(&changed_paths))
+                .map(|rule| rule.pipeline.clone())
+                .collect();
+            if !pipelines.is_empty() {
+                return WatchBatch {
+                    changed: changed_paths,
+                    pipelines,
+                };
+            }
+        }
+    }
+
+    /// Take the repository as it is now as the baseline, so changes the
+    /// pipelines just made don't run them again, and re-resolve the
+    /// pipelines' templates
+    pub async fn rescan(&mut self) {
+        self.snapshot = self.scan().await;
+        for rule in &mut self.rules {
+            rule.refresh_sources(&self.root);
+        }
+    }
+
+    async fn scan(&self) -> FileSnapshot {
+        let root = self.root.clone();
+        let ignore = self.ignore.clone();
+        tokio::task::spawn_blocking(move || FileSnapshot::scan(&root, &ignore))
+            .await
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("README.md"), "hi").unwrap();
+        let ignore = WatchConfig::default().ignore();
+        let before = FileSnapshot::scan(root, &ignore);
+
+        std::fs::write(root.join("src/main.rs"), "fn main() { run() }").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        std::fs::remove_file(root.join("README.md")).unwrap();
+        std::fs::write(root.join("target/debug/app"), "bin").unwrap();
+        std::fs::write(root.join(".git/index"), "").unwrap();
+        let after = FileSnapshot::scan(root, &ignore);
+
+        assert_eq!(
+            after.changes_since(&before),
+            ["README.md", "src/lib.rs", "src/main.rs"]
+        );
+        assert!(after.changes_since(&after).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watcher_runs_matching_pipelines() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("build.yml"),
+            "trigger:\n  paths:\n    include: [src]\nsteps:\n  - script: make\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("all.yml"), "steps:\n  - script: make\n").unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+
+        let config: WatchConfig =
+            toml::from_str("debounce-ms = 50\n[pipelines]\n\"docs.yml\" = [\"docs/*.md\"]\n")
+                .unwrap();
+        let rules = ["build.yml", "all.yml", "docs.yml"]
+            .iter()
+            .map(|path| config.rule(root, Path::new(path)).unwrap().unwrap())
+            .collect();
+        let mut watcher = Watcher::new(root, rules, &config);
+
+        std::fs::write(root.join("docs/guide.md"), "# Guide").unwrap();
+        let batch = tokio::time::timeout(Duration::from_secs(10), watcher.next_batch())
+            .await
+            .unwrap();
+        assert_eq!(batch.changed, ["docs/guide.md"]);
+        assert_eq!(
+            batch.pipelines,
+            [PathBuf::from("all.yml"), PathBuf::from("docs.yml")]
+        );
+
+        std::fs::write(root.join("src/main.rs"), "").unwrap();
+        let batch = tokio::time::timeout(Duration::from_secs(10), watcher.next_batch())
+            .await
+            .unwrap();
+        assert_eq!(
+            batch.pipelines,
+            [PathBuf::from("build.yml"), PathBuf::from("all.yml")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watcher_follows_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("templates")).unwrap();
+        std::fs::write(
+            root.join("build.yml"),
+            "trigger:\n  paths:\n    include: [src]\nsteps:\n  - template: templates/build.yml\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("templates/build.yml"),
+            "steps:\n  - script: make\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("templates/test.yml"),
+            "steps:\n  - script: test\n",
+        )
+        .unwrap();
+
+        let config: WatchConfig = toml::from_str("debounce-ms = 50\n").unwrap();
+        let rule = config.rule(root, Path::new("build.yml")).unwrap().unwrap();
+        assert_eq!(rule.sources, ["build.yml", "templates/build.yml"]);
+        let mut watcher = Watcher::new(root, vec![rule], &config);
+
+        // A nested template the next run picks up
+        std::fs::write(
+            root.join("templates/build.yml"),
+            "steps:\n  - template: templates/test.yml\n",
+        )
+        .unwrap();
+        let batch = tokio::time::timeout(Duration::from_secs(10), watcher.next_batch())
+            .await
+            .unwrap();
+        assert_eq!(batch.pipelines, [PathBuf::from("build.yml")]);
+        watcher.rescan().await;
+
+        std::fs::write(
+            root.join("templates/test.yml"),
+            "steps:\n  - script: cargo test\n",
+        )
+        .unwrap();
+        let batch = tokio::time::timeout(Duration::from_secs(10), watcher.next_batch())
+            .await
+            .unwrap();
+        assert_eq!(batch.changed, ["templates/test.yml"]);
+        assert_eq!(batch.pipelines, [PathBuf::from("build.yml")]);
+    }
+}
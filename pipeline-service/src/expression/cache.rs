@@ -0,0 +1,133 @@
+// Expression Cache
+// Parsed expressions keyed by their source text, so conditions repeated across steps and matrix instances are parsed once
+
+use crate::expression::parser::{Expr, ExprParser, ParseExprError};
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Entries kept by the shared cache
+pub const DEFAULT_CACHE_CAPACITY: usize = 4096;
+
+/// Cache of parsed expression ASTs
+///
+/// When the cache is full it is emptied rather than evicting entry by entry;
+/// pipelines repeat a small set of expressions, so this rarely happens.
+#[derive(Debug)]
+pub struct ExpressionCache {
+    entries: Mutex<HashMap<String, Arc<Expr>>>,
+    /// Maximum number of entries (0 disables caching)
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Hit and miss counts of an expression cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl ExpressionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The process-wide cache used by expression engines by default
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<ExpressionCache>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| Arc::new(Self::new(DEFAULT_CACHE_CAPACITY)))
+            .clone()
+    }
+
+    /// Parse `source`, reusing the AST from an earlier call when there is one
+    ///
+    /// Parse errors aren't cached.
+    pub fn parse(&self, source: &str) -> Result<Arc<Expr>, ParseExprError> {
+        if let Some(expr) = self.lock().get(source) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(expr.clone());
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let expr = Arc::new(ExprParser::parse_str(source)?);
+        if self.capacity > 0 {
+            let mut entries = self.lock();
+            if entries.len() >= self.capacity {
+                entries.clear();
+            }
+            entries.insert(source.to_string(), expr.clone());
+        }
+        Ok(expr)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<Expr>>> {
+        // A panic while holding the lock can't leave the map half-updated
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for ExpressionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuses_parsed_expressions() {
+        let cache = ExpressionCache::new(2);
+
+        let first = cache.parse("eq(variables.os, 'linux')").unwrap();
+        let second = cache.parse("eq(variables.os, 'linux')").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+        assert!(cache.parse("eq(").is_err());
+        assert_eq!(cache.len(), 1);
+
+        // Filling the cache starts it over
+        cache.parse("a").unwrap();
+        cache.parse("b").unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_caching() {
+        let cache = ExpressionCache::new(0);
+        cache.parse("succeeded()").unwrap();
+        cache.parse("succeeded()").unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().misses, 2);
+    }
+}
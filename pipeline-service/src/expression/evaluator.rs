@@ -1,12 +1,14 @@
 // Expression Engine Evaluator
 // Evaluates AST expressions with context (variables, parameters, etc.)
 
+use crate::expression::cache::ExpressionCache;
 use crate::expression::functions::BuiltinFunctions;
 use crate::expression::parser::{BinaryOp, Expr, Reference, ReferencePart, UnaryOp};
-use crate::parser::models::Value;
+use crate::parser::models::{Value, VariableSubstitution};
 
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 
 /// Evaluation error
 #[derive(Debug, Clone)]
@@ -59,6 +61,9 @@ pub struct ExpressionContext {
 
     /// Resources context
     pub resources: ResourcesContext,
+
+    /// CI-parity mode: reject roxid-only extensions such as `env()`
+    pub strict: bool,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -675,18 +680,29 @@ impl<'a> Evaluator<'a> {
 /// High-level expression engine
 pub struct ExpressionEngine {
     context: ExpressionContext,
+    /// Parsed expressions, shared with every other engine by default
+    cache: Arc<ExpressionCache>,
 }
 
 impl ExpressionEngine {
     pub fn new(context: ExpressionContext) -> Self {
-        Self { context }
+        Self {
+            context,
+            cache: ExpressionCache::shared(),
+        }
+    }
+
+    /// Parse expressions through `cache` instead of the shared one
+    pub fn with_cache(mut self, cache: Arc<ExpressionCache>) -> Self {
+        self.cache = cache;
+        self
     }
 
     /// Evaluate a compile-time expression: ${{ expression }}
     pub fn evaluate_compile_time(&self, expr: &str) -> Result<Value, EvalError> {
-        use crate::expression::parser::ExprParser;
-
-        let ast = ExprParser::parse_str(expr)
+        let ast = self
+            .cache
+            .parse(expr)
             .map_err(|e| EvalError::new(format!("parse error: {}", e)))?;
 
         let evaluator = Evaluator::new(&self.context);
@@ -701,30 +717,46 @@ impl ExpressionEngine {
 
     /// Substitute macro variables: $(variableName)
     pub fn substitute_macros(&self, text: &str) -> Result<String, EvalError> {
+        self.substitute_macros_audited(text)
+            .map(|(result, _)| result)
+    }
+
+    /// Substitute macro variables and record each substitution that was made
+    pub fn substitute_macros_audited(
+        &self,
+        text: &str,
+    ) -> Result<(String, Vec<VariableSubstitution>), EvalError> {
         use crate::expression::lexer::{extract_expressions, ExpressionType};
 
         let expressions = extract_expressions(text);
         let mut result = String::new();
+        let mut substitutions = Vec::new();
 
         for expr in expressions {
-            match expr {
-                ExpressionType::Text(s) => result.push_str(&s),
+            let (name, value) = match expr {
+                ExpressionType::Text(s) => {
+                    result.push_str(&s);
+                    continue;
+                }
                 ExpressionType::Macro(var_path) => {
                     let value = self.resolve_variable_path(&var_path)?;
-                    result.push_str(&value.as_string());
+                    (var_path, value)
                 }
                 ExpressionType::CompileTime(expr) => {
                     let value = self.evaluate_compile_time(&expr)?;
-                    result.push_str(&value.as_string());
+                    (expr.trim().to_string(), value)
                 }
                 ExpressionType::Runtime(expr) => {
                     let value = self.evaluate_runtime(&expr)?;
-                    result.push_str(&value.as_string());
+                    (expr.trim().to_string(), value)
                 }
-            }
+            };
+            let value = value.as_string();
+            result.push_str(&value);
+            substitutions.push(VariableSubstitution::new(name, value));
         }
 
-        Ok(result)
+        Ok((result, substitutions))
     }
 
     fn resolve_variable_path(&self, path: &str) -> Result<Value, EvalError> {
@@ -939,6 +971,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_substitute_macros_audited() {
+        let engine = ExpressionEngine::new(make_context());
+
+        let (text, subs) = engine
+            .substitute_macros_audited("echo $(foo) ${{ parameters.config }}")
+            .unwrap();
+        assert_eq!(text, "echo bar Release");
+        assert_eq!(
+            subs,
+            vec![
+                VariableSubstitution::new("foo", "bar"),
+                VariableSubstitution::new("parameters.config", "Release"),
+            ]
+        );
+    }
+
     #[test]
     fn test_undefined_variable() {
         let engine = ExpressionEngine::new(make_context());
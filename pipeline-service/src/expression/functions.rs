@@ -2,8 +2,11 @@
 // Implements all standard Azure DevOps expression functions
 
 use crate::expression::evaluator::{EvalError, ExpressionContext};
+use crate::expression::version::{self, Version};
 use crate::parser::models::Value;
 
+use std::cmp::Ordering;
+
 /// Registry of built-in functions
 pub struct BuiltinFunctions;
 
@@ -51,6 +54,10 @@ impl BuiltinFunctions {
             // Conversion functions
             "converttojson" => self.fn_convert_to_json(args),
 
+            // Version functions
+            "semvercompare" => self.fn_semver_compare(args),
+            "semversatisfies" => self.fn_semver_satisfies(args),
+
             // Status functions (context-aware)
             "succeeded" => self.fn_succeeded(args, context),
             "failed" => self.fn_failed(args, context),
@@ -61,6 +68,7 @@ impl BuiltinFunctions {
             // Utility functions
             "coalesce" => self.fn_coalesce(args),
             "counter" => self.fn_counter(args),
+            "env" => self.fn_env(args, context),
             "iif" => self.fn_iif(args),
             "length" => self.fn_length(args),
 
@@ -84,26 +92,30 @@ impl BuiltinFunctions {
 
     fn fn_lt(&self, args: Vec<Value>) -> Result<Value, EvalError> {
         self.require_args(&args, 2, "lt")?;
-        let (a, b) = self.as_numbers(&args[0], &args[1])?;
-        Ok(Value::Bool(a < b))
+        Ok(Value::Bool(
+            self.compare_ordered(&args[0], &args[1])?.is_lt(),
+        ))
     }
 
     fn fn_le(&self, args: Vec<Value>) -> Result<Value, EvalError> {
         self.require_args(&args, 2, "le")?;
-        let (a, b) = self.as_numbers(&args[0], &args[1])?;
-        Ok(Value::Bool(a <= b))
+        Ok(Value::Bool(
+            self.compare_ordered(&args[0], &args[1])?.is_le(),
+        ))
     }
 
     fn fn_gt(&self, args: Vec<Value>) -> Result<Value, EvalError> {
         self.require_args(&args, 2, "gt")?;
-        let (a, b) = self.as_numbers(&args[0], &args[1])?;
-        Ok(Value::Bool(a > b))
+        Ok(Value::Bool(
+            self.compare_ordered(&args[0], &args[1])?.is_gt(),
+        ))
     }
 
     fn fn_ge(&self, args: Vec<Value>) -> Result<Value, EvalError> {
         self.require_args(&args, 2, "ge")?;
-        let (a, b) = self.as_numbers(&args[0], &args[1])?;
-        Ok(Value::Bool(a >= b))
+        Ok(Value::Bool(
+            self.compare_ordered(&args[0], &args[1])?.is_ge(),
+        ))
     }
 
     fn fn_in(&self, args: Vec<Value>) -> Result<Value, EvalError> {
@@ -288,7 +300,9 @@ impl BuiltinFunctions {
         if args.is_empty() {
             // Check current job status
             if let Some(job) = &context.job {
-                return Ok(Value::Bool(job.status.succeeded && !job.status.failed));
+                return Ok(Value::Bool(
+                    job.status.succeeded && !job.status.failed && !job.status.canceled,
+                ));
             }
             // If no job context, default to true (pipeline is still running)
             return Ok(Value::Bool(true));
@@ -374,6 +388,33 @@ impl BuiltinFunctions {
         }
     }
 
+    // =========================================================================
+    // Version Functions
+    // =========================================================================
+
+    /// semverCompare(a, b) - returns -1, 0 or 1
+    fn fn_semver_compare(&self, args: Vec<Value>) -> Result<Value, EvalError> {
+        self.require_args(&args, 2, "semverCompare")?;
+        let a = self.as_version(&args[0], "semverCompare")?;
+        let b = self.as_version(&args[1], "semverCompare")?;
+        let result = match a.cmp(&b) {
+            Ordering::Less => -1.0,
+            Ordering::Equal => 0.0,
+            Ordering::Greater => 1.0,
+        };
+        Ok(Value::Number(result))
+    }
+
+    /// semverSatisfies(version, range) - e.g. semverSatisfies('1.4.2', '>=1.2 <2')
+    fn fn_semver_satisfies(&self, args: Vec<Value>) -> Result<Value, EvalError> {
+        self.require_args(&args, 2, "semverSatisfies")?;
+        let version = self.as_version(&args[0], "semverSatisfies")?;
+        let range = args[1].as_string();
+        version::satisfies(&version, &range)
+            .map(Value::Bool)
+            .map_err(|e| EvalError::new(format!("semverSatisfies(): {}", e)))
+    }
+
     // =========================================================================
     // Utility Functions
     // =========================================================================
@@ -402,6 +443,36 @@ impl BuiltinFunctions {
         Ok(Value::Number(seed))
     }
 
+    /// env(name, default) - reads the local environment (roxid extension)
+    ///
+    /// Variables set on the context win over the process environment, so
+    /// values passed to the run take precedence over the shell's.
+    fn fn_env(&self, args: Vec<Value>, context: &ExpressionContext) -> Result<Value, EvalError> {
+        if context.strict {
+            return Err(EvalError::new(
+                "env() is not available in strict mode (Azure DevOps has no env() function)",
+            ));
+        }
+        if args.is_empty() || args.len() > 2 {
+            return Err(EvalError::new(format!(
+                "env() requires 1 or 2 argument(s), got {}",
+                args.len()
+            )));
+        }
+
+        let name = args[0].as_string();
+        if let Some(value) = context.env.get(&name) {
+            return Ok(value.clone());
+        }
+        match std::env::var(&name) {
+            Ok(value) => Ok(Value::String(value)),
+            Err(_) => Ok(args
+                .get(1)
+                .cloned()
+                .unwrap_or_else(|| Value::String(String::new()))),
+        }
+    }
+
     fn fn_iif(&self, args: Vec<Value>) -> Result<Value, EvalError> {
         self.require_args(&args, 3, "iif")?;
         if args[0].is_truthy() {
@@ -490,6 +561,31 @@ impl BuiltinFunctions {
         }
     }
 
+    /// Order two values numerically, falling back to dotted-version ordering
+    /// when either side is a version string such as `1.10.2`
+    fn compare_ordered(&self, a: &Value, b: &Value) -> Result<Ordering, EvalError> {
+        if let (Some(a), Some(b)) = (a.as_number(), b.as_number()) {
+            return a
+                .partial_cmp(&b)
+                .ok_or_else(|| EvalError::new("cannot compare NaN"));
+        }
+        if let (Some(a), Some(b)) = (
+            Version::parse(&a.as_string()),
+            Version::parse(&b.as_string()),
+        ) {
+            return Ok(a.cmp(&b));
+        }
+        let (a, b) = self.as_numbers(a, b)?;
+        a.partial_cmp(&b)
+            .ok_or_else(|| EvalError::new("cannot compare NaN"))
+    }
+
+    fn as_version(&self, value: &Value, name: &str) -> Result<Version, EvalError> {
+        let text = value.as_string();
+        Version::parse(&text)
+            .ok_or_else(|| EvalError::new(format!("{}(): '{}' is not a valid version", name, text)))
+    }
+
     fn as_numbers(&self, a: &Value, b: &Value) -> Result<(f64, f64), EvalError> {
         let a_num = a
             .as_number()
@@ -671,6 +767,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_env() {
+        assert_eq!(
+            eval("env('PATH')"),
+            Value::String(std::env::var("PATH").unwrap())
+        );
+        assert_eq!(
+            eval("env('ROXID_TEST_UNSET_VARIABLE', 'fallback')"),
+            Value::String("fallback".to_string())
+        );
+        assert_eq!(
+            eval("env('ROXID_TEST_UNSET_VARIABLE')"),
+            Value::String(String::new())
+        );
+
+        // The context's environment wins over the process environment
+        let mut ctx = ExpressionContext::default();
+        ctx.env
+            .insert("PATH".to_string(), Value::String("/override".to_string()));
+        let result = BuiltinFunctions::new()
+            .call("env", vec![Value::String("PATH".to_string())], &ctx)
+            .unwrap();
+        assert_eq!(result, Value::String("/override".to_string()));
+    }
+
+    #[test]
+    fn test_env_strict() {
+        let ctx = ExpressionContext {
+            strict: true,
+            ..Default::default()
+        };
+        let result =
+            BuiltinFunctions::new().call("env", vec![Value::String("PATH".to_string())], &ctx);
+        assert!(result.unwrap_err().message.contains("strict mode"));
+    }
+
     #[test]
     fn test_iif() {
         assert_eq!(
@@ -688,6 +820,54 @@ mod tests {
         assert_eq!(eval("length('hello')"), Value::Number(5.0));
     }
 
+    // =========================================================================
+    // Version Functions
+    // =========================================================================
+
+    #[test]
+    fn test_version_ordering_in_comparisons() {
+        assert_eq!(eval("gt('1.10.0', '1.9.2')"), Value::Bool(true));
+        assert_eq!(eval("lt('v2.0', '2.0.1')"), Value::Bool(true));
+        assert_eq!(eval("ge('1.2', '1.2.0')"), Value::Bool(true));
+        assert_eq!(eval("le(3, '3.0.1')"), Value::Bool(true));
+        // Plain numbers keep numeric semantics
+        assert_eq!(eval("gt('1.10', '1.9')"), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_semver_compare() {
+        assert_eq!(eval("semverCompare('1.2.10', '1.2.9')"), Value::Number(1.0));
+        assert_eq!(
+            eval("semverCompare('1.0.0-rc.1', '1.0.0')"),
+            Value::Number(-1.0)
+        );
+        assert_eq!(eval("semverCompare('v1.2', '1.2.0')"), Value::Number(0.0));
+    }
+
+    #[test]
+    fn test_semver_satisfies() {
+        assert_eq!(
+            eval("semverSatisfies('1.4.2', '>=1.2.0 <2.0.0')"),
+            Value::Bool(true)
+        );
+        assert_eq!(eval("semverSatisfies('2.1.0', '^1.2')"), Value::Bool(false));
+        assert_eq!(eval("semverSatisfies('8.0.1', '~8.0')"), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_semver_invalid_version() {
+        let ctx = ExpressionContext::default();
+        let result = BuiltinFunctions::new().call(
+            "semverCompare",
+            vec![
+                Value::String("latest".to_string()),
+                Value::String("1.0".to_string()),
+            ],
+            &ctx,
+        );
+        assert!(result.is_err());
+    }
+
     // =========================================================================
     // Status Functions
     // =========================================================================
@@ -633,6 +633,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_lexer_numbers() {
         let mut lexer = Lexer::new("42 3.14 0");
         let tokens = lexer.tokenize().unwrap();
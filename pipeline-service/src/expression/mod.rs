@@ -1,11 +1,14 @@
 // Expression Engine Module
 // Full Azure DevOps expression support: ${{ }}, $[ ], and $(var)
 
+pub mod cache;
 pub mod evaluator;
 pub mod functions;
 pub mod lexer;
 pub mod parser;
+pub mod version;
 
+pub use cache::{CacheStats, ExpressionCache};
 pub use evaluator::{
     AgentContext, DependenciesContext, EvalError, Evaluator, ExpressionContext, ExpressionEngine,
     JobContext, JobDependency, JobStatusContext, PipelineContext, PipelineResourceContext,
@@ -15,3 +18,4 @@ pub use evaluator::{
 pub use functions::BuiltinFunctions;
 pub use lexer::{extract_expressions, ExpressionType, LexError, Lexer, Token};
 pub use parser::{BinaryOp, Expr, ExprParser, ParseExprError, Reference, ReferencePart, UnaryOp};
+pub use version::Version;
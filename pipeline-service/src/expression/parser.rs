@@ -86,6 +86,72 @@ impl Reference {
         self.parts.push(ReferencePart::Index(Box::new(index)));
         self
     }
+
+    /// `name` for `parameters.name` or `parameters['name']`
+    pub fn parameter_name(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [ReferencePart::Property(root), part, ..] if root == "parameters" => match part {
+                ReferencePart::Property(name) => Some(name),
+                ReferencePart::Index(index) => match index.as_ref() {
+                    Expr::String(name) => Some(name),
+                    _ => None,
+                },
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Expr {
+    /// Every context reference in the expression, including those nested in
+    /// function arguments and index expressions
+    pub fn references(&self) -> Vec<&Reference> {
+        let mut found = Vec::new();
+        self.collect_references(&mut found);
+        found
+    }
+
+    fn collect_references<'a>(&'a self, found: &mut Vec<&'a Reference>) {
+        match self {
+            Expr::Null | Expr::Bool(_) | Expr::Number(_) | Expr::String(_) => {}
+            Expr::Reference(reference) => {
+                found.push(reference);
+                for part in &reference.parts {
+                    if let ReferencePart::Index(index) = part {
+                        index.collect_references(found);
+                    }
+                }
+            }
+            Expr::FunctionCall { args, .. } | Expr::Array(args) => {
+                args.iter().for_each(|arg| arg.collect_references(found));
+            }
+            Expr::Index { object, index } => {
+                object.collect_references(found);
+                index.collect_references(found);
+            }
+            Expr::Member { object, .. } | Expr::Unary { expr: object, .. } => {
+                object.collect_references(found);
+            }
+            Expr::Binary { left, right, .. } => {
+                left.collect_references(found);
+                right.collect_references(found);
+            }
+            Expr::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                condition.collect_references(found);
+                then_expr.collect_references(found);
+                else_expr.collect_references(found);
+            }
+            Expr::Object(fields) => {
+                fields
+                    .iter()
+                    .for_each(|(_, value)| value.collect_references(found));
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -606,6 +672,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parameter_references() {
+        let expr = ExprParser::parse_str(
+            "format('{0}-{1}', parameters.service, coalesce(parameters['env'], variables.x))",
+        )
+        .unwrap();
+        let names: Vec<Option<&str>> = expr
+            .references()
+            .iter()
+            .map(|r| r.parameter_name())
+            .collect();
+        assert_eq!(names, [Some("service"), Some("env"), None]);
+    }
+
     #[test]
     fn test_parse_index_access() {
         let expr = ExprParser::parse_str("variables['foo']").unwrap();
@@ -0,0 +1,313 @@
+// Version Comparison
+// Dotted version ordering and semver range matching for expression functions
+
+use std::cmp::Ordering;
+
+/// A parsed dotted version such as `1.10.2`, `v2.0` or `3.1.0-rc.1+build.5`
+///
+/// Any number of release components is accepted; missing trailing components
+/// compare as zero, so `1.2` equals `1.2.0`. Build metadata is ignored.
+#[derive(Debug, Clone, Eq)]
+pub struct Version {
+    /// Numeric release components (`major.minor.patch...`)
+    pub release: Vec<u64>,
+    /// Pre-release identifiers following `-`
+    pub pre: Vec<String>,
+}
+
+impl Version {
+    /// Parse a version, allowing a leading `v`
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        let s = s.split_once('+').map(|(v, _)| v).unwrap_or(s);
+        let (release, pre) = match s.split_once('-') {
+            Some((release, pre)) => (release, Some(pre)),
+            None => (s, None),
+        };
+
+        let release = release
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+
+        let pre = match pre {
+            Some(pre) if pre.split('.').any(str::is_empty) => return None,
+            Some(pre) => pre.split('.').map(str::to_string).collect(),
+            None => Vec::new(),
+        };
+
+        Some(Self { release, pre })
+    }
+
+    fn component(&self, index: usize) -> u64 {
+        self.release.get(index).copied().unwrap_or(0)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.release.len().max(other.release.len());
+        for i in 0..len {
+            match self.component(i).cmp(&other.component(i)) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+
+        // A pre-release sorts before the release it precedes
+        match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => compare_pre_release(&self.pre, &other.pre),
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+/// Compare pre-release identifiers: numeric identifiers numerically and
+/// below alphanumeric ones, alphanumeric identifiers lexically
+fn compare_pre_release(a: &[String], b: &[String]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Check whether `version` satisfies a semver range
+///
+/// Supports comparators (`>=1.2`, `<2`, `=1.4.0`, `!=1.3.0`), caret (`^1.2`) and
+/// tilde (`~1.2.3`) ranges, wildcards (`1.x`, `1.2.*`, `*`), space- or
+/// comma-separated intersections and `||` unions.
+pub fn satisfies(version: &Version, range: &str) -> Result<bool, String> {
+    let mut matched = false;
+
+    for alternative in range.split("||") {
+        let mut tokens = alternative
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty());
+
+        let mut all = true;
+        while let Some(token) = tokens.next() {
+            // Allow a space between the operator and the version (`>= 1.2`)
+            let comparator = if token.chars().all(|c| "<>=!^~".contains(c)) {
+                let version = tokens
+                    .next()
+                    .ok_or_else(|| format!("missing version after '{}'", token))?;
+                format!("{}{}", token, version)
+            } else {
+                token.to_string()
+            };
+            all &= comparator_matches(version, &comparator)?;
+        }
+        matched |= all;
+    }
+
+    Ok(matched)
+}
+
+/// A comparator operand, which may leave trailing components unspecified
+struct Partial {
+    /// Components written explicitly (`1.2.x` yields `[1, 2]`)
+    components: Vec<u64>,
+    /// Fewer than three components given, or a wildcard used
+    is_partial: bool,
+    /// The fully specified version
+    version: Version,
+}
+
+impl Partial {
+    fn parse(s: &str) -> Result<Self, String> {
+        let is_wildcard = |p: &str| matches!(p, "x" | "X" | "*");
+
+        if s.is_empty() || is_wildcard(s) {
+            return Ok(Self {
+                components: Vec::new(),
+                is_partial: true,
+                version: Version {
+                    release: Vec::new(),
+                    pre: Vec::new(),
+                },
+            });
+        }
+
+        let stripped = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        let release = stripped.split(['-', '+']).next().unwrap_or(stripped);
+        let parts: Vec<&str> = release.split('.').collect();
+        let specified = parts.iter().take_while(|p| !is_wildcard(p)).count();
+
+        if parts[specified..].iter().any(|p| !is_wildcard(p)) {
+            return Err(format!("invalid version '{}' in range", s));
+        }
+
+        if specified == parts.len() {
+            let version =
+                Version::parse(s).ok_or_else(|| format!("invalid version '{}' in range", s))?;
+            return Ok(Self {
+                components: version.release.clone(),
+                is_partial: version.release.len() < 3,
+                version,
+            });
+        }
+
+        let components = parts[..specified]
+            .iter()
+            .map(|p| p.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("invalid version '{}' in range", s))?;
+        Ok(Self {
+            version: Version {
+                release: components.clone(),
+                pre: Vec::new(),
+            },
+            components,
+            is_partial: true,
+        })
+    }
+
+    /// Exclusive upper bound after incrementing the component at `index`
+    ///
+    /// The bound carries the lowest pre-release so that pre-releases of the
+    /// next version (`2.0.0-beta` for `^1.2`) are excluded as well.
+    fn bump(&self, index: usize) -> Version {
+        let mut release = self.components[..index].to_vec();
+        release.push(self.components[index] + 1);
+        Version {
+            release,
+            pre: vec!["0".to_string()],
+        }
+    }
+
+    /// Upper bound of the wildcard range this operand stands for (`1.2` → `<1.3.0`)
+    fn next(&self) -> Version {
+        self.bump(self.components.len() - 1)
+    }
+}
+
+fn comparator_matches(version: &Version, comparator: &str) -> Result<bool, String> {
+    const OPERATORS: [&str; 10] = [">=", "<=", "!=", "==", ">", "<", "=", "^", "~", ""];
+    let op = OPERATORS
+        .iter()
+        .find(|op| comparator.starts_with(*op))
+        .copied()
+        .unwrap_or_default();
+    let operand = Partial::parse(&comparator[op.len()..])?;
+    let any = operand.components.is_empty();
+
+    let exact = |v: &Version| {
+        if any {
+            true
+        } else if operand.is_partial {
+            *v >= operand.version && *v < operand.next()
+        } else {
+            *v == operand.version
+        }
+    };
+
+    Ok(match op {
+        "" | "=" | "==" => exact(version),
+        "!=" => !exact(version),
+        ">=" => any || *version >= operand.version,
+        "<" => !any && *version < operand.version,
+        ">" if operand.is_partial => !any && *version >= operand.next(),
+        ">" => *version > operand.version,
+        "<=" if operand.is_partial => any || *version < operand.next(),
+        "<=" => *version <= operand.version,
+        "^" if any => true,
+        "^" => {
+            // Bump the first non-zero component (or the last one given)
+            let index = operand
+                .components
+                .iter()
+                .position(|c| *c != 0)
+                .unwrap_or(operand.components.len() - 1);
+            *version >= operand.version && *version < operand.bump(index)
+        }
+        "~" if any => true,
+        "~" => {
+            let index = if operand.components.len() >= 2 { 1 } else { 0 };
+            *version >= operand.version && *version < operand.bump(index)
+        }
+        _ => unreachable!(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    fn sat(version: &str, range: &str) -> bool {
+        satisfies(&v(version), range).unwrap()
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        assert!(v("1.10.0") > v("1.9.2"));
+        assert!(v("v2.0") == v("2.0.0"));
+        assert!(v("1.2.3.4") > v("1.2.3"));
+        assert!(v("1.0.0-rc.1") < v("1.0.0"));
+        assert!(v("1.0.0-alpha") < v("1.0.0-alpha.1"));
+        assert!(v("1.0.0-2") < v("1.0.0-10"));
+        assert!(v("1.0.0-beta") > v("1.0.0-10"));
+        assert!(v("1.0.0+build.7") == v("1.0.0"));
+        assert!(Version::parse("latest").is_none());
+        assert!(Version::parse("1..2").is_none());
+    }
+
+    #[test]
+    fn test_satisfies_comparators() {
+        assert!(sat("1.5.0", ">=1.2.0 <2.0.0"));
+        assert!(!sat("2.0.0", ">=1.2.0 <2.0.0"));
+        assert!(sat("1.2.0", ">= 1.2, < 2"));
+        assert!(sat("1.4.0", "=1.4.0"));
+        assert!(!sat("1.3.0", "!=1.3.0"));
+        assert!(sat("1.3.0", ">1.2"));
+        assert!(!sat("1.2.9", ">1.2"));
+        assert!(sat("1.2.9", "<=1.2"));
+    }
+
+    #[test]
+    fn test_satisfies_caret_tilde_wildcard() {
+        assert!(sat("1.9.0", "^1.2"));
+        assert!(!sat("2.0.0-beta", "^1.2"));
+        assert!(!sat("0.3.0", "^0.2.1"));
+        assert!(sat("1.2.9", "~1.2.3"));
+        assert!(!sat("1.3.0", "~1.2.3"));
+        assert!(sat("1.7.2", "1.x"));
+        assert!(sat("1.2.5", "1.2.*"));
+        assert!(!sat("1.3.0", "1.2"));
+        assert!(sat("9.9.9", "*"));
+    }
+
+    #[test]
+    fn test_satisfies_union_and_errors() {
+        assert!(sat("3.1.0", "^1.0 || ^3.0"));
+        assert!(!sat("2.1.0", "^1.0 || ^3.0"));
+        assert!(satisfies(&v("1.0.0"), ">=banana").is_err());
+        assert!(satisfies(&v("1.0.0"), ">=").is_err());
+    }
+}
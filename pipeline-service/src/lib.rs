@@ -25,8 +25,9 @@ pub use expression::{EvalError, ExpressionContext, ExpressionEngine, ExpressionT
 
 // Re-export execution types
 pub use execution::{
-    ExecutionEvent, ExecutionGraph, ExecutionResult, GraphError, JobNode, MatrixExpander,
-    MatrixInstance, PipelineExecutor, ProgressSender, RuntimeContext, StageNode,
+    EventBus, EventSink, ExecutionEvent, ExecutionGraph, ExecutionResult, GraphError, JobNode,
+    MatrixExpander, MatrixInstance, PipelineExecutor, PipelineFilter, ProgressSender,
+    RuntimeContext, StageNode,
 };
 
 // Re-export runner types
@@ -42,4 +43,4 @@ pub use testing::{
 };
 
 // Re-export utility types
-pub use utils::{find_repo_root, resolve_working_dir};
+pub use utils::{find_repo_root, resolve_working_dir, GitWorktree};
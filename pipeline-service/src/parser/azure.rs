@@ -2,10 +2,14 @@
 // Parses azure-pipelines.yml files with template support
 
 use crate::parser::error::{ParseError, ParseResult, ValidationError};
+use crate::parser::limits::ParseLimits;
 use crate::parser::models::*;
+use crate::parser::normalize::apply_merge_keys;
+use crate::utils::closest_match;
 
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Azure DevOps pipeline parser
 pub struct AzureParser;
@@ -13,13 +17,27 @@ pub struct AzureParser;
 impl AzureParser {
     /// Parse pipeline from YAML string
     pub fn parse(content: &str) -> ParseResult<Pipeline> {
+        Self::parse_with_limits(content, &ParseLimits::default())
+    }
+
+    /// Parse pipeline from YAML string, rejecting documents over `limits`
+    pub fn parse_with_limits(content: &str, limits: &ParseLimits) -> ParseResult<Pipeline> {
+        limits.check_source(content)?;
+
         // First pass: parse as raw YAML to detect template directives
-        let raw_value: serde_yaml::Value =
+        let mut raw_value: serde_yaml::Value =
             serde_yaml::from_str(content).map_err(|e| ParseError::from_yaml_error(&e, content))?;
+        limits.check_depth(&raw_value)?;
+        let merged = apply_merge_keys(&mut raw_value)?;
 
-        // Second pass: deserialize into typed Pipeline
-        let mut pipeline: Pipeline =
-            serde_yaml::from_str(content).map_err(|e| ParseError::from_yaml_error(&e, content))?;
+        // Second pass: deserialize into typed Pipeline. With `<<` merges the
+        // source no longer matches the data, so errors lose their position
+        let mut pipeline: Pipeline = if merged {
+            serde_yaml::from_value(raw_value.clone())
+        } else {
+            serde_yaml::from_str(content)
+        }
+        .map_err(|e| ParseError::from_yaml_error(&e, content))?;
 
         // Set template directive flags based on raw YAML scan
         Self::detect_template_directives(&raw_value, &mut pipeline);
@@ -27,15 +45,67 @@ impl AzureParser {
         Ok(pipeline)
     }
 
+    /// Parse every pipeline in a YAML stream separated by `---`
+    ///
+    /// Empty documents, like the one before a leading `---`, are skipped.
+    /// Error positions are lines of the whole stream.
+    pub fn parse_documents(content: &str) -> ParseResult<Vec<PipelineDocument>> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut documents = Vec::new();
+        for (start, end) in document_ranges(&lines) {
+            // Pad with the preceding lines so errors point into the whole file
+            let mut source = "\n".repeat(start);
+            for line in &lines[start..end] {
+                source.push_str(line);
+                source.push('\n');
+            }
+            let pipeline = Self::parse(&source)?;
+            documents.push(PipelineDocument {
+                index: documents.len(),
+                name: pipeline.name.clone(),
+                line: start + 1,
+                pipeline,
+            });
+        }
+        Ok(documents)
+    }
+
     /// Parse pipeline from file
+    ///
+    /// A `#<name-or-index>` suffix (`ci.yml#deploy`, `ci.yml#1`) picks one
+    /// pipeline from a multi-document file; a file with several documents
+    /// needs one.
     pub fn parse_file<P: AsRef<Path>>(path: P) -> ParseResult<Pipeline> {
-        let path = path.as_ref();
-        let content = fs::read_to_string(path).map_err(|e| {
+        let (path, selector) = split_document_selector(path.as_ref());
+        let content = fs::read_to_string(&path).map_err(|e| {
             ParseError::new(format!("failed to read file: {}", e), 0, 0)
                 .with_kind(crate::parser::error::ParseErrorKind::IoError)
         })?;
 
-        Self::parse(&content)
+        if selector.is_none() && document_ranges(&content.lines().collect::<Vec<_>>()).len() <= 1 {
+            return Self::parse(&content);
+        }
+        let mut documents = Self::parse_documents(&content)?;
+        let file = path.display();
+        let position = match &selector {
+            Some(selector) => documents.iter().position(|d| d.matches(selector)),
+            None => None,
+        };
+        match position {
+            Some(i) => Ok(documents.swap_remove(i).pipeline),
+            None => {
+                let available: Vec<String> = documents.iter().map(|d| d.label()).collect();
+                let message = match &selector {
+                    Some(selector) => format!("{} has no pipeline '{}'", file, selector),
+                    None => format!("{} has {} pipelines", file, documents.len()),
+                };
+                Err(ParseError::new(message, 0, 0).with_suggestion(format!(
+                    "pick one with {}#<name-or-index>: {}",
+                    file,
+                    available.join(", ")
+                )))
+            }
+        }
     }
 
     /// Parse pipeline with template resolution
@@ -43,9 +113,22 @@ impl AzureParser {
     /// Resolves all template references (step, job, stage, variable, extends)
     /// relative to the given repository root directory.
     pub fn parse_with_templates<P: AsRef<Path>>(path: P, repo_root: P) -> ParseResult<Pipeline> {
+        Self::parse_with_repositories(path, repo_root, &HashMap::new())
+    }
+
+    /// Parse pipeline with template resolution, including `template@alias`
+    /// references into the given local repository checkouts
+    pub fn parse_with_repositories<P: AsRef<Path>>(
+        path: P,
+        repo_root: P,
+        repositories: &HashMap<String, PathBuf>,
+    ) -> ParseResult<Pipeline> {
         let pipeline = Self::parse_file(&path)?;
         let mut engine =
             crate::parser::template::TemplateEngine::new(repo_root.as_ref().to_path_buf());
+        for (alias, path) in repositories {
+            engine = engine.with_resource_repo(alias.clone(), path.clone());
+        }
         engine.resolve_pipeline(pipeline)
     }
 
@@ -153,6 +236,82 @@ impl AzureParser {
     }
 }
 
+/// One pipeline of a multi-document YAML file
+#[derive(Debug, Clone)]
+pub struct PipelineDocument {
+    /// Position among the file's non-empty documents, from 0
+    pub index: usize,
+    /// The document's `name:`
+    pub name: Option<String>,
+    /// Line the document starts on (1-indexed)
+    pub line: usize,
+    pub pipeline: Pipeline,
+}
+
+impl PipelineDocument {
+    /// Whether `selector` is this document's index or name
+    pub fn matches(&self, selector: &str) -> bool {
+        selector == self.index.to_string()
+            || self
+                .name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(selector))
+    }
+
+    /// The selector to pick this document, `1 (deploy)`
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} ({})", self.index, name),
+            None => self.index.to_string(),
+        }
+    }
+}
+
+/// Split a `file.yml#<name-or-index>` path into the file and the selector
+///
+/// A path that exists as written has no selector, so files with `#` in their
+/// name still open.
+pub fn split_document_selector(path: &Path) -> (PathBuf, Option<String>) {
+    if !path.exists() {
+        if let Some((file, selector)) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.rsplit_once('#'))
+        {
+            if !file.is_empty() && !selector.is_empty() {
+                return (path.with_file_name(file), Some(selector.to_string()));
+            }
+        }
+    }
+    (path.to_path_buf(), None)
+}
+
+/// Line ranges of the non-empty documents in a YAML stream, each starting at
+/// its `---` marker
+fn document_ranges(lines: &[&str]) -> Vec<(usize, usize)> {
+    let mut starts = vec![0];
+    starts.extend(
+        lines
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, line)| **line == "---" || line.starts_with("--- "))
+            .map(|(i, _)| i),
+    );
+    let ends = starts.iter().skip(1).copied().chain([lines.len()]);
+    starts
+        .iter()
+        .copied()
+        .zip(ends)
+        .filter(|&(start, end)| {
+            lines[start..end].iter().any(|line| {
+                let line = line.strip_prefix("---").unwrap_or(line).trim();
+                !line.is_empty() && !line.starts_with('#') && line != "..."
+            })
+        })
+        .collect()
+}
+
 /// Validator for parsed pipelines
 pub struct PipelineValidator;
 
@@ -193,7 +352,19 @@ impl PipelineValidator {
 
         // Validate dependencies
         Self::validate_stage_dependencies(&pipeline.stages, &mut errors);
-        Self::validate_job_dependencies(&pipeline.jobs, &mut errors);
+        Self::validate_job_dependencies(&pipeline.jobs, "jobs", &mut errors);
+        // Jobs supplied by templates aren't known until the templates are resolved
+        for stage in pipeline
+            .stages
+            .iter()
+            .filter(|s| !s.has_template_directives && s.jobs.iter().all(|j| j.template.is_none()))
+        {
+            let path = format!(
+                "stages.{}.jobs",
+                stage.stage.as_deref().unwrap_or("unknown")
+            );
+            Self::validate_job_dependencies(&stage.jobs, &path, &mut errors);
+        }
 
         if errors.is_empty() {
             Ok(())
@@ -238,6 +409,15 @@ impl PipelineValidator {
             );
         }
 
+        if let Some(memory) = job.resources.as_ref().and_then(|r| r.memory.as_deref()) {
+            if let Err(e) = crate::execution::disk_quota::parse_size(memory) {
+                errors.push(ValidationError::new(
+                    e,
+                    format!("{}.resources.memory", path),
+                ));
+            }
+        }
+
         // Validate steps within the job
         for (i, step) in job.steps.iter().enumerate() {
             Self::validate_step(step, &format!("{}.steps[{}]", path, i), errors);
@@ -251,10 +431,32 @@ impl PipelineValidator {
 
     fn validate_stage_dependencies(stages: &[Stage], errors: &mut Vec<ValidationError>) {
         let stage_names: Vec<&str> = stages.iter().filter_map(|s| s.stage.as_deref()).collect();
+        for name in duplicates(&stage_names) {
+            errors.push(
+                ValidationError::new(
+                    format!("stage '{}' is defined more than once", name),
+                    format!("stages.{}", name),
+                )
+                .with_suggestion("give each stage a unique name"),
+            );
+        }
 
         for stage in stages {
             for dep in stage.depends_on.as_vec() {
-                if !stage_names.contains(&dep.as_str()) {
+                let stage_name = stage.stage.as_deref().unwrap_or("unknown");
+                // Names from template expressions are only known once resolved
+                if dep.contains("${{") {
+                    continue;
+                }
+                if dep == stage_name {
+                    errors.push(
+                        ValidationError::new(
+                            format!("stage '{}' depends on itself", stage_name),
+                            format!("stages.{}.dependsOn", stage_name),
+                        )
+                        .with_suggestion(format!("remove '{}' from dependsOn", dep)),
+                    );
+                } else if !stage_names.contains(&dep.as_str()) {
                     errors.push(
                         ValidationError::new(
                             format!(
@@ -267,7 +469,11 @@ impl PipelineValidator {
                                 stage.stage.as_deref().unwrap_or("unknown")
                             ),
                         )
-                        .with_suggestion(format!("available stages: {}", stage_names.join(", "))),
+                        .with_suggestion(dependency_suggestion(
+                            &dep,
+                            &stage_names,
+                            "stages",
+                        )),
                     );
                 }
             }
@@ -280,16 +486,28 @@ impl PipelineValidator {
                 .find(|s| s.stage.as_deref() == Some(name))
                 .map(|s| s.depends_on.as_vec())
                 .unwrap_or_default()
+                .into_iter()
+                .filter(|dep| dep != name)
+                .collect()
         }) {
             errors.push(ValidationError::new(
-                format!("circular dependency detected: {}", cycle.join(" -> ")),
+                format!("circular dependency detected: {}", cycle.join(" → ")),
                 "stages",
             ));
         }
     }
 
-    fn validate_job_dependencies(jobs: &[Job], errors: &mut Vec<ValidationError>) {
+    fn validate_job_dependencies(jobs: &[Job], path: &str, errors: &mut Vec<ValidationError>) {
         let job_names: Vec<&str> = jobs.iter().filter_map(|j| j.identifier()).collect();
+        for name in duplicates(&job_names) {
+            errors.push(
+                ValidationError::new(
+                    format!("job '{}' is defined more than once", name),
+                    format!("{}.{}", path, name),
+                )
+                .with_suggestion("give each job in a stage a unique name"),
+            );
+        }
 
         for job in jobs {
             let Some(job_name) = job.identifier() else {
@@ -297,13 +515,24 @@ impl PipelineValidator {
             };
 
             for dep in job.depends_on.as_vec() {
-                if !job_names.contains(&dep.as_str()) {
+                if dep.contains("${{") {
+                    continue;
+                }
+                if dep == job_name {
+                    errors.push(
+                        ValidationError::new(
+                            format!("job '{}' depends on itself", job_name),
+                            format!("{}.{}.dependsOn", path, job_name),
+                        )
+                        .with_suggestion(format!("remove '{}' from dependsOn", dep)),
+                    );
+                } else if !job_names.contains(&dep.as_str()) {
                     errors.push(
                         ValidationError::new(
                             format!("job '{}' depends on unknown job '{}'", job_name, dep),
-                            format!("jobs.{}.dependsOn", job_name),
+                            format!("{}.{}.dependsOn", path, job_name),
                         )
-                        .with_suggestion(format!("available jobs: {}", job_names.join(", "))),
+                        .with_suggestion(dependency_suggestion(&dep, &job_names, "jobs")),
                     );
                 }
             }
@@ -315,10 +544,13 @@ impl PipelineValidator {
                 .find(|j| j.identifier() == Some(name))
                 .map(|j| j.depends_on.as_vec())
                 .unwrap_or_default()
+                .into_iter()
+                .filter(|dep| dep != name)
+                .collect()
         }) {
             errors.push(ValidationError::new(
-                format!("circular dependency detected: {}", cycle.join(" -> ")),
-                "jobs",
+                format!("circular dependency detected: {}", cycle.join(" → ")),
+                path,
             ));
         }
     }
@@ -352,9 +584,12 @@ impl PipelineValidator {
         {
             match states.get(node) {
                 Some(NodeState::Visiting) => {
-                    // Found a cycle
-                    path.push(node.to_string());
-                    return Err(path.clone());
+                    // Found a cycle; report it from its first node, without
+                    // the path that led into it
+                    let start = path.iter().position(|n| n == node).unwrap_or(0);
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(node.to_string());
+                    return Err(cycle);
                 }
                 Some(NodeState::Visited) => return Ok(()),
                 _ => {}
@@ -380,6 +615,26 @@ impl PipelineValidator {
     }
 }
 
+/// Names that appear more than once, in the order they first repeat
+fn duplicates<'a>(names: &[&'a str]) -> Vec<&'a str> {
+    let mut seen = std::collections::HashSet::new();
+    let mut repeated = Vec::new();
+    for name in names {
+        if !seen.insert(*name) && !repeated.contains(name) {
+            repeated.push(*name);
+        }
+    }
+    repeated
+}
+
+/// Suggest a close match for an unknown dependency, or list what's available
+fn dependency_suggestion(dep: &str, names: &[&str], kind: &str) -> String {
+    match closest_match(dep, names.iter().copied()) {
+        Some(name) => format!("did you mean '{}'?", name),
+        None => format!("available {}: {}", kind, names.join(", ")),
+    }
+}
+
 /// Normalize a pipeline to the full stages/jobs/steps structure
 pub fn normalize_pipeline(mut pipeline: Pipeline) -> Pipeline {
     // If pipeline has direct steps (no jobs/stages), wrap in default job/stage
@@ -404,7 +659,10 @@ pub fn normalize_pipeline(mut pipeline: Pipeline) -> Pipeline {
             template: None,
             parameters: std::collections::HashMap::new(),
             environment: None,
+            compose: None,
+            resources: None,
             has_template_directives: false,
+            from_template: None,
         }];
     }
 
@@ -422,6 +680,7 @@ pub fn normalize_pipeline(mut pipeline: Pipeline) -> Pipeline {
             parameters: std::collections::HashMap::new(),
             pool: pipeline.pool.clone(),
             has_template_directives: false,
+            from_template: None,
         }];
     }
 
@@ -431,6 +690,7 @@ pub fn normalize_pipeline(mut pipeline: Pipeline) -> Pipeline {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::error::ParseErrorKind;
 
     #[test]
     fn test_parse_simple_pipeline() {
@@ -600,6 +860,70 @@ stages:
         assert!(result.is_err());
         let errors = result.unwrap_err();
         assert!(errors.iter().any(|e| e.message.contains("circular")));
+        assert!(errors.iter().any(|e| e.message.ends_with("A → C → B → A")));
+    }
+
+    #[test]
+    fn test_validate_job_dependencies_in_stages() {
+        let yaml = r#"
+stages:
+  - stage: Build
+    jobs:
+      - job: Compile
+        steps:
+          - script: echo compile
+      - job: Package
+        dependsOn: Compiel
+        steps:
+          - script: echo package
+"#;
+        let pipeline = AzureParser::parse(yaml).unwrap();
+        let errors = PipelineValidator::validate(&pipeline).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "stages.Build.jobs.Package.dependsOn");
+        assert_eq!(
+            errors[0].suggestion.as_deref(),
+            Some("did you mean 'Compile'?")
+        );
+    }
+
+    #[test]
+    fn test_validate_duplicate_names_and_self_dependencies() {
+        let yaml = r#"
+stages:
+  - stage: Build
+    dependsOn: Build
+    jobs:
+      - job: Compile
+        steps:
+          - script: echo compile
+      - job: Compile
+        dependsOn: ${{ parameters.after }}
+        steps:
+          - script: echo again
+  - stage: Build
+    jobs:
+      - job: Lint
+        steps:
+          - script: echo lint
+"#;
+        let pipeline = AzureParser::parse(yaml).unwrap();
+        let errors = PipelineValidator::validate(&pipeline).unwrap_err();
+        let found: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("stages.Build", "stage 'Build' is defined more than once"),
+                ("stages.Build.dependsOn", "stage 'Build' depends on itself"),
+                (
+                    "stages.Build.jobs.Compile",
+                    "job 'Compile' is defined more than once"
+                ),
+            ]
+        );
     }
 
     #[test]
@@ -644,4 +968,67 @@ jobs:
         let pipeline = AzureParser::parse(yaml).unwrap();
         assert!(pipeline.jobs[0].container.is_some());
     }
+
+    #[test]
+    fn test_parse_limits() {
+        // Billion laughs: few aliases, each doubling what came before
+        let mut yaml = String::from("a0: &a0 [lol, lol]\n");
+        for i in 1..30 {
+            yaml.push_str(&format!("a{}: &a{} [*a{}, *a{}]\n", i, i, i - 1, i - 1));
+        }
+        yaml.push_str("steps:\n  - script: echo\n");
+        let err = AzureParser::parse(&yaml).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::LimitExceeded);
+
+        let nested = format!(
+            "steps:\n  - script: echo\nx: {}1{}\n",
+            "[".repeat(100),
+            "]".repeat(100)
+        );
+        let err = AzureParser::parse(&nested).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::LimitExceeded);
+
+        let limits = ParseLimits {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let err =
+            AzureParser::parse_with_limits("steps:\n  - script: echo\n", &limits).unwrap_err();
+        assert!(err.message.contains("deeper than 2 levels"));
+    }
+
+    #[test]
+    fn test_parse_documents() {
+        let yaml = "---\nname: build\nsteps:\n  - script: make\n---\nname: deploy\nsteps:\n  - script: ship\n--- # third\nsteps:\n  - script: echo\n";
+        let documents = AzureParser::parse_documents(yaml).unwrap();
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[1].name.as_deref(), Some("deploy"));
+        assert_eq!(documents[1].line, 5);
+        assert_eq!(documents[2].label(), "2");
+        assert!(documents[1].matches("Deploy") && documents[1].matches("1"));
+
+        // Errors point at lines of the whole file
+        let err =
+            AzureParser::parse_documents("steps:\n  - script: a\n---\nsteps: [\n").unwrap_err();
+        assert!(err.line >= 4, "line {}", err.line);
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("ci.yml");
+        std::fs::write(&file, yaml).unwrap();
+        let err = AzureParser::parse_file(&file).unwrap_err();
+        assert!(err.message.contains("has 3 pipelines"));
+        assert!(err.suggestion.unwrap().contains("0 (build), 1 (deploy), 2"));
+
+        let pipeline = AzureParser::parse_file(dir.path().join("ci.yml#deploy")).unwrap();
+        assert_eq!(pipeline.name.as_deref(), Some("deploy"));
+        let pipeline = AzureParser::parse_file(dir.path().join("ci.yml#2")).unwrap();
+        assert_eq!(pipeline.steps.len(), 1);
+        let err = AzureParser::parse_file(dir.path().join("ci.yml#test")).unwrap_err();
+        assert!(err.message.contains("has no pipeline 'test'"));
+
+        // A single document with a leading marker parses as before
+        std::fs::write(&file, "---\nsteps:\n  - script: echo\n").unwrap();
+        assert!(AzureParser::parse_file(&file).is_ok());
+        assert_eq!(split_document_selector(&file), (file.clone(), None));
+    }
 }
@@ -2,6 +2,7 @@
 // Provides context, line/column info, and suggestions for common mistakes
 
 use std::fmt;
+use std::path::PathBuf;
 
 /// Detailed parse error with location and context
 #[derive(Debug, Clone)]
@@ -18,9 +19,22 @@ pub struct ParseError {
     pub suggestion: Option<String>,
     /// The kind of error
     pub kind: ParseErrorKind,
+    /// Template and source text the error came from (boxed to keep errors small)
+    pub origin: Box<ErrorOrigin>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where an error was raised, for rendering it against the right source
+#[derive(Debug, Clone, Default)]
+pub struct ErrorOrigin {
+    /// File the error is in, when it isn't the pipeline being parsed
+    pub file: Option<PathBuf>,
+    /// Templates that included `file`, outermost first (excluding the pipeline)
+    pub include_chain: Vec<String>,
+    /// Source text the error is about, used to locate it when the line is unknown
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParseErrorKind {
     /// YAML syntax error
     YamlSyntax,
@@ -38,6 +52,25 @@ pub enum ParseErrorKind {
     IoError,
     /// Validation error (semantic)
     ValidationError,
+    /// Document too large, too deeply nested or with too many aliases
+    LimitExceeded,
+}
+
+impl ParseErrorKind {
+    /// Short label shown next to the error location
+    pub fn label(&self) -> &'static str {
+        match self {
+            ParseErrorKind::YamlSyntax => "syntax",
+            ParseErrorKind::InvalidSchema => "schema",
+            ParseErrorKind::UnknownField => "unknown field",
+            ParseErrorKind::InvalidValue => "invalid value",
+            ParseErrorKind::TemplateError => "template",
+            ParseErrorKind::ExpressionError => "expression",
+            ParseErrorKind::IoError => "io",
+            ParseErrorKind::ValidationError => "validation",
+            ParseErrorKind::LimitExceeded => "limit",
+        }
+    }
 }
 
 impl ParseError {
@@ -49,6 +82,7 @@ impl ParseError {
             context: String::new(),
             suggestion: None,
             kind: ParseErrorKind::InvalidSchema,
+            origin: Box::default(),
         }
     }
 
@@ -60,6 +94,7 @@ impl ParseError {
             context: String::new(),
             suggestion: None,
             kind: ParseErrorKind::YamlSyntax,
+            origin: Box::default(),
         }
     }
 
@@ -78,6 +113,138 @@ impl ParseError {
         self
     }
 
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.origin.file = Some(file.into());
+        self
+    }
+
+    pub fn with_include_chain(mut self, chain: Vec<String>) -> Self {
+        self.origin.include_chain = chain;
+        self
+    }
+
+    pub fn with_snippet(mut self, snippet: impl Into<String>) -> Self {
+        self.origin.snippet = Some(snippet.into());
+        self
+    }
+
+    /// Render the error with the offending source lines, a caret under the
+    /// location and a label, in the style of rustc diagnostics
+    ///
+    /// `path` names the file in the `-->` line. When the error has no line
+    /// number, its snippet is looked up in `source` instead.
+    pub fn render(&self, path: &str, source: Option<&str>, color: bool) -> String {
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut lines: Vec<&str> = source.map(|s| s.lines().collect()).unwrap_or_default();
+        let (line, column, width) = self.locate(&lines);
+        if source.is_some() && line == lines.len() + 1 {
+            // Errors at the end of the input point just past the last line
+            lines.push("");
+        }
+
+        let mut out = format!(
+            "{}{}\n",
+            paint("1;31", &format!("error[{}]", self.kind.label())),
+            paint("1", &format!(": {}", self.message))
+        );
+        if line == 0 {
+            out.push_str(&format!("{} {}\n", paint("1;34", "  -->"), path));
+        } else {
+            out.push_str(&format!(
+                "{} {}:{}:{}\n",
+                paint("1;34", "  -->"),
+                path,
+                line,
+                column
+            ));
+        }
+
+        let gutter_width = (line + 1).to_string().len();
+        let gutter = |number: Option<usize>| {
+            let number = number.map(|n| n.to_string()).unwrap_or_default();
+            paint(
+                "1;34",
+                &format!("{:>width$} |", number, width = gutter_width),
+            )
+        };
+
+        if line > 0 && line <= lines.len() {
+            out.push_str(&format!("{}\n", gutter(None)));
+            for number in line.saturating_sub(1).max(1)..=(line + 1).min(lines.len()) {
+                out.push_str(&format!("{} {}\n", gutter(Some(number)), lines[number - 1]));
+                if number == line {
+                    let marker = format!(
+                        "{}{} {}",
+                        " ".repeat(column.saturating_sub(1)),
+                        "^".repeat(width.max(1)),
+                        self.kind.label()
+                    );
+                    out.push_str(&format!("{} {}\n", gutter(None), paint("1;31", &marker)));
+                }
+            }
+            out.push_str(&format!("{}\n", gutter(None)));
+        } else if !self.context.is_empty() {
+            out.push('\n');
+            out.push_str(&self.context);
+        }
+
+        let note = " ".repeat(gutter_width + 1);
+        if !self.origin.include_chain.is_empty() {
+            out.push_str(&format!(
+                "{}{} included from {}\n",
+                note,
+                paint("1;34", "= note:"),
+                self.origin.include_chain.join(" → ")
+            ));
+        }
+        if let Some(suggestion) = &self.suggestion {
+            out.push_str(&format!(
+                "{}{} {}\n",
+                note,
+                paint("1;36", "= help:"),
+                suggestion
+            ));
+        }
+        out
+    }
+
+    /// Line, column and width of the error in `lines` (line 0 when unknown)
+    fn locate(&self, lines: &[&str]) -> (usize, usize, usize) {
+        if self.line > 0 {
+            // Underline the token starting at the column
+            let width = lines
+                .get(self.line - 1)
+                .and_then(|text| text.get(self.column.saturating_sub(1)..))
+                .map(|rest| rest.chars().take_while(|c| !c.is_whitespace()).count())
+                .unwrap_or(1);
+            return (self.line, self.column.max(1), width);
+        }
+
+        let Some(snippet) = self.origin.snippet.as_deref().filter(|s| !s.is_empty()) else {
+            return (0, 0, 0);
+        };
+        lines
+            .iter()
+            .enumerate()
+            .find_map(|(i, text)| {
+                text.find(snippet).map(|byte| {
+                    (
+                        i + 1,
+                        text[..byte].chars().count() + 1,
+                        snippet.chars().count(),
+                    )
+                })
+            })
+            .unwrap_or((0, 0, 0))
+    }
+
     /// Create context from source content
     pub fn with_source_context(mut self, source: &str, context_lines: usize) -> Self {
         let lines: Vec<&str> = source.lines().collect();
@@ -111,9 +278,16 @@ impl ParseError {
         let message = format_yaml_error_message(err);
         let suggestion = suggest_yaml_fix(err, source, line);
 
-        ParseError::yaml_error(message, line, column)
+        let error = ParseError::yaml_error(message, line, column)
             .with_source_context(source, 2)
-            .with_suggestion_opt(suggestion)
+            .with_suggestion_opt(suggestion);
+        // serde_yaml's own nesting and alias expansion limits
+        if error.message.contains("recursion limit exceeded")
+            || error.message.contains("repetition limit exceeded")
+        {
+            return error.with_kind(ParseErrorKind::LimitExceeded);
+        }
+        error
     }
 
     fn with_suggestion_opt(mut self, suggestion: Option<String>) -> Self {
@@ -330,6 +504,31 @@ jobs:
         assert!(err.context.contains("job: Build"));
     }
 
+    #[test]
+    fn test_render_locates_snippet() {
+        let source = "steps:\n  - script: echo ${{ nope() }}\n  - script: echo done\n";
+        let err = ParseError::new("unknown function: nope", 0, 0)
+            .with_kind(ParseErrorKind::ExpressionError)
+            .with_snippet("nope()")
+            .with_include_chain(vec!["azure-pipelines.yml".to_string()])
+            .with_suggestion("check the function name");
+
+        let output = err.render("templates/build.yml", Some(source), false);
+        assert_eq!(
+            output,
+            "error[expression]: unknown function: nope\n\
+             \x20 --> templates/build.yml:2:22\n\
+             \x20 |\n\
+             1 | steps:\n\
+             2 |   - script: echo ${{ nope() }}\n\
+             \x20 |                      ^^^^^^ expression\n\
+             3 |   - script: echo done\n\
+             \x20 |\n\
+             \x20 = note: included from azure-pipelines.yml\n\
+             \x20 = help: check the function name\n"
+        );
+    }
+
     #[test]
     fn test_extract_field_name() {
         let msg = "missing field `steps` at line 10";
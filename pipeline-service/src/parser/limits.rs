@@ -0,0 +1,207 @@
+// YAML Limits
+// Bounds on document size, nesting and aliases, so pathological YAML fails to parse instead of exhausting memory
+
+use crate::parser::error::{ParseError, ParseErrorKind, ParseResult};
+
+/// Largest pipeline or template file parsed by default
+pub const DEFAULT_MAX_BYTES: usize = 8 << 20;
+
+/// Deepest nesting of mappings and sequences parsed by default
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Most `*alias` references one document may use by default
+pub const DEFAULT_MAX_ALIASES: usize = 100;
+
+/// Limits checked before and while parsing a YAML document
+///
+/// Anchors are the billion-laughs vector: each alias can expand to a copy of
+/// everything before it, so their count is bounded before the document is
+/// parsed at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    pub max_bytes: usize,
+    pub max_depth: usize,
+    pub max_aliases: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_aliases: DEFAULT_MAX_ALIASES,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Check the size and alias count of `content` before it's parsed
+    pub fn check_source(&self, content: &str) -> ParseResult<()> {
+        if content.len() > self.max_bytes {
+            return Err(limit_error(
+                format!(
+                    "document is {} bytes, over the {} byte limit",
+                    content.len(),
+                    self.max_bytes
+                ),
+                0,
+                0,
+            ));
+        }
+
+        let mut aliases = 0;
+        for (line, column) in alias_positions(content) {
+            aliases += 1;
+            if aliases > self.max_aliases {
+                return Err(limit_error(
+                    format!("document uses more than {} aliases", self.max_aliases),
+                    line,
+                    column,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the nesting of a parsed document
+    pub fn check_depth(&self, value: &serde_yaml::Value) -> ParseResult<()> {
+        if depth(value) > self.max_depth {
+            return Err(limit_error(
+                format!("document nests deeper than {} levels", self.max_depth),
+                0,
+                0,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn limit_error(message: String, line: usize, column: usize) -> ParseError {
+    ParseError::new(message, line, column).with_kind(ParseErrorKind::LimitExceeded)
+}
+
+/// Nesting depth of a value; scalars are 0
+fn depth(value: &serde_yaml::Value) -> usize {
+    match value {
+        serde_yaml::Value::Sequence(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        serde_yaml::Value::Mapping(map) => {
+            1 + map
+                .iter()
+                .map(|(key, value)| depth(key).max(depth(value)))
+                .max()
+                .unwrap_or(0)
+        }
+        serde_yaml::Value::Tagged(tagged) => depth(&tagged.value),
+        _ => 0,
+    }
+}
+
+/// Lines and columns (1-based) of the `*alias` references in `content`
+///
+/// A lexical scan: a `*` starts an alias where a node can start, outside
+/// quotes, comments and block scalars, so `ls *.txt` in a script isn't one.
+fn alias_positions(content: &str) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut block_indent: Option<usize> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let indent = line.len() - line.trim_start().len();
+        if let Some(block) = block_indent {
+            if line.trim().is_empty() || indent > block {
+                continue;
+            }
+            block_indent = None;
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let mut quote: Option<char> = None;
+        let mut node_start = true;
+        for (column, &c) in chars.iter().enumerate() {
+            if let Some(open) = quote {
+                if c == open {
+                    quote = None;
+                }
+                continue;
+            }
+            match c {
+                '#' if column == 0 || chars[column - 1].is_whitespace() => break,
+                '\'' | '"' if node_start => quote = Some(c),
+                '*' if node_start
+                    && chars
+                        .get(column + 1)
+                        .is_some_and(|next| next.is_alphanumeric() || *next == '_') =>
+                {
+                    found.push((index + 1, column + 1));
+                }
+                _ => {}
+            }
+            node_start = c.is_whitespace() && node_start
+                || matches!(c, '[' | '{' | ',')
+                || (matches!(c, ':' | '-' | '?')
+                    && chars
+                        .get(column + 1)
+                        .is_none_or(|next| next.is_whitespace()));
+        }
+
+        // `key: |` or `- >-` starts a block scalar on the following lines
+        let trimmed = line.trim_end();
+        let header = trimmed.trim_end_matches(|c: char| c == '+' || c == '-' || c.is_ascii_digit());
+        if quote.is_none()
+            && (header.ends_with(": |")
+                || header.ends_with(": >")
+                || header.ends_with("- |")
+                || header.ends_with("- >")
+                || header == "|"
+                || header == ">")
+        {
+            block_indent = Some(indent);
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_positions() {
+        let yaml = "\
+base: &base
+  pool: linux
+job: *base
+list: [*base, 'not *quoted', x]
+steps:
+  - script: ls *.txt # *comment
+  - bash: |
+      rm -rf *
+      echo *done
+  - *base
+";
+        assert_eq!(alias_positions(yaml), [(3, 6), (4, 8), (10, 5)]);
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let limits = ParseLimits {
+            max_bytes: 200,
+            max_depth: 3,
+            max_aliases: 2,
+        };
+        assert!(limits.check_source("a: &a [1]\nb: *a\nc: *a\n").is_ok());
+
+        let err = limits
+            .check_source("a: &a [1]\nb: *a\nc: *a\nd: *a\n")
+            .unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::LimitExceeded);
+        assert_eq!((err.line, err.column), (4, 4));
+
+        let err = limits.check_source(&"x".repeat(201)).unwrap_err();
+        assert!(err.message.contains("over the 200 byte limit"));
+
+        let shallow: serde_yaml::Value = serde_yaml::from_str("a: {b: [1]}").unwrap();
+        assert!(limits.check_depth(&shallow).is_ok());
+        let deep: serde_yaml::Value = serde_yaml::from_str("a: {b: [[1]]}").unwrap();
+        assert!(limits.check_depth(&deep).is_err());
+    }
+}
@@ -3,10 +3,24 @@
 
 pub mod azure;
 pub mod error;
+pub mod limits;
 pub mod models;
+pub mod normalize;
+pub mod references;
 pub mod template;
+pub mod template_docs;
+pub mod template_graph;
+pub mod unused;
 
-pub use azure::{normalize_pipeline, AzureParser, PipelineValidator};
-pub use error::{ParseError, ParseErrorKind, ParseResult, ValidationError};
+pub use azure::{
+    normalize_pipeline, split_document_selector, AzureParser, PipelineDocument, PipelineValidator,
+};
+pub use error::{ErrorOrigin, ParseError, ParseErrorKind, ParseResult, ValidationError};
+pub use limits::ParseLimits;
 pub use models::*;
-pub use template::{TemplateEngine, TemplateError, TemplateErrorKind};
+pub use normalize::{apply_merge_keys, normalize_documents, to_canonical_yaml};
+pub use references::check_references;
+pub use template::{TemplateEngine, TemplateError, TemplateErrorKind, TemplateInclude};
+pub use template_docs::{ParameterDoc, TemplateDoc};
+pub use template_graph::TemplateGraph;
+pub use unused::{check_unused, LintSource, LintWarning};
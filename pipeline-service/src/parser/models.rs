@@ -1,9 +1,11 @@
 // Azure DevOps Pipeline Data Models
 // Comprehensive types representing the full Azure DevOps YAML schema
 
+use crate::execution::run_id::new_run_id;
+
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime};
 
 /// A value that can be either a boolean literal or a runtime expression string.
 /// Azure DevOps allows fields like `continueOnError` to use runtime expressions
@@ -81,6 +83,9 @@ pub struct Pipeline {
     #[serde(rename = "lockBehavior")]
     pub lock_behavior: Option<LockBehavior>,
 
+    /// Docker Compose services kept up for the whole run (roxid extension)
+    pub compose: Option<ComposeSpec>,
+
     /// Whether stages/jobs/steps lists contained compile-time template directives
     /// (${{ if }}, ${{ each }}) that were dropped during deserialization.
     #[serde(skip)]
@@ -135,6 +140,49 @@ pub struct TagFilter {
     pub exclude: Vec<String>,
 }
 
+impl Trigger {
+    /// Whether a change to any of the given repository-relative paths would
+    /// trigger a CI run under this configuration
+    pub fn matches_changed_paths(&self, paths: &[String]) -> bool {
+        match self {
+            Trigger::None => false,
+            Trigger::Branches(_) => !paths.is_empty(),
+            Trigger::Full(config) => match &config.paths {
+                Some(filter) => paths.iter().any(|p| filter.matches(p)),
+                None => !paths.is_empty(),
+            },
+        }
+    }
+}
+
+impl PathFilter {
+    /// Check a repository-relative path against the include/exclude filters.
+    ///
+    /// Entries without wildcards match the path itself and everything beneath it;
+    /// entries with `*` or `?` are matched as wildcard patterns.
+    pub fn matches(&self, path: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|f| path_filter_matches(f, path));
+        included && !self.exclude.iter().any(|f| path_filter_matches(f, path))
+    }
+}
+
+fn path_filter_matches(filter: &str, path: &str) -> bool {
+    let filter = filter.trim_start_matches('/').trim_end_matches('/');
+    let path = path.trim_start_matches('/');
+
+    if filter.is_empty() {
+        return true;
+    }
+    if filter.contains('*') || filter.contains('?') {
+        return crate::utils::wildcard_match(filter, path);
+    }
+    path == filter
+        || path
+            .strip_prefix(filter)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
 /// PR trigger configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -422,13 +470,60 @@ pub enum ParameterType {
     Boolean,
     Object,
     Step,
+    #[serde(alias = "stepList")]
     StepList,
     Job,
+    #[serde(alias = "jobList")]
     JobList,
     Stage,
+    #[serde(alias = "stageList")]
     StageList,
 }
 
+impl ParameterType {
+    /// Type name as written in Azure DevOps YAML
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParameterType::String => "string",
+            ParameterType::Number => "number",
+            ParameterType::Boolean => "boolean",
+            ParameterType::Object => "object",
+            ParameterType::Step => "step",
+            ParameterType::StepList => "stepList",
+            ParameterType::Job => "job",
+            ParameterType::JobList => "jobList",
+            ParameterType::Stage => "stage",
+            ParameterType::StageList => "stageList",
+        }
+    }
+
+    /// Whether `value` has the shape this type expects
+    pub fn accepts(&self, value: &serde_yaml::Value) -> bool {
+        match self {
+            ParameterType::String => value.is_string() || value.is_number() || value.is_bool(),
+            ParameterType::Number => {
+                value.is_number()
+                    || value
+                        .as_str()
+                        .map(|s| s.parse::<f64>().is_ok())
+                        .unwrap_or(false)
+            }
+            ParameterType::Boolean => {
+                value.is_bool()
+                    || value
+                        .as_str()
+                        .map(|s| s == "true" || s == "false")
+                        .unwrap_or(false)
+            }
+            ParameterType::Object => value.is_mapping() || value.is_sequence(),
+            ParameterType::Step | ParameterType::Job | ParameterType::Stage => value.is_mapping(),
+            ParameterType::StepList | ParameterType::JobList | ParameterType::StageList => {
+                value.is_sequence()
+            }
+        }
+    }
+}
+
 // =============================================================================
 // Pool
 // =============================================================================
@@ -503,6 +598,9 @@ pub struct Stage {
     /// When true, the validator should not require jobs to be non-empty.
     #[serde(skip)]
     pub has_template_directives: bool,
+    /// Template file the stage was expanded from, for errors
+    #[serde(skip)]
+    pub from_template: Option<String>,
 }
 
 // =============================================================================
@@ -575,11 +673,20 @@ pub struct Job {
     /// Deployment environment (for deployment jobs)
     pub environment: Option<Environment>,
 
+    /// Docker Compose services kept up while the job runs (roxid extension)
+    pub compose: Option<ComposeSpec>,
+
+    /// CPUs and memory the job needs, for `--max-parallel auto` (roxid extension)
+    pub resources: Option<JobResources>,
+
     /// Whether the steps list contained compile-time template directives
     /// (${{ if }}, ${{ each }}) that were dropped during deserialization.
     /// When true, the validator should not require steps to be non-empty.
     #[serde(skip)]
     pub has_template_directives: bool,
+    /// Template file the job was expanded from, for errors
+    #[serde(skip)]
+    pub from_template: Option<String>,
 }
 
 impl Job {
@@ -589,6 +696,46 @@ impl Job {
     }
 }
 
+/// Collect steps from deployment strategy hooks in execution order.
+///
+/// Deployment jobs define steps inside strategy hooks (runOnce, rolling, canary)
+/// rather than in the top-level `steps` field. This function extracts steps from
+/// the hooks in the correct Azure DevOps execution order:
+/// preDeploy → deploy → routeTraffic → postRouteTraffic
+pub fn collect_deployment_steps(job: &Job) -> Vec<Step> {
+    let mut steps = Vec::new();
+
+    if let Some(strategy) = &job.strategy {
+        // Helper to extract steps from DeploymentHooks
+        let extract_from_hooks = |hooks: &DeploymentHooks, steps: &mut Vec<Step>| {
+            if let Some(hook) = &hooks.pre_deploy {
+                steps.extend(hook.steps.clone());
+            }
+            if let Some(hook) = &hooks.deploy {
+                steps.extend(hook.steps.clone());
+            }
+            if let Some(hook) = &hooks.route_traffic {
+                steps.extend(hook.steps.clone());
+            }
+            if let Some(hook) = &hooks.post_route_traffic {
+                steps.extend(hook.steps.clone());
+            }
+        };
+
+        if let Some(run_once) = &strategy.run_once {
+            extract_from_hooks(run_once, &mut steps);
+        }
+        if let Some(rolling) = &strategy.rolling {
+            extract_from_hooks(&rolling.hooks, &mut steps);
+        }
+        if let Some(canary) = &strategy.canary {
+            extract_from_hooks(&canary.hooks, &mut steps);
+        }
+    }
+
+    steps
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ContainerRef {
@@ -615,6 +762,43 @@ pub struct ContainerSpec {
     pub mount_read_only: Option<MountReadOnly>,
 }
 
+/// Docker Compose project brought up around a pipeline or job
+///
+/// ```yaml
+/// compose:
+///   file: docker-compose.test.yml
+///   services: [db, cache]
+///   waitTimeoutInSeconds: 120
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ComposeSpec {
+    /// Compose file, relative to the working directory
+    pub file: String,
+    /// Services to start (default: all services in the file)
+    #[serde(default)]
+    pub services: Vec<String>,
+    /// How long to wait for services to report healthy
+    pub wait_timeout_in_seconds: Option<u32>,
+}
+
+/// Host resources a job needs, so `--max-parallel auto` doesn't start more
+/// jobs than the machine can hold
+///
+/// ```yaml
+/// resources:
+///   cpus: 4
+///   memory: 8GB
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct JobResources {
+    /// CPUs (default 1)
+    pub cpus: Option<u32>,
+    /// Memory, e.g. `512M` or `8GB` (default 1GB)
+    pub memory: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MountReadOnly {
     pub work: Option<bool>,
@@ -728,7 +912,7 @@ pub struct CanaryStrategy {
 // DependsOn
 // =============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Default)]
 #[serde(untagged)]
 pub enum DependsOn {
     /// No dependencies (default: depends on previous)
@@ -757,6 +941,41 @@ impl DependsOn {
     }
 }
 
+impl<'de> Deserialize<'de> for DependsOn {
+    /// A name or a list of names; lists inserted by template expressions
+    /// (`- ${{ parameters.stages }}`) are flattened into the outer list
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        fn names(value: serde_yaml::Value, out: &mut Vec<String>) -> Result<(), String> {
+            match value {
+                serde_yaml::Value::String(name) => out.push(name),
+                serde_yaml::Value::Number(n) => out.push(n.to_string()),
+                serde_yaml::Value::Bool(b) => out.push(b.to_string()),
+                serde_yaml::Value::Sequence(items) => {
+                    for item in items {
+                        names(item, out)?;
+                    }
+                }
+                serde_yaml::Value::Null => {}
+                _ => return Err("dependsOn must be a name or a list of names".to_string()),
+            }
+            Ok(())
+        }
+
+        match serde_yaml::Value::deserialize(deserializer)? {
+            serde_yaml::Value::Null => Ok(DependsOn::Default),
+            serde_yaml::Value::String(name) => Ok(DependsOn::Single(name)),
+            value => {
+                let mut out = Vec::new();
+                names(value, &mut out).map_err(serde::de::Error::custom)?;
+                Ok(DependsOn::Multiple(out))
+            }
+        }
+    }
+}
+
 // =============================================================================
 // Step
 // =============================================================================
@@ -787,6 +1006,14 @@ pub struct Step {
     /// Retry count on failure
     pub retry_count_on_task_failure: Option<u32>,
 
+    /// Report the step's command instead of running it (script steps only)
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Labels for selecting steps, e.g. skipping `slow` ones in an execution profile
+    #[serde(default)]
+    pub tags: Vec<String>,
+
     /// Step-level environment variables
     #[serde(default)]
     pub env: HashMap<String, String>,
@@ -831,6 +1058,8 @@ pub struct ScriptStep {
     pub working_directory: Option<String>,
     #[serde(default)]
     pub fail_on_stderr: bool,
+    /// Shell to run the script with (roxid extension), e.g. `fish`, `nu`, or `perl {0}`
+    pub shell: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -990,7 +1219,7 @@ pub struct Extends {
 // =============================================================================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "camelCase")]
 pub enum LockBehavior {
     RunLatest,
     Sequential,
@@ -1008,8 +1237,90 @@ pub struct StepResult {
     pub output: String,
     pub error: Option<String>,
     pub duration: Duration,
+    /// When the step started; `None` if it never ran
+    pub started_at: Option<SystemTime>,
+    /// When the step finished; `None` if it never ran
+    pub finished_at: Option<SystemTime>,
     pub exit_code: Option<i32>,
     pub outputs: HashMap<String, String>,
+    /// Variables and expressions substituted into the step's command
+    pub substitutions: Vec<VariableSubstitution>,
+    /// Markdown summary published by the step, if any
+    pub summary: Option<String>,
+    /// Constructs roxid skipped or only stubbed while running the step
+    pub unsupported: Vec<UnsupportedFeature>,
+    /// Environment roxid gave the step's command, by name, secrets masked
+    pub env: Vec<(String, String)>,
+}
+
+/// A pipeline construct roxid doesn't implement, encountered during a run
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnsupportedFeature {
+    pub kind: UnsupportedKind,
+    /// Task reference, step keyword or logging command (e.g. `artifact.upload`)
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UnsupportedKind {
+    /// A task that couldn't be run locally
+    Task,
+    /// A step type with no local implementation
+    StepType,
+    /// A `##vso[...]` logging command that was ignored
+    LoggingCommand,
+}
+
+impl UnsupportedFeature {
+    pub fn new(kind: UnsupportedKind, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for UnsupportedFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            UnsupportedKind::Task => write!(f, "task {}", self.name),
+            UnsupportedKind::StepType => write!(f, "{} step", self.name),
+            UnsupportedKind::LoggingCommand => write!(f, "##vso[{}]", self.name),
+        }
+    }
+}
+
+/// A single variable or expression substituted into a step's command text
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableSubstitution {
+    /// Variable path or expression source (e.g. `Build.Configuration`)
+    pub name: String,
+    /// Value that was substituted
+    pub value: String,
+}
+
+/// Characters that can chain, redirect or inject commands when they appear
+/// unquoted in a substituted value
+const SHELL_METACHARACTERS: &[char] = &[';', '&', '|', '$', '`', '<', '>', '\n'];
+
+impl VariableSubstitution {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Shell metacharacters present in the substituted value (deduplicated, in order)
+    pub fn shell_metacharacters(&self) -> Vec<char> {
+        let mut found = Vec::new();
+        for c in self.value.chars() {
+            if SHELL_METACHARACTERS.contains(&c) && !found.contains(&c) {
+                found.push(c);
+            }
+        }
+        found
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1030,9 +1341,105 @@ pub struct JobResult {
     pub status: JobStatus,
     pub steps: Vec<StepResult>,
     pub duration: Duration,
+    /// When the job started; `None` if it never ran
+    pub started_at: Option<SystemTime>,
+    /// When the job finished; `None` if it never ran
+    pub finished_at: Option<SystemTime>,
     pub outputs: HashMap<String, String>,
 }
 
+impl JobResult {
+    /// Result name of a matrix instance: `Build[linux]`
+    pub fn instance_name(job: &str, instance: &str) -> String {
+        format!("{}[{}]", job, instance)
+    }
+
+    /// The job and matrix instance a result name refers to
+    /// (`Build[linux]` -> `Build`, `linux`)
+    pub fn split_name(name: &str) -> (&str, Option<&str>) {
+        name.strip_suffix(']')
+            .and_then(|rest| rest.rsplit_once('['))
+            .map_or((name, None), |(job, instance)| (job, Some(instance)))
+    }
+
+    /// Name of the job, without its matrix instance
+    pub fn job(&self) -> &str {
+        Self::split_name(&self.job_name).0
+    }
+
+    pub fn matrix_instance(&self) -> Option<&str> {
+        Self::split_name(&self.job_name).1
+    }
+
+    /// One result for a matrix job from the results of its instances
+    ///
+    /// The status is the worst of the instances', steps run back to back, and
+    /// outputs are prefixed with the instance (`linux.step.var`) as in Azure
+    /// DevOps.
+    pub fn combine(instances: &[JobResult]) -> JobResult {
+        let statuses = || instances.iter().map(|job| &job.status);
+        let status = if statuses().any(|s| *s == JobStatus::Failed) {
+            JobStatus::Failed
+        } else if statuses().any(|s| *s == JobStatus::Canceled) {
+            JobStatus::Canceled
+        } else if statuses().all(|s| *s == JobStatus::Skipped) {
+            JobStatus::Skipped
+        } else if statuses().any(|s| *s == JobStatus::SucceededWithIssues) {
+            JobStatus::SucceededWithIssues
+        } else {
+            JobStatus::Succeeded
+        };
+
+        JobResult {
+            job_name: instances
+                .first()
+                .map(|job| job.job().to_string())
+                .unwrap_or_default(),
+            display_name: None,
+            status,
+            steps: instances
+                .iter()
+                .flat_map(|job| job.steps.iter().cloned())
+                .collect(),
+            duration: instances.iter().map(|job| job.duration).sum(),
+            started_at: instances.iter().filter_map(|job| job.started_at).min(),
+            finished_at: instances.iter().filter_map(|job| job.finished_at).max(),
+            outputs: instances
+                .iter()
+                .flat_map(|job| {
+                    job.outputs.iter().map(move |(name, value)| {
+                        let name = match job.matrix_instance() {
+                            Some(instance) => format!("{}.{}", instance, name),
+                            None => name.clone(),
+                        };
+                        (name, value.clone())
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// A stage's job results with the instances of each matrix job combined
+    pub fn combine_instances(jobs: &[JobResult]) -> Vec<JobResult> {
+        let mut combined: Vec<JobResult> = Vec::new();
+        let mut start = 0;
+        for end in 1..=jobs.len() {
+            if end < jobs.len()
+                && jobs[end].matrix_instance().is_some()
+                && jobs[end].job() == jobs[start].job()
+            {
+                continue;
+            }
+            match &jobs[start..end] {
+                [job] if job.matrix_instance().is_none() => combined.push(job.clone()),
+                instances => combined.push(Self::combine(instances)),
+            }
+            start = end;
+        }
+        combined
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JobStatus {
     Pending,
@@ -1051,6 +1458,10 @@ pub struct StageResult {
     pub status: StageStatus,
     pub jobs: Vec<JobResult>,
     pub duration: Duration,
+    /// When the stage started; `None` if it never ran
+    pub started_at: Option<SystemTime>,
+    /// When the stage finished; `None` if it never ran
+    pub finished_at: Option<SystemTime>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1067,20 +1478,29 @@ pub enum StageStatus {
 #[derive(Debug, Clone)]
 pub struct ExecutionContext {
     pub pipeline_name: String,
+    /// Unique ID of this run, a ULID; `Roxid.RunId` in the pipeline
+    pub run_id: String,
     pub env: HashMap<String, String>,
     pub working_dir: String,
     pub variables: HashMap<String, String>,
     pub parameters: HashMap<String, serde_yaml::Value>,
+    /// CI-parity mode: roxid-only expression functions such as `env()` fail
+    pub strict: bool,
+    /// Values of the variable groups the pipeline references (`- group: name`)
+    pub variable_groups: HashMap<String, BTreeMap<String, String>>,
 }
 
 impl ExecutionContext {
     pub fn new(pipeline_name: String, working_dir: String) -> Self {
         Self {
             pipeline_name,
+            run_id: new_run_id(),
             env: HashMap::new(),
             working_dir,
             variables: HashMap::new(),
             parameters: HashMap::new(),
+            strict: false,
+            variable_groups: HashMap::new(),
         }
     }
 
@@ -1089,6 +1509,12 @@ impl ExecutionContext {
         self
     }
 
+    /// Use a known run ID instead of a new one, e.g. to reproduce a run's logs
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = run_id.into();
+        self
+    }
+
     pub fn with_variables(mut self, variables: HashMap<String, String>) -> Self {
         self.variables = variables;
         self
@@ -1098,6 +1524,19 @@ impl ExecutionContext {
         self.parameters = parameters;
         self
     }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn with_variable_groups(
+        mut self,
+        variable_groups: HashMap<String, BTreeMap<String, String>>,
+    ) -> Self {
+        self.variable_groups = variable_groups;
+        self
+    }
 }
 
 // =============================================================================
@@ -1232,6 +1671,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_value_as_string() {
         assert_eq!(Value::Null.as_string(), "");
         assert_eq!(Value::Bool(true).as_string(), "true");
@@ -1252,5 +1692,30 @@ mod tests {
             DependsOn::Multiple(vec!["a".to_string(), "b".to_string()]).as_vec(),
             vec!["a".to_string(), "b".to_string()]
         );
+
+        // Lists inserted by template expressions are flattened
+        let nested: DependsOn = serde_yaml::from_str("[Build, [Lint, Test], []]").unwrap();
+        assert_eq!(nested.as_vec(), ["Build", "Lint", "Test"]);
+        let single: DependsOn = serde_yaml::from_str("Build").unwrap();
+        assert!(matches!(single, DependsOn::Single(name) if name == "Build"));
+        assert!(serde_yaml::from_str::<DependsOn>("{ stage: Build }").is_err());
+    }
+
+    #[test]
+    fn test_trigger_matches_changed_paths() {
+        let trigger = Trigger::Full(TriggerConfig {
+            paths: Some(PathFilter {
+                include: vec!["src".to_string(), "*.yml".to_string()],
+                exclude: vec!["src/docs".to_string()],
+            }),
+            ..Default::default()
+        });
+
+        assert!(trigger.matches_changed_paths(&["src/main.rs".to_string()]));
+        assert!(trigger.matches_changed_paths(&["azure-pipelines.yml".to_string()]));
+        assert!(!trigger.matches_changed_paths(&["src/docs/readme.md".to_string()]));
+        assert!(!trigger.matches_changed_paths(&["srcfoo/lib.rs".to_string()]));
+        assert!(!trigger.matches_changed_paths(&[]));
+        assert!(!Trigger::None.matches_changed_paths(&["src/main.rs".to_string()]));
     }
 }
@@ -0,0 +1,201 @@
+// YAML Normalization
+// Applies `<<` merge keys so anchored mappings reach the pipeline models the way Azure DevOps expands them
+
+use crate::parser::error::{ParseError, ParseResult};
+use crate::parser::limits::ParseLimits;
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+/// Merge the mappings named by `<<` keys into their parents, throughout `value`
+///
+/// serde_yaml expands `*alias` references while parsing but leaves `<<` as an
+/// ordinary key. Keys written next to `<<` win over merged ones, and in
+/// `<<: [*a, *b]` earlier mappings win over later ones. Returns whether
+/// `value` had any merge keys.
+pub fn apply_merge_keys(value: &mut Value) -> ParseResult<bool> {
+    if !has_merge_keys(value) {
+        return Ok(false);
+    }
+    value.apply_merge().map_err(|e| {
+        ParseError::yaml_error(format!("invalid `<<` merge: {}", e), 0, 0)
+            .with_suggestion("`<<` takes a mapping or a list of mappings, like `<<: *defaults`")
+    })?;
+    Ok(true)
+}
+
+/// Parse each document of a YAML stream with aliases expanded and merge keys
+/// applied; empty documents are skipped
+pub fn normalize_documents(content: &str, limits: &ParseLimits) -> ParseResult<Vec<Value>> {
+    limits.check_source(content)?;
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let mut value =
+            Value::deserialize(document).map_err(|e| ParseError::from_yaml_error(&e, content))?;
+        if value.is_null() {
+            continue;
+        }
+        limits.check_depth(&value)?;
+        apply_merge_keys(&mut value)?;
+        documents.push(value);
+    }
+    Ok(documents)
+}
+
+/// The canonical YAML of `documents`, separated by `---`
+pub fn to_canonical_yaml(documents: &[Value]) -> ParseResult<String> {
+    let mut yaml = String::new();
+    for (i, document) in documents.iter().enumerate() {
+        if i > 0 {
+            yaml.push_str("---\n");
+        }
+        let text = serde_yaml::to_string(document)
+            .map_err(|e| ParseError::new(format!("failed to write YAML: {}", e), 0, 0))?;
+        yaml.push_str(&text);
+    }
+    Ok(yaml)
+}
+
+fn has_merge_keys(value: &Value) -> bool {
+    match value {
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .any(|(key, value)| key.as_str() == Some("<<") || has_merge_keys(value)),
+        Value::Sequence(items) => items.iter().any(has_merge_keys),
+        Value::Tagged(tagged) => has_merge_keys(&tagged.value),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser, StepAction, TemplateEngine};
+
+    fn normalize(yaml: &str) -> Value {
+        normalize_documents(yaml, &ParseLimits::default())
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn test_merge_keys() {
+        let value = normalize(
+            "\
+defaults: &defaults
+  pool: linux
+  timeout: 10
+  env: &env
+    CI: 'true'
+single:
+  <<: *defaults
+  timeout: 20
+list:
+  <<: [{ pool: windows, retries: 1 }, *defaults]
+nested:
+  <<: { inner: { <<: *env, MODE: fast } }
+alias: *env
+",
+        );
+        // Explicit keys win; earlier mappings in a list win over later ones
+        assert_eq!(value["single"]["pool"], "linux");
+        assert_eq!(value["single"]["timeout"], 20);
+        assert_eq!(value["list"]["pool"], "windows");
+        assert_eq!(value["list"]["timeout"], 10);
+        assert_eq!(value["nested"]["inner"]["CI"], "true");
+        assert_eq!(value["nested"]["inner"]["MODE"], "fast");
+        assert_eq!(value["alias"]["CI"], "true");
+        assert!(!has_merge_keys(&value));
+
+        let mut scalar: Value = serde_yaml::from_str("a:\n  <<: 1\n").unwrap();
+        let err = apply_merge_keys(&mut scalar).unwrap_err();
+        assert!(err.message.contains("invalid `<<` merge"));
+
+        let mut plain: Value = serde_yaml::from_str("a: &a 1\nb: *a\n").unwrap();
+        assert!(!apply_merge_keys(&mut plain).unwrap());
+    }
+
+    #[test]
+    fn test_merge_keys_in_pipeline() {
+        let pipeline = AzureParser::parse(
+            "\
+jobs:
+  - job: Linux
+    <<: &job
+      timeoutInMinutes: 15
+      pool:
+        vmImage: ubuntu-latest
+    steps:
+      - <<: &step
+          displayName: Build
+          condition: succeeded()
+        script: make
+      - <<: *step
+        script: make test
+        displayName: Test
+  - job: Windows
+    <<: *job
+    pool:
+      vmImage: windows-latest
+    steps:
+      - script: build.cmd
+",
+        )
+        .unwrap();
+        let pipeline = normalize_pipeline(pipeline);
+        let jobs = &pipeline.stages[0].jobs;
+        assert_eq!(jobs[0].timeout_in_minutes, Some(15));
+        assert_eq!(jobs[1].timeout_in_minutes, Some(15));
+        let steps = &jobs[0].steps;
+        assert_eq!(steps[0].display_name.as_deref(), Some("Build"));
+        assert_eq!(steps[1].display_name.as_deref(), Some("Test"));
+        assert_eq!(steps[1].condition.as_deref(), Some("succeeded()"));
+        assert!(matches!(&steps[1].action, StepAction::Script(s) if s.script == "make test"));
+    }
+
+    #[test]
+    fn test_merge_keys_in_template() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("steps.yml"),
+            "\
+parameters:
+  - name: target
+    default: all
+steps:
+  - <<: &base
+      displayName: Make
+      env:
+        CI: 'true'
+    script: make ${{ parameters.target }}
+  - <<: *base
+    script: make check
+",
+        )
+        .unwrap();
+        let pipeline = AzureParser::parse("steps:\n  - template: steps.yml\n").unwrap();
+        let resolved = TemplateEngine::new(dir.path().to_path_buf())
+            .resolve_pipeline(pipeline)
+            .unwrap();
+        assert_eq!(resolved.steps.len(), 2);
+        assert_eq!(resolved.steps[1].display_name.as_deref(), Some("Make"));
+        assert_eq!(
+            resolved.steps[1].env.get("CI").map(String::as_str),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn test_canonical_yaml() {
+        let documents = normalize_documents(
+            "---\nbase: &b { x: 1 }\nuse:\n  <<: *b\n  y: 2\n---\n---\nname: second\n",
+            &ParseLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(
+            to_canonical_yaml(&documents).unwrap(),
+            "base:\n  x: 1\nuse:\n  y: 2\n  x: 1\n---\nname: second\n"
+        );
+    }
+}
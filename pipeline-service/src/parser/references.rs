@@ -0,0 +1,351 @@
+// Expression Reference Checks
+// Finds dependencies, stageDependencies, variables and parameters references naming nothing the pipeline declares
+
+use crate::expression::{
+    extract_expressions, Expr, ExprParser, ExpressionType, Reference, ReferencePart,
+};
+use crate::parser::error::ValidationError;
+use crate::parser::models::{Job, MatrixStrategy, Pipeline, Stage, StepAction, Variable};
+use crate::utils::closest_match;
+
+/// Names an expression can refer to from where it's written
+struct Scope<'a> {
+    pipeline: &'a Pipeline,
+    /// Stage names for `dependencies.X` in stage conditions, else the jobs of
+    /// the stage
+    dependencies: Vec<&'a str>,
+    /// `None` where a variable group, variable template or matrix expression
+    /// makes the set unknowable
+    variables: Option<Vec<String>>,
+}
+
+/// Check the `dependencies.X`, `stageDependencies.X.Y`, `variables.Foo` and
+/// `parameters.bar` references in conditions and variable values against the
+/// stages, jobs, variables and parameters the pipeline declares
+///
+/// Expects a normalized pipeline with its templates resolved. Variables with
+/// a dot in their name (`Build.SourcesDirectory`, `step.output`) are
+/// predefined or set at run time and aren't checked, and neither are names
+/// scripts set with `##vso[task.setvariable]`.
+pub fn check_references(pipeline: &Pipeline) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let set_at_runtime = runtime_variables(pipeline);
+    let stage_names: Vec<&str> = pipeline
+        .stages
+        .iter()
+        .filter_map(|s| s.stage.as_deref())
+        .collect();
+
+    for stage in &pipeline.stages {
+        let stage_path = format!("stages.{}", stage.stage.as_deref().unwrap_or("unknown"));
+        let mut stage_vars = declared_variables(&pipeline.variables, &set_at_runtime);
+        extend_variables(&mut stage_vars, &stage.variables);
+        let scope = Scope {
+            pipeline,
+            dependencies: stage_names.clone(),
+            variables: stage_vars.clone(),
+        };
+        if let Some(condition) = &stage.condition {
+            scope.check(
+                condition,
+                true,
+                &format!("{}.condition", stage_path),
+                &mut errors,
+            );
+        }
+        scope.check_variables(&stage.variables, &stage_path, &mut errors);
+
+        let job_names: Vec<&str> = stage.jobs.iter().filter_map(|j| j.identifier()).collect();
+        for job in &stage.jobs {
+            let job_path = format!(
+                "{}.jobs.{}",
+                stage_path,
+                job.identifier().unwrap_or("unknown")
+            );
+            let mut job_vars = stage_vars.clone();
+            extend_variables(&mut job_vars, &job.variables);
+            add_matrix_variables(&mut job_vars, job);
+            let scope = Scope {
+                pipeline,
+                dependencies: job_names.clone(),
+                variables: job_vars,
+            };
+            if let Some(condition) = &job.condition {
+                scope.check(
+                    condition,
+                    true,
+                    &format!("{}.condition", job_path),
+                    &mut errors,
+                );
+            }
+            scope.check_variables(&job.variables, &job_path, &mut errors);
+            for (i, step) in job.steps.iter().enumerate() {
+                if let Some(condition) = &step.condition {
+                    scope.check(
+                        condition,
+                        true,
+                        &format!("{}.steps[{}].condition", job_path, i),
+                        &mut errors,
+                    );
+                }
+            }
+        }
+    }
+    errors
+}
+
+impl Scope<'_> {
+    fn check_variables(
+        &self,
+        variables: &[Variable],
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for variable in variables {
+            if let Variable::KeyValue { name, value, .. } = variable {
+                self.check(
+                    value,
+                    false,
+                    &format!("{}.variables.{}", path, name),
+                    errors,
+                );
+            }
+        }
+    }
+
+    /// Check the expressions in `text`; a condition is an expression itself
+    fn check(&self, text: &str, condition: bool, path: &str, errors: &mut Vec<ValidationError>) {
+        let mut expressions: Vec<String> = extract_expressions(text)
+            .into_iter()
+            .filter_map(|e| match e {
+                ExpressionType::CompileTime(expr) | ExpressionType::Runtime(expr) => Some(expr),
+                _ => None,
+            })
+            .collect();
+        let trimmed = text.trim();
+        if condition && !trimmed.starts_with("${{") && !trimmed.starts_with("$[") {
+            expressions.push(trimmed.to_string());
+        }
+
+        for expr in expressions {
+            // Unparseable expressions are reported when they're evaluated
+            let Ok(parsed) = ExprParser::parse_str(&expr) else {
+                continue;
+            };
+            for reference in parsed.references() {
+                if let Some(error) = self.check_reference(reference, path) {
+                    errors.push(error);
+                }
+            }
+        }
+    }
+
+    fn check_reference(&self, reference: &Reference, path: &str) -> Option<ValidationError> {
+        let names: Vec<&str> = reference.parts.iter().map_while(part_name).collect();
+        let root = names.first()?;
+
+        if root.eq_ignore_ascii_case("parameters") {
+            let name = reference.parameter_name()?;
+            let declared: Vec<&str> = self
+                .pipeline
+                .parameters
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect();
+            return unknown("parameter", name, &declared, path);
+        }
+        if root.eq_ignore_ascii_case("variables") {
+            let name = names.get(1)?;
+            let declared = self.variables.as_ref()?;
+            if name.contains('.') {
+                return None;
+            }
+            let declared: Vec<&str> = declared.iter().map(String::as_str).collect();
+            return unknown("variable", name, &declared, path);
+        }
+        if root.eq_ignore_ascii_case("dependencies") {
+            let name = names.get(1)?;
+            return unknown("dependency", name, &self.dependencies, path);
+        }
+        if root.eq_ignore_ascii_case("stageDependencies") {
+            let stage_name = names.get(1)?;
+            let stages: Vec<&Stage> = self.pipeline.stages.iter().collect();
+            let stage_names: Vec<&str> = stages.iter().filter_map(|s| s.stage.as_deref()).collect();
+            if let Some(error) = unknown("stage", stage_name, &stage_names, path) {
+                return Some(error);
+            }
+            let job_name = names.get(2)?;
+            let stage = stages.iter().find(|s| {
+                s.stage
+                    .as_deref()
+                    .is_some_and(|s| s.eq_ignore_ascii_case(stage_name))
+            })?;
+            let job_names: Vec<&str> = stage.jobs.iter().filter_map(Job::identifier).collect();
+            return unknown(
+                &format!("job in stage '{}'", stage_name),
+                job_name,
+                &job_names,
+                path,
+            );
+        }
+        None
+    }
+}
+
+/// An error for `name` unless it's one of `declared`
+fn unknown(kind: &str, name: &str, declared: &[&str], path: &str) -> Option<ValidationError> {
+    if declared.iter().any(|d| d.eq_ignore_ascii_case(name)) {
+        return None;
+    }
+    let error = ValidationError::new(format!("reference to unknown {} '{}'", kind, name), path);
+    Some(match closest_match(name, declared.iter().copied()) {
+        Some(close) => error.with_suggestion(format!("did you mean '{}'?", close)),
+        None if declared.is_empty() => error,
+        None => error.with_suggestion(format!("declared: {}", declared.join(", "))),
+    })
+}
+
+/// The name a reference part spells out: `.name` or `['name']`
+fn part_name(part: &ReferencePart) -> Option<&str> {
+    match part {
+        ReferencePart::Property(name) => Some(name),
+        ReferencePart::Index(index) => match index.as_ref() {
+            Expr::String(name) => Some(name),
+            _ => None,
+        },
+    }
+}
+
+fn declared_variables(variables: &[Variable], set_at_runtime: &[String]) -> Option<Vec<String>> {
+    let mut declared = Some(set_at_runtime.to_vec());
+    extend_variables(&mut declared, variables);
+    declared
+}
+
+fn extend_variables(declared: &mut Option<Vec<String>>, variables: &[Variable]) {
+    for variable in variables {
+        match (variable, declared.as_mut()) {
+            (Variable::KeyValue { name, .. }, Some(names)) => names.push(name.clone()),
+            (Variable::Group { .. } | Variable::Template { .. }, _) => *declared = None,
+            _ => {}
+        }
+    }
+}
+
+fn add_matrix_variables(declared: &mut Option<Vec<String>>, job: &Job) {
+    match job.strategy.as_ref().and_then(|s| s.matrix.as_ref()) {
+        Some(MatrixStrategy::Inline(matrix)) => {
+            if let Some(names) = declared.as_mut() {
+                names.extend(matrix.values().flat_map(|vars| vars.keys().cloned()));
+            }
+        }
+        Some(MatrixStrategy::Expression(_)) => *declared = None,
+        None => {}
+    }
+}
+
+/// Variables any script in the pipeline sets with `##vso[task.setvariable variable=NAME]`
+fn runtime_variables(pipeline: &Pipeline) -> Vec<String> {
+    let mut names = Vec::new();
+    let steps = pipeline
+        .stages
+        .iter()
+        .flat_map(|s| &s.jobs)
+        .flat_map(|j| &j.steps);
+    for step in steps {
+        let scripts: Vec<&str> = match &step.action {
+            StepAction::Script(s) => vec![&s.script],
+            StepAction::Bash(s) => vec![&s.bash],
+            StepAction::Pwsh(s) => vec![&s.pwsh],
+            StepAction::PowerShell(s) => vec![&s.powershell],
+            StepAction::Task(t) => t.inputs.values().map(String::as_str).collect(),
+            _ => Vec::new(),
+        };
+        for script in scripts {
+            for (at, _) in script.match_indices("task.setvariable") {
+                let rest = &script[at..];
+                let Some(start) = rest.find("variable=") else {
+                    continue;
+                };
+                let name: String = rest[start + "variable=".len()..]
+                    .chars()
+                    .take_while(|c| !matches!(c, ';' | ']' | ' '))
+                    .collect();
+                if !name.is_empty() {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    fn check(yaml: &str) -> Vec<String> {
+        let pipeline = normalize_pipeline(AzureParser::parse(yaml).unwrap());
+        check_references(&pipeline)
+            .into_iter()
+            .map(|e| format!("{}: {}", e.path, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_references() {
+        let errors = check(
+            r###"
+parameters:
+  - name: deploy
+    type: boolean
+    default: false
+variables:
+  configuration: Release
+stages:
+  - stage: Build
+    jobs:
+      - job: Compile
+        steps:
+          - bash: echo "##vso[task.setvariable variable=version;isOutput=true]1.0"
+            name: setVersion
+          - script: echo tests
+            condition: eq(variables['Configuraton'], 'Release')
+      - job: Package
+        dependsOn: Compile
+        condition: succeeded('Compile')
+        variables:
+          version: $[ dependencies.Compiles.outputs['setVersion.version'] ]
+        steps:
+          - script: echo $(version)
+            condition: and(eq(variables.version, '1.0'), eq(variables['Build.Reason'], 'Manual'))
+  - stage: Deploy
+    condition: and(succeeded(), eq('${{ parameters.deploi }}', 'true'), dependencies.Build)
+    variables:
+      fromBuild: $[ stageDependencies.Build.Packag.outputs['x.y'] ]
+    jobs:
+      - job: Ship
+        steps:
+          - script: echo ship
+            condition: eq(stageDependencies.Biuld.Compile.result, 'Succeeded')
+"###,
+        );
+        assert_eq!(
+            errors,
+            [
+                "stages.Build.jobs.Compile.steps[1].condition: reference to unknown variable 'Configuraton'",
+                "stages.Build.jobs.Package.variables.version: reference to unknown dependency 'Compiles'",
+                "stages.Deploy.condition: reference to unknown parameter 'deploi'",
+                "stages.Deploy.variables.fromBuild: reference to unknown job in stage 'Build' 'Packag'",
+                "stages.Deploy.jobs.Ship.steps[0].condition: reference to unknown stage 'Biuld'",
+            ]
+        );
+
+        // Variable groups could declare anything
+        let errors = check(
+            "variables:\n  - group: shared\nsteps:\n  - script: echo\n    condition: eq(variables.fromGroup, 'x')\n",
+        );
+        assert!(errors.is_empty());
+    }
+}
@@ -5,12 +5,16 @@
 use crate::expression::{ExpressionContext, ExpressionEngine};
 use crate::parser::azure::AzureParser;
 use crate::parser::error::{ParseError, ParseErrorKind, ParseResult};
+use crate::parser::limits::ParseLimits;
 use crate::parser::models::*;
+use crate::parser::normalize::apply_merge_keys;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+
 /// Maximum template inclusion depth to prevent infinite recursion
 const MAX_TEMPLATE_DEPTH: usize = 50;
 
@@ -69,7 +73,11 @@ impl TemplateError {
     }
 
     pub fn to_parse_error(&self) -> ParseError {
-        ParseError::new(self.to_string(), 0, 0).with_kind(ParseErrorKind::TemplateError)
+        let kind = match self.kind {
+            TemplateErrorKind::ExpressionError => ParseErrorKind::ExpressionError,
+            _ => ParseErrorKind::TemplateError,
+        };
+        ParseError::new(self.to_string(), 0, 0).with_kind(kind)
     }
 }
 
@@ -107,6 +115,22 @@ pub struct TemplateFile {
     pub content: TemplateContent,
 }
 
+/// One template reference the engine followed, in the order it was expanded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateInclude {
+    /// Repository-relative path of the file holding the reference; `None`
+    /// for the pipeline itself
+    pub parent: Option<String>,
+    /// Repository-relative path of the included template
+    pub template: String,
+    /// What the reference expands to: extends, stages, jobs, steps or variables
+    pub kind: &'static str,
+    /// Parameters passed with the reference
+    pub parameters: BTreeMap<String, serde_yaml::Value>,
+    /// 1 for templates the pipeline includes directly
+    pub depth: usize,
+}
+
 /// A raw template file with its declared parameters (pre-expression processing)
 #[derive(Debug, Clone)]
 struct RawTemplateFile {
@@ -127,6 +151,8 @@ enum TemplateDirective {
     Else,
     /// `${{ each <var> in <collection> }}`
     Each(String, String),
+    /// `${{ insert }}`: merges a mapping into the enclosing mapping
+    Insert,
 }
 
 /// Template resolution engine
@@ -144,6 +170,12 @@ pub struct TemplateEngine {
     resource_repos: HashMap<String, PathBuf>,
     /// Track included templates for cycle detection
     include_stack: Vec<String>,
+    /// Reject roxid-only expression functions such as `env()`
+    strict: bool,
+    /// Every template reference followed, for `roxid graph --templates`
+    trace: Vec<TemplateInclude>,
+    /// Size, nesting and alias limits for template files
+    limits: ParseLimits,
 }
 
 impl TemplateEngine {
@@ -153,6 +185,9 @@ impl TemplateEngine {
             repo_root,
             resource_repos: HashMap::new(),
             include_stack: Vec::new(),
+            strict: false,
+            trace: Vec::new(),
+            limits: ParseLimits::default(),
         }
     }
 
@@ -162,9 +197,29 @@ impl TemplateEngine {
         self
     }
 
+    /// Expand templates in CI-parity mode, where `env()` is an error
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Parse template files within `limits` instead of the defaults
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The template references followed so far, parents before the templates
+    /// they include
+    pub fn trace(&self) -> &[TemplateInclude] {
+        &self.trace
+    }
+
     /// Resolve all templates in a pipeline, returning a fully expanded pipeline
     /// with no template references remaining.
     pub fn resolve_pipeline(&mut self, pipeline: Pipeline) -> ParseResult<Pipeline> {
+        // Errors leave their templates on the stack; start each pipeline afresh
+        self.include_stack.clear();
         let mut resolved = pipeline;
 
         // 1. Handle extends template (pipeline inheritance)
@@ -187,6 +242,42 @@ impl TemplateEngine {
         Ok(resolved)
     }
 
+    /// Expand a single template file as a reference to it with `parameters`
+    /// would, without a pipeline around it (used to test template libraries)
+    pub fn expand_template(
+        &mut self,
+        template_ref: &str,
+        parameters: &HashMap<String, serde_yaml::Value>,
+    ) -> ParseResult<TemplateContent> {
+        self.include_stack.clear();
+        let raw = self.load_template_file(template_ref)?;
+        self.pop_template();
+
+        match raw.content {
+            RawTemplateContent::Steps(_) => self
+                .expand_step_template(template_ref, parameters)
+                .map(TemplateContent::Steps),
+            RawTemplateContent::Jobs(_) => self
+                .expand_job_template(template_ref, parameters)
+                .map(TemplateContent::Jobs),
+            RawTemplateContent::Stages(_) => self
+                .expand_stage_template(template_ref, parameters)
+                .map(TemplateContent::Stages),
+            RawTemplateContent::Variables(_) => self
+                .expand_variable_template(template_ref, parameters)
+                .map(TemplateContent::Variables),
+            RawTemplateContent::Pipeline(_) => Err(TemplateError::new(
+                format!(
+                    "template '{}' has no steps, jobs, stages or variables",
+                    template_ref
+                ),
+                TemplateErrorKind::TypeMismatch,
+            )
+            .with_path(template_ref)
+            .to_parse_error()),
+        }
+    }
+
     // =========================================================================
     // Extends Resolution
     // =========================================================================
@@ -197,6 +288,7 @@ impl TemplateEngine {
         let canonical = self.canonical_path(&template_path);
 
         self.push_template(&canonical)?;
+        self.record_include("extends", &extends.parameters);
 
         let template_content = fs::read_to_string(&template_path).map_err(|e| {
             TemplateError::new(
@@ -207,17 +299,18 @@ impl TemplateEngine {
             .to_parse_error()
         })?;
 
-        let mut parent = AzureParser::parse(&template_content).map_err(|e| {
-            ParseError::new(
-                format!(
-                    "error in extends template '{}': {}",
-                    extends.template, e.message
-                ),
-                e.line,
-                e.column,
-            )
-            .with_kind(ParseErrorKind::TemplateError)
-        })?;
+        let mut parent =
+            AzureParser::parse_with_limits(&template_content, &self.limits).map_err(|e| {
+                ParseError::new(
+                    format!(
+                        "error in extends template '{}': {}",
+                        extends.template, e.message
+                    ),
+                    e.line,
+                    e.column,
+                )
+                .with_kind(ParseErrorKind::TemplateError)
+            })?;
 
         // Validate parameters
         let params =
@@ -313,10 +406,12 @@ impl TemplateEngine {
         call_params: &HashMap<String, serde_yaml::Value>,
     ) -> ParseResult<Vec<Variable>> {
         let raw_template_file = self.load_template_file(template_ref)?;
+        self.record_include("variables", call_params);
 
         // Validate and resolve parameters
-        let params =
-            self.resolve_parameters(&raw_template_file.parameters, call_params, template_ref)?;
+        let params = self
+            .resolve_parameters(&raw_template_file.parameters, call_params, template_ref)
+            .map_err(|e| self.locate_reference(e, template_ref))?;
 
         // Build engine and process ${{ if }}, ${{ each }}, and parameter substitution
         let engine = self.build_parameter_engine(&params);
@@ -379,9 +474,11 @@ impl TemplateEngine {
         call_params: &HashMap<String, serde_yaml::Value>,
     ) -> ParseResult<Vec<Stage>> {
         let raw_template_file = self.load_template_file(template_ref)?;
+        self.record_include("stages", call_params);
 
-        let params =
-            self.resolve_parameters(&raw_template_file.parameters, call_params, template_ref)?;
+        let params = self
+            .resolve_parameters(&raw_template_file.parameters, call_params, template_ref)
+            .map_err(|e| self.locate_reference(e, template_ref))?;
 
         // Build engine and process ${{ if }}, ${{ each }}, and parameter substitution
         let engine = self.build_parameter_engine(&params);
@@ -397,6 +494,11 @@ impl TemplateEngine {
                     expanded_stage.variables =
                         self.resolve_variable_templates(&expanded_stage.variables)?;
                     expanded_stage.jobs = self.resolve_job_templates(&expanded_stage.jobs)?;
+                    expanded_stage.from_template = Some(template_ref.to_string());
+                    for job in &mut expanded_stage.jobs {
+                        job.from_template
+                            .get_or_insert_with(|| template_ref.to_string());
+                    }
                     resolved.push(expanded_stage);
                 }
                 Ok(resolved)
@@ -445,9 +547,11 @@ impl TemplateEngine {
         call_params: &HashMap<String, serde_yaml::Value>,
     ) -> ParseResult<Vec<Job>> {
         let raw_template_file = self.load_template_file(template_ref)?;
+        self.record_include("jobs", call_params);
 
-        let params =
-            self.resolve_parameters(&raw_template_file.parameters, call_params, template_ref)?;
+        let params = self
+            .resolve_parameters(&raw_template_file.parameters, call_params, template_ref)
+            .map_err(|e| self.locate_reference(e, template_ref))?;
 
         // Build engine and process ${{ if }}, ${{ each }}, and parameter substitution
         let engine = self.build_parameter_engine(&params);
@@ -463,6 +567,7 @@ impl TemplateEngine {
                     expanded_job.variables =
                         self.resolve_variable_templates(&expanded_job.variables)?;
                     expanded_job.steps = self.resolve_step_templates(&expanded_job.steps)?;
+                    expanded_job.from_template = Some(template_ref.to_string());
                     resolved.push(expanded_job);
                 }
                 Ok(resolved)
@@ -507,9 +612,11 @@ impl TemplateEngine {
         call_params: &HashMap<String, serde_yaml::Value>,
     ) -> ParseResult<Vec<Step>> {
         let raw_template_file = self.load_template_file(template_ref)?;
+        self.record_include("steps", call_params);
 
-        let params =
-            self.resolve_parameters(&raw_template_file.parameters, call_params, template_ref)?;
+        let params = self
+            .resolve_parameters(&raw_template_file.parameters, call_params, template_ref)
+            .map_err(|e| self.locate_reference(e, template_ref))?;
 
         // Build engine and process ${{ if }}, ${{ each }}, and parameter substitution
         let engine = self.build_parameter_engine(&params);
@@ -584,8 +691,15 @@ impl TemplateEngine {
         content: &str,
     ) -> ParseResult<RawTemplateFile> {
         // Parse as generic YAML first
-        let yaml: serde_yaml::Value =
-            serde_yaml::from_str(content).map_err(|e| ParseError::from_yaml_error(&e, content))?;
+        self.limits
+            .check_source(content)
+            .map_err(|e| self.locate(e, ""))?;
+        let mut yaml: serde_yaml::Value = serde_yaml::from_str(content)
+            .map_err(|e| self.locate(ParseError::from_yaml_error(&e, content), ""))?;
+        self.limits
+            .check_depth(&yaml)
+            .map_err(|e| self.locate(e, ""))?;
+        apply_merge_keys(&mut yaml).map_err(|e| self.locate(e, ""))?;
 
         let mapping = yaml.as_mapping().ok_or_else(|| {
             TemplateError::new(
@@ -598,7 +712,7 @@ impl TemplateEngine {
 
         // Extract parameters
         let parameters = if let Some(params_val) = mapping.get("parameters") {
-            self.parse_template_parameters(params_val)?
+            Self::parse_template_parameters(params_val)?
         } else {
             Vec::new()
         };
@@ -716,8 +830,7 @@ impl TemplateEngine {
     }
 
     /// Parse template parameter declarations
-    fn parse_template_parameters(
-        &self,
+    pub(crate) fn parse_template_parameters(
         params_val: &serde_yaml::Value,
     ) -> ParseResult<Vec<Parameter>> {
         match params_val {
@@ -760,6 +873,9 @@ impl TemplateEngine {
     // =========================================================================
 
     /// Validate and resolve parameters passed to a template
+    ///
+    /// Defaults may use `${{ }}` expressions over other parameters; they are
+    /// evaluated once everything they reference is resolved.
     fn resolve_parameters(
         &self,
         declared: &[Parameter],
@@ -767,6 +883,7 @@ impl TemplateEngine {
         template_ref: &str,
     ) -> ParseResult<HashMap<String, Value>> {
         let mut resolved = HashMap::new();
+        let mut defaulted = Vec::new();
 
         for param in declared {
             if let Some(provided_val) = provided.get(&param.name) {
@@ -791,9 +908,8 @@ impl TemplateEngine {
                 }
 
                 resolved.insert(param.name.clone(), yaml_to_value(provided_val));
-            } else if let Some(default) = &param.default {
-                // Use default value
-                resolved.insert(param.name.clone(), yaml_to_value(default));
+            } else if param.default.is_some() {
+                defaulted.push(param);
             } else {
                 // Required parameter missing
                 return Err(TemplateError::new(
@@ -815,9 +931,95 @@ impl TemplateEngine {
             }
         }
 
+        let mut visiting = Vec::new();
+        for param in &defaulted {
+            self.resolve_default(
+                param,
+                &defaulted,
+                &mut resolved,
+                &mut visiting,
+                template_ref,
+            )?;
+        }
+
         Ok(resolved)
     }
 
+    /// Resolve a parameter's default, first resolving the defaults of the
+    /// parameters its expressions reference
+    fn resolve_default<'a>(
+        &self,
+        param: &'a Parameter,
+        defaulted: &[&'a Parameter],
+        resolved: &mut HashMap<String, Value>,
+        visiting: &mut Vec<&'a str>,
+        template_ref: &str,
+    ) -> ParseResult<()> {
+        use crate::expression::lexer::{extract_expressions, ExpressionType};
+        use crate::expression::ExprParser;
+
+        if resolved.contains_key(&param.name) {
+            return Ok(());
+        }
+        if let Some(start) = visiting.iter().position(|name| *name == param.name) {
+            let mut cycle = visiting[start..].to_vec();
+            cycle.push(&param.name);
+            return Err(TemplateError::new(
+                format!("parameter defaults form a cycle: {}", cycle.join(" -> ")),
+                TemplateErrorKind::CircularReference,
+            )
+            .with_path(template_ref)
+            .to_parse_error());
+        }
+        let Some(default) = &param.default else {
+            return Ok(());
+        };
+
+        let mut texts = Vec::new();
+        yaml_strings(default, &mut texts);
+        let expressions: Vec<String> = texts
+            .iter()
+            .flat_map(|text| extract_expressions(text))
+            .filter_map(|expr| match expr {
+                ExpressionType::CompileTime(expr) => Some(expr),
+                _ => None,
+            })
+            .collect();
+        if expressions.is_empty() {
+            resolved.insert(param.name.clone(), yaml_to_value(default));
+            return Ok(());
+        }
+
+        visiting.push(&param.name);
+        for expr in &expressions {
+            // Unparseable expressions are reported when they're evaluated
+            let Ok(parsed) = ExprParser::parse_str(expr) else {
+                continue;
+            };
+            for name in parsed
+                .references()
+                .iter()
+                .filter_map(|r| r.parameter_name())
+            {
+                if let Some(dependency) = defaulted.iter().find(|p| p.name == name) {
+                    self.resolve_default(dependency, defaulted, resolved, visiting, template_ref)?;
+                }
+            }
+        }
+        visiting.pop();
+
+        let engine = self.build_parameter_engine(resolved);
+        let value = match default {
+            serde_yaml::Value::String(text) => match self.evaluate_whole(text, &engine)? {
+                Some(value) => value,
+                None => Value::String(self.substitute_compile_time(text, &engine)?),
+            },
+            other => yaml_to_value(&self.process_template_expressions(other, &engine)?),
+        };
+        resolved.insert(param.name.clone(), value);
+        Ok(())
+    }
+
     /// Validate that a parameter value matches the declared type
     fn validate_parameter_type(
         &self,
@@ -826,33 +1028,8 @@ impl TemplateEngine {
         param_type: &ParameterType,
         template_ref: &str,
     ) -> ParseResult<()> {
-        let valid = match param_type {
-            ParameterType::String => value.is_string() || value.is_number() || value.is_bool(),
-            ParameterType::Number => {
-                value.is_number()
-                    || value
-                        .as_str()
-                        .map(|s| s.parse::<f64>().is_ok())
-                        .unwrap_or(false)
-            }
-            ParameterType::Boolean => {
-                value.is_bool()
-                    || value
-                        .as_str()
-                        .map(|s| s == "true" || s == "false")
-                        .unwrap_or(false)
-            }
-            ParameterType::Object => value.is_mapping() || value.is_sequence(),
-            ParameterType::Step => value.is_mapping(),
-            ParameterType::StepList => value.is_sequence(),
-            ParameterType::Job => value.is_mapping(),
-            ParameterType::JobList => value.is_sequence(),
-            ParameterType::Stage => value.is_mapping(),
-            ParameterType::StageList => value.is_sequence(),
-        };
-
-        if !valid {
-            Err(TemplateError::new(
+        if !param_type.accepts(value) {
+            return Err(TemplateError::new(
                 format!(
                     "parameter '{}' expected type {:?} but got {:?}",
                     name, param_type, value
@@ -860,9 +1037,61 @@ impl TemplateEngine {
                 TemplateErrorKind::TypeMismatch,
             )
             .with_path(template_ref)
-            .to_parse_error())
-        } else {
-            Ok(())
+            .to_parse_error());
+        }
+
+        self.validate_pipeline_content(value, param_type)
+            .map_err(|message| {
+                TemplateError::new(
+                    format!(
+                        "parameter '{}' is not a valid {}: {}",
+                        name,
+                        param_type.name(),
+                        message
+                    ),
+                    TemplateErrorKind::TypeMismatch,
+                )
+                .with_path(template_ref)
+                .to_parse_error()
+            })
+    }
+
+    /// Check that a step, job or stage parameter (or list of them) deserializes
+    /// into what it declares, so mistakes surface at the call site
+    ///
+    /// Template directives in lists are skipped; they're expanded where the
+    /// parameter is used.
+    fn validate_pipeline_content(
+        &self,
+        value: &serde_yaml::Value,
+        param_type: &ParameterType,
+    ) -> Result<(), String> {
+        fn check<T: serde::de::DeserializeOwned>(value: &serde_yaml::Value) -> Result<(), String> {
+            serde_yaml::from_value::<T>(value.clone())
+                .map(drop)
+                .map_err(|e| e.to_string())
+        }
+        let check_item = |item: &serde_yaml::Value| match param_type {
+            ParameterType::Step | ParameterType::StepList => check::<Step>(item),
+            ParameterType::Job | ParameterType::JobList => check::<Job>(item),
+            ParameterType::Stage | ParameterType::StageList => check::<Stage>(item),
+            _ => Ok(()),
+        };
+
+        match (param_type, value) {
+            (
+                ParameterType::StepList | ParameterType::JobList | ParameterType::StageList,
+                serde_yaml::Value::Sequence(items),
+            ) => {
+                for (i, item) in items.iter().enumerate() {
+                    if self.extract_directive(item).is_some() {
+                        continue;
+                    }
+                    check_item(item).map_err(|e| format!("item {}: {}", i + 1, e))?;
+                }
+                Ok(())
+            }
+            _ => check_item(value),
         }
     }
 
@@ -874,6 +1103,7 @@ impl TemplateEngine {
     fn build_parameter_engine(&self, params: &HashMap<String, Value>) -> ExpressionEngine {
         let ctx = ExpressionContext {
             parameters: params.clone(),
+            strict: self.strict,
             ..Default::default()
         };
         ExpressionEngine::new(ctx)
@@ -896,14 +1126,17 @@ impl TemplateEngine {
                 ExpressionType::Text(s) => result.push_str(&s),
                 ExpressionType::CompileTime(expr_str) => {
                     let value = engine.evaluate_compile_time(&expr_str).map_err(|e| {
-                        TemplateError::new(
-                            format!(
-                                "expression error in '${{{{ {} }}}}': {}",
-                                expr_str, e.message
-                            ),
-                            TemplateErrorKind::ExpressionError,
+                        self.locate(
+                            TemplateError::new(
+                                format!(
+                                    "expression error in '${{{{ {} }}}}': {}",
+                                    expr_str, e.message
+                                ),
+                                TemplateErrorKind::ExpressionError,
+                            )
+                            .to_parse_error(),
+                            &expr_str,
                         )
-                        .to_parse_error()
                     })?;
                     result.push_str(&value.as_string());
                 }
@@ -1004,6 +1237,8 @@ impl TemplateEngine {
             new_stage.condition = Some(self.substitute_compile_time(condition, engine)?);
         }
 
+        new_stage.depends_on = self.substitute_depends_on(&stage.depends_on, engine)?;
+
         // Substitute in variables
         new_stage.variables = stage
             .variables
@@ -1037,6 +1272,8 @@ impl TemplateEngine {
             new_job.condition = Some(self.substitute_compile_time(condition, engine)?);
         }
 
+        new_job.depends_on = self.substitute_depends_on(&job.depends_on, engine)?;
+
         // Substitute in variables
         new_job.variables = job
             .variables
@@ -1054,6 +1291,27 @@ impl TemplateEngine {
         Ok(new_job)
     }
 
+    /// Substitute parameters in `dependsOn`; a lone expression yielding a
+    /// list (`dependsOn: ${{ parameters.stages }}`) depends on each name in it
+    fn substitute_depends_on(
+        &self,
+        depends_on: &DependsOn,
+        engine: &ExpressionEngine,
+    ) -> ParseResult<DependsOn> {
+        let mut names = Vec::new();
+        for name in depends_on.as_vec() {
+            match self.evaluate_whole(&name, engine)? {
+                Some(Value::Array(items)) => names.extend(items.iter().map(Value::as_string)),
+                _ => names.push(self.substitute_compile_time(&name, engine)?),
+            }
+        }
+        Ok(match depends_on {
+            DependsOn::Single(_) if names.len() == 1 => DependsOn::Single(names.remove(0)),
+            DependsOn::Single(_) | DependsOn::Multiple(_) => DependsOn::Multiple(names),
+            other => other.clone(),
+        })
+    }
+
     /// Substitute parameters in a step
     fn substitute_step_params(&self, step: &Step, engine: &ExpressionEngine) -> ParseResult<Step> {
         let mut new_step = step.clone();
@@ -1099,6 +1357,7 @@ impl TemplateEngine {
                     script: new_script,
                     working_directory: new_wd,
                     fail_on_stderr: script_step.fail_on_stderr,
+                    shell: script_step.shell.clone(),
                 }))
             }
             StepAction::Bash(bash_step) => {
@@ -1237,14 +1496,17 @@ impl TemplateEngine {
                         Some((TemplateDirective::If(condition), val)) => {
                             let cond_result =
                                 engine.evaluate_compile_time(&condition).map_err(|e| {
-                                    TemplateError::new(
-                                        format!(
-                                            "error evaluating if condition '{}': {}",
-                                            condition, e.message
-                                        ),
-                                        TemplateErrorKind::ExpressionError,
+                                    self.locate(
+                                        TemplateError::new(
+                                            format!(
+                                                "error evaluating if condition '{}': {}",
+                                                condition, e.message
+                                            ),
+                                            TemplateErrorKind::ExpressionError,
+                                        )
+                                        .to_parse_error(),
+                                        &condition,
                                     )
-                                    .to_parse_error()
                                 })?;
 
                             if cond_result.is_truthy() {
@@ -1257,14 +1519,17 @@ impl TemplateEngine {
                             if !chain_taken {
                                 let cond_result =
                                     engine.evaluate_compile_time(&condition).map_err(|e| {
-                                        TemplateError::new(
-                                            format!(
-                                                "error evaluating elseif condition '{}': {}",
-                                                condition, e.message
-                                            ),
-                                            TemplateErrorKind::ExpressionError,
+                                        self.locate(
+                                            TemplateError::new(
+                                                format!(
+                                                    "error evaluating elseif condition '{}': {}",
+                                                    condition, e.message
+                                                ),
+                                                TemplateErrorKind::ExpressionError,
+                                            )
+                                            .to_parse_error(),
+                                            &condition,
                                         )
-                                        .to_parse_error()
                                     })?;
 
                                 if cond_result.is_truthy() {
@@ -1285,14 +1550,17 @@ impl TemplateEngine {
                             let collection = engine
                                 .evaluate_compile_time(&collection_expr)
                                 .map_err(|e| {
-                                    TemplateError::new(
-                                        format!(
-                                            "error evaluating each collection '{}': {}",
-                                            collection_expr, e.message
-                                        ),
-                                        TemplateErrorKind::ExpressionError,
+                                    self.locate(
+                                        TemplateError::new(
+                                            format!(
+                                                "error evaluating each collection '{}': {}",
+                                                collection_expr, e.message
+                                            ),
+                                            TemplateErrorKind::ExpressionError,
+                                        )
+                                        .to_parse_error(),
+                                        &collection_expr,
                                     )
-                                    .to_parse_error()
                                 })?;
 
                             let items = self.value_to_iterable(&collection)?;
@@ -1305,10 +1573,17 @@ impl TemplateEngine {
                                 result.extend(expanded);
                             }
                         }
-                        None => {
+                        // `- ${{ insert }}: ...` is a mapping item; the mapping handles it
+                        Some((TemplateDirective::Insert, _)) | None => {
                             // Not a directive - process recursively and include
                             let processed = self.process_template_expressions(item, engine)?;
-                            result.push(processed);
+                            match processed {
+                                // `- ${{ parameters.steps }}` inserts the list's items
+                                serde_yaml::Value::Sequence(items) if item.is_string() => {
+                                    result.extend(items)
+                                }
+                                processed => result.push(processed),
+                            }
                         }
                     }
                 }
@@ -1386,6 +1661,15 @@ impl TemplateEngine {
                                     }
                                     continue;
                                 }
+                                TemplateDirective::Insert => {
+                                    let inserted = self.evaluate_insert(val, engine)?;
+                                    for (ik, iv) in inserted {
+                                        let processed =
+                                            self.process_template_expressions(&iv, engine)?;
+                                        result.insert(ik, processed);
+                                    }
+                                    continue;
+                                }
                             }
                         }
                     }
@@ -1397,6 +1681,13 @@ impl TemplateEngine {
                 Ok(serde_yaml::Value::Mapping(result))
             }
             serde_yaml::Value::String(s) => {
+                // A lone expression yielding a list or object (e.g. a jobList
+                // parameter) is inserted as YAML rather than as text
+                if let Some(value @ (Value::Array(_) | Value::Object(_))) =
+                    self.evaluate_whole(s, engine)?
+                {
+                    return Ok(value_to_yaml(&value));
+                }
                 // Substitute compile-time expressions in strings
                 let substituted = self.substitute_compile_time(s, engine)?;
                 Ok(serde_yaml::Value::String(substituted))
@@ -1406,6 +1697,69 @@ impl TemplateEngine {
         }
     }
 
+    /// Resolve the value of an `${{ insert }}` key to the mapping it inserts
+    ///
+    /// The value is either a mapping written in place or a single expression,
+    /// such as `${{ parameters.env }}`, that evaluates to an object.
+    fn evaluate_insert(
+        &self,
+        value: &serde_yaml::Value,
+        engine: &ExpressionEngine,
+    ) -> ParseResult<serde_yaml::Mapping> {
+        let inserted = match value {
+            serde_yaml::Value::String(text) => match self.evaluate_whole(text, engine)? {
+                Some(value) => value_to_yaml(&value),
+                None => value.clone(),
+            },
+            other => other.clone(),
+        };
+        match inserted {
+            serde_yaml::Value::Mapping(map) => Ok(map),
+            // An empty or missing parameter inserts nothing
+            serde_yaml::Value::Null => Ok(serde_yaml::Mapping::new()),
+            other => Err(self.locate(
+                TemplateError::new(
+                    format!(
+                        "${{{{ insert }}}} needs a mapping to merge, got: {}",
+                        yaml_to_value(&other).as_string()
+                    ),
+                    TemplateErrorKind::ExpressionError,
+                )
+                .to_parse_error(),
+                value.as_str().unwrap_or_default(),
+            )),
+        }
+    }
+
+    /// Evaluate `text` to a value when it consists of a single `${{ }}`
+    /// expression; `None` for anything else
+    fn evaluate_whole(&self, text: &str, engine: &ExpressionEngine) -> ParseResult<Option<Value>> {
+        use crate::expression::lexer::{extract_expressions, ExpressionType};
+
+        let mut expressions = extract_expressions(text.trim());
+        let (Some(ExpressionType::CompileTime(expr_str)), None) =
+            (expressions.pop(), expressions.pop())
+        else {
+            return Ok(None);
+        };
+        engine
+            .evaluate_compile_time(&expr_str)
+            .map(Some)
+            .map_err(|e| {
+                self.locate(
+                    TemplateError::new(
+                        format!(
+                            "expression error in '${{{{ {} }}}}': {}",
+                            expr_str, e.message
+                        ),
+                        TemplateErrorKind::ExpressionError,
+                    )
+                    .to_parse_error(),
+                    &expr_str,
+                )
+            })
+    }
+
     /// Extract a template directive and its value from a YAML sequence item.
     /// Returns `None` if the item is not a directive.
     fn extract_directive<'a>(
@@ -1479,6 +1833,8 @@ impl TemplateEngine {
             Some(TemplateDirective::ElseIf(condition))
         } else if inner == "else" {
             Some(TemplateDirective::Else)
+        } else if inner == "insert" {
+            Some(TemplateDirective::Insert)
         } else if let Some(rest) = inner.strip_prefix("each ") {
             // Parse: each <var> in <collection>
             let rest = rest.trim();
@@ -1595,16 +1951,19 @@ impl TemplateEngine {
             return Ok(full_path);
         }
 
-        Err(TemplateError::new(
-            format!(
-                "template '{}' not found (looked in {})",
-                template_ref,
-                full_path.display()
-            ),
-            TemplateErrorKind::NotFound,
-        )
-        .with_path(template_ref)
-        .to_parse_error())
+        Err(self.locate(
+            TemplateError::new(
+                format!(
+                    "template '{}' not found (looked in {})",
+                    template_ref,
+                    full_path.display()
+                ),
+                TemplateErrorKind::NotFound,
+            )
+            .with_path(template_ref)
+            .to_parse_error(),
+            template_ref,
+        ))
     }
 
     // =========================================================================
@@ -1644,11 +2003,93 @@ impl TemplateEngine {
         Ok(())
     }
 
+    /// Attach the template being expanded, the templates that included it and
+    /// the offending source text to an error raised while expanding it
+    fn locate(&self, error: ParseError, snippet: &str) -> ParseError {
+        self.locate_in(error, snippet, self.include_stack.len())
+    }
+
+    /// Like `locate`, for errors about the reference to the template on top of
+    /// the stack, which belong to the file that included it
+    fn locate_reference(&self, error: ParseError, template_ref: &str) -> ParseError {
+        self.locate_in(
+            error,
+            template_ref,
+            self.include_stack.len().saturating_sub(1),
+        )
+    }
+
+    /// Locate an error in the template `depth` entries into the include stack
+    /// (0 is the pipeline itself, which callers know the path of)
+    fn locate_in(&self, mut error: ParseError, snippet: &str, depth: usize) -> ParseError {
+        let origin = &mut error.origin;
+        if origin.file.is_some() || (depth == 0 && origin.snippet.is_some()) {
+            return error;
+        }
+        if origin.snippet.is_none() && !snippet.is_empty() {
+            origin.snippet = Some(snippet.to_string());
+        }
+        if depth > 0 {
+            let root = self.canonical_path(&self.repo_root);
+            let display = |path: &String| {
+                Path::new(path)
+                    .strip_prefix(&root)
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| path.clone())
+            };
+            origin.file = Some(PathBuf::from(&self.include_stack[depth - 1]));
+            origin.include_chain = self.include_stack[..depth - 1]
+                .iter()
+                .map(display)
+                .collect();
+        }
+        error
+    }
+
     /// Pop the current template from the include stack
     fn pop_template(&mut self) {
         self.include_stack.pop();
     }
 
+    /// Trace the template just pushed onto the include stack
+    fn record_include(
+        &mut self,
+        kind: &'static str,
+        parameters: &HashMap<String, serde_yaml::Value>,
+    ) {
+        let depth = self.include_stack.len();
+        let relative = |canonical: &String| self.relative_path(canonical);
+        let Some(template) = self.include_stack.last().map(relative) else {
+            return;
+        };
+        let parent = depth
+            .checked_sub(2)
+            .map(|i| relative(&self.include_stack[i]));
+        self.trace.push(TemplateInclude {
+            parent,
+            template,
+            kind,
+            parameters: parameters
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            depth,
+        });
+    }
+
+    /// A canonical template path relative to the repository root, where it's
+    /// inside it
+    fn relative_path(&self, canonical: &str) -> String {
+        let root = self
+            .repo_root
+            .canonicalize()
+            .unwrap_or_else(|_| self.repo_root.clone());
+        Path::new(canonical)
+            .strip_prefix(&root)
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| canonical.to_string())
+    }
+
     /// Get a canonical path string for comparison
     fn canonical_path(&self, path: &Path) -> String {
         path.canonicalize()
@@ -1662,6 +2103,22 @@ impl TemplateEngine {
 // Helper Functions
 // =============================================================================
 
+/// Collect every string (keys included) in a YAML value
+fn yaml_strings<'a>(yaml: &'a serde_yaml::Value, found: &mut Vec<&'a str>) {
+    match yaml {
+        serde_yaml::Value::String(s) => found.push(s),
+        serde_yaml::Value::Sequence(seq) => seq.iter().for_each(|item| yaml_strings(item, found)),
+        serde_yaml::Value::Mapping(map) => {
+            for (key, value) in map {
+                yaml_strings(key, found);
+                yaml_strings(value, found);
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_strings(&tagged.value, found),
+        _ => {}
+    }
+}
+
 /// Convert serde_yaml::Value to our Value type
 pub fn yaml_to_value(yaml: &serde_yaml::Value) -> Value {
     match yaml {
@@ -1767,6 +2224,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/build.yml".to_string(),
@@ -1796,6 +2255,81 @@ steps:
         );
     }
 
+    #[test]
+    fn test_expand_template_on_its_own() {
+        let dir = setup_templates(&[
+            (
+                "jobs/build.yml",
+                r#"
+parameters:
+  - name: platforms
+    type: object
+jobs:
+  - ${{ each platform in parameters.platforms }}:
+    - job: Build_${{ platform }}
+      steps:
+        - template: ../steps/compile.yml
+          parameters:
+            target: ${{ platform }}
+"#,
+            ),
+            (
+                "steps/compile.yml",
+                "parameters:\n  - name: target\nsteps:\n  - script: make ${{ parameters.target }}\n",
+            ),
+            ("pipeline.yml", "trigger: none\n"),
+        ]);
+        let mut engine = TemplateEngine::new(dir.path().join("jobs"));
+
+        let params = HashMap::from([(
+            "platforms".to_string(),
+            serde_yaml::from_str("[linux, macos]").unwrap(),
+        )]);
+        let TemplateContent::Jobs(jobs) = engine.expand_template("build.yml", &params).unwrap()
+        else {
+            panic!("expected jobs");
+        };
+        let names: Vec<_> = jobs.iter().filter_map(|j| j.identifier()).collect();
+        assert_eq!(names, ["Build_linux", "Build_macos"]);
+        assert!(matches!(
+            &jobs[1].steps[0].action,
+            StepAction::Script(s) if s.script == "make macos"
+        ));
+
+        assert!(engine
+            .expand_template("build.yml", &HashMap::new())
+            .is_err());
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        assert!(engine
+            .expand_template("pipeline.yml", &HashMap::new())
+            .unwrap_err()
+            .message
+            .contains("no steps, jobs, stages or variables"));
+    }
+
+    #[test]
+    fn test_step_template_keeps_shell() {
+        let dir = setup_templates(&[(
+            "steps/lint.yml",
+            "parameters:\n  - name: target\nsteps:\n  - script: ruff ${{ parameters.target }}\n    shell: pwsh\n",
+        )]);
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+
+        let params = HashMap::from([(
+            "target".to_string(),
+            serde_yaml::Value::String("src".to_string()),
+        )]);
+        let TemplateContent::Steps(steps) =
+            engine.expand_template("steps/lint.yml", &params).unwrap()
+        else {
+            panic!("expected steps");
+        };
+        assert!(matches!(
+            &steps[0].action,
+            StepAction::Script(s) if s.script == "ruff src" && s.shell.as_deref() == Some("pwsh")
+        ));
+    }
+
     #[test]
     fn test_resolve_step_template_default_params() {
         let dir = setup_templates(&[(
@@ -1822,6 +2356,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/build.yml".to_string(),
@@ -1963,11 +2499,14 @@ variables:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Script(ScriptStep {
                     script: "echo hello".to_string(),
                     working_directory: None,
                     fail_on_stderr: false,
+                    shell: None,
                 }),
             }],
             ..Default::default()
@@ -2054,6 +2593,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "a.yml".to_string(),
@@ -2098,6 +2639,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/build.yml".to_string(),
@@ -2131,6 +2674,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "nonexistent.yml".to_string(),
@@ -2145,6 +2690,280 @@ steps:
         assert!(result.unwrap_err().message.contains("not found"));
     }
 
+    #[test]
+    fn test_errors_locate_template_and_include_chain() {
+        let dir = setup_templates(&[
+            (
+                "steps/inner.yml",
+                "steps:\n  - script: echo ${{ nope(parameters.msg) }}\n",
+            ),
+            ("steps/outer.yml", "steps:\n  - template: steps/inner.yml\n"),
+        ]);
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        let pipeline = AzureParser::parse("steps:\n  - template: steps/outer.yml\n").unwrap();
+
+        let err = engine.resolve_pipeline(pipeline).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::ExpressionError);
+        assert!(err
+            .origin
+            .file
+            .as_ref()
+            .unwrap()
+            .ends_with("steps/inner.yml"));
+        assert_eq!(err.origin.include_chain, vec!["steps/outer.yml"]);
+        assert_eq!(err.origin.snippet.as_deref(), Some("nope(parameters.msg)"));
+
+        // A missing template is reported at the reference, in the pipeline itself
+        let pipeline = AzureParser::parse("steps:\n  - template: steps/gone.yml\n").unwrap();
+        let err = engine.resolve_pipeline(pipeline).unwrap_err();
+        assert!(err.origin.file.is_none());
+        assert_eq!(err.origin.snippet.as_deref(), Some("steps/gone.yml"));
+    }
+
+    #[test]
+    fn test_insert_directive_merges_env_and_inputs() {
+        let dir = setup_templates(&[(
+            "steps/deploy.yml",
+            r#"
+parameters:
+  - name: extraEnv
+    type: object
+    default: {}
+  - name: extraInputs
+    type: object
+    default: {}
+
+steps:
+  - script: ./deploy.sh
+    env:
+      TARGET: prod
+      ${{ insert }}: ${{ parameters.extraEnv }}
+  - task: AzureCLI@2
+    inputs:
+      scriptType: bash
+      ${{ insert }}: ${{ parameters.extraInputs }}
+"#,
+        )]);
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        let pipeline = AzureParser::parse(
+            r#"
+steps:
+  - template: steps/deploy.yml
+    parameters:
+      extraEnv:
+        REGION: westeurope
+        TARGET: staging
+      extraInputs:
+        azureSubscription: dev
+"#,
+        )
+        .unwrap();
+
+        let resolved = engine.resolve_pipeline(pipeline).unwrap();
+        let env = &resolved.steps[0].env;
+        assert_eq!(env.get("REGION").map(String::as_str), Some("westeurope"));
+        // Inserted keys win over the ones written before the directive
+        assert_eq!(env.get("TARGET").map(String::as_str), Some("staging"));
+        let StepAction::Task(task) = &resolved.steps[1].action else {
+            panic!("expected a task step");
+        };
+        assert_eq!(task.inputs.len(), 2);
+        assert_eq!(task.inputs["azureSubscription"], "dev");
+
+        // The defaults insert nothing
+        let pipeline = AzureParser::parse("steps:\n  - template: steps/deploy.yml\n").unwrap();
+        let resolved = engine.resolve_pipeline(pipeline).unwrap();
+        assert_eq!(resolved.steps[0].env.len(), 1);
+    }
+
+    #[test]
+    fn test_default_expressions_reference_other_parameters() {
+        let dir = setup_templates(&[
+            (
+                "steps/deploy.yml",
+                r#"
+parameters:
+  - name: slot
+    default: ${{ parameters.stagingName }}
+  - name: stagingName
+    default: ${{ parameters.serviceName }}-staging
+  - name: serviceName
+    type: string
+  - name: replicas
+    type: number
+    default: ${{ parameters.minReplicas }}
+  - name: minReplicas
+    type: number
+    default: 2
+
+steps:
+  - script: deploy ${{ parameters.slot }} ${{ parameters.stagingName }} x${{ parameters.replicas }}
+"#,
+            ),
+            (
+                "steps/cycle.yml",
+                r#"
+parameters:
+  - name: first
+    default: ${{ parameters.second }}
+  - name: second
+    default: ${{ parameters['third'] }}
+  - name: third
+    default: x-${{ parameters.first }}
+
+steps:
+  - script: echo ${{ parameters.first }}
+"#,
+            ),
+        ]);
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+
+        let pipeline = AzureParser::parse(
+            "steps:\n  - template: steps/deploy.yml\n    parameters:\n      serviceName: api\n  - template: steps/deploy.yml\n    parameters:\n      serviceName: web\n      stagingName: web-qa\n      minReplicas: 3\n",
+        )
+        .unwrap();
+        let resolved = engine.resolve_pipeline(pipeline).unwrap();
+        let scripts: Vec<&str> = resolved
+            .steps
+            .iter()
+            .map(|step| match &step.action {
+                StepAction::Script(script) => script.script.as_str(),
+                _ => panic!("expected script step"),
+            })
+            .collect();
+        assert_eq!(
+            scripts,
+            [
+                "deploy api-staging api-staging x2",
+                "deploy web-qa web-qa x3"
+            ]
+        );
+
+        let pipeline = AzureParser::parse("steps:\n  - template: steps/cycle.yml\n").unwrap();
+        let err = engine.resolve_pipeline(pipeline).unwrap_err();
+        assert!(
+            err.message
+                .contains("parameter defaults form a cycle: first -> second -> third -> first"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_insert_directive_rejects_non_mapping() {
+        let dir = setup_templates(&[(
+            "steps/bad.yml",
+            "steps:\n  - script: echo\n    env:\n      ${{ insert }}: ${{ parameters.names }}\n",
+        )]);
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        let pipeline = AzureParser::parse(
+            "steps:\n  - template: steps/bad.yml\n    parameters:\n      names: [a, b]\n",
+        )
+        .unwrap();
+
+        let err = engine.resolve_pipeline(pipeline).unwrap_err();
+        assert!(err.message.contains("needs a mapping"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_job_list_parameter_inserted_into_jobs() {
+        let dir = setup_templates(&[
+            (
+                "stages/build.yml",
+                r#"
+parameters:
+  - name: jobs
+    type: jobList
+
+stages:
+  - stage: Build
+    jobs: ${{ parameters.jobs }}
+"#,
+            ),
+            (
+                "jobs/wrap.yml",
+                r#"
+parameters:
+  - name: jobs
+    type: jobList
+
+jobs:
+  - job: Setup
+    steps:
+      - script: echo setup
+  - ${{ parameters.jobs }}
+"#,
+            ),
+        ]);
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+
+        let pipeline = AzureParser::parse(
+            r#"
+stages:
+  - template: stages/build.yml
+    parameters:
+      jobs:
+        - job: Compile
+          steps:
+            - script: cargo build
+        - job: Lint
+          steps:
+            - script: cargo clippy
+"#,
+        )
+        .unwrap();
+        let resolved = engine.resolve_pipeline(pipeline).unwrap();
+        let jobs: Vec<_> = resolved.stages[0]
+            .jobs
+            .iter()
+            .map(|j| j.job.as_deref().unwrap())
+            .collect();
+        assert_eq!(jobs, vec!["Compile", "Lint"]);
+
+        let pipeline = AzureParser::parse(
+            "jobs:\n  - template: jobs/wrap.yml\n    parameters:\n      jobs:\n        - job: Test\n          steps:\n            - script: cargo test\n",
+        )
+        .unwrap();
+        let resolved = engine.resolve_pipeline(pipeline).unwrap();
+        let jobs: Vec<_> = resolved
+            .jobs
+            .iter()
+            .map(|j| j.job.as_deref().unwrap())
+            .collect();
+        assert_eq!(jobs, vec!["Setup", "Test"]);
+    }
+
+    #[test]
+    fn test_job_list_parameter_validated_at_call_site() {
+        let dir = setup_templates(&[(
+            "stages/build.yml",
+            "parameters:\n  - name: jobs\n    type: jobList\nstages:\n  - stage: Build\n    jobs: ${{ parameters.jobs }}\n",
+        )]);
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        let pipeline = AzureParser::parse(
+            r#"
+stages:
+  - template: stages/build.yml
+    parameters:
+      jobs:
+        - job: Compile
+          steps:
+            - script: cargo build
+        - job: Lint
+          timeoutInMinutes: soon
+"#,
+        )
+        .unwrap();
+
+        let err = engine.resolve_pipeline(pipeline).unwrap_err();
+        assert!(
+            err.message
+                .contains("parameter 'jobs' is not a valid jobList: item 2: invalid type"),
+            "{}",
+            err.message
+        );
+    }
+
     #[test]
     fn test_nested_templates() {
         let dir = setup_templates(&[
@@ -2187,6 +3006,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/outer.yml".to_string(),
@@ -2250,6 +3071,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/build.yml".to_string(),
@@ -2341,6 +3164,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/build.yml".to_string(),
@@ -2391,6 +3216,8 @@ steps:
                     enabled: true,
                     timeout_in_minutes: None,
                     retry_count_on_task_failure: None,
+                    dry_run: false,
+                    tags: Vec::new(),
                     env: HashMap::new(),
                     action: StepAction::Template(TemplateStep {
                         template: "steps/build.yml".to_string(),
@@ -2405,6 +3232,8 @@ steps:
                     enabled: true,
                     timeout_in_minutes: None,
                     retry_count_on_task_failure: None,
+                    dry_run: false,
+                    tags: Vec::new(),
                     env: HashMap::new(),
                     action: StepAction::Template(TemplateStep {
                         template: "steps/test.yml".to_string(),
@@ -2505,6 +3334,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/conditional.yml".to_string(),
@@ -2558,6 +3389,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/conditional.yml".to_string(),
@@ -2609,6 +3442,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/env-steps.yml".to_string(),
@@ -2663,6 +3498,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/multi.yml".to_string(),
@@ -2720,6 +3557,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/deploy.yml".to_string(),
@@ -2780,6 +3619,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/multi-deploy.yml".to_string(),
@@ -2844,6 +3685,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/deploy.yml".to_string(),
@@ -2902,6 +3745,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/combined.yml".to_string(),
@@ -2964,6 +3809,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/combined.yml".to_string(),
@@ -3139,6 +3986,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/deploy.yml".to_string(),
@@ -3195,6 +4044,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/deploy.yml".to_string(),
@@ -3251,6 +4102,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/deploy.yml".to_string(),
@@ -3310,6 +4163,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/config.yml".to_string(),
@@ -3366,6 +4221,8 @@ steps:
                 enabled: true,
                 timeout_in_minutes: None,
                 retry_count_on_task_failure: None,
+                dry_run: false,
+                tags: Vec::new(),
                 env: HashMap::new(),
                 action: StepAction::Template(TemplateStep {
                     template: "steps/broken.yml".to_string(),
@@ -0,0 +1,297 @@
+// Template Documentation
+// Extracts template parameters and comments and renders them as markdown
+
+use crate::parser::error::{ParseError, ParseErrorKind, ParseResult};
+use crate::parser::models::Parameter;
+use crate::parser::normalize::apply_merge_keys;
+use crate::parser::template::TemplateEngine;
+
+/// Documentation for one template file
+#[derive(Debug, Clone)]
+pub struct TemplateDoc {
+    /// Path used in `- template:` references and as the page title
+    pub path: String,
+    /// What the template expands to: steps, jobs, stages, variables or pipeline
+    pub kind: &'static str,
+    /// Comment block at the top of the file
+    pub description: Option<String>,
+    pub parameters: Vec<ParameterDoc>,
+}
+
+/// A documented template parameter
+#[derive(Debug, Clone)]
+pub struct ParameterDoc {
+    pub parameter: Parameter,
+    /// Comment lines directly above the parameter's declaration
+    pub description: Option<String>,
+}
+
+impl TemplateDoc {
+    /// Document a template from its YAML source
+    ///
+    /// Comments aren't part of the YAML data, so descriptions are read from
+    /// the source lines: the comment block at the top of the file, and the
+    /// comments directly above each parameter.
+    pub fn parse(path: impl Into<String>, content: &str) -> ParseResult<Self> {
+        let mut yaml: serde_yaml::Value =
+            serde_yaml::from_str(content).map_err(|e| ParseError::from_yaml_error(&e, content))?;
+        apply_merge_keys(&mut yaml)?;
+        let mapping = yaml.as_mapping().ok_or_else(|| {
+            ParseError::new("template must be a YAML mapping", 0, 0)
+                .with_kind(ParseErrorKind::TemplateError)
+        })?;
+
+        let kind = ["steps", "jobs", "stages", "variables"]
+            .into_iter()
+            .find(|key| mapping.contains_key(*key))
+            .unwrap_or("pipeline");
+        let parameters = match mapping.get("parameters") {
+            Some(params) => TemplateEngine::parse_template_parameters(params)?,
+            None => Vec::new(),
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let parameters = parameters
+            .into_iter()
+            .map(|parameter| ParameterDoc {
+                description: parameter_comment(&lines, &parameter.name),
+                parameter,
+            })
+            .collect();
+
+        Ok(Self {
+            path: path.into(),
+            kind,
+            description: leading_comment(&lines),
+            parameters,
+        })
+    }
+
+    /// Render the template as a markdown page
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# {}\n\n", self.path);
+        if let Some(description) = &self.description {
+            md.push_str(description);
+            md.push_str("\n\n");
+        }
+        md.push_str(&format!("**Kind:** {} template\n\n", self.kind));
+
+        if self.parameters.is_empty() {
+            md.push_str("This template has no parameters.\n\n");
+        } else {
+            md.push_str("## Parameters\n\n");
+            md.push_str("| Name | Type | Default | Allowed values | Description |\n");
+            md.push_str("|------|------|---------|----------------|-------------|\n");
+            for doc in &self.parameters {
+                let p = &doc.parameter;
+                let name = match &p.display_name {
+                    Some(display) => format!("`{}` ({})", p.name, display),
+                    None => format!("`{}`", p.name),
+                };
+                let default = match &p.default {
+                    Some(value) => format!("`{}`", yaml_inline(value)),
+                    None => "*required*".to_string(),
+                };
+                let values = p
+                    .values
+                    .iter()
+                    .flatten()
+                    .map(|v| format!("`{}`", yaml_inline(v)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let description = doc
+                    .description
+                    .as_deref()
+                    .unwrap_or_default()
+                    .replace('\n', " ");
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    table_cell(&name),
+                    p.param_type.name(),
+                    table_cell(&default),
+                    table_cell(&values),
+                    table_cell(&description)
+                ));
+            }
+            md.push('\n');
+        }
+
+        md.push_str("## Usage\n\n```yaml\n");
+        md.push_str(&self.usage());
+        md.push_str("```\n");
+        md
+    }
+
+    /// Example reference passing every required parameter
+    fn usage(&self) -> String {
+        let mut usage = match self.kind {
+            "pipeline" => format!("extends:\n  template: {}\n", self.path),
+            "variables" => format!("variables:\n- template: {}\n", self.path),
+            kind => format!("{}:\n- template: {}\n", kind, self.path),
+        };
+        let required: Vec<_> = self
+            .parameters
+            .iter()
+            .filter(|doc| doc.parameter.default.is_none())
+            .collect();
+        if !required.is_empty() {
+            usage.push_str("  parameters:\n");
+            for doc in required {
+                usage.push_str(&format!(
+                    "    {}: # {}\n",
+                    doc.parameter.name,
+                    doc.parameter.param_type.name()
+                ));
+            }
+        }
+        usage
+    }
+}
+
+/// The comment block at the top of a file, before any YAML
+fn leading_comment(lines: &[&str]) -> Option<String> {
+    let comment: Vec<&str> = lines
+        .iter()
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| line.starts_with('#'))
+        .map(comment_text)
+        .collect();
+    join_comment(&comment)
+}
+
+/// The comment lines directly above a parameter's declaration, which is
+/// either `- name: <name>` or, in the mapping form, `<name>: <default>`
+fn parameter_comment(lines: &[&str], name: &str) -> Option<String> {
+    let start = lines
+        .iter()
+        .position(|line| line.trim_end() == "parameters:")?;
+    let declaration = lines[start + 1..].iter().position(|line| {
+        let line = line.trim();
+        let declared = line.strip_prefix("- name:").or_else(|| {
+            line.strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(|_| name)
+        });
+        declared.is_some_and(|declared| declared.trim().trim_matches(['\'', '"']) == name)
+    })? + start
+        + 1;
+
+    let mut comment: Vec<&str> = lines[start + 1..declaration]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with('#'))
+        .map(comment_text)
+        .collect();
+    comment.reverse();
+    join_comment(&comment)
+}
+
+fn comment_text(line: &str) -> &str {
+    let text = line.trim_start_matches('#');
+    text.strip_prefix(' ').unwrap_or(text).trim_end()
+}
+
+fn join_comment(lines: &[&str]) -> Option<String> {
+    let text = lines.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// A YAML value on one line: scalars as written, collections as JSON
+fn yaml_inline(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(s) if s.is_empty() => "''".to_string(),
+        serde_yaml::Value::String(s) => s.clone(),
+        serde_yaml::Value::Null => "null".to_string(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"
+# Builds a .NET project.
+# Restores, builds and runs the tests.
+
+parameters:
+  # Build configuration
+  - name: configuration
+    type: string
+    default: Release
+    values: [Debug, Release]
+  # Projects to build,
+  # as a glob
+  - name: projects
+    type: string
+  - name: extraSteps
+    type: stepList
+    default: []
+
+steps:
+  - script: dotnet build ${{ parameters.projects }}
+"#;
+
+    #[test]
+    fn test_extract_parameters_and_comments() {
+        let doc = TemplateDoc::parse("templates/build.yml", TEMPLATE).unwrap();
+        assert_eq!(doc.kind, "steps");
+        assert_eq!(
+            doc.description.as_deref(),
+            Some("Builds a .NET project.\nRestores, builds and runs the tests.")
+        );
+        assert_eq!(doc.parameters.len(), 3);
+        assert_eq!(
+            doc.parameters[0].description.as_deref(),
+            Some("Build configuration")
+        );
+        assert_eq!(
+            doc.parameters[1].description.as_deref(),
+            Some("Projects to build,\nas a glob")
+        );
+        assert_eq!(doc.parameters[2].description, None);
+    }
+
+    #[test]
+    fn test_markdown() {
+        let md = TemplateDoc::parse("templates/build.yml", TEMPLATE)
+            .unwrap()
+            .to_markdown();
+        assert!(md.starts_with("# templates/build.yml\n\nBuilds a .NET project."));
+        assert!(md.contains(
+            "| `configuration` | string | `Release` | `Debug`, `Release` | Build configuration |"
+        ));
+        assert!(
+            md.contains("| `projects` | string | *required* |  | Projects to build, as a glob |")
+        );
+        assert!(md.contains("| `extraSteps` | stepList | `[]` |  |  |"));
+        assert!(md.contains(
+            "steps:\n- template: templates/build.yml\n  parameters:\n    projects: # string\n"
+        ));
+    }
+
+    #[test]
+    fn test_mapping_parameters() {
+        let doc = TemplateDoc::parse(
+            "vars.yml",
+            "parameters:\n  # Target region\n  region: eastus\nvariables:\n  location: ${{ parameters.region }}\n",
+        )
+        .unwrap();
+        assert_eq!(doc.kind, "variables");
+        assert_eq!(
+            doc.parameters[0].description.as_deref(),
+            Some("Target region")
+        );
+        assert!(doc
+            .to_markdown()
+            .contains("| `region` | string | `eastus` |"));
+    }
+}
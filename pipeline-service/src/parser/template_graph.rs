@@ -0,0 +1,256 @@
+// Template Include Graph
+// Which files include which templates and with what parameters, as DOT, Mermaid or JSON
+
+use crate::parser::template::TemplateInclude;
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::Serialize;
+
+/// The templates a pipeline includes, built from a [`TemplateEngine`] trace
+///
+/// [`TemplateEngine`]: crate::parser::TemplateEngine
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateGraph {
+    /// The pipeline file, the root of the graph
+    pub pipeline: String,
+    pub includes: Vec<TemplateInclude>,
+    /// Template files in the library that nothing includes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unused: Vec<String>,
+}
+
+impl TemplateGraph {
+    pub fn new(pipeline: impl Into<String>, trace: &[TemplateInclude]) -> Self {
+        Self {
+            pipeline: pipeline.into(),
+            includes: trace.to_vec(),
+            unused: Vec::new(),
+        }
+    }
+
+    /// Find the YAML files under `library` (relative to `repo_root`) that
+    /// the pipeline never includes
+    pub fn with_unused(mut self, repo_root: &Path, library: &Path) -> Self {
+        let included = self.templates();
+        let mut unused = Vec::new();
+        let mut pending = vec![repo_root.join(library)];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                if !matches!(
+                    path.extension().and_then(|e| e.to_str()),
+                    Some("yml" | "yaml")
+                ) {
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(repo_root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                if !included.contains(relative.as_str()) && relative != self.pipeline {
+                    unused.push(relative);
+                }
+            }
+        }
+        unused.sort();
+        self.unused = unused;
+        self
+    }
+
+    /// Every template included at least once
+    pub fn templates(&self) -> BTreeSet<&str> {
+        self.includes
+            .iter()
+            .map(|include| include.template.as_str())
+            .collect()
+    }
+
+    /// The longest chain of includes, from the pipeline's own reference down
+    pub fn deepest_chain(&self) -> Vec<&str> {
+        let Some(deepest) = self
+            .includes
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, include)| (include.depth, std::cmp::Reverse(*i)))
+        else {
+            return Vec::new();
+        };
+        // The trace lists parents before their templates, so each link is the
+        // nearest earlier include one level up
+        let (mut index, include) = deepest;
+        let mut chain = vec![include.template.as_str()];
+        let mut depth = include.depth;
+        while depth > 1 {
+            let Some(parent) = self.includes[..index]
+                .iter()
+                .rposition(|candidate| candidate.depth == depth - 1)
+            else {
+                break;
+            };
+            index = parent;
+            depth -= 1;
+            chain.push(self.includes[index].template.as_str());
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Graphviz `dot` source, parameters as edge labels
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph templates {\n    rankdir=LR;\n    node [shape=box];\n");
+        dot.push_str(&format!(
+            "    \"{}\" [style=bold];\n",
+            dot_escape(&self.pipeline)
+        ));
+        for include in &self.includes {
+            let parent = include.parent.as_deref().unwrap_or(&self.pipeline);
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                dot_escape(parent),
+                dot_escape(&include.template),
+                dot_escape(&edge_label(include))
+            ));
+        }
+        for template in &self.unused {
+            dot.push_str(&format!(
+                "    \"{}\" [style=dashed, color=gray];\n",
+                dot_escape(template)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// A Mermaid flowchart, parameters as edge labels
+    pub fn to_mermaid(&self) -> String {
+        let mut nodes = vec![self.pipeline.clone()];
+        let mut id = |file: &str| match nodes.iter().position(|node| node == file) {
+            Some(i) => format!("n{}", i),
+            None => {
+                nodes.push(file.to_string());
+                format!("n{}", nodes.len() - 1)
+            }
+        };
+        let mut edges = Vec::new();
+        for include in &self.includes {
+            let parent = id(include.parent.as_deref().unwrap_or(&self.pipeline));
+            let template = id(&include.template);
+            edges.push(format!(
+                "    {} -->|\"{}\"| {}",
+                parent,
+                mermaid_escape(&edge_label(include)),
+                template
+            ));
+        }
+        let unused: Vec<String> = self.unused.iter().map(|file| id(file)).collect();
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for (i, node) in nodes.iter().enumerate() {
+            mermaid.push_str(&format!("    n{}[\"{}\"]\n", i, mermaid_escape(node)));
+        }
+        for edge in edges {
+            mermaid.push_str(&edge);
+            mermaid.push('\n');
+        }
+        if !unused.is_empty() {
+            mermaid.push_str("    classDef unused stroke-dasharray: 5 5,color:gray\n");
+            mermaid.push_str(&format!("    class {} unused\n", unused.join(",")));
+        }
+        mermaid
+    }
+}
+
+/// `kind` followed by the parameters, `steps: env=prod, count=2`
+fn edge_label(include: &TemplateInclude) -> String {
+    if include.parameters.is_empty() {
+        return include.kind.to_string();
+    }
+    let parameters: Vec<String> = include
+        .parameters
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_yaml::Value::String(s) => s.clone(),
+                other => serde_json::to_string(other).unwrap_or_default(),
+            };
+            format!("{}={}", name, value)
+        })
+        .collect();
+    format!("{}: {}", include.kind, parameters.join(", "))
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{AzureParser, TemplateEngine};
+
+    #[test]
+    fn test_template_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("templates")).unwrap();
+        std::fs::write(
+            root.join("templates/build.yml"),
+            "parameters:\n  - name: config\n    default: Debug\nsteps:\n  - template: templates/echo.yml\n  - script: make ${{ parameters.config }}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("templates/echo.yml"),
+            "steps:\n  - script: echo hi\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("templates/old.yml"), "steps: []\n").unwrap();
+
+        let pipeline = AzureParser::parse(
+            "steps:\n  - template: templates/build.yml\n    parameters:\n      config: Release\n",
+        )
+        .unwrap();
+        let mut engine = TemplateEngine::new(root.to_path_buf());
+        engine.resolve_pipeline(pipeline).unwrap();
+
+        let graph = TemplateGraph::new("azure-pipelines.yml", engine.trace())
+            .with_unused(root, Path::new("templates"));
+        assert_eq!(graph.includes.len(), 2);
+        assert_eq!(graph.includes[0].parent, None);
+        assert_eq!(graph.includes[0].template, "templates/build.yml");
+        assert_eq!(
+            graph.includes[1].parent.as_deref(),
+            Some("templates/build.yml")
+        );
+        assert_eq!(graph.includes[1].depth, 2);
+        assert_eq!(graph.unused, ["templates/old.yml"]);
+        assert_eq!(
+            graph.deepest_chain(),
+            ["templates/build.yml", "templates/echo.yml"]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.contains(
+            "\"azure-pipelines.yml\" -> \"templates/build.yml\" [label=\"steps: config=Release\"];"
+        ));
+        assert!(
+            dot.contains("\"templates/build.yml\" -> \"templates/echo.yml\" [label=\"steps\"];")
+        );
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.contains("n0 -->|\"steps: config=Release\"| n1"));
+        assert!(mermaid.contains("class n3 unused"));
+    }
+}
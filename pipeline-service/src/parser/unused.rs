@@ -0,0 +1,500 @@
+// Unused Declaration Lint
+// Finds parameters and variables nothing references, and variables hiding ones declared at a wider scope
+
+use crate::parser::models::{Pipeline, Variable};
+use crate::parser::template::TemplateEngine;
+
+use std::ops::Range;
+
+use serde::Deserialize;
+
+/// A pipeline or template file the lint reads declarations and compile-time
+/// expressions from
+#[derive(Debug, Clone)]
+pub struct LintSource {
+    /// Repository-relative path, as reported in warnings
+    pub path: String,
+    pub content: String,
+}
+
+/// Something declared that's never used, or that hides another declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// `unused-parameter`, `unused-variable` or `shadowed-variable`
+    pub rule: &'static str,
+    pub message: String,
+    /// Where the declaration sits in the resolved pipeline, e.g.
+    /// `stages.Build.variables.configuration`
+    pub path: String,
+    /// File and line of the declaration, when it can be found in a source
+    pub file: Option<String>,
+    pub line: Option<usize>,
+}
+
+/// A variable declared at one scope of the resolved pipeline
+struct Declared<'a> {
+    name: &'a str,
+    path: String,
+    /// Index of the pipeline, stage or job text where it can be used
+    scope: usize,
+}
+
+/// Flag parameters and variables that are declared but never referenced,
+/// and variables that shadow one of the same name from a wider scope
+///
+/// `pipeline` is the normalized pipeline with its templates resolved;
+/// `sources` are the pipeline file followed by the templates it includes.
+/// Parameters are checked per file against the `${{ }}` expressions in that
+/// file. Variables are checked against the scope they're declared at, where
+/// `$(name)` macros, `variables.name` in runtime expressions and conditions,
+/// and the `$NAME`, `$env:NAME` or `%NAME%` environment variable in scripts
+/// all count as uses, plus `${{ variables.name }}` in any source. Names with
+/// a dot (`system.debug`) configure the agent or tasks and aren't checked.
+pub fn check_unused(pipeline: &Pipeline, sources: &[LintSource]) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    for source in sources {
+        check_parameters(source, &mut warnings);
+    }
+
+    let compile_time: String = sources
+        .iter()
+        .flat_map(|source| template_expressions(&source.content))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+
+    let mut scopes = vec![scope_text(pipeline)];
+    let mut declared = Vec::new();
+    declare(&pipeline.variables, "", 0, &mut declared);
+    let pipeline_vars = 0..declared.len();
+    // Each declaration that hides another, with the path of the one it hides
+    let mut shadowed = Vec::new();
+    for stage in &pipeline.stages {
+        let stage_path = format!("stages.{}.", stage.stage.as_deref().unwrap_or("unknown"));
+        scopes.push(scope_text(stage));
+        let first = declared.len();
+        declare(
+            &stage.variables,
+            &stage_path,
+            scopes.len() - 1,
+            &mut declared,
+        );
+        let stage_vars = first..declared.len();
+        for i in stage_vars.clone() {
+            shadowed.extend(hidden(&declared, i, std::slice::from_ref(&pipeline_vars)));
+        }
+
+        for job in &stage.jobs {
+            let job_path = format!(
+                "{}jobs.{}.",
+                stage_path,
+                job.identifier().unwrap_or("unknown")
+            );
+            scopes.push(scope_text(job));
+            let first = declared.len();
+            declare(&job.variables, &job_path, scopes.len() - 1, &mut declared);
+            for i in first..declared.len() {
+                shadowed.extend(hidden(
+                    &declared,
+                    i,
+                    &[pipeline_vars.clone(), stage_vars.clone()],
+                ));
+            }
+        }
+    }
+
+    for (i, hidden_path) in shadowed {
+        let variable = &declared[i];
+        let (file, line) = locate_variable(sources, &declared, i);
+        warnings.push(LintWarning {
+            rule: "shadowed-variable",
+            message: format!(
+                "variable '{}' shadows the one declared at {}",
+                variable.name, hidden_path
+            ),
+            path: variable.path.clone(),
+            file,
+            line,
+        });
+    }
+    for (i, variable) in declared.iter().enumerate() {
+        if is_used(variable.name, &scopes[variable.scope], &compile_time) {
+            continue;
+        }
+        let (file, line) = locate_variable(sources, &declared, i);
+        warnings.push(LintWarning {
+            rule: "unused-variable",
+            message: format!("variable '{}' is declared but never used", variable.name),
+            path: variable.path.clone(),
+            file,
+            line,
+        });
+    }
+    warnings
+}
+
+fn check_parameters(source: &LintSource, warnings: &mut Vec<LintWarning>) {
+    let mut parameters = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(&source.content) {
+        let Ok(yaml) = serde_yaml::Value::deserialize(document) else {
+            return;
+        };
+        if let Some(declared) = yaml.get("parameters") {
+            parameters
+                .extend(TemplateEngine::parse_template_parameters(declared).unwrap_or_default());
+        }
+    }
+    if parameters.is_empty() {
+        return;
+    }
+
+    let mut used = Vec::new();
+    for expression in template_expressions(&source.content) {
+        match parameter_references(&expression) {
+            Some(names) => used.extend(names),
+            // `${{ each p in parameters }}` or `convertToJson(parameters)`
+            None => return,
+        }
+    }
+    for parameter in parameters {
+        if used.iter().any(|u| u.eq_ignore_ascii_case(&parameter.name)) {
+            continue;
+        }
+        warnings.push(LintWarning {
+            rule: "unused-parameter",
+            message: format!("parameter '{}' is declared but never used", parameter.name),
+            path: format!("parameters.{}", parameter.name),
+            file: Some(source.path.clone()),
+            line: declaration_lines(&source.content, "parameters", &parameter.name)
+                .first()
+                .copied(),
+        });
+    }
+}
+
+/// The text between each `${{` and `}}`
+fn template_expressions(content: &str) -> Vec<String> {
+    let mut expressions = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("${{") {
+        rest = &rest[start + 3..];
+        let end = rest.find("}}").unwrap_or(rest.len());
+        expressions.push(rest[..end].to_string());
+        rest = &rest[end..];
+    }
+    expressions
+}
+
+/// Parameters an expression names with `parameters.name` or
+/// `parameters['name']`; `None` when it uses `parameters` as a whole
+fn parameter_references(expression: &str) -> Option<Vec<String>> {
+    let lower = expression.to_ascii_lowercase();
+    let mut names = Vec::new();
+    for (at, _) in lower.match_indices("parameters") {
+        let before = lower[..at].chars().next_back();
+        if before.is_some_and(|c| is_name_char(c) || c == '.') {
+            continue;
+        }
+        let after = &expression[at + "parameters".len()..];
+        if after.starts_with(is_name_char) {
+            continue;
+        }
+        let name = match after.trim_start().strip_prefix('.') {
+            Some(property) => property.chars().take_while(|c| is_name_char(*c)).collect(),
+            None => match after.trim_start().strip_prefix('[') {
+                Some(index) => index
+                    .trim_start()
+                    .trim_start_matches(['\'', '"'])
+                    .chars()
+                    .take_while(|c| is_name_char(*c))
+                    .collect(),
+                None => String::new(),
+            },
+        };
+        if name.is_empty() {
+            return None;
+        }
+        names.push(name);
+    }
+    Some(names)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn declare<'a>(
+    variables: &'a [Variable],
+    path: &str,
+    scope: usize,
+    declared: &mut Vec<Declared<'a>>,
+) {
+    for variable in variables {
+        if let Variable::KeyValue { name, .. } = variable {
+            if name.contains('.') {
+                continue;
+            }
+            declared.push(Declared {
+                name,
+                path: format!("{}variables.{}", path, name),
+                scope,
+            });
+        }
+    }
+}
+
+/// The path of the declaration `declared[i]` hides: the last one of the same
+/// name in the nearest of the `outer` scopes
+fn hidden(declared: &[Declared], i: usize, outer: &[Range<usize>]) -> Option<(usize, String)> {
+    let name = declared[i].name;
+    outer.iter().rev().find_map(|scope| {
+        declared[scope.clone()]
+            .iter()
+            .rev()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+            .map(|d| (i, d.path.clone()))
+    })
+}
+
+/// Every string in a pipeline, stage or job, lowercased
+fn scope_text(scope: &impl serde::Serialize) -> String {
+    fn collect(value: &serde_yaml::Value, text: &mut String) {
+        match value {
+            serde_yaml::Value::String(s) => {
+                text.push_str(s);
+                text.push('\n');
+            }
+            serde_yaml::Value::Sequence(items) => items.iter().for_each(|v| collect(v, text)),
+            serde_yaml::Value::Mapping(mapping) => {
+                for (key, value) in mapping {
+                    collect(key, text);
+                    collect(value, text);
+                }
+            }
+            serde_yaml::Value::Tagged(tagged) => collect(&tagged.value, text),
+            _ => {}
+        }
+    }
+    let mut text = String::new();
+    if let Ok(value) = serde_yaml::to_value(scope) {
+        collect(&value, &mut text);
+    }
+    text.to_lowercase()
+}
+
+/// Whether lowercased `text` uses the variable, or a `${{ }}` expression in
+/// `compile_time` does
+fn is_used(name: &str, text: &str, compile_time: &str) -> bool {
+    let name = name.to_lowercase();
+    let env = name.replace(['.', ' '], "_");
+    let in_expression = |text: &str| {
+        text.contains(&format!("variables['{}']", name))
+            || text.contains(&format!("variables[\"{}\"]", name))
+            || contains_name(text, &format!("variables.{}", name))
+    };
+    text.contains(&format!("$({})", name))
+        || in_expression(text)
+        || in_expression(compile_time)
+        || contains_name(text, &format!("${}", env))
+        || contains_name(text, &format!("${{{}}}", env))
+        || contains_name(text, &format!("$env:{}", env))
+        || text.contains(&format!("%{}%", env))
+}
+
+/// Whether `text` contains `needle` not followed by more of a name
+fn contains_name(text: &str, needle: &str) -> bool {
+    text.match_indices(needle).any(|(at, _)| {
+        !text[at + needle.len()..]
+            .chars()
+            .next()
+            .is_some_and(is_name_char)
+    })
+}
+
+/// Where `declared[i]` is in the sources
+///
+/// The resolved pipeline doesn't record where a variable came from, so the
+/// n-th declaration of a name in it is taken to be the n-th one in the
+/// sources, or the last one found.
+fn locate_variable(
+    sources: &[LintSource],
+    declared: &[Declared],
+    i: usize,
+) -> (Option<String>, Option<usize>) {
+    let name = declared[i].name;
+    let nth = declared[..i]
+        .iter()
+        .filter(|d| d.name.eq_ignore_ascii_case(name))
+        .count();
+    let found: Vec<(&str, usize)> = sources
+        .iter()
+        .flat_map(|source| {
+            declaration_lines(&source.content, "variables", name)
+                .into_iter()
+                .map(|line| (source.path.as_str(), line))
+        })
+        .collect();
+    match found.get(nth).or(found.last()) {
+        Some((file, line)) => (Some(file.to_string()), Some(*line)),
+        None => (None, None),
+    }
+}
+
+/// Lines (1-indexed) declaring `name` in a `section:` block, as either
+/// `- name: <name>` or, in the mapping form, `<name>: <value>`
+fn declaration_lines(content: &str, section: &str, name: &str) -> Vec<usize> {
+    let header = format!("{}:", section);
+    let mut lines = Vec::new();
+    let mut section_indent = None;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if let Some(outer) = section_indent {
+            if indent > outer || (indent == outer && trimmed.starts_with('-')) {
+                if declares(trimmed, name) {
+                    lines.push(i + 1);
+                }
+                continue;
+            }
+            section_indent = None;
+        }
+        if trimmed == header {
+            section_indent = Some(indent);
+        }
+    }
+    lines
+}
+
+fn declares(line: &str, name: &str) -> bool {
+    let line = line.strip_prefix('-').unwrap_or(line).trim_start();
+    let declared = match line.strip_prefix("name:") {
+        Some(declared) => declared,
+        None => match line.split_once(':') {
+            Some((key, _)) => key,
+            None => return false,
+        },
+    };
+    declared
+        .trim()
+        .trim_matches(['\'', '"'])
+        .eq_ignore_ascii_case(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{normalize_pipeline, AzureParser};
+
+    fn lint(yaml: &str, templates: &[(&str, &str)]) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sources = vec![LintSource {
+            path: "azure-pipelines.yml".to_string(),
+            content: yaml.to_string(),
+        }];
+        for (path, content) in templates {
+            std::fs::write(dir.path().join(path), content).unwrap();
+            sources.push(LintSource {
+                path: path.to_string(),
+                content: content.to_string(),
+            });
+        }
+        let pipeline = TemplateEngine::new(dir.path().to_path_buf())
+            .resolve_pipeline(AzureParser::parse(yaml).unwrap())
+            .unwrap();
+        check_unused(&normalize_pipeline(pipeline), &sources)
+            .into_iter()
+            .map(|w| {
+                format!(
+                    "{}:{} [{}] {}",
+                    w.file.unwrap_or_default(),
+                    w.line.unwrap_or_default(),
+                    w.rule,
+                    w.message
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_check_unused() {
+        let warnings = lint(
+            r#"parameters:
+  - name: environment
+    default: dev
+  - name: verbose
+    type: boolean
+    default: false
+variables:
+  configuration: Release
+  unusedGlobal: x
+  system.debug: true
+stages:
+  - stage: Build
+    variables:
+      - name: configuration
+        value: Debug
+    jobs:
+      - job: Compile
+        variables:
+          outputDir: out
+          toolPath: /opt/tool
+          buildId: "1"
+        steps:
+          - template: steps.yml
+            parameters:
+              target: ${{ parameters.environment }}
+          - bash: make -C $(outputDir) CONFIG=$(configuration)
+          - pwsh: Write-Host $env:TOOLPATH
+            condition: eq(variables['buildId'], '1')
+"#,
+            &[(
+                "steps.yml",
+                r#"parameters:
+  - name: target
+  - name: retries
+    default: 3
+steps:
+  - script: deploy ${{ parameters.target }}
+"#,
+            )],
+        );
+        assert_eq!(
+            warnings,
+            [
+                "azure-pipelines.yml:4 [unused-parameter] parameter 'verbose' is declared but never used",
+                "steps.yml:3 [unused-parameter] parameter 'retries' is declared but never used",
+                "azure-pipelines.yml:14 [shadowed-variable] variable 'configuration' shadows the one declared at variables.configuration",
+                "azure-pipelines.yml:9 [unused-variable] variable 'unusedGlobal' is declared but never used",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_whole_parameters_and_compile_time_variables() {
+        let warnings = lint(
+            r#"parameters:
+  - name: a
+    default: 1
+  - name: b
+    default: 2
+variables:
+  region: west
+steps:
+  - ${{ each p in parameters }}:
+    - script: echo ${{ p.key }} ${{ variables.region }}
+"#,
+            &[],
+        );
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        assert_eq!(
+            parameter_references("eq(parameters.deploy, true) and parameters['env']"),
+            Some(vec!["deploy".to_string(), "env".to_string()])
+        );
+        assert_eq!(parameter_references("convertToJson(parameters)"), None);
+        assert_eq!(parameter_references("myparameters.x"), Some(Vec::new()));
+    }
+}
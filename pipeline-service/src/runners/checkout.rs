@@ -0,0 +1,335 @@
+// Checkout Runner
+// Clones repository resources into the working directory for `checkout:` steps
+
+use crate::parser::models::{CheckoutSource, CheckoutStep, RepositoryResource, SubmoduleOption};
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Where a checkout step places a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckoutTarget {
+    /// Repository alias (`self` for the pipeline's own repository)
+    pub alias: String,
+    /// Repository name (e.g. `org/tools`)
+    pub name: String,
+    /// Local directory the repository is checked out to
+    pub path: PathBuf,
+    /// Clone URL or local directory, `None` when the working directory already holds the repository
+    pub source: Option<String>,
+    /// Branch, tag or commit to check out
+    pub git_ref: Option<String>,
+}
+
+/// Runner for `checkout:` steps
+pub struct CheckoutRunner;
+
+impl CheckoutRunner {
+    /// Resolve which repository a checkout step fetches and where it goes
+    ///
+    /// Returns `Ok(None)` for `checkout: none`. `self` is always the working
+    /// directory. Other repositories are placed at the step's `path` (default:
+    /// the repository name) relative to the working directory. `sources` maps
+    /// repository aliases to a clone URL or local directory and takes
+    /// precedence over the URL derived from the resource definition.
+    pub fn resolve(
+        step: &CheckoutStep,
+        repositories: &[RepositoryResource],
+        sources: &HashMap<String, String>,
+        working_dir: &Path,
+    ) -> Result<Option<CheckoutTarget>, String> {
+        let alias = match &step.checkout {
+            CheckoutSource::None(_) => return Ok(None),
+            CheckoutSource::SelfRepo(_) => {
+                let name = working_dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "self".to_string());
+                return Ok(Some(CheckoutTarget {
+                    alias: "self".to_string(),
+                    name,
+                    path: working_dir.to_path_buf(),
+                    source: None,
+                    git_ref: None,
+                }));
+            }
+            CheckoutSource::Repository(alias) => alias,
+        };
+
+        let resource = repositories
+            .iter()
+            .find(|r| r.repository == *alias)
+            .ok_or_else(|| {
+                format!(
+                    "repository '{}' is not declared in resources.repositories",
+                    alias
+                )
+            })?;
+        let name = resource.name.clone().unwrap_or_else(|| alias.clone());
+
+        let source = match sources.get(alias) {
+            Some(source) => source.clone(),
+            None => clone_url(resource, &name).ok_or_else(|| {
+                format!(
+                    "no clone source configured for repository '{}' (type: {})",
+                    alias,
+                    resource.repo_type.as_deref().unwrap_or("git")
+                )
+            })?,
+        };
+
+        let relative = match &step.path {
+            Some(path) => PathBuf::from(path),
+            None => PathBuf::from(name.rsplit('/').next().unwrap_or(&name)),
+        };
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!(
+                "checkout path '{}' must be relative to the working directory",
+                relative.display()
+            ));
+        }
+
+        Ok(Some(CheckoutTarget {
+            alias: alias.clone(),
+            name,
+            path: working_dir.join(relative),
+            source: Some(source),
+            git_ref: resource.git_ref.as_deref().map(short_ref),
+        }))
+    }
+
+    /// Clone the target repository, returning the log output
+    ///
+    /// An existing git checkout at the target path is reused unless the step
+    /// sets `clean: true`, in which case it is removed and cloned again.
+    pub async fn checkout(target: &CheckoutTarget, step: &CheckoutStep) -> Result<String, String> {
+        let Some(source) = &target.source else {
+            let mut output = format!(
+                "Checkout: Using existing working directory {}",
+                target.path.display()
+            );
+            if let Some(path) = &step.path {
+                output.push_str(&format!(
+                    "\n'self' is the working directory locally; path '{}' is not used",
+                    path
+                ));
+            }
+            return Ok(output);
+        };
+
+        if target.path.exists() {
+            if step.clean {
+                std::fs::remove_dir_all(&target.path)
+                    .map_err(|e| format!("failed to clean {}: {}", target.path.display(), e))?;
+            } else if target.path.join(".git").exists() {
+                return Ok(format!(
+                    "Checkout: Reusing existing clone of '{}' at {}",
+                    target.alias,
+                    target.path.display()
+                ));
+            } else if std::fs::read_dir(&target.path).is_ok_and(|mut d| d.next().is_some()) {
+                return Err(format!(
+                    "{} already exists and is not a git repository",
+                    target.path.display()
+                ));
+            }
+        }
+
+        let mut args = vec!["clone".to_string()];
+        if let Some(depth) = step.fetch_depth.filter(|d| *d > 0) {
+            args.push("--depth".to_string());
+            args.push(depth.to_string());
+        }
+        if step.fetch_tags == Some(false) {
+            args.push("--no-tags".to_string());
+        }
+        if !matches!(step.submodules, SubmoduleOption::False) {
+            args.push("--recurse-submodules".to_string());
+        }
+        // `--branch` takes branches and tags; commits are checked out after cloning
+        let commit = target.git_ref.as_deref().filter(|r| is_commit(r));
+        if let Some(git_ref) = target.git_ref.as_deref().filter(|r| !is_commit(r)) {
+            args.push("--branch".to_string());
+            args.push(git_ref.to_string());
+        }
+        args.push(source.clone());
+        args.push(target.path.to_string_lossy().to_string());
+
+        let mut output = git(&args, None).await?;
+        if let Some(commit) = commit {
+            output.push_str(
+                &git(
+                    &["checkout".to_string(), commit.to_string()],
+                    Some(&target.path),
+                )
+                .await?,
+            );
+        }
+
+        output.push_str(&format!(
+            "Checked out '{}' to {}",
+            target.alias,
+            target.path.display()
+        ));
+        Ok(output)
+    }
+}
+
+/// Clone URL for a repository resource hosted on a known service
+fn clone_url(resource: &RepositoryResource, name: &str) -> Option<String> {
+    match resource.repo_type.as_deref() {
+        Some("github") => Some(format!("https://github.com/{}.git", name)),
+        Some("bitbucket") => Some(format!("https://bitbucket.org/{}.git", name)),
+        // Azure Repos (`git`) URLs depend on the organization, which the
+        // pipeline doesn't declare
+        _ => None,
+    }
+}
+
+/// Strip `refs/heads/` and `refs/tags/` so the ref can be passed to `git clone --branch`
+fn short_ref(git_ref: &str) -> String {
+    git_ref
+        .strip_prefix("refs/heads/")
+        .or_else(|| git_ref.strip_prefix("refs/tags/"))
+        .unwrap_or(git_ref)
+        .to_string()
+}
+
+fn is_commit(git_ref: &str) -> bool {
+    (7..=40).contains(&git_ref.len()) && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Run git, returning combined output
+async fn git(args: &[String], dir: Option<&Path>) -> Result<String, String> {
+    let mut cmd = tokio::process::Command::new("git");
+    cmd.args(args);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("failed to run git: {}", e))?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().map(String::as_str).unwrap_or_default(),
+            text.trim()
+        ));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::models::CheckoutSelf;
+
+    fn step(source: CheckoutSource, path: Option<&str>) -> CheckoutStep {
+        CheckoutStep {
+            checkout: source,
+            clean: false,
+            fetch_depth: None,
+            fetch_tags: None,
+            lfs: false,
+            submodules: SubmoduleOption::False,
+            path: path.map(String::from),
+            persistent_credentials: None,
+        }
+    }
+
+    fn repo(alias: &str, repo_type: &str, name: &str, git_ref: Option<&str>) -> RepositoryResource {
+        RepositoryResource {
+            repository: alias.to_string(),
+            repo_type: Some(repo_type.to_string()),
+            name: Some(name.to_string()),
+            git_ref: git_ref.map(String::from),
+            endpoint: None,
+            trigger: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_repository_with_path() {
+        let repos = vec![repo(
+            "tools",
+            "github",
+            "org/build-tools",
+            Some("refs/heads/release"),
+        )];
+        let target = CheckoutRunner::resolve(
+            &step(
+                CheckoutSource::Repository("tools".into()),
+                Some("deps/tools"),
+            ),
+            &repos,
+            &HashMap::new(),
+            Path::new("/work"),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(target.path, PathBuf::from("/work/deps/tools"));
+        assert_eq!(
+            target.source.as_deref(),
+            Some("https://github.com/org/build-tools.git")
+        );
+        assert_eq!(target.git_ref.as_deref(), Some("release"));
+    }
+
+    #[test]
+    fn test_resolve_defaults_and_overrides() {
+        let repos = vec![repo("tools", "git", "Project/tools", None)];
+        let checkout = step(CheckoutSource::Repository("tools".into()), None);
+
+        // Azure Repos have no derivable URL without an override
+        assert!(
+            CheckoutRunner::resolve(&checkout, &repos, &HashMap::new(), Path::new("/w")).is_err()
+        );
+
+        let sources = HashMap::from([("tools".to_string(), "/src/tools".to_string())]);
+        let target = CheckoutRunner::resolve(&checkout, &repos, &sources, Path::new("/w"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.path, PathBuf::from("/w/tools"));
+        assert_eq!(target.source.as_deref(), Some("/src/tools"));
+
+        let self_target = CheckoutRunner::resolve(
+            &step(CheckoutSource::SelfRepo(CheckoutSelf::SelfRepo), None),
+            &repos,
+            &sources,
+            Path::new("/w"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(self_target.path, PathBuf::from("/w"));
+        assert!(self_target.source.is_none());
+    }
+
+    #[test]
+    fn test_resolve_rejects_escaping_paths() {
+        let repos = vec![repo("tools", "github", "org/tools", None)];
+        for path in ["../outside", "/abs/path"] {
+            let result = CheckoutRunner::resolve(
+                &step(CheckoutSource::Repository("tools".into()), Some(path)),
+                &repos,
+                &HashMap::new(),
+                Path::new("/w"),
+            );
+            assert!(result.is_err(), "{} should be rejected", path);
+        }
+        assert!(CheckoutRunner::resolve(
+            &step(CheckoutSource::Repository("missing".into()), None),
+            &repos,
+            &HashMap::new(),
+            Path::new("/w"),
+        )
+        .is_err());
+    }
+}
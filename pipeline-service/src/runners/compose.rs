@@ -0,0 +1,419 @@
+// Compose Runner
+// Brings up docker compose services for the duration of a pipeline or job
+
+use crate::parser::models::ComposeSpec;
+use crate::runners::container::{uuid_v4_simple, ContainerError};
+use crate::runners::hosts::HostOverride;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Default time to wait for compose services to become healthy
+const DEFAULT_WAIT_TIMEOUT_SECS: u32 = 120;
+
+/// A service started by docker compose
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeService {
+    /// Service name from the compose file
+    pub name: String,
+    /// Container port to published host port
+    pub ports: BTreeMap<u16, u16>,
+}
+
+/// A running compose project
+#[derive(Debug)]
+pub struct ComposeProject {
+    /// Compose project name (unique per run)
+    pub name: String,
+    /// Compose file the project was started from
+    pub file: PathBuf,
+    /// Generated file adding host overrides to the services, removed on `down`
+    pub hosts_file: Option<PathBuf>,
+    /// Services and their published ports
+    pub services: Vec<ComposeService>,
+}
+
+impl ComposeProject {
+    /// Variables describing how to reach each service from the host
+    ///
+    /// For a service `db` publishing container port 5432 this yields
+    /// `compose.db.host`, `compose.db.port` (first published port) and
+    /// `compose.db.ports.5432`.
+    pub fn variables(&self) -> Vec<(String, String)> {
+        let mut vars = Vec::new();
+        for service in &self.services {
+            let prefix = format!("compose.{}", service.name);
+            vars.push((format!("{}.host", prefix), "localhost".to_string()));
+            if let Some(port) = service.ports.values().next() {
+                vars.push((format!("{}.port", prefix), port.to_string()));
+            }
+            for (target, published) in &service.ports {
+                vars.push((
+                    format!("{}.ports.{}", prefix, target),
+                    published.to_string(),
+                ));
+            }
+        }
+        vars
+    }
+}
+
+/// Runner that manages docker compose projects
+pub struct ComposeRunner;
+
+impl ComposeRunner {
+    /// Start the services in a compose file and wait until they are healthy
+    ///
+    /// `scope` names the pipeline or job the services belong to and is used in
+    /// the generated project name. `hosts` are added to every service started as
+    /// `extra_hosts`. The project is torn down again if startup fails.
+    pub async fn up(
+        spec: &ComposeSpec,
+        working_dir: &Path,
+        scope: &str,
+        hosts: &[HostOverride],
+    ) -> Result<ComposeProject, ContainerError> {
+        let file = working_dir.join(&spec.file);
+        if !file.exists() {
+            return Err(ContainerError::ComposeFailed(format!(
+                "Compose file not found: {}",
+                file.display()
+            )));
+        }
+
+        let mut project = ComposeProject {
+            name: project_name(scope),
+            file,
+            hosts_file: None,
+            services: Vec::new(),
+        };
+
+        if !hosts.is_empty() {
+            let content = std::fs::read_to_string(&project.file)?;
+            let overrides = hosts_override(&content, &spec.services, hosts).map_err(|e| {
+                ContainerError::ComposeFailed(format!("invalid {}: {}", spec.file, e))
+            })?;
+            let hosts_file = std::env::temp_dir().join(format!("{}.hosts.yml", project.name));
+            std::fs::write(&hosts_file, overrides)?;
+            project.hosts_file = Some(hosts_file);
+        }
+
+        let timeout = spec
+            .wait_timeout_in_seconds
+            .unwrap_or(DEFAULT_WAIT_TIMEOUT_SECS)
+            .to_string();
+        let mut args = vec!["up", "--detach", "--wait", "--wait-timeout", &timeout];
+        args.extend(spec.services.iter().map(String::as_str));
+
+        if let Err(e) = compose_command(&project, &args).await {
+            let _ = Self::down(project).await;
+            return Err(e);
+        }
+
+        match compose_command(&project, &["ps", "--format", "json"]).await {
+            Ok(stdout) => project.services = parse_ps_output(&stdout),
+            Err(e) => {
+                let _ = Self::down(project).await;
+                return Err(e);
+            }
+        }
+
+        Ok(project)
+    }
+
+    /// Stop the project and remove its containers, networks and volumes
+    pub async fn down(project: ComposeProject) -> Result<(), ContainerError> {
+        let result = compose_command(&project, &["down", "--volumes", "--remove-orphans"]).await;
+        if let Some(hosts_file) = &project.hosts_file {
+            let _ = std::fs::remove_file(hosts_file);
+        }
+        result.map(|_| ())
+    }
+
+    /// Host ports the compose file publishes at fixed numbers, by service
+    ///
+    /// Ports left for docker to choose (`"5432"`) are not included; only the
+    /// services the spec starts are considered.
+    pub fn fixed_host_ports(
+        spec: &ComposeSpec,
+        working_dir: &Path,
+    ) -> Result<Vec<(String, u16)>, String> {
+        let file = working_dir.join(&spec.file);
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("failed to read {}: {}", file.display(), e))?;
+        parse_host_ports(&content, &spec.services)
+            .map_err(|e| format!("invalid {}: {}", file.display(), e))
+    }
+}
+
+/// Run `docker compose` against a project, returning stdout
+async fn compose_command(
+    project: &ComposeProject,
+    args: &[&str],
+) -> Result<String, ContainerError> {
+    let output = tokio::process::Command::new("docker")
+        .arg("compose")
+        .arg("--project-name")
+        .arg(&project.name)
+        .arg("--file")
+        .arg(&project.file)
+        .args(
+            project
+                .hosts_file
+                .iter()
+                .flat_map(|file| [std::ffi::OsStr::new("--file"), file.as_os_str()]),
+        )
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| ContainerError::DockerNotAvailable(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(ContainerError::ComposeFailed(format!(
+            "docker compose {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Build a compose project name: lowercase alphanumerics, `-` and `_` only
+///
+/// The name ends in the roxid process ID and a random suffix
+/// (`roxid-build-4242-1a2b3c4d`), see [`project_pid`].
+fn project_name(scope: &str) -> String {
+    let scope: String = scope
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!(
+        "roxid-{}-{}-{}",
+        scope,
+        std::process::id(),
+        &uuid_v4_simple()[..8]
+    )
+}
+
+/// The roxid process that started a compose project, from its name
+pub fn project_pid(name: &str) -> Option<u32> {
+    let mut parts = name.strip_prefix("roxid-")?.rsplitn(3, '-');
+    let suffix = parts.next()?;
+    let pid = parts.next()?;
+    parts.next()?;
+    (suffix.len() == 8).then_some(())?;
+    pid.parse().ok()
+}
+
+/// Parse `docker compose ps --format json` output
+///
+/// Newer compose releases print one JSON object per line, older ones a single array.
+fn parse_ps_output(stdout: &str) -> Vec<ComposeService> {
+    let entries: Vec<serde_json::Value> = match serde_json::from_str(stdout.trim()) {
+        Ok(serde_json::Value::Array(entries)) => entries,
+        _ => stdout
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect(),
+    };
+
+    let mut services: BTreeMap<String, BTreeMap<u16, u16>> = BTreeMap::new();
+    for entry in entries {
+        let Some(name) = entry.get("Service").and_then(|s| s.as_str()) else {
+            continue;
+        };
+        let ports = services.entry(name.to_string()).or_default();
+
+        let publishers = entry.get("Publishers").and_then(|p| p.as_array());
+        for publisher in publishers.into_iter().flatten() {
+            let target = publisher.get("TargetPort").and_then(|p| p.as_u64());
+            let published = publisher.get("PublishedPort").and_then(|p| p.as_u64());
+            if let (Some(target), Some(published)) = (target, published) {
+                // Unpublished ports are reported with PublishedPort 0
+                if published != 0 {
+                    ports.insert(target as u16, published as u16);
+                }
+            }
+        }
+    }
+
+    services
+        .into_iter()
+        .map(|(name, ports)| ComposeService { name, ports })
+        .collect()
+}
+
+/// A compose file adding `hosts` as `extra_hosts` to each service in `content`
+/// (or just those in `only`)
+fn hosts_override(
+    content: &str,
+    only: &[String],
+    hosts: &[HostOverride],
+) -> Result<String, String> {
+    let compose: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+    let extra_hosts: serde_yaml::Value = hosts
+        .iter()
+        .map(|host| serde_yaml::Value::String(host.docker_host()))
+        .collect::<Vec<_>>()
+        .into();
+
+    let mut services = serde_yaml::Mapping::new();
+    let names = compose
+        .get("services")
+        .and_then(|s| s.as_mapping())
+        .into_iter()
+        .flat_map(|services| services.keys())
+        .filter_map(|name| name.as_str());
+    for name in names {
+        if only.is_empty() || only.iter().any(|s| s == name) {
+            let mut service = serde_yaml::Mapping::new();
+            service.insert("extra_hosts".into(), extra_hosts.clone());
+            services.insert(name.into(), service.into());
+        }
+    }
+
+    let mut file = serde_yaml::Mapping::new();
+    file.insert("services".into(), services.into());
+    serde_yaml::to_string(&file).map_err(|e| e.to_string())
+}
+
+/// Fixed host ports from a compose file's `ports:` lists
+///
+/// Handles the short syntax (`"8080:80"`, `"127.0.0.1:8080:80/tcp"`) and the
+/// long syntax (`published: 8080`).
+fn parse_host_ports(content: &str, only: &[String]) -> Result<Vec<(String, u16)>, String> {
+    let compose: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+    let Some(services) = compose.get("services").and_then(|s| s.as_mapping()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut ports = Vec::new();
+    for (name, service) in services {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        if !only.is_empty() && !only.iter().any(|s| s == name) {
+            continue;
+        }
+        let entries = service.get("ports").and_then(|p| p.as_sequence());
+        for entry in entries.into_iter().flatten() {
+            let published = match entry {
+                serde_yaml::Value::String(short) => {
+                    let mapping = short.split('/').next().unwrap_or_default();
+                    let parts: Vec<&str> = mapping.rsplitn(3, ':').collect();
+                    parts.get(1).and_then(|host| host.parse::<u16>().ok())
+                }
+                serde_yaml::Value::Mapping(_) => entry.get("published").and_then(|p| match p {
+                    serde_yaml::Value::Number(n) => n.as_u64().map(|n| n as u16),
+                    serde_yaml::Value::String(s) => s.parse().ok(),
+                    _ => None,
+                }),
+                _ => None,
+            };
+            if let Some(port) = published.filter(|port| *port != 0) {
+                ports.push((name.to_string(), port));
+            }
+        }
+    }
+    Ok(ports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ps_output_ndjson() {
+        let stdout = r#"{"Service":"db","Publishers":[{"URL":"0.0.0.0","TargetPort":5432,"PublishedPort":55001,"Protocol":"tcp"},{"URL":"::","TargetPort":5432,"PublishedPort":55001,"Protocol":"tcp"}]}
+{"Service":"worker","Publishers":[{"URL":"","TargetPort":8080,"PublishedPort":0,"Protocol":"tcp"}]}"#;
+
+        let services = parse_ps_output(stdout);
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].name, "db");
+        assert_eq!(services[0].ports.get(&5432), Some(&55001));
+        assert!(services[1].ports.is_empty());
+    }
+
+    #[test]
+    fn test_parse_ps_output_array() {
+        let stdout =
+            r#"[{"Service":"cache","Publishers":[{"TargetPort":6379,"PublishedPort":6380}]}]"#;
+        let services = parse_ps_output(stdout);
+        assert_eq!(services[0].name, "cache");
+        assert_eq!(services[0].ports.get(&6379), Some(&6380));
+    }
+
+    #[test]
+    fn test_parse_host_ports() {
+        let content = r#"
+services:
+  db:
+    image: postgres
+    ports:
+      - "5432"
+      - "55432:5432"
+      - "127.0.0.1:8080:80/tcp"
+  web:
+    image: nginx
+    ports:
+      - target: 80
+        published: 8081
+"#;
+        let ports = parse_host_ports(content, &[]).unwrap();
+        assert_eq!(
+            ports,
+            [
+                ("db".to_string(), 55432),
+                ("db".to_string(), 8080),
+                ("web".to_string(), 8081)
+            ]
+        );
+        let ports = parse_host_ports(content, &["web".to_string()]).unwrap();
+        assert_eq!(ports, [("web".to_string(), 8081)]);
+    }
+
+    #[test]
+    fn test_hosts_override() {
+        let content = "services:\n  db:\n    image: postgres\n  web:\n    image: nginx\n";
+        let hosts = [HostOverride::parse("api.internal=127.0.0.1:8080").unwrap()];
+        let overrides = hosts_override(content, &["web".to_string()], &hosts).unwrap();
+        assert_eq!(
+            overrides,
+            "services:\n  web:\n    extra_hosts:\n    - api.internal:host-gateway\n"
+        );
+    }
+
+    #[test]
+    fn test_project_variables() {
+        let project = ComposeProject {
+            name: "roxid-test".to_string(),
+            file: PathBuf::from("docker-compose.yml"),
+            hosts_file: None,
+            services: vec![ComposeService {
+                name: "db".to_string(),
+                ports: BTreeMap::from([(5432, 55001)]),
+            }],
+        };
+
+        let vars = project.variables();
+        assert!(vars.contains(&("compose.db.host".to_string(), "localhost".to_string())));
+        assert!(vars.contains(&("compose.db.port".to_string(), "55001".to_string())));
+        assert!(vars.contains(&("compose.db.ports.5432".to_string(), "55001".to_string())));
+    }
+
+    #[test]
+    fn test_project_name_is_sanitized() {
+        let name = project_name("Build.Integration Tests");
+        assert!(name.starts_with("roxid-build-integration-tests-"));
+        assert_eq!(project_pid(&name), Some(std::process::id()));
+        assert_eq!(project_pid("roxid-test"), None);
+        assert_eq!(project_pid("myapp-build-42-1a2b3c4d"), None);
+    }
+}
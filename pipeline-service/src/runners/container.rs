@@ -2,14 +2,27 @@
 // Executes jobs inside Docker containers
 
 use crate::parser::models::{
-    ContainerRef, ContainerSpec, Job, JobResult, JobStatus, Step, StepResult, StepStatus,
+    ContainerRef, ContainerResource, ContainerSpec, Job, JobResult, JobStatus, Pipeline, Step,
+    StepResult, StepStatus,
 };
+use crate::runners::hosts::{add_host_args, host_env, HostOverride};
+use crate::runners::path_map::{PathMap, CONTAINER_WORKSPACE};
+use crate::tasks::ServiceConnections;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::Deserialize;
 use thiserror::Error;
 
+/// Label on job and service containers naming the roxid process that created
+/// them, so ones left behind by a crashed run can be found
+pub const PID_LABEL: &str = "roxid.pid";
+
 /// Errors that can occur with container execution
 #[derive(Debug, Error)]
 pub enum ContainerError {
@@ -19,6 +32,9 @@ pub enum ContainerError {
     #[error("Failed to pull image: {0}")]
     PullFailed(String),
 
+    #[error("Failed to log in to registry: {0}")]
+    LoginFailed(String),
+
     #[error("Failed to create container: {0}")]
     CreateFailed(String),
 
@@ -31,6 +47,9 @@ pub enum ContainerError {
     #[error("Failed to stop container: {0}")]
     StopFailed(String),
 
+    #[error("Docker compose failed: {0}")]
+    ComposeFailed(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -46,6 +65,13 @@ pub struct ContainerConfig {
     pub timeout: Duration,
     /// Whether to remove containers after execution
     pub auto_remove: bool,
+    /// Hostnames to point at local stand-ins
+    pub hosts: Vec<HostOverride>,
+    /// Digests to run images at, by image reference (`node:20` to `sha256:...`)
+    pub pins: HashMap<String, String>,
+    /// Host directories mounted in job containers besides the workspace and
+    /// temp directory, by host path (`C:\tools` to `/tools`)
+    pub mounts: BTreeMap<String, String>,
 }
 
 impl Default for ContainerConfig {
@@ -55,23 +81,96 @@ impl Default for ContainerConfig {
             pull_policy: ImagePullPolicy::IfNotPresent,
             timeout: Duration::from_secs(3600),
             auto_remove: true,
+            hosts: Vec::new(),
+            pins: HashMap::new(),
+            mounts: BTreeMap::new(),
         }
     }
 }
 
 /// Image pull policy
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum ImagePullPolicy {
     /// Always pull the image
     Always,
     /// Pull only if not present locally
+    #[default]
     IfNotPresent,
     /// Never pull (must be present locally)
     Never,
 }
 
+impl FromStr for ImagePullPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "always" => Ok(Self::Always),
+            "if-not-present" => Ok(Self::IfNotPresent),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "unknown pull policy '{}' (expected always, if-not-present or never)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ImagePullPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Always => "always",
+            Self::IfNotPresent => "if-not-present",
+            Self::Never => "never",
+        })
+    }
+}
+
+/// Container settings from `.roxid.toml`
+///
+/// ```toml
+/// [containers]
+/// pull-policy = "never"
+/// pins = { "node:20" = "sha256:..." }
+/// mounts = { "C:\\tools" = "/tools" }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ContainerSettings {
+    /// When to pull images; `--pull` takes precedence
+    pub pull_policy: Option<ImagePullPolicy>,
+    /// Run these image references at a fixed digest
+    #[serde(default)]
+    pub pins: HashMap<String, String>,
+    /// Mount these host directories in job containers, at these paths
+    #[serde(default)]
+    pub mounts: BTreeMap<String, String>,
+}
+
+impl ContainerSettings {
+    /// Check that every pin is a `sha256:` digest
+    pub fn validate(&self) -> Result<(), String> {
+        let mut images: Vec<&String> = self.pins.keys().collect();
+        images.sort();
+        for image in images {
+            let digest = &self.pins[image];
+            let valid = digest
+                .strip_prefix("sha256:")
+                .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+            if !valid {
+                return Err(format!(
+                    "pin for '{}' must be a sha256: digest, got '{}'",
+                    image, digest
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Handle to a running container
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ContainerHandle {
     /// Container ID
     pub id: String,
@@ -79,6 +178,8 @@ pub struct ContainerHandle {
     pub name: String,
     /// Image used
     pub image: String,
+    /// Host directories mounted in the container
+    pub paths: PathMap,
 }
 
 /// Handle to service containers
@@ -91,19 +192,119 @@ pub struct ServiceHandles {
 /// Container runner for Docker-based execution
 pub struct ContainerRunner {
     config: ContainerConfig,
+    /// `resources.containers` by alias
+    aliases: HashMap<String, ContainerSpec>,
+    /// Connections to log in to registries named by a container's `endpoint`
+    registries: ServiceConnections,
+    /// Images already checked or pulled, so each is pulled at most once per run
+    pulled: Mutex<HashSet<String>>,
+    /// Registries already logged in to
+    logged_in: Mutex<HashSet<String>>,
+    /// Digest each image ran at, by the reference the pipeline wrote
+    digests: Mutex<BTreeMap<String, String>>,
 }
 
 impl ContainerRunner {
     /// Create a new container runner with default configuration
     pub fn new() -> Self {
-        Self {
-            config: ContainerConfig::default(),
-        }
+        Self::with_config(ContainerConfig::default())
     }
 
     /// Create a container runner with custom configuration
     pub fn with_config(config: ContainerConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            aliases: HashMap::new(),
+            registries: ServiceConnections::default(),
+            pulled: Mutex::new(HashSet::new()),
+            logged_in: Mutex::new(HashSet::new()),
+            digests: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Resolve `container: <alias>` on jobs and services to these
+    /// `resources.containers` entries
+    pub fn with_resources(mut self, resources: &[ContainerResource]) -> Self {
+        for resource in resources {
+            self.aliases.insert(
+                resource.container.clone(),
+                ContainerSpec {
+                    image: resource.image.clone(),
+                    endpoint: resource.endpoint.clone(),
+                    env: resource.env.clone(),
+                    ports: resource.ports.clone(),
+                    volumes: resource.volumes.clone(),
+                    options: resource.options.clone(),
+                    map_docker_socket: resource.map_docker_socket,
+                    mount_read_only: None,
+                },
+            );
+        }
+        self
+    }
+
+    /// Log in to registries with these connections (`dockerregistry`, with
+    /// `username` and `password` auth) when a container names one as `endpoint`
+    pub fn with_registries(mut self, registries: ServiceConnections) -> Self {
+        self.registries = registries;
+        self
+    }
+
+    /// Pull (if needed), create and start the container a job's steps run in
+    pub async fn start_job_container(
+        &self,
+        name: &str,
+        container: &ContainerRef,
+        env: &HashMap<String, String>,
+        working_dir: &Path,
+    ) -> Result<ContainerHandle, ContainerError> {
+        let spec = self.prepare_image(container).await?;
+        self.create_container(name, &spec, env, working_dir).await
+    }
+
+    /// Stop and remove a container started by `start_job_container`
+    pub async fn stop_job_container(&self, handle: &ContainerHandle) {
+        let _ = self.stop_container(handle).await;
+    }
+
+    /// Wrap `command` (program then arguments) so it runs in `container`
+    ///
+    /// Working directories and path variables (`BUILD_SOURCESDIRECTORY`, ...)
+    /// under a mounted directory are translated to where it is mounted. Like
+    /// the sandbox, docker reads the values of other `env` entries that
+    /// differ from roxid's own environment from its environment.
+    pub fn exec_command(
+        container: &ContainerHandle,
+        command: &[String],
+        working_dir: &Path,
+        env: &HashMap<String, String>,
+    ) -> Vec<String> {
+        let working_dir = working_dir.to_string_lossy();
+        let working_dir = container
+            .paths
+            .translate(&working_dir)
+            .unwrap_or_else(|| working_dir.to_string());
+        let mut wrapped: Vec<String> = ["docker", "exec", "-i", "-w"].map(String::from).to_vec();
+        wrapped.push(working_dir);
+        let translated = container.paths.translate_env(env);
+        let mut names: Vec<&String> = env
+            .iter()
+            .filter(|(name, value)| std::env::var(name).ok().as_ref() != Some(*value))
+            .map(|(name, _)| name)
+            .filter(|name| !translated.iter().any(|(translated, _)| translated == *name))
+            .collect();
+        names.sort();
+        for name in names {
+            wrapped.push("-e".to_string());
+            wrapped.push(name.clone());
+        }
+        for (name, path) in translated {
+            wrapped.push("-e".to_string());
+            wrapped.push(format!("{}={}", name, path));
+        }
+        wrapped.push(container.name.clone());
+        wrapped.extend(command.iter().cloned());
+        wrapped
     }
 
     /// Check if Docker is available
@@ -128,13 +329,11 @@ impl ContainerRunner {
         working_dir: &Path,
     ) -> Result<JobResult, ContainerError> {
         let start = Instant::now();
+        let started_at = SystemTime::now();
         let job_name = job.identifier().unwrap_or("job").to_string();
 
-        // Parse container spec
-        let container_spec = self.parse_container_ref(container)?;
-
-        // Pull image if needed
-        self.pull_image_if_needed(&container_spec.image).await?;
+        // Parse container spec and pull image if needed
+        let container_spec = self.prepare_image(container).await?;
 
         // Create and start the container
         let container_handle = self
@@ -175,6 +374,8 @@ impl ContainerRunner {
             steps: step_results,
             duration: start.elapsed(),
             outputs: HashMap::new(),
+            started_at: Some(started_at),
+            finished_at: Some(SystemTime::now()),
         })
     }
 
@@ -188,10 +389,7 @@ impl ContainerRunner {
         let mut handles = HashMap::new();
 
         for (service_name, container_ref) in services {
-            let container_spec = self.parse_container_ref(container_ref)?;
-
-            // Pull image if needed
-            self.pull_image_if_needed(&container_spec.image).await?;
+            let container_spec = self.prepare_image(container_ref).await?;
 
             // Create service container
             let handle = self
@@ -215,12 +413,83 @@ impl ContainerRunner {
         Ok(())
     }
 
-    /// Parse a container reference into a spec
+    /// Digest each image ran at so far, by the reference the pipeline wrote
+    ///
+    /// Images pulled from a registry report their repository digest; images
+    /// that only exist locally report their image ID.
+    pub fn resolved_digests(&self) -> BTreeMap<String, String> {
+        self.digests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Resolve a container reference, apply its pin, log in to its registry
+    /// and pull it if needed
+    async fn prepare_image(
+        &self,
+        container: &ContainerRef,
+    ) -> Result<ContainerSpec, ContainerError> {
+        let mut spec = self.parse_container_ref(container)?;
+        let written = spec.image.clone();
+        spec.image = pin_image(&written, &self.config.pins);
+        self.login(&spec).await?;
+        self.pull_image_if_needed(&spec.image).await?;
+        if !self
+            .digests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(&written)
+        {
+            if let Some(digest) = self.inspect_digest(&spec.image).await {
+                self.digests
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(written, digest);
+            }
+        }
+        Ok(spec)
+    }
+
+    /// The repository digest of a local image, or its ID when it has none
+    async fn inspect_digest(&self, image: &str) -> Option<String> {
+        let output = tokio::process::Command::new("docker")
+            .args([
+                "image",
+                "inspect",
+                "--format",
+                "{{.Id}}{{range .RepoDigests}} {{.}}{{end}}",
+                image,
+            ])
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split_whitespace();
+        let id = fields.next()?;
+        let repo_digests: Vec<(&str, &str)> =
+            fields.filter_map(|digest| digest.split_once('@')).collect();
+        let digest = repo_digests
+            .iter()
+            .find(|(name, _)| *name == image_name(image))
+            .or(repo_digests.first())
+            .map_or(id, |(_, digest)| digest);
+        Some(digest.to_string())
+    }
+
+    /// Parse a container reference into a spec, resolving
+    /// `resources.containers` aliases
     fn parse_container_ref(
         &self,
         container: &ContainerRef,
     ) -> Result<ContainerSpec, ContainerError> {
         match container {
+            ContainerRef::Image(alias) if self.aliases.contains_key(alias) => {
+                Ok(self.aliases[alias].clone())
+            }
             ContainerRef::Image(image) => Ok(ContainerSpec {
                 image: image.clone(),
                 endpoint: None,
@@ -235,8 +504,88 @@ impl ContainerRunner {
         }
     }
 
-    /// Pull an image if needed based on pull policy
+    /// Log in to the registry a container's `endpoint` names, once per run
+    async fn login(&self, spec: &ContainerSpec) -> Result<(), ContainerError> {
+        let Some(endpoint) = &spec.endpoint else {
+            return Ok(());
+        };
+        let Some((name, connection)) = self.registries.get(endpoint) else {
+            return Err(ContainerError::LoginFailed(format!(
+                "no service connection named '{}' for image {}; define it in .roxid.toml",
+                endpoint, spec.image
+            )));
+        };
+        if self
+            .logged_in
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(name)
+        {
+            return Ok(());
+        }
+        let auth = |key: &str| {
+            connection
+                .auth
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.clone())
+        };
+        let (Some(username), Some(password)) = (auth("username"), auth("password")) else {
+            return Err(ContainerError::LoginFailed(format!(
+                "service connection '{}' needs username and password auth",
+                name
+            )));
+        };
+
+        let mut args = vec!["login".to_string(), "--username".to_string(), username];
+        args.push("--password-stdin".to_string());
+        args.extend(connection.url.clone());
+        let mut child = tokio::process::Command::new("docker")
+            .args(&args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| ContainerError::DockerNotAvailable(e.to_string()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            use tokio::io::AsyncWriteExt;
+            stdin.write_all(password.as_bytes()).await?;
+        }
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(ContainerError::LoginFailed(format!(
+                "{}: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        self.logged_in
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.to_string());
+        Ok(())
+    }
+
+    /// Pull an image if needed based on pull policy; each image is handled
+    /// once per run, however many jobs use it
     async fn pull_image_if_needed(&self, image: &str) -> Result<(), ContainerError> {
+        if self
+            .pulled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(image)
+        {
+            return Ok(());
+        }
+        self.pull_image_by_policy(image).await?;
+        self.pulled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(image.to_string());
+        Ok(())
+    }
+
+    async fn pull_image_by_policy(&self, image: &str) -> Result<(), ContainerError> {
         match self.config.pull_policy {
             ImagePullPolicy::Never => Ok(()),
             ImagePullPolicy::Always => self.pull_image(image).await,
@@ -286,18 +635,35 @@ impl ContainerRunner {
     ) -> Result<ContainerHandle, ContainerError> {
         let container_name = format!("roxid-{}-{}", name, uuid_v4_simple());
 
+        let paths = self
+            .config
+            .mounts
+            .iter()
+            .fold(PathMap::new(working_dir), |paths, (host, container)| {
+                paths.with_mount(host, container)
+            });
         let mut args = vec![
             "create".to_string(),
             "--name".to_string(),
             container_name.clone(),
+            "--label".to_string(),
+            pid_label(),
             "-w".to_string(),
-            "/workspace".to_string(),
-            "-v".to_string(),
-            format!("{}:/workspace", working_dir.display()),
+            CONTAINER_WORKSPACE.to_string(),
         ];
-
-        // Add environment variables
-        for (key, value) in env {
+        args.extend(paths.volume_args());
+        args.extend(add_host_args(&self.config.hosts));
+
+        // Add environment variables, with path variables moved into the container
+        let hosts = host_env(&self.config.hosts);
+        let mut env: Vec<(&String, &String)> =
+            hosts.iter().map(|(k, v)| (k, v)).chain(env).collect();
+        let translated = paths.translate_env(env.iter().copied());
+        env.retain(|(key, _)| !translated.iter().any(|(name, _)| name == *key));
+        for (key, value) in env
+            .into_iter()
+            .chain(translated.iter().map(|(k, v)| (k, v)))
+        {
             args.push("-e".to_string());
             args.push(format!("{}={}", key, value));
         }
@@ -328,10 +694,7 @@ impl ContainerRunner {
 
         // Add any additional options
         if let Some(options) = &spec.options {
-            // Parse options string (space-separated Docker flags)
-            for opt in options.split_whitespace() {
-                args.push(opt.to_string());
-            }
+            args.extend(split_options(options));
         }
 
         // Add the image
@@ -371,6 +734,7 @@ impl ContainerRunner {
             id: container_id,
             name: container_name,
             image: spec.image.clone(),
+            paths,
         })
     }
 
@@ -389,7 +753,10 @@ impl ContainerRunner {
             "-d".to_string(),
             "--name".to_string(),
             container_name.clone(),
+            "--label".to_string(),
+            pid_label(),
         ];
+        args.extend(add_host_args(&self.config.hosts));
 
         // Add environment variables
         for (key, value) in env {
@@ -434,6 +801,7 @@ impl ContainerRunner {
             id: container_id,
             name: container_name,
             image: spec.image.clone(),
+            paths: PathMap::default(),
         })
     }
 
@@ -446,6 +814,7 @@ impl ContainerRunner {
         _working_dir: &Path,
     ) -> StepResult {
         let start = Instant::now();
+        let started_at = SystemTime::now();
         let step_name = step.name.clone();
 
         // For now, we only support script steps in containers
@@ -464,6 +833,12 @@ impl ContainerRunner {
                     duration: start.elapsed(),
                     exit_code: None,
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: None,
+                    finished_at: None,
+                    env: Vec::new(),
                 };
             }
         };
@@ -506,6 +881,12 @@ impl ContainerRunner {
                     duration: start.elapsed(),
                     exit_code: output.status.code(),
                     outputs: HashMap::new(),
+                    substitutions: Vec::new(),
+                    summary: None,
+                    unsupported: Vec::new(),
+                    started_at: Some(started_at),
+                    finished_at: Some(SystemTime::now()),
+                    env: Vec::new(),
                 }
             }
             Err(e) => StepResult {
@@ -517,6 +898,12 @@ impl ContainerRunner {
                 duration: start.elapsed(),
                 exit_code: None,
                 outputs: HashMap::new(),
+                substitutions: Vec::new(),
+                summary: None,
+                unsupported: Vec::new(),
+                started_at: Some(started_at),
+                finished_at: Some(SystemTime::now()),
+                env: Vec::new(),
             },
         }
     }
@@ -576,11 +963,97 @@ impl Default for ContainerRunner {
     }
 }
 
+/// Repository of an image reference, without its tag or digest
+/// (`registry:5000/team/app:1.2` is `registry:5000/team/app`)
+pub fn image_name(image: &str) -> &str {
+    let name = image.split_once('@').map_or(image, |(name, _)| name);
+    match name.rfind(':') {
+        Some(colon) if !name[colon..].contains('/') => &name[..colon],
+        _ => name,
+    }
+}
+
+/// `image` at its pinned digest, if `pins` has one for it
+pub fn pin_image(image: &str, pins: &HashMap<String, String>) -> String {
+    match pins.get(image) {
+        Some(digest) => format!("{}@{}", image_name(image), digest),
+        None => image.to_string(),
+    }
+}
+
+/// Whether `image` follows whatever was pushed last: no digest, and no tag or
+/// `latest`
+pub fn is_floating(image: &str) -> bool {
+    if image.contains('@') || image.contains("$(") || image.contains("${{") {
+        return false;
+    }
+    let tag = image.strip_prefix(image_name(image)).unwrap_or_default();
+    tag.is_empty() || tag == ":latest"
+}
+
+/// Images a pipeline's jobs and services run that float and aren't pinned
+pub fn floating_images(pipeline: &Pipeline, pins: &HashMap<String, String>) -> Vec<String> {
+    let aliases: HashSet<&str> = pipeline
+        .resources
+        .iter()
+        .flat_map(|resources| &resources.containers)
+        .map(|resource| resource.container.as_str())
+        .collect();
+    let resources = pipeline
+        .resources
+        .iter()
+        .flat_map(|resources| &resources.containers)
+        .map(|resource| resource.image.as_str());
+    let jobs = pipeline
+        .stages
+        .iter()
+        .flat_map(|stage| &stage.jobs)
+        .flat_map(|job| job.container.iter().chain(job.services.values()))
+        .filter_map(|container| match container {
+            ContainerRef::Image(image) if aliases.contains(image.as_str()) => None,
+            ContainerRef::Image(image) => Some(image.as_str()),
+            ContainerRef::Spec(spec) => Some(spec.image.as_str()),
+        });
+    resources
+        .chain(jobs)
+        .filter(|image| is_floating(image) && !pins.contains_key(*image))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// `roxid.pid=<this process>`
+fn pid_label() -> String {
+    format!("{}={}", PID_LABEL, std::process::id())
+}
+
+/// Split a container's `options:` into docker arguments, honoring quotes
+/// (`--label "team=build tools"`)
+fn split_options(options: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    for c in options.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (_, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    args
+}
+
 /// Generate a simple unique identifier string (16 hex chars)
 ///
 /// Uses nanosecond timestamp XORed with the process ID, plus an atomic counter
 /// to ensure uniqueness even for rapid successive calls.
-fn uuid_v4_simple() -> String {
+pub(crate) fn uuid_v4_simple() -> String {
     use std::sync::atomic::{AtomicU64, Ordering};
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -634,6 +1107,134 @@ mod tests {
         assert!(spec.map_docker_socket.unwrap_or(false));
     }
 
+    #[test]
+    fn test_resources_containers_aliases() {
+        let resources: Vec<ContainerResource> = serde_yaml::from_str(
+            r#"
+- container: builder
+  image: registry.example.com/build:1.4
+  endpoint: my-registry
+  options: --cpus 2 --label "team=build tools"
+  env: { CI: "true" }
+"#,
+        )
+        .unwrap();
+        let runner = ContainerRunner::new().with_resources(&resources);
+
+        let spec = runner
+            .parse_container_ref(&ContainerRef::Image("builder".to_string()))
+            .unwrap();
+        assert_eq!(spec.image, "registry.example.com/build:1.4");
+        assert_eq!(spec.endpoint.as_deref(), Some("my-registry"));
+        assert_eq!(spec.env.get("CI").map(String::as_str), Some("true"));
+        assert_eq!(
+            split_options(spec.options.as_deref().unwrap()),
+            ["--cpus", "2", "--label", "team=build tools"]
+        );
+
+        // Anything else is an image
+        let spec = runner
+            .parse_container_ref(&ContainerRef::Image("ubuntu:22.04".to_string()))
+            .unwrap();
+        assert_eq!(spec.image, "ubuntu:22.04");
+    }
+
+    #[test]
+    fn test_exec_command_moves_into_workspace() {
+        let handle = ContainerHandle {
+            id: "abc".to_string(),
+            name: "roxid-build".to_string(),
+            image: "ubuntu".to_string(),
+            paths: PathMap::default().with_mount("/src/app", CONTAINER_WORKSPACE),
+        };
+        let env = HashMap::from([
+            ("ROXID_TEST_EXEC_VAR".to_string(), "1".to_string()),
+            (
+                "BUILD_SOURCESDIRECTORY".to_string(),
+                "/src/app/checkout".to_string(),
+            ),
+        ]);
+        let command = ContainerRunner::exec_command(
+            &handle,
+            &["sh".to_string(), "-c".to_string(), "make".to_string()],
+            Path::new("/src/app/web"),
+            &env,
+        );
+        assert_eq!(
+            command,
+            [
+                "docker",
+                "exec",
+                "-i",
+                "-w",
+                "/workspace/web",
+                "-e",
+                "ROXID_TEST_EXEC_VAR",
+                "-e",
+                "BUILD_SOURCESDIRECTORY=/workspace/checkout",
+                "roxid-build",
+                "sh",
+                "-c",
+                "make"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pins_and_floating_tags() {
+        let digest = format!("sha256:{}", "a".repeat(64));
+        let pins = HashMap::from([("node:20".to_string(), digest.clone())]);
+        assert_eq!(pin_image("node:20", &pins), format!("node@{}", digest));
+        assert_eq!(pin_image("node:22", &pins), "node:22");
+        assert_eq!(
+            image_name("registry:5000/team/app:1.2"),
+            "registry:5000/team/app"
+        );
+
+        assert!(is_floating("ubuntu"));
+        assert!(is_floating("registry:5000/app:latest"));
+        assert!(is_floating("registry:5000/app"));
+        assert!(!is_floating("ubuntu:22.04"));
+        assert!(!is_floating(&format!("ubuntu@{}", digest)));
+
+        let pipeline = crate::parser::normalize_pipeline(
+            crate::parser::AzureParser::parse(
+                r#"
+resources:
+  containers:
+    - container: build
+      image: mcr.microsoft.com/dotnet/sdk
+jobs:
+  - job: Build
+    container: build
+    services:
+      cache: redis:latest
+      db: postgres:16
+    steps:
+      - script: dotnet build
+  - job: Test
+    container: node:latest
+    steps:
+      - script: npm test
+"#,
+            )
+            .unwrap(),
+        );
+        let pins = HashMap::from([("node:latest".to_string(), digest.clone())]);
+        assert_eq!(
+            floating_images(&pipeline, &pins),
+            ["mcr.microsoft.com/dotnet/sdk", "redis:latest"]
+        );
+
+        let settings = ContainerSettings {
+            pull_policy: Some("never".parse().unwrap()),
+            pins: HashMap::from([("node:20".to_string(), "latest".to_string())]),
+            mounts: BTreeMap::new(),
+        };
+        assert!(settings.validate().is_err());
+        assert!("sometimes".parse::<ImagePullPolicy>().is_err());
+    }
+
     #[test]
     fn test_uuid_v4_simple() {
         let id1 = uuid_v4_simple();
@@ -0,0 +1,172 @@
+// Host Overrides
+// Point hostnames at local stand-ins for steps, containers and compose services
+
+use std::net::{IpAddr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+/// Environment variable listing every override as `host=address` pairs
+pub const HOSTS_ENV: &str = "ROXID_HOSTS";
+
+/// A hostname sent to a local address, e.g. `api.internal` to `127.0.0.1:8080`
+///
+/// Containers get it as `--add-host` (compose services as `extra_hosts`).
+/// Steps on the host can't have their name resolution changed, so they get
+/// the address in the environment instead: `ROXID_HOST_API_INTERNAL` holds
+/// `127.0.0.1:8080`, and `ROXID_HOSTS` lists all overrides.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HostOverride {
+    pub host: String,
+    pub address: IpAddr,
+    pub port: Option<u16>,
+}
+
+impl HostOverride {
+    /// Parse `--add-host` values: `api.internal=127.0.0.1:8080`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (host, target) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("expected host=address, got '{}'", spec))?;
+        Self::new(host, target)
+    }
+
+    /// Send `host` to `target`, an IP address with an optional port
+    pub fn new(host: &str, target: &str) -> Result<Self, String> {
+        let host = host.trim();
+        if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == ':' || c == '=') {
+            return Err(format!("invalid hostname '{}'", host));
+        }
+        let target = target.trim();
+        let (address, port) = match target.parse::<SocketAddr>() {
+            Ok(socket) => (socket.ip(), Some(socket.port())),
+            Err(_) => (
+                target
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse()
+                    .map_err(|_| {
+                        format!(
+                            "invalid address '{}' for '{}' (expected an IP address and optional port)",
+                            target, host
+                        )
+                    })?,
+                None,
+            ),
+        };
+        Ok(Self {
+            host: host.to_string(),
+            address,
+            port,
+        })
+    }
+
+    /// The address and port, as steps see it
+    pub fn target(&self) -> String {
+        match self.port {
+            Some(port) => SocketAddr::new(self.address, port).to_string(),
+            None => self.address.to_string(),
+        }
+    }
+
+    /// `host:address` for `docker --add-host` and compose `extra_hosts`
+    ///
+    /// Inside a container loopback is the container itself, so loopback
+    /// addresses point at the machine running roxid (`host-gateway`).
+    pub fn docker_host(&self) -> String {
+        if self.address.is_loopback() {
+            format!("{}:host-gateway", self.host)
+        } else {
+            format!("{}:{}", self.host, self.address)
+        }
+    }
+
+    /// Name of the variable holding this override's address
+    pub fn env_name(&self) -> String {
+        let name: String = self
+            .host
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("ROXID_HOST_{}", name)
+    }
+}
+
+impl TryFrom<String> for HostOverride {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, String> {
+        Self::parse(&spec)
+    }
+}
+
+impl From<HostOverride> for String {
+    fn from(host: HostOverride) -> String {
+        format!("{}={}", host.host, host.target())
+    }
+}
+
+/// Environment variables describing `hosts` to steps
+pub fn host_env(hosts: &[HostOverride]) -> Vec<(String, String)> {
+    if hosts.is_empty() {
+        return Vec::new();
+    }
+    let mut env: Vec<(String, String)> = hosts
+        .iter()
+        .map(|host| (host.env_name(), host.target()))
+        .collect();
+    let all: Vec<String> = hosts
+        .iter()
+        .map(|host| format!("{}={}", host.host, host.target()))
+        .collect();
+    env.push((HOSTS_ENV.to_string(), all.join(" ")));
+    env
+}
+
+/// `--add-host` arguments for `docker create` and `docker run`
+pub fn add_host_args(hosts: &[HostOverride]) -> Vec<String> {
+    hosts
+        .iter()
+        .flat_map(|host| ["--add-host".to_string(), host.docker_host()])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_overrides() {
+        let api = HostOverride::parse("api.internal=127.0.0.1:8080").unwrap();
+        assert_eq!(api.target(), "127.0.0.1:8080");
+        assert_eq!(api.docker_host(), "api.internal:host-gateway");
+        assert_eq!(api.env_name(), "ROXID_HOST_API_INTERNAL");
+
+        let db = HostOverride::parse("db=10.0.0.5").unwrap();
+        assert_eq!(db.port, None);
+        assert_eq!(db.docker_host(), "db:10.0.0.5");
+
+        let v6 = HostOverride::parse("cache=[::1]:6379").unwrap();
+        assert_eq!(v6.target(), "[::1]:6379");
+
+        assert!(HostOverride::parse("api.internal").is_err());
+        assert!(HostOverride::parse("api.internal=localhost:8080").is_err());
+        assert!(HostOverride::parse("=127.0.0.1").is_err());
+
+        let env = host_env(&[api.clone(), db]);
+        assert!(env.contains(&(
+            "ROXID_HOSTS".to_string(),
+            "api.internal=127.0.0.1:8080 db=10.0.0.5".to_string()
+        )));
+        assert_eq!(
+            add_host_args(&[api]),
+            ["--add-host", "api.internal:host-gateway"]
+        );
+    }
+}
@@ -1,13 +1,23 @@
 // Runners Module
 // Provides step execution runners for different step types
 
+pub mod checkout;
+pub mod compose;
 pub mod container;
+pub mod hosts;
+pub mod path_map;
+pub mod sandbox;
 pub mod shell;
 pub mod task;
 
 // Re-export key types
+pub use checkout::CheckoutRunner;
+pub use compose::ComposeRunner;
 pub use container::ContainerRunner;
-pub use shell::ShellRunner;
+pub use hosts::HostOverride;
+pub use path_map::PathMap;
+pub use sandbox::{Sandbox, SandboxBackend, SandboxProfile};
+pub use shell::{ShellRunner, ShellSpec};
 pub use task::TaskRunner;
 
 use crate::parser::models::{Step, StepResult};
@@ -0,0 +1,187 @@
+// Path Mapping
+// Translates host paths to where they are mounted inside a job's container
+
+use crate::execution::step_summary::STEP_SUMMARY_ENV;
+
+use std::path::Path;
+
+/// Where the workspace is mounted in job containers
+pub const CONTAINER_WORKSPACE: &str = "/workspace";
+
+/// Where the host's temp directory is mounted in job containers
+pub const CONTAINER_TEMP: &str = "/roxid/tmp";
+
+/// Environment variables holding paths, translated for steps in containers
+pub const PATH_VARIABLES: &[&str] = &[
+    "AGENT_BUILDDIRECTORY",
+    "AGENT_TEMPDIRECTORY",
+    "AGENT_WORKFOLDER",
+    "BUILD_ARTIFACTSTAGINGDIRECTORY",
+    "BUILD_BINARIESDIRECTORY",
+    "BUILD_REPOSITORY_LOCALPATH",
+    "BUILD_SOURCESDIRECTORY",
+    "BUILD_STAGINGDIRECTORY",
+    "COMMON_TESTRESULTSDIRECTORY",
+    "PIPELINE_WORKSPACE",
+    "SYSTEM_DEFAULTWORKINGDIRECTORY",
+    STEP_SUMMARY_ENV,
+];
+
+/// A host directory and where it appears inside the container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMount {
+    pub host: String,
+    pub container: String,
+}
+
+/// Host directories mounted into a container, for translating paths
+///
+/// Works on path text rather than the host's path type, so Windows paths
+/// (`C:\work\src`) translate to Linux ones (`/workspace/src`) on any host.
+/// Drive letters and Windows paths compare case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathMap {
+    mounts: Vec<PathMount>,
+}
+
+impl PathMap {
+    /// The workspace at `/workspace` and the temp directory at `/roxid/tmp`
+    pub fn new(workspace: &Path) -> Self {
+        Self::default()
+            .with_mount(&workspace.to_string_lossy(), CONTAINER_WORKSPACE)
+            .with_mount(&std::env::temp_dir().to_string_lossy(), CONTAINER_TEMP)
+    }
+
+    /// Also mount `host` at `container`
+    pub fn with_mount(mut self, host: &str, container: &str) -> Self {
+        self.mounts.push(PathMount {
+            host: host.to_string(),
+            container: container.trim_end_matches('/').to_string(),
+        });
+        self
+    }
+
+    pub fn mounts(&self) -> &[PathMount] {
+        &self.mounts
+    }
+
+    /// `-v host:container` arguments for `docker create`
+    pub fn volume_args(&self) -> Vec<String> {
+        self.mounts
+            .iter()
+            .flat_map(|mount| {
+                [
+                    "-v".to_string(),
+                    format!("{}:{}", mount.host, mount.container),
+                ]
+            })
+            .collect()
+    }
+
+    /// Where `path` is inside the container, if it is under a mount
+    ///
+    /// The most specific mount wins.
+    pub fn translate(&self, path: &str) -> Option<String> {
+        let path = normalize(path);
+        self.mounts
+            .iter()
+            .filter_map(|mount| {
+                let host = normalize(&mount.host);
+                let rest = strip_prefix(&path, &host)?;
+                Some((host.len(), format!("{}{}", mount.container, rest)))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, translated)| translated)
+    }
+
+    /// Path variables in `env` whose values change inside the container
+    pub fn translate_env<'a>(
+        &self,
+        env: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> Vec<(String, String)> {
+        let mut translated: Vec<(String, String)> = env
+            .into_iter()
+            .filter(|(name, _)| PATH_VARIABLES.contains(&name.as_str()))
+            .filter_map(|(name, value)| {
+                let path = self.translate(value)?;
+                (path != *value).then(|| (name.clone(), path))
+            })
+            .collect();
+        translated.sort();
+        translated
+    }
+}
+
+/// Forward slashes, no trailing slash
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// The rest of `path` after `prefix`, on a component boundary
+fn strip_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix == "/" {
+        return path.starts_with('/').then_some(path);
+    }
+    let head = path.get(..prefix.len())?;
+    let matches = if is_windows_path(prefix) {
+        head.eq_ignore_ascii_case(prefix)
+    } else {
+        head == prefix
+    };
+    let rest = &path[prefix.len()..];
+    (matches && (rest.is_empty() || rest.starts_with('/'))).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_paths() {
+        let map = PathMap::default()
+            .with_mount(r"C:\work", CONTAINER_WORKSPACE)
+            .with_mount(r"C:\work\tools", "/tools/")
+            .with_mount("/home/dev/repo", CONTAINER_WORKSPACE);
+
+        assert_eq!(
+            map.translate(r"c:\Work\src\app").as_deref(),
+            Some("/workspace/src/app")
+        );
+        assert_eq!(map.translate(r"C:\work\").as_deref(), Some("/workspace"));
+        assert_eq!(
+            map.translate(r"C:\work\tools\bin").as_deref(),
+            Some("/tools/bin")
+        );
+        assert_eq!(map.translate(r"C:\workspace"), None);
+        assert_eq!(
+            map.translate("/home/dev/repo/target").as_deref(),
+            Some("/workspace/target")
+        );
+        assert_eq!(map.translate("/home/dev/Repo"), None);
+
+        let env = std::collections::HashMap::from([
+            ("BUILD_SOURCESDIRECTORY".to_string(), r"C:\work".to_string()),
+            ("PATH".to_string(), r"C:\work\bin".to_string()),
+        ]);
+        assert_eq!(
+            map.translate_env(&env),
+            [(
+                "BUILD_SOURCESDIRECTORY".to_string(),
+                "/workspace".to_string()
+            )]
+        );
+        assert_eq!(
+            map.volume_args()[..2],
+            ["-v".to_string(), r"C:\work:/workspace".to_string()]
+        );
+    }
+}
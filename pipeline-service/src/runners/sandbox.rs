@@ -0,0 +1,297 @@
+// Sandbox Runner
+// Wraps step commands so they run without network access and can only write to the workspace
+
+use crate::runners::shell::find_executable;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Image used by the container fallback when the profile doesn't name one
+pub const DEFAULT_SANDBOX_IMAGE: &str = "ubuntu:24.04";
+
+/// Restrictions applied to every command a run starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxProfile {
+    /// Keep network access (by default steps only see an unconnected loopback)
+    pub network: bool,
+    /// Paths steps may write to besides the workspace and the temp directory
+    pub writable: Vec<PathBuf>,
+    /// Image for the container fallback
+    pub image: String,
+}
+
+impl Default for SandboxProfile {
+    fn default() -> Self {
+        Self {
+            network: false,
+            writable: Vec::new(),
+            image: DEFAULT_SANDBOX_IMAGE.to_string(),
+        }
+    }
+}
+
+/// How the sandbox is enforced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxBackend {
+    /// Linux user, mount and network namespaces via `unshare`
+    Namespaces,
+    /// A throwaway `docker run` with `--network none` and a read-only root
+    Container,
+}
+
+/// A sandbox profile together with the backend that enforces it here
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    profile: SandboxProfile,
+    backend: SandboxBackend,
+}
+
+/// Runs inside the new namespaces: bind-mounts the writable paths onto
+/// themselves, remounts every other mount read-only, re-enters the working
+/// directory through the new mounts, then execs the command with every
+/// capability dropped, so it can't undo the remounts as namespace root.
+/// Arguments are the writable paths, `--`, and the command. Exits 125 without
+/// running the command if any mount can't be made read-only.
+///
+/// /proc/self/mounts writes spaces and other special characters in mount
+/// points as octal escapes (`\040`); they're rewritten as `\0040` for
+/// `printf %b` to decode.
+const NAMESPACE_SCRIPT: &str = r#"writable=
+while [ "$1" != -- ]; do
+  mount --bind "$1" "$1" || exit 125
+  writable="$writable
+$1"
+  shift
+done
+shift
+awk '{ print $2 }' /proc/self/mounts | sed 's/\\\([0-7][0-7][0-7]\)/\\0\1/g' | while read -r point; do
+  point=$(printf '%b.' "$point")
+  point=${point%.}
+  case "$point" in /proc|/proc/*|/sys|/sys/*|/dev|/dev/*) continue ;; esac
+  printf '%s\n' "$writable" | grep -qxF "$point" && continue
+  mount -o remount,bind,ro "$point" || {
+    echo "roxid sandbox: cannot make $point read-only" >&2
+    exit 125
+  }
+done || exit 125
+cd "$(pwd -P)" || exit 125
+exec setpriv --no-new-privs --inh-caps=-all --bounding-set=-all \
+  -- "$@""#;
+
+impl Sandbox {
+    /// Pick a backend for `profile`: namespaces where the kernel allows
+    /// unprivileged ones, otherwise the container fallback
+    pub fn detect(profile: SandboxProfile) -> Result<Self, String> {
+        let backend = if cfg!(target_os = "linux") && namespaces_available() {
+            SandboxBackend::Namespaces
+        } else if find_executable("docker") {
+            SandboxBackend::Container
+        } else {
+            return Err(
+                "The sandbox needs unprivileged user namespaces (Linux, via 'unshare' \
+                 and 'setpriv') or docker for the container fallback"
+                    .to_string(),
+            );
+        };
+        Ok(Self { profile, backend })
+    }
+
+    /// Use `backend` without probing for it
+    pub fn with_backend(profile: SandboxProfile, backend: SandboxBackend) -> Self {
+        Self { profile, backend }
+    }
+
+    pub fn profile(&self) -> &SandboxProfile {
+        &self.profile
+    }
+
+    pub fn backend(&self) -> SandboxBackend {
+        self.backend
+    }
+
+    /// Paths left writable for a run in `workspace`
+    pub fn writable_paths(&self, workspace: &Path) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        for path in std::iter::once(workspace.to_path_buf())
+            .chain(std::iter::once(std::env::temp_dir()))
+            .chain(self.profile.writable.iter().cloned())
+        {
+            let path = path.canonicalize().unwrap_or(path);
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        // Binding a parent would hide a bind of its child made before it
+        let parents = paths.clone();
+        paths.retain(|path| {
+            !parents
+                .iter()
+                .any(|parent| parent != path && path.starts_with(parent))
+        });
+        paths
+    }
+
+    /// Wrap `command` (program then arguments) so it runs inside the sandbox
+    ///
+    /// `env` is the step's full environment; the container fallback forwards
+    /// the entries that differ from roxid's own environment.
+    pub fn wrap(
+        &self,
+        command: &[String],
+        workspace: &Path,
+        working_dir: &Path,
+        env: &HashMap<String, String>,
+    ) -> Vec<String> {
+        let writable = self.writable_paths(workspace);
+        let mut wrapped: Vec<String> = Vec::new();
+
+        match self.backend {
+            SandboxBackend::Namespaces => {
+                wrapped.extend(["unshare", "--map-root-user", "--mount"].map(String::from));
+                if !self.profile.network {
+                    wrapped.push("--net".to_string());
+                }
+                wrapped.extend(
+                    ["--", "sh", "-c", NAMESPACE_SCRIPT, "roxid-sandbox"].map(String::from),
+                );
+                wrapped.extend(writable.iter().map(|p| p.to_string_lossy().to_string()));
+                wrapped.push("--".to_string());
+            }
+            SandboxBackend::Container => {
+                wrapped.extend(["docker", "run", "--rm", "--read-only"].map(String::from));
+                if !self.profile.network {
+                    wrapped.extend(["--network", "none"].map(String::from));
+                }
+                for path in &writable {
+                    let path = path.to_string_lossy();
+                    wrapped.push("-v".to_string());
+                    wrapped.push(format!("{}:{}", path, path));
+                }
+                wrapped.push("-w".to_string());
+                wrapped.push(working_dir.to_string_lossy().to_string());
+                let mut names: Vec<&String> = env
+                    .iter()
+                    .filter(|(name, value)| std::env::var(name).ok().as_ref() != Some(*value))
+                    .map(|(name, _)| name)
+                    .collect();
+                names.sort();
+                for name in names {
+                    // Docker reads the value from its own environment
+                    wrapped.push("-e".to_string());
+                    wrapped.push(name.clone());
+                }
+                wrapped.push(self.profile.image.clone());
+            }
+        }
+
+        wrapped.extend(command.iter().cloned());
+        wrapped
+    }
+}
+
+/// Whether unprivileged user namespaces with their own mounts and network
+/// work here, and capabilities can be dropped inside them
+fn namespaces_available() -> bool {
+    std::process::Command::new("unshare")
+        .args([
+            "--map-root-user",
+            "--mount",
+            "--net",
+            "setpriv",
+            "--bounding-set=-all",
+            "true",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_wrap() {
+        let sandbox = Sandbox::with_backend(
+            SandboxProfile {
+                writable: vec![PathBuf::from("/opt/cache")],
+                ..Default::default()
+            },
+            SandboxBackend::Container,
+        );
+        let mut env = HashMap::new();
+        env.insert("ROXID_SANDBOX_TEST_VAR".to_string(), "1".to_string());
+        let command = vec!["sh".to_string(), "-c".to_string(), "make".to_string()];
+
+        let wrapped = sandbox.wrap(
+            &command,
+            Path::new("/work/repo"),
+            Path::new("/work/repo/src"),
+            &env,
+        );
+
+        let joined = wrapped.join(" ");
+        assert!(joined
+            .starts_with("docker run --rm --read-only --network none -v /work/repo:/work/repo"));
+        assert!(joined.contains("-v /opt/cache:/opt/cache"));
+        assert!(joined.contains("-w /work/repo/src -e ROXID_SANDBOX_TEST_VAR"));
+        assert!(joined.ends_with("ubuntu:24.04 sh -c make"));
+    }
+
+    #[test]
+    fn test_network_kept_when_allowed() {
+        let sandbox = Sandbox::with_backend(
+            SandboxProfile {
+                network: true,
+                ..Default::default()
+            },
+            SandboxBackend::Namespaces,
+        );
+        let wrapped = sandbox.wrap(
+            &["true".to_string()],
+            Path::new("/work"),
+            Path::new("/work"),
+            &HashMap::new(),
+        );
+        assert_eq!(wrapped[..3], ["unshare", "--map-root-user", "--mount"]);
+        assert!(!wrapped.contains(&"--net".to_string()));
+        assert_eq!(wrapped.last().map(String::as_str), Some("true"));
+    }
+
+    #[test]
+    fn test_namespace_sandbox_cannot_remount_read_write() {
+        if !cfg!(target_os = "linux") || !namespaces_available() {
+            eprintln!("skipped: the sandbox needs unprivileged user namespaces");
+            return;
+        }
+        let workspace = tempfile::TempDir::new().unwrap();
+        let sandbox = Sandbox::with_backend(SandboxProfile::default(), SandboxBackend::Namespaces);
+        let command = [
+            "sh",
+            "-c",
+            "mount -o remount,bind,rw / && touch /roxid-sandbox-probe",
+        ]
+        .map(String::from);
+
+        let wrapped = sandbox.wrap(
+            &command,
+            workspace.path(),
+            workspace.path(),
+            &HashMap::new(),
+        );
+        let status = std::process::Command::new(&wrapped[0])
+            .args(&wrapped[1..])
+            .current_dir(workspace.path())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+
+        let escaped = Path::new("/roxid-sandbox-probe").exists();
+        let _ = std::fs::remove_file("/roxid-sandbox-probe");
+        assert!(!status.success());
+        assert!(!escaped);
+    }
+}
@@ -1,11 +1,14 @@
 // Shell Runner
 // Executes script, bash, pwsh, and powershell steps
 
+use crate::execution::process::StepProcess;
 use crate::parser::models::{StepResult, StepStatus, Value};
+use crate::runners::sandbox::Sandbox;
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
@@ -48,6 +51,165 @@ impl Shell {
     }
 }
 
+/// Placeholder for the script file path in a custom shell command
+const SCRIPT_PLACEHOLDER: &str = "{0}";
+
+static NEXT_SCRIPT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Command line used to run a `script` step with a named shell
+///
+/// Built-in shells pass the script inline. Custom shells are command templates
+/// in the GitHub Actions style (e.g. `perl {0}`), where `{0}` is replaced with
+/// the path of a temporary file holding the script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellSpec {
+    /// Executable to run
+    pub program: String,
+    /// Arguments placed before the script (or containing `{0}`)
+    pub args: Vec<String>,
+}
+
+impl ShellSpec {
+    /// Look up a built-in shell by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (program, args): (&str, &[&str]) = match name {
+            "sh" => ("sh", &["-c"]),
+            "bash" => ("bash", &["-c"]),
+            "zsh" => ("zsh", &["-c"]),
+            "fish" => ("fish", &["-c"]),
+            "nu" | "nushell" => ("nu", &["-c"]),
+            "pwsh" => Shell::Pwsh.get_command(),
+            "powershell" => Shell::PowerShell.get_command(),
+            "cmd" => ("cmd", &["/C"]),
+            "python" => ("python3", &["-c"]),
+            _ => return None,
+        };
+        Some(Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        })
+    }
+
+    /// Parse a custom shell command template such as `perl {0}` or `deno run {0}`
+    pub fn from_template(template: &str) -> Option<Self> {
+        let mut parts = template.split_whitespace().map(String::from);
+        let program = parts.next()?;
+        Some(Self {
+            program,
+            args: parts.collect(),
+        })
+    }
+
+    /// Resolve a step's `shell:` value
+    ///
+    /// Names in `custom` take precedence over built-in shells; anything else
+    /// containing `{0}` is treated as an inline command template.
+    pub fn resolve(name: &str, custom: &HashMap<String, String>) -> Result<Self, String> {
+        if let Some(template) = custom.get(name) {
+            return Self::from_template(template)
+                .ok_or_else(|| format!("Shell '{}' has an empty command", name));
+        }
+        if let Some(spec) = Self::builtin(name) {
+            return Ok(spec);
+        }
+        if name.contains(SCRIPT_PLACEHOLDER) {
+            if let Some(spec) = Self::from_template(name) {
+                return Ok(spec);
+            }
+        }
+        Err(format!(
+            "Unknown shell '{}' (built-in shells: sh, bash, zsh, fish, nu, pwsh, powershell, cmd, python; \
+             custom shells use a command template such as 'perl {{0}}')",
+            name
+        ))
+    }
+
+    /// Whether the shell's executable can be found
+    pub fn is_available(&self) -> bool {
+        find_executable(&self.program)
+    }
+
+    /// Whether the script is passed through a temporary file
+    pub fn uses_script_file(&self) -> bool {
+        self.args.iter().any(|a| a.contains(SCRIPT_PLACEHOLDER))
+    }
+
+    /// Build the full argument list for running `script`
+    ///
+    /// For template shells the script is written to a temporary file that lives
+    /// as long as the returned `ScriptFile`.
+    pub fn command_args(&self, script: &str) -> std::io::Result<(Vec<String>, Option<ScriptFile>)> {
+        if !self.uses_script_file() {
+            let mut args = self.args.clone();
+            args.push(script.to_string());
+            return Ok((args, None));
+        }
+
+        let file = ScriptFile::create(script)?;
+        let path = file.path().to_string_lossy().to_string();
+        let args = self
+            .args
+            .iter()
+            .map(|a| a.replace(SCRIPT_PLACEHOLDER, &path))
+            .collect();
+        Ok((args, Some(file)))
+    }
+}
+
+impl Default for ShellSpec {
+    /// `sh -c`, used by `script` steps without a `shell:` key
+    fn default() -> Self {
+        Self {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string()],
+        }
+    }
+}
+
+/// Temporary file holding a script for a template shell; removed on drop
+#[derive(Debug)]
+pub struct ScriptFile {
+    path: PathBuf,
+}
+
+impl ScriptFile {
+    fn create(script: &str) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "roxid-script-{}-{}",
+            std::process::id(),
+            NEXT_SCRIPT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, script)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScriptFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Check whether `program` is an existing path or an executable on PATH
+pub(crate) fn find_executable(program: &str) -> bool {
+    let candidate = Path::new(program);
+    if candidate.components().count() > 1 {
+        return candidate.is_file();
+    }
+
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| {
+        let path = dir.join(program);
+        path.is_file() || (cfg!(windows) && path.with_extension("exe").is_file())
+    })
+}
+
 /// Configuration for shell execution
 #[derive(Debug, Clone, Default)]
 pub struct ShellConfig {
@@ -83,6 +245,8 @@ pub type OutputCallback = Box<dyn Fn(&str, bool) + Send + Sync>;
 pub struct ShellRunner {
     /// Default shell to use
     default_shell: Shell,
+    /// Restrictions applied to every script, when set
+    sandbox: Option<Sandbox>,
 }
 
 impl ShellRunner {
@@ -90,6 +254,7 @@ impl ShellRunner {
     pub fn new() -> Self {
         Self {
             default_shell: Shell::Default,
+            sandbox: None,
         }
     }
 
@@ -97,9 +262,16 @@ impl ShellRunner {
     pub fn with_default_shell(shell: Shell) -> Self {
         Self {
             default_shell: shell,
+            sandbox: None,
         }
     }
 
+    /// Run every script inside `sandbox`
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
     /// Execute a script using the default shell
     pub async fn run_script(
         &self,
@@ -162,6 +334,31 @@ impl ShellRunner {
             .await
     }
 
+    /// Command running `script`, inside the sandbox when one is set
+    fn command(
+        &self,
+        shell_cmd: &str,
+        shell_args: &[&str],
+        script: &str,
+        workspace: &Path,
+        work_dir: &Path,
+        env: &HashMap<String, String>,
+    ) -> Command {
+        let mut command: Vec<String> = std::iter::once(shell_cmd)
+            .chain(shell_args.iter().copied())
+            .chain([script])
+            .map(str::to_string)
+            .collect();
+        if let Some(sandbox) = &self.sandbox {
+            command = sandbox.wrap(&command, workspace, work_dir, env);
+        }
+
+        let mut cmd = Command::new(&command[0]);
+        cmd.args(&command[1..]);
+        cmd.current_dir(work_dir);
+        cmd
+    }
+
     /// Execute a script with a specific shell
     async fn run_with_shell(
         &self,
@@ -180,16 +377,13 @@ impl ShellRunner {
             .map(Path::new)
             .unwrap_or(working_dir);
 
-        let mut cmd = Command::new(shell_cmd);
-        cmd.args(shell_args);
-        cmd.arg(script);
-        cmd.current_dir(work_dir);
+        let mut cmd = self.command(shell_cmd, shell_args, script, working_dir, work_dir, env);
         cmd.envs(env);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
         // Spawn the process
-        let mut child = match cmd.spawn() {
+        let mut child = match StepProcess::spawn(&mut cmd) {
             Ok(child) => child,
             Err(e) => {
                 return ShellOutput {
@@ -202,7 +396,7 @@ impl ShellRunner {
             }
         };
 
-        let stdout = match child.stdout.take() {
+        let stdout = match child.take_stdout() {
             Some(s) => s,
             None => {
                 return ShellOutput {
@@ -214,7 +408,7 @@ impl ShellRunner {
                 };
             }
         };
-        let stderr = match child.stderr.take() {
+        let stderr = match child.take_stderr() {
             Some(s) => s,
             None => {
                 return ShellOutput {
@@ -260,8 +454,8 @@ impl ShellRunner {
             match tokio::time::timeout(timeout, child.wait()).await {
                 Ok(result) => result,
                 Err(_) => {
-                    // Timeout - kill the process
-                    let _ = child.kill().await;
+                    // Timeout - stop the process and everything it started, so the pipes close
+                    drop(child);
                     return ShellOutput {
                         stdout: stdout_handle.await.unwrap_or_default(),
                         stderr: format!("Process timed out after {:?}", timeout),
@@ -329,15 +523,12 @@ impl ShellRunner {
             .map(Path::new)
             .unwrap_or(working_dir);
 
-        let mut cmd = Command::new(shell_cmd);
-        cmd.args(shell_args);
-        cmd.arg(script);
-        cmd.current_dir(work_dir);
+        let mut cmd = self.command(shell_cmd, shell_args, script, working_dir, work_dir, env);
         cmd.envs(env);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
-        let mut child = match cmd.spawn() {
+        let mut child = match StepProcess::spawn(&mut cmd) {
             Ok(child) => child,
             Err(e) => {
                 return ShellOutput {
@@ -350,7 +541,7 @@ impl ShellRunner {
             }
         };
 
-        let stdout = match child.stdout.take() {
+        let stdout = match child.take_stdout() {
             Some(s) => s,
             None => {
                 return ShellOutput {
@@ -362,7 +553,7 @@ impl ShellRunner {
                 };
             }
         };
-        let stderr = match child.stderr.take() {
+        let stderr = match child.take_stderr() {
             Some(s) => s,
             None => {
                 return ShellOutput {
@@ -414,7 +605,7 @@ impl ShellRunner {
             match tokio::time::timeout(timeout, child.wait()).await {
                 Ok(result) => result,
                 Err(_) => {
-                    let _ = child.kill().await;
+                    drop(child);
                     return ShellOutput {
                         stdout: stdout_handle.await.unwrap_or_default(),
                         stderr: format!("Process timed out after {:?}", timeout),
@@ -473,6 +664,12 @@ impl ShellRunner {
             duration,
             exit_code: output.exit_code,
             outputs: output.outputs,
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
         }
     }
 }
@@ -680,6 +877,76 @@ mod tests {
         assert!(output.stderr.contains("error"));
     }
 
+    #[tokio::test]
+    async fn test_shell_runner_timeout_stops_background_processes() {
+        // Descendants are only tracked down on Unix
+        if !cfg!(unix) {
+            return;
+        }
+        let runner = ShellRunner::new();
+        let env = HashMap::new();
+        let working_dir = std::env::current_dir().unwrap();
+        let config = ShellConfig {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+
+        // The background sleep holds stdout open until it's killed too
+        let started = std::time::Instant::now();
+        let output = runner
+            .run_script("sleep 30 & sleep 30", &env, &working_dir, &config)
+            .await;
+
+        assert_eq!(output.exit_code, None);
+        assert!(output.stderr.contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_shell_spec_builtin_and_custom() {
+        let custom = HashMap::from([("perl".to_string(), "perl -w {0}".to_string())]);
+
+        let fish = ShellSpec::resolve("fish", &custom).unwrap();
+        assert_eq!(fish.program, "fish");
+        assert_eq!(fish.args, vec!["-c"]);
+
+        let perl = ShellSpec::resolve("perl", &custom).unwrap();
+        assert_eq!(perl.program, "perl");
+        assert!(perl.uses_script_file());
+
+        let inline = ShellSpec::resolve("ruby {0}", &custom).unwrap();
+        assert_eq!(inline.program, "ruby");
+
+        assert!(ShellSpec::resolve("tcsh", &custom).is_err());
+    }
+
+    #[test]
+    fn test_shell_spec_command_args() {
+        let sh = ShellSpec::builtin("sh").unwrap();
+        let (args, file) = sh.command_args("echo hi").unwrap();
+        assert_eq!(args, vec!["-c", "echo hi"]);
+        assert!(file.is_none());
+
+        let template = ShellSpec::from_template("sh -e {0}").unwrap();
+        let (args, file) = template.command_args("echo hi").unwrap();
+        let file = file.unwrap();
+        assert_eq!(args[0], "-e");
+        assert_eq!(args[1], file.path().to_string_lossy());
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "echo hi");
+
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_shell_spec_availability() {
+        assert!(ShellSpec::builtin("sh").unwrap().is_available());
+        assert!(!ShellSpec::from_template("roxid-no-such-shell {0}")
+            .unwrap()
+            .is_available());
+    }
+
     #[test]
     fn test_parse_logging_commands_setvariable() {
         let output = r#"
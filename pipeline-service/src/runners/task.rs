@@ -2,6 +2,7 @@
 // Executes Azure DevOps tasks (Bash@3, PowerShell@2, etc.)
 
 use crate::parser::models::{StepResult, StepStatus};
+use crate::runners::sandbox::Sandbox;
 use crate::runners::shell::{ShellConfig, ShellRunner};
 use crate::tasks::cache::{CachedTask, TaskCache, TaskCacheError};
 
@@ -74,6 +75,12 @@ impl TaskRunner {
         self
     }
 
+    /// Run task scripts inside `sandbox`
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.shell_runner = self.shell_runner.with_sandbox(sandbox);
+        self
+    }
+
     /// Get the task cache
     pub fn cache(&self) -> &TaskCache {
         &self.cache
@@ -119,6 +126,12 @@ impl TaskRunner {
                 duration,
                 exit_code: None,
                 outputs: HashMap::new(),
+                substitutions: Vec::new(),
+                summary: None,
+                unsupported: Vec::new(),
+                started_at: None,
+                finished_at: None,
+                env: Vec::new(),
             }),
         }
     }
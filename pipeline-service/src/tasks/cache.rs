@@ -1,13 +1,14 @@
 // Task Cache
 // Downloads and caches Azure DevOps tasks from the marketplace
 
+use crate::parser::models::{collect_deployment_steps, Pipeline, StepAction};
 use crate::tasks::manifest::{TaskManifest, TaskManifestError};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 use thiserror::Error;
 use tokio::sync::RwLock;
 
@@ -34,8 +35,14 @@ pub enum TaskCacheError {
 
     #[error("Archive error: {0}")]
     ArchiveError(String),
+
+    #[error("Invalid pin file: {0}")]
+    PinFileError(String),
 }
 
+/// File in the cache directory that maps `Name@major` to a pinned full version
+const PIN_FILE: &str = "pins.json";
+
 /// Configuration for the task cache
 #[derive(Debug, Clone)]
 pub struct TaskCacheConfig {
@@ -104,6 +111,8 @@ pub struct TaskCache {
     config: TaskCacheConfig,
     /// In-memory cache of loaded manifests
     cache: Arc<RwLock<HashMap<String, CachedTask>>>,
+    /// Pinned versions, read from the pin file on first use
+    pins: Mutex<Option<BTreeMap<String, String>>>,
 }
 
 impl TaskCache {
@@ -117,6 +126,7 @@ impl TaskCache {
         Self {
             config,
             cache: Arc::new(RwLock::new(HashMap::new())),
+            pins: Mutex::new(None),
         }
     }
 
@@ -164,6 +174,7 @@ impl TaskCache {
         name: &str,
         version: &str,
     ) -> Result<CachedTask, TaskCacheError> {
+        let version = &self.resolve_version(name, version);
         let cache_key = format!("{}@{}", name, version);
 
         // Check in-memory cache first
@@ -367,11 +378,101 @@ impl TaskCache {
         Ok(tasks)
     }
 
-    /// Clear all cached tasks
+    /// Clear all cached tasks (pins are kept)
     pub fn clear_cache(&self) -> io::Result<()> {
-        if self.config.cache_dir.exists() {
-            fs::remove_dir_all(&self.config.cache_dir)?;
+        if !self.config.cache_dir.exists() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(&self.config.cache_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a task is available in the cache without downloading (pins applied)
+    pub fn is_cached(&self, name: &str, version: &str) -> bool {
+        let version = self.resolve_version(name, version);
+        self.task_path(name, &version).join("task.json").exists()
+    }
+
+    // =========================================================================
+    // Version Pinning
+    // =========================================================================
+
+    /// Pinned versions, keyed by `Name@major`
+    pub fn pins(&self) -> Result<BTreeMap<String, String>, TaskCacheError> {
+        Ok(self.loaded_pins()?.clone().unwrap_or_default())
+    }
+
+    /// The pins, reading the pin file the first time
+    fn loaded_pins(
+        &self,
+    ) -> Result<MutexGuard<'_, Option<BTreeMap<String, String>>>, TaskCacheError> {
+        let mut pins = self.pins.lock().unwrap_or_else(|e| e.into_inner());
+        if pins.is_none() {
+            let path = self.config.cache_dir.join(PIN_FILE);
+            *pins = Some(if path.exists() {
+                let content = fs::read_to_string(&path)?;
+                serde_json::from_str(&content)
+                    .map_err(|e| TaskCacheError::PinFileError(e.to_string()))?
+            } else {
+                BTreeMap::new()
+            });
+        }
+        Ok(pins)
+    }
+
+    /// Pin the major version of a task to a specific version
+    ///
+    /// `pin("Bash", "3.231.0")` makes every `Bash@3` reference use 3.231.0.
+    /// Returns the `Name@major` key that was pinned.
+    pub fn pin(&self, name: &str, version: &str) -> Result<String, TaskCacheError> {
+        let major = version.split('.').next().unwrap_or(version);
+        if major.is_empty() || version == major {
+            return Err(TaskCacheError::InvalidTaskReference(format!(
+                "{}@{} (pin a full version such as {}@{}.0.0)",
+                name, version, name, major
+            )));
         }
+
+        let key = format!("{}@{}", name, major);
+        let mut pins = self.pins()?;
+        pins.insert(key.clone(), version.to_string());
+        self.write_pins(&pins)?;
+        Ok(key)
+    }
+
+    /// Remove the pin for `Name@major`, returning the version it was pinned to
+    pub fn unpin(&self, name: &str, major: &str) -> Result<Option<String>, TaskCacheError> {
+        let mut pins = self.pins()?;
+        let removed = pins.remove(&format!("{}@{}", name, major));
+        if removed.is_some() {
+            self.write_pins(&pins)?;
+        }
+        Ok(removed)
+    }
+
+    /// Apply a pin to a task version: `Bash`, `3` → `3.231.0` when `Bash@3` is pinned
+    pub fn resolve_version(&self, name: &str, version: &str) -> String {
+        self.loaded_pins()
+            .ok()
+            .and_then(|pins| {
+                pins.as_ref()?
+                    .get(&format!("{}@{}", name, version))
+                    .cloned()
+            })
+            .unwrap_or_else(|| version.to_string())
+    }
+
+    fn write_pins(&self, pins: &BTreeMap<String, String>) -> Result<(), TaskCacheError> {
+        fs::create_dir_all(&self.config.cache_dir)?;
+        let json = serde_json::to_string_pretty(pins)
+            .map_err(|e| TaskCacheError::PinFileError(e.to_string()))?;
+        fs::write(self.config.cache_dir.join(PIN_FILE), json)?;
+        *self.pins.lock().unwrap_or_else(|e| e.into_inner()) = Some(pins.clone());
         Ok(())
     }
 
@@ -391,6 +492,20 @@ impl Default for TaskCache {
     }
 }
 
+/// Task references (e.g. `Bash@3`) used by a pipeline's steps, including deployment hooks
+pub fn required_tasks(pipeline: &Pipeline) -> BTreeSet<String> {
+    let mut tasks = BTreeSet::new();
+    for job in pipeline.stages.iter().flat_map(|s| &s.jobs) {
+        let deployment_steps = collect_deployment_steps(job);
+        for step in job.steps.iter().chain(&deployment_steps) {
+            if let StepAction::Task(task_step) = &step.action {
+                tasks.insert(task_step.task.clone());
+            }
+        }
+    }
+    tasks
+}
+
 /// Create a stub manifest for built-in Azure DevOps tasks
 fn create_builtin_task_stub(name: &str, version: &str) -> Option<TaskManifest> {
     // Parse major version
@@ -662,6 +777,62 @@ mod tests {
         assert!(manifest.is_none());
     }
 
+    #[tokio::test]
+    async fn test_pinned_version_is_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = TaskCache::with_cache_dir(dir.path());
+
+        assert!(cache.pin("Bash", "3").is_err());
+        assert_eq!(cache.pin("Bash", "3.231.0").unwrap(), "Bash@3");
+        assert_eq!(cache.resolve_version("Bash", "3"), "3.231.0");
+        assert!(!cache.is_cached("Bash", "3"));
+
+        let task = cache.get_task("Bash@3").await.unwrap();
+        assert_eq!(task.version, "3.231.0");
+        assert!(cache.is_cached("Bash", "3"));
+
+        cache.clear_cache().unwrap();
+        assert!(!cache.is_cached("Bash", "3"));
+        assert_eq!(cache.pins().unwrap().len(), 1);
+
+        assert_eq!(
+            cache.unpin("Bash", "3").unwrap().as_deref(),
+            Some("3.231.0")
+        );
+        assert_eq!(cache.resolve_version("Bash", "3"), "3");
+    }
+
+    #[test]
+    fn test_pin_file_is_read_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(PIN_FILE), r#"{"Bash@3": "3.231.0"}"#).unwrap();
+        let cache = TaskCache::with_cache_dir(dir.path());
+        assert_eq!(cache.resolve_version("Bash", "3"), "3.231.0");
+
+        std::fs::remove_file(dir.path().join(PIN_FILE)).unwrap();
+        assert_eq!(cache.resolve_version("Bash", "3"), "3.231.0");
+    }
+
+    #[test]
+    fn test_required_tasks() {
+        let pipeline = crate::parser::azure::AzureParser::parse(
+            r#"
+steps:
+  - task: Bash@3
+    inputs:
+      targetType: inline
+  - script: echo hi
+  - task: CmdLine@2
+  - task: Bash@3
+"#,
+        )
+        .unwrap();
+        let pipeline = crate::parser::normalize_pipeline(pipeline);
+
+        let tasks: Vec<_> = required_tasks(&pipeline).into_iter().collect();
+        assert_eq!(tasks, vec!["Bash@3", "CmdLine@2"]);
+    }
+
     #[tokio::test]
     async fn test_task_cache_config() {
         let cache = TaskCache::new();
@@ -3,7 +3,9 @@
 
 pub mod cache;
 pub mod manifest;
+pub mod service_connection;
 
 // Re-export key types
-pub use cache::{TaskCache, TaskCacheConfig, TaskCacheError};
+pub use cache::{required_tasks, TaskCache, TaskCacheConfig, TaskCacheError};
 pub use manifest::{TaskExecution, TaskInput, TaskManifest, TaskManifestError};
+pub use service_connection::{ServiceConnection, ServiceConnections};
@@ -0,0 +1,215 @@
+// Service Connections
+// Local stand-ins for Azure DevOps service connections, passed to tasks the way the agent does
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+
+/// Prefix for values read from roxid's environment instead of the config file
+const ENV_PREFIX: &str = "env:";
+
+/// A service connection defined in `.roxid.toml`
+///
+/// ```toml
+/// [service-connections.my-subscription]
+/// type = "AzureRM"
+/// url = "https://management.azure.com/"
+/// scheme = "ServicePrincipal"
+/// auth = { tenantid = "...", serviceprincipalid = "...", serviceprincipalkey = "env:AZURE_CLIENT_SECRET" }
+/// data = { subscriptionId = "..." }
+/// env = { AZURE_CLIENT_SECRET = "env:AZURE_CLIENT_SECRET" }
+/// ```
+///
+/// Any value can be `env:NAME` to read it from the environment roxid runs in.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceConnection {
+    /// Connection type, e.g. `AzureRM` or `dockerregistry`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: Option<String>,
+    /// Authorization scheme, e.g. `ServicePrincipal` or `UsernamePassword`
+    pub scheme: Option<String>,
+    /// Authorization parameters; their values are masked in step output
+    #[serde(default)]
+    pub auth: BTreeMap<String, String>,
+    /// Other connection data, e.g. `subscriptionId`
+    #[serde(default)]
+    pub data: BTreeMap<String, String>,
+    /// Extra environment variables for tasks that use the connection
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Service connections by name, with `env:` values resolved
+#[derive(Debug, Clone, Default)]
+pub struct ServiceConnections {
+    connections: BTreeMap<String, ServiceConnection>,
+}
+
+impl ServiceConnections {
+    /// Resolve `env:` values; fails if a referenced variable isn't set
+    pub fn resolve(definitions: &HashMap<String, ServiceConnection>) -> Result<Self, String> {
+        let mut connections = BTreeMap::new();
+        for (name, definition) in definitions {
+            let resolve = |values: &BTreeMap<String, String>| {
+                values
+                    .iter()
+                    .map(|(key, value)| {
+                        resolve_value(value)
+                            .map(|value| (key.clone(), value))
+                            .map_err(|e| format!("service connection '{}': {}", name, e))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, _>>()
+            };
+            let url = definition
+                .url
+                .as_deref()
+                .map(resolve_value)
+                .transpose()
+                .map_err(|e| format!("service connection '{}': {}", name, e))?;
+            connections.insert(
+                name.clone(),
+                ServiceConnection {
+                    kind: definition.kind.clone(),
+                    url,
+                    scheme: definition.scheme.clone(),
+                    auth: resolve(&definition.auth)?,
+                    data: resolve(&definition.data)?,
+                    env: resolve(&definition.env)?,
+                },
+            );
+        }
+        Ok(Self { connections })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Look up a connection by name, ignoring case like Azure DevOps
+    pub fn get(&self, name: &str) -> Option<(&str, &ServiceConnection)> {
+        self.connections
+            .iter()
+            .find(|(candidate, _)| candidate.eq_ignore_ascii_case(name.trim()))
+            .map(|(name, connection)| (name.as_str(), connection))
+    }
+
+    /// Environment for a task whose inputs name connections
+    ///
+    /// Sets the `ENDPOINT_URL_<name>`, `ENDPOINT_AUTH_<name>`,
+    /// `ENDPOINT_AUTH_SCHEME_<name>`, `ENDPOINT_AUTH_PARAMETER_<name>_<KEY>` and
+    /// `ENDPOINT_DATA_<name>_<KEY>` variables the task library reads, using the
+    /// connection name as its ID, plus the connection's own `env`.
+    pub fn task_env(&self, inputs: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+        for value in inputs.values() {
+            let Some((name, connection)) = self.get(value) else {
+                continue;
+            };
+            let id = variable_key(value.trim());
+            let scheme = connection.scheme.clone().unwrap_or_default();
+
+            if let Some(url) = &connection.url {
+                env.insert(format!("ENDPOINT_URL_{}", id), url.clone());
+            }
+            let auth = serde_json::json!({
+                "parameters": connection.auth,
+                "scheme": scheme,
+            });
+            env.insert(format!("ENDPOINT_AUTH_{}", id), auth.to_string());
+            env.insert(format!("ENDPOINT_AUTH_SCHEME_{}", id), scheme);
+            for (key, value) in &connection.auth {
+                env.insert(
+                    format!("ENDPOINT_AUTH_PARAMETER_{}_{}", id, variable_key(key)),
+                    value.clone(),
+                );
+            }
+            for (key, value) in &connection.data {
+                env.insert(
+                    format!("ENDPOINT_DATA_{}_{}", id, variable_key(key)),
+                    value.clone(),
+                );
+            }
+            env.insert(format!("ENDPOINT_TYPE_{}", id), connection.kind.clone());
+            env.insert(format!("ENDPOINT_NAME_{}", id), name.to_string());
+            env.extend(connection.env.clone());
+        }
+        env
+    }
+
+    /// Authorization values to mask in output
+    pub fn secrets(&self) -> Vec<String> {
+        self.connections
+            .values()
+            .flat_map(|connection| connection.auth.values())
+            .filter(|value| !value.is_empty())
+            .cloned()
+            .collect()
+    }
+}
+
+fn resolve_value(value: &str) -> Result<String, String> {
+    match value.strip_prefix(ENV_PREFIX) {
+        Some(name) => {
+            std::env::var(name).map_err(|_| format!("environment variable '{}' is not set", name))
+        }
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Variable names as the task library looks them up: `.` and spaces become `_`, upper case
+fn variable_key(name: &str) -> String {
+    name.replace(['.', ' '], "_").to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_env_for_referenced_connection() {
+        std::env::set_var("ROXID_TEST_SP_KEY", "s3cret");
+        let definitions: HashMap<String, ServiceConnection> = toml::from_str(
+            r#"
+[my-sub]
+type = "AzureRM"
+url = "https://management.azure.com/"
+scheme = "ServicePrincipal"
+auth = { serviceprincipalid = "app", serviceprincipalkey = "env:ROXID_TEST_SP_KEY" }
+data = { subscriptionId = "sub-1" }
+env = { ARM_CLIENT_SECRET = "env:ROXID_TEST_SP_KEY" }
+"#,
+        )
+        .unwrap();
+        let connections = ServiceConnections::resolve(&definitions).unwrap();
+
+        let inputs = HashMap::from([
+            ("azureSubscription".to_string(), "My-Sub".to_string()),
+            ("scriptType".to_string(), "bash".to_string()),
+        ]);
+        let env = connections.task_env(&inputs);
+        assert_eq!(env["ENDPOINT_URL_MY-SUB"], "https://management.azure.com/");
+        assert_eq!(
+            env["ENDPOINT_AUTH_PARAMETER_MY-SUB_SERVICEPRINCIPALKEY"],
+            "s3cret"
+        );
+        assert_eq!(env["ENDPOINT_DATA_MY-SUB_SUBSCRIPTIONID"], "sub-1");
+        assert_eq!(env["ARM_CLIENT_SECRET"], "s3cret");
+        let auth: serde_json::Value = serde_json::from_str(&env["ENDPOINT_AUTH_MY-SUB"]).unwrap();
+        assert_eq!(auth["scheme"], "ServicePrincipal");
+        assert!(connections.secrets().contains(&"s3cret".to_string()));
+
+        assert!(connections
+            .task_env(&HashMap::from([("x".to_string(), "other".to_string())]))
+            .is_empty());
+
+        let mut missing = definitions.clone();
+        missing.get_mut("my-sub").unwrap().auth.insert(
+            "token".to_string(),
+            "env:ROXID_TEST_UNSET_VARIABLE".to_string(),
+        );
+        let err = ServiceConnections::resolve(&missing).unwrap_err();
+        assert!(err.contains("ROXID_TEST_UNSET_VARIABLE"));
+    }
+}
@@ -2,10 +2,14 @@
 // Evaluates test assertions against pipeline execution results
 
 use crate::execution::executor::ExecutionResult;
+use crate::execution::run_id::correlation_id;
 use crate::parser::models::{
-    JobResult, JobStatus, StageResult, StageStatus, StepResult, StepStatus, Value,
+    collect_deployment_steps, Job, JobResult, JobStatus, Pipeline, StageResult, StageStatus, Step,
+    StepAction, StepResult, StepStatus, Value,
 };
+use crate::parser::normalize_pipeline;
 
+use std::borrow::Cow;
 use std::fmt;
 
 // =============================================================================
@@ -29,6 +33,13 @@ pub enum Assertion {
     StepSkipped {
         step: String,
     },
+    StepCanceled {
+        step: String,
+    },
+    /// No result at all, unlike a skipped step
+    StepNotExecuted {
+        step: String,
+    },
 
     // Job status assertions
     JobSucceeded {
@@ -40,6 +51,12 @@ pub enum Assertion {
     JobSkipped {
         job: String,
     },
+    JobCanceled {
+        job: String,
+    },
+    JobNotExecuted {
+        job: String,
+    },
 
     // Stage status assertions
     StageSucceeded {
@@ -51,6 +68,9 @@ pub enum Assertion {
     StageSkipped {
         stage: String,
     },
+    StageCanceled {
+        stage: String,
+    },
 
     // Output assertions
     StepOutputEquals {
@@ -83,6 +103,60 @@ pub enum Assertion {
         name: String,
         pattern: String,
     },
+
+    // Substitution assertions
+    StepSubstitutedVariables {
+        step: String,
+        variables: Vec<String>,
+    },
+
+    // Environment assertions
+    StepEnvContains {
+        step: String,
+        name: String,
+        /// If None, the variable only has to be set
+        value: Option<Value>,
+    },
+
+    // Structure assertions, on the resolved pipeline or template
+    ResolvedStepCount {
+        count: usize,
+    },
+    ResolvedJobCount {
+        count: usize,
+    },
+    ResolvedStageCount {
+        count: usize,
+    },
+    StepExists {
+        step: String,
+    },
+    JobExists {
+        job: String,
+    },
+    StageExists {
+        stage: String,
+    },
+    StepScriptContains {
+        step: String,
+        pattern: String,
+    },
+}
+
+impl Assertion {
+    /// Checked against the resolved pipeline rather than execution results
+    pub fn is_structural(&self) -> bool {
+        matches!(
+            self,
+            Assertion::ResolvedStepCount { .. }
+                | Assertion::ResolvedJobCount { .. }
+                | Assertion::ResolvedStageCount { .. }
+                | Assertion::StepExists { .. }
+                | Assertion::JobExists { .. }
+                | Assertion::StageExists { .. }
+                | Assertion::StepScriptContains { .. }
+        )
+    }
 }
 
 /// Result of evaluating a single assertion
@@ -96,15 +170,31 @@ pub struct AssertionResult {
     pub message: String,
     /// Details about the failure (if any)
     pub failure_detail: Option<String>,
+    /// Name of the assertion group this belongs to
+    pub group: Option<String>,
+    /// A failure is reported but doesn't fail the test
+    pub soft: bool,
 }
 
 impl AssertionResult {
+    /// Failed in a way that fails the test
+    pub fn is_hard_failure(&self) -> bool {
+        !self.passed && !self.soft
+    }
+
+    /// Failed in a soft group
+    pub fn is_soft_failure(&self) -> bool {
+        !self.passed && self.soft
+    }
+
     fn pass(assertion: &str, message: impl Into<String>) -> Self {
         Self {
             assertion: assertion.to_string(),
             passed: true,
             message: message.into(),
             failure_detail: None,
+            group: None,
+            soft: false,
         }
     }
 
@@ -114,6 +204,8 @@ impl AssertionResult {
             passed: false,
             message: message.into(),
             failure_detail: Some(detail.into()),
+            group: None,
+            soft: false,
         }
     }
 }
@@ -126,12 +218,17 @@ impl fmt::Display for Assertion {
             Assertion::StepSucceeded { step } => write!(f, "step_succeeded({})", step),
             Assertion::StepFailed { step } => write!(f, "step_failed({})", step),
             Assertion::StepSkipped { step } => write!(f, "step_skipped({})", step),
+            Assertion::StepCanceled { step } => write!(f, "step_canceled({})", step),
+            Assertion::StepNotExecuted { step } => write!(f, "step_not_executed({})", step),
             Assertion::JobSucceeded { job } => write!(f, "job_succeeded({})", job),
             Assertion::JobFailed { job } => write!(f, "job_failed({})", job),
             Assertion::JobSkipped { job } => write!(f, "job_skipped({})", job),
+            Assertion::JobCanceled { job } => write!(f, "job_canceled({})", job),
+            Assertion::JobNotExecuted { job } => write!(f, "job_not_executed({})", job),
             Assertion::StageSucceeded { stage } => write!(f, "stage_succeeded({})", stage),
             Assertion::StageFailed { stage } => write!(f, "stage_failed({})", stage),
             Assertion::StageSkipped { stage } => write!(f, "stage_skipped({})", stage),
+            Assertion::StageCanceled { stage } => write!(f, "stage_canceled({})", stage),
             Assertion::StepOutputEquals {
                 step,
                 output,
@@ -158,6 +255,35 @@ impl fmt::Display for Assertion {
             Assertion::VariableContains { name, pattern } => {
                 write!(f, "variable_contains({}, \"{}\")", name, pattern)
             }
+            Assertion::StepSubstitutedVariables { step, variables } => {
+                write!(
+                    f,
+                    "step_substituted_variables({}, [{}])",
+                    step,
+                    variables.join(", ")
+                )
+            }
+            Assertion::StepEnvContains { step, name, value } => match value {
+                Some(value) => write!(
+                    f,
+                    "step_env_contains({}, {}={})",
+                    step,
+                    name,
+                    value.as_string()
+                ),
+                None => write!(f, "step_env_contains({}, {})", step, name),
+            },
+            Assertion::ResolvedStepCount { count } => write!(f, "resolved_step_count({})", count),
+            Assertion::ResolvedJobCount { count } => write!(f, "resolved_job_count({})", count),
+            Assertion::ResolvedStageCount { count } => {
+                write!(f, "resolved_stage_count({})", count)
+            }
+            Assertion::StepExists { step } => write!(f, "step_exists({})", step),
+            Assertion::JobExists { job } => write!(f, "job_exists({})", job),
+            Assertion::StageExists { stage } => write!(f, "stage_exists({})", stage),
+            Assertion::StepScriptContains { step, pattern } => {
+                write!(f, "step_script_contains({}, \"{}\")", step, pattern)
+            }
         }
     }
 }
@@ -167,29 +293,134 @@ impl fmt::Display for Assertion {
 // =============================================================================
 
 /// Evaluates assertions against execution results
+///
+/// Steps are found by name, display name, or `Stage/Job/<step number>` (the
+/// ID events use); jobs by name, display name, or `Stage/Job`. A name shared
+/// by steps or jobs in different places must be qualified, either that way
+/// or by evaluating [`within`](Self::within) a stage or job.
 pub struct AssertionEvaluator<'a> {
     result: &'a ExecutionResult,
     /// Flattened step results with their indices for ordering
     step_index: Vec<StepInfo>,
+    /// Only look for steps and jobs in this stage
+    in_stage: Option<String>,
+    /// Only look for steps in this job
+    in_job: Option<String>,
+    /// The resolved pipeline, for structure assertions
+    pipeline: Option<Pipeline>,
 }
 
-/// Flattened step info for lookup
+/// Flattened step info for lookup, holding the step's result, or for
+/// structure assertions the resolved step itself
 #[derive(Debug)]
-struct StepInfo {
+struct StepInfo<T = StepResult> {
     name: Option<String>,
     display_name: Option<String>,
-    #[allow(dead_code)]
     stage_name: String,
+    stage_display_name: Option<String>,
     job_name: String,
-    index: usize, // Global execution order
-    result: StepResult,
+    job_display_name: Option<String>,
+    step_index: usize, // Position within the job
+    index: usize,      // Global execution order
+    result: T,
+}
+
+impl<T> StepInfo<T> {
+    /// `Stage/Job/<step number>`, the same for each matrix instance
+    fn id(&self) -> String {
+        correlation_id(
+            &self.stage_name,
+            Some(JobResult::split_name(&self.job_name).0),
+            Some(self.step_index),
+        )
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        self.name.as_deref() == Some(name) || self.display_name.as_deref() == Some(name)
+    }
+}
+
+/// Why a step or job reference doesn't pick out one result
+enum LookupError {
+    NotFound,
+    /// IDs of the places the name matched
+    Ambiguous(Vec<String>),
+}
+
+/// The single match, treating matches with the same ID (matrix instances of
+/// one step) as one
+fn unique<T>(matches: Vec<T>, id: impl Fn(&T) -> String) -> Result<T, LookupError> {
+    let mut ids: Vec<String> = matches.iter().map(&id).collect();
+    ids.sort();
+    ids.dedup();
+    if ids.len() > 1 {
+        return Err(LookupError::Ambiguous(ids));
+    }
+    matches.into_iter().next().ok_or(LookupError::NotFound)
+}
+
+fn named(name: &str, display_name: Option<&str>, wanted: &str) -> bool {
+    name == wanted || display_name == Some(wanted)
+}
+
+/// A job named `wanted`, or an instance of matrix job `wanted`
+fn job_named(name: &str, display_name: Option<&str>, wanted: &str) -> bool {
+    named(name, display_name, wanted) || JobResult::split_name(name).0 == wanted
+}
+
+/// The script a step runs: its `script:`-style body, or a task's inline script
+fn step_script(step: &Step) -> Option<String> {
+    match &step.action {
+        StepAction::Script(s) => Some(s.script.clone()),
+        StepAction::Bash(s) => Some(s.bash.clone()),
+        StepAction::Pwsh(s) => Some(s.pwsh.clone()),
+        StepAction::PowerShell(s) => Some(s.powershell.clone()),
+        StepAction::Task(task) => task
+            .inputs
+            .iter()
+            .find(|(key, _)| {
+                matches!(
+                    key.to_lowercase().as_str(),
+                    "script" | "inline" | "inlinescript"
+                )
+            })
+            .map(|(_, script)| script.clone()),
+        _ => None,
+    }
 }
 
 impl<'a> AssertionEvaluator<'a> {
     /// Create a new evaluator from execution results
     pub fn new(result: &'a ExecutionResult) -> Self {
         let step_index = Self::build_step_index(result);
-        Self { result, step_index }
+        Self {
+            result,
+            step_index,
+            in_stage: None,
+            in_job: None,
+            pipeline: None,
+        }
+    }
+
+    /// Check structure assertions against `pipeline`, as resolved from
+    /// templates (a pipeline of bare steps or jobs is given its default stage)
+    pub fn with_pipeline(mut self, pipeline: &Pipeline) -> Self {
+        self.pipeline = Some(normalize_pipeline(pipeline.clone()));
+        self
+    }
+
+    /// An evaluator that only looks for steps and jobs in the given stage
+    /// and/or job (by name or display name); unset scopes are inherited
+    pub fn within(&self, in_stage: Option<&str>, in_job: Option<&str>) -> Self {
+        Self {
+            result: self.result,
+            step_index: Self::build_step_index(self.result),
+            in_stage: in_stage
+                .map(str::to_string)
+                .or_else(|| self.in_stage.clone()),
+            in_job: in_job.map(str::to_string).or_else(|| self.in_job.clone()),
+            pipeline: self.pipeline.clone(),
+        }
     }
 
     /// Evaluate a single assertion
@@ -200,9 +431,13 @@ impl<'a> AssertionEvaluator<'a> {
             Assertion::StepSucceeded { step } => self.eval_step_status(step, StepStatus::Succeeded),
             Assertion::StepFailed { step } => self.eval_step_status(step, StepStatus::Failed),
             Assertion::StepSkipped { step } => self.eval_step_status(step, StepStatus::Skipped),
+            Assertion::StepCanceled { step } => self.eval_step_status(step, StepStatus::Canceled),
+            Assertion::StepNotExecuted { step } => self.eval_step_not_executed(step),
             Assertion::JobSucceeded { job } => self.eval_job_status(job, JobStatus::Succeeded),
             Assertion::JobFailed { job } => self.eval_job_status(job, JobStatus::Failed),
             Assertion::JobSkipped { job } => self.eval_job_status(job, JobStatus::Skipped),
+            Assertion::JobCanceled { job } => self.eval_job_status(job, JobStatus::Canceled),
+            Assertion::JobNotExecuted { job } => self.eval_job_not_executed(job),
             Assertion::StageSucceeded { stage } => {
                 self.eval_stage_status(stage, StageStatus::Succeeded)
             }
@@ -210,6 +445,9 @@ impl<'a> AssertionEvaluator<'a> {
             Assertion::StageSkipped { stage } => {
                 self.eval_stage_status(stage, StageStatus::Skipped)
             }
+            Assertion::StageCanceled { stage } => {
+                self.eval_stage_status(stage, StageStatus::Canceled)
+            }
             Assertion::StepOutputEquals {
                 step,
                 output,
@@ -228,6 +466,48 @@ impl<'a> AssertionEvaluator<'a> {
             Assertion::VariableContains { name, pattern } => {
                 self.eval_variable_contains(name, pattern)
             }
+            Assertion::StepSubstitutedVariables { step, variables } => {
+                self.eval_step_substituted_variables(step, variables)
+            }
+            Assertion::StepEnvContains { step, name, value } => {
+                self.eval_step_env_contains(step, name, value.as_ref())
+            }
+            _ if self.pipeline.is_none() => AssertionResult::fail(
+                &assertion.to_string(),
+                "No resolved pipeline to check the structure of",
+                "Structure assertions need the pipeline or template before it runs",
+            ),
+            Assertion::ResolvedStepCount { count } => {
+                let names = self
+                    .resolved_steps()
+                    .iter()
+                    .filter(|s| self.in_scope(s))
+                    .map(|s| s.id())
+                    .collect();
+                self.eval_count(&assertion.to_string(), "steps", *count, names)
+            }
+            Assertion::ResolvedJobCount { count } => {
+                let names = self
+                    .resolved_jobs()
+                    .iter()
+                    .map(|(stage, job)| correlation_id(stage, job.identifier(), None))
+                    .collect();
+                self.eval_count(&assertion.to_string(), "jobs", *count, names)
+            }
+            Assertion::ResolvedStageCount { count } => {
+                let names = self
+                    .resolved_stages()
+                    .iter()
+                    .map(|(name, _)| name.to_string())
+                    .collect();
+                self.eval_count(&assertion.to_string(), "stages", *count, names)
+            }
+            Assertion::StepExists { step } => self.eval_step_exists(step),
+            Assertion::JobExists { job } => self.eval_job_exists(job),
+            Assertion::StageExists { stage } => self.eval_stage_exists(stage),
+            Assertion::StepScriptContains { step, pattern } => {
+                self.eval_step_script_contains(step, pattern)
+            }
         }
     }
 
@@ -284,7 +564,7 @@ impl<'a> AssertionEvaluator<'a> {
         let desc = format!("step_{:?}({})", expected, step_name).to_lowercase();
 
         match self.find_step(step_name) {
-            Some(info) => {
+            Ok(info) => {
                 if info.result.status == expected {
                     AssertionResult::pass(
                         &desc,
@@ -309,21 +589,90 @@ impl<'a> AssertionEvaluator<'a> {
                     )
                 }
             }
-            None => AssertionResult::fail(
+            Err(error) => {
+                self.lookup_failed(&desc, "Step", step_name, error, self.available_steps_hint())
+            }
+        }
+    }
+
+    /// Passes when the step has no result; skipped steps were still
+    /// evaluated, so they fail
+    fn eval_step_not_executed(&self, step_name: &str) -> AssertionResult {
+        let desc = format!("step_not_executed({})", step_name);
+        match self.find_step(step_name) {
+            Ok(info) => AssertionResult::fail(
                 &desc,
-                format!("Step '{}' not found in execution results", step_name),
                 format!(
-                    "Available steps: [{}]",
-                    self.step_index
-                        .iter()
-                        .filter_map(|s| s.name.as_deref())
-                        .collect::<Vec<_>>()
-                        .join(", ")
+                    "Step '{}' expected not to be executed but was {:?}",
+                    step_name, info.result.status
                 ),
+                format!("Result at {}", info.id()),
+            ),
+            Err(LookupError::Ambiguous(ids)) => AssertionResult::fail(
+                &desc,
+                format!(
+                    "Step '{}' expected not to be executed but ran in {} places",
+                    step_name,
+                    ids.len()
+                ),
+                format!("Results at: {}", ids.join(", ")),
+            ),
+            Err(LookupError::NotFound) => AssertionResult::pass(
+                &desc,
+                self.not_executed_message("Step", step_name, |name| {
+                    !matches!(
+                        self.find_in(&self.resolved_steps(), name),
+                        Err(LookupError::NotFound)
+                    )
+                }),
             ),
         }
     }
 
+    fn eval_job_not_executed(&self, job_name: &str) -> AssertionResult {
+        let desc = format!("job_not_executed({})", job_name);
+        match self.find_job(job_name) {
+            Ok(job) => AssertionResult::fail(
+                &desc,
+                format!(
+                    "Job '{}' expected not to be executed but was {:?}",
+                    job_name, job.status
+                ),
+                format!("Actual status: {:?}", job.status),
+            ),
+            Err(LookupError::Ambiguous(ids)) => AssertionResult::fail(
+                &desc,
+                format!(
+                    "Job '{}' expected not to be executed but ran in {} places",
+                    job_name,
+                    ids.len()
+                ),
+                format!("Results at: {}", ids.join(", ")),
+            ),
+            Err(LookupError::NotFound) => AssertionResult::pass(
+                &desc,
+                self.not_executed_message("Job", job_name, |name| self.job_resolved(name)),
+            ),
+        }
+    }
+
+    /// Why something with no result wasn't executed, from the resolved
+    /// pipeline when there is one
+    fn not_executed_message(
+        &self,
+        kind: &str,
+        name: &str,
+        resolved: impl Fn(&str) -> bool,
+    ) -> String {
+        match &self.pipeline {
+            Some(_) if resolved(name) => {
+                format!("{} '{}' is in the pipeline but wasn't executed", kind, name)
+            }
+            Some(_) => format!("{} '{}' isn't in the resolved pipeline", kind, name),
+            None => format!("{} '{}' has no result", kind, name),
+        }
+    }
+
     // =========================================================================
     // Job status assertions
     // =========================================================================
@@ -332,7 +681,7 @@ impl<'a> AssertionEvaluator<'a> {
         let desc = format!("job_{:?}({})", expected, job_name).to_lowercase();
 
         match self.find_job(job_name) {
-            Some(job) => {
+            Ok(job) => {
                 if job.status == expected {
                     AssertionResult::pass(
                         &desc,
@@ -349,7 +698,7 @@ impl<'a> AssertionEvaluator<'a> {
                     )
                 }
             }
-            None => {
+            Err(error) => {
                 let available: Vec<&str> = self
                     .result
                     .stages
@@ -357,11 +706,8 @@ impl<'a> AssertionEvaluator<'a> {
                     .flat_map(|s| s.jobs.iter())
                     .map(|j| j.job_name.as_str())
                     .collect();
-                AssertionResult::fail(
-                    &desc,
-                    format!("Job '{}' not found in execution results", job_name),
-                    format!("Available jobs: [{}]", available.join(", ")),
-                )
+                let available = format!("Available jobs: [{}]", available.join(", "));
+                self.lookup_failed(&desc, "Job", job_name, error, available)
             }
         }
     }
@@ -420,7 +766,7 @@ impl<'a> AssertionEvaluator<'a> {
         let desc = format!("step_output_equals({}.{})", step_name, output_name);
 
         match self.find_step(step_name) {
-            Some(info) => {
+            Ok(info) => {
                 if let Some(actual) = info.result.outputs.get(output_name) {
                     let expected_str = expected.as_string();
                     if *actual == expected_str {
@@ -451,11 +797,9 @@ impl<'a> AssertionEvaluator<'a> {
                     )
                 }
             }
-            None => AssertionResult::fail(
-                &desc,
-                format!("Step '{}' not found", step_name),
-                self.available_steps_hint(),
-            ),
+            Err(error) => {
+                self.lookup_failed(&desc, "Step", step_name, error, self.available_steps_hint())
+            }
         }
     }
 
@@ -468,7 +812,7 @@ impl<'a> AssertionEvaluator<'a> {
         let desc = format!("step_output_contains({}, \"{}\")", step_name, pattern);
 
         match self.find_step(step_name) {
-            Some(info) => {
+            Ok(info) => {
                 let text = if let Some(output_key) = output_name {
                     info.result
                         .outputs
@@ -498,11 +842,9 @@ impl<'a> AssertionEvaluator<'a> {
                     )
                 }
             }
-            None => AssertionResult::fail(
-                &desc,
-                format!("Step '{}' not found", step_name),
-                self.available_steps_hint(),
-            ),
+            Err(error) => {
+                self.lookup_failed(&desc, "Step", step_name, error, self.available_steps_hint())
+            }
         }
     }
 
@@ -517,7 +859,7 @@ impl<'a> AssertionEvaluator<'a> {
         let second = self.find_step(before_name);
 
         match (first, second) {
-            (Some(first_info), Some(second_info)) => {
+            (Ok(first_info), Ok(second_info)) => {
                 if first_info.index < second_info.index {
                     AssertionResult::pass(
                         &desc,
@@ -537,14 +879,14 @@ impl<'a> AssertionEvaluator<'a> {
                     )
                 }
             }
-            (None, _) => AssertionResult::fail(
-                &desc,
-                format!("Step '{}' not found", step_name),
-                self.available_steps_hint(),
-            ),
-            (_, None) => AssertionResult::fail(
+            (Err(error), _) => {
+                self.lookup_failed(&desc, "Step", step_name, error, self.available_steps_hint())
+            }
+            (_, Err(error)) => self.lookup_failed(
                 &desc,
-                format!("Step '{}' not found", before_name),
+                "Step",
+                before_name,
+                error,
                 self.available_steps_hint(),
             ),
         }
@@ -559,10 +901,18 @@ impl<'a> AssertionEvaluator<'a> {
         let mut missing: Vec<&str> = Vec::new();
 
         for name in step_names {
-            if let Some(info) = self.find_step(name) {
-                found_steps.push(info);
-            } else {
-                missing.push(name);
+            match self.find_step(name) {
+                Ok(info) => found_steps.push(info),
+                Err(LookupError::NotFound) => missing.push(name),
+                Err(error) => {
+                    return self.lookup_failed(
+                        &desc,
+                        "Step",
+                        name,
+                        error,
+                        self.available_steps_hint(),
+                    )
+                }
             }
         }
 
@@ -575,8 +925,10 @@ impl<'a> AssertionEvaluator<'a> {
         }
 
         // Check if steps are in different jobs (which could run in parallel)
-        let job_names: std::collections::HashSet<&str> =
-            found_steps.iter().map(|s| s.job_name.as_str()).collect();
+        let job_names: std::collections::BTreeSet<String> = found_steps
+            .iter()
+            .map(|s| correlation_id(&s.stage_name, Some(&s.job_name), None))
+            .collect();
 
         if job_names.len() > 1 {
             // Steps are in different jobs - they could run in parallel
@@ -669,21 +1021,406 @@ impl<'a> AssertionEvaluator<'a> {
     }
 
     // =========================================================================
-    // Lookup helpers
+    // Substitution assertions
     // =========================================================================
 
-    fn find_step(&self, name: &str) -> Option<&StepInfo> {
-        self.step_index
+    fn eval_step_substituted_variables(
+        &self,
+        step_name: &str,
+        expected: &[String],
+    ) -> AssertionResult {
+        let desc = format!("step_substituted_variables({})", step_name);
+
+        match self.find_step(step_name) {
+            Ok(info) => {
+                let mut actual: Vec<&str> = info
+                    .result
+                    .substitutions
+                    .iter()
+                    .map(|s| s.name.as_str())
+                    .collect();
+                actual.sort_unstable();
+                actual.dedup();
+
+                let mut wanted: Vec<&str> = expected.iter().map(|s| s.as_str()).collect();
+                wanted.sort_unstable();
+                wanted.dedup();
+
+                if actual == wanted {
+                    AssertionResult::pass(
+                        &desc,
+                        format!(
+                            "Step '{}' substituted exactly [{}]",
+                            step_name,
+                            wanted.join(", ")
+                        ),
+                    )
+                } else {
+                    AssertionResult::fail(
+                        &desc,
+                        format!("Step '{}' substituted variables do not match", step_name),
+                        format!(
+                            "Expected: [{}], Actual: [{}]",
+                            wanted.join(", "),
+                            actual.join(", ")
+                        ),
+                    )
+                }
+            }
+            Err(error) => {
+                self.lookup_failed(&desc, "Step", step_name, error, self.available_steps_hint())
+            }
+        }
+    }
+
+    // =========================================================================
+    // Environment assertions
+    // =========================================================================
+
+    fn eval_step_env_contains(
+        &self,
+        step_name: &str,
+        name: &str,
+        expected: Option<&Value>,
+    ) -> AssertionResult {
+        let desc = format!("step_env_contains({}, {})", step_name, name);
+
+        let info = match self.find_step(step_name) {
+            Ok(info) => info,
+            Err(error) => {
+                return self.lookup_failed(
+                    &desc,
+                    "Step",
+                    step_name,
+                    error,
+                    self.available_steps_hint(),
+                )
+            }
+        };
+        let actual = info
+            .result
+            .env
             .iter()
-            .find(|s| s.name.as_deref() == Some(name) || s.display_name.as_deref() == Some(name))
+            .find(|(env_name, _)| env_name == name)
+            .map(|(_, value)| value);
+
+        match (actual, expected.map(Value::as_string)) {
+            (None, _) => {
+                let names: Vec<&str> = info.result.env.iter().map(|(n, _)| n.as_str()).collect();
+                AssertionResult::fail(
+                    &desc,
+                    format!(
+                        "Step '{}' got no environment variable '{}'",
+                        step_name, name
+                    ),
+                    format!("Environment: [{}]", names.join(", ")),
+                )
+            }
+            (Some(actual), Some(expected)) if *actual != expected => AssertionResult::fail(
+                &desc,
+                format!(
+                    "Step '{}' environment variable '{}' does not match",
+                    step_name, name
+                ),
+                format!("Expected: '{}', Actual: '{}'", expected, actual),
+            ),
+            (Some(actual), _) => AssertionResult::pass(
+                &desc,
+                format!("Step '{}' got {}='{}'", step_name, name, actual),
+            ),
+        }
     }
 
-    fn find_job(&self, name: &str) -> Option<&JobResult> {
-        self.result
-            .stages
+    // =========================================================================
+    // Structure assertions
+    // =========================================================================
+
+    fn eval_count(
+        &self,
+        desc: &str,
+        kind: &str,
+        expected: usize,
+        names: Vec<String>,
+    ) -> AssertionResult {
+        if names.len() == expected {
+            AssertionResult::pass(desc, format!("Resolved to {} {}", expected, kind))
+        } else {
+            AssertionResult::fail(
+                desc,
+                format!(
+                    "Expected {} {} but resolved to {}",
+                    expected,
+                    kind,
+                    names.len()
+                ),
+                format!("Resolved {}: [{}]", kind, names.join(", ")),
+            )
+        }
+    }
+
+    fn eval_step_exists(&self, step_name: &str) -> AssertionResult {
+        let desc = format!("step_exists({})", step_name);
+        match self.find_in(&self.resolved_steps(), step_name) {
+            Ok(_) | Err(LookupError::Ambiguous(_)) => {
+                AssertionResult::pass(&desc, format!("Step '{}' is in the pipeline", step_name))
+            }
+            Err(error) => {
+                let available = self.resolved_steps_hint();
+                self.lookup_failed(&desc, "Step", step_name, error, available)
+            }
+        }
+    }
+
+    fn eval_job_exists(&self, job_name: &str) -> AssertionResult {
+        let desc = format!("job_exists({})", job_name);
+        if self.job_resolved(job_name) {
+            AssertionResult::pass(&desc, format!("Job '{}' is in the pipeline", job_name))
+        } else {
+            let names: Vec<&str> = self
+                .resolved_jobs()
+                .iter()
+                .filter_map(|(_, job)| job.identifier())
+                .collect();
+            AssertionResult::fail(
+                &desc,
+                format!("Job '{}' not found", job_name),
+                format!("Resolved jobs: [{}]", names.join(", ")),
+            )
+        }
+    }
+
+    fn eval_stage_exists(&self, stage_name: &str) -> AssertionResult {
+        let desc = format!("stage_exists({})", stage_name);
+        let stages = self.resolved_stages();
+        if stages
+            .iter()
+            .any(|(name, display)| named(name, *display, stage_name))
+        {
+            AssertionResult::pass(&desc, format!("Stage '{}' is in the pipeline", stage_name))
+        } else {
+            let names: Vec<&str> = stages.iter().map(|(name, _)| *name).collect();
+            AssertionResult::fail(
+                &desc,
+                format!("Stage '{}' not found", stage_name),
+                format!("Resolved stages: [{}]", names.join(", ")),
+            )
+        }
+    }
+
+    fn eval_step_script_contains(&self, step_name: &str, pattern: &str) -> AssertionResult {
+        let desc = format!("step_script_contains({}, \"{}\")", step_name, pattern);
+        match self.find_in(&self.resolved_steps(), step_name) {
+            Ok(info) => match step_script(&info.result) {
+                Some(script) if script.contains(pattern) => AssertionResult::pass(
+                    &desc,
+                    format!("Step '{}' script contains '{}'", step_name, pattern),
+                ),
+                Some(script) => AssertionResult::fail(
+                    &desc,
+                    format!("Step '{}' script does not contain '{}'", step_name, pattern),
+                    format!("Script: '{}'", script.trim()),
+                ),
+                None => AssertionResult::fail(
+                    &desc,
+                    format!("Step '{}' has no script", step_name),
+                    "Only script, bash, pwsh and powershell steps and inline task scripts have one",
+                ),
+            },
+            Err(error) => {
+                let available = self.resolved_steps_hint();
+                self.lookup_failed(&desc, "Step", step_name, error, available)
+            }
+        }
+    }
+
+    /// Stages of the resolved pipeline in scope, as (name, display name)
+    fn resolved_stages(&self) -> Vec<(&str, Option<&str>)> {
+        self.pipeline
+            .iter()
+            .flat_map(|p| &p.stages)
+            .map(|s| {
+                (
+                    s.stage.as_deref().unwrap_or_default(),
+                    s.display_name.as_deref(),
+                )
+            })
+            .filter(|(name, display)| {
+                self.in_stage
+                    .as_deref()
+                    .is_none_or(|stage| named(name, *display, stage))
+            })
+            .collect()
+    }
+
+    /// Jobs of the resolved pipeline in scope, with their stage's name
+    fn resolved_jobs(&self) -> Vec<(&str, &Job)> {
+        let stages = self.pipeline.iter().flat_map(|p| &p.stages).filter(|s| {
+            self.in_stage.as_deref().is_none_or(|stage| {
+                named(
+                    s.stage.as_deref().unwrap_or_default(),
+                    s.display_name.as_deref(),
+                    stage,
+                )
+            })
+        });
+        stages
+            .flat_map(|s| {
+                let stage = s.stage.as_deref().unwrap_or_default();
+                s.jobs.iter().map(move |j| (stage, j))
+            })
+            .filter(|(_, j)| {
+                self.in_job.as_deref().is_none_or(|job| {
+                    named(
+                        j.identifier().unwrap_or_default(),
+                        j.display_name.as_deref(),
+                        job,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Steps of the resolved pipeline, including deployment strategy hooks
+    fn resolved_steps(&self) -> Vec<StepInfo<Step>> {
+        let mut steps = Vec::new();
+        for stage in self.pipeline.iter().flat_map(|p| &p.stages) {
+            for job in &stage.jobs {
+                let deployment = collect_deployment_steps(job);
+                let job_steps = if deployment.is_empty() {
+                    &job.steps
+                } else {
+                    &deployment
+                };
+                for (step_index, step) in job_steps.iter().enumerate() {
+                    steps.push(StepInfo {
+                        name: step.name.clone(),
+                        display_name: step.display_name.clone(),
+                        stage_name: stage.stage.clone().unwrap_or_default(),
+                        stage_display_name: stage.display_name.clone(),
+                        job_name: job.identifier().unwrap_or_default().to_string(),
+                        job_display_name: job.display_name.clone(),
+                        step_index,
+                        index: steps.len(),
+                        result: step.clone(),
+                    });
+                }
+            }
+        }
+        steps
+    }
+
+    fn resolved_steps_hint(&self) -> String {
+        let names: Vec<String> = self
+            .resolved_steps()
             .iter()
-            .flat_map(|s| s.jobs.iter())
-            .find(|j| j.job_name == name || j.display_name.as_deref() == Some(name))
+            .filter(|s| self.in_scope(s))
+            .map(|s| {
+                s.name
+                    .clone()
+                    .or(s.display_name.clone())
+                    .unwrap_or_else(|| s.id())
+            })
+            .collect();
+        format!("Resolved steps: [{}]", names.join(", "))
+    }
+
+    // =========================================================================
+    // Lookup helpers
+    // =========================================================================
+
+    /// Whether the resolved pipeline has the job, by name, display name or
+    /// `Stage/Job`
+    fn job_resolved(&self, job_name: &str) -> bool {
+        self.resolved_jobs().iter().any(|(stage, job)| {
+            let id = job.identifier().unwrap_or_default();
+            named(id, job.display_name.as_deref(), job_name)
+                || job_name == correlation_id(stage, Some(id), None)
+        })
+    }
+
+    fn find_step(&self, name: &str) -> Result<&StepInfo, LookupError> {
+        self.find_in(&self.step_index, name)
+    }
+
+    fn find_in<'s, T>(
+        &self,
+        steps: &'s [StepInfo<T>],
+        name: &str,
+    ) -> Result<&'s StepInfo<T>, LookupError> {
+        let mut matches: Vec<&StepInfo<T>> = steps
+            .iter()
+            .filter(|s| s.is_named(name) && self.in_scope(s))
+            .collect();
+
+        // Otherwise `Stage/Job/<step>`, where the step is a name or 1-based number
+        if matches.is_empty() {
+            if let [stage, job, step] = name.splitn(3, '/').collect::<Vec<_>>()[..] {
+                matches = steps
+                    .iter()
+                    .filter(|s| {
+                        named(&s.stage_name, s.stage_display_name.as_deref(), stage)
+                            && job_named(&s.job_name, s.job_display_name.as_deref(), job)
+                            && (s.is_named(step) || step == (s.step_index + 1).to_string())
+                    })
+                    .collect();
+            }
+        }
+
+        unique(matches, |s| s.id())
+    }
+
+    fn in_scope<T>(&self, step: &StepInfo<T>) -> bool {
+        self.in_stage
+            .as_deref()
+            .is_none_or(|stage| named(&step.stage_name, step.stage_display_name.as_deref(), stage))
+            && self
+                .in_job
+                .as_deref()
+                .is_none_or(|job| job_named(&step.job_name, step.job_display_name.as_deref(), job))
+    }
+
+    /// The job named `name`; the instances of a matrix job are combined
+    /// unless one is named (`Build[linux]`)
+    fn find_job(&self, name: &str) -> Result<Cow<'_, JobResult>, LookupError> {
+        let jobs = || {
+            self.result.stages.iter().filter(|s| {
+                self.in_stage
+                    .as_deref()
+                    .is_none_or(|stage| named(&s.stage_name, s.display_name.as_deref(), stage))
+            })
+        };
+        let mut matches: Vec<(&StageResult, &JobResult)> = jobs()
+            .flat_map(|s| s.jobs.iter().map(move |j| (s, j)))
+            .filter(|(_, j)| job_named(&j.job_name, j.display_name.as_deref(), name))
+            .collect();
+
+        // Otherwise `Stage/Job`
+        if matches.is_empty() {
+            if let Some((stage, job)) = name.split_once('/') {
+                matches = jobs()
+                    .filter(|s| named(&s.stage_name, s.display_name.as_deref(), stage))
+                    .flat_map(|s| s.jobs.iter().map(move |j| (s, j)))
+                    .filter(|(_, j)| job_named(&j.job_name, j.display_name.as_deref(), job))
+                    .collect();
+            }
+        }
+
+        // Instances of one matrix job
+        if matches.len() > 1
+            && matches.iter().all(|(s, j)| {
+                j.matrix_instance().is_some()
+                    && s.stage_name == matches[0].0.stage_name
+                    && j.job() == matches[0].1.job()
+            })
+        {
+            let instances: Vec<JobResult> = matches.iter().map(|(_, j)| (*j).clone()).collect();
+            return Ok(Cow::Owned(JobResult::combine(&instances)));
+        }
+
+        unique(matches, |(s, j)| {
+            correlation_id(&s.stage_name, Some(&j.job_name), None)
+        })
+        .map(|(_, j)| Cow::Borrowed(j))
     }
 
     fn find_stage(&self, name: &str) -> Option<&StageResult> {
@@ -707,18 +1444,45 @@ impl<'a> AssertionEvaluator<'a> {
         format!("Available steps: [{}]", names.join(", "))
     }
 
+    /// Failure for a step or job reference that didn't pick out one result
+    fn lookup_failed(
+        &self,
+        desc: &str,
+        kind: &str,
+        name: &str,
+        error: LookupError,
+        available: String,
+    ) -> AssertionResult {
+        match error {
+            LookupError::NotFound => {
+                AssertionResult::fail(desc, format!("{} '{}' not found", kind, name), available)
+            }
+            LookupError::Ambiguous(ids) => AssertionResult::fail(
+                desc,
+                format!("{} '{}' is ambiguous: {} match", kind, name, ids.len()),
+                format!(
+                    "Qualify it with in_stage:/in_job:, or use one of [{}]",
+                    ids.join(", ")
+                ),
+            ),
+        }
+    }
+
     fn build_step_index(result: &ExecutionResult) -> Vec<StepInfo> {
         let mut steps = Vec::new();
         let mut global_index = 0;
 
         for stage in &result.stages {
             for job in &stage.jobs {
-                for step in &job.steps {
+                for (step_index, step) in job.steps.iter().enumerate() {
                     steps.push(StepInfo {
                         name: step.step_name.clone(),
                         display_name: step.display_name.clone(),
                         stage_name: stage.stage_name.clone(),
+                        stage_display_name: stage.display_name.clone(),
                         job_name: job.job_name.clone(),
+                        job_display_name: job.display_name.clone(),
+                        step_index,
                         index: global_index,
                         result: step.clone(),
                     });
@@ -734,6 +1498,7 @@ impl<'a> AssertionEvaluator<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::models::VariableSubstitution;
     use std::collections::HashMap;
     use std::time::Duration;
 
@@ -752,6 +1517,12 @@ mod tests {
             duration: Duration::from_millis(100),
             exit_code: Some(exit_code),
             outputs: HashMap::new(),
+            substitutions: Vec::new(),
+            summary: None,
+            unsupported: Vec::new(),
+            started_at: None,
+            finished_at: None,
+            env: Vec::new(),
         }
     }
 
@@ -763,6 +1534,8 @@ mod tests {
             steps,
             duration: Duration::from_millis(500),
             outputs: HashMap::new(),
+            started_at: None,
+            finished_at: None,
         }
     }
 
@@ -773,15 +1546,21 @@ mod tests {
             status,
             jobs,
             duration: Duration::from_secs(1),
+            started_at: None,
+            finished_at: None,
         }
     }
 
     fn make_result(stages: Vec<StageResult>, success: bool) -> ExecutionResult {
         ExecutionResult {
+            run_id: String::new(),
             stages,
             duration: Duration::from_secs(5),
             success,
             variables: HashMap::new(),
+            images: Default::default(),
+            started_at: None,
+            finished_at: None,
         }
     }
 
@@ -1035,6 +1814,168 @@ mod tests {
         assert!(r.passed);
     }
 
+    #[test]
+    fn test_not_executed() {
+        let result = make_result(
+            vec![make_stage(
+                "Build",
+                StageStatus::Succeeded,
+                vec![make_job(
+                    "Compile",
+                    JobStatus::Succeeded,
+                    vec![
+                        make_step("build", StepStatus::Succeeded),
+                        make_step("lint", StepStatus::Skipped),
+                    ],
+                )],
+            )],
+            true,
+        );
+        let pipeline = crate::parser::AzureParser::parse(
+            r#"
+stages:
+  - stage: Build
+    jobs:
+      - job: Compile
+        steps:
+          - script: make
+            name: build
+          - script: make lint
+            name: lint
+  - stage: Deploy
+    jobs:
+      - job: Release
+        steps:
+          - script: ./publish.sh
+            name: publish
+"#,
+        )
+        .unwrap();
+        let evaluator = AssertionEvaluator::new(&result).with_pipeline(&pipeline);
+        let step = |name: &str| {
+            evaluator.evaluate(&Assertion::StepNotExecuted {
+                step: name.to_string(),
+            })
+        };
+        let job = |name: &str| {
+            evaluator.evaluate(&Assertion::JobNotExecuted {
+                job: name.to_string(),
+            })
+        };
+
+        let pruned = step("publish");
+        assert!(pruned.passed);
+        assert!(pruned
+            .message
+            .contains("in the pipeline but wasn't executed"));
+        let excluded = step("sign");
+        assert!(excluded.passed);
+        assert!(excluded.message.contains("isn't in the resolved pipeline"));
+        // Skipped steps were still evaluated
+        let skipped = step("lint");
+        assert!(!skipped.passed);
+        assert!(skipped.message.contains("was Skipped"));
+        assert!(!step("build").passed);
+
+        assert!(job("Release").passed);
+        assert!(!job("Compile").passed);
+    }
+
+    #[test]
+    fn test_matrix_instances_targeted_individually() {
+        let result = make_result(
+            vec![make_stage(
+                "Build",
+                StageStatus::Failed,
+                vec![
+                    make_job(
+                        "Compile[linux]",
+                        JobStatus::Succeeded,
+                        vec![make_step("make", StepStatus::Succeeded)],
+                    ),
+                    make_job(
+                        "Compile[windows]",
+                        JobStatus::Failed,
+                        vec![make_step("make", StepStatus::Failed)],
+                    ),
+                ],
+            )],
+            false,
+        );
+        let evaluator = AssertionEvaluator::new(&result);
+        let passes = |assertion: Assertion| evaluator.evaluate(&assertion).passed;
+
+        // The job as a whole fails if any instance does
+        assert!(passes(Assertion::JobFailed {
+            job: "Compile".to_string(),
+        }));
+        assert!(passes(Assertion::JobSucceeded {
+            job: "Compile[linux]".to_string(),
+        }));
+        assert!(passes(Assertion::JobFailed {
+            job: "Build/Compile[windows]".to_string(),
+        }));
+        assert!(passes(Assertion::StepFailed {
+            step: "Build/Compile[windows]/make".to_string(),
+        }));
+        assert!(passes(Assertion::StepSucceeded {
+            step: "Build/Compile[linux]/make".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_duplicate_step_names_must_be_qualified() {
+        let job = |name: &str, status: StepStatus| {
+            make_job(
+                name,
+                JobStatus::Succeeded,
+                vec![
+                    make_step("Checkout", StepStatus::Succeeded),
+                    make_step("Build", status),
+                ],
+            )
+        };
+        let result = make_result(
+            vec![make_stage(
+                "CI",
+                StageStatus::Failed,
+                vec![
+                    job("Linux", StepStatus::Succeeded),
+                    job("Windows", StepStatus::Failed),
+                ],
+            )],
+            false,
+        );
+        let evaluator = AssertionEvaluator::new(&result);
+        let failed = |evaluator: &AssertionEvaluator, step: &str| {
+            evaluator.evaluate(&Assertion::StepFailed {
+                step: step.to_string(),
+            })
+        };
+
+        let r = failed(&evaluator, "Build");
+        assert!(!r.passed);
+        assert!(r.message.contains("ambiguous"));
+        assert!(r
+            .failure_detail
+            .unwrap()
+            .contains("[CI/Linux/2, CI/Windows/2]"));
+
+        assert!(failed(&evaluator, "CI/Windows/Build").passed);
+        assert!(failed(&evaluator, "CI/Windows/2").passed);
+        assert!(!failed(&evaluator, "CI/Linux/2").passed);
+        assert!(failed(&evaluator.within(None, Some("Windows")), "Build").passed);
+        assert!(!failed(&evaluator.within(Some("CI"), Some("Linux")), "Build").passed);
+        assert!(failed(&evaluator.within(Some("CD"), None), "Build")
+            .message
+            .contains("not found"));
+
+        let r = evaluator.evaluate(&Assertion::JobSucceeded {
+            job: "CI/Linux".to_string(),
+        });
+        assert!(r.passed);
+    }
+
     #[test]
     fn test_steps_ran_in_parallel_different_jobs() {
         let result = make_result(
@@ -1131,4 +2072,71 @@ mod tests {
         assert_eq!(results.len(), 3);
         assert!(results.iter().all(|r| r.passed));
     }
+
+    #[test]
+    fn test_step_substituted_variables() {
+        let mut step = make_step("Build", StepStatus::Succeeded);
+        step.substitutions = vec![
+            VariableSubstitution::new("configuration", "Release"),
+            VariableSubstitution::new("version", "1.0.0"),
+            VariableSubstitution::new("configuration", "Release"),
+        ];
+
+        let result = make_result(
+            vec![make_stage(
+                "Build",
+                StageStatus::Succeeded,
+                vec![make_job("compile", JobStatus::Succeeded, vec![step])],
+            )],
+            true,
+        );
+
+        let evaluator = AssertionEvaluator::new(&result);
+        let r = evaluator.evaluate(&Assertion::StepSubstitutedVariables {
+            step: "Build".to_string(),
+            variables: vec!["version".to_string(), "configuration".to_string()],
+        });
+        assert!(r.passed);
+
+        let r = evaluator.evaluate(&Assertion::StepSubstitutedVariables {
+            step: "Build".to_string(),
+            variables: vec!["version".to_string()],
+        });
+        assert!(!r.passed);
+        assert!(r.failure_detail.unwrap().contains("configuration"));
+    }
+
+    #[test]
+    fn test_step_env_contains() {
+        let mut step = make_step("Build", StepStatus::Succeeded);
+        step.env = vec![
+            ("CONFIGURATION".to_string(), "Release".to_string()),
+            ("TOKEN".to_string(), "***".to_string()),
+        ];
+        let result = make_result(
+            vec![make_stage(
+                "Build",
+                StageStatus::Succeeded,
+                vec![make_job("compile", JobStatus::Succeeded, vec![step])],
+            )],
+            true,
+        );
+        let evaluator = AssertionEvaluator::new(&result);
+        let check = |name: &str, value: Option<&str>| {
+            evaluator.evaluate(&Assertion::StepEnvContains {
+                step: "Build".to_string(),
+                name: name.to_string(),
+                value: value.map(|v| Value::String(v.to_string())),
+            })
+        };
+
+        assert!(check("CONFIGURATION", Some("Release")).passed);
+        assert!(check("TOKEN", None).passed);
+        let r = check("CONFIGURATION", Some("Debug"));
+        assert!(!r.passed);
+        assert!(r.failure_detail.unwrap().contains("Actual: 'Release'"));
+        let r = check("VERSION", None);
+        assert!(!r.passed);
+        assert!(r.failure_detail.unwrap().contains("CONFIGURATION, TOKEN"));
+    }
 }
@@ -0,0 +1,292 @@
+// Test Fixtures
+// Creates temporary directories, files and git repositories before a test runs
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// A temporary directory declared in a test's `fixtures:` section
+///
+/// ```yaml
+/// fixtures:
+///   - name: repo
+///     git:
+///       commits:
+///         - message: Initial commit
+///           files: { README.md: "# Demo" }
+///           tag: v1.0.0
+///         - message: Add login
+///           branch: feature/login
+///           files: { src/login.rs: "fn login() {}" }
+///     files:
+///       notes.txt: uncommitted change
+/// ```
+///
+/// The directory's path is exposed to the pipeline as the variable `name`
+/// (`$(repo)`); git fixtures also set `<name>.branch` and `<name>.commit`
+/// to the checked out branch and commit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureDef {
+    /// Variable the directory's path is exposed as
+    pub name: String,
+    /// Files to create, relative path to contents (written after any commits,
+    /// so they're uncommitted changes in a git fixture)
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// Initialize the directory as a git repository
+    #[serde(default)]
+    pub git: Option<GitFixtureDef>,
+}
+
+/// A scratch git repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFixtureDef {
+    /// Initial branch
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// Commits to create, in order; HEAD is left on the branch of the last one
+    #[serde(default)]
+    pub commits: Vec<CommitDef>,
+}
+
+/// A commit in a git fixture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitDef {
+    pub message: String,
+    /// Files to write before committing, relative path to contents
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// Branch to commit on, created from the current commit if it doesn't exist
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Tag to put on the commit
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+/// Fixtures created for one test, removed when dropped
+#[derive(Debug, Default)]
+pub struct TestFixtures {
+    dirs: Vec<PathBuf>,
+    variables: HashMap<String, String>,
+}
+
+impl TestFixtures {
+    /// Create every fixture, removing those already created if one fails
+    pub fn create(defs: &[FixtureDef]) -> Result<Self, String> {
+        let mut fixtures = Self::default();
+        for def in defs {
+            fixtures.create_fixture(def)?;
+        }
+        Ok(fixtures)
+    }
+
+    /// Variables pointing the pipeline at the fixtures
+    pub fn variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
+
+    fn create_fixture(&mut self, def: &FixtureDef) -> Result<(), String> {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let dir = std::env::temp_dir().join(format!(
+            "roxid-fixture-{}-{}-{}",
+            std::process::id(),
+            self.dirs.len(),
+            unique
+        ));
+        std::fs::create_dir_all(&dir).map_err(|e| format!("fixture '{}': {}", def.name, e))?;
+        self.dirs.push(dir.clone());
+
+        let fail = |e: String| format!("fixture '{}': {}", def.name, e);
+        if let Some(git) = &def.git {
+            create_repository(&dir, git).map_err(fail)?;
+            // Unlike rev-parse, this also names the branch of a repository without commits
+            let branch = git_output(&dir, &["symbolic-ref", "--short", "HEAD"]).map_err(fail)?;
+            self.variables
+                .insert(format!("{}.branch", def.name), branch);
+            if !git.commits.is_empty() {
+                let commit = git_output(&dir, &["rev-parse", "HEAD"]).map_err(fail)?;
+                self.variables
+                    .insert(format!("{}.commit", def.name), commit);
+            }
+        }
+        write_files(&dir, &def.files).map_err(fail)?;
+
+        self.variables
+            .insert(def.name.clone(), dir.to_string_lossy().to_string());
+        Ok(())
+    }
+}
+
+impl Drop for TestFixtures {
+    fn drop(&mut self) {
+        for dir in &self.dirs {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+fn create_repository(dir: &Path, def: &GitFixtureDef) -> Result<(), String> {
+    git(dir, &["init", "--quiet"])?;
+    git(
+        dir,
+        &[
+            "symbolic-ref",
+            "HEAD",
+            &format!("refs/heads/{}", def.branch),
+        ],
+    )?;
+
+    for commit in &def.commits {
+        if let Some(branch) = &commit.branch {
+            let exists = git(dir, &["rev-parse", "--verify", "--quiet", branch]).is_ok();
+            if exists {
+                git(dir, &["checkout", "--quiet", branch])?;
+            } else {
+                git(dir, &["checkout", "--quiet", "-b", branch])?;
+            }
+        }
+        write_files(dir, &commit.files)?;
+        git(dir, &["add", "--all"])?;
+        git(
+            dir,
+            &["commit", "--quiet", "--allow-empty", "-m", &commit.message],
+        )?;
+        if let Some(tag) = &commit.tag {
+            git(dir, &["tag", tag])?;
+        }
+    }
+    Ok(())
+}
+
+fn write_files(dir: &Path, files: &BTreeMap<String, String>) -> Result<(), String> {
+    for (name, contents) in files {
+        let relative = Path::new(name);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!(
+                "file '{}' must be a relative path inside the fixture",
+                name
+            ));
+        }
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", name, e))?;
+        }
+        std::fs::write(&path, contents).map_err(|e| format!("{}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+/// Run git in `dir` with a fixed identity so commits don't depend on user config
+fn git(dir: &Path, args: &[&str]) -> Result<(), String> {
+    git_output(dir, args).map(|_| ())
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "user.name=roxid",
+            "-c",
+            "user.email=roxid@localhost",
+            "-c",
+            "commit.gpgsign=false",
+            "-c",
+            "tag.gpgsign=false",
+        ])
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_fixture() {
+        let defs: Vec<FixtureDef> = serde_yaml::from_str(
+            r##"
+- name: repo
+  git:
+    commits:
+      - message: Initial commit
+        files: { README.md: "# Demo" }
+        tag: v1.0.0
+      - message: Add login
+        branch: feature/login
+        files: { src/login.rs: "fn login() {}" }
+  files:
+    notes.txt: draft
+- name: scratch
+"##,
+        )
+        .unwrap();
+
+        let fixtures = TestFixtures::create(&defs).unwrap();
+        let vars = fixtures.variables();
+        let repo = PathBuf::from(&vars["repo"]);
+        assert!(repo.join("src/login.rs").exists());
+        assert_eq!(vars["repo.branch"], "feature/login");
+        assert_eq!(vars["repo.commit"].len(), 40);
+        assert_eq!(
+            git_output(&repo, &["describe", "--tags", "main"]).unwrap(),
+            "v1.0.0"
+        );
+        assert_eq!(
+            git_output(&repo, &["status", "--porcelain"]).unwrap(),
+            "?? notes.txt"
+        );
+
+        let scratch = PathBuf::from(&vars["scratch"]);
+        assert!(scratch.is_dir());
+
+        drop(fixtures);
+        assert!(!repo.exists());
+        assert!(!scratch.exists());
+    }
+
+    #[test]
+    fn test_git_fixture_without_commits() {
+        let defs: Vec<FixtureDef> =
+            serde_yaml::from_str("- name: empty\n  git:\n    branch: trunk\n").unwrap();
+
+        let fixtures = TestFixtures::create(&defs).unwrap();
+        let vars = fixtures.variables();
+        assert_eq!(vars["empty.branch"], "trunk");
+        assert!(!vars.contains_key("empty.commit"));
+    }
+
+    #[test]
+    fn test_fixture_files_stay_inside() {
+        let def = FixtureDef {
+            name: "bad".to_string(),
+            files: BTreeMap::from([("../escape.txt".to_string(), String::new())]),
+            git: None,
+        };
+        let err = TestFixtures::create(&[def]).unwrap_err();
+        assert!(err.contains("must be a relative path"));
+    }
+}
@@ -2,17 +2,21 @@
 // Provides pipeline test definitions, execution, assertions, and reporting
 
 pub mod assertions;
+pub mod fixtures;
 pub mod parser;
 pub mod reporter;
 pub mod runner;
 
 // Re-export key types
-pub use assertions::{Assertion, AssertionResult};
+pub use assertions::{Assertion, AssertionEvaluator, AssertionResult};
+pub use fixtures::{FixtureDef, TestFixtures};
 pub use parser::TestFileParser;
 pub use reporter::{ReportFormat, TestReporter};
-pub use runner::{TestResult, TestRunner, TestSuiteResult};
+pub use runner::{RunningStep, TestResult, TestRunner, TestSuiteResult};
 
+use crate::execution::faults::Fault;
 use crate::parser::models::Value;
+use crate::runners::hosts::HostOverride;
 
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -49,6 +53,9 @@ pub struct TestDefaults {
     /// Default working directory
     #[serde(default)]
     pub working_dir: Option<String>,
+    /// Default timeout in seconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
 }
 
 /// A single pipeline test definition
@@ -57,21 +64,62 @@ pub struct PipelineTest {
     /// Test name (used in reporting)
     pub name: String,
     /// Path to the pipeline YAML file (relative to test file)
+    #[serde(default)]
     pub pipeline: PathBuf,
+    /// Path to a template to test on its own instead of a pipeline (relative
+    /// to the test file); it's expanded with `parameters` and checked with
+    /// structure assertions, without running anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<PathBuf>,
     /// Variables to set for this test run
     #[serde(default)]
     pub variables: HashMap<String, String>,
     /// Parameters to pass for this test run
     #[serde(default)]
     pub parameters: HashMap<String, serde_yaml::Value>,
-    /// Working directory for execution
+    /// Variable and parameter sets to run the test with, one test per case;
+    /// `{{name}}` in the test name is replaced with the case's value
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cases: Vec<TestCase>,
+    /// Working directory for execution; may refer to a fixture as `$(name)`
     #[serde(default)]
     pub working_dir: Option<String>,
+    /// Temporary directories and git repositories created before the test
+    #[serde(default)]
+    pub fixtures: Vec<FixtureDef>,
+    /// Seconds the pipeline may run before it's cancelled and the test errors
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Failures and delays to force, to check conditions and cleanup steps
+    #[serde(default)]
+    pub faults: Vec<Fault>,
+    /// Hostnames to point at local stand-ins, as `host=address[:port]`
+    #[serde(default)]
+    pub hosts: Vec<HostOverride>,
     /// Assertions to evaluate after execution
     #[serde(default)]
     pub assertions: Vec<AssertionDef>,
 }
 
+/// One row of a test's `cases:` table, layered over the test's own
+/// variables and parameters
+///
+/// ```yaml
+/// - name: "deploy {{env}}"
+///   pipeline: deploy.yml
+///   cases:
+///     - variables: { env: dev }
+///     - variables: { env: prod }
+///       parameters: { approve: true }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestCase {
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_yaml::Value>,
+}
+
 /// An assertion definition as parsed from YAML
 ///
 /// Each variant maps to a YAML key in the assertions list.
@@ -82,6 +130,8 @@ pub struct PipelineTest {
 /// - Bare string: `pipeline_succeeded`
 /// - Key-value: `step_succeeded: Build`
 /// - Key-struct: `step_output_contains: { step: Build, pattern: "..." }`
+/// - Qualified: `step_succeeded: Build` with `in_job: Linux` and/or
+///   `in_stage: CI` beside it, or `step_succeeded: CI/Linux/Build`
 #[derive(Debug, Clone, Serialize)]
 pub enum AssertionDef {
     /// Assert a step succeeded
@@ -93,6 +143,13 @@ pub enum AssertionDef {
     /// Assert a step was skipped
     StepSkipped(String),
 
+    /// Assert a step was canceled
+    StepCanceled(String),
+
+    /// Assert a step has no result at all: left out at compile time or in a
+    /// stage or job that never started
+    StepNotExecuted(String),
+
     /// Assert a job succeeded
     JobSucceeded(String),
 
@@ -102,6 +159,12 @@ pub enum AssertionDef {
     /// Assert a job was skipped
     JobSkipped(String),
 
+    /// Assert a job was canceled
+    JobCanceled(String),
+
+    /// Assert a job has no result at all
+    JobNotExecuted(String),
+
     /// Assert a stage succeeded
     StageSucceeded(String),
 
@@ -111,6 +174,9 @@ pub enum AssertionDef {
     /// Assert a stage was skipped
     StageSkipped(String),
 
+    /// Assert a stage was canceled
+    StageCanceled(String),
+
     /// Assert step output equals a value
     StepOutputEquals(StepOutputAssertion),
 
@@ -129,11 +195,76 @@ pub enum AssertionDef {
     /// Assert a variable contains a pattern
     VariableContains(VariablePatternAssertion),
 
+    /// Assert exactly which variables were substituted into a step's command
+    StepSubstitutedVariables(SubstitutionAssertion),
+
+    /// Assert a step's command got an environment variable (with a value)
+    StepEnvContains(StepEnvAssertion),
+
+    /// Assert how many steps the pipeline or template resolves to
+    ResolvedStepCount(usize),
+
+    /// Assert how many jobs the pipeline or template resolves to
+    ResolvedJobCount(usize),
+
+    /// Assert how many stages the pipeline or template resolves to
+    ResolvedStageCount(usize),
+
+    /// Assert the resolved pipeline or template has a step
+    StepExists(String),
+
+    /// Assert the resolved pipeline or template has a job
+    JobExists(String),
+
+    /// Assert the resolved pipeline or template has a stage
+    StageExists(String),
+
+    /// Assert a resolved step's script contains a pattern
+    StepScriptContains(StepScriptAssertion),
+
     /// Assert the pipeline succeeded overall
     PipelineSucceeded,
 
     /// Assert the pipeline failed overall
     PipelineFailed,
+
+    /// An assertion reported with a custom message instead of the generated one
+    WithMessage {
+        assertion: Box<AssertionDef>,
+        message: String,
+    },
+
+    /// An assertion whose steps and jobs are only looked for in one stage
+    /// and/or job, for names used in several places
+    Scoped {
+        assertion: Box<AssertionDef>,
+        in_stage: Option<String>,
+        in_job: Option<String>,
+    },
+
+    /// Assertions reported together under a name
+    Group(AssertionGroup),
+}
+
+/// A named group of assertions
+///
+/// ```yaml
+/// - group: Build outputs
+///   soft: true
+///   assertions:
+///     - step_succeeded: Package
+///     - step_output_contains: { step: Package, pattern: "zip" }
+///       message: The package step should report the archive it created
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssertionGroup {
+    /// Name shown next to each assertion in reports
+    #[serde(rename = "group", default)]
+    pub name: Option<String>,
+    /// Failures are reported but don't fail the test
+    #[serde(default)]
+    pub soft: bool,
+    pub assertions: Vec<AssertionDef>,
 }
 
 impl<'de> Deserialize<'de> for AssertionDef {
@@ -167,110 +298,138 @@ impl<'de> Deserialize<'de> for AssertionDef {
                 }
             }
 
-            // Handle mappings: `- step_succeeded: Build` or `- step_output_contains: { ... }`
+            // Handle mappings: `- step_succeeded: Build` or `- step_output_contains: { ... }`,
+            // optionally with a `message:` override, and `- group: ...` groups
             fn visit_map<M>(self, mut map: M) -> Result<AssertionDef, M::Error>
             where
                 M: MapAccess<'de>,
             {
-                let key: String = map
-                    .next_key()?
-                    .ok_or_else(|| de::Error::custom("expected assertion key"))?;
-
-                let result = match key.as_str() {
-                    "step_succeeded" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::StepSucceeded(val))
-                    }
-                    "step_failed" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::StepFailed(val))
-                    }
-                    "step_skipped" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::StepSkipped(val))
-                    }
-                    "job_succeeded" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::JobSucceeded(val))
-                    }
-                    "job_failed" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::JobFailed(val))
-                    }
-                    "job_skipped" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::JobSkipped(val))
-                    }
-                    "stage_succeeded" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::StageSucceeded(val))
-                    }
-                    "stage_failed" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::StageFailed(val))
-                    }
-                    "stage_skipped" => {
-                        let val: String = map.next_value()?;
-                        Ok(AssertionDef::StageSkipped(val))
-                    }
-                    "step_output_equals" => {
-                        let val: StepOutputAssertion = map.next_value()?;
-                        Ok(AssertionDef::StepOutputEquals(val))
-                    }
-                    "step_output_contains" => {
-                        let val: StepOutputPatternAssertion = map.next_value()?;
-                        Ok(AssertionDef::StepOutputContains(val))
-                    }
-                    "step_ran_before" => {
-                        let val: OrderAssertion = map.next_value()?;
-                        Ok(AssertionDef::StepRanBefore(val))
-                    }
-                    "steps_ran_in_parallel" => {
-                        let val: ParallelAssertion = map.next_value()?;
-                        Ok(AssertionDef::StepsRanInParallel(val))
-                    }
-                    "variable_equals" => {
-                        let val: VariableAssertion = map.next_value()?;
-                        Ok(AssertionDef::VariableEquals(val))
+                let mut entries: Vec<(String, serde_yaml::Value)> = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+
+                if entries.iter().any(|(key, _)| key == "group") {
+                    let mapping = entries
+                        .into_iter()
+                        .map(|(key, value)| (serde_yaml::Value::String(key), value))
+                        .collect();
+                    return from_yaml(serde_yaml::Value::Mapping(mapping)).map(AssertionDef::Group);
+                }
+
+                let mut take = |name: &str| match entries.iter().position(|(key, _)| key == name) {
+                    Some(index) => from_yaml::<String, M::Error>(entries.remove(index).1).map(Some),
+                    None => Ok(None),
+                };
+                let message = take("message")?;
+                let in_stage = take("in_stage")?;
+                let in_job = take("in_job")?;
+
+                let (key, value) = match entries.len() {
+                    1 => entries.remove(0),
+                    0 => return Err(de::Error::custom("expected assertion key")),
+                    _ => {
+                        let keys: Vec<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
+                        return Err(de::Error::custom(format!(
+                            "expected one assertion per list item, found {}",
+                            keys.join(", ")
+                        )));
                     }
-                    "variable_contains" => {
-                        let val: VariablePatternAssertion = map.next_value()?;
-                        Ok(AssertionDef::VariableContains(val))
+                };
+
+                let assertion = match key.as_str() {
+                    "step_succeeded" => AssertionDef::StepSucceeded(from_yaml(value)?),
+                    "step_failed" => AssertionDef::StepFailed(from_yaml(value)?),
+                    "step_skipped" => AssertionDef::StepSkipped(from_yaml(value)?),
+                    "step_canceled" => AssertionDef::StepCanceled(from_yaml(value)?),
+                    "step_not_executed" => AssertionDef::StepNotExecuted(from_yaml(value)?),
+                    "job_succeeded" => AssertionDef::JobSucceeded(from_yaml(value)?),
+                    "job_failed" => AssertionDef::JobFailed(from_yaml(value)?),
+                    "job_skipped" => AssertionDef::JobSkipped(from_yaml(value)?),
+                    "job_canceled" => AssertionDef::JobCanceled(from_yaml(value)?),
+                    "job_not_executed" => AssertionDef::JobNotExecuted(from_yaml(value)?),
+                    "stage_succeeded" => AssertionDef::StageSucceeded(from_yaml(value)?),
+                    "stage_failed" => AssertionDef::StageFailed(from_yaml(value)?),
+                    "stage_skipped" => AssertionDef::StageSkipped(from_yaml(value)?),
+                    "stage_canceled" => AssertionDef::StageCanceled(from_yaml(value)?),
+                    "step_output_equals" => AssertionDef::StepOutputEquals(from_yaml(value)?),
+                    "step_output_contains" => AssertionDef::StepOutputContains(from_yaml(value)?),
+                    "step_ran_before" => AssertionDef::StepRanBefore(from_yaml(value)?),
+                    "steps_ran_in_parallel" => AssertionDef::StepsRanInParallel(from_yaml(value)?),
+                    "variable_equals" => AssertionDef::VariableEquals(from_yaml(value)?),
+                    "variable_contains" => AssertionDef::VariableContains(from_yaml(value)?),
+                    "step_substituted_variables" => {
+                        AssertionDef::StepSubstitutedVariables(from_yaml(value)?)
                     }
-                    "pipeline_succeeded" => {
-                        // Allow `pipeline_succeeded:` with null/empty value in mapping form
-                        let _: serde_yaml::Value = map.next_value()?;
-                        Ok(AssertionDef::PipelineSucceeded)
+                    "step_env_contains" => AssertionDef::StepEnvContains(from_yaml(value)?),
+                    "resolved_step_count" => AssertionDef::ResolvedStepCount(from_yaml(value)?),
+                    "resolved_job_count" => AssertionDef::ResolvedJobCount(from_yaml(value)?),
+                    "resolved_stage_count" => AssertionDef::ResolvedStageCount(from_yaml(value)?),
+                    "step_exists" => AssertionDef::StepExists(from_yaml(value)?),
+                    "job_exists" => AssertionDef::JobExists(from_yaml(value)?),
+                    "stage_exists" => AssertionDef::StageExists(from_yaml(value)?),
+                    "step_script_contains" => AssertionDef::StepScriptContains(from_yaml(value)?),
+                    // Allow `pipeline_succeeded:` with null/empty value in mapping form
+                    "pipeline_succeeded" => AssertionDef::PipelineSucceeded,
+                    "pipeline_failed" => AssertionDef::PipelineFailed,
+                    _ => {
+                        return Err(de::Error::unknown_field(
+                            &key,
+                            &[
+                                "step_succeeded",
+                                "step_failed",
+                                "step_skipped",
+                                "step_canceled",
+                                "step_not_executed",
+                                "job_succeeded",
+                                "job_failed",
+                                "job_skipped",
+                                "job_canceled",
+                                "job_not_executed",
+                                "stage_succeeded",
+                                "stage_failed",
+                                "stage_skipped",
+                                "stage_canceled",
+                                "step_output_equals",
+                                "step_output_contains",
+                                "step_ran_before",
+                                "steps_ran_in_parallel",
+                                "variable_equals",
+                                "variable_contains",
+                                "step_substituted_variables",
+                                "step_env_contains",
+                                "resolved_step_count",
+                                "resolved_job_count",
+                                "resolved_stage_count",
+                                "step_exists",
+                                "job_exists",
+                                "stage_exists",
+                                "step_script_contains",
+                                "pipeline_succeeded",
+                                "pipeline_failed",
+                                "group",
+                            ],
+                        ))
                     }
-                    "pipeline_failed" => {
-                        let _: serde_yaml::Value = map.next_value()?;
-                        Ok(AssertionDef::PipelineFailed)
+                };
+
+                let assertion = if in_stage.is_some() || in_job.is_some() {
+                    AssertionDef::Scoped {
+                        assertion: Box::new(assertion),
+                        in_stage,
+                        in_job,
                     }
-                    _ => Err(de::Error::unknown_field(
-                        &key,
-                        &[
-                            "step_succeeded",
-                            "step_failed",
-                            "step_skipped",
-                            "job_succeeded",
-                            "job_failed",
-                            "job_skipped",
-                            "stage_succeeded",
-                            "stage_failed",
-                            "stage_skipped",
-                            "step_output_equals",
-                            "step_output_contains",
-                            "step_ran_before",
-                            "steps_ran_in_parallel",
-                            "variable_equals",
-                            "variable_contains",
-                            "pipeline_succeeded",
-                            "pipeline_failed",
-                        ],
-                    )),
+                } else {
+                    assertion
                 };
 
-                result
+                Ok(match message {
+                    Some(message) => AssertionDef::WithMessage {
+                        assertion: Box::new(assertion),
+                        message,
+                    },
+                    None => assertion,
+                })
             }
         }
 
@@ -278,6 +437,15 @@ impl<'de> Deserialize<'de> for AssertionDef {
     }
 }
 
+/// Deserialize an assertion's value, reporting errors through the outer deserializer
+fn from_yaml<T, E>(value: serde_yaml::Value) -> Result<T, E>
+where
+    T: de::DeserializeOwned,
+    E: de::Error,
+{
+    serde_yaml::from_value(value).map_err(E::custom)
+}
+
 /// Assertion for step output equality
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StepOutputAssertion {
@@ -335,20 +503,61 @@ pub struct VariablePatternAssertion {
     pub pattern: String,
 }
 
+/// Assertion for the script of a resolved step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepScriptAssertion {
+    /// Step name
+    pub step: String,
+    /// Substring to search for in the script
+    pub pattern: String,
+}
+
+/// Assertion for the variables substituted into a step's command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubstitutionAssertion {
+    /// Step name
+    pub step: String,
+    /// Variable names expected to be substituted (order-insensitive)
+    #[serde(default)]
+    pub variables: Vec<String>,
+}
+
+/// Assertion for the environment a step's command got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepEnvAssertion {
+    /// Step name
+    pub step: String,
+    /// Environment variable name, e.g. `BUILD_CONFIGURATION`
+    pub name: String,
+    /// Expected value; any value passes if omitted
+    #[serde(default)]
+    pub value: Option<serde_yaml::Value>,
+}
+
 // =============================================================================
 // Conversion helpers
 // =============================================================================
 
 impl AssertionDef {
     /// Convert this YAML assertion definition into an evaluable `Assertion`
-    pub fn to_assertion(&self) -> Assertion {
-        match self {
+    ///
+    /// Returns `None` for groups, which hold several assertions; use
+    /// [`AssertionDef::evaluate`] to evaluate those, and scoped assertions
+    /// within their stage or job.
+    pub fn to_assertion(&self) -> Option<Assertion> {
+        let assertion = match self {
             AssertionDef::StepSucceeded(name) => Assertion::StepSucceeded { step: name.clone() },
             AssertionDef::StepFailed(name) => Assertion::StepFailed { step: name.clone() },
             AssertionDef::StepSkipped(name) => Assertion::StepSkipped { step: name.clone() },
+            AssertionDef::StepCanceled(name) => Assertion::StepCanceled { step: name.clone() },
+            AssertionDef::StepNotExecuted(name) => {
+                Assertion::StepNotExecuted { step: name.clone() }
+            }
             AssertionDef::JobSucceeded(name) => Assertion::JobSucceeded { job: name.clone() },
             AssertionDef::JobFailed(name) => Assertion::JobFailed { job: name.clone() },
             AssertionDef::JobSkipped(name) => Assertion::JobSkipped { job: name.clone() },
+            AssertionDef::JobCanceled(name) => Assertion::JobCanceled { job: name.clone() },
+            AssertionDef::JobNotExecuted(name) => Assertion::JobNotExecuted { job: name.clone() },
             AssertionDef::StageSucceeded(name) => Assertion::StageSucceeded {
                 stage: name.clone(),
             },
@@ -358,6 +567,9 @@ impl AssertionDef {
             AssertionDef::StageSkipped(name) => Assertion::StageSkipped {
                 stage: name.clone(),
             },
+            AssertionDef::StageCanceled(name) => Assertion::StageCanceled {
+                stage: name.clone(),
+            },
             AssertionDef::StepOutputEquals(a) => Assertion::StepOutputEquals {
                 step: a.step.clone(),
                 output: a.output.clone(),
@@ -383,8 +595,101 @@ impl AssertionDef {
                 name: a.name.clone(),
                 pattern: a.pattern.clone(),
             },
+            AssertionDef::StepSubstitutedVariables(a) => Assertion::StepSubstitutedVariables {
+                step: a.step.clone(),
+                variables: a.variables.clone(),
+            },
+            AssertionDef::StepEnvContains(a) => Assertion::StepEnvContains {
+                step: a.step.clone(),
+                name: a.name.clone(),
+                value: a.value.as_ref().map(yaml_to_value),
+            },
+            AssertionDef::ResolvedStepCount(count) => {
+                Assertion::ResolvedStepCount { count: *count }
+            }
+            AssertionDef::ResolvedJobCount(count) => Assertion::ResolvedJobCount { count: *count },
+            AssertionDef::ResolvedStageCount(count) => {
+                Assertion::ResolvedStageCount { count: *count }
+            }
+            AssertionDef::StepExists(name) => Assertion::StepExists { step: name.clone() },
+            AssertionDef::JobExists(name) => Assertion::JobExists { job: name.clone() },
+            AssertionDef::StageExists(name) => Assertion::StageExists {
+                stage: name.clone(),
+            },
+            AssertionDef::StepScriptContains(a) => Assertion::StepScriptContains {
+                step: a.step.clone(),
+                pattern: a.pattern.clone(),
+            },
             AssertionDef::PipelineSucceeded => Assertion::PipelineSucceeded,
             AssertionDef::PipelineFailed => Assertion::PipelineFailed,
+            AssertionDef::WithMessage { assertion, .. }
+            | AssertionDef::Scoped { assertion, .. } => return assertion.to_assertion(),
+            AssertionDef::Group(_) => return None,
+        };
+        Some(assertion)
+    }
+
+    /// Whether every assertion here checks the resolved pipeline rather than
+    /// execution results
+    pub fn is_structural(&self) -> bool {
+        match self {
+            AssertionDef::WithMessage { assertion, .. }
+            | AssertionDef::Scoped { assertion, .. } => assertion.is_structural(),
+            AssertionDef::Group(group) => group.assertions.iter().all(|a| a.is_structural()),
+            _ => self
+                .to_assertion()
+                .is_some_and(|assertion| assertion.is_structural()),
+        }
+    }
+
+    /// Evaluate this definition, flattening groups into one result per
+    /// assertion and applying `message:` overrides
+    pub fn evaluate(&self, evaluator: &AssertionEvaluator) -> Vec<AssertionResult> {
+        match self {
+            AssertionDef::Group(group) => group
+                .assertions
+                .iter()
+                .flat_map(|def| def.evaluate(evaluator))
+                .map(|mut result| {
+                    result.soft |= group.soft;
+                    result.group = match (&group.name, result.group) {
+                        (Some(name), Some(inner)) => Some(format!("{} / {}", name, inner)),
+                        (name, inner) => inner.or_else(|| name.clone()),
+                    };
+                    result
+                })
+                .collect(),
+            AssertionDef::WithMessage { assertion, message } => assertion
+                .evaluate(evaluator)
+                .into_iter()
+                .map(|mut result| {
+                    result.message = message.clone();
+                    result
+                })
+                .collect(),
+            AssertionDef::Scoped {
+                assertion,
+                in_stage,
+                in_job,
+            } => {
+                let scope: Vec<&str> = [in_stage.as_deref(), in_job.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                assertion
+                    .evaluate(&evaluator.within(in_stage.as_deref(), in_job.as_deref()))
+                    .into_iter()
+                    .map(|mut result| {
+                        result.assertion = format!("{} in {}", result.assertion, scope.join("/"));
+                        result
+                    })
+                    .collect()
+            }
+            _ => self
+                .to_assertion()
+                .map(|assertion| evaluator.evaluate(&assertion))
+                .into_iter()
+                .collect(),
         }
     }
 }
@@ -425,7 +730,7 @@ mod tests {
     #[test]
     fn test_assertion_def_to_assertion_step_succeeded() {
         let def = AssertionDef::StepSucceeded("Build".to_string());
-        let assertion = def.to_assertion();
+        let assertion = def.to_assertion().unwrap();
         assert!(matches!(
             assertion,
             Assertion::StepSucceeded { step } if step == "Build"
@@ -438,7 +743,7 @@ mod tests {
             name: "BUILD_CONFIG".to_string(),
             expected: serde_yaml::Value::String("Release".to_string()),
         });
-        let assertion = def.to_assertion();
+        let assertion = def.to_assertion().unwrap();
         assert!(matches!(
             assertion,
             Assertion::VariableEquals { name, expected }
@@ -446,6 +751,125 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_assertion_def_step_substituted_variables() {
+        let yaml = r#"
+step_substituted_variables:
+  step: Build
+  variables: [Build.Configuration, version]
+"#;
+        let def: AssertionDef = serde_yaml::from_str(yaml).unwrap();
+        assert!(matches!(
+            def.to_assertion().unwrap(),
+            Assertion::StepSubstitutedVariables { step, variables }
+                if step == "Build" && variables.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_assertion_def_step_env_contains() {
+        let yaml = r#"
+- step_env_contains: { step: Build, name: CONFIGURATION, value: Release }
+- step_env_contains: { step: Build, name: REPLICAS, value: 3 }
+- step_env_contains: { step: Build, name: TOKEN }
+"#;
+        let defs: Vec<AssertionDef> = serde_yaml::from_str(yaml).unwrap();
+        let assertions: Vec<String> = defs
+            .iter()
+            .map(|def| def.to_assertion().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            assertions,
+            [
+                "step_env_contains(Build, CONFIGURATION=Release)",
+                "step_env_contains(Build, REPLICAS=3)",
+                "step_env_contains(Build, TOKEN)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assertion_groups_and_messages() {
+        let yaml = r#"
+- pipeline_succeeded:
+  message: The pipeline should pass
+- group: Build outputs
+  soft: true
+  assertions:
+    - step_succeeded: Package
+      message: Package step should run
+    - variable_equals: { name: version, expected: "1.0" }
+"#;
+        let defs: Vec<AssertionDef> = serde_yaml::from_str(yaml).unwrap();
+        assert!(matches!(&defs[1], AssertionDef::Group(g) if g.soft && g.assertions.len() == 2));
+
+        let result = crate::execution::ExecutionResult {
+            run_id: String::new(),
+            stages: Vec::new(),
+            duration: std::time::Duration::ZERO,
+            success: true,
+            variables: HashMap::from([("version".to_string(), "1.0".to_string())]),
+            images: Default::default(),
+            started_at: None,
+            finished_at: None,
+        };
+        let evaluator = AssertionEvaluator::new(&result);
+        let results: Vec<AssertionResult> = defs
+            .iter()
+            .flat_map(|def| def.evaluate(&evaluator))
+            .collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].passed);
+        assert_eq!(results[0].message, "The pipeline should pass");
+        assert!(results[1].is_soft_failure());
+        assert_eq!(results[1].message, "Package step should run");
+        assert_eq!(results[1].group.as_deref(), Some("Build outputs"));
+        assert!(results[2].passed);
+
+        let err = serde_yaml::from_str::<AssertionDef>("{step_succeeded: A, step_failed: B}")
+            .unwrap_err();
+        assert!(err.to_string().contains("one assertion per list item"));
+    }
+
+    #[test]
+    fn test_scoped_assertions() {
+        let yaml = r#"
+- step_succeeded: Build
+  in_job: Linux
+- step_failed: Build
+  in_stage: CI
+  in_job: Windows
+  message: Windows builds are broken
+"#;
+        let defs: Vec<AssertionDef> = serde_yaml::from_str(yaml).unwrap();
+        assert!(matches!(
+            &defs[0],
+            AssertionDef::Scoped { in_job: Some(job), in_stage: None, .. } if job == "Linux"
+        ));
+
+        let result = crate::execution::ExecutionResult {
+            run_id: String::new(),
+            stages: Vec::new(),
+            duration: std::time::Duration::ZERO,
+            success: false,
+            variables: HashMap::new(),
+            images: Default::default(),
+            started_at: None,
+            finished_at: None,
+        };
+        let evaluator = AssertionEvaluator::new(&result);
+        let results: Vec<AssertionResult> = defs
+            .iter()
+            .flat_map(|def| def.evaluate(&evaluator))
+            .collect();
+
+        assert!(results[0].message.contains("not found"));
+        assert_eq!(results[0].assertion, "step_succeeded(build) in Linux");
+        assert_eq!(results[1].assertion, "step_failed(build) in CI/Windows");
+        assert_eq!(results[1].message, "Windows builds are broken");
+    }
+
     #[test]
     fn test_yaml_to_value_primitives() {
         assert_eq!(yaml_to_value(&serde_yaml::Value::Null), Value::Null);
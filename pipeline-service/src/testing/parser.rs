@@ -1,7 +1,7 @@
 // Test File Parser
 // Loads and validates roxid-test.yml test suite files
 
-use crate::testing::{PipelineTest, TestDefaults, TestSuite};
+use crate::testing::{PipelineTest, TestCase, TestDefaults, TestSuite};
 
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -54,7 +54,8 @@ impl From<serde_yaml::Error> for TestParseError {
 impl TestFileParser {
     /// Parse a test suite from a YAML string
     pub fn parse(content: &str) -> Result<TestSuite, TestParseError> {
-        let suite: TestSuite = serde_yaml::from_str(content)?;
+        let mut suite: TestSuite = serde_yaml::from_str(content)?;
+        suite.tests = Self::expand_cases(std::mem::take(&mut suite.tests))?;
         Self::validate(&suite)?;
         Ok(suite)
     }
@@ -68,14 +69,18 @@ impl TestFileParser {
         let content = fs::read_to_string(path)?;
         let mut suite = Self::parse(&content)?;
 
-        // Resolve pipeline paths relative to the test file's directory
+        // Resolve pipeline and template paths relative to the test file's directory
         let base_dir = path
             .parent()
             .unwrap_or_else(|| Path::new("."))
             .to_path_buf();
 
         for test in &mut suite.tests {
-            if test.pipeline.is_relative() {
+            if let Some(template) = &mut test.template {
+                if template.is_relative() {
+                    *template = base_dir.join(&*template);
+                }
+            } else if test.pipeline.is_relative() {
                 test.pipeline = base_dir.join(&test.pipeline);
             }
         }
@@ -143,6 +148,37 @@ impl TestFileParser {
         if test.working_dir.is_none() {
             test.working_dir.clone_from(&defaults.working_dir);
         }
+
+        if test.timeout.is_none() {
+            test.timeout = defaults.timeout;
+        }
+    }
+
+    /// Replace each test that has a `cases:` table with one test per case
+    fn expand_cases(tests: Vec<PipelineTest>) -> Result<Vec<PipelineTest>, TestParseError> {
+        let mut expanded = Vec::new();
+        for mut test in tests {
+            let cases = std::mem::take(&mut test.cases);
+            if cases.is_empty() {
+                expanded.push(test);
+                continue;
+            }
+            for (i, case) in cases.into_iter().enumerate() {
+                let mut instance = test.clone();
+                instance.name = case_name(&test.name, &case, &test).map_err(|key| {
+                    TestParseError::ValidationError(format!(
+                        "Test '{}': case {} has no variable or parameter '{}'",
+                        test.name,
+                        i + 1,
+                        key
+                    ))
+                })?;
+                instance.variables.extend(case.variables);
+                instance.parameters.extend(case.parameters);
+                expanded.push(instance);
+            }
+        }
+        Ok(expanded)
     }
 
     /// Validate a test suite
@@ -161,11 +197,31 @@ impl TestFileParser {
                 )));
             }
 
-            if test.pipeline.as_os_str().is_empty() {
-                return Err(TestParseError::ValidationError(format!(
-                    "Test '{}' must specify a pipeline file",
-                    test.name
-                )));
+            match &test.template {
+                None if test.pipeline.as_os_str().is_empty() => {
+                    return Err(TestParseError::ValidationError(format!(
+                        "Test '{}' must specify a pipeline file",
+                        test.name
+                    )));
+                }
+                Some(_) if !test.pipeline.as_os_str().is_empty() => {
+                    return Err(TestParseError::ValidationError(format!(
+                        "Test '{}' must specify a pipeline or a template, not both",
+                        test.name
+                    )));
+                }
+                Some(_) => {
+                    if let Some(def) = test.assertions.iter().find(|a| !a.is_structural()) {
+                        return Err(TestParseError::ValidationError(format!(
+                            "Test '{}' tests a template, which doesn't run; {} needs a pipeline",
+                            test.name,
+                            def.to_assertion()
+                                .map(|a| a.to_string())
+                                .unwrap_or_else(|| "an assertion group".to_string())
+                        )));
+                    }
+                }
+                None => {}
             }
         }
 
@@ -184,6 +240,61 @@ impl TestFileParser {
     }
 }
 
+/// A case's test name: `{{name}}` in `template` replaced with the case's
+/// variable or parameter (or else the test's own), or the case's values in
+/// brackets when the template has no placeholders. Errs with an unknown name.
+fn case_name(template: &str, case: &TestCase, test: &PipelineTest) -> Result<String, String> {
+    let lookup = |key: &str| {
+        case.variables
+            .get(key)
+            .cloned()
+            .or_else(|| case.parameters.get(key).map(yaml_scalar))
+            .or_else(|| test.variables.get(key).cloned())
+            .or_else(|| test.parameters.get(key).map(yaml_scalar))
+    };
+
+    if !template.contains("{{") {
+        let mut values: Vec<String> = case
+            .variables
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .chain(
+                case.parameters
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, yaml_scalar(v))),
+            )
+            .collect();
+        values.sort();
+        return Ok(format!("{} [{}]", template, values.join(", ")));
+    }
+
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let key = rest[start + 2..start + end].trim();
+        name.push_str(&rest[..start]);
+        name.push_str(&lookup(key).ok_or_else(|| key.to_string())?);
+        rest = &rest[start + end + 2..];
+    }
+    name.push_str(rest);
+    Ok(name)
+}
+
+/// A YAML value as it reads in a test name
+fn yaml_scalar(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(s) => s.clone(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,6 +336,11 @@ tests:
     pipeline: azure-pipelines.yml
     variables:
       BUILD_REASON: PullRequest
+    faults:
+      - step: Publish
+        exit: 2
+      - job: Tests
+        ms: 500
     assertions:
       - step_skipped: Deploy
       - pipeline_succeeded
@@ -233,6 +349,8 @@ tests:
         assert_eq!(suite.name, Some("My test suite".to_string()));
         assert!(suite.defaults.is_some());
         assert_eq!(suite.tests.len(), 2);
+        assert_eq!(suite.tests[1].faults.len(), 2);
+        assert_eq!(suite.tests[1].faults[0].exit, Some(2));
     }
 
     #[test]
@@ -263,6 +381,37 @@ tests:
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_template_tests_only_check_structure() {
+        let yaml = r#"
+tests:
+  - name: "Template"
+    template: steps/build.yml
+    assertions:
+      - step_exists: compile
+"#;
+        assert!(TestFileParser::parse(yaml).is_ok());
+
+        let yaml = r#"
+tests:
+  - name: "Template"
+    template: steps/build.yml
+    assertions:
+      - step_succeeded: compile
+"#;
+        let err = TestFileParser::parse(yaml).unwrap_err().to_string();
+        assert!(err.contains("needs a pipeline"), "{}", err);
+
+        let yaml = r#"
+tests:
+  - name: "Both"
+    pipeline: pipeline.yml
+    template: steps/build.yml
+    assertions: []
+"#;
+        assert!(TestFileParser::parse(yaml).is_err());
+    }
+
     #[test]
     fn test_apply_defaults() {
         let defaults = TestDefaults {
@@ -274,18 +423,25 @@ tests:
             },
             parameters: std::collections::HashMap::new(),
             working_dir: Some("/tmp".to_string()),
+            timeout: None,
         };
 
         let mut test = PipelineTest {
             name: "test".to_string(),
             pipeline: PathBuf::from("pipeline.yml"),
+            template: None,
             variables: {
                 let mut m = std::collections::HashMap::new();
                 m.insert("ENV".to_string(), "prod".to_string()); // Should NOT be overridden
                 m
             },
             parameters: std::collections::HashMap::new(),
+            cases: Vec::new(),
             working_dir: None,
+            fixtures: vec![],
+            timeout: None,
+            faults: vec![],
+            hosts: vec![],
             assertions: vec![],
         };
 
@@ -295,6 +451,69 @@ tests:
         assert_eq!(test.working_dir, Some("/tmp".to_string())); // Default applied
     }
 
+    #[test]
+    fn test_expand_cases() {
+        let yaml = r#"
+tests:
+  - name: "deploy {{env}} to {{ region }}"
+    pipeline: deploy.yml
+    variables:
+      region: westeurope
+      verbose: "true"
+    cases:
+      - variables: { env: dev }
+      - variables: { env: prod, region: northeurope }
+        parameters: { approve: true }
+    assertions:
+      - pipeline_succeeded
+  - name: build
+    pipeline: build.yml
+    cases:
+      - parameters: { config: Release, arch: arm64 }
+"#;
+        let suite = TestFileParser::parse(yaml).unwrap();
+        let names: Vec<_> = suite.tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "deploy dev to westeurope",
+                "deploy prod to northeurope",
+                "build [arch=arm64, config=Release]"
+            ]
+        );
+        let prod = &suite.tests[1];
+        assert_eq!(prod.variables["region"], "northeurope");
+        assert_eq!(prod.variables["verbose"], "true");
+        assert_eq!(prod.parameters["approve"], serde_yaml::Value::Bool(true));
+        assert_eq!(prod.assertions.len(), 1);
+        assert!(suite.tests.iter().all(|t| t.cases.is_empty()));
+
+        let unknown = r#"
+tests:
+  - name: "deploy {{envv}}"
+    pipeline: deploy.yml
+    cases:
+      - variables: { env: dev }
+"#;
+        let err = TestFileParser::parse(unknown).unwrap_err().to_string();
+        assert!(
+            err.contains("case 1 has no variable or parameter 'envv'"),
+            "{}",
+            err
+        );
+
+        let repeated = r#"
+tests:
+  - name: "deploy {{env}}"
+    pipeline: deploy.yml
+    cases:
+      - variables: { env: dev }
+      - variables: { env: dev, debug: "1" }
+"#;
+        let err = TestFileParser::parse(repeated).unwrap_err().to_string();
+        assert!(err.contains("Duplicate test name: 'deploy dev'"), "{}", err);
+    }
+
     #[test]
     fn test_parse_file_not_found() {
         let result = TestFileParser::parse_file(Path::new("/nonexistent/roxid-test.yml"));
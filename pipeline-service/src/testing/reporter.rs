@@ -1,7 +1,9 @@
 // Test Reporter
-// Generates test output in JUnit XML, TAP, and terminal formats
+// Generates test output in JUnit XML, TAP, terminal and markdown formats
 
-use crate::testing::runner::TestSuiteResult;
+use crate::execution::markdown_summary::{cell, fenced, format_duration};
+use crate::testing::assertions::AssertionResult;
+use crate::testing::runner::{TestResult, TestSuiteResult};
 
 use std::fmt;
 
@@ -64,17 +66,19 @@ impl TestReporter {
 
         // Test suite element
         xml.push_str(&format!(
-            "<testsuites tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\">\n",
+            "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
             results.total,
             results.failed,
+            results.errored,
             results.duration.as_secs_f64()
         ));
 
         xml.push_str(&format!(
-            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\">\n",
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
             xml_escape(&results.suite_name),
             results.total,
             results.failed,
+            results.errored,
             results.duration.as_secs_f64()
         ));
 
@@ -85,8 +89,34 @@ impl TestReporter {
                 test.duration.as_secs_f64()
             ));
 
-            if test.passed {
+            let soft_failures: Vec<_> = test
+                .assertions
+                .iter()
+                .filter(|a| a.is_soft_failure())
+                .collect();
+
+            if test.passed && soft_failures.is_empty() {
                 xml.push_str(" />\n");
+            } else if test.passed {
+                xml.push_str(">\n      <system-out>\n");
+                for assertion in soft_failures {
+                    xml.push_str(&format!(
+                        "        SOFT FAIL: {}\n",
+                        xml_escape(&assertion_label(assertion))
+                    ));
+                }
+                xml.push_str("      </system-out>\n");
+                xml.push_str("    </testcase>\n");
+            } else if test.errored {
+                xml.push_str(&format!(
+                    ">\n      <error message=\"{}\">\n",
+                    xml_escape(test.failure_message.as_deref().unwrap_or("Test errored"))
+                ));
+                for line in running_steps_dump(test) {
+                    xml.push_str(&format!("        {}\n", xml_escape(&line)));
+                }
+                xml.push_str("      </error>\n");
+                xml.push_str("    </testcase>\n");
             } else {
                 xml.push_str(">\n");
 
@@ -104,8 +134,9 @@ impl TestReporter {
                 for assertion in &test.assertions {
                     if !assertion.passed {
                         xml.push_str(&format!(
-                            "        FAIL: {}\n",
-                            xml_escape(&assertion.message)
+                            "        {}: {}\n",
+                            if assertion.soft { "SOFT FAIL" } else { "FAIL" },
+                            xml_escape(&assertion_label(assertion))
                         ));
                         if let Some(ref detail) = assertion.failure_detail {
                             xml.push_str(&format!("          {}\n", xml_escape(detail)));
@@ -134,8 +165,19 @@ impl TestReporter {
         for (i, test) in results.results.iter().enumerate() {
             let test_num = i + 1;
 
+            let soft_failures: Vec<_> = test
+                .assertions
+                .iter()
+                .filter(|a| a.is_soft_failure())
+                .collect();
+
             if test.passed {
                 tap.push_str(&format!("ok {} - {}\n", test_num, test.name));
+                if !soft_failures.is_empty() {
+                    tap.push_str("  ---\n");
+                    push_tap_failures(&mut tap, "soft_failures", &soft_failures);
+                    tap.push_str("  ...\n");
+                }
             } else {
                 tap.push_str(&format!("not ok {} - {}\n", test_num, test.name));
 
@@ -147,29 +189,39 @@ impl TestReporter {
                     tap.push_str(&format!("  message: \"{}\"\n", msg));
                 }
 
-                // List failed assertions
-                let failed: Vec<_> = test.assertions.iter().filter(|a| !a.passed).collect();
-                if !failed.is_empty() {
-                    tap.push_str("  failures:\n");
-                    for assertion in failed {
-                        tap.push_str(&format!("    - assertion: \"{}\"\n", assertion.assertion));
-                        tap.push_str(&format!("      message: \"{}\"\n", assertion.message));
-                        if let Some(ref detail) = assertion.failure_detail {
-                            tap.push_str(&format!("      detail: \"{}\"\n", detail));
+                if test.errored {
+                    tap.push_str("  severity: error\n");
+                    if !test.running_steps.is_empty() {
+                        tap.push_str("  running_steps:\n");
+                        for line in running_steps_dump(test) {
+                            tap.push_str(&format!("    {}\n", line));
                         }
                     }
                 }
 
+                // List failed assertions
+                let failed: Vec<_> = test
+                    .assertions
+                    .iter()
+                    .filter(|a| a.is_hard_failure())
+                    .collect();
+                push_tap_failures(&mut tap, "failures", &failed);
+                push_tap_failures(&mut tap, "soft_failures", &soft_failures);
+
                 tap.push_str("  ...\n");
             }
         }
 
         // Summary comment
         tap.push_str(&format!(
-            "# tests {}\n# pass {}\n# fail {}\n# duration {:.3}s\n",
-            results.total,
-            results.passed,
-            results.failed,
+            "# tests {}\n# pass {}\n# fail {}\n",
+            results.total, results.passed, results.failed,
+        ));
+        if results.errored > 0 {
+            tap.push_str(&format!("# error {}\n", results.errored));
+        }
+        tap.push_str(&format!(
+            "# duration {:.3}s\n",
             results.duration.as_secs_f64()
         ));
 
@@ -187,8 +239,13 @@ impl TestReporter {
 
         // Individual test results
         for test in &results.results {
-            let status = if test.passed { "PASS" } else { "FAIL" };
-            let symbol = if test.passed { "+" } else { "x" };
+            let (status, symbol) = if test.passed {
+                ("PASS", "+")
+            } else if test.errored {
+                ("ERROR", "!")
+            } else {
+                ("FAIL", "x")
+            };
 
             out.push_str(&format!(
                 "  [{}] {} ({:.2}s) {}\n",
@@ -198,14 +255,26 @@ impl TestReporter {
                 test.name,
             ));
 
-            // Show assertion details for failures
-            if !test.passed {
-                for assertion in &test.assertions {
-                    if !assertion.passed {
-                        out.push_str(&format!("       FAIL: {}\n", assertion.message));
-                        if let Some(ref detail) = assertion.failure_detail {
-                            out.push_str(&format!("             {}\n", detail));
-                        }
+            if test.errored {
+                if let Some(ref msg) = test.failure_message {
+                    out.push_str(&format!("       {}\n", msg));
+                }
+                for line in running_steps_dump(test) {
+                    out.push_str(&format!("         {}\n", line));
+                }
+            }
+
+            // Show assertion details for failures; soft failures are shown
+            // even when the test passed
+            for assertion in &test.assertions {
+                if !assertion.passed {
+                    out.push_str(&format!(
+                        "       {}: {}\n",
+                        if assertion.soft { "WARN" } else { "FAIL" },
+                        assertion_label(assertion)
+                    ));
+                    if let Some(ref detail) = assertion.failure_detail {
+                        out.push_str(&format!("             {}\n", detail));
                     }
                 }
             }
@@ -215,12 +284,20 @@ impl TestReporter {
         out.push_str(&"-".repeat(60));
         out.push('\n');
 
-        let status_line = if results.failed == 0 {
+        let status_line = if results.all_passed() {
             format!(
                 "  All {} tests passed ({:.2}s)",
                 results.total,
                 results.duration.as_secs_f64()
             )
+        } else if results.errored > 0 {
+            format!(
+                "  {} of {} tests failed, {} errored ({:.2}s)",
+                results.failed,
+                results.total,
+                results.errored,
+                results.duration.as_secs_f64()
+            )
         } else {
             format!(
                 "  {} of {} tests failed ({:.2}s)",
@@ -239,6 +316,110 @@ impl TestReporter {
         out.push('\n');
         out
     }
+
+    /// Generate a compact markdown summary of test suites (`--summary-md`),
+    /// for posting as a pull request comment
+    pub fn to_markdown(suites: &[TestSuiteResult]) -> String {
+        let count = |f: fn(&TestSuiteResult) -> usize| suites.iter().map(f).sum::<usize>();
+        let (passed, failed, errored, skipped) = (
+            count(|s| s.passed),
+            count(|s| s.failed),
+            count(|s| s.errored),
+            count(|s| s.skipped),
+        );
+        let duration = suites.iter().map(|s| s.duration).sum();
+
+        let mut out = format!(
+            "### {} {} of {} tests passed in {}\n",
+            if failed + errored == 0 { "✅" } else { "❌" },
+            passed,
+            passed + failed + errored + skipped,
+            format_duration(duration)
+        );
+
+        for suite in suites {
+            out.push_str(&format!("\n#### {}\n\n", cell(&suite.suite_name)));
+            out.push_str("| Test | Result | Duration |\n");
+            out.push_str("|------|--------|----------|\n");
+            for test in &suite.results {
+                let result = if test.passed {
+                    "✅ Passed"
+                } else if test.errored {
+                    "⏱️ Errored"
+                } else {
+                    "❌ Failed"
+                };
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    cell(&test.name),
+                    result,
+                    format_duration(test.duration)
+                ));
+            }
+
+            for test in suite.results.iter().filter(|t| !t.passed) {
+                let mut lines: Vec<String> = test.failure_message.iter().cloned().collect();
+                lines.extend(running_steps_dump(test));
+                for assertion in test.assertions.iter().filter(|a| a.is_hard_failure()) {
+                    lines.push(format!("FAIL: {}", assertion_label(assertion)));
+                    if let Some(detail) = &assertion.failure_detail {
+                        lines.push(format!("      {}", detail));
+                    }
+                }
+                out.push_str(&format!(
+                    "\n<details><summary>❌ {}</summary>\n\n",
+                    cell(&test.name)
+                ));
+                if !lines.is_empty() {
+                    out.push_str(&fenced(&lines.join("\n")));
+                }
+                out.push_str("</details>\n");
+            }
+        }
+        out
+    }
+}
+
+/// Assertion message prefixed with its group, if any
+fn assertion_label(assertion: &AssertionResult) -> String {
+    match &assertion.group {
+        Some(group) => format!("[{}] {}", group, assertion.message),
+        None => assertion.message.clone(),
+    }
+}
+
+/// Lines describing the steps that were running when a test timed out
+fn running_steps_dump(test: &TestResult) -> Vec<String> {
+    test.running_steps
+        .iter()
+        .map(|step| {
+            format!(
+                "- {}/{}/{} (running {:.1}s)",
+                step.stage,
+                step.job,
+                step.step,
+                step.running_for.as_secs_f64()
+            )
+        })
+        .collect()
+}
+
+/// Append a TAP diagnostics list of failed assertions
+fn push_tap_failures(tap: &mut String, key: &str, failed: &[&AssertionResult]) {
+    if failed.is_empty() {
+        return;
+    }
+    tap.push_str(&format!("  {}:\n", key));
+    for assertion in failed {
+        tap.push_str(&format!("    - assertion: \"{}\"\n", assertion.assertion));
+        if let Some(ref group) = assertion.group {
+            tap.push_str(&format!("      group: \"{}\"\n", group));
+        }
+        tap.push_str(&format!("      message: \"{}\"\n", assertion.message));
+        if let Some(ref detail) = assertion.failure_detail {
+            tap.push_str(&format!("      detail: \"{}\"\n", detail));
+        }
+    }
 }
 
 /// Escape special XML characters
@@ -253,23 +434,26 @@ fn xml_escape(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testing::assertions::AssertionResult;
-    use crate::testing::runner::TestResult;
+    use crate::testing::runner::RunningStep;
     use std::time::Duration;
 
     fn make_passing_test(name: &str) -> TestResult {
         TestResult {
             name: name.to_string(),
             passed: true,
+            errored: false,
             duration: Duration::from_millis(150),
             assertions: vec![AssertionResult {
                 assertion: "pipeline_succeeded".to_string(),
                 passed: true,
                 message: "Pipeline completed successfully".to_string(),
                 failure_detail: None,
+                group: None,
+                soft: false,
             }],
             failure_message: None,
             pipeline_path: "pipeline.yml".to_string(),
+            running_steps: vec![],
         }
     }
 
@@ -277,6 +461,7 @@ mod tests {
         TestResult {
             name: name.to_string(),
             passed: false,
+            errored: false,
             duration: Duration::from_millis(300),
             assertions: vec![
                 AssertionResult {
@@ -284,6 +469,8 @@ mod tests {
                     passed: true,
                     message: "Step 'Build' has status Succeeded".to_string(),
                     failure_detail: None,
+                    group: None,
+                    soft: false,
                 },
                 AssertionResult {
                     assertion: "step_succeeded(Deploy)".to_string(),
@@ -292,10 +479,13 @@ mod tests {
                     failure_detail: Some(
                         "Actual status: Failed, error: connection timeout".to_string(),
                     ),
+                    group: None,
+                    soft: false,
                 },
             ],
             failure_message: Some("1 of 2 assertions failed".to_string()),
             pipeline_path: "pipeline.yml".to_string(),
+            running_steps: vec![],
         }
     }
 
@@ -310,6 +500,7 @@ mod tests {
             total: 3,
             passed: 2,
             failed: 1,
+            errored: 0,
             skipped: 0,
             duration: Duration::from_secs(2),
         }
@@ -358,6 +549,19 @@ mod tests {
         assert!(terminal.contains("1 of 3 tests failed"));
     }
 
+    #[test]
+    fn test_markdown_output() {
+        let markdown = TestReporter::to_markdown(&[make_suite_result()]);
+
+        assert!(markdown.starts_with("### ❌ 2 of 3 tests passed in 2.00s\n"));
+        assert!(markdown.contains("#### Integration Tests\n"));
+        assert!(markdown.contains("| Build succeeds | ✅ Passed | 0.15s |\n"));
+        assert!(markdown.contains("| Deploy works | ❌ Failed | 0.30s |\n"));
+        assert!(markdown.contains("<summary>❌ Deploy works</summary>"));
+        assert!(markdown.contains("FAIL: Step 'Deploy' expected Succeeded but was Failed\n"));
+        assert!(!markdown.contains("FAIL: Step 'Build'"));
+    }
+
     #[test]
     fn test_terminal_all_pass() {
         let results = TestSuiteResult {
@@ -366,6 +570,7 @@ mod tests {
             total: 2,
             passed: 2,
             failed: 0,
+            errored: 0,
             skipped: 0,
             duration: Duration::from_millis(500),
         };
@@ -373,6 +578,80 @@ mod tests {
         assert!(terminal.contains("All 2 tests passed"));
     }
 
+    #[test]
+    fn test_soft_failures_reported_for_passing_test() {
+        let mut test = make_passing_test("Outputs");
+        test.assertions.push(AssertionResult {
+            assertion: "step_output_contains(Package, \"zip\")".to_string(),
+            passed: false,
+            message: "Package should report its archive".to_string(),
+            failure_detail: None,
+            group: Some("Build outputs".to_string()),
+            soft: true,
+        });
+        let results = TestSuiteResult {
+            suite_name: "Soft".to_string(),
+            results: vec![test],
+            total: 1,
+            passed: 1,
+            failed: 0,
+            errored: 0,
+            skipped: 0,
+            duration: Duration::from_millis(100),
+        };
+
+        let terminal = TestReporter::to_terminal(&results);
+        assert!(terminal.contains("[+] PASS"));
+        assert!(terminal.contains("WARN: [Build outputs] Package should report its archive"));
+
+        let xml = TestReporter::to_junit_xml(&results);
+        assert!(xml.contains("<system-out>"));
+        assert!(!xml.contains("<failure"));
+
+        let tap = TestReporter::to_tap(&results);
+        assert!(tap.contains("ok 1 - Outputs"));
+        assert!(tap.contains("soft_failures:"));
+    }
+
+    #[test]
+    fn test_errored_test_reported_with_running_steps() {
+        let mut test = make_passing_test("Hangs");
+        test.passed = false;
+        test.errored = true;
+        test.failure_message = Some("Timed out after 5s with 1 step(s) running".to_string());
+        test.running_steps = vec![RunningStep {
+            stage: "Build".to_string(),
+            job: "Compile".to_string(),
+            step: "restore".to_string(),
+            running_for: Duration::from_secs(4),
+        }];
+        let results = TestSuiteResult {
+            suite_name: "Timeouts".to_string(),
+            results: vec![test],
+            total: 1,
+            passed: 0,
+            failed: 0,
+            errored: 1,
+            skipped: 0,
+            duration: Duration::from_secs(5),
+        };
+
+        let terminal = TestReporter::to_terminal(&results);
+        assert!(terminal.contains("[!] ERROR"));
+        assert!(terminal.contains("- Build/Compile/restore (running 4.0s)"));
+        assert!(terminal.contains("0 of 1 tests failed, 1 errored"));
+
+        let xml = TestReporter::to_junit_xml(&results);
+        assert!(xml.contains("errors=\"1\""));
+        assert!(xml.contains("<error message=\"Timed out after 5s"));
+        assert!(!xml.contains("<failure"));
+
+        let tap = TestReporter::to_tap(&results);
+        assert!(tap.contains("not ok 1 - Hangs"));
+        assert!(tap.contains("severity: error"));
+        assert!(tap.contains("# error 1"));
+    }
+
     #[test]
     fn test_xml_escaping() {
         assert_eq!(xml_escape("<test>"), "&lt;test&gt;");
@@ -1,14 +1,18 @@
 // Test Runner
 // Executes pipeline tests and collects results
 
-use crate::execution::events::progress_channel;
-use crate::execution::executor::PipelineExecutor;
-use crate::parser::models::ExecutionContext;
-use crate::testing::assertions::{Assertion, AssertionEvaluator, AssertionResult};
-use crate::testing::{PipelineTest, TestFileParser, TestSuite};
+use crate::execution::events::{progress_channel, ExecutionEvent, ProgressReceiver};
+use crate::execution::executor::{ExecutorConfig, PipelineExecutor};
+use crate::execution::recording::StepRecorder;
+use crate::execution::ExecutionResult;
+use crate::parser::models::{ExecutionContext, Pipeline};
+use crate::parser::template::{TemplateContent, TemplateEngine};
+use crate::testing::assertions::{AssertionEvaluator, AssertionResult};
+use crate::testing::{PipelineTest, TestFileParser, TestFixtures, TestSuite};
 use crate::AzureParser;
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 // =============================================================================
@@ -22,6 +26,8 @@ pub struct TestResult {
     pub name: String,
     /// Whether all assertions passed
     pub passed: bool,
+    /// Whether the test errored (timed out) rather than failed an assertion
+    pub errored: bool,
     /// Test execution duration
     pub duration: Duration,
     /// Individual assertion results
@@ -30,6 +36,19 @@ pub struct TestResult {
     pub failure_message: Option<String>,
     /// The pipeline file that was tested
     pub pipeline_path: String,
+    /// Steps still running when the test timed out
+    pub running_steps: Vec<RunningStep>,
+}
+
+/// A step that was running when a test timed out
+#[derive(Debug, Clone)]
+pub struct RunningStep {
+    pub stage: String,
+    pub job: String,
+    /// Step name, display name or 1-based position
+    pub step: String,
+    /// How long the step had been running
+    pub running_for: Duration,
 }
 
 /// Result of running a test suite
@@ -45,6 +64,8 @@ pub struct TestSuiteResult {
     pub passed: usize,
     /// Number of failed tests
     pub failed: usize,
+    /// Number of tests that errored (timed out)
+    pub errored: usize,
     /// Number of skipped tests
     pub skipped: usize,
     /// Total duration
@@ -54,7 +75,7 @@ pub struct TestSuiteResult {
 impl TestSuiteResult {
     /// Whether all tests passed
     pub fn all_passed(&self) -> bool {
-        self.failed == 0
+        self.failed == 0 && self.errored == 0
     }
 }
 
@@ -107,6 +128,11 @@ pub struct TestRunnerConfig {
     pub fail_fast: bool,
     /// Collect execution events for debugging
     pub collect_events: bool,
+    /// Record step output, or replay it instead of running commands; step
+    /// identities are scoped by test name
+    pub recorder: Option<StepRecorder>,
+    /// CI-parity mode: roxid-only expression functions such as `env()` fail
+    pub strict: bool,
 }
 
 impl Default for TestRunnerConfig {
@@ -117,6 +143,8 @@ impl Default for TestRunnerConfig {
             max_parallel: 0,
             fail_fast: false,
             collect_events: false,
+            recorder: None,
+            strict: false,
         }
     }
 }
@@ -161,9 +189,24 @@ impl TestRunner {
         self
     }
 
+    /// Set CI-parity mode, where `env()` is an error
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.config.strict = strict;
+        self
+    }
+
+    /// Record step output to, or replay it from, `recorder`
+    pub fn with_recorder(mut self, recorder: StepRecorder) -> Self {
+        self.config.recorder = Some(recorder);
+        self
+    }
+
     /// Run a single test
     pub async fn run_test(&self, test: &PipelineTest) -> TestResult {
         let start = Instant::now();
+        if let Some(template) = &test.template {
+            return self.run_template_test(test, template, start);
+        }
         let pipeline_path = test.pipeline.display().to_string();
 
         // Parse the pipeline file
@@ -177,10 +220,55 @@ impl TestRunner {
                     assertions: vec![],
                     failure_message: Some(format!("Failed to parse pipeline: {}", e)),
                     pipeline_path,
+                    errored: false,
+                    running_steps: vec![],
                 };
             }
         };
 
+        // Check the resolved structure first; when that fails, or there's
+        // nothing else to check, the pipeline isn't run
+        let structure = match self.resolve_structure(test, &pipeline) {
+            Ok(structure) => Some(structure),
+            Err(e) if test.assertions.iter().any(|def| def.is_structural()) => {
+                return TestResult {
+                    name: test.name.clone(),
+                    passed: false,
+                    duration: start.elapsed(),
+                    assertions: vec![],
+                    failure_message: Some(format!("Failed to resolve templates: {}", e)),
+                    pipeline_path,
+                    errored: false,
+                    running_steps: vec![],
+                };
+            }
+            Err(_) => None,
+        };
+        let not_run = not_run();
+        let mut evaluator = AssertionEvaluator::new(&not_run);
+        if let Some(structure) = &structure {
+            evaluator = evaluator.with_pipeline(structure);
+        }
+        let structural: Vec<Option<Vec<AssertionResult>>> = test
+            .assertions
+            .iter()
+            .map(|def| def.is_structural().then(|| def.evaluate(&evaluator)))
+            .collect();
+        let structure_failed = structural
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|result| result.is_hard_failure());
+        let structural_only = structural.iter().all(Option::is_some);
+        if structure_failed || structural_only {
+            let results = structural.into_iter().flatten().flatten().collect();
+            let mut result = conclude(test, results, start, pipeline_path);
+            if let (Some(message), false) = (&mut result.failure_message, structural_only) {
+                message.push_str("; the pipeline was not run");
+            }
+            return result;
+        }
+
         // Create the executor
         let executor = match PipelineExecutor::from_pipeline(&pipeline) {
             Ok(e) => e,
@@ -192,64 +280,163 @@ impl TestRunner {
                     assertions: vec![],
                     failure_message: Some(format!("Failed to build execution graph: {}", e)),
                     pipeline_path,
+                    errored: false,
+                    running_steps: vec![],
                 };
             }
         };
 
-        // Set up progress channel if collecting events
-        let (executor, _rx) = if self.config.collect_events {
+        let executor = executor.with_config(ExecutorConfig {
+            recorder: self
+                .config
+                .recorder
+                .as_ref()
+                .map(|recorder| recorder.scoped(&test.name)),
+            faults: test.faults.clone(),
+            hosts: test.hosts.clone(),
+            ..Default::default()
+        });
+
+        // Set up progress channel if collecting events or watching for hangs
+        let (executor, rx) = if self.config.collect_events || test.timeout.is_some() {
             let (tx, rx) = progress_channel();
             (executor.with_progress(tx), Some(rx))
         } else {
             (executor, None)
         };
 
+        // Create fixtures; they're removed when dropped after the run
+        let fixtures = match TestFixtures::create(&test.fixtures) {
+            Ok(f) => f,
+            Err(e) => {
+                return TestResult {
+                    name: test.name.clone(),
+                    passed: false,
+                    duration: start.elapsed(),
+                    assertions: vec![],
+                    failure_message: Some(format!("Failed to create fixtures: {}", e)),
+                    pipeline_path,
+                    errored: false,
+                    running_steps: vec![],
+                };
+            }
+        };
+
         // Build execution context from test definition
-        let working_dir = test
+        let mut working_dir = test
             .working_dir
             .clone()
             .unwrap_or_else(|| self.config.working_dir.clone());
+        for (name, path) in fixtures.variables() {
+            working_dir = working_dir.replace(&format!("$({})", name), path);
+        }
 
-        let context = ExecutionContext::new(test.name.clone(), working_dir)
-            .with_variables(test.variables.clone())
-            .with_parameters(test.parameters.clone());
+        // Test variables win over fixture variables
+        let mut variables = fixtures.variables().clone();
+        variables.extend(test.variables.clone());
 
-        // Execute the pipeline
-        let exec_result = executor.execute(context).await;
+        let context = ExecutionContext::new(test.name.clone(), working_dir)
+            .with_variables(variables)
+            .with_parameters(test.parameters.clone())
+            .with_strict(self.config.strict);
+
+        // Execute the pipeline, cancelling it if it runs past the timeout
+        let exec_result = match (test.timeout, rx) {
+            (Some(seconds), Some(rx)) => {
+                let timeout = Duration::from_secs(seconds);
+                match run_with_timeout(executor.execute(context), rx, timeout).await {
+                    Ok(result) => result,
+                    Err(running_steps) => {
+                        return TestResult {
+                            name: test.name.clone(),
+                            passed: false,
+                            errored: true,
+                            duration: start.elapsed(),
+                            assertions: vec![],
+                            failure_message: Some(format!(
+                                "Timed out after {}s with {} step(s) running",
+                                seconds,
+                                running_steps.len()
+                            )),
+                            pipeline_path,
+                            running_steps,
+                        };
+                    }
+                }
+            }
+            _ => executor.execute(context).await,
+        };
 
-        // Convert assertion definitions to evaluable assertions
-        let assertions: Vec<Assertion> = test
+        // Evaluate the remaining assertions against execution results
+        let mut evaluator = AssertionEvaluator::new(&exec_result);
+        if let Some(structure) = &structure {
+            evaluator = evaluator.with_pipeline(structure);
+        }
+        let results = test
             .assertions
             .iter()
-            .map(|def| def.to_assertion())
+            .zip(structural)
+            .flat_map(|(def, checked)| checked.unwrap_or_else(|| def.evaluate(&evaluator)))
             .collect();
+        conclude(test, results, start, pipeline_path)
+    }
 
-        // Evaluate assertions against execution results
-        let evaluator = AssertionEvaluator::new(&exec_result);
-        let assertion_results = evaluator.evaluate_all(&assertions);
-
-        // Compute pass/fail
-        let all_passed = assertion_results.iter().all(|r| r.passed);
-        let failed_count = assertion_results.iter().filter(|r| !r.passed).count();
-
-        let failure_message = if !all_passed {
-            Some(format!(
-                "{} of {} assertions failed",
-                failed_count,
-                assertion_results.len()
-            ))
-        } else {
-            None
+    /// Expand a template on its own with the test's parameters and check its
+    /// structure; nothing runs
+    fn run_template_test(
+        &self,
+        test: &PipelineTest,
+        template: &Path,
+        start: Instant,
+    ) -> TestResult {
+        let template_path = template.display().to_string();
+        let repo_root = test
+            .working_dir
+            .clone()
+            .unwrap_or_else(|| self.config.working_dir.clone());
+        let mut engine =
+            TemplateEngine::new(PathBuf::from(repo_root)).with_strict(self.config.strict);
+
+        let pipeline = match engine.expand_template(&template_path, &test.parameters) {
+            Ok(TemplateContent::Steps(steps)) => Pipeline {
+                steps,
+                ..Default::default()
+            },
+            Ok(TemplateContent::Jobs(jobs)) => Pipeline {
+                jobs,
+                ..Default::default()
+            },
+            Ok(TemplateContent::Stages(stages)) => Pipeline {
+                stages,
+                ..Default::default()
+            },
+            Ok(TemplateContent::Variables(variables)) => Pipeline {
+                variables,
+                ..Default::default()
+            },
+            Ok(TemplateContent::Pipeline(pipeline)) => *pipeline,
+            Err(e) => {
+                return TestResult {
+                    name: test.name.clone(),
+                    passed: false,
+                    duration: start.elapsed(),
+                    assertions: vec![],
+                    failure_message: Some(format!("Failed to expand template: {}", e)),
+                    pipeline_path: template_path,
+                    errored: false,
+                    running_steps: vec![],
+                };
+            }
         };
 
-        TestResult {
-            name: test.name.clone(),
-            passed: all_passed,
-            duration: start.elapsed(),
-            assertions: assertion_results,
-            failure_message,
-            pipeline_path,
-        }
+        let not_run = not_run();
+        let evaluator = AssertionEvaluator::new(&not_run).with_pipeline(&pipeline);
+        let results = test
+            .assertions
+            .iter()
+            .flat_map(|def| def.evaluate(&evaluator))
+            .collect();
+        conclude(test, results, start, template_path)
     }
 
     /// Run a test suite
@@ -277,7 +464,8 @@ impl TestRunner {
 
         let total = tests.len();
         let passed = results.iter().filter(|r| r.passed).count();
-        let failed = results.iter().filter(|r| !r.passed).count();
+        let errored = results.iter().filter(|r| r.errored).count();
+        let failed = results.iter().filter(|r| !r.passed).count() - errored;
         let skipped = total - results.len();
 
         TestSuiteResult {
@@ -286,6 +474,7 @@ impl TestRunner {
             total,
             passed,
             failed,
+            errored,
             skipped,
             duration: start.elapsed(),
         }
@@ -316,6 +505,24 @@ impl TestRunner {
         AzureParser::parse_file(path).map_err(|e| TestError::ParseError(format!("{}", e)))
     }
 
+    /// The pipeline with its templates expanded, as structure assertions see
+    /// it; template paths resolve from the pipeline's directory
+    fn resolve_structure(
+        &self,
+        test: &PipelineTest,
+        pipeline: &Pipeline,
+    ) -> Result<Pipeline, String> {
+        let repo_root = test
+            .pipeline
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        TemplateEngine::new(repo_root)
+            .with_strict(self.config.strict)
+            .resolve_pipeline(pipeline.clone())
+            .map_err(|e| e.to_string())
+    }
+
     fn prepare_tests(&self, suite: &TestSuite) -> Vec<PipelineTest> {
         let mut tests: Vec<PipelineTest> = suite.tests.clone();
 
@@ -341,6 +548,144 @@ impl Default for TestRunner {
     }
 }
 
+/// Results for checking structure without running anything
+fn not_run() -> ExecutionResult {
+    ExecutionResult {
+        run_id: String::new(),
+        stages: Vec::new(),
+        duration: Duration::ZERO,
+        started_at: None,
+        finished_at: None,
+        success: true,
+        variables: HashMap::new(),
+        images: Default::default(),
+    }
+}
+
+/// Report whether a test passed from its assertion results
+fn conclude(
+    test: &PipelineTest,
+    assertion_results: Vec<AssertionResult>,
+    start: Instant,
+    pipeline_path: String,
+) -> TestResult {
+    // Compute pass/fail; failures in soft groups are only reported
+    let failed_count = assertion_results
+        .iter()
+        .filter(|r| r.is_hard_failure())
+        .count();
+    let all_passed = failed_count == 0;
+
+    let failure_message = if !all_passed {
+        Some(format!(
+            "{} of {} assertions failed",
+            failed_count,
+            assertion_results.len()
+        ))
+    } else {
+        None
+    };
+
+    TestResult {
+        name: test.name.clone(),
+        passed: all_passed,
+        duration: start.elapsed(),
+        assertions: assertion_results,
+        failure_message,
+        pipeline_path,
+        errored: false,
+        running_steps: vec![],
+    }
+}
+
+/// Await `execution`, tracking running steps from its events; on timeout the
+/// execution is dropped, which kills its processes, and the steps that were
+/// still running are returned
+async fn run_with_timeout(
+    execution: impl std::future::Future<Output = ExecutionResult>,
+    mut rx: ProgressReceiver,
+    timeout: Duration,
+) -> Result<ExecutionResult, Vec<RunningStep>> {
+    let mut tracker = StepTracker::default();
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(execution, deadline);
+
+    loop {
+        tokio::select! {
+            result = &mut execution => return Ok(result),
+            _ = &mut deadline => break,
+            Some(event) = rx.recv() => tracker.observe(event),
+        }
+    }
+
+    while let Ok(event) = rx.try_recv() {
+        tracker.observe(event);
+    }
+    Err(tracker.running())
+}
+
+/// Steps that have started but not completed, with their step index and start
+#[derive(Default)]
+struct StepTracker {
+    running: Vec<(usize, Instant, RunningStep)>,
+}
+
+impl StepTracker {
+    fn observe(&mut self, event: ExecutionEvent) {
+        match event {
+            ExecutionEvent::StepStarted {
+                stage_name,
+                job_name,
+                step_name,
+                display_name,
+                step_index,
+                ..
+            } => {
+                let step = display_name
+                    .or(step_name)
+                    .unwrap_or_else(|| (step_index + 1).to_string());
+                self.running.push((
+                    step_index,
+                    Instant::now(),
+                    RunningStep {
+                        stage: stage_name,
+                        job: job_name,
+                        step,
+                        running_for: Duration::ZERO,
+                    },
+                ));
+            }
+            ExecutionEvent::StepCompleted {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            }
+            | ExecutionEvent::StepSkipped {
+                stage_name,
+                job_name,
+                step_index,
+                ..
+            } => {
+                self.running.retain(|(index, _, step)| {
+                    !(*index == step_index && step.stage == stage_name && step.job == job_name)
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn running(self) -> Vec<RunningStep> {
+        self.running
+            .into_iter()
+            .map(|(_, started, step)| RunningStep {
+                running_for: started.elapsed(),
+                ..step
+            })
+            .collect()
+    }
+}
+
 /// Simple glob-style filter matching
 fn matches_filter(name: &str, filter: &str) -> bool {
     if filter.is_empty() {
@@ -406,6 +751,7 @@ mod tests {
             total: 3,
             passed: 3,
             failed: 0,
+            errored: 0,
             skipped: 0,
             duration: Duration::from_secs(1),
         };
@@ -420,6 +766,7 @@ mod tests {
             total: 3,
             passed: 2,
             failed: 1,
+            errored: 0,
             skipped: 0,
             duration: Duration::from_secs(1),
         };
@@ -448,17 +795,29 @@ mod tests {
                 PipelineTest {
                     name: "Build test".to_string(),
                     pipeline: PathBuf::from("pipeline.yml"),
+                    template: None,
                     variables: HashMap::new(),
                     parameters: HashMap::new(),
+                    cases: Vec::new(),
                     working_dir: None,
+                    fixtures: vec![],
+                    timeout: None,
+                    faults: vec![],
+                    hosts: vec![],
                     assertions: vec![],
                 },
                 PipelineTest {
                     name: "Deploy test".to_string(),
                     pipeline: PathBuf::from("pipeline.yml"),
+                    template: None,
                     variables: HashMap::new(),
                     parameters: HashMap::new(),
+                    cases: Vec::new(),
                     working_dir: None,
+                    fixtures: vec![],
+                    timeout: None,
+                    faults: vec![],
+                    hosts: vec![],
                     assertions: vec![],
                 },
             ],
@@ -479,9 +838,15 @@ mod tests {
             tests: vec![PipelineTest {
                 name: "Test".to_string(),
                 pipeline: PathBuf::from("pipeline.yml"),
+                template: None,
                 variables: HashMap::new(),
                 parameters: HashMap::new(),
+                cases: Vec::new(),
                 working_dir: None,
+                fixtures: vec![],
+                timeout: None,
+                faults: vec![],
+                hosts: vec![],
                 assertions: vec![],
             }],
             defaults: Some(TestDefaults {
@@ -492,6 +857,7 @@ mod tests {
                 },
                 parameters: HashMap::new(),
                 working_dir: Some("/workspace".to_string()),
+                timeout: None,
             }),
         };
 
@@ -506,9 +872,15 @@ mod tests {
         let test = PipelineTest {
             name: "Missing pipeline test".to_string(),
             pipeline: PathBuf::from("/nonexistent/pipeline.yml"),
+            template: None,
             variables: HashMap::new(),
             parameters: HashMap::new(),
+            cases: Vec::new(),
             working_dir: None,
+            fixtures: vec![],
+            timeout: None,
+            faults: vec![],
+            hosts: vec![],
             assertions: vec![AssertionDef::PipelineSucceeded],
         };
 
@@ -538,9 +910,15 @@ steps:
         let test = PipelineTest {
             name: "Simple pipeline test".to_string(),
             pipeline: pipeline_path,
+            template: None,
             variables: HashMap::new(),
             parameters: HashMap::new(),
+            cases: Vec::new(),
             working_dir: Some(dir.path().to_str().unwrap().to_string()),
+            fixtures: vec![],
+            timeout: None,
+            faults: vec![],
+            hosts: vec![],
             assertions: vec![
                 AssertionDef::PipelineSucceeded,
                 AssertionDef::StepSucceeded("hello".to_string()),
@@ -552,4 +930,280 @@ steps:
         // Assertions may pass or fail depending on execution
         assert!(!result.name.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_assertions_match_resolved_display_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let pipeline_path = dir.path().join("pipeline.yml");
+        std::fs::write(
+            &pipeline_path,
+            r#"
+parameters:
+  - name: target
+    default: linux
+stages:
+  - stage: Build
+    displayName: Build for ${{ parameters.target }}
+    jobs:
+      - job: Compile
+        displayName: Compile $(profile)
+        steps:
+          - script: echo building
+            displayName: Build $(profile) on ${{ parameters.target }}
+"#,
+        )
+        .unwrap();
+
+        let test: PipelineTest = serde_yaml::from_str(&format!(
+            r#"
+name: display names
+pipeline: {}
+variables: {{ profile: release }}
+parameters: {{ target: macos }}
+assertions:
+  - stage_succeeded: Build for macos
+  - job_succeeded: Compile release
+  - step_succeeded: Build release on macos
+"#,
+            pipeline_path.display()
+        ))
+        .unwrap();
+
+        let result = TestRunner::new()
+            .with_working_dir(dir.path().to_str().unwrap())
+            .run_test(&test)
+            .await;
+        assert!(result.passed, "{:?}", result.failure_message);
+    }
+
+    #[tokio::test]
+    async fn test_template_test_checks_resolved_structure() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("templates/steps")).unwrap();
+        std::fs::write(
+            dir.path().join("templates/build.yml"),
+            r#"
+parameters:
+  - name: platforms
+    type: object
+jobs:
+  - ${{ each platform in parameters.platforms }}:
+    - job: Build_${{ platform }}
+      steps:
+        - template: steps/compile.yml
+          parameters:
+            target: ${{ platform }}
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("templates/steps/compile.yml"),
+            "parameters:\n  - name: target\nsteps:\n  - script: make ${{ parameters.target }}\n    name: compile\n",
+        )
+        .unwrap();
+        let test_file = dir.path().join("roxid-test.yml");
+        std::fs::write(
+            &test_file,
+            r#"
+tests:
+  - name: one job per platform
+    template: templates/build.yml
+    parameters: { platforms: [linux, macos] }
+    assertions:
+      - resolved_job_count: 2
+      - job_exists: Build_macos
+      - step_script_contains: { step: compile, pattern: "make macos" }
+        in_job: Build_macos
+      - resolved_step_count: 3
+"#,
+        )
+        .unwrap();
+
+        let suite = TestFileParser::parse_file(&test_file).unwrap();
+        let runner =
+            TestRunner::new().with_working_dir(dir.path().join("templates").to_str().unwrap());
+        let result = runner.run_test(&suite.tests[0]).await;
+        assert!(!result.passed);
+        assert_eq!(
+            result.failure_message.as_deref(),
+            Some("1 of 4 assertions failed")
+        );
+        assert!(
+            result.assertions[..3].iter().all(|a| a.passed),
+            "{:?}",
+            result.assertions
+        );
+    }
+
+    #[tokio::test]
+    async fn test_structure_checked_before_running() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("build.yml"),
+            "steps:\n  - script: cargo build --release\n    name: Build\n",
+        )
+        .unwrap();
+        let pipeline_path = dir.path().join("pipeline.yml");
+        std::fs::write(
+            &pipeline_path,
+            r#"
+steps:
+  - template: build.yml
+  - script: touch ran
+    name: Mark
+"#,
+        )
+        .unwrap();
+
+        let run = |assertions: &str| {
+            let test: PipelineTest = serde_yaml::from_str(&format!(
+                "name: structure\npipeline: {}\nassertions:\n{}",
+                pipeline_path.display(),
+                assertions
+            ))
+            .unwrap();
+            let runner = TestRunner::new().with_working_dir(dir.path().to_str().unwrap());
+            async move { runner.run_test(&test).await }
+        };
+
+        let result = run(
+            "  - resolved_step_count: 2\n  - step_script_contains: { step: Build, pattern: --release }\n",
+        )
+        .await;
+        assert!(result.passed, "{:?}", result.assertions);
+        assert!(!dir.path().join("ran").exists());
+
+        let result = run("  - job_exists: Deploy\n  - step_succeeded: Mark\n").await;
+        assert!(!result.passed);
+        assert_eq!(result.assertions.len(), 1);
+        assert_eq!(
+            result.failure_message.as_deref(),
+            Some("1 of 1 assertions failed; the pipeline was not run")
+        );
+        assert!(!dir.path().join("ran").exists());
+
+        let result = run("  - step_exists: Build\n  - step_succeeded: Mark\n").await;
+        assert!(result.passed, "{:?}", result.assertions);
+        assert!(dir.path().join("ran").exists());
+    }
+
+    #[tokio::test]
+    async fn test_host_overrides_reach_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let pipeline_path = dir.path().join("pipeline.yml");
+        std::fs::write(
+            &pipeline_path,
+            r#"
+steps:
+  - script: 'echo "api at $ROXID_HOST_API_INTERNAL, all $ROXID_HOSTS"'
+    name: call
+"#,
+        )
+        .unwrap();
+
+        let test: PipelineTest = serde_yaml::from_str(&format!(
+            r#"
+name: hosts
+pipeline: {}
+hosts: [api.internal=127.0.0.1:8080, db=10.0.0.5]
+assertions:
+  - step_output_contains: {{ step: call, pattern: "api at 127.0.0.1:8080, all api.internal=127.0.0.1:8080 db=10.0.0.5" }}
+"#,
+            pipeline_path.display()
+        ))
+        .unwrap();
+        assert!(serde_yaml::from_str::<PipelineTest>("name: x\nhosts: [api=nowhere]").is_err());
+
+        let result = TestRunner::new()
+            .with_working_dir(dir.path().to_str().unwrap())
+            .run_test(&test)
+            .await;
+        assert!(result.passed, "{:?}", result.assertions);
+    }
+
+    #[tokio::test]
+    async fn test_run_test_with_git_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let pipeline_path = dir.path().join("pipeline.yml");
+        std::fs::write(
+            &pipeline_path,
+            r#"
+steps:
+  - script: git rev-parse --abbrev-ref HEAD && cat version.txt
+    name: inspect
+"#,
+        )
+        .unwrap();
+
+        let test: PipelineTest = serde_yaml::from_str(&format!(
+            r#"
+name: fixture test
+pipeline: {}
+working_dir: $(repo)
+fixtures:
+  - name: repo
+    git:
+      branch: release
+      commits:
+        - message: Initial commit
+          files: {{ version.txt: "1.2.3" }}
+assertions:
+  - step_output_contains: {{ step: inspect, pattern: "release" }}
+  - step_output_contains: {{ step: inspect, pattern: "1.2.3" }}
+  - variable_equals: {{ name: repo.branch, expected: release }}
+"#,
+            pipeline_path.display()
+        ))
+        .unwrap();
+
+        let result = TestRunner::new().run_test(&test).await;
+        assert!(result.passed, "{:?}", result.assertions);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_errors_with_running_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let pipeline_path = dir.path().join("pipeline.yml");
+        std::fs::write(
+            &pipeline_path,
+            r#"
+steps:
+  - script: echo ready
+    name: prepare
+  - script: sleep 30
+    name: hang
+"#,
+        )
+        .unwrap();
+
+        let runner = TestRunner::new().with_working_dir(dir.path().to_str().unwrap());
+        let suite = TestSuite {
+            name: None,
+            tests: vec![PipelineTest {
+                name: "hangs".to_string(),
+                pipeline: pipeline_path,
+                template: None,
+                variables: HashMap::new(),
+                parameters: HashMap::new(),
+                cases: Vec::new(),
+                working_dir: None,
+                fixtures: vec![],
+                timeout: Some(1),
+                faults: vec![],
+                hosts: vec![],
+                assertions: vec![AssertionDef::PipelineSucceeded],
+            }],
+            defaults: None,
+        };
+
+        let result = runner.run_suite(&suite).await;
+        assert_eq!((result.failed, result.errored), (0, 1));
+        assert!(!result.all_passed());
+
+        let test = &result.results[0];
+        assert!(test.errored && !test.passed);
+        assert!(test.duration < Duration::from_secs(10));
+        assert_eq!(test.running_steps.len(), 1);
+        assert_eq!(test.running_steps[0].step, "hang");
+    }
 }
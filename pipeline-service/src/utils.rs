@@ -1,7 +1,10 @@
 // Utility Functions
 // Common helpers for path resolution and project root detection
 
+use crate::error::{ServiceError, ServiceResult};
+
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
 /// Find the root of a git repository by walking up from the given starting path.
 ///
@@ -34,6 +37,172 @@ pub fn resolve_working_dir() -> PathBuf {
     find_repo_root(&cwd).unwrap_or(cwd)
 }
 
+/// Match `text` against a wildcard `pattern` where `*` matches any run of
+/// characters and `?` matches a single character.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Find the candidate closest to `name` for "did you mean" suggestions
+///
+/// Compares case-insensitively and only returns candidates within a few edits
+/// (about a third of the name's length), so unrelated names aren't suggested.
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count().div_ceil(3)).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// List files that differ between `base` and the working tree's HEAD (or the
+/// staged index when `base` is `None`), relative to the repository root.
+pub fn git_changed_files(repo_root: &Path, base: Option<&str>) -> ServiceResult<Vec<String>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(repo_root).args(["diff", "--name-only"]);
+    match base {
+        Some(base) => {
+            cmd.arg(format!("{}...HEAD", base));
+        }
+        None => {
+            cmd.arg("--cached");
+        }
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| ServiceError::Internal(format!("failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(ServiceError::InvalidInput(format!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .collect())
+}
+
+/// A temporary git worktree checked out at a specific ref.
+///
+/// The worktree is created in the system temp directory as a detached checkout
+/// and is removed (along with its git metadata) when the value is dropped, so the
+/// user's own checkout is never touched.
+#[derive(Debug)]
+pub struct GitWorktree {
+    repo_root: PathBuf,
+    path: PathBuf,
+}
+
+impl GitWorktree {
+    /// Materialize `git_ref` (branch, tag, or commit) from the repository at `repo_root`
+    pub fn create(repo_root: &Path, git_ref: &str) -> ServiceResult<Self> {
+        if git_ref.is_empty() || git_ref.starts_with('-') {
+            return Err(ServiceError::InvalidInput(format!(
+                "invalid git ref '{}'",
+                git_ref
+            )));
+        }
+
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let path =
+            std::env::temp_dir().join(format!("roxid-worktree-{}-{}", std::process::id(), unique));
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["worktree", "add", "--detach", "--quiet"])
+            .arg(&path)
+            .arg(git_ref)
+            .output()
+            .map_err(|e| ServiceError::Internal(format!("failed to run git: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(ServiceError::InvalidInput(format!(
+                "failed to check out '{}': {}",
+                git_ref,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(Self {
+            repo_root: repo_root.to_path_buf(),
+            path,
+        })
+    }
+
+    /// Root directory of the checked-out worktree
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for GitWorktree {
+    fn drop(&mut self) {
+        let _ = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_root)
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.path)
+            .output();
+        if self.path.exists() {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,10 +269,74 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::cmp_owned)]
     fn test_resolve_working_dir_returns_path() {
         // resolve_working_dir should always return a valid path
         let dir = resolve_working_dir();
         // It should either be the repo root (if we're in a git repo) or cwd
         assert!(dir.exists() || dir == PathBuf::from("."));
     }
+
+    #[test]
+    fn test_git_worktree_checks_out_ref() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args([
+                    "-c",
+                    "user.name=roxid",
+                    "-c",
+                    "user.email=roxid@example.com",
+                ])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+
+        git(&["init", "--quiet"]);
+        fs::write(repo.join("pipeline.yml"), "steps: []\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "first"]);
+        git(&["tag", "v1"]);
+        fs::remove_file(repo.join("pipeline.yml")).unwrap();
+        git(&["commit", "--quiet", "-am", "second"]);
+
+        let worktree = GitWorktree::create(repo, "v1").unwrap();
+        let path = worktree.path().to_path_buf();
+        assert!(path.join("pipeline.yml").exists());
+        assert!(!repo.join("pipeline.yml").exists());
+
+        drop(worktree);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_git_worktree_rejects_option_like_ref() {
+        let temp = tempfile::tempdir().unwrap();
+        let result = GitWorktree::create(temp.path(), "--force");
+        assert!(matches!(result, Err(ServiceError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("src/*.rs", "src/main.rs"));
+        assert!(wildcard_match("*/docs/*", "a/docs/readme.md"));
+        assert!(wildcard_match("v?", "v1"));
+        assert!(!wildcard_match("src/*.rs", "src/main.ts"));
+        assert!(!wildcard_match("v?", "v10"));
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let names = ["Build", "Test", "Deploy"];
+        assert_eq!(closest_match("Biuld", names), Some("Build"));
+        assert_eq!(closest_match("deploy", names), Some("Deploy"));
+        assert_eq!(closest_match("Publish", names), None);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
 }
@@ -1,9 +1,11 @@
 pub mod models;
 pub mod parser;
+pub mod reusable;
 
 pub use models::{
-    ContinueOnError, Defaults, Environment, EventConfig, Job, JobNeeds, Matrix, Permissions,
-    RunDefaults, RunsOn, Service, Step, Strategy, Trigger, Workflow, WorkflowInput, WorkflowOutput,
-    WorkflowSecret,
+    ContinueOnError, Defaults, Environment, EventConfig, Job, JobNeeds, JobSecrets, Matrix,
+    Permissions, RunDefaults, RunsOn, Service, Step, Strategy, Trigger, Workflow, WorkflowInput,
+    WorkflowOutput, WorkflowSecret,
 };
 pub use parser::WorkflowParser;
+pub use reusable::{WorkflowResolver, MAX_REUSABLE_WORKFLOWS, MAX_WORKFLOW_NESTING};
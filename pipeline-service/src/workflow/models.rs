@@ -261,6 +261,37 @@ pub struct Job {
     /// Environment deployment target
     #[serde(default)]
     pub environment: Option<Environment>,
+
+    /// Reusable workflow to call instead of running steps
+    /// (`./.github/workflows/x.yml` or `owner/repo/.github/workflows/x.yml@ref`)
+    #[serde(default)]
+    pub uses: Option<String>,
+
+    /// Inputs passed to the called workflow
+    #[serde(default)]
+    pub with: HashMap<String, Value>,
+
+    /// Secrets passed to the called workflow
+    #[serde(default)]
+    pub secrets: Option<JobSecrets>,
+}
+
+/// Secrets passed to a reusable workflow - `inherit` or a mapping.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum JobSecrets {
+    /// `secrets: inherit` passes all of the caller's secrets
+    Keyword(String),
+
+    /// Secret name to the caller's value for it
+    Named(HashMap<String, String>),
+}
+
+impl JobSecrets {
+    /// Whether the caller's secrets are all passed through.
+    pub fn is_inherit(&self) -> bool {
+        matches!(self, JobSecrets::Keyword(keyword) if keyword == "inherit")
+    }
 }
 
 /// Job dependencies - can be a single string or a list.
@@ -46,8 +46,17 @@ impl WorkflowParser {
         // Validate no circular dependencies
         Self::check_circular_dependencies(workflow)?;
 
-        // Validate each job has at least one step
+        // Validate each job has at least one step, or calls a reusable workflow instead
         for (job_id, job) in &workflow.jobs {
+            if job.uses.is_some() {
+                if !job.steps.is_empty() {
+                    return Err(crate::ServiceError::InvalidInput(format!(
+                        "Job '{}' calls a reusable workflow and cannot have steps",
+                        job_id
+                    )));
+                }
+                continue;
+            }
             if job.steps.is_empty() {
                 return Err(crate::ServiceError::InvalidInput(format!(
                     "Job '{}' has no steps",
@@ -0,0 +1,742 @@
+use crate::workflow::models::{EventConfig, Job, JobNeeds, JobSecrets, Trigger, Workflow};
+use crate::workflow::parser::WorkflowParser;
+use crate::{ServiceError, ServiceResult};
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+/// Levels of workflows that can be connected, counting the top-level caller.
+pub const MAX_WORKFLOW_NESTING: usize = 10;
+
+/// Distinct reusable workflows a single workflow may call, directly or nested.
+pub const MAX_REUSABLE_WORKFLOWS: usize = 50;
+
+/// Expands jobs that call reusable workflows into the called workflows' jobs.
+///
+/// A caller job `deploy` calling a workflow with a `release` job becomes the
+/// job `deploy-release`. Inputs and secrets passed with `with:` and `secrets:`
+/// are substituted into the called jobs, and references to the caller's outputs
+/// (`needs.deploy.outputs.x`) are rewritten to the jobs that produce them.
+pub struct WorkflowResolver {
+    /// Repository root that `./` workflow references are relative to
+    repo_root: PathBuf,
+    /// Local checkouts for `owner/repo` (or `owner/repo@ref`) references
+    repositories: HashMap<String, PathBuf>,
+    /// Workflows being expanded, outermost first, for cycle detection
+    stack: Vec<String>,
+    /// Every reusable workflow called so far
+    called: HashSet<String>,
+}
+
+impl WorkflowResolver {
+    /// Create a resolver for workflows in the repository at `repo_root`.
+    pub fn new(repo_root: impl Into<PathBuf>) -> Self {
+        Self {
+            repo_root: repo_root.into(),
+            repositories: HashMap::new(),
+            stack: Vec::new(),
+            called: HashSet::new(),
+        }
+    }
+
+    /// Use the checkout at `path` for workflows in `repository`
+    /// (`owner/repo`, or `owner/repo@ref` for one ref only).
+    pub fn with_repository(mut self, repository: impl Into<String>, path: PathBuf) -> Self {
+        self.repositories.insert(repository.into(), path);
+        self
+    }
+
+    /// Expand every reusable workflow call in `workflow`.
+    pub fn resolve(&mut self, workflow: Workflow) -> ServiceResult<Workflow> {
+        self.stack.clear();
+        self.called.clear();
+        self.expand(workflow)
+    }
+
+    fn expand(&mut self, mut workflow: Workflow) -> ServiceResult<Workflow> {
+        let mut callers: Vec<String> = workflow
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.uses.is_some())
+            .map(|(id, _)| id.clone())
+            .collect();
+        callers.sort();
+
+        for caller_id in callers {
+            let Some(caller) = workflow.jobs.remove(&caller_id) else {
+                return Err(ServiceError::Internal(format!(
+                    "Job '{}' went missing while expanding reusable workflows",
+                    caller_id
+                )));
+            };
+            let reference = caller.uses.clone().unwrap_or_default();
+            let path = self.locate(&reference)?;
+            let key = path
+                .canonicalize()
+                .unwrap_or_else(|_| path.clone())
+                .to_string_lossy()
+                .to_string();
+
+            if self.stack.contains(&key) {
+                let mut cycle = self.stack.clone();
+                cycle.push(key);
+                return Err(ServiceError::InvalidInput(format!(
+                    "Circular reusable workflow reference: {}",
+                    cycle.join(" -> ")
+                )));
+            }
+            // The top-level workflow and the ones already being expanded
+            if self.stack.len() + 2 > MAX_WORKFLOW_NESTING {
+                return Err(ServiceError::InvalidInput(format!(
+                    "Job '{}' calls '{}' beyond the maximum of {} nested workflows",
+                    caller_id, reference, MAX_WORKFLOW_NESTING
+                )));
+            }
+            self.called.insert(key.clone());
+            if self.called.len() > MAX_REUSABLE_WORKFLOWS {
+                return Err(ServiceError::InvalidInput(format!(
+                    "More than {} reusable workflows are called",
+                    MAX_REUSABLE_WORKFLOWS
+                )));
+            }
+
+            let called = WorkflowParser::from_file(&path).map_err(|e| {
+                ServiceError::InvalidInput(format!(
+                    "Reusable workflow '{}' called by job '{}': {}",
+                    reference, caller_id, e
+                ))
+            })?;
+            let call = workflow_call(&called).ok_or_else(|| {
+                ServiceError::InvalidInput(format!(
+                    "Job '{}' calls '{}', which has no workflow_call trigger",
+                    caller_id, reference
+                ))
+            })?;
+            let bindings = bind(&caller_id, &caller, &call)?;
+
+            self.stack.push(key);
+            let called = self.expand(called);
+            self.stack.pop();
+            let called = called?;
+
+            let jobs = inline_jobs(&caller_id, &caller, called, &bindings)?;
+            let job_ids: Vec<String> = jobs.keys().cloned().collect();
+            for (id, job) in jobs {
+                if workflow.jobs.contains_key(&id) {
+                    return Err(ServiceError::InvalidInput(format!(
+                        "Job '{}' from the workflow called by '{}' clashes with an existing job",
+                        id, caller_id
+                    )));
+                }
+                workflow.jobs.insert(id, job);
+            }
+
+            // Point the caller's dependents at the jobs that replaced it
+            let outputs = caller_outputs(&caller_id, &call, &bindings);
+            for (id, job) in workflow.jobs.iter_mut() {
+                if job_ids.contains(id) {
+                    continue;
+                }
+                let needs = job.needs.to_vec();
+                if needs.contains(&caller_id) {
+                    let mut replaced: Vec<String> = Vec::new();
+                    for need in needs {
+                        if need == caller_id {
+                            replaced.extend(job_ids.iter().cloned());
+                        } else {
+                            replaced.push(need);
+                        }
+                    }
+                    replaced.sort();
+                    replaced.dedup();
+                    job.needs = JobNeeds::Multiple(replaced);
+                }
+                let prefix = format!("needs.{}.outputs.", caller_id);
+                *job = rewrite_job(job, &|expr| {
+                    replace_references(expr, &prefix, &|name| outputs.get(name).cloned())
+                })?;
+            }
+        }
+
+        Ok(workflow)
+    }
+
+    /// Path of the workflow file a `uses:` reference names.
+    fn locate(&self, reference: &str) -> ServiceResult<PathBuf> {
+        if let Some(local) = reference.strip_prefix("./") {
+            let path = self.repo_root.join(local);
+            if path.is_file() {
+                return Ok(path);
+            }
+            return Err(ServiceError::NotFound(format!(
+                "reusable workflow '{}' (looked in {})",
+                reference,
+                path.display()
+            )));
+        }
+
+        let (location, git_ref) = reference.split_once('@').ok_or_else(|| {
+            ServiceError::InvalidInput(format!(
+                "Reusable workflow '{}' must be './path' or 'owner/repo/path@ref'",
+                reference
+            ))
+        })?;
+        let mut parts = location.splitn(3, '/');
+        let (Some(owner), Some(repo), Some(file)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ServiceError::InvalidInput(format!(
+                "Reusable workflow '{}' must be './path' or 'owner/repo/path@ref'",
+                reference
+            )));
+        };
+
+        let repository = format!("{}/{}", owner, repo);
+        let checkout = self
+            .repositories
+            .get(&format!("{}@{}", repository, git_ref))
+            .or_else(|| self.repositories.get(&repository))
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!(
+                    "no local checkout of '{}' for reusable workflow '{}'",
+                    repository, reference
+                ))
+            })?;
+        let path = checkout.join(file);
+        if path.is_file() {
+            Ok(path)
+        } else {
+            Err(ServiceError::NotFound(format!(
+                "reusable workflow '{}' (looked in {})",
+                reference,
+                path.display()
+            )))
+        }
+    }
+}
+
+/// Inputs and secrets a caller passes, as expressions for the called workflow.
+struct Bindings {
+    inputs: HashMap<String, String>,
+    /// `None` when the caller passes `secrets: inherit`
+    secrets: Option<HashMap<String, String>>,
+}
+
+/// The workflow_call trigger of `workflow`, if it has one.
+fn workflow_call(workflow: &Workflow) -> Option<EventConfig> {
+    match &workflow.on {
+        Trigger::Single(event) => (event == "workflow_call").then(EventConfig::default),
+        Trigger::Multiple(events) => events
+            .iter()
+            .any(|event| event == "workflow_call")
+            .then(EventConfig::default),
+        Trigger::Detailed(events) => events
+            .get("workflow_call")
+            .map(|config| config.clone().unwrap_or_default()),
+    }
+}
+
+/// Check the caller's `with:` and `secrets:` against what the called workflow declares.
+fn bind(caller_id: &str, caller: &Job, call: &EventConfig) -> ServiceResult<Bindings> {
+    let reference = caller.uses.as_deref().unwrap_or_default();
+
+    let mut undeclared: Vec<&String> = caller
+        .with
+        .keys()
+        .filter(|name| !call.inputs.contains_key(*name))
+        .collect();
+    undeclared.sort();
+    if let Some(name) = undeclared.first() {
+        return Err(ServiceError::InvalidInput(format!(
+            "Job '{}' passes input '{}', which '{}' doesn't declare",
+            caller_id, name, reference
+        )));
+    }
+
+    let mut inputs = HashMap::new();
+    for (name, input) in &call.inputs {
+        let value = match caller.with.get(name).or(input.default.as_ref()) {
+            Some(value) => value.clone(),
+            None if input.required => {
+                return Err(ServiceError::InvalidInput(format!(
+                    "Job '{}' doesn't pass required input '{}' to '{}'",
+                    caller_id, name, reference
+                )));
+            }
+            None => match input.input_type.as_deref() {
+                Some("boolean") => Value::Bool(false),
+                Some("number") => Value::from(0),
+                _ => Value::String(String::new()),
+            },
+        };
+        if let (Some("boolean"), Value::String(text)) = (input.input_type.as_deref(), &value) {
+            if whole_expression(text).is_none() {
+                return Err(ServiceError::InvalidInput(format!(
+                    "Input '{}' of '{}' must be a boolean, got '{}'",
+                    name, reference, text
+                )));
+            }
+        }
+        inputs.insert(name.clone(), expression_for(&value));
+    }
+
+    let secrets = match &caller.secrets {
+        Some(secrets) if secrets.is_inherit() => None,
+        Some(JobSecrets::Keyword(keyword)) => {
+            return Err(ServiceError::InvalidInput(format!(
+                "Job '{}' has 'secrets: {}'; use 'inherit' or a mapping",
+                caller_id, keyword
+            )));
+        }
+        passed => {
+            let passed = match passed {
+                Some(JobSecrets::Named(passed)) => passed.clone(),
+                _ => HashMap::new(),
+            };
+            let mut secrets = HashMap::new();
+            for (name, secret) in &call.secrets {
+                match passed.get(name) {
+                    Some(value) => {
+                        secrets.insert(name.clone(), expression_for(&Value::from(value.clone())));
+                    }
+                    None if secret.required => {
+                        return Err(ServiceError::InvalidInput(format!(
+                            "Job '{}' doesn't pass required secret '{}' to '{}'",
+                            caller_id, name, reference
+                        )));
+                    }
+                    None => {
+                        secrets.insert(name.clone(), "''".to_string());
+                    }
+                }
+            }
+            if let Some(name) = passed.keys().find(|name| !call.secrets.contains_key(*name)) {
+                return Err(ServiceError::InvalidInput(format!(
+                    "Job '{}' passes secret '{}', which '{}' doesn't declare",
+                    caller_id, name, reference
+                )));
+            }
+            Some(secrets)
+        }
+    };
+
+    Ok(Bindings { inputs, secrets })
+}
+
+/// The called workflow's jobs, renamed under the caller and with its inputs substituted.
+fn inline_jobs(
+    caller_id: &str,
+    caller: &Job,
+    called: Workflow,
+    bindings: &Bindings,
+) -> ServiceResult<HashMap<String, Job>> {
+    let called_ids: Vec<String> = called.jobs.keys().cloned().collect();
+    let prefixed = |id: &str| format!("{}-{}", caller_id, id);
+    let caller_name = caller.name.clone().unwrap_or_else(|| caller_id.to_string());
+
+    let mut jobs = HashMap::new();
+    for (id, mut job) in called.jobs {
+        for (name, value) in &called.env {
+            job.env.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+        if job.defaults.is_none() {
+            job.defaults = called.defaults.clone();
+        }
+
+        let mut job = rewrite_job(&job, &|expr| {
+            let expr =
+                replace_references(expr, "inputs.", &|name| bindings.inputs.get(name).cloned());
+            let expr = match &bindings.secrets {
+                Some(secrets) => {
+                    replace_references(&expr, "secrets.", &|name| secrets.get(name).cloned())
+                }
+                None => expr,
+            };
+            replace_references(&expr, "needs.", &|name| {
+                called_ids
+                    .iter()
+                    .any(|id| id == name)
+                    .then(|| format!("needs.{}", prefixed(name)))
+            })
+        })?;
+
+        job.needs = if job.needs.is_empty() {
+            caller.needs.clone()
+        } else {
+            JobNeeds::Multiple(job.needs.to_vec().iter().map(|n| prefixed(n)).collect())
+        };
+        job.if_condition = match (&caller.if_condition, job.if_condition.take()) {
+            (Some(outer), Some(inner)) => Some(format!(
+                "({}) && ({})",
+                condition_expression(outer),
+                condition_expression(&inner)
+            )),
+            (outer, inner) => inner.or_else(|| outer.clone()),
+        };
+        job.name = Some(format!(
+            "{} / {}",
+            caller_name,
+            job.name.as_deref().unwrap_or(&id)
+        ));
+        if job.permissions.is_none() {
+            job.permissions = caller.permissions.clone();
+        }
+        if caller.strategy.is_some() {
+            if job.strategy.is_some() {
+                return Err(ServiceError::InvalidInput(format!(
+                    "Job '{}' has a matrix and calls a workflow whose job '{}' has one too",
+                    caller_id, id
+                )));
+            }
+            job.strategy = caller.strategy.clone();
+        }
+
+        jobs.insert(prefixed(&id), job);
+    }
+    Ok(jobs)
+}
+
+/// Expressions for the caller's outputs, in terms of the inlined jobs.
+fn caller_outputs(
+    caller_id: &str,
+    call: &EventConfig,
+    bindings: &Bindings,
+) -> HashMap<String, String> {
+    call.outputs
+        .iter()
+        .map(|(name, output)| {
+            let expr = match whole_expression(&output.value) {
+                Some(expr) => {
+                    let expr = replace_references(expr, "inputs.", &|input| {
+                        bindings.inputs.get(input).cloned()
+                    });
+                    let expr = replace_references(&expr, "jobs.", &|job| {
+                        Some(format!("needs.{}-{}", caller_id, job))
+                    });
+                    format!("({})", expr)
+                }
+                None => quote(&output.value),
+            };
+            (name.clone(), expr)
+        })
+        .collect()
+}
+
+/// Apply `rewrite` to every expression in `job`'s strings.
+fn rewrite_job(job: &Job, rewrite: &dyn Fn(&str) -> String) -> ServiceResult<Job> {
+    let mut value = serde_json::to_value(job)
+        .map_err(|e| ServiceError::Internal(format!("Failed to rewrite job: {}", e)))?;
+    rewrite_value(&mut value, false, rewrite);
+    serde_json::from_value(value)
+        .map_err(|e| ServiceError::Internal(format!("Failed to rewrite job: {}", e)))
+}
+
+fn rewrite_value(value: &mut Value, condition: bool, rewrite: &dyn Fn(&str) -> String) {
+    match value {
+        Value::String(text) => *text = rewrite_text(text, condition, rewrite),
+        Value::Array(items) => {
+            for item in items {
+                rewrite_value(item, false, rewrite);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                rewrite_value(item, key == "if", rewrite);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Apply `rewrite` to each `${{ }}` in `text`, or to all of it for a bare `if:` condition.
+fn rewrite_text(text: &str, condition: bool, rewrite: &dyn Fn(&str) -> String) -> String {
+    if condition && !text.contains("${{") {
+        return rewrite(text);
+    }
+
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${{") {
+        let Some(length) = rest[start..].find("}}") else {
+            break;
+        };
+        let original = &rest[start..start + length + 2];
+        let expr = rest[start + 3..start + length].trim();
+        let rewritten = rewrite(expr);
+
+        result.push_str(&rest[..start]);
+        if rewritten == expr {
+            result.push_str(original);
+        } else if let Some(literal) = literal_text(&rewritten) {
+            result.push_str(&literal);
+        } else {
+            result.push_str(&format!("${{{{ {} }}}}", rewritten));
+        }
+        rest = &rest[start + length + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Replace `prefix<name>` references in `expr` with `lookup(name)`, leaving
+/// string literals and names `lookup` doesn't know alone.
+fn replace_references(expr: &str, prefix: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+
+    let mut result = String::new();
+    let mut in_string = false;
+    let mut previous: Option<char> = None;
+    let mut index = 0;
+    while let Some(c) = expr[index..].chars().next() {
+        let rest = &expr[index..];
+        if c == '\'' {
+            in_string = !in_string;
+        } else if !in_string
+            && rest.starts_with(prefix)
+            && !previous.is_some_and(|p| is_name(p) || p == '.')
+        {
+            let name_length = rest[prefix.len()..]
+                .find(|c: char| !is_name(c))
+                .unwrap_or(rest.len() - prefix.len());
+            let name = &rest[prefix.len()..prefix.len() + name_length];
+            if let Some(replacement) = lookup(name) {
+                result.push_str(&replacement);
+                index += prefix.len() + name_length;
+                previous = name.chars().last();
+                continue;
+            }
+        }
+        result.push(c);
+        previous = Some(c);
+        index += c.len_utf8();
+    }
+    result
+}
+
+/// The expression inside `text` when all of it is a single `${{ }}`.
+fn whole_expression(text: &str) -> Option<&str> {
+    let inner = text.trim().strip_prefix("${{")?.strip_suffix("}}")?;
+    (!inner.contains("${{")).then(|| inner.trim())
+}
+
+/// An expression that evaluates to `value`.
+fn expression_for(value: &Value) -> String {
+    match value {
+        Value::String(text) => match whole_expression(text) {
+            Some(expr) => format!("({})", expr),
+            None => quote(text),
+        },
+        Value::Null => "null".to_string(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(_) | Value::Object(_) => format!("fromJSON({})", quote(&value.to_string())),
+    }
+}
+
+/// The text a literal expression stands for, when `expr` is one.
+fn literal_text(expr: &str) -> Option<String> {
+    if let Some(inner) = expr
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+    {
+        return (!inner.replace("''", "").contains('\'')).then(|| inner.replace("''", "'"));
+    }
+    match expr {
+        "true" | "false" => Some(expr.to_string()),
+        "null" => Some(String::new()),
+        _ => expr.parse::<f64>().is_ok().then(|| expr.to_string()),
+    }
+}
+
+/// A condition written with or without `${{ }}`, as a bare expression.
+fn condition_expression(condition: &str) -> &str {
+    whole_expression(condition).unwrap_or(condition)
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    fn write(dir: &Path, path: &str, content: &str) {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_inline_local_reusable_workflow() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write(
+            dir.path(),
+            ".github/workflows/deploy.yml",
+            r#"
+on:
+  workflow_call:
+    inputs:
+      environment:
+        type: string
+        required: true
+      dry-run:
+        type: boolean
+        default: false
+    secrets:
+      token:
+        required: true
+    outputs:
+      url:
+        value: ${{ jobs.release.outputs.url }}
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo building for ${{ inputs.environment }}
+  release:
+    needs: build
+    if: ${{ !inputs.dry-run }}
+    runs-on: ubuntu-latest
+    outputs:
+      url: ${{ steps.publish.outputs.url }}
+    steps:
+      - id: publish
+        run: ./publish.sh
+        env:
+          TOKEN: ${{ secrets.token }}
+          PREVIOUS: ${{ needs.build.result }}
+"#,
+        );
+        let workflow = WorkflowParser::parse(
+            r#"
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - run: cargo test
+  deploy:
+    needs: test
+    uses: ./.github/workflows/deploy.yml
+    with:
+      environment: staging
+    secrets:
+      token: ${{ secrets.STAGING_TOKEN }}
+  notify:
+    needs: deploy
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo "deployed to ${{ needs.deploy.outputs.url }}"
+"#,
+        )
+        .unwrap();
+
+        let resolved = WorkflowResolver::new(dir.path()).resolve(workflow).unwrap();
+        WorkflowParser::validate(&resolved).unwrap();
+
+        let mut ids: Vec<&String> = resolved.jobs.keys().collect();
+        ids.sort();
+        assert_eq!(ids, ["deploy-build", "deploy-release", "notify", "test"]);
+
+        let build = &resolved.jobs["deploy-build"];
+        assert_eq!(build.name.as_deref(), Some("deploy / build"));
+        assert_eq!(build.needs.to_vec(), ["test"]);
+        assert_eq!(
+            build.steps[0].run.as_deref(),
+            Some("echo building for staging")
+        );
+
+        let release = &resolved.jobs["deploy-release"];
+        assert_eq!(release.needs.to_vec(), ["deploy-build"]);
+        assert_eq!(release.if_condition.as_deref(), Some("${{ !false }}"));
+        let env = &release.steps[0].env;
+        assert_eq!(env["TOKEN"], "${{ (secrets.STAGING_TOKEN) }}");
+        assert_eq!(env["PREVIOUS"], "${{ needs.deploy-build.result }}");
+
+        let notify = &resolved.jobs["notify"];
+        assert_eq!(notify.needs.to_vec(), ["deploy-build", "deploy-release"]);
+        assert_eq!(
+            notify.steps[0].run.as_deref(),
+            Some("echo \"deployed to ${{ (needs.deploy-release.outputs.url) }}\"")
+        );
+    }
+
+    #[test]
+    fn test_remote_workflow_inputs_and_cycles() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let shared = dir.path().join("shared");
+        write(
+            &shared,
+            ".github/workflows/lint.yml",
+            r#"
+on:
+  workflow_call:
+    inputs:
+      path:
+        type: string
+        required: true
+jobs:
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - run: lint ${{ inputs.path }}
+"#,
+        );
+        write(
+            dir.path(),
+            ".github/workflows/loop.yml",
+            r#"
+on: workflow_call
+jobs:
+  again:
+    uses: ./.github/workflows/loop.yml
+"#,
+        );
+        let resolver =
+            || WorkflowResolver::new(dir.path()).with_repository("octo/shared", shared.clone());
+        let caller = |uses: &str, with: &str| {
+            WorkflowParser::parse(&format!(
+                "on: push\njobs:\n  check:\n    uses: {}\n    with: {}\n",
+                uses, with
+            ))
+            .unwrap()
+        };
+
+        let resolved = resolver()
+            .resolve(caller(
+                "octo/shared/.github/workflows/lint.yml@v1",
+                "{path: src}",
+            ))
+            .unwrap();
+        assert_eq!(
+            resolved.jobs["check-lint"].steps[0].run.as_deref(),
+            Some("lint src")
+        );
+
+        let missing = resolver()
+            .resolve(caller("octo/shared/.github/workflows/lint.yml@v1", "{}"))
+            .unwrap_err();
+        assert!(missing.to_string().contains("required input 'path'"));
+
+        let unknown = resolver()
+            .resolve(caller(
+                "octo/shared/.github/workflows/lint.yml@v1",
+                "{path: src, fix: true}",
+            ))
+            .unwrap_err();
+        assert!(unknown.to_string().contains("input 'fix'"));
+
+        let unconfigured = resolver()
+            .resolve(caller("octo/other/.github/workflows/lint.yml@v1", "{}"))
+            .unwrap_err();
+        assert!(unconfigured.to_string().contains("no local checkout"));
+
+        let cycle = resolver()
+            .resolve(caller("./.github/workflows/loop.yml", "{}"))
+            .unwrap_err();
+        assert!(cycle.to_string().contains("Circular reusable workflow"));
+    }
+}
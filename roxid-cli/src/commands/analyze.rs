@@ -0,0 +1,165 @@
+use crate::output::{self, Tone};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Args;
+use color_eyre::Result;
+
+use pipeline_service::execution::{JobTimings, PipelineAnalysis};
+use pipeline_service::parser::split_document_selector;
+use pipeline_service::{normalize_pipeline, AzureParser, ExecutionGraph};
+
+/// Forecast a pipeline's duration and find its critical path
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    /// Path to the pipeline YAML file
+    pub pipeline: PathBuf,
+
+    /// Job durations recorded with 'roxid run --timings FILE', or a run record or
+    /// Azure DevOps timeline to forecast with real cloud durations
+    #[arg(long, value_name = "FILE")]
+    pub timings: Option<PathBuf>,
+
+    /// Number of jobs that can run at once for the forecast (0 = unlimited)
+    #[arg(long, short = 'j', default_value = "1", value_name = "N")]
+    pub parallel: usize,
+
+    /// Seconds per step assumed for jobs without a recorded duration
+    #[arg(long, default_value = "30", value_name = "SECONDS")]
+    pub step_estimate: u64,
+}
+
+pub fn execute(args: AnalyzeArgs) -> Result<()> {
+    let (file, _) = split_document_selector(&args.pipeline);
+    if !file.exists() {
+        color_eyre::eyre::bail!("Pipeline file not found: {}", file.display());
+    }
+
+    let pipeline = AzureParser::parse_file(&args.pipeline)
+        .map(normalize_pipeline)
+        .map_err(|e| color_eyre::eyre::eyre!("Parse error: {}", e.message))?;
+    let graph = ExecutionGraph::from_pipeline(&pipeline).map_err(|e| {
+        color_eyre::eyre::eyre!(
+            "Failed to build execution graph for {}: {}",
+            args.pipeline.display(),
+            e.message
+        )
+    })?;
+
+    let timings = match &args.timings {
+        Some(path) => JobTimings::load(path).map_err(|e| color_eyre::eyre::eyre!("{}", e))?,
+        None => JobTimings::default(),
+    };
+
+    let analysis = PipelineAnalysis::new(&graph, &timings, Duration::from_secs(args.step_estimate));
+    if analysis.jobs.is_empty() {
+        output::warning("Pipeline has no jobs");
+        return Ok(());
+    }
+
+    let estimated = analysis.jobs.iter().filter(|j| !j.measured).count();
+    if estimated > 0 {
+        output::warning(&format!(
+            "{} of {} job(s) have no recorded duration; assuming {}s per step",
+            estimated,
+            analysis.jobs.len(),
+            args.step_estimate
+        ));
+        if args.timings.is_none() {
+            output::dim(
+                "  Record durations with 'roxid run --timings FILE' and pass --timings FILE",
+            );
+        }
+    }
+
+    let width = analysis
+        .jobs
+        .iter()
+        .map(|j| j.key.len())
+        .max()
+        .unwrap_or(0)
+        .max("Job".len());
+
+    println!();
+    println!(
+        "{}",
+        output::bold(&format!(
+            "    {:<width$}  {:>10}  {:>10}  {:>10}",
+            "Job",
+            "Duration",
+            "Start",
+            "Slack",
+            width = width
+        ))
+    );
+    for job in &analysis.jobs {
+        let critical = analysis.critical_path.contains(&job.key);
+        println!(
+            "  {} {:<width$}  {:>10}  {:>10}  {:>10}",
+            if critical {
+                output::paint(Tone::Failure, false, "*")
+            } else {
+                " ".to_string()
+            },
+            job.key,
+            format!(
+                "{}{}",
+                if job.measured { "" } else { "~" },
+                format_duration(job.duration)
+            ),
+            format_duration(job.earliest_start),
+            format_duration(job.slack),
+            width = width
+        );
+    }
+    output::dim("  * on the critical path, ~ estimated from the step count");
+
+    println!();
+    output::info(&format!(
+        "Critical path: {}",
+        analysis.critical_path.join(" → ")
+    ));
+    output::info(&format!(
+        "Minimum duration (unlimited parallelism): {}",
+        format_duration(analysis.critical_duration)
+    ));
+    output::info(&format!(
+        "Forecast with {} at a time: {}",
+        if args.parallel == 0 {
+            "unlimited jobs".to_string()
+        } else {
+            format!("{} job(s)", args.parallel)
+        },
+        format_duration(analysis.duration_with_parallelism(args.parallel))
+    ));
+    output::info(&format!(
+        "Serial duration: {}",
+        format_duration(analysis.serial_duration)
+    ));
+
+    let bottlenecks = analysis.bottlenecks();
+    if !bottlenecks.is_empty() {
+        println!();
+        output::header("Speeding up these jobs shortens the pipeline the most");
+        for job in bottlenecks.iter().take(5) {
+            println!(
+                "    {:<width$}  up to {} shorter",
+                job.key,
+                format_duration(job.impact),
+                width = width
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs >= 60.0 {
+        format!("{}m {:02}s", (secs / 60.0) as u64, (secs % 60.0) as u64)
+    } else {
+        format!("{:.2}s", secs)
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use clap::{Arg, Args, Command, ValueEnum};
+use color_eyre::Result;
+
+use pipeline_service::execution::{RunHistory, LAST_RUN};
+use pipeline_service::parser::models::Job;
+use pipeline_service::{AzureParser, Pipeline};
+
+/// Print a shell completion script
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate the script for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Complete a partial command line (used by the completion scripts)
+#[derive(Args, Debug)]
+pub struct CompleteArgs {
+    /// Words after 'roxid'; the last one is the word being completed
+    #[arg(last = true, allow_hyphen_values = true)]
+    pub words: Vec<String>,
+}
+
+const BASH: &str = r#"_roxid() {
+    local IFS=$'\n'
+    COMPREPLY=($(roxid __complete -- "${COMP_WORDS[@]:1:$COMP_CWORD}" 2>/dev/null))
+    if [[ ${#COMPREPLY[@]} -eq 1 && ${COMPREPLY[0]} == */ ]]; then
+        compopt -o nospace
+    fi
+}
+complete -F _roxid roxid
+"#;
+
+const ZSH: &str = r#"#compdef roxid
+_roxid() {
+    local -a candidates
+    candidates=(${(f)"$(roxid __complete -- "${(@)words[2,$CURRENT]}" 2>/dev/null)"})
+    compadd -S '' -- ${(M)candidates:#*/}
+    compadd -- ${candidates:#*/}
+}
+compdef _roxid roxid
+"#;
+
+const FISH: &str = r#"complete -c roxid -f -a '(roxid __complete -- (commandline -opc)[2..-1] (commandline -ct))'
+"#;
+
+pub fn print_script(args: CompletionsArgs) -> Result<()> {
+    print!(
+        "{}",
+        match args.shell {
+            Shell::Bash => BASH,
+            Shell::Zsh => ZSH,
+            Shell::Fish => FISH,
+        }
+    );
+    Ok(())
+}
+
+pub fn execute(args: CompleteArgs, cli: Command) -> Result<()> {
+    for candidate in complete(&cli, &args.words) {
+        println!("{}", candidate);
+    }
+    Ok(())
+}
+
+/// Candidates for the last of `words`, given the words before it
+fn complete(cli: &Command, words: &[String]) -> Vec<String> {
+    let (current, before) = match words.split_last() {
+        Some((current, before)) => (current.as_str(), before),
+        None => ("", &[][..]),
+    };
+
+    // Find the (sub)command being completed and the option expecting a value
+    let mut command = cli;
+    let mut positionals = Vec::new();
+    let mut pending: Option<&Arg> = None;
+    for word in before {
+        if pending.take().is_some() {
+            continue;
+        }
+        if word.starts_with('-') {
+            if !word.contains('=') {
+                pending = find_option(command, word).filter(|arg| takes_value(arg));
+            }
+        } else if let Some(sub) = command.find_subcommand(word) {
+            command = sub;
+        } else {
+            positionals.push(word.as_str());
+        }
+    }
+
+    let candidates: Vec<String> = match pending {
+        Some(arg) => {
+            let pipeline = positionals
+                .iter()
+                .find(|word| is_yaml(word))
+                .and_then(|path| AzureParser::parse_file(Path::new(path)).ok());
+            match (arg.get_id().as_str(), &pipeline) {
+                ("stage", Some(pipeline)) => stage_names(pipeline),
+                ("job", Some(pipeline)) => job_names(pipeline),
+                ("skip_steps", Some(pipeline)) => step_names(pipeline),
+                _ if takes_path(arg) => paths(current, false),
+                _ => Vec::new(),
+            }
+        }
+        None if current.starts_with('-') => command
+            .get_arguments()
+            .filter(|arg| !arg.is_hide_set())
+            .filter_map(|arg| arg.get_long().map(|long| format!("--{}", long)))
+            .chain(["--help".to_string()])
+            .collect(),
+        None => {
+            let mut candidates: Vec<String> = command
+                .get_subcommands()
+                .filter(|sub| !sub.is_hide_set())
+                .map(|sub| sub.get_name().to_string())
+                .collect();
+            if let Some(arg) = command.get_positionals().nth(positionals.len()) {
+                if arg.get_id() == "run" {
+                    candidates.extend(run_ids());
+                } else if takes_path(arg) {
+                    candidates.extend(paths(current, arg.get_id() == "pipeline"));
+                }
+            }
+            candidates
+        }
+    };
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(current))
+        .collect()
+}
+
+fn find_option<'a>(command: &'a Command, word: &str) -> Option<&'a Arg> {
+    command.get_arguments().find(|arg| {
+        word.strip_prefix("--")
+            .map(|long| arg.get_long() == Some(long))
+            .or_else(|| {
+                word.strip_prefix('-')
+                    .and_then(|short| short.chars().next())
+                    .map(|short| arg.get_short() == Some(short))
+            })
+            .unwrap_or(false)
+    })
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+/// Whether an argument is a file or directory, judged by its value name
+fn takes_path(arg: &Arg) -> bool {
+    let id = arg.get_id().as_str();
+    let value_names = arg.get_value_names().unwrap_or_default();
+    ["pipeline", "pipelines", "path", "file", "files"].contains(&id)
+        || value_names
+            .iter()
+            .any(|name| ["FILE", "DIR", "PATH"].contains(&name.as_str()))
+}
+
+fn is_yaml(path: &str) -> bool {
+    path.ends_with(".yml") || path.ends_with(".yaml")
+}
+
+/// Files and directories starting with `prefix`; directories end with '/'
+fn paths(prefix: &str, yaml_only: bool) -> Vec<String> {
+    let (dir, name) = match prefix.rfind('/') {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("", prefix),
+    };
+    let Ok(entries) = std::fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !file_name.starts_with(name)
+                || (file_name.starts_with('.') && !name.starts_with('.'))
+            {
+                return None;
+            }
+            let path = format!("{}{}", dir, file_name);
+            if entry.path().is_dir() {
+                Some(format!("{}/", path))
+            } else if !yaml_only || is_yaml(&path) {
+                Some(path)
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// `last` and the saved run IDs, newest first
+fn run_ids() -> Vec<String> {
+    let mut ids = RunHistory::default().run_ids().unwrap_or_default();
+    ids.reverse();
+    std::iter::once(LAST_RUN.to_string()).chain(ids).collect()
+}
+
+fn stage_names(pipeline: &Pipeline) -> Vec<String> {
+    pipeline
+        .stages
+        .iter()
+        .filter_map(|stage| stage.stage.clone())
+        .collect()
+}
+
+/// Job names, plus `Stage.Job` for jobs in explicit stages
+fn job_names(pipeline: &Pipeline) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    for job in pipeline.jobs.iter().filter_map(Job::identifier) {
+        names.insert(job.to_string());
+    }
+    for stage in &pipeline.stages {
+        for job in stage.jobs.iter().filter_map(Job::identifier) {
+            names.insert(job.to_string());
+            if let Some(stage) = &stage.stage {
+                names.insert(format!("{}.{}", stage, job));
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+/// Step names and display names
+fn step_names(pipeline: &Pipeline) -> Vec<String> {
+    let jobs = pipeline
+        .jobs
+        .iter()
+        .chain(pipeline.stages.iter().flat_map(|stage| stage.jobs.iter()));
+    let steps = pipeline
+        .steps
+        .iter()
+        .chain(jobs.flat_map(|job| job.steps.iter()));
+
+    let mut names = BTreeSet::new();
+    for step in steps {
+        names.extend(step.name.clone());
+        names.extend(step.display_name.clone());
+    }
+    names.into_iter().collect()
+}
@@ -0,0 +1,173 @@
+use crate::output;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use color_eyre::Result;
+
+use pipeline_service::execution::{LocalVariables, RoxidConfig, CONFIG_FILE, LOCAL_VARS_FILE};
+use pipeline_service::utils::find_repo_root;
+
+/// Show the project configuration roxid picks up
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Show .roxid.toml profiles and .roxid/vars.yml overrides
+    Show {
+        /// List the variables set outside the pipeline, in precedence order
+        #[arg(long)]
+        vars: bool,
+
+        /// Include this .roxid.toml profile's variables
+        #[arg(long, value_name = "NAME", env = "ROXID_PROFILE")]
+        profile: Option<String>,
+
+        /// Repository directory (default: repository root)
+        #[arg(long, short = 'w', value_name = "DIR")]
+        working_dir: Option<PathBuf>,
+    },
+}
+
+pub fn execute(args: ConfigArgs) -> Result<()> {
+    match args.command {
+        ConfigCommand::Show {
+            vars,
+            profile,
+            working_dir,
+        } => {
+            let repo_dir = match working_dir {
+                Some(dir) => dir,
+                None => {
+                    let cwd = std::env::current_dir()?;
+                    find_repo_root(&cwd).unwrap_or(cwd)
+                }
+            };
+            let config =
+                RoxidConfig::discover(&repo_dir).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            let local = LocalVariables::discover(&repo_dir)
+                .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+
+            if vars {
+                show_vars(&config, profile.as_deref(), local.as_ref())?;
+            } else {
+                show_files(&repo_dir, &config, local.as_ref());
+            }
+            if let Some(local) = &local {
+                warn_if_tracked(&repo_dir, &local.path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn show_files(repo_dir: &Path, config: &RoxidConfig, local: Option<&LocalVariables>) {
+    let config_path = repo_dir.join(CONFIG_FILE);
+    if config_path.is_file() {
+        let mut names: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        names.sort();
+        output::check(&format!(
+            "{}: {} profile(s){}",
+            config_path.display(),
+            names.len(),
+            if names.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", names.join(", "))
+            }
+        ));
+    } else {
+        output::dim(&format!("  No {} in {}", CONFIG_FILE, repo_dir.display()));
+    }
+
+    match local {
+        Some(local) => output::check(&format!(
+            "{}: {} variable override(s)",
+            local.path.display(),
+            local.variables.len()
+        )),
+        None => output::dim(&format!(
+            "  No {} in {}",
+            LOCAL_VARS_FILE,
+            repo_dir.display()
+        )),
+    }
+}
+
+/// Variables from each source, highest precedence first; shadowed ones are marked
+fn show_vars(
+    config: &RoxidConfig,
+    profile: Option<&str>,
+    local: Option<&LocalVariables>,
+) -> Result<()> {
+    output::header("Variable precedence (highest first)");
+    output::dim("  1. --var on the command line");
+    output::dim(&format!("  2. {} (this machine only)", LOCAL_VARS_FILE));
+    output::dim(&format!(
+        "  3. the --profile's variables in {}",
+        CONFIG_FILE
+    ));
+    output::dim("  4. the pipeline's own variables");
+    println!();
+
+    let mut seen = HashSet::new();
+    match local {
+        Some(local) => {
+            output::status("Local", &local.path.display().to_string());
+            if local.variables.is_empty() {
+                output::dim("  (no variables)");
+            }
+            for (name, value) in &local.variables {
+                seen.insert(name.clone());
+                println!("  {} = {}", name, value);
+            }
+        }
+        None => output::dim(&format!("  No {}", LOCAL_VARS_FILE)),
+    }
+
+    if let Some(name) = profile {
+        let profile = config
+            .profile(name)
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+        output::status("Profile", name);
+        if profile.variables.is_empty() {
+            output::dim("  (no variables)");
+        }
+        let mut variables: Vec<_> = profile.variables.iter().collect();
+        variables.sort();
+        for (name, value) in variables {
+            if seen.contains(name) {
+                println!(
+                    "  {} = {}  (overridden by {})",
+                    name, value, LOCAL_VARS_FILE
+                );
+            } else {
+                println!("  {} = {}", name, value);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The overrides are personal; warn when git would commit them
+fn warn_if_tracked(repo_dir: &Path, path: &Path) {
+    let ignored = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(["check-ignore", "--quiet"])
+        .arg(path)
+        .status();
+    if matches!(ignored.map(|status| status.code()), Ok(Some(1))) {
+        output::warning(&format!(
+            "{} isn't ignored by git; add it to .gitignore so it isn't committed",
+            LOCAL_VARS_FILE
+        ));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::output;
+
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use color_eyre::Result;
+
+use pipeline_service::parser::TemplateDoc;
+
+/// Generate markdown documentation for a template library
+#[derive(Args, Debug)]
+pub struct DocsArgs {
+    /// Directory of template files (searched recursively)
+    pub templates: PathBuf,
+
+    /// Write one page per template plus an index to DIR instead of printing
+    #[arg(long, short, value_name = "DIR")]
+    pub out: Option<PathBuf>,
+}
+
+pub fn execute(args: DocsArgs) -> Result<()> {
+    if !args.templates.is_dir() {
+        color_eyre::eyre::bail!("Template directory not found: {}", args.templates.display());
+    }
+
+    let mut docs = Vec::new();
+    for file in yaml_files(&args.templates)? {
+        let relative = file.strip_prefix(&args.templates).unwrap_or(&file);
+        let reference = file.to_string_lossy().replace('\\', "/");
+        let content = std::fs::read_to_string(&file)?;
+        match TemplateDoc::parse(reference.trim_start_matches("./"), &content) {
+            // Files with neither parameters nor a header comment are usually pipelines
+            Ok(doc) if doc.parameters.is_empty() && doc.description.is_none() => {}
+            Ok(doc) => docs.push((relative.to_path_buf(), doc)),
+            Err(e) => output::warning(&format!("Skipping {}: {}", file.display(), e.message)),
+        }
+    }
+
+    if docs.is_empty() {
+        output::warning(&format!(
+            "No templates with parameters found in {}",
+            args.templates.display()
+        ));
+        return Ok(());
+    }
+
+    let Some(out) = args.out else {
+        let pages: Vec<String> = docs.iter().map(|(_, doc)| doc.to_markdown()).collect();
+        print!("{}", pages.join("\n"));
+        return Ok(());
+    };
+
+    let mut index = String::from(
+        "# Templates\n\n| Template | Kind | Description |\n|----------|------|-------------|\n",
+    );
+    for (relative, doc) in &docs {
+        let page = relative.with_extension("md");
+        let path = out.join(&page);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, doc.to_markdown())?;
+
+        let summary = doc
+            .description
+            .as_deref()
+            .and_then(|d| d.lines().next())
+            .unwrap_or_default();
+        index.push_str(&format!(
+            "| [{}]({}) | {} | {} |\n",
+            doc.path,
+            page.to_string_lossy().replace('\\', "/"),
+            doc.kind,
+            summary.replace('|', "\\|")
+        ));
+    }
+    std::fs::create_dir_all(&out)?;
+    std::fs::write(out.join("README.md"), index)?;
+
+    output::success(&format!(
+        "Documented {} template(s) in {}",
+        docs.len(),
+        out.display()
+    ));
+    Ok(())
+}
+
+/// YAML files under `dir`, sorted
+fn yaml_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for extension in ["yml", "yaml"] {
+        let pattern = dir.join(format!("**/*.{}", extension));
+        for entry in glob::glob(&pattern.to_string_lossy())?.flatten() {
+            files.push(entry);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,178 @@
+use crate::output;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::Result;
+
+use pipeline_service::execution::diagnostics;
+use pipeline_service::execution::{Diagnostic, DiagnosticStatus, Preflight, Requirement};
+use pipeline_service::parser::split_document_selector;
+use pipeline_service::utils::find_repo_root;
+use pipeline_service::{normalize_pipeline, AzureParser, TaskCache};
+
+/// Check the local environment and the tools a pipeline needs
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Pipeline YAML file whose tools and compose ports to check as well
+    pub pipeline: Option<PathBuf>,
+
+    /// Define a shell for `shell:` on script steps, as for `roxid run --shell`
+    #[arg(long = "shell", value_name = "NAME=COMMAND")]
+    pub shells: Vec<String>,
+
+    /// Task cache directory to check (default: ~/.roxid/tasks)
+    #[arg(long, value_name = "DIR")]
+    pub task_cache: Option<PathBuf>,
+
+    /// Working directory compose files are relative to (default: repository root)
+    #[arg(long, value_name = "DIR")]
+    pub working_dir: Option<PathBuf>,
+}
+
+pub fn execute(args: DoctorArgs) -> Result<()> {
+    let mut shells = HashMap::new();
+    for shell_str in &args.shells {
+        match shell_str.split_once('=') {
+            Some((name, command)) if !command.trim().is_empty() => {
+                shells.insert(name.to_string(), command.to_string());
+            }
+            _ => color_eyre::eyre::bail!(
+                "Invalid shell format '{}'. Expected name=command (e.g. perl='perl {{0}}')",
+                shell_str
+            ),
+        }
+    }
+
+    output::header("Environment");
+    let cache_dir = args
+        .task_cache
+        .clone()
+        .unwrap_or_else(|| TaskCache::new().cache_dir().to_path_buf());
+    let mut diagnostics = diagnostics::environment(&cache_dir);
+    report(&diagnostics);
+
+    let mut missing_tools = 0;
+    if let Some(pipeline_path) = &args.pipeline {
+        let (file, _) = split_document_selector(pipeline_path);
+        if !file.exists() {
+            color_eyre::eyre::bail!("Pipeline file not found: {}", file.display());
+        }
+
+        println!();
+        output::header(&format!("Pipeline {}", pipeline_path.display()));
+        let pipeline = match AzureParser::parse_file(pipeline_path) {
+            Ok(pipeline) => normalize_pipeline(pipeline),
+            Err(e) => {
+                output::parse_error(&e, pipeline_path);
+                std::process::exit(1);
+            }
+        };
+
+        let preflight = Preflight::scan(&pipeline, &shells);
+        let mut missing = Vec::new();
+        for requirement in &preflight.requirements {
+            if requirement.is_available() {
+                output::check(&format!(
+                    "{} (used by {})",
+                    requirement.tool,
+                    used_by(requirement)
+                ));
+            } else {
+                missing.push(requirement);
+            }
+        }
+        if preflight.requirements.is_empty() {
+            output::check("The pipeline doesn't run any external tools");
+        }
+
+        let working_dir = match &args.working_dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let cwd = std::env::current_dir()?;
+                find_repo_root(&cwd).unwrap_or(cwd)
+            }
+        };
+        let ports = diagnostics::port_conflicts(&pipeline, &working_dir);
+        report(&ports);
+        diagnostics.extend(ports);
+
+        if !missing.is_empty() {
+            report_missing(&missing);
+            missing_tools = missing.len();
+        }
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.status == DiagnosticStatus::Error)
+        .count()
+        + missing_tools;
+    let warnings = diagnostics
+        .iter()
+        .filter(|d| d.status == DiagnosticStatus::Warning)
+        .count();
+
+    println!();
+    match (errors, warnings) {
+        (0, 0) => output::success("Everything looks good"),
+        (0, _) => output::success(&format!(
+            "No problems that stop pipelines from running ({} warning(s))",
+            warnings
+        )),
+        _ => {
+            output::failure(&format!(
+                "{} problem(s) found, {} warning(s)",
+                errors, warnings
+            ));
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Print diagnostics with the fix for each warning and error
+fn report(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        match diagnostic.status {
+            DiagnosticStatus::Ok => output::check(&diagnostic.message),
+            DiagnosticStatus::Warning => output::warning(&diagnostic.message),
+            DiagnosticStatus::Error => output::failure(&diagnostic.message),
+        }
+        if let Some(fix) = &diagnostic.fix {
+            output::dim(&format!("    Fix: {}", fix));
+        }
+    }
+}
+
+/// Print missing tools with the steps that need them and how to install them
+pub fn report_missing(missing: &[&Requirement]) {
+    output::error(&format!("{} required tool(s) not found:", missing.len()));
+    for requirement in missing {
+        output::error(&format!(
+            "  - {} (used by {})",
+            requirement.tool,
+            used_by(requirement)
+        ));
+        if let Some(hint) = requirement.install_hint() {
+            output::info(&format!("    Hint: {}", hint));
+        }
+    }
+}
+
+/// The first few users of a tool, e.g. `Build/Test: Run tests and 3 more`
+fn used_by(requirement: &Requirement) -> String {
+    const SHOWN: usize = 2;
+    let mut text = requirement
+        .used_by
+        .iter()
+        .take(SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if requirement.used_by.len() > SHOWN {
+        text.push_str(&format!(" and {} more", requirement.used_by.len() - SHOWN));
+    }
+    text
+}
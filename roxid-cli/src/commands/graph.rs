@@ -0,0 +1,104 @@
+use crate::output;
+
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use color_eyre::Result;
+
+use pipeline_service::parser::{split_document_selector, TemplateGraph};
+use pipeline_service::utils::find_repo_root;
+use pipeline_service::{AzureParser, TemplateEngine};
+
+/// Export a pipeline's template inclusion graph
+#[derive(Args, Debug)]
+pub struct GraphArgs {
+    /// Path to the pipeline YAML file
+    pub pipeline: PathBuf,
+
+    /// Graph which files include which templates, with their parameters
+    #[arg(long, required = true)]
+    pub templates: bool,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value = "dot")]
+    pub format: GraphFormat,
+
+    /// Also list the templates under DIR the pipeline never includes
+    #[arg(long, value_name = "DIR")]
+    pub unused: Option<PathBuf>,
+
+    /// Repository root for template resolution (default: repository root)
+    #[arg(long, value_name = "DIR")]
+    pub repo_root: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+    Json,
+}
+
+pub fn execute(args: GraphArgs) -> Result<()> {
+    let (file, selector) = split_document_selector(&args.pipeline);
+    if !file.exists() {
+        color_eyre::eyre::bail!("Pipeline file not found: {}", file.display());
+    }
+    let repo_root = args.repo_root.clone().unwrap_or_else(|| {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        find_repo_root(&cwd).unwrap_or(cwd)
+    });
+
+    let pipeline = AzureParser::parse_file(&args.pipeline).map_err(|e| {
+        output::parse_error(&e, &args.pipeline);
+        color_eyre::eyre::eyre!("Failed to parse {}", args.pipeline.display())
+    })?;
+    let mut engine = TemplateEngine::new(repo_root.clone());
+    if let Err(e) = engine.resolve_pipeline(pipeline) {
+        output::parse_error(&e, &args.pipeline);
+        color_eyre::eyre::bail!("Failed to resolve templates");
+    }
+
+    let mut pipeline_name = match (file.canonicalize(), repo_root.canonicalize()) {
+        (Ok(pipeline), Ok(root)) => pipeline
+            .strip_prefix(&root)
+            .unwrap_or(&pipeline)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        _ => file.to_string_lossy().replace('\\', "/"),
+    };
+    if let Some(selector) = selector {
+        pipeline_name = format!("{}#{}", pipeline_name, selector);
+    }
+    let mut graph = TemplateGraph::new(pipeline_name, engine.trace());
+    if let Some(library) = &args.unused {
+        graph = graph.with_unused(&repo_root, library);
+    }
+
+    match args.format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+    }
+
+    let chain = graph.deepest_chain();
+    output::info(&format!(
+        "{} template(s) included {} time(s), nested up to {} deep{}",
+        graph.templates().len(),
+        graph.includes.len(),
+        chain.len(),
+        if chain.len() > 1 {
+            format!(": {}", chain.join(" → "))
+        } else {
+            String::new()
+        }
+    ));
+    if !graph.unused.is_empty() {
+        output::warning(&format!(
+            "{} template(s) never included: {}",
+            graph.unused.len(),
+            graph.unused.join(", ")
+        ));
+    }
+    Ok(())
+}
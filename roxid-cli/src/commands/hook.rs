@@ -0,0 +1,307 @@
+use crate::commands::{run, test};
+use crate::output;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::{Args, Subcommand, ValueEnum};
+use color_eyre::Result;
+
+use pipeline_service::utils::{find_repo_root, git_changed_files};
+use pipeline_service::{AzureParser, TestFileParser};
+
+/// Marker line identifying hooks written by roxid
+const HOOK_MARKER: &str = "# roxid-managed hook";
+
+/// Manage git hooks that run pipelines or tests locally
+#[derive(Args, Debug)]
+pub struct HookArgs {
+    #[command(subcommand)]
+    pub command: HookCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HookCommand {
+    /// Install a git hook that runs a pipeline or test suite
+    Install {
+        #[command(flatten)]
+        target: HookTarget,
+
+        /// Overwrite an existing hook that was not installed by roxid
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove a hook previously installed by roxid
+    Uninstall {
+        /// Which git hook to remove
+        #[arg(long, value_enum, default_value = "pre-push")]
+        hook: HookKind,
+    },
+
+    /// Run the hook target (invoked by the installed git hook)
+    Run {
+        #[command(flatten)]
+        target: HookTarget,
+    },
+}
+
+/// What an installed hook runs, and when
+#[derive(Args, Debug, Clone)]
+pub struct HookTarget {
+    /// Which git hook to use
+    #[arg(long, value_enum, default_value = "pre-push")]
+    pub hook: HookKind,
+
+    /// Pipeline to run (default: azure-pipelines.yml)
+    #[arg(long, value_name = "FILE", conflicts_with = "test")]
+    pub pipeline: Option<PathBuf>,
+
+    /// Test file to run instead of a pipeline
+    #[arg(long, value_name = "FILE")]
+    pub test: Option<PathBuf>,
+
+    /// Filter tests by name pattern (with --test)
+    #[arg(long, short = 'f', value_name = "PATTERN", requires = "test")]
+    pub filter: Option<String>,
+
+    /// Only run when changed files match the pipeline's trigger path filters
+    #[arg(long)]
+    pub changed_only: bool,
+}
+
+impl HookTarget {
+    /// Rewrite file paths given relative to `cwd` so they resolve from the
+    /// repository root, where git runs hooks
+    fn relative_to(&self, cwd: &Path, repo_root: &Path) -> Self {
+        let resolve = |path: &PathBuf| {
+            let absolute = cwd.join(path);
+            absolute
+                .strip_prefix(repo_root)
+                .map(Path::to_path_buf)
+                .unwrap_or(absolute)
+        };
+        Self {
+            pipeline: self.pipeline.as_ref().map(resolve),
+            test: self.test.as_ref().map(resolve),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    PreCommit,
+    PrePush,
+}
+
+impl HookKind {
+    fn file_name(self) -> &'static str {
+        match self {
+            HookKind::PreCommit => "pre-commit",
+            HookKind::PrePush => "pre-push",
+        }
+    }
+}
+
+pub async fn execute(args: HookArgs) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let repo_root = find_repo_root(&cwd)
+        .ok_or_else(|| color_eyre::eyre::eyre!("Not inside a git repository"))?;
+
+    match args.command {
+        HookCommand::Install { target, force } => {
+            install(&repo_root, &target.relative_to(&cwd, &repo_root), force)
+        }
+        HookCommand::Uninstall { hook } => uninstall(&repo_root, hook),
+        HookCommand::Run { target } => run_target(&repo_root, target).await,
+    }
+}
+
+fn install(repo_root: &Path, target: &HookTarget, force: bool) -> Result<()> {
+    let hook_path = hooks_dir(repo_root)?.join(target.hook.file_name());
+
+    if hook_path.exists() && !is_roxid_hook(&hook_path) && !force {
+        color_eyre::eyre::bail!(
+            "{} already exists and was not installed by roxid (use --force to overwrite)",
+            hook_path.display()
+        );
+    }
+
+    if let Some(parent) = hook_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&hook_path, hook_script(target))?;
+    make_executable(&hook_path)?;
+
+    output::success(&format!(
+        "Installed {} hook at {}",
+        target.hook.file_name(),
+        hook_path.display()
+    ));
+    Ok(())
+}
+
+fn uninstall(repo_root: &Path, hook: HookKind) -> Result<()> {
+    let hook_path = hooks_dir(repo_root)?.join(hook.file_name());
+
+    if !hook_path.exists() {
+        output::dim(&format!("No {} hook installed", hook.file_name()));
+        return Ok(());
+    }
+    if !is_roxid_hook(&hook_path) {
+        color_eyre::eyre::bail!(
+            "{} was not installed by roxid; leaving it in place",
+            hook_path.display()
+        );
+    }
+
+    std::fs::remove_file(&hook_path)?;
+    output::success(&format!("Removed {} hook", hook.file_name()));
+    Ok(())
+}
+
+async fn run_target(repo_root: &Path, target: HookTarget) -> Result<()> {
+    if target.changed_only && !has_relevant_changes(repo_root, &target)? {
+        output::dim("No changed files match the trigger path filters; skipping");
+        return Ok(());
+    }
+
+    match target.test {
+        Some(file) => {
+            test::execute(test::TestArgs {
+                file: Some(file),
+                filter: target.filter,
+                fail_fast: true,
+                ..Default::default()
+            })
+            .await
+        }
+        None => {
+            run::execute(run::RunArgs::new(
+                target
+                    .pipeline
+                    .unwrap_or_else(|| PathBuf::from("azure-pipelines.yml")),
+            ))
+            .await
+        }
+    }
+}
+
+/// Decide whether any changed file would trigger one of the target pipelines
+fn has_relevant_changes(repo_root: &Path, target: &HookTarget) -> Result<bool> {
+    let changed = match target.hook {
+        HookKind::PreCommit => {
+            git_changed_files(repo_root, None).map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+        }
+        // Compare against the upstream branch; without one, treat everything as changed
+        HookKind::PrePush => match git_changed_files(repo_root, Some("@{upstream}")) {
+            Ok(files) => files,
+            Err(_) => return Ok(true),
+        },
+    };
+
+    if changed.is_empty() {
+        return Ok(false);
+    }
+
+    let pipelines = match &target.test {
+        Some(file) => {
+            let tests = TestFileParser::parse_file(file)
+                .map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+                .tests;
+            // Template tests have no trigger, so any change is relevant
+            if tests.iter().any(|t| t.template.is_some()) {
+                return Ok(true);
+            }
+            tests.into_iter().map(|t| t.pipeline).collect()
+        }
+        None => vec![target
+            .pipeline
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("azure-pipelines.yml"))],
+    };
+
+    for path in pipelines {
+        let pipeline = AzureParser::parse_file(&path)
+            .map_err(|e| color_eyre::eyre::eyre!("Parse error: {}", e.message))?;
+        // No trigger section means CI runs for every change
+        let triggered = match &pipeline.trigger {
+            Some(trigger) => trigger.matches_changed_paths(&changed),
+            None => true,
+        };
+        if triggered {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn hook_script(target: &HookTarget) -> String {
+    let mut command = format!("roxid hook run --hook {}", target.hook.file_name());
+    if let Some(pipeline) = &target.pipeline {
+        command.push_str(&format!(
+            " --pipeline {}",
+            shell_quote(&pipeline.to_string_lossy())
+        ));
+    }
+    if let Some(test) = &target.test {
+        command.push_str(&format!(" --test {}", shell_quote(&test.to_string_lossy())));
+    }
+    if let Some(filter) = &target.filter {
+        command.push_str(&format!(" --filter {}", shell_quote(filter)));
+    }
+    if target.changed_only {
+        command.push_str(" --changed-only");
+    }
+
+    format!(
+        "#!/bin/sh\n{} (installed by `roxid hook install`)\nexec {}\n",
+        HOOK_MARKER, command
+    )
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Resolve the hooks directory, honoring core.hooksPath and linked worktrees
+fn hooks_dir(repo_root: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()?;
+    if !output.status.success() {
+        color_eyre::eyre::bail!(
+            "Failed to locate git hooks directory: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        repo_root.join(path)
+    })
+}
+
+fn is_roxid_hook(path: &Path) -> bool {
+    std::fs::read_to_string(path)
+        .map(|content| content.contains(HOOK_MARKER))
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
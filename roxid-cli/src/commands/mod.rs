@@ -1,4 +1,17 @@
+pub mod analyze;
+pub mod complete;
+pub mod config;
+pub mod docs;
+pub mod doctor;
+pub mod graph;
+pub mod hook;
+pub mod normalize;
+pub mod query;
 pub mod run;
+pub mod run_all;
+pub mod self_update;
 pub mod task;
 pub mod test;
+pub mod timeline;
 pub mod validate;
+pub mod watch;
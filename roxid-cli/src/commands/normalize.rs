@@ -0,0 +1,51 @@
+use crate::output;
+
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::Result;
+
+use pipeline_service::parser::{
+    normalize_documents, split_document_selector, to_canonical_yaml, ParseLimits,
+};
+
+/// Print YAML with anchors, aliases and `<<` merge keys expanded
+#[derive(Args, Debug)]
+pub struct NormalizeArgs {
+    /// Path to the YAML file; `file.yml#<name-or-index>` prints one document
+    /// of a multi-document file
+    pub pipeline: PathBuf,
+}
+
+pub fn execute(args: NormalizeArgs) -> Result<()> {
+    let (file, selector) = split_document_selector(&args.pipeline);
+    if !file.exists() {
+        color_eyre::eyre::bail!("Pipeline file not found: {}", file.display());
+    }
+
+    let content = std::fs::read_to_string(&file)?;
+    let mut documents = normalize_documents(&content, &ParseLimits::default()).map_err(|e| {
+        output::parse_error(&e, &file);
+        color_eyre::eyre::eyre!("Failed to parse {}", file.display())
+    })?;
+
+    if let Some(selector) = selector {
+        let position = documents.iter().enumerate().position(|(i, document)| {
+            i.to_string() == selector
+                || document
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&selector))
+        });
+        match position {
+            Some(i) => documents = vec![documents.swap_remove(i)],
+            None => {
+                color_eyre::eyre::bail!("{} has no document '{}'", file.display(), selector)
+            }
+        }
+    }
+
+    let yaml = to_canonical_yaml(&documents).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+    print!("{}", yaml);
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use clap::Args;
+use color_eyre::Result;
+
+use pipeline_service::execution::{Query, RunHistory, RunRecord};
+
+/// Query a run's results with a jq-like filter
+#[derive(Args, Debug)]
+pub struct QueryArgs {
+    /// Run ID or a unique prefix of one, 'last', or a run record file
+    pub run: String,
+
+    /// Filter, e.g. '.stages[].jobs[].steps[] | select(.result == "failed") | .name'
+    #[arg(default_value = ".")]
+    pub filter: String,
+
+    /// Print strings as JSON instead of raw text
+    #[arg(long)]
+    pub json: bool,
+}
+
+pub fn execute(args: QueryArgs) -> Result<()> {
+    let query =
+        Query::parse(&args.filter).map_err(|e| color_eyre::eyre::eyre!("Invalid filter: {}", e))?;
+
+    let path = Path::new(&args.run);
+    let record = if path.is_file() {
+        RunRecord::load(path)
+    } else {
+        RunHistory::default().load(&args.run)
+    }
+    .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+
+    let document = serde_json::to_value(&record)?;
+    for value in query
+        .apply(&document)
+        .map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+    {
+        match value {
+            serde_json::Value::String(s) if !args.json => println!("{}", s),
+            value => println!("{}", serde_json::to_string_pretty(&value)?),
+        }
+    }
+
+    Ok(())
+}
@@ -1,15 +1,37 @@
+use crate::commands::doctor;
 use crate::output;
+use crate::progress::StepProgress;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use color_eyre::Result;
 
 use pipeline_service::execution::events::progress_channel;
+use pipeline_service::execution::executor::ExecutorConfig;
+use pipeline_service::execution::markdown_summary::run_markdown;
+use pipeline_service::execution::{
+    apply_pools, is_cache_task, referenced_groups, ActiveRun, ChannelSink, ConcurrencyGroup,
+    ConcurrencyPolicy, ExitPolicy, Fault, InfrastructureError, JobTimings, LocalVariables,
+    LogDirSink, LogLine, MatrixSelection, Parallelism, ParityReport, PoolBackend, Preflight,
+    ResourcePool, Resources, RoxidConfig, RunHistory, RunJournal, RunOutcome, RunParameters,
+    RunRecord, RuntimeContext, StepConfirmer, StepIssue, StepPreview, LOCAL_VARS_FILE,
+};
 use pipeline_service::parser::models::{ExecutionContext, JobStatus, StageStatus, StepStatus};
-use pipeline_service::utils::find_repo_root;
-use pipeline_service::{normalize_pipeline, AzureParser, ExecutionEvent, PipelineExecutor};
+use pipeline_service::parser::split_document_selector;
+use pipeline_service::runners::container::{floating_images, ImagePullPolicy};
+use pipeline_service::runners::sandbox::{SandboxProfile, DEFAULT_SANDBOX_IMAGE};
+use pipeline_service::runners::HostOverride;
+use pipeline_service::tasks::{required_tasks, ServiceConnections};
+use pipeline_service::utils::{find_repo_root, GitWorktree};
+use pipeline_service::{
+    normalize_pipeline, AzureParser, EventBus, ExecutionEvent, PipelineExecutor, PipelineFilter,
+    TemplateEngine,
+};
 
 /// Run an Azure DevOps pipeline locally
 #[derive(Args, Debug)]
@@ -21,13 +43,36 @@ pub struct RunArgs {
     #[arg(long = "var", short = 'v', value_name = "NAME=VALUE")]
     pub variables: Vec<String>,
 
-    /// Run only a specific stage
+    /// Read variables from a YAML or JSON file of name: value pairs (--var wins)
+    #[arg(long, value_name = "FILE")]
+    pub vars_file: Option<PathBuf>,
+
+    /// Set a pipeline parameter (can be repeated, format: name=value; lists and
+    /// objects as YAML, e.g. 'regions=[eu, us]')
+    #[arg(long = "param", short = 'p', value_name = "NAME=VALUE")]
+    pub parameters: Vec<String>,
+
+    /// Read pipeline parameters from a YAML or JSON file (--param wins)
+    #[arg(long, value_name = "FILE")]
+    pub params_file: Option<PathBuf>,
+
+    /// Run only matching stages (can be repeated, supports * and ? wildcards)
     #[arg(long, value_name = "STAGE")]
-    pub stage: Option<String>,
+    pub stage: Vec<String>,
 
-    /// Run only a specific job
+    /// Run only matching jobs (can be repeated, supports wildcards, use Stage.Job to
+    /// qualify)
     #[arg(long, value_name = "JOB")]
-    pub job: Option<String>,
+    pub job: Vec<String>,
+
+    /// Leave out steps whose name or display name matches (can be repeated, supports
+    /// wildcards)
+    #[arg(long = "skip-step", value_name = "STEP")]
+    pub skip_steps: Vec<String>,
+
+    /// Also run excluded stages and jobs that the selected ones depend on
+    #[arg(long)]
+    pub with_deps: bool,
 
     /// Working directory for execution
     #[arg(long, short = 'w', value_name = "DIR")]
@@ -36,13 +81,282 @@ pub struct RunArgs {
     /// Enable task runner with cache directory
     #[arg(long, value_name = "DIR")]
     pub task_cache: Option<PathBuf>,
+
+    /// Log every variable substituted into step commands, secrets masked, and
+    /// warn about values with shell metacharacters
+    #[arg(long)]
+    pub audit_vars: bool,
+
+    /// Log the environment each step's command gets (secrets masked)
+    #[arg(long)]
+    pub audit_env: bool,
+
+    /// Run against a branch, tag, or commit in a temporary git worktree
+    #[arg(long = "ref", value_name = "REF")]
+    pub git_ref: Option<String>,
+
+    /// Define a shell for `shell:` on script steps (can be repeated, format: name=command,
+    /// where {0} in the command is replaced with the script file)
+    #[arg(long = "shell", value_name = "NAME=COMMAND")]
+    pub shells: Vec<String>,
+
+    /// Clone source for a repository resource used by `checkout:` steps (can be repeated,
+    /// format: alias=url or alias=path). Local directories also resolve `template@alias`.
+    #[arg(long = "repo", value_name = "ALIAS=SOURCE")]
+    pub repositories: Vec<String>,
+
+    /// Run up to N independent stages, and N independent jobs per stage, at once
+    /// (0 = unlimited), or `auto` to run as many jobs as the host's CPUs and free
+    /// memory allow, weighed by each job's `resources:`
+    #[arg(long, default_value = "1", value_name = "N|auto")]
+    pub max_parallel: Parallelism,
+
+    /// Record job durations to FILE for 'roxid analyze' (merged into an existing file)
+    #[arg(long, value_name = "FILE")]
+    pub timings: Option<PathBuf>,
+
+    /// Write each step's raw output to DIR/<stage>/<job>/<NN>-<step>.log
+    #[arg(long, value_name = "DIR")]
+    pub log_dir: Option<PathBuf>,
+
+    /// Write the stage, job and step results to FILE for 'roxid timeline compare'
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Write a markdown summary (results per stage and job, failed step
+    /// excerpts) to FILE, for posting as a pull request comment
+    #[arg(long, value_name = "FILE")]
+    pub summary_md: Option<PathBuf>,
+
+    /// Show a desktop notification when the run fails or takes longer than
+    /// .roxid.toml [notifications] min-duration-secs (default 30)
+    #[arg(long, conflicts_with = "no_notify")]
+    pub notify: bool,
+
+    /// Don't show a desktop notification, even if [notifications] turns them on
+    #[arg(long)]
+    pub no_notify: bool,
+
+    /// Show each command, its environment changes and working directory, and
+    /// ask before running it
+    #[arg(long)]
+    pub confirm: bool,
+
+    /// Run steps without network access and with writes limited to the workspace and
+    /// the temp directory (user namespaces on Linux, otherwise a docker container)
+    #[arg(long, env = "ROXID_SANDBOX")]
+    pub sandbox: bool,
+
+    /// Run the script steps of jobs with a `container:` inside that container
+    /// (needs docker); otherwise they run on this machine. Not allowed with
+    /// --sandbox for pipelines that have such jobs
+    #[arg(long, env = "ROXID_CONTAINERS")]
+    pub containers: bool,
+
+    /// When to pull container images: always, if-not-present or never
+    /// (default: .roxid.toml [containers] pull-policy, else if-not-present)
+    #[arg(long, value_name = "POLICY")]
+    pub pull: Option<ImagePullPolicy>,
+
+    /// Keep network access inside the sandbox
+    #[arg(long, requires = "sandbox")]
+    pub sandbox_allow_network: bool,
+
+    /// Also let sandboxed steps write to PATH (can be repeated)
+    #[arg(long, value_name = "PATH", requires = "sandbox")]
+    pub sandbox_writable: Vec<PathBuf>,
+
+    /// Image for the container fallback of the sandbox
+    #[arg(
+        long,
+        value_name = "IMAGE",
+        default_value = DEFAULT_SANDBOX_IMAGE,
+        requires = "sandbox"
+    )]
+    pub sandbox_image: String,
+
+    /// Fail steps that use tasks, step types or logging commands roxid doesn't implement
+    #[arg(long)]
+    pub fail_on_unsupported: bool,
+
+    /// Report-only mode: exit 0 when the pipeline fails (invalid pipelines and
+    /// infrastructure errors still exit 2 and 3)
+    #[arg(long)]
+    pub exit_zero_on_failure: bool,
+
+    /// Exit 1 when the pipeline succeeds with warnings (steps that succeeded
+    /// with issues, degraded features)
+    #[arg(long)]
+    pub fail_on_warnings: bool,
+
+    /// CI-parity mode: fail on roxid-only expression functions such as env()
+    #[arg(long, env = "ROXID_STRICT")]
+    pub strict: bool,
+
+    /// Print each script step's command as its output instead of running it
+    /// (per step with `dryRun: true`)
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Take turns with other local runs in GROUP ($(var) macros are expanded);
+    /// pipelines with lockBehavior use their file as the group
+    #[arg(long, value_name = "GROUP")]
+    pub concurrency_group: Option<String>,
+
+    /// Cancel the group's in-progress run instead of waiting for it
+    #[arg(long, requires = "concurrency_group")]
+    pub cancel_in_progress: bool,
+
+    /// Apply the named execution profile from .roxid.toml (e.g. fast)
+    #[arg(long, value_name = "NAME", env = "ROXID_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Run only matrix instances whose variable NAME is VALUE (can be repeated;
+    /// repeating a name allows any of its values)
+    #[arg(long, value_name = "NAME=VALUE")]
+    pub matrix: Vec<String>,
+
+    /// Run only the first matching instance of each matrix
+    #[arg(long)]
+    pub first_instance: bool,
+
+    /// Start even if tools the pipeline needs (shells, git, docker, node) are missing
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    /// Fail matching steps instead of running them (can be repeated, format:
+    /// step=NAME,exit=CODE; stage= and job= narrow it, without step= a job's first
+    /// step fails; cancel=true cancels the step instead)
+    #[arg(long, value_name = "SPEC")]
+    pub inject_failure: Vec<String>,
+
+    /// Delay matching stages, jobs or steps (can be repeated, format: job=NAME,ms=MILLIS)
+    #[arg(long, value_name = "SPEC")]
+    pub inject_delay: Vec<String>,
+
+    /// Point a hostname at a local stand-in (can be repeated, format:
+    /// HOST=IP[:PORT]); containers resolve it, steps get ROXID_HOST_<HOST>
+    #[arg(long, value_name = "HOST=ADDRESS")]
+    pub add_host: Vec<String>,
+
+    /// Also print the errors and warnings steps report (##vso[task.logissue],
+    /// ##[error], ##[warning]) as annotations; `github` shows them inline on
+    /// pull requests when roxid runs in GitHub Actions
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub annotations: Option<AnnotationFormat>,
+}
+
+impl RunArgs {
+    /// Arguments for running `pipeline` with every option at its command-line default
+    pub fn new(pipeline: PathBuf) -> Self {
+        Self {
+            pipeline,
+            variables: Vec::new(),
+            vars_file: None,
+            parameters: Vec::new(),
+            params_file: None,
+            stage: Vec::new(),
+            job: Vec::new(),
+            skip_steps: Vec::new(),
+            with_deps: false,
+            working_dir: None,
+            task_cache: None,
+            audit_vars: false,
+            audit_env: false,
+            git_ref: None,
+            shells: Vec::new(),
+            repositories: Vec::new(),
+            max_parallel: Parallelism::default(),
+            timings: None,
+            log_dir: None,
+            record: None,
+            summary_md: None,
+            notify: false,
+            no_notify: false,
+            confirm: false,
+            sandbox: false,
+            containers: false,
+            pull: None,
+            sandbox_allow_network: false,
+            sandbox_writable: Vec::new(),
+            sandbox_image: DEFAULT_SANDBOX_IMAGE.to_string(),
+            fail_on_unsupported: false,
+            exit_zero_on_failure: false,
+            fail_on_warnings: false,
+            strict: false,
+            dry_run: false,
+            concurrency_group: None,
+            cancel_in_progress: false,
+            profile: None,
+            matrix: Vec::new(),
+            first_instance: false,
+            skip_preflight: false,
+            inject_failure: Vec::new(),
+            inject_delay: Vec::new(),
+            add_host: Vec::new(),
+            annotations: None,
+        }
+    }
+}
+
+/// How `--annotations` prints the issues steps report
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnotationFormat {
+    /// `::error file=...,line=...::message` workflow commands
+    Github,
 }
 
+/// Run the pipeline, exiting with 1 if it failed, 2 if it or the command
+/// line is invalid and 3 on infrastructure errors
 pub async fn execute(args: RunArgs) -> Result<()> {
+    let policy = ExitPolicy {
+        zero_on_failure: args.exit_zero_on_failure,
+        fail_on_warnings: args.fail_on_warnings,
+    };
+    let outcome = match run(args).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            if e.downcast_ref::<InfrastructureError>().is_some()
+                || e.downcast_ref::<std::io::Error>().is_some()
+            {
+                RunOutcome::Infrastructure
+            } else {
+                RunOutcome::Invalid
+            }
+        }
+    };
+    let failed_on_warnings = match outcome {
+        RunOutcome::Succeeded { warnings } if warnings > 0 && policy.fail_on_warnings => {
+            Some(warnings)
+        }
+        _ => None,
+    };
+    if policy.zero_on_failure && (outcome == RunOutcome::Failed || failed_on_warnings.is_some()) {
+        output::dim("  Exiting 0 despite the failure (--exit-zero-on-failure)");
+    } else if let Some(warnings) = failed_on_warnings {
+        output::failure(&format!(
+            "Failing on {} warning(s) (--fail-on-warnings)",
+            warnings
+        ));
+    }
+    match policy.exit_code(outcome) {
+        0 => Ok(()),
+        code => std::process::exit(code),
+    }
+}
+
+async fn run(args: RunArgs) -> Result<RunOutcome> {
     let pipeline_path = &args.pipeline;
 
-    if !pipeline_path.exists() {
-        color_eyre::eyre::bail!("Pipeline file not found: {}", pipeline_path.display());
+    if args.confirm && !std::io::stdin().is_terminal() {
+        color_eyre::eyre::bail!("--confirm needs an interactive terminal to prompt on");
+    }
+
+    // With --ref the pipeline only needs to exist at that ref
+    let (pipeline_file, _) = split_document_selector(pipeline_path);
+    if args.git_ref.is_none() && !pipeline_file.exists() {
+        color_eyre::eyre::bail!("Pipeline file not found: {}", pipeline_file.display());
     }
 
     // Parse variables from --var flags
@@ -54,22 +368,286 @@ pub async fn execute(args: RunArgs) -> Result<()> {
             color_eyre::eyre::bail!("Invalid variable format '{}'. Expected name=value", var_str);
         }
     }
+    if let Some(path) = &args.vars_file {
+        let file = LocalVariables::load(path).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+        output::status(
+            "Variables",
+            &format!("{} from {}", file.variables.len(), path.display()),
+        );
+        file.apply_to(&mut variables);
+    }
+
+    // Parse parameters from --params-file, then --param flags over them
+    let mut parameters = match &args.params_file {
+        Some(path) => RunParameters::load(path).map_err(|e| color_eyre::eyre::eyre!("{}", e))?,
+        None => RunParameters::default(),
+    };
+    for param_str in &args.parameters {
+        parameters
+            .set(param_str)
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+    }
+
+    // Parse custom shells from --shell flags
+    let mut shells = HashMap::new();
+    for shell_str in &args.shells {
+        match shell_str.split_once('=') {
+            Some((name, command)) if !command.trim().is_empty() => {
+                shells.insert(name.to_string(), command.to_string());
+            }
+            _ => color_eyre::eyre::bail!(
+                "Invalid shell format '{}'. Expected name=command (e.g. perl='perl {{0}}')",
+                shell_str
+            ),
+        }
+    }
+
+    // Parse repository sources from --repo flags
+    let mut repositories = HashMap::new();
+    for repo_str in &args.repositories {
+        match repo_str.split_once('=') {
+            Some((alias, source)) if !source.trim().is_empty() => {
+                repositories.insert(alias.to_string(), source.to_string());
+            }
+            _ => color_eyre::eyre::bail!(
+                "Invalid repository format '{}'. Expected alias=url or alias=path",
+                repo_str
+            ),
+        }
+    }
+
+    // Materialize the requested ref in a temporary worktree, if any. The
+    // worktree is removed when `worktree` is dropped.
+    let worktree = match &args.git_ref {
+        Some(git_ref) => {
+            let cwd = std::env::current_dir()?;
+            let repo_root = find_repo_root(&cwd)
+                .ok_or_else(|| color_eyre::eyre::eyre!("--ref requires a git repository"))?;
+            output::status("Checking out", git_ref);
+            let worktree = GitWorktree::create(&repo_root, git_ref)
+                .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            Some((repo_root, worktree))
+        }
+        None => None,
+    };
 
     // Resolve working directory
-    let working_dir = match &args.working_dir {
-        Some(dir) => dir.clone(),
-        None => {
+    let working_dir = match (&args.working_dir, &worktree) {
+        (Some(dir), _) => dir.clone(),
+        (None, Some((_, worktree))) => worktree.path().to_path_buf(),
+        (None, None) => {
             let cwd = std::env::current_dir()?;
             find_repo_root(&cwd).unwrap_or(cwd)
         }
     };
 
-    // Parse the pipeline
+    // Developer overrides from .roxid/vars.yml; they beat profile variables, --var beats them
+    let repo_dir = match &worktree {
+        Some((repo_root, _)) => repo_root.clone(),
+        None => working_dir.clone(),
+    };
+    if let Some(local) =
+        LocalVariables::discover(&repo_dir).map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+    {
+        output::status(
+            "Variables",
+            &format!(
+                "{} override(s) from {}",
+                local.variables.len(),
+                LOCAL_VARS_FILE
+            ),
+        );
+        local.apply_to(&mut variables);
+    }
+
+    // Project configuration from the .roxid.toml next to the pipelines
+    let config =
+        RoxidConfig::discover(&working_dir).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+    let service_connections = ServiceConnections::resolve(&config.service_connections)
+        .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+
+    // The execution profile
+    let profile = match &args.profile {
+        Some(name) => {
+            let profile = config
+                .profile(name)
+                .map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+                .clone();
+            output::status("Profile", name);
+            for (name, value) in &profile.variables {
+                variables
+                    .entry(name.clone())
+                    .or_insert_with(|| value.clone());
+            }
+            Some(profile)
+        }
+        None => None,
+    };
+
+    // Parse the pipeline and resolve its templates (from the worktree when --ref is set)
     output::status("Parsing", &format!("{}", pipeline_path.display()));
-    let raw_pipeline = AzureParser::parse_file(pipeline_path)
-        .map_err(|e| color_eyre::eyre::eyre!("Parse error: {}", e.message))?;
+    let (template_root, pipeline_file) = match &worktree {
+        Some((repo_root, worktree)) => {
+            let absolute = if pipeline_path.is_absolute() {
+                pipeline_path.clone()
+            } else {
+                std::env::current_dir()?.canonicalize()?.join(pipeline_path)
+            };
+            let relative = absolute
+                .strip_prefix(repo_root)
+                .map(|p| p.to_path_buf())
+                .map_err(|_| {
+                    color_eyre::eyre::eyre!(
+                        "Pipeline {} is not inside repository {}",
+                        pipeline_path.display(),
+                        repo_root.display()
+                    )
+                })?;
+            let worktree_root = worktree.path().to_path_buf();
+            let pipeline_file = worktree_root.join(relative);
+            (worktree_root, pipeline_file)
+        }
+        None => {
+            let cwd = std::env::current_dir()?;
+            (find_repo_root(&cwd).unwrap_or(cwd), pipeline_path.clone())
+        }
+    };
+    // `template: file@alias` reads from --repo aliases that name a local directory
+    let mut engine = TemplateEngine::new(template_root).with_strict(args.strict);
+    for (alias, source) in &repositories {
+        let path = PathBuf::from(source);
+        if path.is_dir() {
+            engine = engine.with_resource_repo(alias.clone(), path);
+        }
+    }
+    let parsed = AzureParser::parse_file(pipeline_file)
+        .and_then(|pipeline| engine.resolve_pipeline(pipeline));
+    let raw_pipeline = match parsed {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            output::parse_error(&e, pipeline_path);
+            return Ok(RunOutcome::Invalid);
+        }
+    };
     let pipeline = normalize_pipeline(raw_pipeline);
 
+    // Check supplied parameters against the pipeline's declarations
+    let parameters = if parameters.is_empty() {
+        HashMap::new()
+    } else {
+        parameters
+            .resolve(&pipeline.parameters)
+            .map_err(|e| color_eyre::eyre::eyre!("{}: {}", pipeline_path.display(), e))?
+    };
+
+    // Prune to the stages, jobs and steps selected on the command line
+    let filter = PipelineFilter {
+        stages: args.stage.clone(),
+        jobs: args.job.clone(),
+        skip_steps: args.skip_steps.clone(),
+        include_dependencies: args.with_deps,
+        ..Default::default()
+    };
+    let pipeline = filter
+        .apply(pipeline)
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid filter: {}", e.message))?;
+    let pipeline = match &profile {
+        Some(profile) => profile
+            .apply(pipeline)
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid profile: {}", e.message))?,
+        None => pipeline,
+    };
+
+    // Jobs in pools configured under [pools] in .roxid.toml
+    let (pipeline, pools) = apply_pools(pipeline, &config.pools)
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid pool: {}", e))?;
+    if !pools.is_empty() {
+        let mut names: Vec<&str> = pools.iter().map(|p| p.pool.as_str()).collect();
+        names.sort();
+        names.dedup();
+        output::status(
+            "Pools",
+            &format!("{} job(s) in {}", pools.len(), names.join(", ")),
+        );
+    }
+    let containers = args.containers
+        || pools
+            .iter()
+            .any(|assignment| assignment.backend == PoolBackend::Container);
+
+    // Matrix instances selected with --matrix and --first-instance
+    let matrix = MatrixSelection {
+        filters: args
+            .matrix
+            .iter()
+            .map(|filter| MatrixSelection::parse_filter(filter))
+            .collect::<Result<_, _>>()
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e))?,
+        first_instance: args.first_instance,
+    };
+    matrix
+        .validate(&pipeline)
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid --matrix: {}", e))?;
+
+    // Failures and delays forced with --inject-failure and --inject-delay
+    let mut faults = Vec::new();
+    for spec in &args.inject_failure {
+        let mut fault = Fault::parse(spec)
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid --inject-failure: {}", e))?;
+        if fault.ms.is_some() {
+            color_eyre::eyre::bail!(
+                "Invalid --inject-failure '{}': use --inject-delay for ms=",
+                spec
+            );
+        }
+        if !fault.cancel {
+            fault.exit.get_or_insert(1);
+        }
+        faults.push(fault);
+    }
+    for spec in &args.inject_delay {
+        let fault = Fault::parse(spec)
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid --inject-delay: {}", e))?;
+        if fault.ms.is_none() || fault.exit.is_some() || fault.cancel {
+            color_eyre::eyre::bail!(
+                "Invalid --inject-delay '{}': expected ms= and no exit=",
+                spec
+            );
+        }
+        faults.push(fault);
+    }
+    if !faults.is_empty() {
+        output::warning(&format!("{} fault(s) will be injected", faults.len()));
+    }
+
+    let hosts = args
+        .add_host
+        .iter()
+        .map(|spec| HostOverride::parse(spec))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid --add-host: {}", e))?;
+
+    // Images that change whenever someone pushes, so runs can't be reproduced
+    if containers {
+        for image in floating_images(&pipeline, &config.containers.pins) {
+            output::warning(&format!(
+                "Image '{}' uses a floating tag; pin it to a digest under [containers] pins in .roxid.toml",
+                image
+            ));
+        }
+    }
+
+    // Report missing tools now rather than partway through the run
+    if !args.skip_preflight && !args.dry_run {
+        let preflight = Preflight::scan(&pipeline, &shells);
+        let missing = preflight.missing();
+        if !missing.is_empty() {
+            doctor::report_missing(&missing);
+            output::dim("  Install them, or pass --skip-preflight to run anyway");
+            return Ok(RunOutcome::Infrastructure);
+        }
+    }
+
     let pipeline_name = pipeline.name.clone().unwrap_or_else(|| {
         pipeline_path
             .file_name()
@@ -92,40 +670,261 @@ pub async fn execute(args: RunArgs) -> Result<()> {
         pipeline_name, stages_count, jobs_count, steps_count
     ));
 
+    // Variable groups from their local providers in .roxid.toml
+    let mut variable_groups = HashMap::new();
+    let mut secrets = Vec::new();
+    for group in referenced_groups(&pipeline) {
+        let Some(provider) = config.variable_groups.get(&group) else {
+            output::warning(&format!(
+                "variable group '{}' isn't defined in .roxid.toml; its variables are unset",
+                group
+            ));
+            continue;
+        };
+        let values = provider
+            .load(&working_dir)
+            .map_err(|e| InfrastructureError(format!("variable group '{}': {}", group, e)))?;
+        output::status(
+            "Variables",
+            &format!("{} from variable group '{}'", values.len(), group),
+        );
+        if provider.secret {
+            secrets.extend(values.values().cloned());
+        }
+        variable_groups.insert(group, values);
+    }
+
     // Build execution context
     let context = ExecutionContext::new(
         pipeline_name.clone(),
         working_dir.to_string_lossy().to_string(),
     )
-    .with_variables(variables);
+    .with_variables(variables)
+    .with_parameters(parameters)
+    .with_variable_groups(variable_groups)
+    .with_strict(args.strict);
 
-    // Create progress channel and executor
-    let (tx, mut rx) = progress_channel();
+    // Wait for (or cancel) earlier runs in the same concurrency group
+    let group = match (&args.concurrency_group, &pipeline.lock_behavior) {
+        (Some(group), behavior) => {
+            let mut runtime = RuntimeContext::new(context.clone());
+            runtime
+                .merge_pipeline_variables(&pipeline.variables)
+                .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            let name = runtime
+                .substitute_variables(group)
+                .map_err(|e| color_eyre::eyre::eyre!("Invalid concurrency group: {}", e))?;
+            let policy = match behavior {
+                _ if args.cancel_in_progress => ConcurrencyPolicy::CancelInProgress,
+                Some(behavior) => ConcurrencyPolicy::from(behavior),
+                None => ConcurrencyPolicy::Queue,
+            };
+            Some(ConcurrencyGroup::new(name, policy))
+        }
+        (None, Some(behavior)) => {
+            // One group per pipeline, so each document of a file gets its own
+            let (file, selector) = split_document_selector(pipeline_path);
+            let mut path = file
+                .canonicalize()
+                .unwrap_or(file)
+                .to_string_lossy()
+                .into_owned();
+            if let Some(selector) = selector {
+                path = format!("{}#{}", path, selector);
+            }
+            Some(ConcurrencyGroup::new(
+                path,
+                ConcurrencyPolicy::from(behavior),
+            ))
+        }
+        (None, None) => None,
+    };
+    let concurrency_lock = match &group {
+        Some(group) => {
+            if !group.members().is_empty() {
+                let action = match group.policy() {
+                    ConcurrencyPolicy::Queue => "Waiting",
+                    ConcurrencyPolicy::CancelInProgress => "Canceling",
+                };
+                output::status(
+                    action,
+                    &format!("earlier runs in concurrency group '{}'", group.name()),
+                );
+            }
+            match group.acquire().await? {
+                Some(lock) => Some(lock),
+                None => {
+                    output::warning(&format!(
+                        "Canceled: a newer run joined concurrency group '{}'",
+                        group.name()
+                    ));
+                    return Ok(RunOutcome::Failed);
+                }
+            }
+        }
+        None => None,
+    };
+
+    // Clean up after earlier runs that crashed or were killed, then journal
+    // this one until it finishes
+    let journal = RunJournal::default();
+    for orphan in journal.orphans() {
+        let cleanup = journal.recover(&orphan, &RunHistory::default()).await;
+        let removed = if cleanup.is_empty() {
+            String::new()
+        } else {
+            format!("; removed {}", cleanup)
+        };
+        output::warning(&format!(
+            "Run {} of '{}' was interrupted{}",
+            orphan.run_id, orphan.pipeline, removed
+        ));
+    }
+    let active_run = ActiveRun {
+        run_id: context.run_id.clone(),
+        pipeline: pipeline_name.clone(),
+        pid: std::process::id(),
+        repo: worktree.as_ref().map(|(repo_root, _)| repo_root.clone()),
+    };
+    let _journal_entry = match journal.begin(&active_run) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            output::warning(&format!("Run not journaled for crash cleanup: {}", e));
+            None
+        }
+    };
+
+    // Route events through the bus; the console output below is one of its sinks
+    let (console_tx, mut rx) = progress_channel();
+    let mut bus = EventBus::new().subscribe(ChannelSink::new(console_tx));
+    if let Some(log_dir) = &args.log_dir {
+        let sink = LogDirSink::new(log_dir).map_err(|e| {
+            InfrastructureError(format!(
+                "Cannot create log directory {}: {}",
+                log_dir.display(),
+                e
+            ))
+        })?;
+        bus = bus.subscribe(sink);
+    }
+    let (tx, bus_handle) = bus.start();
+
+    // With `auto`, stages and jobs start freely and share the host's CPUs and memory
+    let (max_parallel_stages, resources) = match args.max_parallel {
+        Parallelism::Fixed(n) => (n, None),
+        Parallelism::Auto => {
+            let host = Resources::detect();
+            let jobs = host.default_jobs();
+            output::dim(&format!(
+                "Parallelism: up to {} job{} at once ({})",
+                jobs,
+                if jobs == 1 { "" } else { "s" },
+                host
+            ));
+            (0, Some(ResourcePool::new(host)))
+        }
+    };
 
-    let mut executor = PipelineExecutor::from_pipeline(&pipeline)
-        .map_err(|e| color_eyre::eyre::eyre!("Failed to build execution graph: {}", e.message))?;
-    executor = executor.with_progress(tx);
+    let mut executor = PipelineExecutor::from_pipeline(&pipeline).map_err(|e| {
+        color_eyre::eyre::eyre!(
+            "Failed to build execution graph for {}: {}",
+            pipeline_path.display(),
+            e.message
+        )
+    })?;
+    executor = executor
+        .with_config(ExecutorConfig {
+            max_parallel_stages,
+            max_parallel_jobs: max_parallel_stages,
+            resources,
+            audit_substitutions: args.audit_vars,
+            audit_env: args.audit_env,
+            shells,
+            repositories,
+            fail_on_unsupported: args.fail_on_unsupported,
+            sandbox: args.sandbox.then(|| SandboxProfile {
+                network: args.sandbox_allow_network,
+                writable: args.sandbox_writable.clone(),
+                image: args.sandbox_image.clone(),
+            }),
+            dry_run: args.dry_run,
+            matrix,
+            service_connections,
+            key_vaults: config.key_vaults.clone(),
+            secrets,
+            faults,
+            hosts,
+            enable_containers: containers,
+            pull_policy: args
+                .pull
+                .or(config.containers.pull_policy)
+                .unwrap_or_default(),
+            image_pins: config.containers.pins.clone(),
+            container_mounts: config.containers.mounts.clone(),
+            disk_quota: config.workspace,
+            // A misconfigured cache backend only matters to pipelines that cache
+            cache_storage: if required_tasks(&pipeline)
+                .iter()
+                .any(|task| is_cache_task(task))
+            {
+                Some(config.cache.storage().map_err(InfrastructureError)?)
+            } else {
+                None
+            },
+            ..Default::default()
+        })
+        .with_progress(tx);
 
-    if let Some(cache_dir) = args.task_cache {
+    let task_cache = args
+        .task_cache
+        .or_else(|| profile.as_ref().and_then(|p| p.task_cache_dir()));
+    if let Some(cache_dir) = task_cache {
         executor = executor.with_task_runner(cache_dir);
     }
+    if args.confirm {
+        executor = executor.with_confirmer(Arc::new(TerminalConfirmer::default()));
+    }
 
     // Spawn execution in background
     let exec_handle = tokio::spawn(async move { executor.execute(context).await });
 
+    // Stop the run when a newer one in its cancel-in-progress group starts; the
+    // group is left once the cancelled steps have been killed
+    let concurrency_watch = concurrency_lock.map(|lock| {
+        let abort = exec_handle.abort_handle();
+        tokio::spawn(async move {
+            lock.superseded().await;
+            abort.abort();
+            lock
+        })
+    });
+
     // Process events in the foreground
     let mut overall_success = true;
+    // Whether step output is currently inside a ##[group] section
+    let mut in_group = false;
+    let mut summaries = Vec::new();
+    // Steps of jobs running side by side are told apart by their `Stage/Job/N` ID
+    let parallel = args.max_parallel != Parallelism::Fixed(1);
+    // Annotation paths are made relative to the sources, like GitHub expects
+    let annotation_root = working_dir.clone();
+    // Bars for steps that report ##vso[task.setprogress], kept below the output
+    let mut progress = StepProgress::new(std::io::stderr().is_terminal());
     while let Some(event) = rx.recv().await {
+        progress.clear();
         match &event {
             ExecutionEvent::PipelineStarted {
                 pipeline_name,
+                run_id,
                 total_stages,
+                ..
             } => {
                 println!();
                 output::header(&format!(
                     "Pipeline '{}' ({} stages)",
                     pipeline_name, total_stages
                 ));
+                output::dim(&format!("  Run ID: {}", run_id));
             }
 
             ExecutionEvent::PipelineCompleted {
@@ -150,6 +949,7 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                 stage_name,
                 display_name,
                 total_jobs,
+                ..
             } => {
                 let label = display_name.as_deref().unwrap_or(stage_name);
                 output::stage_header(label, *total_jobs);
@@ -159,10 +959,12 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                 stage_name,
                 status,
                 duration,
+                ..
             } => {
                 let symbol = match status {
                     StageStatus::Succeeded => "OK",
                     StageStatus::Failed => "FAIL",
+                    StageStatus::Canceled => "CANCELED",
                     _ => "DONE",
                 };
                 let color_fn = if *status == StageStatus::Succeeded {
@@ -203,6 +1005,7 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                 let symbol = match status {
                     JobStatus::Succeeded => "OK",
                     JobStatus::Failed => "FAIL",
+                    JobStatus::Canceled => "CANCELED",
                     _ => "DONE",
                 };
                 if *status == JobStatus::Succeeded {
@@ -238,17 +1041,39 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                     .as_deref()
                     .or(step_name.as_deref())
                     .unwrap_or("step");
-                println!("      [Step {}] {}", step_index + 1, label);
+                let id = event.correlation_id().unwrap_or_default();
+                if parallel {
+                    println!("      [{}] {}", id, label);
+                    progress.start(&id, &format!("[{}] {}", id, label));
+                } else {
+                    println!("      [Step {}] {}", step_index + 1, label);
+                    progress.start(&id, label);
+                }
+                in_group = false;
             }
 
             ExecutionEvent::StepOutput {
                 output, is_error, ..
             } => {
                 for line in output.lines() {
-                    if *is_error {
-                        output::step_error(line);
-                    } else {
-                        output::step_output(line);
+                    if args.annotations == Some(AnnotationFormat::Github) {
+                        if let Some(issue) = StepIssue::parse(line) {
+                            github_annotation(issue, &event, &annotation_root);
+                        }
+                    }
+                    match LogLine::parse(line) {
+                        LogLine::GroupStart(title) => {
+                            output::step_group(title);
+                            in_group = true;
+                        }
+                        LogLine::GroupEnd => in_group = false,
+                        LogLine::Progress { percent, operation } => progress.update(
+                            &event.correlation_id().unwrap_or_default(),
+                            percent,
+                            operation,
+                        ),
+                        LogLine::Text(text) if *is_error => output::step_error(text, in_group),
+                        LogLine::Text(text) => output::step_output(text, in_group),
                     }
                 }
             }
@@ -259,9 +1084,11 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                 exit_code,
                 ..
             } => {
+                progress.finish(&event.correlation_id().unwrap_or_default());
                 let symbol = match status {
                     StepStatus::Succeeded => "OK",
                     StepStatus::Failed => "FAIL",
+                    StepStatus::Canceled => "CANCELED",
                     StepStatus::Skipped => "SKIP",
                     _ => "DONE",
                 };
@@ -269,6 +1096,10 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                     Some(code) if *code != 0 => format!(" (exit code: {})", code),
                     _ => String::new(),
                 };
+                let symbol = match event.correlation_id() {
+                    Some(id) if parallel => format!("[{}] {}", id, symbol),
+                    _ => symbol.to_string(),
+                };
                 if *status == StepStatus::Succeeded {
                     output::dim_success(&format!(
                         "        {} ({:.2}s){}",
@@ -293,10 +1124,29 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                 }
             }
 
+            ExecutionEvent::StepSummary {
+                step_name,
+                step_index,
+                markdown,
+                ..
+            } => {
+                let id = event.correlation_id().unwrap_or_default();
+                let label = match step_name {
+                    Some(name) => format!("{} ({})", name, id),
+                    None => format!("Step {} ({})", step_index + 1, id),
+                };
+                summaries.push((label, markdown.clone()));
+            }
+
             ExecutionEvent::StepSkipped {
                 step_name, reason, ..
             } => {
-                let label = step_name.as_deref().unwrap_or("step");
+                progress.finish(&event.correlation_id().unwrap_or_default());
+                let label = match (step_name, event.correlation_id()) {
+                    (Some(name), Some(id)) if parallel => format!("{} ({})", name, id),
+                    (Some(name), _) => name.clone(),
+                    (None, id) => id.unwrap_or_else(|| "step".to_string()),
+                };
                 output::warning(&format!("        {} skipped: {}", label, reason));
             }
 
@@ -319,18 +1169,182 @@ pub async fn execute(args: RunArgs) -> Result<()> {
                 }
             }
 
+            ExecutionEvent::DiskQuotaExceeded { message, .. } => {
+                output::dim_failure(&format!("        Disk quota exceeded: {}", message));
+            }
+
             ExecutionEvent::Error { message, .. } => {
                 output::error(&format!("ERROR: {}", message));
             }
         }
+        progress.draw();
     }
+    progress.clear();
 
     // Wait for executor to finish
-    let _result = exec_handle.await?;
+    let result = match exec_handle.await {
+        Ok(result) => result,
+        Err(e) if e.is_cancelled() => {
+            bus_handle.finished().await;
+            println!();
+            output::failure(&format!(
+                "Canceled: a newer run started in concurrency group '{}'",
+                group
+                    .as_ref()
+                    .map(ConcurrencyGroup::name)
+                    .unwrap_or_default()
+            ));
+            drop(concurrency_watch);
+            return Ok(RunOutcome::Failed);
+        }
+        Err(e) => return Err(InfrastructureError(format!("The run crashed: {}", e)).into()),
+    };
+    bus_handle.finished().await;
+    // Leave the concurrency group
+    if let Some(watch) = concurrency_watch {
+        watch.abort();
+    }
+    if let Some(path) = &args.timings {
+        let mut timings = if path.exists() {
+            JobTimings::load(path).map_err(InfrastructureError)?
+        } else {
+            JobTimings::default()
+        };
+        timings.merge(JobTimings::from_result(&result));
+        timings.save(path).map_err(InfrastructureError)?;
+        output::dim(&format!("  Job timings written to {}", path.display()));
+    }
+    for (image, digest) in &result.images {
+        output::dim(&format!("  Image {} ran at {}", image, digest));
+    }
+    let record = RunRecord::from_result(&result);
+    if let Some(path) = &args.record {
+        record.save(path).map_err(InfrastructureError)?;
+        output::dim(&format!("  Run record written to {}", path.display()));
+    }
+    if let Some(path) = &args.summary_md {
+        std::fs::write(path, run_markdown(&pipeline_name, &result)).map_err(|e| {
+            InfrastructureError(format!("failed to write {}: {}", path.display(), e))
+        })?;
+        output::dim(&format!("  Summary written to {}", path.display()));
+    }
+    if let Err(e) = RunHistory::default().save(&record) {
+        output::warning(&format!("Run not kept for 'roxid query': {}", e));
+    }
+    if let Some(log_dir) = &args.log_dir {
+        output::dim(&format!("  Step logs written to {}", log_dir.display()));
+    }
+    let counters = rx.counters();
+    if counters.merged_lines > 0 || counters.dropped_lines > 0 {
+        output::dim(&format!(
+            "  Console fell behind: {} output line(s) merged, {} dropped",
+            counters.merged_lines, counters.dropped_lines
+        ));
+    }
+
+    // Markdown summaries published by steps
+    for (label, markdown) in &summaries {
+        println!();
+        output::header(&format!("Summary: {}", label));
+        output::markdown(markdown);
+    }
+
+    // Constructs that were skipped or stubbed, so a green run isn't mistaken for parity
+    let parity = ParityReport::from_result(&result);
+    if !parity.is_empty() {
+        println!();
+        output::warning(&format!(
+            "{} feature{} degraded (not supported by roxid):",
+            parity.len(),
+            if parity.len() == 1 { "" } else { "s" }
+        ));
+        for entry in &parity.entries {
+            output::dim(&format!(
+                "    {} — {}",
+                entry.feature,
+                entry.locations.join(", ")
+            ));
+        }
+        if !args.fail_on_unsupported {
+            output::dim("    Use --fail-on-unsupported to fail these steps instead");
+        }
+    }
+
+    let mut notifications = config.notifications;
+    notifications.enabled = (notifications.enabled || args.notify) && !args.no_notify;
+    notifications.notify_run(&pipeline_name, overall_success, result.duration);
 
     if !overall_success {
-        std::process::exit(1);
+        return Ok(RunOutcome::Failed);
+    }
+    Ok(RunOutcome::from_result(&result))
+}
+
+/// Print an issue a step reported as a GitHub Actions annotation, titled with
+/// the step's `Stage/Job/N` ID
+fn github_annotation(mut issue: StepIssue, event: &ExecutionEvent, root: &Path) {
+    if let Some(file) = &issue.file {
+        if let Ok(relative) = Path::new(file).strip_prefix(root) {
+            issue.file = Some(relative.to_string_lossy().into_owned());
+        }
     }
+    let title = event.correlation_id();
+    println!("{}", issue.to_github_annotation(title.as_deref()));
+}
 
-    Ok(())
+/// Prompts on the terminal before each command: y(es), n(o) or a(lways)
+#[derive(Default)]
+struct TerminalConfirmer {
+    /// Set by "always"; later steps run without asking
+    always: AtomicBool,
+    /// Keeps prompts from parallel jobs from interleaving
+    prompt: Mutex<()>,
+}
+
+impl StepConfirmer for TerminalConfirmer {
+    fn confirm(&self, preview: &StepPreview) -> bool {
+        let _prompt = self.prompt.lock().unwrap_or_else(|e| e.into_inner());
+        if self.always.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        eprintln!();
+        output::header(&format!(
+            "{} ({}/{})",
+            preview.step, preview.stage_name, preview.job_name
+        ));
+        let (script, program) = match preview.command.split_last() {
+            Some((script, program)) => (script.as_str(), program),
+            None => ("", &[][..]),
+        };
+        output::dim(&format!("  $ {}", program.join(" ")));
+        for line in script.lines() {
+            eprintln!("  | {}", line);
+        }
+        output::dim(&format!("  cwd: {}", preview.working_dir));
+        for (name, value) in &preview.env_changes {
+            output::dim(&format!("  env: {}={}", name, value));
+        }
+
+        loop {
+            eprint!("  Run this step? [y]es / [n]o / [a]lways: ");
+            let _ = std::io::stderr().flush();
+            let mut answer = String::new();
+            // The executor asks on a blocking thread, so waiting here stalls
+            // no async work. End of input declines, so a closed terminal
+            // never runs anything
+            if std::io::stdin().lock().read_line(&mut answer).unwrap_or(0) == 0 {
+                return false;
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return true,
+                "n" | "no" => return false,
+                "a" | "always" => {
+                    self.always.store(true, Ordering::Relaxed);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+    }
 }
@@ -0,0 +1,382 @@
+use crate::output::{self, Tone};
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use color_eyre::Result;
+
+use pipeline_service::execution::LocalVariables;
+use pipeline_service::parser::models::{ExecutionContext, StepStatus};
+use pipeline_service::utils::find_repo_root;
+use pipeline_service::{normalize_pipeline, AzureParser, PipelineExecutor};
+
+/// Run several pipelines and summarize the results
+#[derive(Args, Debug)]
+pub struct RunAllArgs {
+    /// Pipeline files or glob patterns (e.g. 'pipelines/**/*.yml')
+    #[arg(required = true)]
+    pub patterns: Vec<String>,
+
+    /// Set a variable for every pipeline (can be repeated, format: name=value)
+    #[arg(long = "var", short = 'v', value_name = "NAME=VALUE")]
+    pub variables: Vec<String>,
+
+    /// Working directory for execution
+    #[arg(long, short = 'w', value_name = "DIR")]
+    pub working_dir: Option<PathBuf>,
+
+    /// Number of pipelines to run at once
+    #[arg(long, short = 'j', default_value = "1", value_name = "N")]
+    pub parallel: usize,
+
+    /// Don't start further pipelines after the first failure
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Output format: terminal, json
+    #[arg(long, short = 'o', default_value = "terminal")]
+    pub output: String,
+}
+
+/// Final state of one pipeline in the batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Succeeded,
+    Failed,
+    /// The file could not be parsed or turned into an execution graph
+    Error,
+    /// Not started because of --fail-fast
+    Skipped,
+}
+
+impl Outcome {
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Succeeded => "succeeded",
+            Outcome::Failed => "failed",
+            Outcome::Error => "error",
+            Outcome::Skipped => "skipped",
+        }
+    }
+}
+
+struct PipelineRun {
+    path: PathBuf,
+    name: String,
+    outcome: Outcome,
+    duration: Duration,
+    /// Parse error or `stage/job/step: error` for failed steps
+    errors: Vec<String>,
+}
+
+pub async fn execute(args: RunAllArgs) -> Result<()> {
+    let json = match args.output.as_str() {
+        "terminal" => false,
+        "json" => true,
+        other => color_eyre::eyre::bail!(
+            "Unknown output format '{}'. Expected terminal or json",
+            other
+        ),
+    };
+
+    let mut variables = HashMap::new();
+    for var_str in &args.variables {
+        if let Some((name, value)) = var_str.split_once('=') {
+            variables.insert(name.to_string(), value.to_string());
+        } else {
+            color_eyre::eyre::bail!("Invalid variable format '{}'. Expected name=value", var_str);
+        }
+    }
+
+    let pipelines = expand_patterns(&args.patterns)?;
+    if pipelines.is_empty() {
+        color_eyre::eyre::bail!("No pipeline files matched {}", args.patterns.join(" "));
+    }
+
+    let working_dir = match &args.working_dir {
+        Some(dir) => dir.clone(),
+        None => {
+            let cwd = std::env::current_dir()?;
+            find_repo_root(&cwd).unwrap_or(cwd)
+        }
+    };
+
+    if let Some(local) =
+        LocalVariables::discover(&working_dir).map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+    {
+        local.apply_to(&mut variables);
+    }
+
+    output::status(
+        "Running",
+        &format!(
+            "{} pipeline(s), {} at a time",
+            pipelines.len(),
+            args.parallel.max(1)
+        ),
+    );
+
+    let start = Instant::now();
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(args.parallel.max(1)));
+    let failed = Arc::new(AtomicBool::new(false));
+    let mut handles = Vec::new();
+
+    for path in pipelines {
+        let semaphore = semaphore.clone();
+        let failed = failed.clone();
+        let variables = variables.clone();
+        let working_dir = working_dir.clone();
+        let fail_fast = args.fail_fast;
+
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok();
+            if fail_fast && failed.load(Ordering::SeqCst) {
+                return skipped(path);
+            }
+
+            let run = run_pipeline(path, working_dir, variables).await;
+            match run.outcome {
+                Outcome::Succeeded => output::success(&format!(
+                    "{} ({:.2}s)",
+                    run.path.display(),
+                    run.duration.as_secs_f64()
+                )),
+                _ => {
+                    failed.store(true, Ordering::SeqCst);
+                    output::failure(&format!(
+                        "{} ({:.2}s)",
+                        run.path.display(),
+                        run.duration.as_secs_f64()
+                    ));
+                    for error in &run.errors {
+                        output::dim_failure(&format!("      {}", error));
+                    }
+                }
+            }
+            run
+        }));
+    }
+
+    let mut runs = Vec::new();
+    for handle in handles {
+        runs.push(handle.await?);
+    }
+    let total = start.elapsed();
+    let success = runs.iter().all(|r| r.outcome == Outcome::Succeeded);
+
+    if json {
+        println!("{}", json_summary(&runs, total, success));
+    } else {
+        print_table(&runs, total);
+    }
+
+    if !success {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Expand glob patterns (plain paths are kept as-is), dropping duplicates
+///
+/// A file holding several pipelines expands to one `file.yml#<index>` path
+/// per document.
+fn expand_patterns(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let matches = glob::glob(pattern)
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid pattern '{}': {}", pattern, e))?;
+        let before = paths.len();
+        for path in matches.flatten() {
+            if !path.is_file() {
+                continue;
+            }
+            let documents = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| AzureParser::parse_documents(&content).ok())
+                .map_or(1, |documents| documents.len());
+            let expanded = if documents > 1 {
+                (0..documents)
+                    .map(|i| PathBuf::from(format!("{}#{}", path.display(), i)))
+                    .collect()
+            } else {
+                vec![path]
+            };
+            for path in expanded {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        if paths.len() == before {
+            output::warning(&format!("No files matched '{}'", pattern));
+        }
+    }
+    Ok(paths)
+}
+
+/// Parse and execute one pipeline without streaming its output
+async fn run_pipeline(
+    path: PathBuf,
+    working_dir: PathBuf,
+    variables: HashMap<String, String>,
+) -> PipelineRun {
+    let start = Instant::now();
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("pipeline")
+        .to_string();
+
+    let error = |path: PathBuf, name: String, message: String| PipelineRun {
+        path,
+        name,
+        outcome: Outcome::Error,
+        duration: start.elapsed(),
+        errors: vec![message],
+    };
+
+    let pipeline = match AzureParser::parse_file(&path) {
+        Ok(raw) => normalize_pipeline(raw),
+        Err(e) => return error(path, file_name, format!("Parse error: {}", e.message)),
+    };
+    let name = pipeline.name.clone().unwrap_or(file_name);
+
+    let executor = match PipelineExecutor::from_pipeline(&pipeline) {
+        Ok(executor) => executor,
+        Err(e) => {
+            return error(
+                path,
+                name,
+                format!("Failed to build execution graph: {}", e.message),
+            )
+        }
+    };
+
+    let context = ExecutionContext::new(name.clone(), working_dir.to_string_lossy().to_string())
+        .with_variables(variables);
+    let result = executor.execute(context).await;
+
+    let mut errors = Vec::new();
+    for stage in &result.stages {
+        for job in &stage.jobs {
+            for step in &job.steps {
+                if step.status == StepStatus::Failed {
+                    let step_name = step
+                        .display_name
+                        .clone()
+                        .or_else(|| step.step_name.clone())
+                        .unwrap_or_else(|| "step".to_string());
+                    let reason = match (&step.error, step.exit_code) {
+                        (Some(error), _) => error.clone(),
+                        (None, Some(code)) => format!("exit code {}", code),
+                        (None, None) => "failed".to_string(),
+                    };
+                    errors.push(format!(
+                        "{}/{}/{}: {}",
+                        stage.stage_name, job.job_name, step_name, reason
+                    ));
+                }
+            }
+        }
+    }
+
+    PipelineRun {
+        path,
+        name,
+        outcome: if result.success {
+            Outcome::Succeeded
+        } else {
+            Outcome::Failed
+        },
+        duration: result.duration,
+        errors,
+    }
+}
+
+fn skipped(path: PathBuf) -> PipelineRun {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("pipeline")
+        .to_string();
+    PipelineRun {
+        path,
+        name,
+        outcome: Outcome::Skipped,
+        duration: Duration::ZERO,
+        errors: Vec::new(),
+    }
+}
+
+fn print_table(runs: &[PipelineRun], total: Duration) {
+    let width = runs
+        .iter()
+        .map(|r| r.path.display().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("Pipeline".len());
+
+    println!();
+    println!(
+        "{}",
+        output::bold(&format!(
+            "  {:<width$}  {:<10}  {:>9}",
+            "Pipeline",
+            "Status",
+            "Duration",
+            width = width
+        ))
+    );
+    for run in runs {
+        let tone = match run.outcome {
+            Outcome::Succeeded => Tone::Success,
+            Outcome::Failed | Outcome::Error => Tone::Failure,
+            Outcome::Skipped => Tone::Muted,
+        };
+        println!(
+            "  {:<width$}  {}  {:>8.2}s",
+            run.path.display(),
+            output::paint(tone, false, &format!("{:<10}", run.outcome.label())),
+            run.duration.as_secs_f64(),
+            width = width
+        );
+    }
+
+    let count = |outcome| runs.iter().filter(|r| r.outcome == outcome).count();
+    println!();
+    println!(
+        "  {} succeeded, {} failed, {} error(s), {} skipped in {:.2}s",
+        count(Outcome::Succeeded),
+        count(Outcome::Failed),
+        count(Outcome::Error),
+        count(Outcome::Skipped),
+        total.as_secs_f64()
+    );
+}
+
+fn json_summary(runs: &[PipelineRun], total: Duration, success: bool) -> String {
+    let pipelines: Vec<serde_json::Value> = runs
+        .iter()
+        .map(|run| {
+            serde_json::json!({
+                "path": run.path.display().to_string(),
+                "name": run.name,
+                "status": run.outcome.label(),
+                "durationSeconds": run.duration.as_secs_f64(),
+                "errors": run.errors,
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "success": success,
+        "durationSeconds": total.as_secs_f64(),
+        "pipelines": pipelines,
+    }))
+    .unwrap_or_default()
+}
@@ -0,0 +1,302 @@
+use crate::output;
+
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::{Args, ValueEnum};
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use minisign_verify::{PublicKey, Signature};
+use pipeline_service::expression::Version;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Release feed used when neither --feed nor ROXID_UPDATE_FEED is set
+const DEFAULT_FEED: &str =
+    "https://github.com/trey-herrington/roxid/releases/latest/download/releases.json";
+
+/// minisign public key that release binaries are signed with, pinned when the
+/// release is built; the feed can't vouch for itself
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("ROXID_RELEASE_PUBLIC_KEY");
+
+/// Version of this build: the crate version, or the nightly version (e.g.
+/// `2.6.0-nightly.20261015`) set when a nightly build is made
+const BUILD_VERSION: &str = match option_env!("ROXID_BUILD_VERSION") {
+    Some(version) => version,
+    None => env!("CARGO_PKG_VERSION"),
+};
+
+/// Update roxid to the latest release
+#[derive(Args, Debug)]
+pub struct SelfUpdateArgs {
+    /// Release channel to update from
+    #[arg(long, value_enum, default_value = "stable")]
+    pub channel: Channel,
+
+    /// Release feed URL (also ROXID_UPDATE_FEED); file:// URLs work for mirrors
+    #[arg(long, value_name = "URL", env = "ROXID_UPDATE_FEED")]
+    pub feed: Option<String>,
+
+    /// Only report whether an update is available
+    #[arg(long)]
+    pub check: bool,
+
+    /// Reinstall the channel's release if it's the installed version (never a downgrade)
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Stable,
+    Nightly,
+}
+
+impl Channel {
+    fn name(self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Nightly => "nightly",
+        }
+    }
+}
+
+/// A release binary listed in the feed
+struct Release {
+    version: String,
+    url: String,
+    sha256: String,
+}
+
+pub fn execute(args: SelfUpdateArgs) -> Result<()> {
+    let feed_url = args.feed.as_deref().unwrap_or(DEFAULT_FEED);
+    let current = BUILD_VERSION;
+    let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+
+    output::status(
+        "Checking",
+        &format!("{} ({})", feed_url, args.channel.name()),
+    );
+    let feed = fetch(feed_url)?;
+    let feed: Value = serde_json::from_slice(&feed)
+        .map_err(|e| eyre!("Invalid release feed {}: {}", feed_url, e))?;
+    let release = find_release(&feed, args.channel, &platform)?;
+
+    let order = compare_versions(&release.version, current).ok_or_else(|| {
+        eyre!(
+            "Cannot compare the '{}' channel's version {} with roxid {}",
+            args.channel.name(),
+            release.version,
+            current
+        )
+    })?;
+    if order == Ordering::Less && args.force {
+        bail!(
+            "The '{}' channel has roxid {}, older than the installed {}; not downgrading",
+            args.channel.name(),
+            release.version,
+            current
+        );
+    }
+    if order == Ordering::Less || (order == Ordering::Equal && !args.force) {
+        output::success(&format!("roxid {} is up to date", current));
+        return Ok(());
+    }
+    if args.check {
+        output::info(&format!(
+            "roxid {} is available ({} installed); run 'roxid self-update' to install it",
+            release.version, current
+        ));
+        return Ok(());
+    }
+    let Some(public_key) = RELEASE_PUBLIC_KEY else {
+        bail!(
+            "This build of roxid has no release signing key to verify updates with; \
+             install roxid {} the way this copy was installed",
+            release.version
+        );
+    };
+
+    output::status(
+        "Downloading",
+        &format!("roxid {} for {}", release.version, platform),
+    );
+    let binary = fetch(&release.url)?;
+    let checksum = format!("{:x}", Sha256::digest(&binary));
+    if !checksum.eq_ignore_ascii_case(&release.sha256) {
+        bail!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            release.url,
+            release.sha256,
+            checksum
+        );
+    }
+    let signature_url = format!("{}.minisig", release.url);
+    let signature = fetch(&signature_url)?;
+    verify_signature(&binary, &signature, public_key)
+        .map_err(|e| eyre!("Signature check failed for {}: {}", release.url, e))?;
+
+    let exe = std::env::current_exe()
+        .and_then(|p| p.canonicalize())
+        .map_err(|e| eyre!("Cannot locate the running executable: {}", e))?;
+    replace_executable(&exe, &binary)?;
+
+    output::success(&format!(
+        "Updated roxid {} → {} ({})",
+        current,
+        release.version,
+        exe.display()
+    ));
+    Ok(())
+}
+
+/// Look up the channel's release for this platform
+///
+/// ```json
+/// { "channels": { "stable": { "version": "2.5.0", "assets": {
+///     "linux-x86_64": { "url": "https://…/roxid-linux-x86_64", "sha256": "…" } } } } }
+/// ```
+fn find_release(feed: &Value, channel: Channel, platform: &str) -> Result<Release> {
+    let entry = feed
+        .get("channels")
+        .and_then(|c| c.get(channel.name()))
+        .ok_or_else(|| eyre!("Release feed has no '{}' channel", channel.name()))?;
+    let version = entry
+        .get("version")
+        .and_then(Value::as_str)
+        .ok_or_else(|| eyre!("Release feed's '{}' channel has no version", channel.name()))?;
+    let asset = entry
+        .get("assets")
+        .and_then(|a| a.get(platform))
+        .ok_or_else(|| eyre!("roxid {} has no binary for {}", version, platform))?;
+    let field = |name: &str| {
+        asset
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| eyre!("Release feed's {} asset has no '{}'", platform, name))
+    };
+    Ok(Release {
+        version: version.to_string(),
+        url: field("url")?,
+        sha256: field("sha256")?,
+    })
+}
+
+/// How `candidate` orders against `current`; `None` if either isn't a version
+fn compare_versions(candidate: &str, current: &str) -> Option<Ordering> {
+    Some(Version::parse(candidate)?.cmp(&Version::parse(current)?))
+}
+
+/// Check a detached minisign `signature` of `binary` against `public_key`
+fn verify_signature(binary: &[u8], signature: &[u8], public_key: &str) -> Result<(), String> {
+    let key =
+        PublicKey::from_base64(public_key).map_err(|e| format!("invalid public key: {}", e))?;
+    let signature = std::str::from_utf8(signature)
+        .map_err(|_| "the signature isn't text".to_string())
+        .and_then(|text| Signature::decode(text).map_err(|e| e.to_string()))?;
+    key.verify(binary, &signature, false)
+        .map_err(|e| e.to_string())
+}
+
+/// Download `url` with curl
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", url])
+        .output()
+        .map_err(|e| eyre!("Failed to run curl (is it installed?): {}", e))?;
+    if !output.status.success() {
+        bail!(
+            "Failed to download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Swap in the new binary with a rename, so the executable is never half-written
+fn replace_executable(exe: &Path, binary: &[u8]) -> Result<()> {
+    let dir = exe
+        .parent()
+        .ok_or_else(|| eyre!("Cannot determine the directory of {}", exe.display()))?;
+    let staged = dir.join(format!(".roxid-update-{}", std::process::id()));
+    std::fs::write(&staged, binary)
+        .map_err(|e| eyre!("Cannot write to {} (try with sudo?): {}", dir.display(), e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows can't replace a running executable, but it can rename it
+    let old: PathBuf = exe.with_extension("old.exe");
+    if cfg!(windows) {
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)?;
+    }
+
+    std::fs::rename(&staged, exe).map_err(|e| {
+        let _ = std::fs::remove_file(&staged);
+        // Put the running executable back, so roxid still starts
+        if cfg!(windows) {
+            let _ = std::fs::rename(&old, exe);
+        }
+        eyre!("Failed to replace {}: {}", exe.display(), e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "RWR2d3oNv4fOm/Qbgsu+Wv8/raXsT8vzeUCqq7fTtCYK6Myf6Emdrdo9";
+    const SIGNATURE: &str = "untrusted comment: signature from roxid release key
+RUR2d3oNv4fOm/TCGJX4046XzUXxWLND8lAECqfCGnZqVWQjxS69GJplHZylClAXZd63BZpiFm2Zt8OX/VsrsPBsgIWwtt5n7gk=
+trusted comment: timestamp:0\tfile:roxid-linux-x86_64
+Q2nXHJ8KL2KYLVwzoMadDuzl+tihyCgZuQT7dnFNBxNfQJ6vllHxQvVwJ5FfTfyqOthBw//u59rlqrmcn49BCQ==
+";
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("2.10.0", "2.9.1"), Some(Ordering::Greater));
+        assert_eq!(
+            compare_versions("v2.5.0", "2.5.0-rc.2"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_versions("2.5.0-rc.2", "2.5.0"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(compare_versions("2.5", "2.5.0"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("nightly", "2.5.0"), None);
+
+        // Nightlies order by their build date, and after the release they follow
+        assert_eq!(
+            compare_versions("2.6.0-nightly.20261016", "2.6.0-nightly.20261015"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_versions("2.6.0-nightly.20261014", "2.6.0-nightly.20261015"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_versions("2.6.0-nightly.20261015", "2.5.0"),
+            Some(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let binary = b"roxid 9.9.9\n";
+        assert!(verify_signature(binary, SIGNATURE.as_bytes(), PUBLIC_KEY).is_ok());
+
+        // A binary swapped along with its checksum in the feed
+        assert!(verify_signature(b"roxid 6.6.6\n", SIGNATURE.as_bytes(), PUBLIC_KEY).is_err());
+        assert!(verify_signature(binary, b"not a signature", PUBLIC_KEY).is_err());
+        let other_key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+        assert!(verify_signature(binary, SIGNATURE.as_bytes(), other_key).is_err());
+    }
+}
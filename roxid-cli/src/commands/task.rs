@@ -1,9 +1,12 @@
 use crate::output;
 
+use std::path::{Path, PathBuf};
+
 use clap::{Args, Subcommand};
 use color_eyre::Result;
 
-use pipeline_service::TaskCache;
+use pipeline_service::tasks::required_tasks;
+use pipeline_service::{normalize_pipeline, AzureParser, TaskCache};
 
 /// Manage the Azure DevOps task cache
 #[derive(Args, Debug)]
@@ -14,12 +17,40 @@ pub struct TaskArgs {
 
 #[derive(Subcommand, Debug)]
 pub enum TaskCommand {
-    /// List cached tasks
-    List,
+    /// List cached tasks, or the tasks a pipeline requires
+    List {
+        /// Show the tasks used by this pipeline and whether they are cached
+        #[arg(long, value_name = "FILE")]
+        pipeline: Option<PathBuf>,
+    },
 
-    /// Pre-download a task (e.g., Bash@3)
+    /// Pre-download a task (e.g., Bash@3), or every task a pipeline uses
+    #[command(visible_alias = "add")]
     Fetch {
         /// Task reference (e.g., Bash@3, PowerShell@2)
+        #[arg(required_unless_present = "pipeline")]
+        task_ref: Option<String>,
+
+        /// Fetch all tasks used by this pipeline for offline use
+        #[arg(long, value_name = "FILE", conflicts_with = "task_ref")]
+        pipeline: Option<PathBuf>,
+    },
+
+    /// Download a cached task again
+    Update {
+        /// Task reference (e.g., Bash@3)
+        task_ref: String,
+    },
+
+    /// Pin a task's major version to a specific version (e.g., Bash@3.231.0)
+    Pin {
+        /// Task reference with a full version
+        task_ref: String,
+    },
+
+    /// Remove a pin (e.g., Bash@3)
+    Unpin {
+        /// Task reference with the pinned major version
         task_ref: String,
     },
 
@@ -37,7 +68,32 @@ pub async fn execute(args: TaskArgs) -> Result<()> {
     let cache = TaskCache::new();
 
     match args.command {
-        TaskCommand::List => {
+        TaskCommand::List {
+            pipeline: Some(pipeline),
+        } => {
+            let tasks = pipeline_tasks(&pipeline)?;
+            output::status("Tasks", &format!("required by {}", pipeline.display()));
+
+            if tasks.is_empty() {
+                output::dim("  Pipeline uses no tasks");
+            }
+            for task_ref in &tasks {
+                let (name, version) = parse_reference(task_ref)?;
+                let resolved = cache.resolve_version(&name, &version);
+                let pinned = if resolved != version {
+                    format!(" (pinned to {})", resolved)
+                } else {
+                    String::new()
+                };
+                if cache.is_cached(&name, &version) {
+                    output::check(&format!("{}{}", task_ref, pinned));
+                } else {
+                    output::warning(&format!("{}{} - not cached", task_ref, pinned));
+                }
+            }
+        }
+
+        TaskCommand::List { pipeline: None } => {
             output::status(
                 "Tasks",
                 &format!("cached in {}", cache.cache_dir().display()),
@@ -60,32 +116,75 @@ pub async fn execute(args: TaskArgs) -> Result<()> {
                     std::process::exit(1);
                 }
             }
-        }
 
-        TaskCommand::Fetch { task_ref } => {
-            output::status("Fetching", &task_ref);
-
-            match cache.get_task(&task_ref).await {
-                Ok(task) => {
-                    output::success(&format!(
-                        "Cached {}@{} at {}",
-                        task.name,
-                        task.version,
-                        task.path.display()
-                    ));
+            let pins = cache
+                .pins()
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to read pins: {}", e))?;
+            if !pins.is_empty() {
+                println!();
+                output::status("Pinned", &format!("{} task(s)", pins.len()));
+                for (task_ref, version) in &pins {
+                    println!("  {} -> {}", task_ref, version);
                 }
-                Err(e) => {
-                    output::error(&format!("Failed to fetch '{}': {}", task_ref, e));
-                    std::process::exit(1);
+            }
+        }
+
+        TaskCommand::Fetch {
+            task_ref: Some(task_ref),
+            ..
+        } => {
+            fetch(&cache, &task_ref).await;
+        }
+
+        TaskCommand::Fetch {
+            pipeline: Some(pipeline),
+            ..
+        } => {
+            let tasks = pipeline_tasks(&pipeline)?;
+            if tasks.is_empty() {
+                output::dim("  Pipeline uses no tasks");
+            }
+            for task_ref in &tasks {
+                fetch(&cache, task_ref).await;
+            }
+        }
+
+        TaskCommand::Fetch { .. } => unreachable!("clap requires a task or --pipeline"),
+
+        TaskCommand::Update { task_ref } => {
+            let (name, version) = parse_reference(&task_ref)?;
+            let resolved = cache.resolve_version(&name, &version);
+            cache
+                .clear_task(&name, &resolved)
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to clear task: {}", e))?;
+            fetch(&cache, &task_ref).await;
+        }
+
+        TaskCommand::Pin { task_ref } => {
+            let (name, version) = parse_reference(&task_ref)?;
+            let pinned = cache
+                .pin(&name, &version)
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to pin task: {}", e))?;
+            output::success(&format!("Pinned {} to {}", pinned, version));
+            fetch(&cache, &pinned).await;
+        }
+
+        TaskCommand::Unpin { task_ref } => {
+            let (name, major) = parse_reference(&task_ref)?;
+            let removed = cache
+                .unpin(&name, &major)
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to unpin task: {}", e))?;
+            match removed {
+                Some(version) => {
+                    output::success(&format!("Unpinned {} (was {})", task_ref, version))
                 }
+                None => output::dim(&format!("  {} is not pinned", task_ref)),
             }
         }
 
         TaskCommand::Clear { task_ref } => {
             if let Some(task_ref) = task_ref {
-                let (name, version) = TaskCache::parse_task_reference(&task_ref).map_err(|e| {
-                    color_eyre::eyre::eyre!("Invalid task reference '{}': {}", task_ref, e)
-                })?;
+                let (name, version) = parse_reference(&task_ref)?;
 
                 output::status("Clearing", &format!("{}@{}", name, version));
                 cache
@@ -108,3 +207,37 @@ pub async fn execute(args: TaskArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Download a task into the cache, exiting on failure
+async fn fetch(cache: &TaskCache, task_ref: &str) {
+    output::status("Fetching", task_ref);
+
+    match cache.get_task(task_ref).await {
+        Ok(task) => {
+            output::success(&format!(
+                "Cached {}@{} at {}",
+                task.name,
+                task.version,
+                task.path.display()
+            ));
+        }
+        Err(e) => {
+            output::error(&format!("Failed to fetch '{}': {}", task_ref, e));
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_reference(task_ref: &str) -> Result<(String, String)> {
+    TaskCache::parse_task_reference(task_ref)
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid task reference '{}': {}", task_ref, e))
+}
+
+/// Task references used by a pipeline file
+fn pipeline_tasks(pipeline: &Path) -> Result<Vec<String>> {
+    let parsed = AzureParser::parse_file(pipeline)
+        .map_err(|e| color_eyre::eyre::eyre!("Parse error: {}", e.message))?;
+    Ok(required_tasks(&normalize_pipeline(parsed))
+        .into_iter()
+        .collect())
+}
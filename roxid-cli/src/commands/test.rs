@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use clap::Args;
 use color_eyre::Result;
 
+use pipeline_service::execution::{Recordings, StepRecorder};
 use pipeline_service::utils::find_repo_root;
 use pipeline_service::{ReportFormat, TestFileParser, TestReporter, TestRunner};
 
@@ -29,6 +30,39 @@ pub struct TestArgs {
     /// Working directory for test execution
     #[arg(long, short = 'w', value_name = "DIR")]
     pub working_dir: Option<PathBuf>,
+
+    /// Record each step's output and exit code to FILE for --replay
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Replay step output recorded with --record instead of running commands
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Write a markdown summary (results per test, failed assertions) to
+    /// FILE, for posting as a pull request comment
+    #[arg(long, value_name = "FILE")]
+    pub summary_md: Option<PathBuf>,
+
+    /// CI-parity mode: fail on roxid-only expression functions such as env()
+    #[arg(long, env = "ROXID_STRICT")]
+    pub strict: bool,
+}
+
+impl Default for TestArgs {
+    fn default() -> Self {
+        Self {
+            file: None,
+            filter: None,
+            output: "terminal".to_string(),
+            fail_fast: false,
+            working_dir: None,
+            record: None,
+            replay: None,
+            summary_md: None,
+            strict: false,
+        }
+    }
 }
 
 pub async fn execute(args: TestArgs) -> Result<()> {
@@ -55,7 +89,20 @@ pub async fn execute(args: TestArgs) -> Result<()> {
         runner = runner.with_filter(filter.clone());
     }
 
-    runner = runner.with_fail_fast(args.fail_fast);
+    runner = runner
+        .with_fail_fast(args.fail_fast)
+        .with_strict(args.strict);
+
+    let recorder = match (&args.record, &args.replay) {
+        (Some(_), _) => Some(StepRecorder::record()),
+        (_, Some(path)) => Some(StepRecorder::replay(
+            Recordings::load(path).map_err(|e| color_eyre::eyre::eyre!("{}", e))?,
+        )),
+        _ => None,
+    };
+    if let Some(recorder) = &recorder {
+        runner = runner.with_recorder(recorder.clone());
+    }
 
     // Discover or use provided test files
     let test_files = if let Some(file) = &args.file {
@@ -77,7 +124,9 @@ pub async fn execute(args: TestArgs) -> Result<()> {
     let mut all_passed = true;
     let mut total_passed = 0usize;
     let mut total_failed = 0usize;
+    let mut total_errored = 0usize;
     let mut total_skipped = 0usize;
+    let mut suites = Vec::new();
 
     for file in &test_files {
         output::dim(&format!("  {}", file.display()));
@@ -86,6 +135,7 @@ pub async fn execute(args: TestArgs) -> Result<()> {
             Ok(suite_result) => {
                 total_passed += suite_result.passed;
                 total_failed += suite_result.failed;
+                total_errored += suite_result.errored;
                 total_skipped += suite_result.skipped;
 
                 if !suite_result.all_passed() {
@@ -94,6 +144,7 @@ pub async fn execute(args: TestArgs) -> Result<()> {
 
                 let report = TestReporter::report(&suite_result, format);
                 print!("{}", report);
+                suites.push(suite_result);
             }
             Err(e) => {
                 all_passed = false;
@@ -107,14 +158,37 @@ pub async fn execute(args: TestArgs) -> Result<()> {
         }
     }
 
+    if let (Some(path), Some(recorder)) = (&args.record, &recorder) {
+        recorder
+            .recordings()
+            .save(path)
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+        output::dim(&format!("  Step recordings written to {}", path.display()));
+    }
+    if let Some(path) = &args.summary_md {
+        std::fs::write(path, TestReporter::to_markdown(&suites))
+            .map_err(|e| color_eyre::eyre::eyre!("failed to write {}: {}", path.display(), e))?;
+        output::dim(&format!("  Summary written to {}", path.display()));
+    }
+
     // Print summary
     println!();
-    let total = total_passed + total_failed + total_skipped;
+    let total = total_passed + total_failed + total_errored + total_skipped;
     if all_passed {
         output::success(&format!(
             "All {} tests passed ({} passed, {} skipped)",
             total, total_passed, total_skipped
         ));
+    } else if total_errored > 0 {
+        output::failure(&format!(
+            "{} of {} tests failed ({} passed, {} failed, {} errored, {} skipped)",
+            total_failed + total_errored,
+            total,
+            total_passed,
+            total_failed,
+            total_errored,
+            total_skipped
+        ));
     } else {
         output::failure(&format!(
             "{} of {} tests failed ({} passed, {} failed, {} skipped)",
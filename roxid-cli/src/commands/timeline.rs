@@ -0,0 +1,148 @@
+use crate::output;
+
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use color_eyre::Result;
+
+use pipeline_service::execution::{ComparisonRow, RunComparison, RunRecord};
+
+/// Import Azure DevOps build timelines and compare runs
+#[derive(Args, Debug)]
+pub struct TimelineArgs {
+    #[command(subcommand)]
+    pub command: TimelineCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TimelineCommand {
+    /// Convert an Azure DevOps build timeline (REST API JSON) into a run record
+    Import {
+        /// Timeline JSON from _apis/build/builds/{id}/timeline
+        timeline: PathBuf,
+
+        /// Write the run record to FILE instead of stdout
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Compare two runs: steps that ran in only one, ordering, results and durations
+    Compare {
+        /// Run record (e.g. from 'roxid run --record FILE') or timeline JSON
+        left: PathBuf,
+
+        /// Run record or timeline JSON to compare against
+        right: PathBuf,
+    },
+}
+
+pub fn execute(args: TimelineArgs) -> Result<()> {
+    match args.command {
+        TimelineCommand::Import {
+            timeline,
+            output: destination,
+        } => {
+            let record =
+                RunRecord::load(&timeline).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            match destination {
+                Some(path) => {
+                    record
+                        .save(&path)
+                        .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                    let jobs = record.jobs().count();
+                    let steps: usize = record.jobs().map(|(_, job)| job.steps.len()).sum();
+                    output::success(&format!(
+                        "Imported {} stage(s), {} job(s) and {} step(s) to {}",
+                        record.stages.len(),
+                        jobs,
+                        steps,
+                        path.display()
+                    ));
+                }
+                None => println!("{}", serde_json::to_string_pretty(&record)?),
+            }
+            Ok(())
+        }
+
+        TimelineCommand::Compare { left, right } => {
+            let left = RunRecord::load(&left).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            let right = RunRecord::load(&right).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            print_comparison(&RunComparison::new(&left, &right));
+            Ok(())
+        }
+    }
+}
+
+fn print_comparison(comparison: &RunComparison) {
+    let width = comparison
+        .rows
+        .iter()
+        .map(|row| display_key(row).chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Job / step".len());
+    let column = comparison
+        .left_source
+        .len()
+        .max(comparison.right_source.len())
+        .max(10);
+
+    println!();
+    println!(
+        "{}",
+        output::bold(&format!(
+            "  {:<width$}  {:>column$}  {:>column$}  {:>10}",
+            "Job / step",
+            comparison.left_source,
+            comparison.right_source,
+            "Change",
+            width = width,
+            column = column
+        ))
+    );
+    for row in &comparison.rows {
+        let change = match (row.left, row.right) {
+            (Some(left), Some(right)) => format!("{:+.2}s", right - left),
+            _ => "-".to_string(),
+        };
+        let line = format!(
+            "  {:<width$}  {:>column$}  {:>column$}  {:>10}",
+            display_key(row),
+            row.left.map_or("-".to_string(), |s| format!("{:.2}s", s)),
+            row.right.map_or("-".to_string(), |s| format!("{:.2}s", s)),
+            change,
+            width = width,
+            column = column
+        );
+        if row.is_step {
+            println!("{}", line);
+        } else {
+            println!("{}", output::bold(&line));
+        }
+    }
+
+    println!();
+    if comparison.differences.is_empty() {
+        output::success(
+            "Runs match: same jobs and steps, in the same order, with the same results",
+        );
+        return;
+    }
+    output::warning(&format!(
+        "{} difference(s) between {} and {}",
+        comparison.differences.len(),
+        comparison.left_source,
+        comparison.right_source
+    ));
+    for difference in &comparison.differences {
+        println!("  - {}", difference);
+    }
+}
+
+/// Steps are indented under their job
+fn display_key(row: &ComparisonRow) -> String {
+    match row.key.split_once('/') {
+        Some((_, step)) if row.is_step => format!("  {}", step),
+        _ => row.key.clone(),
+    }
+}
@@ -1,17 +1,24 @@
 use crate::output;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Args;
 use color_eyre::Result;
 
+use pipeline_service::parser::{
+    check_references, check_unused, split_document_selector, LintSource, TemplateInclude,
+};
+use pipeline_service::tasks::required_tasks;
 use pipeline_service::utils::find_repo_root;
-use pipeline_service::{normalize_pipeline, AzureParser, PipelineValidator, TemplateEngine};
+use pipeline_service::{
+    normalize_pipeline, AzureParser, ExecutionGraph, PipelineValidator, TaskCache, TemplateEngine,
+};
 
 /// Validate a pipeline YAML file
 #[derive(Args, Debug)]
 pub struct ValidateArgs {
-    /// Path to the pipeline YAML file
+    /// Path to the pipeline YAML file; `file.yml#<name-or-index>` picks one
+    /// pipeline from a multi-document file, otherwise all are validated
     pub pipeline: PathBuf,
 
     /// Also validate template resolution
@@ -25,9 +32,36 @@ pub struct ValidateArgs {
 
 pub fn execute(args: ValidateArgs) -> Result<()> {
     let pipeline_path = &args.pipeline;
+    let (file, selector) = split_document_selector(pipeline_path);
 
-    if !pipeline_path.exists() {
-        color_eyre::eyre::bail!("Pipeline file not found: {}", pipeline_path.display());
+    if !file.exists() {
+        color_eyre::eyre::bail!("Pipeline file not found: {}", file.display());
+    }
+
+    // A multi-document file: validate each pipeline in turn
+    if selector.is_none() {
+        let documents = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| AzureParser::parse_documents(&content).ok())
+            .unwrap_or_default();
+        if documents.len() > 1 {
+            output::info(&format!(
+                "{} contains {} pipelines",
+                file.display(),
+                documents.len()
+            ));
+            for (i, document) in documents.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                execute(ValidateArgs {
+                    pipeline: PathBuf::from(format!("{}#{}", file.display(), document.index)),
+                    templates: args.templates,
+                    repo_root: args.repo_root.clone(),
+                })?;
+            }
+            return Ok(());
+        }
     }
 
     // Step 1: Parse YAML syntax
@@ -36,10 +70,7 @@ pub fn execute(args: ValidateArgs) -> Result<()> {
     let raw_pipeline = match AzureParser::parse_file(pipeline_path) {
         Ok(p) => p,
         Err(e) => {
-            output::error(&format!("Parse error: {}", e.message));
-            if let Some(suggestion) = &e.suggestion {
-                output::info(&format!("  Suggestion: {}", suggestion));
-            }
+            output::parse_error(&e, pipeline_path);
             std::process::exit(1);
         }
     };
@@ -72,22 +103,60 @@ pub fn execute(args: ValidateArgs) -> Result<()> {
             output::error(&format!("{} validation error(s):", errors.len()));
             for error in &errors {
                 output::error(&format!("  - [{}] {}", error.path, error.message));
+                if let Some(suggestion) = &error.suggestion {
+                    output::info(&format!("    Suggestion: {}", suggestion));
+                }
             }
             std::process::exit(1);
         }
     }
 
-    // Step 4: Template validation (optional)
-    if args.templates {
-        let repo_root = args.repo_root.clone().unwrap_or_else(|| {
-            let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-            find_repo_root(&cwd).unwrap_or(cwd)
-        });
+    // Step 4: Dependencies as the executor resolves them, including implicit ordering
+    match ExecutionGraph::from_pipeline(&pipeline) {
+        Ok(_) => output::check("Dependency graph valid"),
+        Err(e) => {
+            output::error(&format!(
+                "Dependency error in {}: {}",
+                pipeline_path.display(),
+                e.message
+            ));
+            std::process::exit(1);
+        }
+    }
+
+    // Step 5: Tasks missing from the cache would need a download at run time
+    let cache = TaskCache::new();
+    let missing: Vec<String> = required_tasks(&pipeline)
+        .into_iter()
+        .filter(|task_ref| {
+            TaskCache::parse_task_reference(task_ref)
+                .map(|(name, version)| !cache.is_cached(&name, &version))
+                .unwrap_or(true)
+        })
+        .collect();
+    if !missing.is_empty() {
+        output::warning(&format!(
+            "{} task(s) not in the task cache: {}",
+            missing.len(),
+            missing.join(", ")
+        ));
+        output::dim(&format!(
+            "  Run 'roxid task fetch --pipeline {}' to download them",
+            pipeline_path.display()
+        ));
+    }
 
+    // Step 6: Template validation (optional)
+    let repo_root = args.repo_root.clone().unwrap_or_else(|| {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        find_repo_root(&cwd).unwrap_or(cwd)
+    });
+    let mut resolved_pipeline = None;
+    if args.templates {
         output::status("Resolving", "templates...");
 
-        let mut engine = TemplateEngine::new(repo_root);
-        match engine.resolve_pipeline(pipeline) {
+        let mut engine = TemplateEngine::new(repo_root.clone());
+        match engine.resolve_pipeline(pipeline.clone()) {
             Ok(resolved) => {
                 let resolved_stages = resolved.stages.len();
                 let resolved_jobs: usize = resolved.stages.iter().map(|s| s.jobs.len()).sum();
@@ -102,16 +171,96 @@ pub fn execute(args: ValidateArgs) -> Result<()> {
                     "Templates resolved: {} stages, {} jobs, {} steps",
                     resolved_stages, resolved_jobs, resolved_steps
                 ));
+
+                // dependsOn values produced by template expressions
+                let resolved = normalize_pipeline(resolved);
+                if let Err(e) = ExecutionGraph::from_pipeline(&resolved) {
+                    output::error(&format!("Dependency error after templates: {}", e.message));
+                    std::process::exit(1);
+                }
+                output::check("Resolved dependency graph valid");
+                resolved_pipeline = Some((resolved, engine.trace().to_vec()));
             }
             Err(e) => {
-                output::error(&format!("Template error: {}", e));
+                output::parse_error(&e, pipeline_path);
                 std::process::exit(1);
             }
         }
     }
 
+    // Step 7: Expression references, once templates have supplied every
+    // stage, job and variable
+    let resolved_pipeline = resolved_pipeline.or_else(|| {
+        let mut engine = TemplateEngine::new(repo_root.clone());
+        let resolved = engine.resolve_pipeline(pipeline).ok()?;
+        Some((normalize_pipeline(resolved), engine.trace().to_vec()))
+    });
+    match resolved_pipeline
+        .as_ref()
+        .map(|(resolved, _)| check_references(resolved))
+    {
+        Some(errors) if errors.is_empty() => output::check("Expression references valid"),
+        Some(errors) => {
+            output::error(&format!("{} unknown reference(s):", errors.len()));
+            for error in &errors {
+                output::error(&format!("  - [{}] {}", error.path, error.message));
+                if let Some(suggestion) = &error.suggestion {
+                    output::info(&format!("    Suggestion: {}", suggestion));
+                }
+            }
+            std::process::exit(1);
+        }
+        None => output::dim(
+            "  Expression references not checked: templates don't resolve (see --templates)",
+        ),
+    }
+
+    // Step 8: Parameters and variables nothing uses; warnings, not errors
+    if let Some((resolved, trace)) = &resolved_pipeline {
+        let warnings = check_unused(resolved, &lint_sources(&file, &repo_root, trace));
+        if warnings.is_empty() {
+            output::check("No unused parameters or variables");
+        } else {
+            output::warning(&format!("{} lint warning(s):", warnings.len()));
+            for warning in &warnings {
+                let location = match (&warning.file, warning.line) {
+                    (Some(file), Some(line)) => format!("{}:{}", file, line),
+                    (Some(file), None) => file.clone(),
+                    (None, _) => warning.path.clone(),
+                };
+                output::warning(&format!(
+                    "  - {} [{}] {}",
+                    location, warning.rule, warning.message
+                ));
+            }
+        }
+    }
+
     println!();
     output::success("Pipeline is valid");
 
     Ok(())
 }
+
+/// The pipeline file and each template it includes, once
+fn lint_sources(file: &Path, repo_root: &Path, trace: &[TemplateInclude]) -> Vec<LintSource> {
+    let mut sources = Vec::new();
+    if let Ok(content) = std::fs::read_to_string(file) {
+        sources.push(LintSource {
+            path: file.display().to_string(),
+            content,
+        });
+    }
+    for include in trace {
+        if sources.iter().any(|s| s.path == include.template) {
+            continue;
+        }
+        if let Ok(content) = std::fs::read_to_string(repo_root.join(&include.template)) {
+            sources.push(LintSource {
+                path: include.template.clone(),
+                content,
+            });
+        }
+    }
+    sources
+}
@@ -0,0 +1,201 @@
+use crate::output;
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Instant;
+
+use clap::Args;
+use color_eyre::Result;
+
+use pipeline_service::execution::watch::daemon_log_path;
+use pipeline_service::execution::{notify_desktop, RoxidConfig, Watcher};
+use pipeline_service::utils::find_repo_root;
+
+/// Changed paths listed before the rest are counted
+const LISTED_CHANGES: usize = 3;
+
+/// Run pipelines when the files they watch change
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// Pipelines to watch (default: [watch.pipelines] in .roxid.toml, else
+    /// azure-pipelines.yml)
+    pub pipelines: Vec<PathBuf>,
+
+    /// Keep watching in the background, logging to ~/.roxid/watch.log
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// Quiet period after the last change before pipelines run
+    #[arg(long, value_name = "MS")]
+    pub debounce: Option<u64>,
+
+    /// Don't show a desktop notification when a run finishes
+    #[arg(long)]
+    pub no_notify: bool,
+
+    /// Repository directory (default: repository root)
+    #[arg(long, short = 'w', value_name = "DIR")]
+    pub working_dir: Option<PathBuf>,
+}
+
+pub async fn execute(args: WatchArgs) -> Result<()> {
+    let repo_dir = match &args.working_dir {
+        Some(dir) => dir.clone(),
+        None => {
+            let cwd = std::env::current_dir()?;
+            find_repo_root(&cwd).unwrap_or(cwd)
+        }
+    };
+    let repo_dir = repo_dir.canonicalize()?;
+    let mut config = RoxidConfig::discover(&repo_dir)
+        .map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+        .watch;
+    if let Some(debounce) = args.debounce {
+        config.debounce_ms = Some(debounce);
+    }
+
+    let pipelines = if !args.pipelines.is_empty() {
+        args.pipelines.clone()
+    } else if !config.pipelines.is_empty() {
+        config.pipelines.keys().cloned().collect()
+    } else {
+        vec![PathBuf::from("azure-pipelines.yml")]
+    };
+    let mut rules = Vec::new();
+    for pipeline in &pipelines {
+        match config
+            .rule(&repo_dir, pipeline)
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+        {
+            Some(rule) => rules.push(rule),
+            None => output::warning(&format!(
+                "{} has `trigger: none`; not watching it",
+                pipeline.display()
+            )),
+        }
+    }
+    if rules.is_empty() {
+        color_eyre::eyre::bail!("No pipelines to watch");
+    }
+
+    if args.daemon {
+        return daemonize(&args, &repo_dir);
+    }
+
+    output::header(&format!("Watching {}", repo_dir.display()));
+    for rule in &rules {
+        let mut paths = if rule.paths.include.is_empty() {
+            "any change".to_string()
+        } else {
+            rule.paths.include.join(", ")
+        };
+        let templates = rule.sources.len().saturating_sub(1);
+        if templates > 0 && !rule.paths.include.is_empty() {
+            paths.push_str(&format!(" and its {} template(s)", templates));
+        }
+        output::dim(&format!("  {} <- {}", rule.pipeline.display(), paths));
+    }
+    output::dim("  Press Ctrl+C to stop");
+
+    let mut watcher = Watcher::new(&repo_dir, rules, &config);
+    loop {
+        let batch = watcher.next_batch().await;
+        println!();
+        output::status("Changed", &describe_changes(&batch.changed));
+        for pipeline in &batch.pipelines {
+            run_pipeline(&repo_dir, pipeline, !args.no_notify).await?;
+        }
+        watcher.rescan().await;
+    }
+}
+
+/// Run one pipeline in a child `roxid run`, so a failing run doesn't end
+/// the watch
+async fn run_pipeline(repo_dir: &Path, pipeline: &Path, notify: bool) -> Result<()> {
+    output::status("Running", &pipeline.display().to_string());
+    let start = Instant::now();
+    let status = tokio::process::Command::new(std::env::current_exe()?)
+        .arg("run")
+        .arg(pipeline)
+        .arg("--working-dir")
+        .arg(repo_dir)
+        // The watcher notifies instead
+        .arg("--no-notify")
+        .current_dir(repo_dir)
+        .stdin(Stdio::null())
+        .status()
+        .await?;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let (title, message) = if status.success() {
+        let message = format!("{} succeeded in {:.1}s", pipeline.display(), elapsed);
+        output::success(&message);
+        ("roxid: pipeline succeeded", message)
+    } else {
+        let message = format!("{} failed after {:.1}s", pipeline.display(), elapsed);
+        output::failure(&message);
+        ("roxid: pipeline failed", message)
+    };
+    if notify {
+        notify_desktop(title, &message);
+    }
+    Ok(())
+}
+
+/// Start `roxid watch` again without `--daemon`, detached, with its output
+/// going to the log
+fn daemonize(args: &WatchArgs, repo_dir: &Path) -> Result<()> {
+    let log_path = daemon_log_path();
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command
+        .arg("watch")
+        .args(&args.pipelines)
+        .arg("--working-dir")
+        .arg(repo_dir)
+        .current_dir(repo_dir)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    if let Some(debounce) = args.debounce {
+        command.arg("--debounce").arg(debounce.to_string());
+    }
+    if args.no_notify {
+        command.arg("--no-notify");
+    }
+    // Its own process group, so closing the terminal doesn't stop it
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let child = command.spawn()?;
+
+    output::success(&format!(
+        "Watching {} in the background (pid {})",
+        repo_dir.display(),
+        child.id()
+    ));
+    output::dim(&format!("  Logging to {}", log_path.display()));
+    Ok(())
+}
+
+fn describe_changes(changed: &[String]) -> String {
+    let mut listed = changed
+        .iter()
+        .take(LISTED_CHANGES)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if changed.len() > LISTED_CHANGES {
+        listed.push_str(&format!(" and {} more", changed.len() - LISTED_CHANGES));
+    }
+    listed
+}
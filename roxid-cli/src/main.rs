@@ -2,8 +2,15 @@ use color_eyre::Result;
 
 mod commands;
 mod output;
+mod progress;
 
-use clap::{Parser, Subcommand};
+use std::io::IsTerminal;
+
+use clap::{CommandFactory, Parser, Subcommand};
+
+use pipeline_service::execution::RoxidConfig;
+use pipeline_service::utils::find_repo_root;
+use roxid_tui::theme::{ColorChoice, UiSettings};
 
 /// Roxid - Azure DevOps Pipeline Emulator
 ///
@@ -15,12 +22,20 @@ use clap::{Parser, Subcommand};
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// When to color output: auto is a terminal without NO_COLOR set
+    /// (default: `color` in .roxid.toml [ui], else auto)
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    color: Option<output::ColorWhen>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Run an Azure DevOps pipeline locally
-    Run(commands::run::RunArgs),
+    Run(Box<commands::run::RunArgs>),
+
+    /// Run several pipelines (files or glob patterns) and print a summary
+    RunAll(commands::run_all::RunAllArgs),
 
     /// Run pipeline tests
     Test(commands::test::TestArgs),
@@ -28,11 +43,52 @@ enum Commands {
     /// Validate a pipeline YAML file
     Validate(commands::validate::ValidateArgs),
 
+    /// Show a pipeline's critical path and forecast its duration
+    Analyze(commands::analyze::AnalyzeArgs),
+
+    /// Import Azure DevOps build timelines and compare runs
+    Timeline(commands::timeline::TimelineArgs),
+
+    /// Query a run's results with a jq-like filter
+    Query(commands::query::QueryArgs),
+
+    /// Export a pipeline's template inclusion graph (DOT, Mermaid or JSON)
+    Graph(commands::graph::GraphArgs),
+
+    /// Print a pipeline's YAML with anchors, aliases and merge keys expanded
+    Normalize(commands::normalize::NormalizeArgs),
+
+    /// Generate markdown documentation for template parameters
+    Docs(commands::docs::DocsArgs),
+
+    /// Check the local environment and the tools a pipeline needs
+    Doctor(commands::doctor::DoctorArgs),
+
     /// Launch the interactive TUI
     Tui,
 
     /// Manage the Azure DevOps task cache
+    #[command(visible_alias = "tasks")]
     Task(commands::task::TaskArgs),
+
+    /// Manage git hooks that run pipelines or tests before commit/push
+    Hook(commands::hook::HookArgs),
+
+    /// Run pipelines when the files they watch change
+    Watch(commands::watch::WatchArgs),
+
+    /// Show the project configuration roxid picks up
+    Config(commands::config::ConfigArgs),
+
+    /// Update roxid to the latest release
+    SelfUpdate(commands::self_update::SelfUpdateArgs),
+
+    /// Print a shell completion script (bash, zsh or fish)
+    Completions(commands::complete::CompletionsArgs),
+
+    /// Complete a partial command line for the completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete(commands::complete::CompleteArgs),
 }
 
 #[tokio::main]
@@ -41,16 +97,59 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
+    // Theme and color from .roxid.toml [ui]; commands that read the config
+    // report it if it doesn't parse
+    let cwd = std::env::current_dir()?;
+    let ui = RoxidConfig::discover(&find_repo_root(&cwd).unwrap_or(cwd))
+        .ok()
+        .and_then(|config| UiSettings::from_config(&config).ok())
+        .unwrap_or_default();
+    let color = cli.color.map(ColorChoice::from);
+    output::init(
+        ui.theme.theme(),
+        color
+            .unwrap_or(ui.color)
+            .enabled(std::io::stderr().is_terminal()),
+    );
+
     match cli.command {
         // No subcommand = launch TUI (same as `roxid tui`)
-        None | Some(Commands::Tui) => roxid_tui::run().await,
+        None | Some(Commands::Tui) => roxid_tui::run(color).await,
 
-        Some(Commands::Run(args)) => commands::run::execute(args).await,
+        Some(Commands::Run(args)) => commands::run::execute(*args).await,
+
+        Some(Commands::RunAll(args)) => commands::run_all::execute(args).await,
 
         Some(Commands::Test(args)) => commands::test::execute(args).await,
 
         Some(Commands::Validate(args)) => commands::validate::execute(args),
 
+        Some(Commands::Analyze(args)) => commands::analyze::execute(args),
+
+        Some(Commands::Timeline(args)) => commands::timeline::execute(args),
+
+        Some(Commands::Query(args)) => commands::query::execute(args),
+
+        Some(Commands::Graph(args)) => commands::graph::execute(args),
+
+        Some(Commands::Normalize(args)) => commands::normalize::execute(args),
+
+        Some(Commands::Docs(args)) => commands::docs::execute(args),
+
+        Some(Commands::Doctor(args)) => commands::doctor::execute(args),
+
         Some(Commands::Task(args)) => commands::task::execute(args).await,
+
+        Some(Commands::Hook(args)) => commands::hook::execute(args).await,
+
+        Some(Commands::Watch(args)) => commands::watch::execute(args).await,
+
+        Some(Commands::Config(args)) => commands::config::execute(args),
+
+        Some(Commands::SelfUpdate(args)) => commands::self_update::execute(args),
+
+        Some(Commands::Completions(args)) => commands::complete::print_script(args),
+
+        Some(Commands::Complete(args)) => commands::complete::execute(args, Cli::command()),
     }
 }
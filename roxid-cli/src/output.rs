@@ -1,71 +1,240 @@
 // Output formatting helpers for CLI commands
 
+use std::path::Path;
+use std::sync::OnceLock;
+
+use pipeline_service::parser::split_document_selector;
+use pipeline_service::ParseError;
+use roxid_tui::theme::{ColorChoice, Theme};
+
+/// `--color`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<ColorWhen> for ColorChoice {
+    fn from(when: ColorWhen) -> Self {
+        match when {
+            ColorWhen::Auto => ColorChoice::Auto,
+            ColorWhen::Always => ColorChoice::Always,
+            ColorWhen::Never => ColorChoice::Never,
+        }
+    }
+}
+
+/// The theme output is painted in, and whether it's painted at all
+struct Palette {
+    theme: Theme,
+    color: bool,
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+/// Set the theme and whether to color output, once at startup (`--color`,
+/// `NO_COLOR` and `[ui]` in .roxid.toml)
+pub fn init(theme: Theme, color: bool) {
+    let _ = PALETTE.set(Palette { theme, color });
+}
+
+fn palette() -> &'static Palette {
+    PALETTE.get_or_init(|| Palette {
+        theme: Theme::default(),
+        color: true,
+    })
+}
+
+/// Whether output is colored
+pub fn color_enabled() -> bool {
+    palette().color
+}
+
+/// What a piece of output means, which picks its theme color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Accent,
+    Success,
+    Failure,
+    Warning,
+    Heading,
+    Muted,
+    Plain,
+}
+
+/// `text` in the theme's color for `tone`, optionally bold
+pub fn paint(tone: Tone, bold: bool, text: &str) -> String {
+    let palette = palette();
+    if !palette.color {
+        return text.to_string();
+    }
+    let theme = &palette.theme;
+    let color = match tone {
+        Tone::Accent => Some(theme.accent),
+        Tone::Success => Some(theme.success),
+        Tone::Failure => Some(theme.failure),
+        Tone::Warning => Some(theme.warning),
+        Tone::Heading => Some(theme.heading),
+        Tone::Muted => Some(theme.muted),
+        Tone::Plain => None,
+    };
+    let mut codes = Vec::new();
+    if bold {
+        codes.push("1".to_string());
+    }
+    codes.extend(color.map(|c| c.ansi_fg()));
+    if codes.is_empty() {
+        return text.to_string();
+    }
+    format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+}
+
+/// `text` in bold
+pub fn bold(text: &str) -> String {
+    paint(Tone::Plain, true, text)
+}
+
 /// Print a status message: "  Status message"
 pub fn status(action: &str, message: &str) {
-    eprintln!("\x1b[1;36m{:>12}\x1b[0m {}", action, message);
+    eprintln!(
+        "{} {}",
+        paint(Tone::Accent, true, &format!("{:>12}", action)),
+        message
+    );
 }
 
 /// Print a success message with checkmark
 pub fn success(message: &str) {
-    eprintln!("\x1b[1;32m  \u{2713}\x1b[0m {}", message);
+    eprintln!("{} {}", paint(Tone::Success, true, "  \u{2713}"), message);
 }
 
 /// Print a failure message with X
 pub fn failure(message: &str) {
-    eprintln!("\x1b[1;31m  \u{2717}\x1b[0m {}", message);
+    eprintln!("{} {}", paint(Tone::Failure, true, "  \u{2717}"), message);
 }
 
 /// Print a check/pass item
 pub fn check(message: &str) {
-    eprintln!("\x1b[32m  \u{2713}\x1b[0m {}", message);
+    eprintln!("{} {}", paint(Tone::Success, false, "  \u{2713}"), message);
 }
 
 /// Print a warning message
 pub fn warning(message: &str) {
-    eprintln!("\x1b[33m  !\x1b[0m {}", message);
+    eprintln!("{} {}", paint(Tone::Warning, false, "  !"), message);
 }
 
 /// Print an error message
 pub fn error(message: &str) {
-    eprintln!("\x1b[1;31merror:\x1b[0m {}", message);
+    eprintln!("{} {}", paint(Tone::Failure, true, "error:"), message);
 }
 
 /// Print an info message
 pub fn info(message: &str) {
-    eprintln!("\x1b[36m  i\x1b[0m {}", message);
+    eprintln!("{} {}", paint(Tone::Accent, false, "  i"), message);
 }
 
 /// Print a dim/muted message
 pub fn dim(message: &str) {
-    eprintln!("\x1b[2m{}\x1b[0m", message);
+    eprintln!("{}", paint(Tone::Muted, false, message));
 }
 
 /// Print a dim success message
 pub fn dim_success(message: &str) {
-    eprintln!("\x1b[32m{}\x1b[0m", message);
+    eprintln!("{}", paint(Tone::Success, false, message));
 }
 
 /// Print a dim failure message
 pub fn dim_failure(message: &str) {
-    eprintln!("\x1b[31m{}\x1b[0m", message);
+    eprintln!("{}", paint(Tone::Failure, false, message));
 }
 
 /// Print a stage header
 pub fn stage_header(name: &str, total_jobs: usize) {
-    eprintln!("\x1b[1;34m  Stage\x1b[0m '{}' ({} jobs)", name, total_jobs);
+    eprintln!(
+        "{} '{}' ({} jobs)",
+        paint(Tone::Heading, true, "  Stage"),
+        name,
+        total_jobs
+    );
 }
 
 /// Print step output (indented)
-pub fn step_output(line: &str) {
-    println!("        | {}", line);
+pub fn step_output(line: &str, grouped: bool) {
+    if grouped {
+        println!("        |   {}", line);
+    } else {
+        println!("        | {}", line);
+    }
 }
 
 /// Print step error output (indented, red)
-pub fn step_error(line: &str) {
-    eprintln!("\x1b[31m        | {}\x1b[0m", line);
+pub fn step_error(line: &str, grouped: bool) {
+    let line = if grouped {
+        format!("        |   {}", line)
+    } else {
+        format!("        | {}", line)
+    };
+    eprintln!("{}", paint(Tone::Failure, false, &line));
+}
+
+/// Print the title of a grouped section of step output
+pub fn step_group(title: &str) {
+    println!("{}", bold(&format!("        | ▾ {}", title)));
+}
+
+/// Print a markdown step summary, bolding headings
+pub fn markdown(text: &str) {
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') {
+            println!(
+                "{}",
+                bold(&format!("  {}", trimmed.trim_start_matches('#').trim()))
+            );
+        } else if trimmed.is_empty() {
+            println!();
+        } else {
+            println!("  {}", line);
+        }
+    }
 }
 
 /// Print a header line
 pub fn header(message: &str) {
-    eprintln!("\x1b[1m==> {}\x1b[0m", message);
+    eprintln!("{}", bold(&format!("==> {}", message)));
+}
+
+/// Print a parse, template or expression error with the offending source lines
+///
+/// Errors inside templates name the template file; `pipeline` is the file
+/// being parsed, shown first in the include chain.
+pub fn parse_error(error: &ParseError, pipeline: &Path) {
+    let mut error = error.clone();
+    let file = error
+        .origin
+        .file
+        .clone()
+        .unwrap_or_else(|| split_document_selector(pipeline).0);
+    if error.origin.file.is_some() {
+        error
+            .origin
+            .include_chain
+            .insert(0, relative_display(pipeline));
+    }
+    let source = std::fs::read_to_string(&file).ok();
+    eprint!(
+        "{}",
+        error.render(&relative_display(&file), source.as_deref(), color_enabled())
+    );
+}
+
+/// A path relative to the current directory when it's inside it
+fn relative_display(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path)
+        .display()
+        .to_string()
 }
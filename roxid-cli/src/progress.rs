@@ -0,0 +1,121 @@
+use crate::output::{self, Tone};
+
+use std::io::Write;
+
+/// Cells in a progress bar
+const BAR_WIDTH: usize = 20;
+/// Longest a bar's line gets, so it doesn't wrap on an 80-column terminal
+const LINE_WIDTH: usize = 79;
+
+/// Progress that steps report with `##vso[task.setprogress]`
+///
+/// On a terminal each running step that reported progress gets a bar kept
+/// below the rest of the output; otherwise every update is printed as a line.
+pub struct StepProgress {
+    interactive: bool,
+    bars: Vec<Bar>,
+    /// Bars on screen, to erase before printing anything else
+    drawn: usize,
+}
+
+struct Bar {
+    /// `Stage/Job/N` of the step
+    id: String,
+    label: String,
+    /// None until the step reports progress
+    percent: Option<u8>,
+    operation: String,
+}
+
+impl StepProgress {
+    /// `interactive` draws bars, for a terminal
+    pub fn new(interactive: bool) -> Self {
+        Self {
+            interactive,
+            bars: Vec::new(),
+            drawn: 0,
+        }
+    }
+
+    /// A step started; its bar shows up once it reports progress
+    pub fn start(&mut self, id: &str, label: &str) {
+        self.finish(id);
+        self.bars.push(Bar {
+            id: id.to_string(),
+            label: label.to_string(),
+            percent: None,
+            operation: String::new(),
+        });
+    }
+
+    pub fn update(&mut self, id: &str, percent: u8, operation: &str) {
+        if !self.interactive {
+            output::dim(&format!("        [{:>3}%] {}", percent, operation));
+            return;
+        }
+        if let Some(bar) = self.bars.iter_mut().find(|bar| bar.id == id) {
+            bar.percent = Some(percent);
+            bar.operation = operation.to_string();
+        }
+    }
+
+    /// A step ended; its bar goes away
+    pub fn finish(&mut self, id: &str) {
+        self.bars.retain(|bar| bar.id != id);
+    }
+
+    /// Erase the bars so other output can be printed in their place
+    pub fn clear(&mut self) {
+        if self.drawn == 0 {
+            return;
+        }
+        let _ = std::io::stdout().flush();
+        let mut stderr = std::io::stderr();
+        // Up to the first bar, then clear to the end of the screen
+        let _ = write!(stderr, "\x1b[{}A\x1b[J", self.drawn);
+        let _ = stderr.flush();
+        self.drawn = 0;
+    }
+
+    /// Draw the bars below the output printed so far
+    pub fn draw(&mut self) {
+        if !self.interactive {
+            return;
+        }
+        let _ = std::io::stdout().flush();
+        for bar in &self.bars {
+            if let Some(line) = bar.render() {
+                eprintln!("{}", line);
+                self.drawn += 1;
+            }
+        }
+    }
+}
+
+impl Bar {
+    fn render(&self) -> Option<String> {
+        let percent = self.percent?;
+        let filled = BAR_WIDTH * percent as usize / 100;
+        let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled));
+        let prefix = format!("        {} {:>3}% ", bar, percent);
+        let text = if self.operation.is_empty() {
+            self.label.clone()
+        } else {
+            format!("{}: {}", self.label, self.operation)
+        };
+        let room = LINE_WIDTH.saturating_sub(prefix.chars().count());
+        let text = if text.chars().count() > room {
+            let mut text: String = text.chars().take(room.saturating_sub(1)).collect();
+            text.push('…');
+            text
+        } else {
+            text
+        };
+        Some(format!(
+            "        {} {:>3}% {}",
+            output::paint(Tone::Accent, false, &bar),
+            percent,
+            text
+        ))
+    }
+}
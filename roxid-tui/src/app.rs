@@ -1,16 +1,26 @@
 use crate::events::EventHandler;
+use crate::prefs::LayoutPrefs;
+use crate::theme::{ColorChoice, UiSettings};
 use crate::ui;
 
 use color_eyre::Result;
 use ratatui::DefaultTerminal;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
+
+use tokio::task::JoinHandle;
 
 use pipeline_service::execution::events::{progress_channel, ProgressReceiver};
+use pipeline_service::execution::executor::ExecutorConfig;
+use pipeline_service::execution::{
+    LocalVariables, LogLine, NotificationSettings, RoxidConfig, StageRerun, CONFIG_FILE,
+    LOCAL_VARS_FILE,
+};
 use pipeline_service::parser::models::{
-    ExecutionContext, JobStatus, StageStatus, StepStatus, Variable,
+    ExecutionContext, JobStatus, Stage, StageStatus, StepStatus, Variable,
 };
 use pipeline_service::utils::resolve_working_dir;
 use pipeline_service::{
@@ -36,6 +46,10 @@ pub enum AppState {
     TestResults,
     /// Edit variables and parameters before execution
     VariableEditor,
+    /// Markdown summaries published by executed steps
+    StepSummary,
+    /// Stages, jobs and steps of the run as bars on a timeline
+    Timeline,
 }
 
 // =============================================================================
@@ -57,16 +71,37 @@ pub struct App {
     pub execution_state: Option<ExecutionState>,
     pub event_receiver: Option<ProgressReceiver>,
     pub pending_execution: bool,
+    /// The running executor, which hands back the result kept for re-runs
+    pub execution_task: Option<JoinHandle<Option<ExecutionResult>>>,
+    /// Stage the next execution re-runs, with its dependents, instead of the whole pipeline
+    pub rerun_stage: Option<String>,
 
     // Log viewer state
     pub log_viewer: LogViewerState,
 
+    // Step summary view scroll position
+    pub summary_scroll: usize,
+
+    // Timeline view scroll position
+    pub timeline_scroll: usize,
+
     // Test results state
     pub test_state: Option<TestState>,
     pub pending_test_run: bool,
 
     // Variable editor state
     pub variable_editor: Option<VariableEditorState>,
+
+    // Developer overrides from .roxid/vars.yml
+    pub local_variables: Option<LocalVariables>,
+
+    // Desktop notifications from .roxid.toml [notifications]
+    pub notifications: NotificationSettings,
+
+    // Pane sizes from ~/.roxid/tui.yml
+    pub layout: LayoutPrefs,
+    /// The pane divider is being dragged with the mouse
+    pub dragging_split: bool,
 }
 
 // =============================================================================
@@ -149,8 +184,35 @@ pub struct ExecutionState {
     pub is_complete: bool,
     pub success: bool,
     pub duration: Option<Duration>,
-    #[allow(dead_code)]
+    /// When the run started; progress start offsets are relative to it
+    pub started_at: SystemTime,
+    /// Result of the finished run; a stage re-run reuses it for the other stages
     pub result: Option<ExecutionResult>,
+    /// Stage selected for a re-run once the run is complete
+    pub selected_stage: usize,
+    /// Group id of the currently open ##[group] section in step output
+    pub open_group: Option<usize>,
+    /// Number of output groups created so far (used to assign group ids)
+    pub group_count: usize,
+    /// Markdown summaries published by steps, in completion order
+    pub summaries: Vec<StepSummary>,
+    /// First output line shown while scrolled back; `None` follows the tail
+    pub output_scroll: Option<usize>,
+}
+
+impl ExecutionState {
+    /// How long after the run started an event's timestamp falls
+    pub fn offset(&self, at: SystemTime) -> Duration {
+        at.duration_since(self.started_at).unwrap_or_default()
+    }
+
+    /// Lines of the timeline view: one per stage, job and step
+    pub fn timeline_rows(&self) -> usize {
+        self.stages
+            .iter()
+            .map(|s| 1 + s.jobs.iter().map(|j| 1 + j.steps.len()).sum::<usize>())
+            .sum()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -159,6 +221,8 @@ pub struct StageProgress {
     pub display_name: Option<String>,
     pub status: StageStatus,
     pub jobs: Vec<JobProgress>,
+    /// Offset from the start of the run when it started
+    pub started: Option<Duration>,
     pub duration: Option<Duration>,
 }
 
@@ -168,6 +232,8 @@ pub struct JobProgress {
     pub display_name: Option<String>,
     pub status: JobStatus,
     pub steps: Vec<StepProgress>,
+    /// Offset from the start of the run when it started
+    pub started: Option<Duration>,
     pub duration: Option<Duration>,
 }
 
@@ -178,16 +244,26 @@ pub struct StepProgress {
     #[allow(dead_code)]
     pub display_name: Option<String>,
     pub status: StepStatus,
+    /// Offset from the start of the run when it started
+    pub started: Option<Duration>,
     pub duration: Option<Duration>,
     pub exit_code: Option<i32>,
 }
 
+#[derive(Debug, Clone)]
+pub struct StepSummary {
+    pub title: String,
+    pub markdown: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct OutputLine {
     pub text: String,
     pub kind: OutputKind,
     pub stage_name: Option<String>,
     pub job_name: Option<String>,
+    /// Collapsible output group this line belongs to (or heads)
+    pub group: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -201,6 +277,7 @@ pub enum OutputKind {
     StepHeader,
     StageHeader,
     JobHeader,
+    GroupHeader,
 }
 
 // =============================================================================
@@ -216,6 +293,7 @@ pub struct LogViewerState {
     pub current_match: usize,
     pub filter_stage: Option<String>,
     pub filter_job: Option<String>,
+    pub collapsed_groups: HashSet<usize>,
 }
 
 impl LogViewerState {
@@ -227,6 +305,7 @@ impl LogViewerState {
         self.current_match = 0;
         self.filter_stage = None;
         self.filter_job = None;
+        self.collapsed_groups.clear();
     }
 }
 
@@ -280,8 +359,36 @@ pub struct EditableParameter {
 // =============================================================================
 
 impl App {
-    pub fn new() -> Result<Self> {
-        let (pipelines, discovery_errors) = Self::discover_pipelines();
+    pub fn new(color: Option<ColorChoice>) -> Result<Self> {
+        let (pipelines, mut discovery_errors) = Self::discover_pipelines();
+        let local_variables = match LocalVariables::discover(&resolve_working_dir()) {
+            Ok(local) => local,
+            Err(error) => {
+                discovery_errors.push(DiscoveryError {
+                    file_name: LOCAL_VARS_FILE.to_string(),
+                    error,
+                });
+                None
+            }
+        };
+        let (notifications, ui_settings) = match RoxidConfig::discover(&resolve_working_dir())
+            .and_then(|config| Ok((config.notifications, UiSettings::from_config(&config)?)))
+        {
+            Ok(settings) => settings,
+            Err(error) => {
+                discovery_errors.push(DiscoveryError {
+                    file_name: CONFIG_FILE.to_string(),
+                    error,
+                });
+                (NotificationSettings::default(), UiSettings::default())
+            }
+        };
+        ui::theme::init(
+            ui_settings.theme.theme(),
+            color
+                .unwrap_or(ui_settings.color)
+                .enabled(std::io::stdout().is_terminal()),
+        );
         Ok(Self {
             state: AppState::PipelineList,
             previous_states: Vec::new(),
@@ -293,10 +400,18 @@ impl App {
             execution_state: None,
             event_receiver: None,
             pending_execution: false,
+            execution_task: None,
+            rerun_stage: None,
             log_viewer: LogViewerState::default(),
+            summary_scroll: 0,
+            timeline_scroll: 0,
             test_state: None,
             pending_test_run: false,
             variable_editor: None,
+            local_variables,
+            notifications,
+            layout: LayoutPrefs::load(),
+            dragging_split: false,
         })
     }
 
@@ -319,6 +434,7 @@ impl App {
 
             // Process execution events
             self.process_execution_events();
+            self.collect_execution_result().await;
         }
         Ok(())
     }
@@ -368,35 +484,60 @@ impl App {
                         .unwrap_or("unknown")
                         .to_string();
 
-                    match AzureParser::parse_file(&path) {
-                        Ok(raw_pipeline) => {
-                            let pipeline = normalize_pipeline(raw_pipeline);
-                            let name = pipeline.name.clone().unwrap_or_else(|| file_name.clone());
-
-                            let stages_count = pipeline.stages.len();
-                            let jobs_count: usize =
-                                pipeline.stages.iter().map(|s| s.jobs.len()).sum();
-                            let steps_count: usize = pipeline
-                                .stages
-                                .iter()
-                                .flat_map(|s| &s.jobs)
-                                .map(|j| j.steps.len())
-                                .sum();
-
-                            pipelines.push(PipelineInfo {
-                                name,
-                                path: path.clone(),
-                                pipeline,
-                                stages_count,
-                                jobs_count,
-                                steps_count,
-                            });
-                        }
-                        Err(e) => {
-                            errors.push(DiscoveryError {
-                                file_name,
-                                error: e.message,
-                            });
+                    // A file holding several pipelines lists each as `file.yml#<index>`
+                    let documents = std::fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|content| AzureParser::parse_documents(&content).ok())
+                        .filter(|documents| documents.len() > 1);
+                    let parsed = match documents {
+                        Some(documents) => documents
+                            .into_iter()
+                            .map(|document| {
+                                let label = format!("{}#{}", file_name, document.index);
+                                let path =
+                                    PathBuf::from(format!("{}#{}", path.display(), document.index));
+                                (path, label, Ok(document.pipeline))
+                            })
+                            .collect(),
+                        None => vec![(
+                            path.clone(),
+                            file_name.clone(),
+                            AzureParser::parse_file(&path),
+                        )],
+                    };
+
+                    for (path, file_name, parsed) in parsed {
+                        match parsed {
+                            Ok(raw_pipeline) => {
+                                let pipeline = normalize_pipeline(raw_pipeline);
+                                let name =
+                                    pipeline.name.clone().unwrap_or_else(|| file_name.clone());
+
+                                let stages_count = pipeline.stages.len();
+                                let jobs_count: usize =
+                                    pipeline.stages.iter().map(|s| s.jobs.len()).sum();
+                                let steps_count: usize = pipeline
+                                    .stages
+                                    .iter()
+                                    .flat_map(|s| &s.jobs)
+                                    .map(|j| j.steps.len())
+                                    .sum();
+
+                                pipelines.push(PipelineInfo {
+                                    name,
+                                    path: path.clone(),
+                                    pipeline,
+                                    stages_count,
+                                    jobs_count,
+                                    steps_count,
+                                });
+                            }
+                            Err(e) => {
+                                errors.push(DiscoveryError {
+                                    file_name,
+                                    error: e.message,
+                                });
+                            }
                         }
                     }
                 }
@@ -411,6 +552,7 @@ impl App {
     // Navigation
     // =========================================================================
 
+    #[allow(clippy::collapsible_match)]
     pub fn move_up(&mut self) {
         match self.state {
             AppState::PipelineList => {
@@ -442,10 +584,21 @@ impl App {
                     self.log_viewer.scroll_offset -= 1;
                 }
             }
-            _ => {}
+            AppState::StepSummary => {
+                self.summary_scroll = self.summary_scroll.saturating_sub(1);
+            }
+            AppState::Timeline => {
+                self.timeline_scroll = self.timeline_scroll.saturating_sub(1);
+            }
+            AppState::ExecutingPipeline => {
+                if let Some(exec) = &mut self.execution_state {
+                    exec.selected_stage = exec.selected_stage.saturating_sub(1);
+                }
+            }
         }
     }
 
+    #[allow(clippy::collapsible_match)]
     pub fn move_down(&mut self) {
         match self.state {
             AppState::PipelineList => {
@@ -483,7 +636,32 @@ impl App {
                     }
                 }
             }
-            _ => {}
+            AppState::StepSummary => {
+                if let Some(exec) = &self.execution_state {
+                    let max = exec
+                        .summaries
+                        .iter()
+                        .map(|s| s.markdown.lines().count() + 2)
+                        .sum::<usize>();
+                    if self.summary_scroll < max {
+                        self.summary_scroll += 1;
+                    }
+                }
+            }
+            AppState::Timeline => {
+                if let Some(exec) = &self.execution_state {
+                    if self.timeline_scroll < exec.timeline_rows().saturating_sub(1) {
+                        self.timeline_scroll += 1;
+                    }
+                }
+            }
+            AppState::ExecutingPipeline => {
+                if let Some(exec) = &mut self.execution_state {
+                    if exec.selected_stage < exec.stages.len().saturating_sub(1) {
+                        exec.selected_stage += 1;
+                    }
+                }
+            }
         }
     }
 
@@ -595,6 +773,81 @@ impl App {
         }
     }
 
+    // =========================================================================
+    // Mouse
+    // =========================================================================
+
+    /// Click on row `row` of the pipeline list: select it, or open it when
+    /// it's already selected
+    pub fn click_pipeline(&mut self, row: usize) {
+        if row >= self.pipelines.len() {
+            return;
+        }
+        if row == self.selected_index {
+            self.enter_pipeline_detail();
+        } else {
+            self.selected_index = row;
+        }
+    }
+
+    /// Click on row `row` of the pipeline tree: select it, or expand or
+    /// collapse it when it's already selected
+    pub fn click_tree_line(&mut self, row: usize) {
+        if row >= self.tree_state.total_lines {
+            return;
+        }
+        if row == self.tree_state.selected_line {
+            self.toggle_tree_node();
+        } else {
+            self.tree_state.selected_line = row;
+        }
+    }
+
+    /// Click on row `row` of the stage pane: select the stage on it or
+    /// owning the job on it
+    pub fn click_stage_row(&mut self, row: usize) {
+        let Some(exec) = &mut self.execution_state else {
+            return;
+        };
+        let mut first_row = 0;
+        for (index, stage) in exec.stages.iter().enumerate() {
+            let rows = 1 + stage.jobs.len();
+            if row < first_row + rows {
+                exec.selected_stage = index;
+                return;
+            }
+            first_row += rows;
+        }
+    }
+
+    /// Scroll the execution view's output pane back (negative) or forward;
+    /// reaching the end follows new output again
+    pub fn scroll_output(&mut self, lines: isize, visible_height: usize) {
+        let Some(exec) = &mut self.execution_state else {
+            return;
+        };
+        let tail = exec.output_lines.len().saturating_sub(visible_height);
+        let offset = exec
+            .output_scroll
+            .unwrap_or(tail)
+            .saturating_add_signed(lines)
+            .min(tail);
+        exec.output_scroll = (offset < tail).then_some(offset);
+    }
+
+    /// Move the pane divider to `percent` of the way across
+    pub fn resize_split(&mut self, percent: u16) {
+        self.layout = self.layout.with_split(percent);
+    }
+
+    /// Finish dragging the pane divider and remember where it was left
+    pub fn end_split_drag(&mut self) {
+        if self.dragging_split {
+            self.dragging_split = false;
+            self.layout.save();
+        }
+    }
+
     // =========================================================================
     // Variable Editor
     // =========================================================================
@@ -615,7 +868,14 @@ impl App {
                     readonly,
                 } => Some(EditableVariable {
                     name: name.clone(),
-                    value: value.clone(),
+                    // Start from the developer's override, which the run applies anyway
+                    value: self
+                        .local_variables
+                        .as_ref()
+                        .and_then(|local| local.variables.get(name))
+                        .filter(|_| !*readonly)
+                        .unwrap_or(value)
+                        .clone(),
                     readonly: *readonly,
                 }),
                 _ => None,
@@ -751,65 +1011,93 @@ impl App {
             }
         }
 
+        if let Some(local) = &self.local_variables {
+            local.apply_to(&mut variables);
+        }
+
         let context = ExecutionContext::new(pipeline_name.clone(), working_dir)
             .with_variables(variables)
             .with_parameters(parameters);
 
-        // Initialize execution state from the pipeline structure
-        let stages: Vec<StageProgress> = pipeline
-            .stages
-            .iter()
-            .map(|s| StageProgress {
-                name: s.stage.clone().unwrap_or_default(),
-                display_name: s.display_name.clone(),
-                status: StageStatus::Pending,
-                jobs: s
-                    .jobs
-                    .iter()
-                    .map(|j| JobProgress {
-                        name: j.identifier().unwrap_or("job").to_string(),
-                        display_name: j.display_name.clone(),
-                        status: JobStatus::Pending,
-                        steps: j
-                            .steps
-                            .iter()
-                            .map(|step| StepProgress {
-                                name: step.name.clone(),
-                                display_name: step.display_name.clone(),
-                                status: StepStatus::Pending,
-                                duration: None,
-                                exit_code: None,
-                            })
-                            .collect(),
-                        duration: None,
-                    })
-                    .collect(),
-                duration: None,
-            })
-            .collect();
+        // A re-run keeps the finished run's state and resets only the stages that run again
+        let rerun = match self.rerun_stage.take() {
+            Some(stage) => {
+                let Some(exec) = &mut self.execution_state else {
+                    return;
+                };
+                let Some(previous) = &exec.result else {
+                    return;
+                };
+                match StageRerun::new(&pipeline, &stage, previous) {
+                    Ok(rerun) => {
+                        for (progress, stage) in exec.stages.iter_mut().zip(&pipeline.stages) {
+                            if rerun.stages().contains(&progress.name) {
+                                *progress = stage_progress(stage);
+                            }
+                        }
+                        exec.is_complete = false;
+                        exec.success = false;
+                        exec.duration = None;
+                        exec.result = None;
+                        exec.output_lines.push(OutputLine {
+                            text: format!("Re-running: {}", rerun.stages().join(", ")),
+                            kind: OutputKind::Info,
+                            stage_name: None,
+                            job_name: None,
+                            group: None,
+                        });
+                        Some(rerun)
+                    }
+                    Err(e) => {
+                        exec.output_lines.push(OutputLine {
+                            text: format!("  ERROR: Cannot re-run stage '{}': {}", stage, e),
+                            kind: OutputKind::Error,
+                            stage_name: None,
+                            job_name: None,
+                            group: None,
+                        });
+                        return;
+                    }
+                }
+            }
+            None => None,
+        };
 
-        self.execution_state = Some(ExecutionState {
-            pipeline_name: pipeline_name.clone(),
-            stages,
-            output_lines: Vec::new(),
-            is_complete: false,
-            success: false,
-            duration: None,
-            result: None,
-        });
+        if rerun.is_none() {
+            // Initialize execution state from the pipeline structure
+            self.execution_state = Some(ExecutionState {
+                pipeline_name: pipeline_name.clone(),
+                stages: pipeline.stages.iter().map(stage_progress).collect(),
+                output_lines: Vec::new(),
+                is_complete: false,
+                success: false,
+                duration: None,
+                started_at: SystemTime::now(),
+                result: None,
+                selected_stage: 0,
+                open_group: None,
+                group_count: 0,
+                summaries: Vec::new(),
+                output_scroll: None,
+            });
 
-        self.push_state(AppState::ExecutingPipeline);
+            self.push_state(AppState::ExecutingPipeline);
+        }
 
         // Create progress channel and spawn executor
         let (tx, rx) = progress_channel();
         self.event_receiver = Some(rx);
 
-        tokio::spawn(async move {
+        self.execution_task = Some(tokio::spawn(async move {
             match PipelineExecutor::from_pipeline(&pipeline) {
                 Ok(executor) => {
-                    let executor = executor.with_progress(tx);
-                    let _result = executor.execute(context).await;
-                    // ExecutionResult events have already been sent through the channel
+                    let executor = executor
+                        .with_config(ExecutorConfig {
+                            rerun,
+                            ..Default::default()
+                        })
+                        .with_progress(tx);
+                    Some(executor.execute(context).await)
                 }
                 Err(e) => {
                     let _ = tx.send(ExecutionEvent::Error {
@@ -820,12 +1108,66 @@ impl App {
                     });
                     let _ = tx.send(ExecutionEvent::PipelineCompleted {
                         pipeline_name,
+                        run_id: context.run_id,
                         success: false,
                         duration: Duration::from_secs(0),
+                        finished_at: SystemTime::now(),
                     });
+                    None
                 }
             }
-        });
+        }));
+    }
+
+    /// Keep the result of a finished run so its stages can be re-run
+    async fn collect_execution_result(&mut self) {
+        if !self
+            .execution_task
+            .as_ref()
+            .is_some_and(|task| task.is_finished())
+        {
+            return;
+        }
+        let Some(task) = self.execution_task.take() else {
+            return;
+        };
+        if let (Ok(result), Some(exec)) = (task.await, &mut self.execution_state) {
+            exec.result = result;
+        }
+    }
+
+    /// Re-run the selected stage and the stages that depend on it
+    pub fn rerun_selected_stage(&mut self) {
+        let Some(exec) = &self.execution_state else {
+            return;
+        };
+        if !exec.is_complete || exec.result.is_none() {
+            return;
+        }
+        if let Some(stage) = exec.stages.get(exec.selected_stage) {
+            self.rerun_stage = Some(stage.name.clone());
+            self.request_execute_pipeline();
+        }
+    }
+
+    /// Edit variables, then re-run the selected stage with them (x in the editor)
+    pub fn edit_and_rerun_selected_stage(&mut self) {
+        let Some(exec) = &self.execution_state else {
+            return;
+        };
+        if !exec.is_complete || exec.result.is_none() {
+            return;
+        }
+        let Some(stage) = exec.stages.get(exec.selected_stage) else {
+            return;
+        };
+        self.rerun_stage = Some(stage.name.clone());
+        // Keep the values the run used, if they were edited
+        if self.variable_editor.is_some() {
+            self.push_state(AppState::VariableEditor);
+        } else {
+            self.open_variable_editor();
+        }
     }
 
     pub fn process_execution_events(&mut self) {
@@ -840,16 +1182,19 @@ impl App {
                 match &event {
                     ExecutionEvent::PipelineStarted {
                         pipeline_name,
+                        run_id,
                         total_stages,
+                        ..
                     } => {
                         exec.output_lines.push(OutputLine {
                             text: format!(
-                                "Pipeline '{}' started ({} stages)",
-                                pipeline_name, total_stages
+                                "Pipeline '{}' started ({} stages, run {})",
+                                pipeline_name, total_stages, run_id
                             ),
                             kind: OutputKind::Info,
                             stage_name: None,
                             job_name: None,
+                            group: None,
                         });
                     }
 
@@ -859,6 +1204,8 @@ impl App {
                         exec.is_complete = true;
                         exec.success = *success;
                         exec.duration = Some(*duration);
+                        self.notifications
+                            .notify_run(&exec.pipeline_name, *success, *duration);
 
                         let text = if *success {
                             format!(
@@ -878,6 +1225,7 @@ impl App {
                             kind,
                             stage_name: None,
                             job_name: None,
+                            group: None,
                         });
                         should_close = true;
                     }
@@ -886,10 +1234,13 @@ impl App {
                         stage_name,
                         display_name,
                         total_jobs,
+                        started_at,
                     } => {
+                        let offset = exec.offset(*started_at);
                         if let Some(stage) = exec.stages.iter_mut().find(|s| s.name == *stage_name)
                         {
                             stage.status = StageStatus::Running;
+                            stage.started = Some(offset);
                         }
                         let label = display_name.as_deref().unwrap_or(stage_name);
                         exec.output_lines.push(OutputLine {
@@ -897,6 +1248,7 @@ impl App {
                             kind: OutputKind::StageHeader,
                             stage_name: Some(stage_name.clone()),
                             job_name: None,
+                            group: None,
                         });
                     }
 
@@ -904,6 +1256,7 @@ impl App {
                         stage_name,
                         status,
                         duration,
+                        ..
                     } => {
                         if let Some(stage) = exec.stages.iter_mut().find(|s| s.name == *stage_name)
                         {
@@ -913,6 +1266,7 @@ impl App {
                         let symbol = match status {
                             StageStatus::Succeeded => "OK",
                             StageStatus::Failed => "FAIL",
+                            StageStatus::Canceled => "CANCELED",
                             _ => "DONE",
                         };
                         exec.output_lines.push(OutputLine {
@@ -929,6 +1283,7 @@ impl App {
                             },
                             stage_name: Some(stage_name.clone()),
                             job_name: None,
+                            group: None,
                         });
                     }
 
@@ -944,6 +1299,7 @@ impl App {
                             kind: OutputKind::Warning,
                             stage_name: Some(stage_name.clone()),
                             job_name: None,
+                            group: None,
                         });
                     }
 
@@ -952,12 +1308,15 @@ impl App {
                         job_name,
                         display_name,
                         total_steps,
+                        started_at,
                         ..
                     } => {
+                        let offset = exec.offset(*started_at);
                         if let Some(stage) = exec.stages.iter_mut().find(|s| s.name == *stage_name)
                         {
                             if let Some(job) = stage.jobs.iter_mut().find(|j| j.name == *job_name) {
                                 job.status = JobStatus::Running;
+                                job.started = Some(offset);
                             }
                         }
                         let label = display_name.as_deref().unwrap_or(job_name);
@@ -966,6 +1325,7 @@ impl App {
                             kind: OutputKind::JobHeader,
                             stage_name: Some(stage_name.clone()),
                             job_name: Some(job_name.clone()),
+                            group: None,
                         });
                     }
 
@@ -986,6 +1346,7 @@ impl App {
                         let symbol = match status {
                             JobStatus::Succeeded => "OK",
                             JobStatus::Failed => "FAIL",
+                            JobStatus::Canceled => "CANCELED",
                             _ => "DONE",
                         };
                         exec.output_lines.push(OutputLine {
@@ -1002,6 +1363,7 @@ impl App {
                             },
                             stage_name: Some(stage_name.clone()),
                             job_name: Some(job_name.clone()),
+                            group: None,
                         });
                     }
 
@@ -1021,6 +1383,7 @@ impl App {
                             kind: OutputKind::Warning,
                             stage_name: Some(stage_name.clone()),
                             job_name: Some(job_name.clone()),
+                            group: None,
                         });
                     }
 
@@ -1030,12 +1393,15 @@ impl App {
                         step_name,
                         display_name,
                         step_index,
+                        started_at,
                     } => {
+                        let offset = exec.offset(*started_at);
                         if let Some(stage) = exec.stages.iter_mut().find(|s| s.name == *stage_name)
                         {
                             if let Some(job) = stage.jobs.iter_mut().find(|j| j.name == *job_name) {
                                 if let Some(step) = job.steps.get_mut(*step_index) {
                                     step.status = StepStatus::Running;
+                                    step.started = Some(offset);
                                 }
                             }
                         }
@@ -1043,11 +1409,13 @@ impl App {
                             .as_deref()
                             .or(step_name.as_deref())
                             .unwrap_or("step");
+                        exec.open_group = None;
                         exec.output_lines.push(OutputLine {
                             text: format!("      [Step {}] {}", step_index + 1, label),
                             kind: OutputKind::StepHeader,
                             stage_name: Some(stage_name.clone()),
                             job_name: Some(job_name.clone()),
+                            group: None,
                         });
                     }
 
@@ -1059,16 +1427,48 @@ impl App {
                         ..
                     } => {
                         for line in output.lines() {
-                            exec.output_lines.push(OutputLine {
-                                text: format!("        {}", line),
-                                kind: if *is_error {
-                                    OutputKind::Error
-                                } else {
-                                    OutputKind::Output
-                                },
-                                stage_name: Some(stage_name.clone()),
-                                job_name: Some(job_name.clone()),
-                            });
+                            match LogLine::parse(line) {
+                                LogLine::GroupStart(title) => {
+                                    let id = exec.group_count;
+                                    exec.group_count += 1;
+                                    exec.open_group = Some(id);
+                                    exec.output_lines.push(OutputLine {
+                                        text: format!("        {}", title),
+                                        kind: OutputKind::GroupHeader,
+                                        stage_name: Some(stage_name.clone()),
+                                        job_name: Some(job_name.clone()),
+                                        group: Some(id),
+                                    });
+                                }
+                                LogLine::GroupEnd => exec.open_group = None,
+                                LogLine::Progress { percent, operation } => {
+                                    exec.output_lines.push(OutputLine {
+                                        text: format!("        [{:>3}%] {}", percent, operation),
+                                        kind: OutputKind::Info,
+                                        stage_name: Some(stage_name.clone()),
+                                        job_name: Some(job_name.clone()),
+                                        group: exec.open_group,
+                                    });
+                                }
+                                LogLine::Text(text) => {
+                                    let indent = if exec.open_group.is_some() {
+                                        "          "
+                                    } else {
+                                        "        "
+                                    };
+                                    exec.output_lines.push(OutputLine {
+                                        text: format!("{}{}", indent, text),
+                                        kind: if *is_error {
+                                            OutputKind::Error
+                                        } else {
+                                            OutputKind::Output
+                                        },
+                                        stage_name: Some(stage_name.clone()),
+                                        job_name: Some(job_name.clone()),
+                                        group: exec.open_group,
+                                    });
+                                }
+                            }
                         }
                     }
 
@@ -1094,6 +1494,7 @@ impl App {
                         let symbol = match status {
                             StepStatus::Succeeded => "OK",
                             StepStatus::Failed => "FAIL",
+                            StepStatus::Canceled => "CANCELED",
                             StepStatus::Skipped => "SKIP",
                             _ => "DONE",
                         };
@@ -1108,6 +1509,35 @@ impl App {
                             },
                             stage_name: Some(stage_name.clone()),
                             job_name: Some(job_name.clone()),
+                            group: None,
+                        });
+                    }
+
+                    ExecutionEvent::StepSummary {
+                        stage_name,
+                        job_name,
+                        step_name,
+                        step_index,
+                        markdown,
+                    } => {
+                        let title = format!(
+                            "{} / {} / {}",
+                            stage_name,
+                            job_name,
+                            step_name
+                                .clone()
+                                .unwrap_or_else(|| format!("Step {}", step_index + 1))
+                        );
+                        exec.output_lines.push(OutputLine {
+                            text: format!("        Summary published (press s to view): {}", title),
+                            kind: OutputKind::Info,
+                            stage_name: Some(stage_name.clone()),
+                            job_name: Some(job_name.clone()),
+                            group: None,
+                        });
+                        exec.summaries.push(StepSummary {
+                            title,
+                            markdown: markdown.clone(),
                         });
                     }
 
@@ -1132,6 +1562,7 @@ impl App {
                             kind: OutputKind::Warning,
                             stage_name: Some(stage_name.clone()),
                             job_name: Some(job_name.clone()),
+                            group: None,
                         });
                     }
 
@@ -1149,6 +1580,7 @@ impl App {
                             kind: OutputKind::Info,
                             stage_name: Some(stage_name.clone()),
                             job_name: Some(job_name.clone()),
+                            group: None,
                         });
                     }
 
@@ -1173,6 +1605,22 @@ impl App {
                             kind,
                             stage_name: stage_name.clone(),
                             job_name: job_name.clone(),
+                            group: None,
+                        });
+                    }
+
+                    ExecutionEvent::DiskQuotaExceeded {
+                        stage_name,
+                        job_name,
+                        message,
+                        ..
+                    } => {
+                        exec.output_lines.push(OutputLine {
+                            text: format!("        Disk quota exceeded: {}", message),
+                            kind: OutputKind::Error,
+                            stage_name: Some(stage_name.clone()),
+                            job_name: Some(job_name.clone()),
+                            group: None,
                         });
                     }
 
@@ -1187,6 +1635,7 @@ impl App {
                             kind: OutputKind::Error,
                             stage_name: stage_name.clone(),
                             job_name: job_name.clone(),
+                            group: None,
                         });
                     }
                 }
@@ -1209,6 +1658,24 @@ impl App {
         }
     }
 
+    pub fn open_step_summary(&mut self) {
+        if self
+            .execution_state
+            .as_ref()
+            .is_some_and(|exec| !exec.summaries.is_empty())
+        {
+            self.summary_scroll = 0;
+            self.push_state(AppState::StepSummary);
+        }
+    }
+
+    pub fn open_timeline(&mut self) {
+        if self.execution_state.is_some() {
+            self.timeline_scroll = 0;
+            self.push_state(AppState::Timeline);
+        }
+    }
+
     pub fn start_search(&mut self) {
         if self.state == AppState::ExecutionLog {
             self.log_viewer.search_active = true;
@@ -1249,6 +1716,31 @@ impl App {
         }
     }
 
+    /// Collapse or expand the output group at (or enclosing) the top visible log line
+    pub fn toggle_log_group(&mut self) {
+        let lines = self.filtered_output_lines();
+        let top = self
+            .log_viewer
+            .scroll_offset
+            .min(lines.len().saturating_sub(1));
+        let Some(group) = lines.iter().take(top + 1).rev().find_map(|line| line.group) else {
+            return;
+        };
+
+        if !self.log_viewer.collapsed_groups.remove(&group) {
+            self.log_viewer.collapsed_groups.insert(group);
+        }
+
+        // Keep the group's header at the top of the view
+        if let Some(header) = self
+            .filtered_output_lines()
+            .iter()
+            .position(|line| line.kind == OutputKind::GroupHeader && line.group == Some(group))
+        {
+            self.log_viewer.scroll_offset = header;
+        }
+    }
+
     fn update_search_matches(&mut self) {
         self.log_viewer.search_matches.clear();
         if let Some(exec) = &self.execution_state {
@@ -1314,7 +1806,7 @@ impl App {
         }
 
         let total_passed: usize = results.iter().map(|r| r.passed).sum();
-        let total_failed: usize = results.iter().map(|r| r.failed).sum();
+        let total_failed: usize = results.iter().map(|r| r.failed + r.errored).sum();
         let total_skipped: usize = results.iter().map(|r| r.skipped).sum();
 
         if let Some(test_state) = &mut self.test_state {
@@ -1355,6 +1847,14 @@ impl App {
                             }
                         }
                     }
+                    // Hide the body of collapsed groups, keeping their headers
+                    if let Some(group) = line.group {
+                        if line.kind != OutputKind::GroupHeader
+                            && self.log_viewer.collapsed_groups.contains(&group)
+                        {
+                            return false;
+                        }
+                    }
                     true
                 })
                 .collect()
@@ -1389,3 +1889,37 @@ impl App {
         }
     }
 }
+
+/// Pending progress for every job and step of a stage
+fn stage_progress(stage: &Stage) -> StageProgress {
+    StageProgress {
+        name: stage.stage.clone().unwrap_or_default(),
+        display_name: stage.display_name.clone(),
+        status: StageStatus::Pending,
+        jobs: stage
+            .jobs
+            .iter()
+            .map(|j| JobProgress {
+                name: j.identifier().unwrap_or("job").to_string(),
+                display_name: j.display_name.clone(),
+                status: JobStatus::Pending,
+                steps: j
+                    .steps
+                    .iter()
+                    .map(|step| StepProgress {
+                        name: step.name.clone(),
+                        display_name: step.display_name.clone(),
+                        status: StepStatus::Pending,
+                        started: None,
+                        duration: None,
+                        exit_code: None,
+                    })
+                    .collect(),
+                started: None,
+                duration: None,
+            })
+            .collect(),
+        started: None,
+        duration: None,
+    }
+}
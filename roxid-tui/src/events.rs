@@ -1,11 +1,19 @@
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::layout::{Position, Rect};
 
 use crate::app::{App, AppState};
+use crate::ui::layout;
+
+/// Lines the log moves per mouse wheel notch
+const WHEEL_LINES: usize = 3;
 
 pub trait EventHandler {
     fn handle_events(&mut self) -> Result<()>;
     fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()>;
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) -> Result<()>;
 }
 
 impl EventHandler for App {
@@ -15,12 +23,14 @@ impl EventHandler for App {
                 Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                     self.handle_key_event(key_event)?
                 }
+                Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event)?,
                 _ => {}
             }
         }
         Ok(())
     }
 
+    #[allow(clippy::collapsible_match)]
     fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
         // Handle search input mode first (captures all keys)
         if self.log_viewer.search_active {
@@ -93,7 +103,13 @@ impl EventHandler for App {
                         self.go_back();
                     }
                 }
+                KeyCode::Up | KeyCode::Char('k') => self.move_up(),
+                KeyCode::Down | KeyCode::Char('j') => self.move_down(),
                 KeyCode::Char('l') => self.open_log_viewer(),
+                KeyCode::Char('s') => self.open_step_summary(),
+                KeyCode::Char('g') => self.open_timeline(),
+                KeyCode::Char('r') => self.rerun_selected_stage(),
+                KeyCode::Char('e') => self.edit_and_rerun_selected_stage(),
                 _ => {}
             },
 
@@ -103,8 +119,10 @@ impl EventHandler for App {
                 KeyCode::Down | KeyCode::Char('j') => self.move_down(),
                 KeyCode::PageUp => self.page_up(),
                 KeyCode::PageDown => self.page_down(),
+                KeyCode::Enter | KeyCode::Char(' ') => self.toggle_log_group(),
                 KeyCode::Char('/') => self.start_search(),
                 KeyCode::Char('n') => self.next_search_match(),
+                KeyCode::Char('s') => self.open_step_summary(),
                 KeyCode::Home | KeyCode::Char('g') => {
                     self.log_viewer.scroll_offset = 0;
                 }
@@ -116,6 +134,24 @@ impl EventHandler for App {
                 _ => {}
             },
 
+            AppState::StepSummary => match key_event.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.go_back(),
+                KeyCode::Up | KeyCode::Char('k') => self.move_up(),
+                KeyCode::Down | KeyCode::Char('j') => self.move_down(),
+                KeyCode::PageUp => self.page_up(),
+                KeyCode::PageDown => self.page_down(),
+                _ => {}
+            },
+
+            AppState::Timeline => match key_event.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.go_back(),
+                KeyCode::Up | KeyCode::Char('k') => self.move_up(),
+                KeyCode::Down | KeyCode::Char('j') => self.move_down(),
+                KeyCode::PageUp => self.page_up(),
+                KeyCode::PageDown => self.page_down(),
+                _ => {}
+            },
+
             AppState::TestResults => match key_event.code {
                 KeyCode::Char('q') | KeyCode::Esc => self.go_back(),
                 KeyCode::Up | KeyCode::Char('k') => self.move_up(),
@@ -124,7 +160,10 @@ impl EventHandler for App {
             },
 
             AppState::VariableEditor => match key_event.code {
-                KeyCode::Char('q') | KeyCode::Esc => self.go_back(),
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    self.rerun_stage = None;
+                    self.go_back();
+                }
                 KeyCode::Up | KeyCode::Char('k') => self.move_up(),
                 KeyCode::Down | KeyCode::Char('j') => self.move_down(),
                 KeyCode::Enter => self.start_editing_variable(),
@@ -138,4 +177,109 @@ impl EventHandler for App {
         }
         Ok(())
     }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) -> Result<()> {
+        // Text entry keeps the keyboard's focus
+        if self.log_viewer.search_active || self.variable_editor.as_ref().is_some_and(|e| e.editing)
+        {
+            return Ok(());
+        }
+
+        let (width, height) = crossterm::terminal::size()?;
+        let screen = Rect::new(0, 0, width, height);
+        let at = Position::new(mouse_event.column, mouse_event.row);
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.click(screen, at),
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging_split => {
+                let main = layout::create_execution_layout(screen)[2];
+                let offset = at.x.saturating_sub(main.x) as u32;
+                let percent = (offset * 100 + main.width as u32 / 2) / main.width.max(1) as u32;
+                self.resize_split(percent as u16);
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.end_split_drag(),
+            MouseEventKind::ScrollUp => self.wheel(screen, at, false),
+            MouseEventKind::ScrollDown => self.wheel(screen, at, true),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl App {
+    fn click(&mut self, screen: Rect, at: Position) {
+        match self.state {
+            AppState::PipelineList => {
+                let main = if self.discovery_errors.is_empty() {
+                    layout::create_layout(screen)[1]
+                } else {
+                    layout::create_layout_with_errors(screen)[1]
+                };
+                if let Some(row) = list_row(main, at) {
+                    self.click_pipeline(row);
+                }
+            }
+            AppState::PipelineDetail => {
+                if let Some(row) = list_row(layout::create_layout(screen)[1], at) {
+                    self.click_tree_line(row);
+                }
+            }
+            AppState::ExecutingPipeline => {
+                let main = layout::create_execution_layout(screen)[2];
+                let panes = layout::create_execution_panes(main, self.layout.split_percent);
+                // The divider is the stage pane's right border and the output
+                // pane's left one
+                let on_divider =
+                    (at.x + 1 == panes[0].right() || at.x == panes[1].x) && main.contains(at);
+                if on_divider {
+                    self.dragging_split = true;
+                } else if let Some(row) = list_row(panes[0], at) {
+                    if self.execution_state.as_ref().is_some_and(|e| e.is_complete) {
+                        self.click_stage_row(row);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn wheel(&mut self, screen: Rect, at: Position, down: bool) {
+        match self.state {
+            AppState::ExecutingPipeline => {
+                let main = layout::create_execution_layout(screen)[2];
+                let output = layout::create_execution_panes(main, self.layout.split_percent)[1];
+                if output.contains(at) {
+                    let lines = WHEEL_LINES as isize;
+                    let lines = if down { lines } else { -lines };
+                    self.scroll_output(lines, output.height.saturating_sub(2) as usize);
+                    return;
+                }
+                self.wheel_move(down, 1);
+            }
+            AppState::ExecutionLog | AppState::StepSummary | AppState::Timeline => {
+                self.wheel_move(down, WHEEL_LINES)
+            }
+            _ => self.wheel_move(down, 1),
+        }
+    }
+
+    fn wheel_move(&mut self, down: bool, lines: usize) {
+        for _ in 0..lines {
+            if down {
+                self.move_down();
+            } else {
+                self.move_up();
+            }
+        }
+    }
+}
+
+/// The item row of a bordered list at `at`, if it's inside the list
+fn list_row(area: Rect, at: Position) -> Option<usize> {
+    let inner = Rect::new(
+        area.x + 1,
+        area.y + 1,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+    );
+    inner.contains(at).then(|| (at.y - inner.y) as usize)
 }
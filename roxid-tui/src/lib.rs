@@ -1,13 +1,20 @@
 mod app;
 mod events;
+mod prefs;
+pub mod theme;
 mod ui;
 
 use app::App;
 use color_eyre::Result;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use theme::ColorChoice;
 
-pub async fn run() -> Result<()> {
+/// Run the TUI; `color` overrides the `[ui]` color setting in `.roxid.toml`
+pub async fn run(color: Option<ColorChoice>) -> Result<()> {
     let terminal = ratatui::init();
-    let result = App::new()?.run(terminal).await;
+    crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+    let result = App::new(color)?.run(terminal).await;
+    let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
     ratatui::restore();
     result
 }
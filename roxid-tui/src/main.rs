@@ -3,5 +3,5 @@ use color_eyre::Result;
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
-    roxid_tui::run().await
+    roxid_tui::run(None).await
 }
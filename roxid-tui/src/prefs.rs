@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use std::path::PathBuf;
+
+/// Narrowest and widest the stage pane can be dragged, in percent
+const SPLIT_RANGE: (u16, u16) = (10, 90);
+
+/// Layout choices the TUI remembers between sessions, in `~/.roxid/tui.yml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LayoutPrefs {
+    /// Width of the stage pane beside the output pane, in percent
+    pub split_percent: u16,
+}
+
+impl Default for LayoutPrefs {
+    fn default() -> Self {
+        Self { split_percent: 30 }
+    }
+}
+
+impl LayoutPrefs {
+    pub fn path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".roxid")
+            .join("tui.yml")
+    }
+
+    /// The saved preferences; defaults when there are none or they don't parse
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_yaml::from_str::<Self>(&content).ok())
+            .map(|prefs| prefs.with_split(prefs.split_percent))
+            .unwrap_or_default()
+    }
+
+    /// Save for the next session; a layout isn't worth an error if that fails
+    pub fn save(&self) {
+        let path = Self::path();
+        if let (Some(dir), Ok(yaml)) = (path.parent(), serde_yaml::to_string(self)) {
+            let _ = std::fs::create_dir_all(dir);
+            let _ = std::fs::write(path, yaml);
+        }
+    }
+
+    pub fn with_split(self, percent: u16) -> Self {
+        Self {
+            split_percent: percent.clamp(SPLIT_RANGE.0, SPLIT_RANGE.1),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// Color Themes
+// Named palettes shared by the CLI and TUI, and whether to color output at all (NO_COLOR, --color)
+
+use std::ffi::OsString;
+
+use pipeline_service::execution::{RoxidConfig, CONFIG_FILE};
+use serde::Deserialize;
+
+/// Colors and themes for the CLI and TUI (`[ui]` in `.roxid.toml`)
+///
+/// ```toml
+/// [ui]
+/// theme = "high-contrast"
+/// color = "auto"
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields, default)]
+pub struct UiSettings {
+    pub theme: ThemeName,
+    /// `--color` takes precedence
+    pub color: ColorChoice,
+}
+
+impl UiSettings {
+    /// The `[ui]` section of a project configuration
+    pub fn from_config(config: &RoxidConfig) -> Result<Self, String> {
+        toml::Value::Table(config.ui.clone())
+            .try_into()
+            .map_err(|e| format!("Invalid [ui] in {}: {}", CONFIG_FILE, e))
+    }
+}
+
+/// When to color output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorChoice {
+    /// On a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output written to a terminal (or not)
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        self.resolve(is_terminal, std::env::var_os("NO_COLOR"))
+    }
+
+    fn resolve(self, is_terminal: bool, no_color: Option<OsString>) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            // https://no-color.org: set and not empty
+            Self::Auto => is_terminal && no_color.is_none_or(|value| value.is_empty()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Default,
+    /// Bright colors on the terminal's background, nothing dim
+    HighContrast,
+    Solarized,
+}
+
+impl ThemeName {
+    pub fn theme(self) -> Theme {
+        match self {
+            Self::Default => Theme::default(),
+            Self::HighContrast => Theme::high_contrast(),
+            Self::Solarized => Theme::solarized(),
+        }
+    }
+}
+
+/// A color from the terminal's palette, or an exact one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    DarkGray,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    White,
+    Rgb(u8, u8, u8),
+}
+
+impl ThemeColor {
+    /// The SGR parameters that set this as the foreground color
+    pub fn ansi_fg(self) -> String {
+        let code = match self {
+            Self::Black => 30,
+            Self::Red => 31,
+            Self::Green => 32,
+            Self::Yellow => 33,
+            Self::Blue => 34,
+            Self::Magenta => 35,
+            Self::Cyan => 36,
+            Self::Gray => 37,
+            Self::DarkGray => 90,
+            Self::LightRed => 91,
+            Self::LightGreen => 92,
+            Self::LightYellow => 93,
+            Self::LightBlue => 94,
+            Self::LightMagenta => 95,
+            Self::LightCyan => 96,
+            Self::White => 97,
+            Self::Rgb(r, g, b) => return format!("38;2;{};{};{}", r, g, b),
+        };
+        code.to_string()
+    }
+}
+
+/// Colors by what they mean, so the CLI and TUI agree on them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Titles, borders, actions in progress, the selection
+    pub accent: ThemeColor,
+    pub success: ThemeColor,
+    pub failure: ThemeColor,
+    pub warning: ThemeColor,
+    /// Stage names and markdown headings
+    pub heading: ThemeColor,
+    /// Output groups and summaries
+    pub emphasis: ThemeColor,
+    /// Plain text
+    pub text: ThemeColor,
+    /// Help lines and informational output
+    pub secondary: ThemeColor,
+    /// Durations, counts and pending or skipped items
+    pub muted: ThemeColor,
+    /// Text on the accent color, for the selection
+    pub selection: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: ThemeColor::Cyan,
+            success: ThemeColor::Green,
+            failure: ThemeColor::Red,
+            warning: ThemeColor::Yellow,
+            heading: ThemeColor::Yellow,
+            emphasis: ThemeColor::Magenta,
+            text: ThemeColor::White,
+            secondary: ThemeColor::Gray,
+            muted: ThemeColor::DarkGray,
+            selection: ThemeColor::Black,
+        }
+    }
+}
+
+impl Theme {
+    pub fn high_contrast() -> Self {
+        Self {
+            accent: ThemeColor::LightCyan,
+            success: ThemeColor::LightGreen,
+            failure: ThemeColor::LightRed,
+            warning: ThemeColor::LightYellow,
+            heading: ThemeColor::LightYellow,
+            emphasis: ThemeColor::LightMagenta,
+            text: ThemeColor::White,
+            secondary: ThemeColor::White,
+            muted: ThemeColor::Gray,
+            selection: ThemeColor::Black,
+        }
+    }
+
+    /// Ethan Schoonover's Solarized accents over its dark base tones
+    pub fn solarized() -> Self {
+        Self {
+            accent: ThemeColor::Rgb(0x26, 0x8b, 0xd2),
+            success: ThemeColor::Rgb(0x85, 0x99, 0x00),
+            failure: ThemeColor::Rgb(0xdc, 0x32, 0x2f),
+            warning: ThemeColor::Rgb(0xcb, 0x4b, 0x16),
+            heading: ThemeColor::Rgb(0xb5, 0x89, 0x00),
+            emphasis: ThemeColor::Rgb(0x6c, 0x71, 0xc4),
+            text: ThemeColor::Rgb(0x93, 0xa1, 0xa1),
+            secondary: ThemeColor::Rgb(0x83, 0x94, 0x96),
+            muted: ThemeColor::Rgb(0x58, 0x6e, 0x75),
+            selection: ThemeColor::Rgb(0x00, 0x2b, 0x36),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice() {
+        assert!(ColorChoice::Auto.resolve(true, None));
+        assert!(!ColorChoice::Auto.resolve(false, None));
+        assert!(!ColorChoice::Auto.resolve(true, Some("1".into())));
+        assert!(ColorChoice::Auto.resolve(true, Some("".into())));
+        assert!(ColorChoice::Always.resolve(false, Some("1".into())));
+        assert!(!ColorChoice::Never.resolve(true, None));
+    }
+
+    #[test]
+    fn test_ui_settings() {
+        let settings = |toml: &str| UiSettings::from_config(&RoxidConfig::parse(toml).unwrap());
+        let ui = settings("[ui]\ntheme = \"solarized\"\ncolor = \"never\"\n").unwrap();
+        assert_eq!(ui.theme, ThemeName::Solarized);
+        assert_eq!(ui.color, ColorChoice::Never);
+        assert_eq!(ui.theme.theme().accent.ansi_fg(), "38;2;38;139;210");
+        assert_eq!(settings("").unwrap().theme.theme(), Theme::default());
+        assert!(settings("[ui]\ntheme = \"neon\"\n").is_err());
+    }
+}
@@ -2,9 +2,13 @@ pub mod components;
 pub mod execution;
 pub mod layout;
 pub mod log_viewer;
+pub mod markdown;
 pub mod pipeline_list;
 pub mod pipeline_tree;
+pub mod step_summary;
 pub mod test_results;
+pub mod theme;
+pub mod timeline;
 
 use ratatui::Frame;
 
@@ -17,6 +21,8 @@ pub fn render(app: &App, frame: &mut Frame) {
         AppState::ExecutingPipeline => execution::render(app, frame),
         AppState::ExecutionLog => log_viewer::render(app, frame),
         AppState::TestResults => test_results::render(app, frame),
+        AppState::StepSummary => step_summary::render(app, frame),
+        AppState::Timeline => timeline::render(app, frame),
         AppState::VariableEditor => render_variable_editor(app, frame),
     }
 }
@@ -29,7 +35,7 @@ fn render_variable_editor(app: &App, frame: &mut Frame) {
     if let Some(editor) = &app.variable_editor {
         use ratatui::{
             layout::{Constraint, Direction, Layout},
-            style::{Color, Modifier, Style},
+            style::Style,
             text::{Line, Span},
             widgets::{Block, Borders, List, ListItem},
         };
@@ -58,22 +64,19 @@ fn render_variable_editor(app: &App, frame: &mut Frame) {
                     };
 
                 let style = if is_selected {
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD)
+                    theme::selected()
                 } else if var.readonly {
-                    Style::default().fg(Color::DarkGray)
+                    Style::default().fg(theme::muted())
                 } else {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme::text())
                 };
 
                 ListItem::new(Line::from(vec![
                     Span::styled(indicator, style),
                     Span::styled(&var.name, style),
-                    Span::styled(" = ", Style::default().fg(Color::Gray)),
+                    Span::styled(" = ", Style::default().fg(theme::secondary())),
                     Span::styled(value_display, style),
-                    Span::styled(readonly_marker, Style::default().fg(Color::DarkGray)),
+                    Span::styled(readonly_marker, Style::default().fg(theme::muted())),
                 ]))
             })
             .collect();
@@ -82,7 +85,7 @@ fn render_variable_editor(app: &App, frame: &mut Frame) {
             Block::default()
                 .borders(Borders::ALL)
                 .title("Variables")
-                .border_style(Style::default().fg(Color::Cyan)),
+                .border_style(Style::default().fg(theme::accent())),
         );
         frame.render_widget(var_list, sections[0]);
 
@@ -107,12 +110,9 @@ fn render_variable_editor(app: &App, frame: &mut Frame) {
                 let label = param.display_name.as_deref().unwrap_or(&param.name);
 
                 let style = if is_selected {
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD)
+                    theme::selected()
                 } else {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme::text())
                 };
 
                 let default_info = param
@@ -124,9 +124,9 @@ fn render_variable_editor(app: &App, frame: &mut Frame) {
                 ListItem::new(Line::from(vec![
                     Span::styled(indicator, style),
                     Span::styled(label, style),
-                    Span::styled(" = ", Style::default().fg(Color::Gray)),
+                    Span::styled(" = ", Style::default().fg(theme::secondary())),
                     Span::styled(value_display, style),
-                    Span::styled(default_info, Style::default().fg(Color::DarkGray)),
+                    Span::styled(default_info, Style::default().fg(theme::muted())),
                 ]))
             })
             .collect();
@@ -135,13 +135,13 @@ fn render_variable_editor(app: &App, frame: &mut Frame) {
             Block::default()
                 .borders(Borders::ALL)
                 .title("Parameters")
-                .border_style(Style::default().fg(Color::Magenta)),
+                .border_style(Style::default().fg(theme::emphasis())),
         );
         frame.render_widget(param_list, sections[1]);
     } else {
         use ratatui::widgets::Paragraph;
         let msg = Paragraph::new("No pipeline selected")
-            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Yellow));
+            .style(ratatui::style::Style::default().fg(theme::warning()));
         frame.render_widget(msg, chunks[1]);
     }
 
@@ -1,18 +1,19 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
 use crate::app::DiscoveryError;
+use crate::ui::theme;
 
 pub fn render_header(title: &str, frame: &mut Frame, area: Rect) {
     let header = Paragraph::new(title)
         .style(
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme::accent())
                 .add_modifier(Modifier::BOLD),
         )
         .block(Block::default().borders(Borders::ALL));
@@ -21,7 +22,7 @@ pub fn render_header(title: &str, frame: &mut Frame, area: Rect) {
 
 pub fn render_footer(text: &str, frame: &mut Frame, area: Rect) {
     let footer = Paragraph::new(text)
-        .style(Style::default().fg(Color::Gray))
+        .style(Style::default().fg(theme::secondary()))
         .block(Block::default().borders(Borders::ALL).title("Help"));
     frame.render_widget(footer, area);
 }
@@ -38,13 +39,15 @@ pub fn render_discovery_errors(errors: &[DiscoveryError], frame: &mut Frame, are
                 Line::from(vec![
                     Span::styled(
                         "x ",
-                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                        Style::default()
+                            .fg(theme::failure())
+                            .add_modifier(Modifier::BOLD),
                     ),
-                    Span::styled(&err.file_name, Style::default().fg(Color::Yellow)),
+                    Span::styled(&err.file_name, Style::default().fg(theme::warning())),
                 ]),
                 Line::from(vec![
                     Span::raw("  "),
-                    Span::styled(&err.error, Style::default().fg(Color::Gray)),
+                    Span::styled(&err.error, Style::default().fg(theme::secondary())),
                 ]),
                 Line::from(""),
             ]
@@ -55,7 +58,7 @@ pub fn render_discovery_errors(errors: &[DiscoveryError], frame: &mut Frame, are
         Block::default()
             .borders(Borders::ALL)
             .title("Discovery Errors")
-            .border_style(Style::default().fg(Color::Red)),
+            .border_style(Style::default().fg(theme::failure())),
     );
 
     frame.render_widget(error_widget, area);
@@ -65,9 +68,9 @@ pub fn render_discovery_errors(errors: &[DiscoveryError], frame: &mut Frame, are
 #[allow(dead_code)]
 pub fn status_style(succeeded: bool) -> Style {
     if succeeded {
-        Style::default().fg(Color::Green)
+        Style::default().fg(theme::success())
     } else {
-        Style::default().fg(Color::Red)
+        Style::default().fg(theme::failure())
     }
 }
 
@@ -1,5 +1,4 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Wrap},
@@ -7,7 +6,7 @@ use ratatui::{
 };
 
 use crate::app::{App, OutputKind, StageProgress};
-use crate::ui::{components, layout};
+use crate::ui::{components, layout, theme};
 
 use pipeline_service::parser::models::{JobStatus, StageStatus};
 
@@ -49,7 +48,10 @@ pub fn render(app: &App, frame: &mut Frame) {
             .filter(|s| {
                 matches!(
                     s.status,
-                    StageStatus::Succeeded | StageStatus::Failed | StageStatus::Skipped
+                    StageStatus::Succeeded
+                        | StageStatus::Failed
+                        | StageStatus::Skipped
+                        | StageStatus::Canceled
                 )
             })
             .count();
@@ -58,12 +60,12 @@ pub fn render(app: &App, frame: &mut Frame) {
 
     let gauge_color = if exec.is_complete {
         if exec.success {
-            Color::Green
+            theme::success()
         } else {
-            Color::Red
+            theme::failure()
         }
     } else {
-        Color::Cyan
+        theme::accent()
     };
 
     let gauge = Gauge::default()
@@ -74,30 +76,39 @@ pub fn render(app: &App, frame: &mut Frame) {
     frame.render_widget(gauge, chunks[1]);
 
     // Split main area: stage list on left, output on right
-    let main_sections = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
-        .split(chunks[2]);
+    let main_sections = layout::create_execution_panes(chunks[2], app.layout.split_percent);
 
     // Stage/job progress panel
-    render_stage_panel(&exec.stages, frame, main_sections[0]);
+    let selected = exec.is_complete.then_some(exec.selected_stage);
+    render_stage_panel(&exec.stages, selected, frame, main_sections[0]);
 
     // Output panel
-    render_output_panel(&exec.output_lines, frame, main_sections[1]);
+    render_output_panel(
+        &exec.output_lines,
+        exec.output_scroll,
+        frame,
+        main_sections[1],
+    );
 
     // Footer
-    let footer = if exec.is_complete {
-        "l: View Logs | q/Esc: Back"
-    } else {
-        "l: View Logs | Executing..."
+    let footer = match (exec.is_complete, exec.summaries.is_empty()) {
+        (true, true) => "j/k: Select Stage | r: Re-run Stage | e: Edit Vars & Re-run | l: View Logs | g: Timeline | q/Esc: Back",
+        (true, false) => "j/k: Select Stage | r: Re-run Stage | e: Edit Vars & Re-run | l: View Logs | g: Timeline | s: Step Summaries | q/Esc: Back",
+        (false, true) => "l: View Logs | g: Timeline | Executing...",
+        (false, false) => "l: View Logs | g: Timeline | s: Step Summaries | Executing...",
     };
     components::render_footer(footer, frame, chunks[3]);
 }
 
-fn render_stage_panel(stages: &[StageProgress], frame: &mut Frame, area: ratatui::layout::Rect) {
+fn render_stage_panel(
+    stages: &[StageProgress],
+    selected: Option<usize>,
+    frame: &mut Frame,
+    area: ratatui::layout::Rect,
+) {
     let mut items: Vec<ListItem> = Vec::new();
 
-    for stage in stages {
+    for (index, stage) in stages.iter().enumerate() {
         let (symbol, color) = stage_status_display(&stage.status);
         let duration_str = stage
             .duration
@@ -105,14 +116,19 @@ fn render_stage_panel(stages: &[StageProgress], frame: &mut Frame, area: ratatui
             .unwrap_or_default();
         let stage_label = stage.display_name.as_deref().unwrap_or(&stage.name);
 
-        items.push(ListItem::new(Line::from(vec![
+        let line = Line::from(vec![
             Span::styled(format!("{} ", symbol), Style::default().fg(color)),
             Span::styled(
                 stage_label,
                 Style::default().fg(color).add_modifier(Modifier::BOLD),
             ),
-            Span::styled(duration_str, Style::default().fg(Color::DarkGray)),
-        ])));
+            Span::styled(duration_str, Style::default().fg(theme::muted())),
+        ]);
+        items.push(if selected == Some(index) {
+            ListItem::new(line).style(Style::default().add_modifier(Modifier::REVERSED))
+        } else {
+            ListItem::new(line)
+        });
 
         // Show jobs under the stage
         for job in &stage.jobs {
@@ -127,7 +143,7 @@ fn render_stage_panel(stages: &[StageProgress], frame: &mut Frame, area: ratatui
                 Span::raw("  "),
                 Span::styled(format!("{} ", job_sym), Style::default().fg(job_color)),
                 Span::styled(job_label, Style::default().fg(job_color)),
-                Span::styled(job_dur, Style::default().fg(Color::DarkGray)),
+                Span::styled(job_dur, Style::default().fg(theme::muted())),
             ])));
         }
     }
@@ -136,38 +152,43 @@ fn render_stage_panel(stages: &[StageProgress], frame: &mut Frame, area: ratatui
         Block::default()
             .borders(Borders::ALL)
             .title("Stages")
-            .border_style(Style::default().fg(Color::Cyan)),
+            .border_style(Style::default().fg(theme::accent())),
     );
     frame.render_widget(list, area);
 }
 
+/// The last lines of output, or from `scroll` while scrolled back
 fn render_output_panel(
     lines: &[crate::app::OutputLine],
+    scroll: Option<usize>,
     frame: &mut Frame,
     area: ratatui::layout::Rect,
 ) {
     let visible_height = area.height.saturating_sub(2) as usize;
-    let start = lines.len().saturating_sub(visible_height);
+    let tail = lines.len().saturating_sub(visible_height);
+    let start = scroll.map_or(tail, |offset| offset.min(tail));
 
     let visible_lines: Vec<Line> = lines
         .iter()
         .skip(start)
         .map(|line| {
             let color = match line.kind {
-                OutputKind::Success => Color::Green,
-                OutputKind::Failure => Color::Red,
-                OutputKind::Error => Color::Red,
-                OutputKind::Warning => Color::Yellow,
-                OutputKind::StageHeader => Color::Yellow,
-                OutputKind::JobHeader => Color::Green,
-                OutputKind::StepHeader => Color::Cyan,
-                OutputKind::Info => Color::Gray,
-                OutputKind::Output => Color::White,
+                OutputKind::Success => theme::success(),
+                OutputKind::Failure => theme::failure(),
+                OutputKind::Error => theme::failure(),
+                OutputKind::Warning => theme::warning(),
+                OutputKind::StageHeader => theme::heading(),
+                OutputKind::JobHeader => theme::success(),
+                OutputKind::StepHeader => theme::accent(),
+                OutputKind::Info => theme::secondary(),
+                OutputKind::Output => theme::text(),
+                OutputKind::GroupHeader => theme::emphasis(),
             };
             let modifier = match line.kind {
-                OutputKind::StageHeader | OutputKind::Success | OutputKind::Failure => {
-                    Modifier::BOLD
-                }
+                OutputKind::StageHeader
+                | OutputKind::Success
+                | OutputKind::Failure
+                | OutputKind::GroupHeader => Modifier::BOLD,
                 _ => Modifier::empty(),
             };
             Line::from(Span::styled(
@@ -177,32 +198,37 @@ fn render_output_panel(
         })
         .collect();
 
+    let title = if start < tail {
+        "Output (scrolled back)"
+    } else {
+        "Output"
+    };
     let output = Paragraph::new(visible_lines)
-        .block(Block::default().borders(Borders::ALL).title("Output"))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .wrap(Wrap { trim: false });
     frame.render_widget(output, area);
 }
 
-fn stage_status_display(status: &StageStatus) -> (&str, Color) {
+pub fn stage_status_display(status: &StageStatus) -> (&str, Color) {
     match status {
-        StageStatus::Pending => (".", Color::DarkGray),
-        StageStatus::Running => ("~", Color::Cyan),
-        StageStatus::Succeeded => ("O", Color::Green),
-        StageStatus::SucceededWithIssues => ("!", Color::Yellow),
-        StageStatus::Failed => ("X", Color::Red),
-        StageStatus::Canceled => ("-", Color::DarkGray),
-        StageStatus::Skipped => ("-", Color::DarkGray),
+        StageStatus::Pending => (".", theme::muted()),
+        StageStatus::Running => ("~", theme::accent()),
+        StageStatus::Succeeded => ("O", theme::success()),
+        StageStatus::SucceededWithIssues => ("!", theme::warning()),
+        StageStatus::Failed => ("X", theme::failure()),
+        StageStatus::Canceled => ("-", theme::muted()),
+        StageStatus::Skipped => ("-", theme::muted()),
     }
 }
 
-fn job_status_display(status: &JobStatus) -> (&str, Color) {
+pub fn job_status_display(status: &JobStatus) -> (&str, Color) {
     match status {
-        JobStatus::Pending => (".", Color::DarkGray),
-        JobStatus::Running => ("~", Color::Cyan),
-        JobStatus::Succeeded => ("O", Color::Green),
-        JobStatus::SucceededWithIssues => ("!", Color::Yellow),
-        JobStatus::Failed => ("X", Color::Red),
-        JobStatus::Canceled => ("-", Color::DarkGray),
-        JobStatus::Skipped => ("-", Color::DarkGray),
+        JobStatus::Pending => (".", theme::muted()),
+        JobStatus::Running => ("~", theme::accent()),
+        JobStatus::Succeeded => ("O", theme::success()),
+        JobStatus::SucceededWithIssues => ("!", theme::warning()),
+        JobStatus::Failed => ("X", theme::failure()),
+        JobStatus::Canceled => ("-", theme::muted()),
+        JobStatus::Skipped => ("-", theme::muted()),
     }
 }
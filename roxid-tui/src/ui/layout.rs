@@ -40,3 +40,16 @@ pub fn create_execution_layout(area: Rect) -> Vec<Rect> {
         .split(area)
         .to_vec()
 }
+
+/// Execution main area split into the stage pane and the output pane, the
+/// stage pane `split_percent` wide
+pub fn create_execution_panes(area: Rect, split_percent: u16) -> Vec<Rect> {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(split_percent),
+            Constraint::Percentage(100 - split_percent),
+        ])
+        .split(area)
+        .to_vec()
+}
@@ -1,12 +1,12 @@
 use ratatui::{
-    style::{Color, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
 
 use crate::app::{App, OutputKind};
-use crate::ui::{components, layout};
+use crate::ui::{components, layout, theme};
 
 pub fn render(app: &App, frame: &mut Frame) {
     let chunks = layout::create_layout(frame.area());
@@ -17,7 +17,7 @@ pub fn render(app: &App, frame: &mut Frame) {
 
     if filtered.is_empty() {
         let msg = Paragraph::new("No output yet.")
-            .style(Style::default().fg(Color::DarkGray))
+            .style(Style::default().fg(theme::muted()))
             .block(Block::default().borders(Borders::ALL).title("Log"));
         frame.render_widget(msg, chunks[1]);
     } else {
@@ -38,24 +38,36 @@ pub fn render(app: &App, frame: &mut Frame) {
                 let is_match = app.log_viewer.search_matches.contains(&line_num);
 
                 let color = match line.kind {
-                    OutputKind::Success => Color::Green,
-                    OutputKind::Failure => Color::Red,
-                    OutputKind::Error => Color::Red,
-                    OutputKind::Warning => Color::Yellow,
-                    OutputKind::StageHeader => Color::Yellow,
-                    OutputKind::JobHeader => Color::Green,
-                    OutputKind::StepHeader => Color::Cyan,
-                    OutputKind::Info => Color::Gray,
-                    OutputKind::Output => Color::White,
+                    OutputKind::Success => theme::success(),
+                    OutputKind::Failure => theme::failure(),
+                    OutputKind::Error => theme::failure(),
+                    OutputKind::Warning => theme::warning(),
+                    OutputKind::StageHeader => theme::heading(),
+                    OutputKind::JobHeader => theme::success(),
+                    OutputKind::StepHeader => theme::accent(),
+                    OutputKind::Info => theme::secondary(),
+                    OutputKind::Output => theme::text(),
+                    OutputKind::GroupHeader => theme::emphasis(),
                 };
 
-                let bg = if is_match {
-                    Color::DarkGray
-                } else {
-                    Color::Reset
-                };
-
-                Line::from(Span::styled(&line.text, Style::default().fg(color).bg(bg)))
+                let mut style = Style::default().fg(color);
+                if is_match {
+                    style = style.patch(theme::matched());
+                }
+                match (&line.kind, line.group) {
+                    (OutputKind::GroupHeader, Some(group)) => {
+                        let marker = if app.log_viewer.collapsed_groups.contains(&group) {
+                            "▸"
+                        } else {
+                            "▾"
+                        };
+                        Line::from(Span::styled(
+                            format!("{} {}", marker, line.text.trim_start()),
+                            style.add_modifier(Modifier::BOLD),
+                        ))
+                    }
+                    _ => Line::from(Span::styled(&line.text, style)),
+                }
             })
             .collect();
 
@@ -77,9 +89,9 @@ pub fn render(app: &App, frame: &mut Frame) {
                     .borders(Borders::ALL)
                     .title(title)
                     .border_style(if app.log_viewer.search_active {
-                        Style::default().fg(Color::Yellow)
+                        Style::default().fg(theme::warning())
                     } else {
-                        Style::default().fg(Color::Cyan)
+                        Style::default().fg(theme::accent())
                     }),
             )
             .wrap(Wrap { trim: false });
@@ -89,7 +101,7 @@ pub fn render(app: &App, frame: &mut Frame) {
     let footer = if app.log_viewer.search_active {
         "Type to search | Enter: Confirm | Esc: Cancel"
     } else {
-        "j/k: Scroll | PgUp/PgDn: Page | /: Search | n: Next match | g/G: Top/Bottom | q/Esc: Back"
+        "j/k: Scroll | PgUp/PgDn: Page | Enter: Fold group | /: Search | n: Next match | g/G: Top/Bottom | q/Esc: Back"
     };
     components::render_footer(footer, frame, chunks[2]);
 }
@@ -0,0 +1,123 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::ui::theme;
+
+/// Render a markdown document into styled lines
+///
+/// Supports the subset commonly used in step summaries: headings, bullet and
+/// numbered lists, block quotes, fenced code blocks, tables, and inline
+/// `code` / **bold** spans.
+pub fn render(markdown: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for raw in markdown.lines() {
+        let trimmed = raw.trim_start();
+
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            lines.push(Line::from(Span::styled(
+                format!("    {}", raw),
+                Style::default().fg(theme::accent()),
+            )));
+            continue;
+        }
+
+        if let Some((level, text)) = heading(trimmed) {
+            let color = if level == 1 {
+                theme::heading()
+            } else {
+                theme::success()
+            };
+            lines.push(Line::from(Span::styled(
+                text.to_string(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )));
+            continue;
+        }
+
+        if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            let indent = " ".repeat(raw.len() - trimmed.len());
+            let mut spans = vec![Span::raw(format!("{}  • ", indent))];
+            spans.extend(inline(item));
+            lines.push(Line::from(spans));
+            continue;
+        }
+
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            lines.push(Line::from(Span::styled(
+                format!("  │ {}", quote.trim_start()),
+                Style::default().fg(theme::muted()),
+            )));
+            continue;
+        }
+
+        if trimmed.starts_with('|') {
+            // Drop the |---|---| separator row, keep cells aligned as written
+            if trimmed.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')) {
+                continue;
+            }
+            lines.push(Line::from(Span::raw(format!("  {}", trimmed))));
+            continue;
+        }
+
+        lines.push(Line::from(inline(raw)));
+    }
+
+    lines
+}
+
+/// Parse an ATX heading (`# Title`), returning its level and text
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    line[level..]
+        .strip_prefix(' ')
+        .map(|text| (level, text.trim()))
+}
+
+/// Style inline `code` and **bold** spans
+fn inline(text: &str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let code = rest.find('`');
+        let bold = rest.find("**");
+
+        let (start, marker, style) = match (code, bold) {
+            (Some(c), Some(b)) if b < c => (b, "**", Style::default().add_modifier(Modifier::BOLD)),
+            (Some(c), _) => (c, "`", Style::default().fg(theme::accent())),
+            (None, Some(b)) => (b, "**", Style::default().add_modifier(Modifier::BOLD)),
+            (None, None) => break,
+        };
+
+        let after = &rest[start + marker.len()..];
+        let Some(end) = after.find(marker) else {
+            break;
+        };
+
+        if start > 0 {
+            spans.push(Span::raw(rest[..start].to_string()));
+        }
+        spans.push(Span::styled(after[..end].to_string(), style));
+        rest = &after[end + marker.len()..];
+    }
+
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest.to_string()));
+    }
+    spans
+}
@@ -1,12 +1,12 @@
 use ratatui::{
-    style::{Color, Modifier, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
 use crate::app::App;
-use crate::ui::{components, layout};
+use crate::ui::{components, layout, theme};
 
 pub fn render(app: &App, frame: &mut Frame) {
     if app.discovery_errors.is_empty() {
@@ -40,7 +40,7 @@ fn render_list(app: &App, frame: &mut Frame, area: ratatui::layout::Rect) {
             Line::from("  - Extension: .yaml or .yml"),
             Line::from("  - Valid Azure DevOps YAML schema"),
         ])
-        .style(Style::default().fg(Color::Yellow))
+        .style(Style::default().fg(theme::warning()))
         .block(Block::default().borders(Borders::ALL).title("Pipelines"))
         .wrap(Wrap { trim: true });
         frame.render_widget(empty_msg, area);
@@ -54,12 +54,9 @@ fn render_list(app: &App, frame: &mut Frame, area: ratatui::layout::Rect) {
         .map(|(i, pipeline)| {
             let is_selected = i == app.selected_index;
             let style = if is_selected {
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD)
+                theme::selected()
             } else {
-                Style::default().fg(Color::White)
+                Style::default().fg(theme::text())
             };
 
             let indicator = if is_selected { "> " } else { "  " };
@@ -71,7 +68,7 @@ fn render_list(app: &App, frame: &mut Frame, area: ratatui::layout::Rect) {
             let content = Line::from(vec![
                 Span::styled(indicator, style),
                 Span::styled(&pipeline.name, style),
-                Span::styled(info, Style::default().fg(Color::DarkGray)),
+                Span::styled(info, Style::default().fg(theme::muted())),
             ]);
             ListItem::new(content)
         })
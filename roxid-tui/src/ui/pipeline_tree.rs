@@ -1,12 +1,12 @@
 use ratatui::{
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem},
     Frame,
 };
 
 use crate::app::App;
-use crate::ui::{components, layout};
+use crate::ui::{components, layout, theme};
 
 pub fn render(app: &App, frame: &mut Frame) {
     let chunks = layout::create_layout(frame.area());
@@ -41,20 +41,17 @@ pub fn render(app: &App, frame: &mut Frame) {
         let jobs_info = format!(" ({} jobs)", stage.jobs.len());
 
         let style = if is_selected {
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
+            theme::selected()
         } else {
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme::heading())
                 .add_modifier(Modifier::BOLD)
         };
 
         items.push(ListItem::new(Line::from(vec![
             Span::styled(arrow, style),
             Span::styled(format!("Stage: {}", stage_name), style),
-            Span::styled(jobs_info, Style::default().fg(Color::DarkGray)),
+            Span::styled(jobs_info, Style::default().fg(theme::muted())),
         ])));
         line_idx += 1;
 
@@ -74,18 +71,15 @@ pub fn render(app: &App, frame: &mut Frame) {
                 let steps_info = format!(" ({} steps)", job.steps.len());
 
                 let job_style = if is_job_selected {
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD)
+                    theme::selected()
                 } else {
-                    Style::default().fg(Color::Green)
+                    Style::default().fg(theme::success())
                 };
 
                 items.push(ListItem::new(Line::from(vec![
                     Span::styled(job_arrow, job_style),
                     Span::styled(format!("Job: {}", job_name), job_style),
-                    Span::styled(steps_info, Style::default().fg(Color::DarkGray)),
+                    Span::styled(steps_info, Style::default().fg(theme::muted())),
                 ])));
                 line_idx += 1;
 
@@ -121,12 +115,9 @@ pub fn render(app: &App, frame: &mut Frame) {
                         };
 
                         let step_style = if is_step_selected {
-                            Style::default()
-                                .fg(Color::Black)
-                                .bg(Color::Cyan)
-                                .add_modifier(Modifier::BOLD)
+                            theme::selected()
                         } else {
-                            Style::default().fg(Color::White)
+                            Style::default().fg(theme::text())
                         };
 
                         items.push(ListItem::new(Line::from(vec![
@@ -134,7 +125,7 @@ pub fn render(app: &App, frame: &mut Frame) {
                             Span::styled(step_name, step_style),
                             Span::styled(
                                 format!(" {}", step_type),
-                                Style::default().fg(Color::DarkGray),
+                                Style::default().fg(theme::muted()),
                             ),
                         ])));
                         line_idx += 1;
@@ -148,7 +139,7 @@ pub fn render(app: &App, frame: &mut Frame) {
         Block::default()
             .borders(Borders::ALL)
             .title("Pipeline Structure")
-            .border_style(Style::default().fg(Color::Cyan)),
+            .border_style(Style::default().fg(theme::accent())),
     );
     frame.render_widget(tree, chunks[1]);
 
@@ -0,0 +1,54 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::App;
+use crate::ui::{components, layout, markdown, theme};
+
+pub fn render(app: &App, frame: &mut Frame) {
+    let chunks = layout::create_layout(frame.area());
+
+    components::render_header("Step Summaries", frame, chunks[0]);
+
+    let summaries = app
+        .execution_state
+        .as_ref()
+        .map(|exec| exec.summaries.as_slice())
+        .unwrap_or_default();
+
+    if summaries.is_empty() {
+        let msg = Paragraph::new("No step published a summary.")
+            .style(Style::default().fg(theme::muted()))
+            .block(Block::default().borders(Borders::ALL).title("Summary"));
+        frame.render_widget(msg, chunks[1]);
+    } else {
+        let mut lines: Vec<Line> = Vec::new();
+        for summary in summaries {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(Span::styled(
+                format!("─── {} ───", summary.title),
+                Style::default()
+                    .fg(theme::emphasis())
+                    .add_modifier(Modifier::BOLD),
+            )));
+            lines.extend(markdown::render(&summary.markdown));
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Summary"))
+            .wrap(Wrap { trim: false })
+            .scroll((app.summary_scroll as u16, 0));
+        frame.render_widget(paragraph, chunks[1]);
+    }
+
+    components::render_footer(
+        "j/k: Scroll | PgUp/PgDn: Page | q/Esc: Back",
+        frame,
+        chunks[2],
+    );
+}
@@ -1,13 +1,13 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
 use crate::app::App;
-use crate::ui::{components, layout};
+use crate::ui::{components, layout, theme};
 
 pub fn render(app: &App, frame: &mut Frame) {
     let chunks = layout::create_layout(frame.area());
@@ -19,7 +19,7 @@ pub fn render(app: &App, frame: &mut Frame) {
             let msg = Paragraph::new("Running tests...")
                 .style(
                     Style::default()
-                        .fg(Color::Cyan)
+                        .fg(theme::accent())
                         .add_modifier(Modifier::BOLD),
                 )
                 .block(Block::default().borders(Borders::ALL).title("Tests"));
@@ -31,7 +31,7 @@ pub fn render(app: &App, frame: &mut Frame) {
                 Line::from("Test files should be named 'roxid-test.yml'"),
                 Line::from("and placed in the current directory or subdirectories."),
             ])
-            .style(Style::default().fg(Color::Yellow))
+            .style(Style::default().fg(theme::warning()))
             .block(Block::default().borders(Borders::ALL).title("Tests"))
             .wrap(Wrap { trim: true });
             frame.render_widget(msg, chunks[1]);
@@ -50,14 +50,14 @@ pub fn render(app: &App, frame: &mut Frame) {
                     Span::styled(
                         format!(" {} total", total),
                         Style::default()
-                            .fg(Color::White)
+                            .fg(theme::text())
                             .add_modifier(Modifier::BOLD),
                     ),
                     Span::raw("  |  "),
                     Span::styled(
                         format!("{} passed", test_state.total_passed),
                         Style::default()
-                            .fg(Color::Green)
+                            .fg(theme::success())
                             .add_modifier(Modifier::BOLD),
                     ),
                     Span::raw("  |  "),
@@ -65,22 +65,22 @@ pub fn render(app: &App, frame: &mut Frame) {
                         format!("{} failed", test_state.total_failed),
                         Style::default()
                             .fg(if test_state.total_failed > 0 {
-                                Color::Red
+                                theme::failure()
                             } else {
-                                Color::DarkGray
+                                theme::muted()
                             })
                             .add_modifier(Modifier::BOLD),
                     ),
                     Span::raw("  |  "),
                     Span::styled(
                         format!("{} skipped", test_state.total_skipped),
-                        Style::default().fg(Color::DarkGray),
+                        Style::default().fg(theme::muted()),
                     ),
                 ]),
                 Line::from(""),
                 Line::from(vec![Span::styled(
                     format!(" {} suites", test_state.results.len()),
-                    Style::default().fg(Color::Gray),
+                    Style::default().fg(theme::secondary()),
                 )]),
             ])
             .block(
@@ -88,9 +88,9 @@ pub fn render(app: &App, frame: &mut Frame) {
                     .borders(Borders::ALL)
                     .title("Summary")
                     .border_style(if test_state.total_failed > 0 {
-                        Style::default().fg(Color::Red)
+                        Style::default().fg(theme::failure())
                     } else {
-                        Style::default().fg(Color::Green)
+                        Style::default().fg(theme::success())
                     }),
             );
             frame.render_widget(summary, sections[0]);
@@ -102,21 +102,25 @@ pub fn render(app: &App, frame: &mut Frame) {
             for suite in &test_state.results {
                 // Suite header
                 let suite_name = &suite.suite_name;
-                let all_passed = suite.failed == 0;
+                let all_passed = suite.all_passed();
                 items.push(ListItem::new(Line::from(vec![
                     Span::styled(
                         if all_passed { "O " } else { "X " },
-                        Style::default().fg(if all_passed { Color::Green } else { Color::Red }),
+                        Style::default().fg(if all_passed {
+                            theme::success()
+                        } else {
+                            theme::failure()
+                        }),
                     ),
                     Span::styled(
                         suite_name,
                         Style::default()
-                            .fg(Color::White)
+                            .fg(theme::text())
                             .add_modifier(Modifier::BOLD),
                     ),
                     Span::styled(
                         format!(" ({}/{} passed)", suite.passed, suite.total),
-                        Style::default().fg(Color::DarkGray),
+                        Style::default().fg(theme::muted()),
                     ),
                 ])));
 
@@ -124,18 +128,17 @@ pub fn render(app: &App, frame: &mut Frame) {
                 for test in &suite.results {
                     let is_selected = global_idx == test_state.selected_test;
                     let style = if is_selected {
-                        Style::default()
-                            .fg(Color::Black)
-                            .bg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD)
+                        theme::selected()
                     } else {
-                        Style::default().fg(Color::White)
+                        Style::default().fg(theme::text())
                     };
 
                     let (symbol, symbol_color) = if test.passed {
-                        ("  O ", Color::Green)
+                        ("  O ", theme::success())
+                    } else if test.errored {
+                        ("  ! ", theme::warning())
                     } else {
-                        ("  X ", Color::Red)
+                        ("  X ", theme::failure())
                     };
 
                     let duration_str = format!(
@@ -146,14 +149,14 @@ pub fn render(app: &App, frame: &mut Frame) {
                     let mut spans = vec![
                         Span::styled(symbol, Style::default().fg(symbol_color)),
                         Span::styled(&test.name, style),
-                        Span::styled(duration_str, Style::default().fg(Color::DarkGray)),
+                        Span::styled(duration_str, Style::default().fg(theme::muted())),
                     ];
 
                     if !test.passed {
                         if let Some(msg) = &test.failure_message {
                             spans.push(Span::styled(
                                 format!(" - {}", msg),
-                                Style::default().fg(Color::Red),
+                                Style::default().fg(theme::failure()),
                             ));
                         }
                     }
@@ -167,13 +170,13 @@ pub fn render(app: &App, frame: &mut Frame) {
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Test Results")
-                    .border_style(Style::default().fg(Color::Cyan)),
+                    .border_style(Style::default().fg(theme::accent())),
             );
             frame.render_widget(test_list, sections[1]);
         }
     } else {
         let msg = Paragraph::new("No test results available.")
-            .style(Style::default().fg(Color::DarkGray))
+            .style(Style::default().fg(theme::muted()))
             .block(Block::default().borders(Borders::ALL).title("Tests"));
         frame.render_widget(msg, chunks[1]);
     }
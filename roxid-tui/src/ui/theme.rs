@@ -0,0 +1,81 @@
+use ratatui::style::{Color, Modifier, Style};
+
+use std::sync::OnceLock;
+
+use crate::theme::{Theme, ThemeColor};
+
+/// The theme the TUI is drawn in, and whether it's drawn in color at all
+struct Palette {
+    theme: Theme,
+    color: bool,
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+/// Set the theme once at startup
+pub fn init(theme: Theme, color: bool) {
+    let _ = PALETTE.set(Palette { theme, color });
+}
+
+fn palette() -> &'static Palette {
+    PALETTE.get_or_init(|| Palette {
+        theme: Theme::default(),
+        color: true,
+    })
+}
+
+fn to_color(color: ThemeColor) -> Color {
+    if !palette().color {
+        return Color::Reset;
+    }
+    match color {
+        ThemeColor::Black => Color::Black,
+        ThemeColor::Red => Color::Red,
+        ThemeColor::Green => Color::Green,
+        ThemeColor::Yellow => Color::Yellow,
+        ThemeColor::Blue => Color::Blue,
+        ThemeColor::Magenta => Color::Magenta,
+        ThemeColor::Cyan => Color::Cyan,
+        ThemeColor::Gray => Color::Gray,
+        ThemeColor::DarkGray => Color::DarkGray,
+        ThemeColor::LightRed => Color::LightRed,
+        ThemeColor::LightGreen => Color::LightGreen,
+        ThemeColor::LightYellow => Color::LightYellow,
+        ThemeColor::LightBlue => Color::LightBlue,
+        ThemeColor::LightMagenta => Color::LightMagenta,
+        ThemeColor::LightCyan => Color::LightCyan,
+        ThemeColor::White => Color::White,
+        ThemeColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
+
+macro_rules! roles {
+    ($($role:ident),* $(,)?) => {
+        $(
+            pub fn $role() -> Color {
+                to_color(palette().theme.$role)
+            }
+        )*
+    };
+}
+
+roles!(accent, success, failure, warning, heading, emphasis, text, secondary, muted);
+
+/// The selected row: the accent color behind it, or reversed without color
+pub fn selected() -> Style {
+    let style = Style::default().add_modifier(Modifier::BOLD);
+    if palette().color {
+        style.fg(to_color(palette().theme.selection)).bg(accent())
+    } else {
+        style.add_modifier(Modifier::REVERSED)
+    }
+}
+
+/// A search match in the log: a muted background, or underlined without color
+pub fn matched() -> Style {
+    if palette().color {
+        Style::default().bg(muted())
+    } else {
+        Style::default().add_modifier(Modifier::UNDERLINED)
+    }
+}
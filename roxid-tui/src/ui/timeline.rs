@@ -0,0 +1,208 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, ExecutionState};
+use crate::ui::execution::{job_status_display, stage_status_display};
+use crate::ui::{components, layout, theme};
+
+use std::time::Duration;
+
+use pipeline_service::parser::models::StepStatus;
+
+/// Widest the name column gets
+const MAX_LABEL_WIDTH: usize = 32;
+/// Room kept after each bar for its duration
+const DURATION_WIDTH: usize = 9;
+
+pub fn render(app: &App, frame: &mut Frame) {
+    let chunks = layout::create_layout(frame.area());
+
+    let Some(exec) = &app.execution_state else {
+        components::render_header("Timeline", frame, chunks[0]);
+        return;
+    };
+    components::render_header(
+        &format!("Timeline: {}", exec.pipeline_name),
+        frame,
+        chunks[0],
+    );
+
+    let width = chunks[1].width.saturating_sub(2) as usize;
+    let label_width = (width / 3).min(MAX_LABEL_WIDTH);
+    let bar_width = width.saturating_sub(label_width + DURATION_WIDTH).max(1);
+    let total = run_length(exec);
+
+    let mut lines = vec![axis(total, label_width, bar_width)];
+    let longest = longest_step(exec);
+    let mut rows = Vec::new();
+    for (si, stage) in exec.stages.iter().enumerate() {
+        let (symbol, color) = stage_status_display(&stage.status);
+        let label = stage.display_name.as_deref().unwrap_or(&stage.name);
+        rows.push(row(
+            &format!("{} {}", symbol, label),
+            span(exec, stage.started, stage.duration),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+            (total, label_width, bar_width),
+            false,
+        ));
+        for (ji, job) in stage.jobs.iter().enumerate() {
+            let (symbol, color) = job_status_display(&job.status);
+            let label = job.display_name.as_deref().unwrap_or(&job.name);
+            rows.push(row(
+                &format!("  {} {}", symbol, label),
+                span(exec, job.started, job.duration),
+                Style::default().fg(color),
+                (total, label_width, bar_width),
+                false,
+            ));
+            for (i, step) in job.steps.iter().enumerate() {
+                let label = step
+                    .display_name
+                    .as_deref()
+                    .or(step.name.as_deref())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("Step {}", i + 1));
+                rows.push(row(
+                    &format!("    {}", label),
+                    span(exec, step.started, step.duration),
+                    Style::default().fg(step_color(&step.status)),
+                    (total, label_width, bar_width),
+                    longest == Some((si, ji, i)),
+                ));
+            }
+        }
+    }
+    lines.extend(rows.into_iter().skip(app.timeline_scroll));
+
+    let title = format!(
+        "Timeline ({})",
+        components::format_duration(total.as_secs_f64())
+    );
+    let chart = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(chart, chunks[1]);
+
+    components::render_footer(
+        "j/k: Scroll | PgUp/PgDn: Page | q/Esc: Back",
+        frame,
+        chunks[2],
+    );
+}
+
+/// Start and end offsets of an item that has started; running items end now
+fn span(
+    exec: &ExecutionState,
+    started: Option<Duration>,
+    duration: Option<Duration>,
+) -> Option<(Duration, Duration)> {
+    let started = started?;
+    let end = match duration {
+        Some(duration) => started + duration,
+        None if exec.is_complete => started,
+        None => exec.started_at.elapsed().unwrap_or_default(),
+    };
+    Some((started, end.max(started)))
+}
+
+/// Length of the timeline: the latest end seen so far, or the run's duration if longer
+fn run_length(exec: &ExecutionState) -> Duration {
+    let ends = exec.stages.iter().flat_map(|stage| {
+        let jobs = stage.jobs.iter().flat_map(|job| {
+            job.steps
+                .iter()
+                .map(|step| span(exec, step.started, step.duration))
+                .chain([span(exec, job.started, job.duration)])
+        });
+        jobs.chain([span(exec, stage.started, stage.duration)])
+    });
+    ends.flatten()
+        .map(|(_, end)| end)
+        .chain(exec.duration)
+        .max()
+        .unwrap_or_default()
+}
+
+/// The step that took longest, as (stage, job, step) indices
+fn longest_step(exec: &ExecutionState) -> Option<(usize, usize, usize)> {
+    let mut longest = None;
+    let mut max = Duration::ZERO;
+    for (si, stage) in exec.stages.iter().enumerate() {
+        for (ji, job) in stage.jobs.iter().enumerate() {
+            for (i, step) in job.steps.iter().enumerate() {
+                if let Some(duration) = step.duration.filter(|d| *d > max) {
+                    max = duration;
+                    longest = Some((si, ji, i));
+                }
+            }
+        }
+    }
+    longest
+}
+
+/// Scale line: 0 at the left of the bars, the run length at the right
+fn axis(total: Duration, label_width: usize, bar_width: usize) -> Line<'static> {
+    let end = components::format_duration(total.as_secs_f64());
+    let gap = bar_width.saturating_sub(2 + end.len());
+    Line::from(Span::styled(
+        format!("{:label_width$}0s{:gap$}{}", "", "", end),
+        Style::default().fg(theme::muted()),
+    ))
+}
+
+fn row(
+    label: &str,
+    span: Option<(Duration, Duration)>,
+    style: Style,
+    (total, label_width, bar_width): (Duration, usize, usize),
+    longest: bool,
+) -> Line<'static> {
+    let label: String = label.chars().take(label_width.saturating_sub(1)).collect();
+    let mut spans = vec![Span::styled(format!("{:label_width$}", label), style)];
+
+    let Some((start, end)) = span else {
+        return Line::from(spans);
+    };
+    let (offset, length) = bar(start, end, total, bar_width);
+    spans.push(Span::raw(" ".repeat(offset)));
+    spans.push(Span::styled("█".repeat(length), style));
+    spans.push(Span::styled(
+        format!(
+            " {}",
+            components::format_duration((end - start).as_secs_f64())
+        ),
+        Style::default().fg(theme::muted()),
+    ));
+    if longest {
+        spans.push(Span::styled(
+            " ◀ longest step",
+            Style::default()
+                .fg(theme::emphasis())
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Columns before the bar and the bar's length; every bar gets at least one column
+fn bar(start: Duration, end: Duration, total: Duration, width: usize) -> (usize, usize) {
+    if total.is_zero() {
+        return (0, 1);
+    }
+    let scale = width as f64 / total.as_secs_f64();
+    let offset = ((start.as_secs_f64() * scale) as usize).min(width.saturating_sub(1));
+    let end = ((end.as_secs_f64() * scale).round() as usize).clamp(offset + 1, width);
+    (offset, end - offset)
+}
+
+fn step_color(status: &StepStatus) -> Color {
+    match status {
+        StepStatus::Pending | StepStatus::Canceled | StepStatus::Skipped => theme::muted(),
+        StepStatus::Running => theme::accent(),
+        StepStatus::Succeeded => theme::success(),
+        StepStatus::SucceededWithIssues => theme::warning(),
+        StepStatus::Failed => theme::failure(),
+    }
+}